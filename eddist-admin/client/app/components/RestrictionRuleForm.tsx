@@ -1,25 +1,43 @@
-import { Button, Checkbox, Label, Select, TextInput } from "flowbite-react";
+import { Button, Checkbox, Label, Select, TextInput, Textarea } from "flowbite-react";
 import { useState } from "react";
 import { Controller, useForm } from "react-hook-form";
 
+type RuleType = "Asn" | "IP" | "IPCidr" | "UserAgent" | "Composite";
+type Target = "Thread" | "Response" | "AuthCode";
+type Action = "Block" | "ReAuth" | "Captcha" | "SlowDown";
+
 interface RestrictionRuleFormData {
   name: string;
-  rule_type: "Asn" | "IP" | "IPCidr" | "UserAgent";
+  rule_type: RuleType;
   rule_value: string;
+  board_keys: string;
+  action: Action;
+  captcha_provider: string;
+  slow_down_seconds: string;
   expires_at?: string;
 }
 
 interface DefaultValues {
   name: string;
-  rule_type: "Asn" | "IP" | "IPCidr" | "UserAgent";
+  rule_type: RuleType;
   rule_value: string;
+  board_keys?: string[] | null;
+  targets: Target[];
+  action: Action;
+  captcha_provider?: string | null;
+  slow_down_seconds: number;
   expires_at?: string | null;
 }
 
 interface SubmitData {
   name: string;
-  rule_type: "Asn" | "IP" | "IPCidr" | "UserAgent";
+  rule_type: RuleType;
   rule_value: string;
+  board_keys: string[] | null;
+  targets: Target[];
+  action: Action;
+  captcha_provider: string | null;
+  slow_down_seconds: number;
   expires_at?: string;
 }
 
@@ -39,26 +57,65 @@ const RULE_TYPE_OPTIONS = [
   { value: "IP", label: "IP Address" },
   { value: "IPCidr", label: "IP CIDR" },
   { value: "UserAgent", label: "User Agent" },
+  { value: "Composite", label: "Composite (JSON conditions)" },
+];
+
+const TARGET_OPTIONS: { value: Target; label: string }[] = [
+  { value: "Thread", label: "Thread creation" },
+  { value: "Response", label: "Responses" },
+  { value: "AuthCode", label: "Auth code" },
 ];
 
+const ACTION_OPTIONS: { value: Action; label: string }[] = [
+  { value: "Block", label: "Block" },
+  { value: "ReAuth", label: "Require re-auth" },
+  { value: "Captcha", label: "Force captcha provider" },
+  { value: "SlowDown", label: "Slow down" },
+];
+
+const COMPOSITE_PLACEHOLDER =
+  '{"type":"all","conditions":[{"type":"asn","value":1234},{"type":"user_agent","value":"Y"}]}';
+
 const RestrictionRuleForm = (props: Props) => {
   const defaults = props.mode === "edit" ? props.defaultValues : undefined;
   const [neverExpires, setNeverExpires] = useState(defaults ? !defaults.expires_at : true);
+  const [targets, setTargets] = useState<Target[]>(
+    defaults?.targets ?? TARGET_OPTIONS.map((option) => option.value),
+  );
 
-  const { register, handleSubmit, control, reset } = useForm<RestrictionRuleFormData>();
+  const { register, handleSubmit, control, reset, watch } = useForm<RestrictionRuleFormData>({
+    defaultValues: {
+      action: defaults?.action ?? "Block",
+      board_keys: defaults?.board_keys?.join(", ") ?? "",
+      captcha_provider: defaults?.captcha_provider ?? "",
+      slow_down_seconds: String(defaults?.slow_down_seconds ?? 0),
+    },
+  });
+  const ruleType = watch("rule_type", defaults?.rule_type);
+  const action = watch("action");
 
   return (
     <form
       onSubmit={handleSubmit((data) => {
+        const boardKeys = data.board_keys
+          .split(",")
+          .map((key) => key.trim())
+          .filter((key) => key !== "");
         props.onSubmit({
           name: data.name,
           rule_type: data.rule_type,
           rule_value: data.rule_value,
+          board_keys: boardKeys.length > 0 ? boardKeys : null,
+          targets,
+          action: data.action,
+          captcha_provider: data.action === "Captcha" ? data.captcha_provider : null,
+          slow_down_seconds: data.action === "SlowDown" ? Number(data.slow_down_seconds) : 0,
           expires_at:
             neverExpires || !data.expires_at ? undefined : new Date(data.expires_at).toISOString(),
         });
         reset();
         setNeverExpires(true);
+        setTargets(TARGET_OPTIONS.map((option) => option.value));
       })}
     >
       <div className="flex flex-col space-y-4">
@@ -92,13 +149,83 @@ const RestrictionRuleForm = (props: Props) => {
         </div>
         <div>
           <Label>Rule Value</Label>
+          {ruleType === "Composite" ? (
+            <Textarea
+              rows={4}
+              className="font-mono"
+              placeholder={COMPOSITE_PLACEHOLDER}
+              required
+              defaultValue={defaults?.rule_value}
+              {...register("rule_value", { required: true })}
+            />
+          ) : (
+            <TextInput
+              placeholder="Rule value..."
+              required
+              defaultValue={defaults?.rule_value}
+              {...register("rule_value", { required: true })}
+            />
+          )}
+        </div>
+        <div>
+          <Label>Boards</Label>
           <TextInput
-            placeholder="Rule value..."
-            required
-            defaultValue={defaults?.rule_value}
-            {...register("rule_value", { required: true })}
+            placeholder="Comma-separated board keys, empty for all boards"
+            {...register("board_keys")}
           />
         </div>
+        <div>
+          <Label>Targets</Label>
+          <div className="flex space-x-4 mt-1">
+            {TARGET_OPTIONS.map((option) => (
+              <div key={option.value} className="flex items-center space-x-2">
+                <Checkbox
+                  id={`target-${option.value}`}
+                  checked={targets.includes(option.value)}
+                  onChange={(e) =>
+                    setTargets(
+                      e.target.checked
+                        ? [...targets, option.value]
+                        : targets.filter((target) => target !== option.value),
+                    )
+                  }
+                />
+                <Label htmlFor={`target-${option.value}`}>{option.label}</Label>
+              </div>
+            ))}
+          </div>
+        </div>
+        <div>
+          <Label>Action</Label>
+          <Select {...register("action")}>
+            {ACTION_OPTIONS.map((option) => (
+              <option key={option.value} value={option.value}>
+                {option.label}
+              </option>
+            ))}
+          </Select>
+        </div>
+        {action === "Captcha" && (
+          <div>
+            <Label>Captcha Provider</Label>
+            <TextInput
+              placeholder="Name of the captcha config"
+              required
+              {...register("captcha_provider", { required: true })}
+            />
+          </div>
+        )}
+        {action === "SlowDown" && (
+          <div>
+            <Label>Interval (seconds)</Label>
+            <TextInput
+              type="number"
+              min={1}
+              required
+              {...register("slow_down_seconds", { required: true })}
+            />
+          </div>
+        )}
         <div>
           <div className="flex items-center space-x-2 mb-3">
             <Checkbox
@@ -0,0 +1,66 @@
+import {
+  Spinner,
+  Table,
+  TableBody,
+  TableCell,
+  TableHead,
+  TableHeadCell,
+  TableRow,
+} from "flowbite-react";
+import { getRestrictionRuleHits } from "~/hooks/queries";
+import { formatDateTime } from "~/utils/format";
+
+interface Props {
+  ruleId: string;
+}
+
+const RestrictionRuleHits = ({ ruleId }: Props) => {
+  const { data: hits, isLoading } = getRestrictionRuleHits({
+    params: { path: { rule_id: ruleId } },
+  });
+
+  if (isLoading) {
+    return <Spinner />;
+  }
+  if (!hits) {
+    return <p>Failed to load the hits of the rule.</p>;
+  }
+
+  return (
+    <div>
+      <p className="text-sm">
+        {hits.hit_count} hits, last hit{" "}
+        {hits.last_hit_at ? formatDateTime(hits.last_hit_at) : "never"}
+      </p>
+      <p className="text-xs text-gray-500 mt-1">
+        Hits are counted in Redis and saved periodically, the latest ones may not be included yet.
+      </p>
+      {hits.recent_samples.length > 0 && (
+        <Table className="mt-4">
+          <TableHead>
+            <TableHeadCell>Hit At</TableHeadCell>
+            <TableHeadCell>IP</TableHeadCell>
+            <TableHeadCell>ASN</TableHeadCell>
+            <TableHeadCell>User Agent</TableHeadCell>
+            <TableHeadCell>Board</TableHeadCell>
+            <TableHeadCell>Target</TableHeadCell>
+          </TableHead>
+          <TableBody className="divide-y">
+            {hits.recent_samples.map((sample) => (
+              <TableRow className="border-gray-200" key={`${sample.hit_at}-${sample.ip}`}>
+                <TableCell>{formatDateTime(sample.hit_at)}</TableCell>
+                <TableCell className="font-mono text-sm">{sample.ip}</TableCell>
+                <TableCell>{sample.asn}</TableCell>
+                <TableCell className="text-sm break-all">{sample.user_agent}</TableCell>
+                <TableCell>{sample.board_key ?? "-"}</TableCell>
+                <TableCell>{sample.target}</TableCell>
+              </TableRow>
+            ))}
+          </TableBody>
+        </Table>
+      )}
+    </div>
+  );
+};
+
+export default RestrictionRuleHits;
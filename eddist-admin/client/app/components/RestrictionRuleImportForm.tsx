@@ -0,0 +1,198 @@
+import { Button, Label, Select, TextInput, Textarea } from "flowbite-react";
+import { useForm } from "react-hook-form";
+
+type Format = "Csv" | "Json" | "Cidr";
+type Action = "Block" | "ReAuth" | "Captcha" | "SlowDown";
+
+interface ImportFormData {
+  format: Format;
+  content: string;
+  name: string;
+  board_keys: string;
+  action: Action;
+  captcha_provider: string;
+  slow_down_seconds: string;
+  expires_at: string;
+}
+
+interface SubmitData {
+  format: Format;
+  content: string;
+  name: string | null;
+  board_keys: string[] | null;
+  action: Action;
+  captcha_provider: string | null;
+  slow_down_seconds: number;
+  expires_at: string | null;
+}
+
+interface ImportResult {
+  dry_run: boolean;
+  rules: { rule_type: string; rule_value: string }[];
+  duplicates: { row: number; rule_value: string; existing_rule_id?: string | null }[];
+  errors: { row: number; message: string }[];
+}
+
+interface Props {
+  result?: ImportResult;
+  onSubmit: (data: SubmitData, dryRun: boolean) => void;
+}
+
+const FORMAT_PLACEHOLDERS: Record<Format, string> = {
+  Csv: "name,rule_type,rule_value,board_keys,targets,action,captcha_provider,slow_down_seconds,expires_at",
+  Json: '[{"rule_type":"IPCidr","rule_value":"192.0.2.0/24"}]',
+  Cidr: "192.0.2.0/24 ; comment\n198.51.100.7",
+};
+
+const ACTION_OPTIONS: { value: Action; label: string }[] = [
+  { value: "Block", label: "Block" },
+  { value: "ReAuth", label: "Require re-auth" },
+  { value: "Captcha", label: "Force captcha provider" },
+  { value: "SlowDown", label: "Slow down" },
+];
+
+const RestrictionRuleImportForm = ({ result, onSubmit }: Props) => {
+  const { register, handleSubmit, watch } = useForm<ImportFormData>({
+    defaultValues: {
+      format: "Cidr",
+      action: "Block",
+      slow_down_seconds: "0",
+    },
+  });
+  const format = watch("format");
+  const action = watch("action");
+
+  const submit = (dryRun: boolean) =>
+    handleSubmit((data) => {
+      const boardKeys = data.board_keys
+        .split(",")
+        .map((key) => key.trim())
+        .filter((key) => key !== "");
+      onSubmit(
+        {
+          format: data.format,
+          content: data.content,
+          name: data.name || null,
+          board_keys: boardKeys.length > 0 ? boardKeys : null,
+          action: data.action,
+          captcha_provider: data.action === "Captcha" ? data.captcha_provider : null,
+          slow_down_seconds: data.action === "SlowDown" ? Number(data.slow_down_seconds) : 0,
+          expires_at: data.expires_at ? new Date(data.expires_at).toISOString() : null,
+        },
+        dryRun,
+      );
+    });
+
+  return (
+    <form onSubmit={submit(false)}>
+      <div className="flex flex-col space-y-4">
+        <div>
+          <Label>Format</Label>
+          <Select {...register("format")}>
+            <option value="Cidr">CIDR (one IP or CIDR per line)</option>
+            <option value="Csv">CSV</option>
+            <option value="Json">JSON</option>
+          </Select>
+        </div>
+        <div>
+          <Label>Content</Label>
+          <Textarea
+            rows={8}
+            className="font-mono"
+            placeholder={FORMAT_PLACEHOLDERS[format]}
+            required
+            {...register("content", { required: true })}
+          />
+        </div>
+        <p className="text-sm text-gray-500">
+          The settings below apply to the rows that do not set them.
+        </p>
+        <div>
+          <Label>Name</Label>
+          <TextInput
+            placeholder="Prefix of the rule names, defaults to import"
+            {...register("name")}
+          />
+        </div>
+        <div>
+          <Label>Boards</Label>
+          <TextInput
+            placeholder="Comma-separated board keys, empty for all boards"
+            {...register("board_keys")}
+          />
+        </div>
+        <div>
+          <Label>Action</Label>
+          <Select {...register("action")}>
+            {ACTION_OPTIONS.map((option) => (
+              <option key={option.value} value={option.value}>
+                {option.label}
+              </option>
+            ))}
+          </Select>
+        </div>
+        {action === "Captcha" && (
+          <div>
+            <Label>Captcha Provider</Label>
+            <TextInput
+              placeholder="Name of the captcha config"
+              required
+              {...register("captcha_provider", { required: true })}
+            />
+          </div>
+        )}
+        {action === "SlowDown" && (
+          <div>
+            <Label>Interval (seconds)</Label>
+            <TextInput
+              type="number"
+              min={1}
+              required
+              {...register("slow_down_seconds", { required: true })}
+            />
+          </div>
+        )}
+        <div>
+          <Label>Expires At</Label>
+          <TextInput type="datetime-local" {...register("expires_at")} />
+        </div>
+      </div>
+      {result && (
+        <div className="mt-4 text-sm">
+          <p className="font-semibold">
+            {result.dry_run
+              ? `${result.rules.length} rules would be created`
+              : `${result.rules.length} rules created`}
+            , {result.duplicates.length} duplicates, {result.errors.length} errors
+          </p>
+          {result.duplicates.length > 0 && (
+            <ul className="mt-2 max-h-32 overflow-y-auto text-gray-600">
+              {result.duplicates.map((duplicate) => (
+                <li key={`duplicate-${duplicate.row}`}>
+                  Row {duplicate.row}: {duplicate.rule_value} already exists
+                </li>
+              ))}
+            </ul>
+          )}
+          {result.errors.length > 0 && (
+            <ul className="mt-2 max-h-32 overflow-y-auto text-red-600">
+              {result.errors.map((error) => (
+                <li key={`error-${error.row}`}>
+                  Row {error.row}: {error.message}
+                </li>
+              ))}
+            </ul>
+          )}
+        </div>
+      )}
+      <div className="flex space-x-2 mt-4">
+        <Button type="button" color="light" onClick={submit(true)}>
+          Validate
+        </Button>
+        <Button type="submit">Import</Button>
+      </div>
+    </form>
+  );
+};
+
+export default RestrictionRuleImportForm;
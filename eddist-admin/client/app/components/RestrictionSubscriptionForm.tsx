@@ -0,0 +1,238 @@
+import { Button, Checkbox, Label, Select, TextInput } from "flowbite-react";
+import { useState } from "react";
+import { useForm } from "react-hook-form";
+
+type Format = "Csv" | "Json" | "Cidr";
+type Target = "Thread" | "Response" | "AuthCode";
+type Action = "Block" | "ReAuth" | "Captcha" | "SlowDown";
+
+interface SubscriptionFormData {
+  name: string;
+  source: string;
+  format: Format;
+  board_keys: string;
+  action: Action;
+  captcha_provider: string;
+  slow_down_seconds: string;
+  refresh_interval_minutes: string;
+  expires_after_minutes: string;
+}
+
+interface DefaultValues {
+  name: string;
+  source: string;
+  format: Format;
+  board_keys?: string[] | null;
+  targets: Target[];
+  action: Action;
+  captcha_provider?: string | null;
+  slow_down_seconds: number;
+  refresh_interval_seconds: number;
+  expires_after_seconds: number;
+  enabled: boolean;
+}
+
+interface SubmitData {
+  name: string;
+  source: string;
+  format: Format;
+  board_keys: string[] | null;
+  targets: Target[];
+  action: Action;
+  captcha_provider: string | null;
+  slow_down_seconds: number;
+  refresh_interval_seconds: number;
+  expires_after_seconds: number;
+  enabled: boolean;
+}
+
+type Props =
+  | {
+      mode: "create";
+      onSubmit: (data: SubmitData) => void;
+    }
+  | {
+      mode: "edit";
+      defaultValues: DefaultValues;
+      onSubmit: (data: SubmitData) => void;
+    };
+
+const TARGET_OPTIONS: { value: Target; label: string }[] = [
+  { value: "Thread", label: "Thread creation" },
+  { value: "Response", label: "Responses" },
+  { value: "AuthCode", label: "Auth code" },
+];
+
+const ACTION_OPTIONS: { value: Action; label: string }[] = [
+  { value: "Block", label: "Block" },
+  { value: "ReAuth", label: "Require re-auth" },
+  { value: "Captcha", label: "Force captcha provider" },
+  { value: "SlowDown", label: "Slow down" },
+];
+
+const RestrictionSubscriptionForm = (props: Props) => {
+  const defaults = props.mode === "edit" ? props.defaultValues : undefined;
+  const [enabled, setEnabled] = useState(defaults?.enabled ?? true);
+  const [targets, setTargets] = useState<Target[]>(
+    defaults?.targets ?? TARGET_OPTIONS.map((option) => option.value),
+  );
+
+  const { register, handleSubmit, reset, watch } = useForm<SubscriptionFormData>({
+    defaultValues: {
+      name: defaults?.name ?? "",
+      source: defaults?.source ?? "",
+      format: defaults?.format ?? "Cidr",
+      board_keys: defaults?.board_keys?.join(", ") ?? "",
+      action: defaults?.action ?? "Block",
+      captcha_provider: defaults?.captcha_provider ?? "",
+      slow_down_seconds: String(defaults?.slow_down_seconds ?? 0),
+      refresh_interval_minutes: String((defaults?.refresh_interval_seconds ?? 86400) / 60),
+      expires_after_minutes: String((defaults?.expires_after_seconds ?? 3 * 86400) / 60),
+    },
+  });
+  const action = watch("action");
+
+  return (
+    <form
+      onSubmit={handleSubmit((data) => {
+        const boardKeys = data.board_keys
+          .split(",")
+          .map((key) => key.trim())
+          .filter((key) => key !== "");
+        props.onSubmit({
+          name: data.name,
+          source: data.source,
+          format: data.format,
+          board_keys: boardKeys.length > 0 ? boardKeys : null,
+          targets,
+          action: data.action,
+          captcha_provider: data.action === "Captcha" ? data.captcha_provider : null,
+          slow_down_seconds: data.action === "SlowDown" ? Number(data.slow_down_seconds) : 0,
+          refresh_interval_seconds: Number(data.refresh_interval_minutes) * 60,
+          expires_after_seconds: Number(data.expires_after_minutes) * 60,
+          enabled,
+        });
+        reset();
+        setEnabled(true);
+        setTargets(TARGET_OPTIONS.map((option) => option.value));
+      })}
+    >
+      <div className="flex flex-col space-y-4">
+        <div>
+          <Label>Name</Label>
+          <TextInput
+            placeholder="Subscription name..."
+            required
+            {...register("name", { required: true })}
+          />
+        </div>
+        <div>
+          <Label>Source</Label>
+          <TextInput
+            placeholder="https://example.com/drop.txt or a file in BLOCKLIST_SOURCE_DIR of eddist-cron"
+            required
+            {...register("source", { required: true })}
+          />
+        </div>
+        <div>
+          <Label>Format</Label>
+          <Select {...register("format")}>
+            <option value="Cidr">CIDR (one IP or CIDR per line)</option>
+            <option value="Csv">CSV</option>
+            <option value="Json">JSON</option>
+          </Select>
+        </div>
+        <div>
+          <Label>Boards</Label>
+          <TextInput
+            placeholder="Comma-separated board keys, empty for all boards"
+            {...register("board_keys")}
+          />
+        </div>
+        <div>
+          <Label>Targets</Label>
+          <div className="flex space-x-4 mt-1">
+            {TARGET_OPTIONS.map((option) => (
+              <div key={option.value} className="flex items-center space-x-2">
+                <Checkbox
+                  id={`subscription-target-${option.value}`}
+                  checked={targets.includes(option.value)}
+                  onChange={(e) =>
+                    setTargets(
+                      e.target.checked
+                        ? [...targets, option.value]
+                        : targets.filter((target) => target !== option.value),
+                    )
+                  }
+                />
+                <Label htmlFor={`subscription-target-${option.value}`}>{option.label}</Label>
+              </div>
+            ))}
+          </div>
+        </div>
+        <div>
+          <Label>Action</Label>
+          <Select {...register("action")}>
+            {ACTION_OPTIONS.map((option) => (
+              <option key={option.value} value={option.value}>
+                {option.label}
+              </option>
+            ))}
+          </Select>
+        </div>
+        {action === "Captcha" && (
+          <div>
+            <Label>Captcha Provider</Label>
+            <TextInput
+              placeholder="Name of the captcha config"
+              required
+              {...register("captcha_provider", { required: true })}
+            />
+          </div>
+        )}
+        {action === "SlowDown" && (
+          <div>
+            <Label>Interval (seconds)</Label>
+            <TextInput
+              type="number"
+              min={1}
+              required
+              {...register("slow_down_seconds", { required: true })}
+            />
+          </div>
+        )}
+        <div>
+          <Label>Refresh Interval (minutes)</Label>
+          <TextInput
+            type="number"
+            min={5}
+            required
+            {...register("refresh_interval_minutes", { required: true })}
+          />
+        </div>
+        <div>
+          <Label>Rules Expire After (minutes)</Label>
+          <TextInput
+            type="number"
+            min={6}
+            required
+            {...register("expires_after_minutes", { required: true })}
+          />
+        </div>
+        <div className="flex items-center space-x-2">
+          <Checkbox
+            id="subscription-enabled"
+            checked={enabled}
+            onChange={(e) => setEnabled(e.target.checked)}
+          />
+          <Label htmlFor="subscription-enabled">Enabled</Label>
+        </div>
+      </div>
+      <Button type="submit" className="mt-4">
+        {props.mode === "create" ? "Create Subscription" : "Update Subscription"}
+      </Button>
+    </form>
+  );
+};
+
+export default RestrictionSubscriptionForm;
@@ -9,6 +9,8 @@ interface Thread {
   threadNumber: number;
   title: string;
   responseCount: number;
+  /** Responses within the last hour, not shown for archives */
+  momentum?: number;
   boardId: number;
   lastModified: string;
 }
@@ -33,7 +35,12 @@ const ThreadList: React.FC<ThreadListProps> = ({ threads, board, archives: isArc
           >
             <span className="grow">{thread.title}</span>
           </Link>
-          <span className="ml-auto mr-4">{thread.responseCount} responses</span>
+          {thread.momentum !== undefined && (
+            <span className="ml-auto mr-4 text-gray-600">{thread.momentum} /h</span>
+          )}
+          <span className={thread.momentum !== undefined ? "mr-4" : "ml-auto mr-4"}>
+            {thread.responseCount} responses
+          </span>
         </div>
       ))}
     </div>
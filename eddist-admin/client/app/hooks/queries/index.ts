@@ -6,6 +6,7 @@ export * from "./idps";
 export * from "./ng-words";
 export * from "./notices";
 export * from "./restriction-rules";
+export * from "./restriction-subscriptions";
 export * from "./server-settings";
 export * from "./terms";
 export type { UseQueryOptions } from "./types";
@@ -1,4 +1,4 @@
-import { useMutation, useQueryClient, useSuspenseQuery } from "@tanstack/react-query";
+import { useMutation, useQuery, useQueryClient, useSuspenseQuery } from "@tanstack/react-query";
 import { toast } from "react-toastify";
 import client from "~/openapi/client";
 import type { paths } from "~/openapi/schema";
@@ -10,25 +10,12 @@ export const getRestrictionRules = ({
   params,
 }: UseQueryOptions<paths[typeof GET_RESTRICTION_RULES]["get"]>) => {
   return useSuspenseQuery({
-    queryKey: [GET_RESTRICTION_RULES],
+    queryKey: [GET_RESTRICTION_RULES, params?.query],
     queryFn: async ({ signal }) => {
       const { data } = await client.GET(GET_RESTRICTION_RULES, {
         params,
         signal,
       });
-
-      if (data) {
-        data.sort((a, b) => {
-          if (a.created_at < b.created_at) {
-            return 1;
-          }
-          if (a.created_at > b.created_at) {
-            return -1;
-          }
-          return 0;
-        });
-      }
-
       return data;
     },
   });
@@ -53,6 +40,25 @@ export const getRestrictionRule = ({
   });
 };
 
+const GET_RESTRICTION_RULE_HITS = "/restriction_rules/{rule_id}/hits";
+
+export const getRestrictionRuleHits = ({
+  params,
+  reactQuery,
+}: UseQueryOptions<paths[typeof GET_RESTRICTION_RULE_HITS]["get"]>) => {
+  return useQuery({
+    ...reactQuery,
+    queryKey: [GET_RESTRICTION_RULE_HITS, params.path.rule_id],
+    queryFn: async ({ signal }) => {
+      const { data } = await client.GET(GET_RESTRICTION_RULE_HITS, {
+        params,
+        signal,
+      });
+      return data;
+    },
+  });
+};
+
 const CREATE_RESTRICTION_RULE = "/restriction_rules";
 
 export const useCreateRestrictionRule = () => {
@@ -109,3 +115,44 @@ export const useDeleteRestrictionRule = () => {
     onError: () => toast.error("Failed to delete restriction rule"),
   });
 };
+
+const IMPORT_RESTRICTION_RULES = "/restriction_rules/import";
+
+export const useImportRestrictionRules = () => {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (args: UseQueryOptions<paths[typeof IMPORT_RESTRICTION_RULES]["post"]>) => {
+      const { data, error } = await client.POST(IMPORT_RESTRICTION_RULES, {
+        body: args.body,
+      });
+      if (error) {
+        throw error;
+      }
+      return data;
+    },
+    onSuccess: (data) => {
+      if (data && !data.dry_run) {
+        queryClient.invalidateQueries({ queryKey: [GET_RESTRICTION_RULES] });
+        toast.success(`Successfully imported ${data.rules.length} restriction rules`);
+      }
+    },
+    onError: () => toast.error("Failed to import restriction rules"),
+  });
+};
+
+const EXPORT_RESTRICTION_RULES = "/restriction_rules/export";
+
+export const useExportRestrictionRules = () => {
+  return useMutation({
+    mutationFn: async (args: UseQueryOptions<paths[typeof EXPORT_RESTRICTION_RULES]["get"]>) => {
+      const { data, error } = await client.GET(EXPORT_RESTRICTION_RULES, {
+        params: args.params,
+      });
+      if (error) {
+        throw error;
+      }
+      return data;
+    },
+    onError: () => toast.error("Failed to export restriction rules"),
+  });
+};
@@ -0,0 +1,87 @@
+import { useMutation, useQueryClient, useSuspenseQuery } from "@tanstack/react-query";
+import { toast } from "react-toastify";
+import client from "~/openapi/client";
+import type { paths } from "~/openapi/schema";
+import type { UseQueryOptions } from "./types";
+
+const GET_RESTRICTION_SUBSCRIPTIONS = "/restriction_subscriptions";
+
+export const getRestrictionSubscriptions = ({
+  params,
+}: UseQueryOptions<paths[typeof GET_RESTRICTION_SUBSCRIPTIONS]["get"]>) => {
+  return useSuspenseQuery({
+    queryKey: [GET_RESTRICTION_SUBSCRIPTIONS],
+    queryFn: async ({ signal }) => {
+      const { data } = await client.GET(GET_RESTRICTION_SUBSCRIPTIONS, {
+        params,
+        signal,
+      });
+      return data;
+    },
+  });
+};
+
+const CREATE_RESTRICTION_SUBSCRIPTION = "/restriction_subscriptions";
+
+export const useCreateRestrictionSubscription = () => {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (
+      args: UseQueryOptions<paths[typeof CREATE_RESTRICTION_SUBSCRIPTION]["post"]>,
+    ) => {
+      const { data } = await client.POST(CREATE_RESTRICTION_SUBSCRIPTION, {
+        body: args.body,
+      });
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: [GET_RESTRICTION_SUBSCRIPTIONS] });
+      toast.success("Successfully created blocklist subscription");
+    },
+    onError: () => toast.error("Failed to create blocklist subscription"),
+  });
+};
+
+const UPDATE_RESTRICTION_SUBSCRIPTION = "/restriction_subscriptions/{subscription_id}";
+
+export const useUpdateRestrictionSubscription = () => {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (
+      args: UseQueryOptions<paths[typeof UPDATE_RESTRICTION_SUBSCRIPTION]["patch"]>,
+    ) => {
+      const { data } = await client.PATCH(UPDATE_RESTRICTION_SUBSCRIPTION, {
+        params: args.params,
+        body: args.body,
+      });
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: [GET_RESTRICTION_SUBSCRIPTIONS] });
+      toast.success("Successfully updated blocklist subscription");
+    },
+    onError: () => toast.error("Failed to update blocklist subscription"),
+  });
+};
+
+const DELETE_RESTRICTION_SUBSCRIPTION = "/restriction_subscriptions/{subscription_id}";
+
+export const useDeleteRestrictionSubscription = () => {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (
+      args: UseQueryOptions<paths[typeof DELETE_RESTRICTION_SUBSCRIPTION]["delete"]>,
+    ) => {
+      await client.DELETE(DELETE_RESTRICTION_SUBSCRIPTION, {
+        params: args.params,
+      });
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: [GET_RESTRICTION_SUBSCRIPTIONS] });
+      // Deleting a subscription also deletes its rules
+      queryClient.invalidateQueries({ queryKey: ["/restriction_rules"] });
+      toast.success("Successfully deleted blocklist subscription");
+    },
+    onError: () => toast.error("Failed to delete blocklist subscription"),
+  });
+};
@@ -485,6 +485,38 @@ export interface paths {
         patch?: never;
         trace?: never;
     };
+    "/restriction_rules/export": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        get: operations["export_restriction_rules"];
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/restriction_rules/import": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        get?: never;
+        put?: never;
+        post: operations["import_restriction_rules"];
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
     "/restriction_rules/{rule_id}": {
         parameters: {
             query?: never;
@@ -501,6 +533,54 @@ export interface paths {
         patch: operations["update_restriction_rule"];
         trace?: never;
     };
+    "/restriction_rules/{rule_id}/hits": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        get: operations["get_restriction_rule_hits"];
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/restriction_subscriptions": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        get: operations["get_restriction_subscriptions"];
+        put?: never;
+        post: operations["create_restriction_subscription"];
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/restriction_subscriptions/{subscription_id}": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        get?: never;
+        put?: never;
+        post?: never;
+        delete: operations["delete_restriction_subscription"];
+        options?: never;
+        head?: never;
+        patch: operations["update_restriction_subscription"];
+        trace?: never;
+    };
     "/server-settings/": {
         parameters: {
             query?: never;
@@ -759,11 +839,33 @@ export interface components {
             title: string;
         };
         CreateRestrictionRuleRequest: {
+            action?: null | components["schemas"]["RestrictionActionSchema"];
+            board_keys?: string[] | null;
+            captcha_provider?: string | null;
             /** Format: date-time */
             expires_at?: string | null;
             name: string;
             rule_type: components["schemas"]["RestrictionRuleTypeSchema"];
             rule_value: string;
+            /** Format: int32 */
+            slow_down_seconds?: number | null;
+            targets?: components["schemas"]["RestrictionTargetSchema"][] | null;
+        };
+        CreateRestrictionSubscriptionRequest: {
+            action?: null | components["schemas"]["RestrictionActionSchema"];
+            board_keys?: string[] | null;
+            captcha_provider?: string | null;
+            enabled?: boolean | null;
+            /** Format: int32 */
+            expires_after_seconds: number;
+            format: components["schemas"]["RestrictionRuleFormatSchema"];
+            name: string;
+            /** Format: int32 */
+            refresh_interval_seconds: number;
+            /** Format: int32 */
+            slow_down_seconds?: number | null;
+            source: string;
+            targets?: components["schemas"]["RestrictionTargetSchema"][] | null;
         };
         CreationCapInput: {
             description: string;
@@ -795,6 +897,10 @@ export interface components {
             threads_archive_cron?: string | null;
             threads_archive_trigger_thread_count?: number | null;
         };
+        ExportRestrictionRulesResponse: {
+            content: string;
+            format: components["schemas"]["RestrictionRuleFormatSchema"];
+        };
         /**
          * @description HTTP method for verification requests
          * @enum {string}
@@ -811,6 +917,26 @@ export interface components {
             idp_name: string;
             oidc_config_url: string;
         };
+        ImportRestrictionRulesRequest: {
+            action?: null | components["schemas"]["RestrictionActionSchema"];
+            board_keys?: string[] | null;
+            captcha_provider?: string | null;
+            content: string;
+            dry_run?: boolean | null;
+            /** Format: date-time */
+            expires_at?: string | null;
+            format: components["schemas"]["RestrictionRuleFormatSchema"];
+            name?: string | null;
+            /** Format: int32 */
+            slow_down_seconds?: number | null;
+            targets?: components["schemas"]["RestrictionTargetSchema"][] | null;
+        };
+        ImportRestrictionRulesResponse: {
+            dry_run: boolean;
+            duplicates: components["schemas"]["RestrictionImportDuplicateSchema"][];
+            errors: components["schemas"]["RestrictionImportErrorSchema"][];
+            rules: components["schemas"]["UserRestrictionRuleSchema"][];
+        };
         NativeSessionRequest: {
             access_token: string;
         };
@@ -890,7 +1016,44 @@ export interface components {
             thread_id: string;
         };
         /** @enum {string} */
-        RestrictionRuleTypeSchema: "Asn" | "IP" | "IPCidr" | "UserAgent";
+        RestrictionActionSchema: "Block" | "ReAuth" | "Captcha" | "SlowDown";
+        RestrictionHitSampleSchema: {
+            /** Format: int32 */
+            asn: number;
+            board_key?: string | null;
+            /** Format: date-time */
+            hit_at: string;
+            ip: string;
+            target: components["schemas"]["RestrictionTargetSchema"];
+            user_agent: string;
+        };
+        RestrictionImportDuplicateSchema: {
+            /** Format: uuid */
+            existing_rule_id?: string | null;
+            row: number;
+            rule_value: string;
+        };
+        RestrictionImportErrorSchema: {
+            message: string;
+            row: number;
+        };
+        /** @enum {string} */
+        RestrictionRuleFormatSchema: "Csv" | "Json" | "Cidr";
+        RestrictionRuleHitsSchema: {
+            /** Format: int64 */
+            hit_count: number;
+            /** Format: date-time */
+            last_hit_at?: string | null;
+            /** @description Latest first, as of the last flush */
+            recent_samples: components["schemas"]["RestrictionHitSampleSchema"][];
+            rule_id: string;
+        };
+        /** @enum {string} */
+        RestrictionRuleSortSchema: "CreatedAt" | "HitCount" | "LastHitAt";
+        /** @enum {string} */
+        RestrictionRuleTypeSchema: "Asn" | "IP" | "IPCidr" | "UserAgent" | "Composite";
+        /** @enum {string} */
+        RestrictionTargetSchema: "Thread" | "Response" | "AuthCode";
         ServerSetting: {
             /** Format: date-time */
             created_at: string;
@@ -925,6 +1088,11 @@ export interface components {
             /** Format: date-time */
             last_modified: string;
             metadent: string;
+            /**
+             * Format: int64
+             * @description Responses within the last hour, only filled in the thread list
+             */
+            momentum?: number;
             no_pool: boolean;
             /** Format: int32 */
             response_count: number;
@@ -1004,11 +1172,33 @@ export interface components {
             mail?: string | null;
         };
         UpdateRestrictionRuleRequest: {
+            action?: null | components["schemas"]["RestrictionActionSchema"];
+            board_keys?: string[] | null;
+            captcha_provider?: string | null;
             /** Format: date-time */
             expires_at?: string | null;
             name?: string | null;
             rule_type?: null | components["schemas"]["RestrictionRuleTypeSchema"];
             rule_value?: string | null;
+            /** Format: int32 */
+            slow_down_seconds?: number | null;
+            targets?: components["schemas"]["RestrictionTargetSchema"][] | null;
+        };
+        UpdateRestrictionSubscriptionRequest: {
+            action?: null | components["schemas"]["RestrictionActionSchema"];
+            board_keys?: string[] | null;
+            captcha_provider?: string | null;
+            enabled?: boolean | null;
+            /** Format: int32 */
+            expires_after_seconds?: number | null;
+            format?: null | components["schemas"]["RestrictionRuleFormatSchema"];
+            name?: string | null;
+            /** Format: int32 */
+            refresh_interval_seconds?: number | null;
+            /** Format: int32 */
+            slow_down_seconds?: number | null;
+            source?: string | null;
+            targets?: components["schemas"]["RestrictionTargetSchema"][] | null;
         };
         UpdateTermsInput: {
             content: string;
@@ -1035,6 +1225,9 @@ export interface components {
             user_id: string;
         };
         UserRestrictionRuleSchema: {
+            action: components["schemas"]["RestrictionActionSchema"];
+            board_keys?: string[] | null;
+            captcha_provider?: string | null;
             /** Format: date-time */
             created_at: string;
             created_by_email: string;
@@ -1044,6 +1237,47 @@ export interface components {
             name: string;
             rule_type: components["schemas"]["RestrictionRuleTypeSchema"];
             rule_value: string;
+            /** Format: int32 */
+            slow_down_seconds: number;
+            /** @description Set on the rules of a blocklist subscription */
+            subscription_id?: string | null;
+            targets: components["schemas"]["RestrictionTargetSchema"][];
+            /** Format: date-time */
+            updated_at: string;
+        };
+        UserRestrictionRuleWithHitsSchema: components["schemas"]["UserRestrictionRuleSchema"] & {
+            /**
+             * Format: int64
+             * @description As of the last flush of the hits counted by eddist-server
+             */
+            hit_count: number;
+            /** Format: date-time */
+            last_hit_at?: string | null;
+        };
+        UserRestrictionSubscriptionSchema: {
+            action: components["schemas"]["RestrictionActionSchema"];
+            board_keys?: string[] | null;
+            captcha_provider?: string | null;
+            /** Format: date-time */
+            created_at: string;
+            created_by_email: string;
+            enabled: boolean;
+            /** Format: int32 */
+            expires_after_seconds: number;
+            format: components["schemas"]["RestrictionRuleFormatSchema"];
+            id: string;
+            last_error?: string | null;
+            /** Format: date-time */
+            last_refreshed_at?: string | null;
+            name: string;
+            /** Format: int32 */
+            refresh_interval_seconds: number;
+            /** Format: int32 */
+            rule_count: number;
+            /** Format: int32 */
+            slow_down_seconds: number;
+            source: string;
+            targets: components["schemas"]["RestrictionTargetSchema"][];
             /** Format: date-time */
             updated_at: string;
         };
@@ -2398,20 +2632,25 @@ export interface operations {
     };
     get_restriction_rules: {
         parameters: {
-            query?: never;
+            query?: {
+                /** @description Defaults to `CreatedAt` */
+                sort?: null | components["schemas"]["RestrictionRuleSortSchema"];
+                /** @description Defaults to descending, the newest or most hit first */
+                ascending?: boolean | null;
+            };
             header?: never;
             path?: never;
             cookie?: never;
         };
         requestBody?: never;
         responses: {
-            /** @description List all restriction rules */
+            /** @description List all restriction rules with their hits */
             200: {
                 headers: {
                     [name: string]: unknown;
                 };
                 content: {
-                    "application/json": components["schemas"]["UserRestrictionRuleSchema"][];
+                    "application/json": components["schemas"]["UserRestrictionRuleWithHitsSchema"][];
                 };
             };
         };
@@ -2509,6 +2748,174 @@ export interface operations {
             };
         };
     };
+    get_restriction_rule_hits: {
+        parameters: {
+            query?: never;
+            header?: never;
+            path: {
+                /** @description Rule ID */
+                rule_id: string;
+            };
+            cookie?: never;
+        };
+        requestBody?: never;
+        responses: {
+            /** @description Get the hits of a restriction rule and the clients it matched lately */
+            200: {
+                headers: {
+                    [name: string]: unknown;
+                };
+                content: {
+                    "application/json": components["schemas"]["RestrictionRuleHitsSchema"];
+                };
+            };
+            /** @description Restriction rule not found */
+            404: {
+                headers: {
+                    [name: string]: unknown;
+                };
+                content?: never;
+            };
+        };
+    };
+    import_restriction_rules: {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        requestBody: {
+            content: {
+                "application/json": components["schemas"]["ImportRestrictionRulesRequest"];
+            };
+        };
+        responses: {
+            /** @description Import restriction rules, or validate them with dry_run */
+            200: {
+                headers: {
+                    [name: string]: unknown;
+                };
+                content: {
+                    "application/json": components["schemas"]["ImportRestrictionRulesResponse"];
+                };
+            };
+        };
+    };
+    export_restriction_rules: {
+        parameters: {
+            query: {
+                format: components["schemas"]["RestrictionRuleFormatSchema"];
+            };
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        requestBody?: never;
+        responses: {
+            /** @description Export all restriction rules */
+            200: {
+                headers: {
+                    [name: string]: unknown;
+                };
+                content: {
+                    "application/json": components["schemas"]["ExportRestrictionRulesResponse"];
+                };
+            };
+        };
+    };
+    get_restriction_subscriptions: {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        requestBody?: never;
+        responses: {
+            /** @description List blocklist subscriptions */
+            200: {
+                headers: {
+                    [name: string]: unknown;
+                };
+                content: {
+                    "application/json": components["schemas"]["UserRestrictionSubscriptionSchema"][];
+                };
+            };
+        };
+    };
+    create_restriction_subscription: {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        requestBody: {
+            content: {
+                "application/json": components["schemas"]["CreateRestrictionSubscriptionRequest"];
+            };
+        };
+        responses: {
+            /** @description Create blocklist subscription */
+            201: {
+                headers: {
+                    [name: string]: unknown;
+                };
+                content: {
+                    "application/json": components["schemas"]["UserRestrictionSubscriptionSchema"];
+                };
+            };
+        };
+    };
+    delete_restriction_subscription: {
+        parameters: {
+            query?: never;
+            header?: never;
+            path: {
+                /** @description Subscription ID */
+                subscription_id: string;
+            };
+            cookie?: never;
+        };
+        requestBody?: never;
+        responses: {
+            /** @description Delete blocklist subscription and its rules */
+            200: {
+                headers: {
+                    [name: string]: unknown;
+                };
+                content?: never;
+            };
+        };
+    };
+    update_restriction_subscription: {
+        parameters: {
+            query?: never;
+            header?: never;
+            path: {
+                /** @description Subscription ID */
+                subscription_id: string;
+            };
+            cookie?: never;
+        };
+        requestBody: {
+            content: {
+                "application/json": components["schemas"]["UpdateRestrictionSubscriptionRequest"];
+            };
+        };
+        responses: {
+            /** @description Update blocklist subscription, which is refreshed on the next cron run */
+            200: {
+                headers: {
+                    [name: string]: unknown;
+                };
+                content: {
+                    "application/json": components["schemas"]["UserRestrictionSubscriptionSchema"];
+                };
+            };
+        };
+    };
     list_server_settings: {
         parameters: {
             query?: never;
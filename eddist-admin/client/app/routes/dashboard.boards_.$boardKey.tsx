@@ -85,6 +85,7 @@ const Page = () => {
                       threadNumber: Number(x.thread_number),
                       title: x.title,
                       responseCount: Number(x.response_count),
+                      momentum: Number(x.momentum ?? 0),
                       lastModified: x.last_modified,
                       boardId: Number(board?.id),
                     })) ?? []
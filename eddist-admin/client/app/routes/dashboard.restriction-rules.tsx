@@ -4,6 +4,7 @@ import {
   Modal,
   ModalBody,
   ModalHeader,
+  Select,
   Table,
   TableBody,
   TableCell,
@@ -11,14 +12,24 @@ import {
   TableHeadCell,
   TableRow,
 } from "flowbite-react";
+import { useState } from "react";
 import { BiDotsHorizontalRounded } from "react-icons/bi";
-import { FaPlus } from "react-icons/fa";
+import { FaDownload, FaFileImport, FaPlus } from "react-icons/fa";
 import RestrictionRuleForm from "~/components/RestrictionRuleForm";
+import RestrictionRuleHits from "~/components/RestrictionRuleHits";
+import RestrictionRuleImportForm from "~/components/RestrictionRuleImportForm";
+import RestrictionSubscriptionForm from "~/components/RestrictionSubscriptionForm";
 import {
   getRestrictionRules,
+  getRestrictionSubscriptions,
   useCreateRestrictionRule,
+  useCreateRestrictionSubscription,
   useDeleteRestrictionRule,
+  useDeleteRestrictionSubscription,
+  useExportRestrictionRules,
+  useImportRestrictionRules,
   useUpdateRestrictionRule,
+  useUpdateRestrictionSubscription,
 } from "~/hooks/queries";
 import { useCrudModalState } from "~/hooks/useCrudModalState";
 import { formatDateTime } from "~/utils/format";
@@ -26,20 +37,95 @@ import { formatDateTime } from "~/utils/format";
 interface RestrictionRule {
   id: string;
   name: string;
-  rule_type: "Asn" | "IP" | "IPCidr" | "UserAgent";
+  rule_type: "Asn" | "IP" | "IPCidr" | "UserAgent" | "Composite";
   rule_value: string;
+  board_keys?: string[] | null;
+  targets: ("Thread" | "Response" | "AuthCode")[];
+  action: "Block" | "ReAuth" | "Captcha" | "SlowDown";
+  captcha_provider?: string | null;
+  slow_down_seconds: number;
   expires_at?: string | null;
+  subscription_id?: string | null;
   created_at: string;
   updated_at: string;
   created_by_email: string;
 }
 
+interface RestrictionSubscription {
+  id: string;
+  name: string;
+  source: string;
+  format: "Csv" | "Json" | "Cidr";
+  board_keys?: string[] | null;
+  targets: ("Thread" | "Response" | "AuthCode")[];
+  action: "Block" | "ReAuth" | "Captcha" | "SlowDown";
+  captcha_provider?: string | null;
+  slow_down_seconds: number;
+  refresh_interval_seconds: number;
+  expires_after_seconds: number;
+  enabled: boolean;
+  last_refreshed_at?: string | null;
+  last_error?: string | null;
+  rule_count: number;
+}
+
+const SORT_OPTIONS = [
+  { label: "Newest first", sort: "CreatedAt", ascending: false },
+  { label: "Oldest first", sort: "CreatedAt", ascending: true },
+  { label: "Most hits", sort: "HitCount", ascending: false },
+  { label: "Fewest hits", sort: "HitCount", ascending: true },
+  { label: "Hit most recently", sort: "LastHitAt", ascending: false },
+  { label: "Not hit for longest", sort: "LastHitAt", ascending: true },
+] as const;
+
+const EXPORT_FILE_EXTENSIONS = {
+  Csv: "csv",
+  Json: "json",
+  Cidr: "txt",
+} as const;
+
 const RestrictionRules = () => {
-  const { data: restrictionRules } = getRestrictionRules({});
+  const [sortOption, setSortOption] = useState(0);
+  const { data: restrictionRules } = getRestrictionRules({
+    params: {
+      query: {
+        sort: SORT_OPTIONS[sortOption].sort,
+        ascending: SORT_OPTIONS[sortOption].ascending,
+      },
+    },
+  });
+  const [hitsRule, setHitsRule] = useState<RestrictionRule | undefined>();
   const createMutation = useCreateRestrictionRule();
   const updateMutation = useUpdateRestrictionRule();
   const deleteMutation = useDeleteRestrictionRule();
   const modal = useCrudModalState<RestrictionRule>();
+  const { data: subscriptions } = getRestrictionSubscriptions({});
+  const createSubscriptionMutation = useCreateRestrictionSubscription();
+  const updateSubscriptionMutation = useUpdateRestrictionSubscription();
+  const deleteSubscriptionMutation = useDeleteRestrictionSubscription();
+  const subscriptionModal = useCrudModalState<RestrictionSubscription>();
+  const importMutation = useImportRestrictionRules();
+  const exportMutation = useExportRestrictionRules();
+  const [isImportOpen, setIsImportOpen] = useState(false);
+
+  const subscriptionNames = new Map(subscriptions?.map((s) => [s.id, s.name]));
+
+  const exportRules = (format: keyof typeof EXPORT_FILE_EXTENSIONS) => {
+    exportMutation.mutate(
+      { params: { query: { format } } },
+      {
+        onSuccess: (data) => {
+          if (!data) return;
+          const url = URL.createObjectURL(new Blob([data.content], { type: "text/plain" }));
+          const link = document.createElement("a");
+          link.href = url;
+          link.download = `restriction-rules.${EXPORT_FILE_EXTENSIONS[format]}`;
+          link.click();
+          URL.revokeObjectURL(url);
+        },
+      },
+    );
+  };
 
   const formatExpiry = (expiresAt?: string | null) => {
     if (!expiresAt) return "Never";
@@ -49,6 +135,19 @@ const RestrictionRules = () => {
     return expiry.toLocaleString();
   };
 
+  const formatAction = (
+    rule: Pick<RestrictionRule, "action" | "captcha_provider" | "slow_down_seconds">,
+  ) => {
+    switch (rule.action) {
+      case "Captcha":
+        return `Captcha (${rule.captcha_provider ?? ""})`;
+      case "SlowDown":
+        return `Slow down (${rule.slow_down_seconds}s)`;
+      default:
+        return rule.action;
+    }
+  };
+
   return (
     <>
       <Modal show={modal.isCreateOpen} onClose={() => modal.closeCreate()} dismissible>
@@ -84,9 +183,110 @@ const RestrictionRules = () => {
         </Modal>
       )}
 
+      {hitsRule && (
+        <Modal show onClose={() => setHitsRule(undefined)} size="5xl" dismissible>
+          <ModalHeader className="border-gray-200">Hits of {hitsRule.name}</ModalHeader>
+          <ModalBody>
+            <RestrictionRuleHits ruleId={hitsRule.id} />
+          </ModalBody>
+        </Modal>
+      )}
+
+      <Modal
+        show={isImportOpen}
+        onClose={() => {
+          setIsImportOpen(false);
+          importMutation.reset();
+        }}
+        dismissible
+      >
+        <ModalHeader className="border-gray-200">Import Restriction Rules</ModalHeader>
+        <ModalBody>
+          <RestrictionRuleImportForm
+            result={importMutation.data}
+            onSubmit={(data, dryRun) => {
+              importMutation.mutate({ body: { ...data, dry_run: dryRun } });
+            }}
+          />
+        </ModalBody>
+      </Modal>
+
+      <Modal
+        show={subscriptionModal.isCreateOpen}
+        onClose={() => subscriptionModal.closeCreate()}
+        dismissible
+      >
+        <ModalHeader className="border-gray-200">Create Blocklist Subscription</ModalHeader>
+        <ModalBody>
+          <RestrictionSubscriptionForm
+            mode="create"
+            onSubmit={(data) => {
+              createSubscriptionMutation.mutate(
+                { body: data },
+                { onSuccess: () => subscriptionModal.closeCreate() },
+              );
+            }}
+          />
+        </ModalBody>
+      </Modal>
+
+      {subscriptionModal.editingItem && (
+        <Modal
+          show={subscriptionModal.isEditOpen}
+          onClose={() => subscriptionModal.closeEdit()}
+          dismissible
+        >
+          <ModalHeader className="border-gray-200">Edit Blocklist Subscription</ModalHeader>
+          <ModalBody>
+            <RestrictionSubscriptionForm
+              mode="edit"
+              defaultValues={subscriptionModal.editingItem}
+              onSubmit={(data) => {
+                updateSubscriptionMutation.mutate(
+                  {
+                    params: {
+                      path: { subscription_id: subscriptionModal.editingItem?.id ?? "" },
+                    },
+                    body: data,
+                  },
+                  { onSuccess: () => subscriptionModal.closeEdit() },
+                );
+              }}
+            />
+          </ModalBody>
+        </Modal>
+      )}
+
       <div className="p-2 lg:p-8">
         <div className="flex">
           <h1 className="text-3xl font-bold grow">Restriction Rules</h1>
+          <Select
+            className="mr-2 self-center"
+            value={sortOption}
+            onChange={(e) => setSortOption(Number(e.target.value))}
+          >
+            {SORT_OPTIONS.map((option, idx) => (
+              <option key={option.label} value={idx}>
+                {option.label}
+              </option>
+            ))}
+          </Select>
+          <div className="mr-2 bg-slate-400 p-4 rounded-xl shadow-lg hover:bg-slate-500">
+            <Dropdown label={<FaDownload />} arrowIcon={false} inline>
+              <DropdownItem onClick={() => exportRules("Csv")}>Export as CSV</DropdownItem>
+              <DropdownItem onClick={() => exportRules("Json")}>Export as JSON</DropdownItem>
+              <DropdownItem onClick={() => exportRules("Cidr")}>
+                Export IP rules as CIDR list
+              </DropdownItem>
+            </Dropdown>
+          </div>
+          <button
+            type="button"
+            className="mr-2 bg-slate-400 p-4 rounded-xl shadow-lg hover:bg-slate-500"
+            onClick={() => setIsImportOpen(true)}
+          >
+            <FaFileImport />
+          </button>
           <button
             type="button"
             className="mr-2 bg-slate-400 p-4 rounded-xl shadow-lg hover:bg-slate-500"
@@ -100,7 +300,12 @@ const RestrictionRules = () => {
             <TableHeadCell>Name</TableHeadCell>
             <TableHeadCell>Type</TableHeadCell>
             <TableHeadCell>Value</TableHeadCell>
+            <TableHeadCell>Boards</TableHeadCell>
+            <TableHeadCell>Targets</TableHeadCell>
+            <TableHeadCell>Action</TableHeadCell>
             <TableHeadCell>Expires</TableHeadCell>
+            <TableHeadCell>Hits</TableHeadCell>
+            <TableHeadCell>Last Hit</TableHeadCell>
             <TableHeadCell>Created By</TableHeadCell>
             <TableHeadCell>Created At</TableHeadCell>
             <TableHeadCell></TableHeadCell>
@@ -108,13 +313,23 @@ const RestrictionRules = () => {
           <TableBody className="divide-y">
             {restrictionRules?.map((rule) => (
               <TableRow className="border-gray-200" key={rule.id}>
-                <TableCell className="font-medium">{rule.name}</TableCell>
+                <TableCell className="font-medium">
+                  {rule.name}
+                  {rule.subscription_id && (
+                    <span className="ml-2 px-2 py-1 text-xs font-semibold rounded-full bg-purple-100 text-purple-800">
+                      {subscriptionNames.get(rule.subscription_id) ?? "Subscription"}
+                    </span>
+                  )}
+                </TableCell>
                 <TableCell>
                   <span className="px-2 py-1 text-xs font-semibold rounded-full bg-blue-100 text-blue-800">
                     {rule.rule_type}
                   </span>
                 </TableCell>
-                <TableCell className="font-mono text-sm">{rule.rule_value}</TableCell>
+                <TableCell className="font-mono text-sm break-all">{rule.rule_value}</TableCell>
+                <TableCell>{rule.board_keys?.join(", ") ?? "All"}</TableCell>
+                <TableCell>{rule.targets.join(", ")}</TableCell>
+                <TableCell>{formatAction(rule)}</TableCell>
                 <TableCell>
                   <span
                     className={`px-2 py-1 text-xs font-semibold rounded-full ${
@@ -128,12 +343,17 @@ const RestrictionRules = () => {
                     {formatExpiry(rule.expires_at)}
                   </span>
                 </TableCell>
+                <TableCell>{rule.hit_count}</TableCell>
+                <TableCell>
+                  {rule.last_hit_at ? formatDateTime(rule.last_hit_at) : "Never"}
+                </TableCell>
                 <TableCell>{rule.created_by_email}</TableCell>
                 <TableCell>{formatDateTime(rule.created_at)}</TableCell>
                 <TableCell>
                   <div className="text-right">
                     <Dropdown label={<BiDotsHorizontalRounded />}>
                       <DropdownItem onClick={() => modal.openEdit(rule)}>Edit</DropdownItem>
+                      <DropdownItem onClick={() => setHitsRule(rule)}>Hits</DropdownItem>
                       <DropdownItem
                         className="text-red-500"
                         onClick={() => {
@@ -155,6 +375,88 @@ const RestrictionRules = () => {
             ))}
           </TableBody>
         </Table>
+
+        <div className="flex mt-8">
+          <h2 className="text-2xl font-bold grow">Blocklist Subscriptions</h2>
+          <button
+            type="button"
+            className="mr-2 bg-slate-400 p-4 rounded-xl shadow-lg hover:bg-slate-500"
+            onClick={() => subscriptionModal.openCreate()}
+          >
+            <FaPlus />
+          </button>
+        </div>
+        <Table className="mt-4">
+          <TableHead>
+            <TableHeadCell>Name</TableHeadCell>
+            <TableHeadCell>Source</TableHeadCell>
+            <TableHeadCell>Format</TableHeadCell>
+            <TableHeadCell>Action</TableHeadCell>
+            <TableHeadCell>Refresh</TableHeadCell>
+            <TableHeadCell>Rules</TableHeadCell>
+            <TableHeadCell>Last Refreshed</TableHeadCell>
+            <TableHeadCell>Status</TableHeadCell>
+            <TableHeadCell></TableHeadCell>
+          </TableHead>
+          <TableBody className="divide-y">
+            {subscriptions?.map((subscription) => (
+              <TableRow className="border-gray-200" key={subscription.id}>
+                <TableCell className="font-medium">{subscription.name}</TableCell>
+                <TableCell className="font-mono text-sm break-all">{subscription.source}</TableCell>
+                <TableCell>{subscription.format}</TableCell>
+                <TableCell>{formatAction(subscription)}</TableCell>
+                <TableCell>Every {subscription.refresh_interval_seconds / 60} min</TableCell>
+                <TableCell>{subscription.rule_count}</TableCell>
+                <TableCell>
+                  {subscription.last_refreshed_at
+                    ? formatDateTime(subscription.last_refreshed_at)
+                    : "Never"}
+                </TableCell>
+                <TableCell>
+                  {!subscription.enabled ? (
+                    <span className="px-2 py-1 text-xs font-semibold rounded-full bg-gray-100 text-gray-800">
+                      Disabled
+                    </span>
+                  ) : subscription.last_error ? (
+                    <span
+                      className="px-2 py-1 text-xs font-semibold rounded-full bg-red-100 text-red-800"
+                      title={subscription.last_error}
+                    >
+                      Error
+                    </span>
+                  ) : (
+                    <span className="px-2 py-1 text-xs font-semibold rounded-full bg-green-100 text-green-800">
+                      OK
+                    </span>
+                  )}
+                </TableCell>
+                <TableCell>
+                  <div className="text-right">
+                    <Dropdown label={<BiDotsHorizontalRounded />}>
+                      <DropdownItem onClick={() => subscriptionModal.openEdit(subscription)}>
+                        Edit
+                      </DropdownItem>
+                      <DropdownItem
+                        className="text-red-500"
+                        onClick={() => {
+                          deleteSubscriptionMutation.mutate({
+                            params: {
+                              path: {
+                                subscription_id: subscription.id,
+                              },
+                            },
+                          });
+                        }}
+                      >
+                        Delete
+                      </DropdownItem>
+                    </Dropdown>
+                  </div>
+                </TableCell>
+              </TableRow>
+            ))}
+          </TableBody>
+        </Table>
       </div>
     </>
   );
@@ -11,8 +11,9 @@ use crate::{
         terms_repository::UpdateTermsInput,
     },
     routes::{
-        archives, auth_tokens, boards, captcha, idps, moderation, notices, server_settings, terms,
-        threads, users,
+        admin_roles, archives, audit_logs, auth_tokens, boards, captcha, idps, moderation,
+        moderation_queue, moderation_rules, notices, search, server_settings, terms, threads,
+        users,
     },
 };
 
@@ -32,6 +33,13 @@ use crate::{
         threads::get_responses,
         threads::update_response,
         threads::threads_compaction,
+        threads::stop_thread,
+        threads::reopen_thread,
+        threads::pin_thread,
+        threads::unpin_thread,
+        threads::move_thread,
+        threads::bulk_moderate_responses,
+        threads::get_momentum_alerts,
 
         // Archive routes
         archives::get_archived_threads,
@@ -47,6 +55,7 @@ use crate::{
         auth_tokens::list_authed_tokens,
         auth_tokens::get_authed_token,
         auth_tokens::delete_authed_token,
+        auth_tokens::suspend_authed_token,
         auth_tokens::require_reauth_token,
         auth_tokens::clear_require_reauth_token,
 
@@ -62,8 +71,28 @@ use crate::{
         moderation::get_restriction_rules,
         moderation::create_restriction_rule,
         moderation::get_restriction_rule,
+        moderation::get_restriction_rule_hits,
         moderation::update_restriction_rule,
         moderation::delete_restriction_rule,
+        moderation::import_restriction_rules,
+        moderation::export_restriction_rules,
+        moderation::get_restriction_subscriptions,
+        moderation::create_restriction_subscription,
+        moderation::update_restriction_subscription,
+        moderation::delete_restriction_subscription,
+
+        // Moderation queue routes
+        moderation_queue::get_held_posts,
+        moderation_queue::approve_held_post,
+        moderation_queue::reject_held_post,
+
+        // Moderation rule routes
+        moderation_rules::get_moderation_rules,
+        moderation_rules::create_moderation_rule,
+        moderation_rules::update_moderation_rule,
+        moderation_rules::delete_moderation_rule,
+        moderation_rules::get_moderation_actions,
+        moderation_rules::revert_moderation_action,
 
         // User routes
         users::search_users,
@@ -98,6 +127,22 @@ use crate::{
         server_settings::list_server_settings,
         server_settings::upsert_server_setting,
 
+        // Audit log routes
+        audit_logs::list_audit_logs,
+
+        // Search routes
+        search::search_posts,
+
+        // Admin role routes
+        admin_roles::get_admin_roles,
+        admin_roles::create_admin_role,
+        admin_roles::update_admin_role,
+        admin_roles::delete_admin_role,
+        admin_roles::get_admin_users,
+        admin_roles::assign_admin_role,
+        admin_roles::get_current_admin,
+        admin_roles::delete_admin_user,
+
         // Auth routes
         post_native_session,
     ),
@@ -105,14 +150,24 @@ use crate::{
         // Core models
         Board,
         BoardInfo,
+        BoardModerationModeSchema,
+        BoardModerationConfigSchema,
+        ModerationProviderConfigSchema,
+        KeywordScoreRuleSchema,
         CreateBoardInput,
         EditBoardInput,
         Thread,
         ThreadCompactionInput,
+        MoveThreadInput,
+        MomentumAlert,
         Res,
         ClientInfo,
         Tinker,
         UpdateResInput,
+        BulkResponseFilterType,
+        BulkResponseAction,
+        BulkModerateResponsesInput,
+        BulkModerateResponsesResult,
 
         // Archive models
         ArchivedThread,
@@ -125,6 +180,7 @@ use crate::{
         AuthedToken,
         PaginatedAuthedTokens,
         DeleteAuthedTokenInput,
+        SuspendAuthedTokenInput,
         NativeSessionRequest,
         NativeSessionResponse,
         NativeUserInfo,
@@ -133,13 +189,44 @@ use crate::{
         NgWord,
         CreationNgWordInput,
         UpdateNgWordInput,
+        NgWordMatchTypeSchema,
+        NgWordTargetSchema,
+        NgWordActionSchema,
         Cap,
         CreationCapInput,
         UpdateCapInput,
         CreateRestrictionRuleRequest,
         UpdateRestrictionRuleRequest,
         UserRestrictionRuleSchema,
+        UserRestrictionRuleWithHitsSchema,
+        RestrictionRuleSortSchema,
+        RestrictionHitSampleSchema,
+        RestrictionRuleHitsSchema,
         RestrictionRuleTypeSchema,
+        RestrictionTargetSchema,
+        RestrictionActionSchema,
+        RestrictionRuleFormatSchema,
+        ImportRestrictionRulesRequest,
+        ImportRestrictionRulesResponse,
+        RestrictionImportErrorSchema,
+        RestrictionImportDuplicateSchema,
+        ExportRestrictionRulesResponse,
+        CreateRestrictionSubscriptionRequest,
+        UpdateRestrictionSubscriptionRequest,
+        UserRestrictionSubscriptionSchema,
+
+        // Moderation queue models
+        HeldPost,
+        HeldPostTypeSchema,
+        HoldReasonSchema,
+        ModerationQueueStatusSchema,
+
+        // Moderation rule models
+        ModerationRule,
+        CreateModerationRuleInput,
+        UpdateModerationRuleInput,
+        ModerationRuleActionSchema,
+        ModerationActionLog,
 
         // User models
         User,
@@ -173,6 +260,23 @@ use crate::{
         RequestFormat,
         CreateCaptchaConfigInput,
         UpdateCaptchaConfigInput,
+
+        // Audit log models
+        AuditLog,
+        PaginatedAuditLogs,
+
+        // Search models
+        SearchHit,
+        SearchPostsQuery,
+
+        // Admin role models
+        AdminScope,
+        AdminRole,
+        CreateAdminRoleInput,
+        UpdateAdminRoleInput,
+        AdminUserAssignment,
+        AssignAdminRoleInput,
+        CurrentAdmin,
     ))
 )]
 pub struct ApiDoc;
@@ -1,13 +1,14 @@
-use std::env;
+use std::{env, net::SocketAddr, sync::Arc};
 
 use axum::{
     body::Body,
-    extract::{FromRequestParts, State},
-    http::{self, HeaderValue, Request, StatusCode, request::Parts},
+    extract::{ConnectInfo, FromRequestParts, State},
+    http::{self, Extensions, HeaderValue, Request, StatusCode, request::Parts},
     middleware::Next,
     response::{IntoResponse, Redirect, Response},
 };
 use chrono::{TimeDelta, Utc};
+use eddist_core::client_addr::ClientAddrConfig;
 use jsonwebtoken::errors::ErrorKind;
 use oauth2::{
     AuthorizationCode, CsrfToken, PkceCodeChallenge, PkceCodeVerifier, RefreshToken, Scope,
@@ -15,10 +16,15 @@ use oauth2::{
 };
 use serde::{Deserialize, Serialize};
 use tower_sessions::Session;
+use uuid::Uuid;
 
 use crate::{
     AppState,
-    models::auth::{NativeSessionRequest, NativeSessionResponse, NativeUserInfo},
+    error::ApiError,
+    models::{
+        AdminBoards, AdminScope, AdminScopes,
+        auth::{NativeSessionRequest, NativeSessionResponse, NativeUserInfo},
+    },
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -266,6 +272,35 @@ pub async fn auth_simple_header(
         .unwrap()
 }
 
+/// Loads the scopes and boards of the admin authenticated by `auth_simple_header`, so it must
+/// run after it
+pub async fn load_admin_access(
+    State(state): State<AppState>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(email) = req
+        .extensions()
+        .get::<Auth0Claims>()
+        .map(|claims| claims.email.clone())
+    else {
+        return next.run(req).await;
+    };
+
+    let services = &state.services.admin_role;
+    match tokio::try_join!(services.get_scopes(&email), services.get_boards(&email)) {
+        Ok((scopes, boards)) => {
+            req.extensions_mut().insert(scopes);
+            req.extensions_mut().insert(boards);
+            next.run(req).await
+        }
+        Err(e) => {
+            tracing::error!("failed to load admin access: {e:?}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct AdminSession {
     id: [u8; 16],
@@ -434,12 +469,8 @@ where
                 .and_then(|v| v.to_str().ok())
                 .unwrap_or("")
                 .contains("eddist-manager");
-            let ip = req
-                .headers
-                .get("CF-Connecting-IP")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or("localhost")
-                .to_string();
+            let ip = get_client_ip(&req.headers, &req.extensions)
+                .unwrap_or_else(|| "localhost".to_string());
             let ua = req
                 .headers
                 .get("User-Agent")
@@ -459,6 +490,62 @@ pub struct AdminIdentity {
     pub sub: String,
     pub email: String,
     pub username: String,
+    pub scopes: AdminScopes,
+    pub boards: AdminBoards,
+}
+
+impl AdminIdentity {
+    /// Actor for internal routes, which don't have a session-based actor
+    pub fn system() -> Self {
+        Self {
+            sub: "system".to_string(),
+            email: "system@internal".to_string(),
+            username: "system".to_string(),
+            scopes: AdminScopes::all(),
+            boards: AdminBoards::All,
+        }
+    }
+
+    pub fn require(&self, scope: AdminScope) -> Result<(), ApiError> {
+        if self.scopes.contains(scope) {
+            Ok(())
+        } else {
+            Err(ApiError::forbidden(format!("missing scope: {scope}")))
+        }
+    }
+
+    pub fn require_board_key(&self, board_key: &str) -> Result<(), ApiError> {
+        if self.boards.allows_key(board_key) {
+            Ok(())
+        } else {
+            Err(ApiError::forbidden(format!(
+                "no access to board: {board_key}"
+            )))
+        }
+    }
+
+    /// Site-wide settings (e.g. NG words) are out of reach of board moderators
+    pub fn require_all_boards(&self) -> Result<(), ApiError> {
+        if self.boards.is_restricted() {
+            Err(ApiError::forbidden(
+                "board moderators cannot change site-wide settings",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Boards a listing should be filtered to, checking the requested board if any.
+    /// `None` means every board.
+    pub fn board_filter(&self, board_id: Option<Uuid>) -> Result<Option<Vec<Uuid>>, ApiError> {
+        match board_id {
+            Some(board_id) if !self.boards.allows_id(board_id) => Err(ApiError::forbidden(
+                format!("no access to board: {board_id}"),
+            )),
+            Some(board_id) => Ok(Some(vec![board_id])),
+            None => Ok(self.boards.ids()),
+        }
+    }
 }
 
 impl<S> FromRequestParts<S> for AdminIdentity
@@ -474,6 +561,16 @@ where
                 sub: token.sub.clone(),
                 email: token.email.clone(),
                 username: token.preferred_username.clone(),
+                scopes: req
+                    .extensions
+                    .get::<AdminScopes>()
+                    .cloned()
+                    .unwrap_or_default(),
+                boards: req
+                    .extensions
+                    .get::<AdminBoards>()
+                    .cloned()
+                    .unwrap_or_default(),
             })
             .ok_or((StatusCode::UNAUTHORIZED, "No user information available"))
     }
@@ -505,6 +602,7 @@ pub async fn get_logout(session: Session) -> impl IntoResponse {
 pub async fn post_native_session(
     State(_state): State<AppState>,
     headers: http::HeaderMap,
+    extensions: Extensions,
     session: Session,
     axum::Json(req): axum::Json<NativeSessionRequest>,
 ) -> impl IntoResponse {
@@ -524,7 +622,7 @@ pub async fn post_native_session(
     let ClientInfo {
         client_ip,
         client_ua,
-    } = get_client_info(&headers);
+    } = get_client_info(&headers, &extensions);
 
     // Create AdminSession with the validated access token
     let admin_session = AdminSession {
@@ -570,14 +668,17 @@ struct ClientInfo {
     client_ua: String,
 }
 
-fn get_client_info(headers: &http::HeaderMap) -> ClientInfo {
-    let client_ip = headers
-        .get("Cf-Connecting-IP")
-        .or_else(|| headers.get("X-Real-IP"))
-        .or_else(|| headers.get("X-Forwarded-For"))
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("unknown")
-        .to_string();
+/// The client IP by the [`ClientAddrConfig`] of the app and the peer of the connection
+fn get_client_ip(headers: &http::HeaderMap, extensions: &Extensions) -> Option<String> {
+    let config = extensions.get::<Arc<ClientAddrConfig>>()?;
+    let peer = extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(peer)| *peer);
+    config.client_ip(headers, peer).map(|ip| ip.to_string())
+}
+
+fn get_client_info(headers: &http::HeaderMap, extensions: &Extensions) -> ClientInfo {
+    let client_ip = get_client_ip(headers, extensions).unwrap_or_else(|| "unknown".to_string());
     let client_ua = headers
         .get("User-Agent")
         .and_then(|v| v.to_str().ok())
@@ -1,15 +1,11 @@
-use std::{env, net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, sync::Arc};
 
 use auth::{
     auth_simple_header, get_check_auth, get_login, get_login_callback, get_logout,
-    post_native_session,
-};
-use aws_sdk_s3::{
-    Client as S3Client, Config as S3Config,
-    config::{BehaviorVersion, Credentials, Region},
+    load_admin_access, post_native_session,
 };
 use axum::{
-    Router, ServiceExt,
+    Extension, Router, ServiceExt,
     body::Body,
     extract::{MatchedPath, Request, State},
     http::{HeaderValue, StatusCode},
@@ -17,17 +13,24 @@ use axum::{
     response::{IntoResponse, Response},
     routing::{get, post},
 };
-use eddist_core::{tracing::init_tracing, utils::is_prod};
+use eddist_core::{
+    archive_storage::archive_storage_from_env, client_addr::ClientAddrConfig,
+    tracing::init_tracing, utils::is_prod,
+};
 use oauth2::{AuthUrl, ClientId, ClientSecret, EndpointNotSet, EndpointSet, RedirectUrl, TokenUrl};
 use repository::{
     admin_archive_repository::AdminArchiveRepositoryImpl,
     admin_board_repository::AdminBoardRepositoryImpl,
     admin_response_repository::AdminResponseRepositoryImpl,
+    admin_role_repository::AdminRoleRepositoryImpl,
     admin_thread_repository::AdminThreadRepositoryImpl,
-    admin_user_repository::AdminUserRepositoryImpl,
+    admin_user_repository::AdminUserRepositoryImpl, audit_log_repository::AuditLogRepositoryImpl,
     authed_token_repository::AuthedTokenRepositoryImpl, cap_repository::CapRepositoryImpl,
     captcha_config_repository::CaptchaConfigRepositoryImpl, idp_repository::IdpAdminRepositoryImpl,
+    moderation_queue_repository::ModerationQueueRepositoryImpl,
+    moderation_rule_repository::ModerationRuleRepositoryImpl,
     ngword_repository::NgWordRepositoryImpl, notice_repository::NoticeRepositoryImpl,
+    search_repository::AdminSearchRepositoryImpl,
     server_settings_repository::ServerSettingsRepositoryImpl,
     terms_repository::TermsRepositoryImpl,
     user_restriction_repository::UserRestrictionRepositoryImpl,
@@ -55,14 +58,19 @@ mod repository {
     pub mod admin_bbs_repository;
     pub mod admin_board_repository;
     pub mod admin_response_repository;
+    pub mod admin_role_repository;
     pub mod admin_thread_repository;
     pub mod admin_user_repository;
+    pub mod audit_log_repository;
     pub mod authed_token_repository;
     pub mod cap_repository;
     pub mod captcha_config_repository;
     pub mod idp_repository;
+    pub mod moderation_queue_repository;
+    pub mod moderation_rule_repository;
     pub mod ngword_repository;
     pub mod notice_repository;
+    pub mod search_repository;
     pub mod server_settings_repository;
     pub mod terms_repository;
     pub mod user_restriction_repository;
@@ -72,11 +80,13 @@ mod routes;
 use api_doc::ApiDoc;
 use repository::{
     admin_archive_repository::AdminArchiveRepository, admin_board_repository::AdminBoardRepository,
-    admin_response_repository::AdminResponseRepository,
+    admin_response_repository::AdminResponseRepository, admin_role_repository::AdminRoleRepository,
     admin_thread_repository::AdminThreadRepository, admin_user_repository::AdminUserRepository,
-    authed_token_repository::AuthedTokenRepository, cap_repository::CapRepository,
-    captcha_config_repository::CaptchaConfigRepository, idp_repository::IdpAdminRepository,
-    ngword_repository::NgWordRepository, notice_repository::NoticeRepository,
+    audit_log_repository::AuditLogRepository, authed_token_repository::AuthedTokenRepository,
+    cap_repository::CapRepository, captcha_config_repository::CaptchaConfigRepository,
+    idp_repository::IdpAdminRepository, moderation_queue_repository::ModerationQueueRepository,
+    moderation_rule_repository::ModerationRuleRepository, ngword_repository::NgWordRepository,
+    notice_repository::NoticeRepository, search_repository::AdminSearchRepository,
     server_settings_repository::ServerSettingsRepository, terms_repository::TermsRepository,
     user_restriction_repository::UserRestrictionRepository,
 };
@@ -131,25 +141,30 @@ async fn internal_secret_auth(
     next.run(req).await
 }
 
-/// Repositories for content management (boards, threads, responses, S3 archives).
+/// Repositories for content management (boards, threads, responses, S3 archives, search index).
 #[derive(Clone)]
 pub(crate) struct ContentRepos {
     pub board: Arc<dyn AdminBoardRepository>,
     pub thread: Arc<dyn AdminThreadRepository>,
     pub response: Arc<dyn AdminResponseRepository>,
     pub archive: Arc<dyn AdminArchiveRepository>,
+    pub search: Arc<dyn AdminSearchRepository>,
 }
 
-/// Repositories for moderation (NG words, caps, user restrictions, authed tokens).
+/// Repositories for moderation (NG words, caps, user restrictions, authed tokens, held posts,
+/// moderation rules).
 #[derive(Clone)]
 pub(crate) struct ModerationRepos {
     pub ng_word: Arc<dyn NgWordRepository>,
     pub cap: Arc<dyn CapRepository>,
     pub user_restriction: Arc<dyn UserRestrictionRepository>,
     pub authed_token: Arc<dyn AuthedTokenRepository>,
+    pub moderation_queue: Arc<dyn ModerationQueueRepository>,
+    pub moderation_rule: Arc<dyn ModerationRuleRepository>,
 }
 
-/// Repositories for site administration (users, IdPs, notices, terms, captcha, settings).
+/// Repositories for site administration (users, IdPs, notices, terms, captcha, settings,
+/// audit logs, admin roles).
 #[derive(Clone)]
 pub(crate) struct AdminRepos {
     pub user: Arc<dyn AdminUserRepository>,
@@ -158,6 +173,8 @@ pub(crate) struct AdminRepos {
     pub terms: Arc<dyn TermsRepository>,
     pub captcha_config: Arc<dyn CaptchaConfigRepository>,
     pub server_settings: Arc<dyn ServerSettingsRepository>,
+    pub audit_log: Arc<dyn AuditLogRepository>,
+    pub admin_role: Arc<dyn AdminRoleRepository>,
 }
 
 #[derive(Clone)]
@@ -227,23 +244,7 @@ async fn main() {
         .await
         .unwrap();
 
-    let r2_account_id = env::var("R2_ACCOUNT_ID").unwrap();
-    let s3_bucket_name = env::var("S3_BUCKET_NAME").unwrap().trim().to_string();
-    let s3_endpoint = format!("https://{}.r2.cloudflarestorage.com", r2_account_id.trim());
-    let s3_creds = Credentials::new(
-        env::var("S3_ACCESS_KEY").unwrap().trim(),
-        env::var("S3_ACCESS_SECRET_KEY").unwrap().trim(),
-        None,
-        None,
-        "custom",
-    );
-    let s3_config = S3Config::builder()
-        .behavior_version(BehaviorVersion::latest())
-        .credentials_provider(s3_creds)
-        .region(Region::new("auto"))
-        .endpoint_url(s3_endpoint)
-        .build();
-    let s3_client = S3Client::from_conf(s3_config);
+    let archive_storage = archive_storage_from_env().unwrap();
 
     let session_store = MemoryStore::default();
     let session_layer = SessionManagerLayer::new(session_store)
@@ -265,13 +266,16 @@ async fn main() {
             board: Arc::new(AdminBoardRepositoryImpl::new(pool.clone())),
             thread: Arc::new(AdminThreadRepositoryImpl::new(pool.clone())),
             response: Arc::new(AdminResponseRepositoryImpl::new(pool.clone())),
-            archive: Arc::new(AdminArchiveRepositoryImpl::new(s3_client, s3_bucket_name)),
+            archive: Arc::new(AdminArchiveRepositoryImpl::new(archive_storage)),
+            search: Arc::new(AdminSearchRepositoryImpl::new(pool.clone())),
         },
         ModerationRepos {
             ng_word: Arc::new(NgWordRepositoryImpl::new(pool.clone())),
             cap: Arc::new(CapRepositoryImpl::new(pool.clone())),
             user_restriction: Arc::new(UserRestrictionRepositoryImpl::new(pool.clone())),
             authed_token: Arc::new(AuthedTokenRepositoryImpl::new(pool.clone())),
+            moderation_queue: Arc::new(ModerationQueueRepositoryImpl::new(pool.clone())),
+            moderation_rule: Arc::new(ModerationRuleRepositoryImpl::new(pool.clone())),
         },
         AdminRepos {
             user: Arc::new(AdminUserRepositoryImpl::new(pool.clone())),
@@ -279,11 +283,16 @@ async fn main() {
             notice: Arc::new(NoticeRepositoryImpl::new(pool.clone())),
             terms: Arc::new(TermsRepositoryImpl::new(pool.clone())),
             captcha_config: Arc::new(CaptchaConfigRepositoryImpl::new(pool.clone())),
-            server_settings: Arc::new(ServerSettingsRepositoryImpl::new(pool)),
+            server_settings: Arc::new(ServerSettingsRepositoryImpl::new(pool.clone())),
+            audit_log: Arc::new(AuditLogRepositoryImpl::new(pool.clone())),
+            admin_role: Arc::new(AdminRoleRepositoryImpl::new(pool)),
         },
         redis_conn.clone(),
     );
 
+    // The IPs recorded on the admin sessions, read the same way as eddist-server
+    let client_addr_config = Arc::new(ClientAddrConfig::from_env().unwrap());
+
     let state = AppState {
         oauth2_client: client,
         services: service_container,
@@ -299,10 +308,15 @@ async fn main() {
         .route("/auth/native/session", post(post_native_session))
         .nest(
             "/api",
-            api_routes.layer(axum::middleware::from_fn_with_state(
-                state.clone(),
-                auth_simple_header,
-            )),
+            api_routes
+                .layer(axum::middleware::from_fn_with_state(
+                    state.clone(),
+                    load_admin_access,
+                ))
+                .layer(axum::middleware::from_fn_with_state(
+                    state.clone(),
+                    auth_simple_header,
+                )),
         )
         .nest(
             "/internal/api",
@@ -329,13 +343,17 @@ async fn main() {
             }),
         )
         .layer(axum::middleware::from_fn(add_some_header))
+        .layer(Extension(client_addr_config))
         .layer(session_layer)
         .with_state(state.clone());
 
     let app = NormalizePathLayer::trim_trailing_slash().layer(app);
 
     let listener = TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, ServiceExt::<Request>::into_make_service(app))
-        .await
-        .unwrap();
+    axum::serve(
+        listener,
+        ServiceExt::<Request>::into_make_service_with_connect_info::<SocketAddr>(app),
+    )
+    .await
+    .unwrap();
 }
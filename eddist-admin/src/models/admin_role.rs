@@ -0,0 +1,238 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    str::FromStr,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Permission checked by the admin API routes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ToSchema, Serialize, Deserialize)]
+pub enum AdminScope {
+    /// Grants every scope
+    #[serde(rename = "*")]
+    All,
+    #[serde(rename = "boards:write")]
+    BoardsWrite,
+    #[serde(rename = "threads:write")]
+    ThreadsWrite,
+    #[serde(rename = "archives:write")]
+    ArchivesWrite,
+    #[serde(rename = "archives:delete")]
+    ArchivesDelete,
+    /// NG words, caps, restriction rules, the moderation queue and the actions taken
+    #[serde(rename = "moderation:read")]
+    ModerationRead,
+    #[serde(rename = "moderation:write")]
+    ModerationWrite,
+    #[serde(rename = "tokens:read")]
+    TokensRead,
+    #[serde(rename = "tokens:suspend")]
+    TokensSuspend,
+    #[serde(rename = "tokens:revoke")]
+    TokensRevoke,
+    #[serde(rename = "users:read")]
+    UsersRead,
+    #[serde(rename = "users:write")]
+    UsersWrite,
+    #[serde(rename = "notices:write")]
+    NoticesWrite,
+    #[serde(rename = "terms:write")]
+    TermsWrite,
+    /// Captcha configs, IdPs and server settings, which include decrypted secrets
+    #[serde(rename = "settings:read")]
+    SettingsRead,
+    #[serde(rename = "settings:write")]
+    SettingsWrite,
+    #[serde(rename = "audit_logs:read")]
+    AuditLogsRead,
+    #[serde(rename = "roles:read")]
+    RolesRead,
+    #[serde(rename = "roles:write")]
+    RolesWrite,
+}
+
+impl AdminScope {
+    pub const VALUES: [AdminScope; 19] = [
+        AdminScope::All,
+        AdminScope::BoardsWrite,
+        AdminScope::ThreadsWrite,
+        AdminScope::ArchivesWrite,
+        AdminScope::ArchivesDelete,
+        AdminScope::ModerationRead,
+        AdminScope::ModerationWrite,
+        AdminScope::TokensRead,
+        AdminScope::TokensSuspend,
+        AdminScope::TokensRevoke,
+        AdminScope::UsersRead,
+        AdminScope::UsersWrite,
+        AdminScope::NoticesWrite,
+        AdminScope::TermsWrite,
+        AdminScope::SettingsRead,
+        AdminScope::SettingsWrite,
+        AdminScope::AuditLogsRead,
+        AdminScope::RolesRead,
+        AdminScope::RolesWrite,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminScope::All => "*",
+            AdminScope::BoardsWrite => "boards:write",
+            AdminScope::ThreadsWrite => "threads:write",
+            AdminScope::ArchivesWrite => "archives:write",
+            AdminScope::ArchivesDelete => "archives:delete",
+            AdminScope::ModerationRead => "moderation:read",
+            AdminScope::ModerationWrite => "moderation:write",
+            AdminScope::TokensRead => "tokens:read",
+            AdminScope::TokensSuspend => "tokens:suspend",
+            AdminScope::TokensRevoke => "tokens:revoke",
+            AdminScope::UsersRead => "users:read",
+            AdminScope::UsersWrite => "users:write",
+            AdminScope::NoticesWrite => "notices:write",
+            AdminScope::TermsWrite => "terms:write",
+            AdminScope::SettingsRead => "settings:read",
+            AdminScope::SettingsWrite => "settings:write",
+            AdminScope::AuditLogsRead => "audit_logs:read",
+            AdminScope::RolesRead => "roles:read",
+            AdminScope::RolesWrite => "roles:write",
+        }
+    }
+}
+
+impl fmt::Display for AdminScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AdminScope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AdminScope::VALUES
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("unknown admin scope: {s}"))
+    }
+}
+
+/// Scopes granted to the current admin, inserted into the request extensions
+#[derive(Debug, Clone, Default)]
+pub struct AdminScopes(HashSet<AdminScope>);
+
+impl AdminScopes {
+    pub fn all() -> Self {
+        Self(HashSet::from([AdminScope::All]))
+    }
+
+    pub fn contains(&self, scope: AdminScope) -> bool {
+        self.0.contains(&AdminScope::All) || self.0.contains(&scope)
+    }
+
+    pub fn to_vec(&self) -> Vec<AdminScope> {
+        let mut scopes = self.0.iter().copied().collect::<Vec<_>>();
+        scopes.sort_by_key(|scope| scope.as_str());
+        scopes
+    }
+}
+
+impl FromIterator<AdminScope> for AdminScopes {
+    fn from_iter<T: IntoIterator<Item = AdminScope>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+/// Boards the current admin may act on, inserted into the request extensions
+#[derive(Debug, Clone, Default)]
+pub enum AdminBoards {
+    #[default]
+    All,
+    /// Board moderators, keyed by board id with the board key as value
+    Only(HashMap<Uuid, String>),
+}
+
+impl AdminBoards {
+    pub fn is_restricted(&self) -> bool {
+        matches!(self, AdminBoards::Only(_))
+    }
+
+    pub fn allows_id(&self, board_id: Uuid) -> bool {
+        match self {
+            AdminBoards::All => true,
+            AdminBoards::Only(boards) => boards.contains_key(&board_id),
+        }
+    }
+
+    pub fn allows_key(&self, board_key: &str) -> bool {
+        match self {
+            AdminBoards::All => true,
+            AdminBoards::Only(boards) => boards.values().any(|key| key == board_key),
+        }
+    }
+
+    /// `None` when every board is allowed
+    pub fn ids(&self) -> Option<Vec<Uuid>> {
+        match self {
+            AdminBoards::All => None,
+            AdminBoards::Only(boards) => Some(boards.keys().copied().collect()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct AdminRole {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    pub scopes: Vec<AdminScope>,
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct CreateAdminRoleInput {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub scopes: Vec<AdminScope>,
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct UpdateAdminRoleInput {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// Replaces every scope of the role when set
+    pub scopes: Option<Vec<AdminScope>>,
+}
+
+/// Maps an IdP account, identified by its verified email, to a role
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct AdminUserAssignment {
+    pub id: Uuid,
+    pub email: String,
+    pub role_id: Uuid,
+    /// Boards the admin is a moderator of, or every board when empty
+    pub board_ids: Vec<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct AssignAdminRoleInput {
+    pub email: String,
+    pub role_id: Uuid,
+    /// Restricts the admin to these boards, or allows every board when empty
+    #[serde(default)]
+    pub board_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct CurrentAdmin {
+    pub email: String,
+    pub username: String,
+    pub scopes: Vec<AdminScope>,
+    /// `None` when every board is allowed
+    pub board_ids: Option<Vec<Uuid>>,
+}
@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct AuditLog {
+    pub id: Uuid,
+    pub actor_sub: String,
+    pub actor_email: String,
+    /// e.g. `ng_word.update`
+    pub action: String,
+    /// e.g. `ng_word`
+    pub target_type: String,
+    pub target_id: String,
+    /// Only the changed fields for updates, the whole state for deletions
+    pub before: Option<serde_json::Value>,
+    /// Only the changed fields for updates, the whole state for creations
+    pub after: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, ToSchema, IntoParams, Serialize, Deserialize)]
+pub struct ListAuditLogsQuery {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+    pub actor_email: Option<String>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct PaginatedAuditLogs {
+    pub items: Vec<AuditLog>,
+    pub total: u64,
+    pub page: u32,
+    pub per_page: u32,
+    pub total_pages: u32,
+}
@@ -47,6 +47,11 @@ pub struct DeleteAuthedTokenInput {
     pub using_origin_ip: bool,
 }
 
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct SuspendAuthedTokenInput {
+    pub ttl_seconds: u64,
+}
+
 #[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
 pub struct NativeSessionRequest {
     pub access_token: String,
@@ -1,3 +1,12 @@
+use std::collections::HashMap;
+
+use eddist_core::domain::{
+    board::BoardModerationMode,
+    moderation_provider::{
+        BoardModerationConfig, HttpClassifierConfig, KeywordScoreRule, KeywordScorerConfig,
+        ModerationProviderConfig,
+    },
+};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
@@ -27,6 +36,38 @@ pub struct BoardInfo {
     pub force_metadent_type: Option<String>,
     pub enable_1001_message: bool,
     pub custom_1001_message: Option<String>,
+    pub moderation_mode: BoardModerationModeSchema,
+    /// Internal level below which every post is held in `BelowLevel` mode
+    pub moderation_level_threshold: u32,
+    /// Unset boards use the OpenAI provider with its own verdicts
+    pub moderation_config: Option<BoardModerationConfigSchema>,
+}
+
+#[derive(Debug, Clone, Copy, ToSchema, Serialize, Deserialize)]
+pub enum BoardModerationModeSchema {
+    Off,
+    FlaggedOnly,
+    BelowLevel,
+}
+
+impl From<BoardModerationModeSchema> for BoardModerationMode {
+    fn from(value: BoardModerationModeSchema) -> Self {
+        match value {
+            BoardModerationModeSchema::Off => BoardModerationMode::Off,
+            BoardModerationModeSchema::FlaggedOnly => BoardModerationMode::FlaggedOnly,
+            BoardModerationModeSchema::BelowLevel => BoardModerationMode::BelowLevel,
+        }
+    }
+}
+
+impl From<BoardModerationMode> for BoardModerationModeSchema {
+    fn from(value: BoardModerationMode) -> Self {
+        match value {
+            BoardModerationMode::Off => BoardModerationModeSchema::Off,
+            BoardModerationMode::FlaggedOnly => BoardModerationModeSchema::FlaggedOnly,
+            BoardModerationMode::BelowLevel => BoardModerationModeSchema::BelowLevel,
+        }
+    }
 }
 
 #[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
@@ -65,4 +106,123 @@ pub struct EditBoardInput {
     pub force_metadent_type: Option<String>,
     pub enable_1001_message: Option<bool>,
     pub custom_1001_message: Option<String>,
+    pub moderation_mode: Option<BoardModerationModeSchema>,
+    pub moderation_level_threshold: Option<u32>,
+    pub moderation_config: Option<BoardModerationConfigSchema>,
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct BoardModerationConfigSchema {
+    pub provider: ModerationProviderConfigSchema,
+    /// Score at or above which a category is flagged, 0.5 for non-OpenAI providers when unset
+    pub threshold: Option<f64>,
+    /// Per-category overrides of `threshold`
+    #[serde(default)]
+    pub category_thresholds: HashMap<String, f64>,
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ModerationProviderConfigSchema {
+    OpenAi,
+    HttpClassifier {
+        url: String,
+        /// Stored encrypted and returned as `***`, sending `***` back keeps the stored value
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        /// JSON request body where `{{text}}` is replaced with the post text as a JSON string
+        body_template: Option<String>,
+        /// JSONPath to the overall score without the leading `$.`
+        score_path: String,
+        /// JSONPath to an object of per-category scores without the leading `$.`
+        category_scores_path: Option<String>,
+    },
+    Keyword {
+        rules: Vec<KeywordScoreRuleSchema>,
+    },
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct KeywordScoreRuleSchema {
+    pub pattern: String,
+    #[serde(default)]
+    pub is_regex: bool,
+    pub category: String,
+    pub score: f64,
+}
+
+impl From<BoardModerationConfigSchema> for BoardModerationConfig {
+    fn from(value: BoardModerationConfigSchema) -> Self {
+        let provider = match value.provider {
+            ModerationProviderConfigSchema::OpenAi => ModerationProviderConfig::OpenAi,
+            ModerationProviderConfigSchema::HttpClassifier {
+                url,
+                headers,
+                body_template,
+                score_path,
+                category_scores_path,
+            } => ModerationProviderConfig::HttpClassifier(HttpClassifierConfig {
+                url,
+                headers,
+                body_template: body_template
+                    .unwrap_or_else(HttpClassifierConfig::default_body_template),
+                score_path,
+                category_scores_path,
+            }),
+            ModerationProviderConfigSchema::Keyword { rules } => {
+                ModerationProviderConfig::Keyword(KeywordScorerConfig {
+                    rules: rules
+                        .into_iter()
+                        .map(|r| KeywordScoreRule {
+                            pattern: r.pattern,
+                            is_regex: r.is_regex,
+                            category: r.category,
+                            score: r.score,
+                        })
+                        .collect(),
+                })
+            }
+        };
+
+        BoardModerationConfig {
+            provider,
+            threshold: value.threshold,
+            category_thresholds: value.category_thresholds,
+        }
+    }
+}
+
+impl From<BoardModerationConfig> for BoardModerationConfigSchema {
+    fn from(value: BoardModerationConfig) -> Self {
+        let provider = match value.provider {
+            ModerationProviderConfig::OpenAi => ModerationProviderConfigSchema::OpenAi,
+            ModerationProviderConfig::HttpClassifier(http) => {
+                ModerationProviderConfigSchema::HttpClassifier {
+                    url: http.url,
+                    headers: http.headers,
+                    body_template: Some(http.body_template),
+                    score_path: http.score_path,
+                    category_scores_path: http.category_scores_path,
+                }
+            }
+            ModerationProviderConfig::Keyword(keyword) => ModerationProviderConfigSchema::Keyword {
+                rules: keyword
+                    .rules
+                    .into_iter()
+                    .map(|r| KeywordScoreRuleSchema {
+                        pattern: r.pattern,
+                        is_regex: r.is_regex,
+                        category: r.category,
+                        score: r.score,
+                    })
+                    .collect(),
+            },
+        };
+
+        BoardModerationConfigSchema {
+            provider,
+            threshold: value.threshold,
+            category_thresholds: value.category_thresholds,
+        }
+    }
 }
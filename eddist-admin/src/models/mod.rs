@@ -1,23 +1,33 @@
+pub mod admin_role;
+pub mod audit_log;
 pub mod auth;
 pub mod board;
 pub mod captcha;
 pub mod idp;
 pub mod moderation;
+pub mod moderation_queue;
+pub mod moderation_rule;
 pub mod notice;
 pub mod response;
+pub mod search;
 pub mod server_settings;
 pub mod terms;
 pub mod thread;
 pub mod user;
 
 // Re-export all models for convenience
+pub use admin_role::*;
+pub use audit_log::*;
 pub use auth::*;
 pub use board::*;
 pub use captcha::*;
 pub use idp::*;
 pub use moderation::*;
+pub use moderation_queue::*;
+pub use moderation_rule::*;
 pub use notice::*;
 pub use response::*;
+pub use search::*;
 pub use server_settings::*;
 pub use terms::*;
 pub use thread::*;
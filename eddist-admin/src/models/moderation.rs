@@ -1,7 +1,11 @@
 use chrono::{DateTime, Utc};
-use eddist_core::domain::user_restriction::RestrictionRuleType;
+use eddist_core::domain::{
+    ng_word::{NgWordAction, NgWordMatchType, NgWordTarget},
+    user_restriction::{RestrictionAction, RestrictionRuleType, RestrictionTarget},
+    user_restriction_bulk::RestrictionRuleFormat,
+};
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 // NgWord related structs
@@ -10,6 +14,10 @@ pub struct NgWord {
     pub id: Uuid,
     pub name: String,
     pub word: String,
+    pub match_type: NgWordMatchTypeSchema,
+    pub targets: Vec<NgWordTargetSchema>,
+    pub action: NgWordActionSchema,
+    pub penalty_seconds: u32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub board_ids: Vec<Uuid>,
@@ -19,15 +27,114 @@ pub struct NgWord {
 pub struct CreationNgWordInput {
     pub name: String,
     pub word: String,
+    /// Defaults to `Literal`
+    pub match_type: Option<NgWordMatchTypeSchema>,
+    /// Defaults to body, name, mail and title
+    pub targets: Option<Vec<NgWordTargetSchema>>,
+    /// Defaults to `Reject`
+    pub action: Option<NgWordActionSchema>,
+    /// Extra posting interval added per hit, only used by `Penalize`
+    pub penalty_seconds: Option<u32>,
 }
 
 #[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
 pub struct UpdateNgWordInput {
     pub name: Option<String>,
     pub word: Option<String>,
+    pub match_type: Option<NgWordMatchTypeSchema>,
+    pub targets: Option<Vec<NgWordTargetSchema>>,
+    pub action: Option<NgWordActionSchema>,
+    pub penalty_seconds: Option<u32>,
     pub board_ids: Option<Vec<Uuid>>,
 }
 
+#[derive(Debug, Clone, Copy, ToSchema, Serialize, Deserialize)]
+pub enum NgWordMatchTypeSchema {
+    Literal,
+    Regex,
+}
+
+impl From<NgWordMatchTypeSchema> for NgWordMatchType {
+    fn from(value: NgWordMatchTypeSchema) -> Self {
+        match value {
+            NgWordMatchTypeSchema::Literal => NgWordMatchType::Literal,
+            NgWordMatchTypeSchema::Regex => NgWordMatchType::Regex,
+        }
+    }
+}
+
+impl From<NgWordMatchType> for NgWordMatchTypeSchema {
+    fn from(value: NgWordMatchType) -> Self {
+        match value {
+            NgWordMatchType::Literal => NgWordMatchTypeSchema::Literal,
+            NgWordMatchType::Regex => NgWordMatchTypeSchema::Regex,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ToSchema, Serialize, Deserialize)]
+pub enum NgWordActionSchema {
+    Reject,
+    ShadowAbone,
+    Hold,
+    Penalize,
+}
+
+impl From<NgWordActionSchema> for NgWordAction {
+    fn from(value: NgWordActionSchema) -> Self {
+        match value {
+            NgWordActionSchema::Reject => NgWordAction::Reject,
+            NgWordActionSchema::ShadowAbone => NgWordAction::ShadowAbone,
+            NgWordActionSchema::Hold => NgWordAction::Hold,
+            NgWordActionSchema::Penalize => NgWordAction::Penalize,
+        }
+    }
+}
+
+impl From<NgWordAction> for NgWordActionSchema {
+    fn from(value: NgWordAction) -> Self {
+        match value {
+            NgWordAction::Reject => NgWordActionSchema::Reject,
+            NgWordAction::ShadowAbone => NgWordActionSchema::ShadowAbone,
+            NgWordAction::Hold => NgWordActionSchema::Hold,
+            NgWordAction::Penalize => NgWordActionSchema::Penalize,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ToSchema, Serialize, Deserialize)]
+pub enum NgWordTargetSchema {
+    Body,
+    Name,
+    Mail,
+    Title,
+    UrlHost,
+}
+
+impl From<NgWordTargetSchema> for NgWordTarget {
+    fn from(value: NgWordTargetSchema) -> Self {
+        match value {
+            NgWordTargetSchema::Body => NgWordTarget::Body,
+            NgWordTargetSchema::Name => NgWordTarget::Name,
+            NgWordTargetSchema::Mail => NgWordTarget::Mail,
+            NgWordTargetSchema::Title => NgWordTarget::Title,
+            NgWordTargetSchema::UrlHost => NgWordTarget::UrlHost,
+        }
+    }
+}
+
+impl From<NgWordTarget> for NgWordTargetSchema {
+    fn from(value: NgWordTarget) -> Self {
+        match value {
+            NgWordTarget::Body => NgWordTargetSchema::Body,
+            NgWordTarget::Name => NgWordTargetSchema::Name,
+            NgWordTarget::Mail => NgWordTargetSchema::Mail,
+            NgWordTarget::Title => NgWordTargetSchema::Title,
+            NgWordTarget::UrlHost => NgWordTargetSchema::UrlHost,
+        }
+    }
+}
+
 // Cap related structs
 #[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
 pub struct Cap {
@@ -59,7 +166,18 @@ pub struct UpdateCapInput {
 pub struct CreateRestrictionRuleRequest {
     pub name: String,
     pub rule_type: RestrictionRuleTypeSchema,
+    /// JSON of the condition tree for `Composite`
     pub rule_value: String,
+    /// Omit to apply the rule to every board
+    pub board_keys: Option<Vec<String>>,
+    /// Defaults to thread, response and auth-code
+    pub targets: Option<Vec<RestrictionTargetSchema>>,
+    /// Defaults to `Block`
+    pub action: Option<RestrictionActionSchema>,
+    /// Name of the captcha config, only used by `Captcha`
+    pub captcha_provider: Option<String>,
+    /// Only used by `SlowDown`
+    pub slow_down_seconds: Option<u32>,
     pub expires_at: Option<DateTime<Utc>>,
 }
 
@@ -68,6 +186,11 @@ pub struct UpdateRestrictionRuleRequest {
     pub name: Option<String>,
     pub rule_type: Option<RestrictionRuleTypeSchema>,
     pub rule_value: Option<String>,
+    pub board_keys: Option<Option<Vec<String>>>,
+    pub targets: Option<Vec<RestrictionTargetSchema>>,
+    pub action: Option<RestrictionActionSchema>,
+    pub captcha_provider: Option<Option<String>>,
+    pub slow_down_seconds: Option<u32>,
     pub expires_at: Option<Option<DateTime<Utc>>>,
 }
 
@@ -77,18 +200,70 @@ pub struct UserRestrictionRuleSchema {
     pub name: String,
     pub rule_type: RestrictionRuleTypeSchema,
     pub rule_value: String,
+    pub board_keys: Option<Vec<String>>,
+    pub targets: Vec<RestrictionTargetSchema>,
+    pub action: RestrictionActionSchema,
+    pub captcha_provider: Option<String>,
+    pub slow_down_seconds: u32,
     pub expires_at: Option<DateTime<Utc>>,
+    /// Set on the rules of a blocklist subscription
+    pub subscription_id: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub created_by_email: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserRestrictionRuleWithHitsSchema {
+    #[serde(flatten)]
+    pub rule: UserRestrictionRuleSchema,
+    /// As of the last flush of the hits counted by eddist-server
+    pub hit_count: u64,
+    pub last_hit_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, ToSchema, Serialize, Deserialize)]
+pub enum RestrictionRuleSortSchema {
+    #[default]
+    CreatedAt,
+    HitCount,
+    LastHitAt,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+pub struct ListRestrictionRulesQuery {
+    /// Defaults to `CreatedAt`
+    pub sort: Option<RestrictionRuleSortSchema>,
+    /// Defaults to descending, the newest or most hit first
+    pub ascending: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RestrictionHitSampleSchema {
+    pub ip: String,
+    pub asn: u32,
+    pub user_agent: String,
+    pub board_key: Option<String>,
+    pub target: RestrictionTargetSchema,
+    pub hit_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RestrictionRuleHitsSchema {
+    pub rule_id: String,
+    pub hit_count: u64,
+    pub last_hit_at: Option<DateTime<Utc>>,
+    /// Latest first, as of the last flush
+    pub recent_samples: Vec<RestrictionHitSampleSchema>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub enum RestrictionRuleTypeSchema {
     Asn,
     IP,
     IPCidr,
     UserAgent,
+    Composite,
 }
 
 impl From<RestrictionRuleTypeSchema> for RestrictionRuleType {
@@ -98,6 +273,169 @@ impl From<RestrictionRuleTypeSchema> for RestrictionRuleType {
             RestrictionRuleTypeSchema::IP => RestrictionRuleType::IP,
             RestrictionRuleTypeSchema::IPCidr => RestrictionRuleType::IPCidr,
             RestrictionRuleTypeSchema::UserAgent => RestrictionRuleType::UserAgent,
+            RestrictionRuleTypeSchema::Composite => RestrictionRuleType::Composite,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ToSchema, Serialize, Deserialize)]
+pub enum RestrictionTargetSchema {
+    Thread,
+    Response,
+    AuthCode,
+}
+
+impl From<RestrictionTargetSchema> for RestrictionTarget {
+    fn from(value: RestrictionTargetSchema) -> Self {
+        match value {
+            RestrictionTargetSchema::Thread => RestrictionTarget::Thread,
+            RestrictionTargetSchema::Response => RestrictionTarget::Response,
+            RestrictionTargetSchema::AuthCode => RestrictionTarget::AuthCode,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ToSchema, Serialize, Deserialize)]
+pub enum RestrictionActionSchema {
+    Block,
+    ReAuth,
+    Captcha,
+    SlowDown,
+}
+
+impl From<RestrictionActionSchema> for RestrictionAction {
+    fn from(value: RestrictionActionSchema) -> Self {
+        match value {
+            RestrictionActionSchema::Block => RestrictionAction::Block,
+            RestrictionActionSchema::ReAuth => RestrictionAction::ReAuth,
+            RestrictionActionSchema::Captcha => RestrictionAction::Captcha,
+            RestrictionActionSchema::SlowDown => RestrictionAction::SlowDown,
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, ToSchema, Serialize, Deserialize)]
+pub enum RestrictionRuleFormatSchema {
+    /// Header row with `rule_type` and `rule_value` columns, plus any of `name`, `board_keys`,
+    /// `targets`, `action`, `captcha_provider`, `slow_down_seconds` and `expires_at`
+    Csv,
+    /// Array of rules with the fields of `CreateRestrictionRuleRequest`
+    Json,
+    /// One IP address or CIDR per line
+    Cidr,
+}
+
+impl From<RestrictionRuleFormatSchema> for RestrictionRuleFormat {
+    fn from(value: RestrictionRuleFormatSchema) -> Self {
+        match value {
+            RestrictionRuleFormatSchema::Csv => RestrictionRuleFormat::Csv,
+            RestrictionRuleFormatSchema::Json => RestrictionRuleFormat::Json,
+            RestrictionRuleFormatSchema::Cidr => RestrictionRuleFormat::Cidr,
+        }
+    }
+}
+
+/// Settings other than `format`, `content` and `dry_run` apply to the rows that leave them out
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportRestrictionRulesRequest {
+    pub format: RestrictionRuleFormatSchema,
+    pub content: String,
+    /// Only report what would be created
+    pub dry_run: Option<bool>,
+    /// Unnamed rules are named `"{name} {rule_value}"`, defaults to `import`
+    pub name: Option<String>,
+    pub board_keys: Option<Vec<String>>,
+    pub targets: Option<Vec<RestrictionTargetSchema>>,
+    pub action: Option<RestrictionActionSchema>,
+    pub captcha_provider: Option<String>,
+    pub slow_down_seconds: Option<u32>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RestrictionImportErrorSchema {
+    pub row: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RestrictionImportDuplicateSchema {
+    pub row: usize,
+    pub rule_value: String,
+    /// `null` when the rule repeats an earlier row
+    pub existing_rule_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportRestrictionRulesResponse {
+    pub dry_run: bool,
+    /// Rules created, or to be created by a dry run
+    pub rules: Vec<UserRestrictionRuleSchema>,
+    pub duplicates: Vec<RestrictionImportDuplicateSchema>,
+    pub errors: Vec<RestrictionImportErrorSchema>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+pub struct ExportRestrictionRulesQuery {
+    pub format: RestrictionRuleFormatSchema,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExportRestrictionRulesResponse {
+    pub format: RestrictionRuleFormatSchema,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateRestrictionSubscriptionRequest {
+    pub name: String,
+    /// `http(s)://` URL, or an absolute path or `file://` URL on the cron host
+    pub source: String,
+    pub format: RestrictionRuleFormatSchema,
+    pub board_keys: Option<Vec<String>>,
+    pub targets: Option<Vec<RestrictionTargetSchema>>,
+    pub action: Option<RestrictionActionSchema>,
+    pub captcha_provider: Option<String>,
+    pub slow_down_seconds: Option<u32>,
+    pub refresh_interval_seconds: u32,
+    /// How long the rules outlive the refresh that imported them
+    pub expires_after_seconds: u32,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateRestrictionSubscriptionRequest {
+    pub name: Option<String>,
+    pub source: Option<String>,
+    pub format: Option<RestrictionRuleFormatSchema>,
+    pub board_keys: Option<Option<Vec<String>>>,
+    pub targets: Option<Vec<RestrictionTargetSchema>>,
+    pub action: Option<RestrictionActionSchema>,
+    pub captcha_provider: Option<Option<String>>,
+    pub slow_down_seconds: Option<u32>,
+    pub refresh_interval_seconds: Option<u32>,
+    pub expires_after_seconds: Option<u32>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserRestrictionSubscriptionSchema {
+    pub id: String,
+    pub name: String,
+    pub source: String,
+    pub format: RestrictionRuleFormatSchema,
+    pub board_keys: Option<Vec<String>>,
+    pub targets: Vec<RestrictionTargetSchema>,
+    pub action: RestrictionActionSchema,
+    pub captcha_provider: Option<String>,
+    pub slow_down_seconds: u32,
+    pub refresh_interval_seconds: u32,
+    pub expires_after_seconds: u32,
+    pub enabled: bool,
+    pub last_refreshed_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub rule_count: u32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub created_by_email: String,
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use eddist_core::domain::moderation_queue::{HoldReason, ModerationQueueStatus};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct HeldPost {
+    pub id: Uuid,
+    pub board_id: Uuid,
+    pub board_key: String,
+    pub thread_id: Uuid,
+    /// Unset for responses to threads that no longer exist
+    pub thread_number: Option<u64>,
+    pub post_type: HeldPostTypeSchema,
+    pub reason: HoldReasonSchema,
+    pub ng_word_id: Option<Uuid>,
+    pub status: ModerationQueueStatusSchema,
+    /// Only set for threads
+    pub title: Option<String>,
+    pub author_name: String,
+    pub mail: String,
+    pub body: String,
+    pub author_id: String,
+    pub ip_addr: String,
+    pub authed_token_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub reviewed_by: Option<String>,
+}
+
+#[derive(Debug, Clone, IntoParams, Serialize, Deserialize)]
+pub struct ListHeldPostsQuery {
+    /// Defaults to `Pending`
+    pub status: Option<ModerationQueueStatusSchema>,
+    pub board_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Copy, ToSchema, Serialize, Deserialize)]
+pub enum HeldPostTypeSchema {
+    Response,
+    Thread,
+}
+
+#[derive(Debug, Clone, Copy, ToSchema, Serialize, Deserialize)]
+pub enum HoldReasonSchema {
+    NgWord,
+    AiModeration,
+    LowLevel,
+}
+
+impl From<HoldReason> for HoldReasonSchema {
+    fn from(value: HoldReason) -> Self {
+        match value {
+            HoldReason::NgWord => HoldReasonSchema::NgWord,
+            HoldReason::AiModeration => HoldReasonSchema::AiModeration,
+            HoldReason::LowLevel => HoldReasonSchema::LowLevel,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ToSchema, Serialize, Deserialize)]
+pub enum ModerationQueueStatusSchema {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl From<ModerationQueueStatusSchema> for ModerationQueueStatus {
+    fn from(value: ModerationQueueStatusSchema) -> Self {
+        match value {
+            ModerationQueueStatusSchema::Pending => ModerationQueueStatus::Pending,
+            ModerationQueueStatusSchema::Approved => ModerationQueueStatus::Approved,
+            ModerationQueueStatusSchema::Rejected => ModerationQueueStatus::Rejected,
+        }
+    }
+}
+
+impl From<ModerationQueueStatus> for ModerationQueueStatusSchema {
+    fn from(value: ModerationQueueStatus) -> Self {
+        match value {
+            ModerationQueueStatus::Pending => ModerationQueueStatusSchema::Pending,
+            ModerationQueueStatus::Approved => ModerationQueueStatusSchema::Approved,
+            ModerationQueueStatus::Rejected => ModerationQueueStatusSchema::Rejected,
+        }
+    }
+}
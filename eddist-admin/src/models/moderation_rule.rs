@@ -0,0 +1,158 @@
+use chrono::{DateTime, Utc};
+use eddist_core::domain::moderation_rule::{
+    ModerationActionLog as CoreModerationActionLog, ModerationRule as CoreModerationRule,
+    ModerationRuleAction,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct ModerationRule {
+    pub id: Uuid,
+    pub name: String,
+    /// Applies to every board when unset
+    pub board_id: Option<Uuid>,
+    /// Category in the moderation scores, or `*` for any category
+    pub category: String,
+    pub threshold: f64,
+    pub action: ModerationRuleActionSchema,
+    /// Used by `SuspendToken` and `LowerInternalLevel`
+    pub duration_seconds: u64,
+    /// Used by `LowerInternalLevel`
+    pub internal_level: u32,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<CoreModerationRule> for ModerationRule {
+    fn from(value: CoreModerationRule) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            board_id: value.board_id,
+            category: value.category,
+            threshold: value.threshold,
+            action: value.action.into(),
+            duration_seconds: value.duration_seconds,
+            internal_level: value.internal_level,
+            enabled: value.enabled,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct CreateModerationRuleInput {
+    pub name: String,
+    pub board_id: Option<Uuid>,
+    pub category: String,
+    pub threshold: f64,
+    pub action: ModerationRuleActionSchema,
+    #[serde(default)]
+    pub duration_seconds: u64,
+    #[serde(default)]
+    pub internal_level: u32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct UpdateModerationRuleInput {
+    pub name: Option<String>,
+    /// `Some(None)` makes the rule apply to every board
+    pub board_id: Option<Option<Uuid>>,
+    pub category: Option<String>,
+    pub threshold: Option<f64>,
+    pub action: Option<ModerationRuleActionSchema>,
+    pub duration_seconds: Option<u64>,
+    pub internal_level: Option<u32>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, ToSchema, Serialize, Deserialize)]
+pub enum ModerationRuleActionSchema {
+    AboneResponse,
+    SuspendToken,
+    LowerInternalLevel,
+    MarkThreadUnsafe,
+}
+
+impl From<ModerationRuleActionSchema> for ModerationRuleAction {
+    fn from(value: ModerationRuleActionSchema) -> Self {
+        match value {
+            ModerationRuleActionSchema::AboneResponse => ModerationRuleAction::AboneResponse,
+            ModerationRuleActionSchema::SuspendToken => ModerationRuleAction::SuspendToken,
+            ModerationRuleActionSchema::LowerInternalLevel => {
+                ModerationRuleAction::LowerInternalLevel
+            }
+            ModerationRuleActionSchema::MarkThreadUnsafe => ModerationRuleAction::MarkThreadUnsafe,
+        }
+    }
+}
+
+impl From<ModerationRuleAction> for ModerationRuleActionSchema {
+    fn from(value: ModerationRuleAction) -> Self {
+        match value {
+            ModerationRuleAction::AboneResponse => ModerationRuleActionSchema::AboneResponse,
+            ModerationRuleAction::SuspendToken => ModerationRuleActionSchema::SuspendToken,
+            ModerationRuleAction::LowerInternalLevel => {
+                ModerationRuleActionSchema::LowerInternalLevel
+            }
+            ModerationRuleAction::MarkThreadUnsafe => ModerationRuleActionSchema::MarkThreadUnsafe,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct ModerationActionLog {
+    pub id: Uuid,
+    /// Unset once the rule is deleted
+    pub rule_id: Option<Uuid>,
+    pub action: ModerationRuleActionSchema,
+    pub board_id: Uuid,
+    pub thread_number: u64,
+    pub response_id: Uuid,
+    pub authed_token_id: Uuid,
+    pub category: String,
+    pub score: f64,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub reverted_at: Option<DateTime<Utc>>,
+    pub reverted_by: Option<String>,
+}
+
+impl From<CoreModerationActionLog> for ModerationActionLog {
+    fn from(value: CoreModerationActionLog) -> Self {
+        Self {
+            id: value.id,
+            rule_id: value.rule_id,
+            action: value.action.into(),
+            board_id: value.board_id,
+            thread_number: value.thread_number,
+            response_id: value.response_id,
+            authed_token_id: value.authed_token_id,
+            category: value.category,
+            score: value.score,
+            expires_at: value.expires_at,
+            created_at: value.created_at,
+            reverted_at: value.reverted_at,
+            reverted_by: value.reverted_by,
+        }
+    }
+}
+
+#[derive(Debug, Clone, IntoParams, Serialize, Deserialize)]
+pub struct ListModerationActionLogsQuery {
+    pub board_id: Option<Uuid>,
+    /// Only returns reverted (`true`) or active (`false`) actions when set
+    pub reverted: Option<bool>,
+    /// Defaults to 100
+    pub limit: Option<u32>,
+}
@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use eddist_core::domain::{
     client_info::ClientInfo as CoreClientInfo, tinker::Tinker as CoreTinker,
 };
@@ -76,3 +76,46 @@ pub struct UpdateResInput {
     pub body: Option<String>,
     pub is_abone: Option<bool>,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub enum BulkResponseFilterType {
+    AuthedTokenId,
+    /// The ch5 author ID, which changes daily, so `author_id_date` is required
+    AuthorId,
+    Ip,
+    /// IPv4 address or IPv6 /64 prefix, e.g. `2001:db8:0:1`
+    ReducedIp,
+    Asn,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub enum BulkResponseAction {
+    /// Hides the responses, which can be undone by updating each response
+    Abone,
+    /// Erases the name, mail and body of the responses. Their lines stay as abone in the dat so
+    /// that the response numbers of the thread don't shift.
+    Delete,
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct BulkModerateResponsesInput {
+    pub filter_type: BulkResponseFilterType,
+    pub filter_value: String,
+    /// Day (JST) on which the author ID was shown
+    pub author_id_date: Option<NaiveDate>,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    /// Restricts the target to a single board, every board otherwise
+    pub board_id: Option<Uuid>,
+    pub action: BulkResponseAction,
+    /// Only counts the matching responses without changing them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct BulkModerateResponsesResult {
+    pub dry_run: bool,
+    pub response_count: usize,
+    pub thread_count: usize,
+}
@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, ToSchema, IntoParams, Serialize, Deserialize)]
+pub struct SearchPostsQuery {
+    /// Words every post must contain, may be omitted when another filter is given
+    pub q: Option<String>,
+    pub board_id: Option<Uuid>,
+    pub author_id: Option<String>,
+    pub authed_token_id: Option<Uuid>,
+    pub ip_addr: Option<String>,
+    /// Whether aboned posts are included, which the public search never returns
+    #[serde(default)]
+    pub include_abone: bool,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub response_id: Uuid,
+    pub board_id: Uuid,
+    pub board_key: String,
+    pub thread_number: u64,
+    pub thread_title: String,
+    pub res_order: i32,
+    pub body: String,
+    pub author_id: String,
+    /// Cleared, as the IP address, once past the retention of the search index
+    pub authed_token_id: Option<Uuid>,
+    pub ip_addr: Option<String>,
+    pub is_abone: bool,
+    pub created_at: DateTime<Utc>,
+}
@@ -17,9 +17,41 @@ pub struct Thread {
     pub no_pool: bool,
     pub archived: bool,
     pub active: bool,
+    /// Responses within the last hour, only filled in the thread list
+    #[serde(default)]
+    pub momentum: u64,
 }
 
 #[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
 pub struct ThreadCompactionInput {
     pub target_count: u32,
 }
+
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct MoveThreadInput {
+    /// Key of the board to move the thread to
+    pub board_key: String,
+}
+
+/// A thread that received responses faster than the momentum alert threshold
+#[derive(Debug, Clone, ToSchema, Serialize, Deserialize)]
+pub struct MomentumAlert {
+    pub board_key: String,
+    pub thread_number: u64,
+    /// Responses to the thread within `window_secs`
+    pub responses: u64,
+    pub window_secs: i64,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl From<eddist_core::thread_momentum::MomentumAlert> for MomentumAlert {
+    fn from(alert: eddist_core::thread_momentum::MomentumAlert) -> Self {
+        Self {
+            board_key: alert.board_key,
+            thread_number: alert.thread_number,
+            responses: alert.responses,
+            window_secs: alert.window_secs,
+            detected_at: alert.detected_at,
+        }
+    }
+}
@@ -1,7 +1,11 @@
 use core::str;
+use std::sync::Arc;
 
-use aws_sdk_s3::{Client, primitives::ByteStream};
-use eddist_core::domain::sjis_str::SJisStr;
+use eddist_core::{
+    archive_storage::ArchiveStorage,
+    archived_dat::{ArchivedDatKind, archived_dat_keys, get_archived_dat, put_archived_dat},
+    domain::sjis_str::SJisStr,
+};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -42,13 +46,26 @@ pub struct ArchivedResUpdate {
 
 #[derive(Debug, Clone)]
 pub struct AdminArchiveRepositoryImpl {
-    client: Client,
-    bucket: String,
+    storage: Arc<dyn ArchiveStorage>,
 }
 
 impl AdminArchiveRepositoryImpl {
-    pub fn new(client: Client, bucket: String) -> Self {
-        Self { client, bucket }
+    pub fn new(storage: Arc<dyn ArchiveStorage>) -> Self {
+        Self { storage }
+    }
+
+    async fn get_dat(
+        &self,
+        board_key: &str,
+        kind: ArchivedDatKind,
+        thread_number: u64,
+    ) -> anyhow::Result<Vec<u8>> {
+        let dat = get_archived_dat(self.storage.as_ref(), board_key, kind, thread_number)
+            .await?
+            .ok_or_else(|| {
+                anyhow::anyhow!("archived dat not found: {board_key}/{thread_number}")
+            })?;
+        Ok(dat.decompress()?)
     }
 }
 
@@ -93,16 +110,10 @@ impl AdminArchiveRepository for AdminArchiveRepositoryImpl {
         board_key: &str,
         thread_number: u64,
     ) -> anyhow::Result<ArchivedThread> {
-        let output = self
-            .client
-            .get_object()
-            .bucket(&self.bucket)
-            .key(format!("{board_key}/dat/{thread_number}.dat"))
-            .send()
+        let dat_bytes = self
+            .get_dat(board_key, ArchivedDatKind::Dat, thread_number)
             .await?;
 
-        let dat_bytes = output.body.collect().await?.into_bytes().to_vec();
-
         let utf8_str = if let Ok(dat_bytes) = str::from_utf8(&dat_bytes) {
             dat_bytes.to_string()
         } else {
@@ -119,16 +130,10 @@ impl AdminArchiveRepository for AdminArchiveRepositoryImpl {
         board_key: &str,
         thread_number: u64,
     ) -> anyhow::Result<ArchivedAdminThread> {
-        let output = self
-            .client
-            .get_object()
-            .bucket(&self.bucket)
-            .key(format!("{board_key}/admin/{thread_number}.dat"))
-            .send()
+        let dat_bytes = self
+            .get_dat(board_key, ArchivedDatKind::Admin, thread_number)
             .await?;
 
-        let dat_bytes = output.body.collect().await?.into_bytes().to_vec();
-
         let utf8_str = if let Ok(dat_bytes) = str::from_utf8(&dat_bytes) {
             dat_bytes.to_string()
         } else {
@@ -165,15 +170,14 @@ impl AdminArchiveRepository for AdminArchiveRepositoryImpl {
 
         let dat = convert_reses_to_dat_file(a_thread.responses, &a_thread.title);
 
-        self.client
-            .put_object()
-            .bucket(&self.bucket)
-            .key(format!("{board_key}/dat/{thread_number}.dat"))
-            .body(ByteStream::from(dat))
-            .send()
-            .await?;
-
-        Ok(())
+        put_archived_dat(
+            self.storage.as_ref(),
+            board_key,
+            ArchivedDatKind::Dat,
+            thread_number,
+            &dat,
+        )
+        .await
     }
 
     async fn delete_response(
@@ -195,42 +199,23 @@ impl AdminArchiveRepository for AdminArchiveRepositoryImpl {
 
         let dat = convert_reses_to_dat_file(a_thread.responses, &a_thread.title);
 
-        self.client
-            .put_object()
-            .bucket(&self.bucket)
-            .key(format!("{board_key}/dat/{thread_number}.dat"))
-            .body(ByteStream::from(dat))
-            .send()
-            .await?;
-
-        Ok(())
+        put_archived_dat(
+            self.storage.as_ref(),
+            board_key,
+            ArchivedDatKind::Dat,
+            thread_number,
+            &dat,
+        )
+        .await
     }
 
     async fn delete_thread(&self, board_key: &str, thread_number: u64) -> anyhow::Result<()> {
-        let src = format!("{board_key}/dat/{thread_number}.dat");
-        let dst = format!("{src}.deleted");
-        let get_result = self
-            .client
-            .get_object()
-            .bucket(&self.bucket)
-            .key(&src)
-            .send()
-            .await;
-        if let Ok(output) = get_result {
-            let data = output.body.collect().await?.into_bytes();
-            self.client
-                .put_object()
-                .bucket(&self.bucket)
-                .key(&dst)
-                .body(ByteStream::from(data))
-                .send()
-                .await?;
-            self.client
-                .delete_object()
-                .bucket(&self.bucket)
-                .key(&src)
-                .send()
-                .await?;
+        for (_, src) in archived_dat_keys(board_key, ArchivedDatKind::Dat, thread_number) {
+            let dst = format!("{src}.deleted");
+            if let Ok(Some(data)) = self.storage.get(&src).await {
+                self.storage.put(&dst, data).await?;
+                self.storage.delete(&src).await?;
+            }
         }
 
         Ok(())
@@ -313,7 +298,7 @@ fn convert_admin_dat_file_to_res(dat_file: &str) -> ArchivedAdminThread {
 }
 
 fn convert_reses_to_dat_file(reses: Vec<ArchivedRes>, thread_title: &str) -> Vec<u8> {
-    let sjis_array = reses
+    reses
         .into_iter()
         .enumerate()
         .map(|(idx, res)| {
@@ -345,11 +330,5 @@ fn convert_reses_to_dat_file(reses: Vec<ArchivedRes>, thread_title: &str) -> Vec
         .fold(Vec::new(), |mut cur, next| {
             cur.append(&mut next.get_inner());
             cur
-        });
-
-    encoding_rs::SHIFT_JIS
-        .decode(&sjis_array)
-        .0
-        .into_owned()
-        .into_bytes()
+        })
 }
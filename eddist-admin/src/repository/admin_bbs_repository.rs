@@ -30,6 +30,9 @@ pub struct SelectionBoardInfo {
     pub force_metadent_type: Option<String>,
     pub enable_1001_message: bool,
     pub custom_1001_message: Option<String>,
+    pub moderation_mode: String,
+    pub moderation_level_threshold: u32,
+    pub moderation_config: Option<serde_json::Value>,
 }
 
 #[derive(Debug, FromRow)]
@@ -1,4 +1,5 @@
 use crate::transaction_repository;
+use eddist_core::domain::{board::BoardModerationMode, moderation_provider::BoardModerationConfig};
 use sqlx::{MySqlPool, query, query_as};
 use uuid::Uuid;
 
@@ -99,7 +100,10 @@ impl AdminBoardRepository for AdminBoardRepositoryImpl {
                 read_only AS "read_only!: bool",
                 force_metadent_type,
                 enable_1001_message AS "enable_1001_message!: bool",
-                custom_1001_message
+                custom_1001_message,
+                moderation_mode,
+                moderation_level_threshold,
+                moderation_config AS "moderation_config: serde_json::Value"
             FROM
                 boards_info
             WHERE
@@ -127,6 +131,20 @@ impl AdminBoardRepository for AdminBoardRepositoryImpl {
             force_metadent_type: board.force_metadent_type,
             enable_1001_message: board.enable_1001_message,
             custom_1001_message: board.custom_1001_message,
+            moderation_mode: board
+                .moderation_mode
+                .parse::<BoardModerationMode>()
+                .map_err(|e| anyhow::anyhow!(e))?
+                .into(),
+            moderation_level_threshold: board.moderation_level_threshold,
+            moderation_config: board
+                .moderation_config
+                .map(serde_json::from_value::<BoardModerationConfig>)
+                .transpose()?
+                .map(|mut config| {
+                    config.mask_secrets();
+                    config.into()
+                }),
         })
     }
 
@@ -323,6 +341,41 @@ impl AdminBoardRepository for AdminBoardRepositoryImpl {
         if board.enable_1001_message.is_some() {
             sets.push("enable_1001_message = ?");
         }
+        if board.moderation_mode.is_some() {
+            sets.push("moderation_mode = ?");
+        }
+        if board.moderation_level_threshold.is_some() {
+            sets.push("moderation_level_threshold = ?");
+        }
+        let moderation_config = match board.moderation_config {
+            Some(moderation_config) => {
+                let stored = sqlx::query_scalar::<_, Option<serde_json::Value>>(
+                    r#"
+                    SELECT
+                        bi.moderation_config
+                    FROM
+                        boards_info AS bi
+                        JOIN boards AS b ON b.id = bi.id
+                    WHERE
+                        b.board_key = ?
+                    "#,
+                )
+                .bind(board_key)
+                .fetch_optional(pool)
+                .await?
+                .flatten()
+                .map(serde_json::from_value::<BoardModerationConfig>)
+                .transpose()?;
+
+                let mut moderation_config = BoardModerationConfig::from(moderation_config);
+                moderation_config.seal_secrets(stored.as_ref());
+                Some(serde_json::to_string(&moderation_config)?)
+            }
+            None => None,
+        };
+        if moderation_config.is_some() {
+            sets.push("moderation_config = ?");
+        }
 
         let mut tx = pool.begin().await?;
 
@@ -359,6 +412,15 @@ impl AdminBoardRepository for AdminBoardRepositoryImpl {
             if let Some(enable_1001_message) = board.enable_1001_message {
                 query = query.bind(enable_1001_message);
             }
+            if let Some(moderation_mode) = board.moderation_mode {
+                query = query.bind(BoardModerationMode::from(moderation_mode).as_str());
+            }
+            if let Some(moderation_level_threshold) = board.moderation_level_threshold {
+                query = query.bind(moderation_level_threshold);
+            }
+            if let Some(moderation_config) = moderation_config {
+                query = query.bind(moderation_config);
+            }
             let query = query.bind(board_key);
 
             query.execute(&mut *tx).await?;
@@ -1,7 +1,7 @@
 use crate::transaction_repository;
-use chrono::{TimeZone, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use eddist_core::domain::client_info::ClientInfo;
-use sqlx::{MySqlPool, query_as, types::Json};
+use sqlx::{MySql, MySqlPool, QueryBuilder, query_as, types::Json};
 use uuid::Uuid;
 
 use crate::models::Res;
@@ -32,6 +32,37 @@ pub trait AdminResponseRepository: Send + Sync {
         body: Option<String>,
         is_abone: Option<bool>,
     ) -> anyhow::Result<Res>;
+    async fn get_bulk_moderation_targets(
+        &self,
+        params: BulkResponseFilterParams<'_>,
+    ) -> anyhow::Result<Vec<BulkModerationTarget>>;
+    /// Sets the abone flag, and also clears the name, mail and body when `erase` is set
+    async fn abone_reses(&self, ids: &[Uuid], erase: bool) -> anyhow::Result<()>;
+}
+
+/// Filter of a bulk moderation, only the responses of unarchived threads match
+pub struct BulkResponseFilterParams<'a> {
+    pub authed_token_id: Option<Uuid>,
+    pub author_id: Option<&'a str>,
+    pub ip_addr: Option<&'a str>,
+    /// First 8 bytes (/64) of a v6 `ip_addr`, compared in its binary form as the stored
+    /// address may be written in any notation
+    pub ip_v6_prefix: Option<[u8; 8]>,
+    pub asn_num: Option<u32>,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub board_ids: Option<&'a [Uuid]>,
+    pub limit: u32,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct BulkModerationTarget {
+    pub id: Uuid,
+    pub ip_addr: String,
+    pub res_order: i32,
+    pub board_key: String,
+    pub thread_number: i64,
+    pub thread_title: String,
 }
 
 #[derive(Clone)]
@@ -270,6 +301,7 @@ impl AdminResponseRepository for AdminResponseRepositoryImpl {
             sets.push("mail = ?");
             values.push(mail);
         }
+        let body_changed = body.is_some();
         if let Some(body) = body {
             sets.push("body = ?");
             values.push(body);
@@ -301,6 +333,19 @@ impl AdminResponseRepository for AdminResponseRepositoryImpl {
 
         query.execute(pool).await?;
 
+        if let Some(is_abone) = is_abone {
+            sqlx::query("UPDATE search_documents SET is_abone = ? WHERE response_id = ?")
+                .bind(is_abone)
+                .bind(id)
+                .execute(pool)
+                .await?;
+        }
+        // An edited body no longer matches its n-grams, so the post leaves the search index
+        // until eddist-cron indexes it again
+        if body_changed {
+            delete_search_documents(pool, &[id]).await?;
+        }
+
         let res = query_as!(
             SelectionRes,
             r#"
@@ -330,6 +375,122 @@ impl AdminResponseRepository for AdminResponseRepositoryImpl {
 
         Ok(selection_res_to_res(res))
     }
+
+    async fn get_bulk_moderation_targets(
+        &self,
+        params: BulkResponseFilterParams<'_>,
+    ) -> anyhow::Result<Vec<BulkModerationTarget>> {
+        let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+            r#"
+            SELECT
+                r.id,
+                r.ip_addr,
+                r.res_order,
+                b.board_key,
+                t.thread_number,
+                t.title AS thread_title
+            FROM
+                responses AS r
+            JOIN threads AS t ON r.thread_id = t.id
+            JOIN boards AS b ON t.board_id = b.id
+            WHERE
+                t.archived = 0
+            "#,
+        );
+        builder.push(" AND r.created_at BETWEEN ");
+        builder.push_bind(params.since.naive_utc());
+        builder.push(" AND ");
+        builder.push_bind(params.until.naive_utc());
+
+        if let Some(authed_token_id) = params.authed_token_id {
+            builder.push(" AND r.authed_token_id = ");
+            builder.push_bind(authed_token_id);
+        }
+        if let Some(author_id) = params.author_id {
+            builder.push(" AND r.author_id = ");
+            builder.push_bind(author_id.to_string());
+        }
+        if let Some(ip_addr) = params.ip_addr {
+            builder.push(" AND r.ip_addr = ");
+            builder.push_bind(ip_addr.to_string());
+        }
+        if let Some(prefix) = params.ip_v6_prefix {
+            builder.push(" AND LEFT(INET6_ATON(r.ip_addr), 8) = ");
+            builder.push_bind(prefix.to_vec());
+        }
+        if let Some(asn_num) = params.asn_num {
+            builder.push(" AND JSON_EXTRACT(r.client_info, '$.asn_num') = ");
+            builder.push_bind(asn_num);
+        }
+        if let Some(board_ids) = params.board_ids {
+            if board_ids.is_empty() {
+                return Ok(Vec::new());
+            }
+            builder.push(" AND r.board_id IN (");
+            let mut separated = builder.separated(", ");
+            for board_id in board_ids {
+                separated.push_bind(*board_id);
+            }
+            builder.push(")");
+        }
+        builder.push(" ORDER BY r.created_at ASC LIMIT ");
+        builder.push_bind(params.limit);
+
+        let targets = builder
+            .build_query_as::<BulkModerationTarget>()
+            .fetch_all(&self.0)
+            .await?;
+        Ok(targets)
+    }
+
+    async fn abone_reses(&self, ids: &[Uuid], erase: bool) -> anyhow::Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder: QueryBuilder<MySql> =
+            QueryBuilder::new("UPDATE responses SET is_abone = 1");
+        if erase {
+            builder.push(", author_name = '', mail = '', body = ''");
+        }
+        builder.push(" WHERE id IN (");
+        let mut separated = builder.separated(", ");
+        for id in ids {
+            separated.push_bind(*id);
+        }
+        builder.push(")");
+
+        builder.build().execute(&self.0).await?;
+
+        if erase {
+            delete_search_documents(&self.0, ids).await?;
+        } else {
+            let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+                "UPDATE search_documents SET is_abone = 1 WHERE response_id IN (",
+            );
+            let mut separated = builder.separated(", ");
+            for id in ids {
+                separated.push_bind(*id);
+            }
+            builder.push(")");
+            builder.build().execute(&self.0).await?;
+        }
+        Ok(())
+    }
+}
+
+async fn delete_search_documents(pool: &MySqlPool, ids: &[Uuid]) -> anyhow::Result<()> {
+    for table in ["search_ngrams", "search_documents"] {
+        let mut builder: QueryBuilder<MySql> =
+            QueryBuilder::new(format!("DELETE FROM {table} WHERE response_id IN ("));
+        let mut separated = builder.separated(", ");
+        for id in ids {
+            separated.push_bind(*id);
+        }
+        builder.push(")");
+        builder.build().execute(pool).await?;
+    }
+    Ok(())
 }
 
 transaction_repository!(AdminResponseRepositoryImpl, 0, MySql);
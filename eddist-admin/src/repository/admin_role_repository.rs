@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use sqlx::{MySql, MySqlPool, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    error::ServiceError,
+    models::{AdminRole, AdminScope, AdminUserAssignment},
+};
+
+#[async_trait::async_trait]
+pub trait AdminRoleRepository: Send + Sync {
+    async fn get_roles(&self) -> anyhow::Result<Vec<AdminRole>>;
+    async fn get_role(&self, id: Uuid) -> anyhow::Result<Option<AdminRole>>;
+    async fn create_role(&self, role: &AdminRole) -> anyhow::Result<()>;
+    async fn update_role(&self, role: &AdminRole) -> anyhow::Result<()>;
+    async fn delete_role(&self, id: Uuid) -> anyhow::Result<()>;
+
+    async fn get_assignments(&self) -> anyhow::Result<Vec<AdminUserAssignment>>;
+    async fn get_assignment_by_email(
+        &self,
+        email: &str,
+    ) -> anyhow::Result<Option<AdminUserAssignment>>;
+    /// Assigns the role to the email, replacing the role it had before
+    async fn upsert_assignment(&self, assignment: &AdminUserAssignment) -> anyhow::Result<()>;
+    async fn delete_assignment(&self, id: Uuid) -> anyhow::Result<()>;
+
+    /// Scopes of the role assigned to the email, empty if it has none
+    async fn get_scopes_by_email(&self, email: &str) -> anyhow::Result<Vec<AdminScope>>;
+    /// Ids and keys of the boards the email moderates, empty if it is not restricted
+    async fn get_boards_by_email(&self, email: &str) -> anyhow::Result<Vec<(Uuid, String)>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct AdminRoleRepositoryImpl(pub MySqlPool);
+
+impl AdminRoleRepositoryImpl {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self(pool)
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionAdminRole {
+    id: Uuid,
+    role_name: String,
+    role_description: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionAdminRoleScope {
+    role_id: Uuid,
+    scope_key: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionAdminUser {
+    id: Uuid,
+    email: String,
+    user_role_id: Uuid,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionAdminUserBoard {
+    admin_user_id: Uuid,
+    board_id: Uuid,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionAssignedBoard {
+    id: Uuid,
+    board_key: String,
+}
+
+fn into_assignments(
+    users: Vec<SelectionAdminUser>,
+    boards: Vec<SelectionAdminUserBoard>,
+) -> Vec<AdminUserAssignment> {
+    let mut boards_by_user = HashMap::<Uuid, Vec<Uuid>>::new();
+    for board in boards {
+        boards_by_user
+            .entry(board.admin_user_id)
+            .or_default()
+            .push(board.board_id);
+    }
+
+    users
+        .into_iter()
+        .map(|user| AdminUserAssignment {
+            id: user.id,
+            board_ids: boards_by_user.remove(&user.id).unwrap_or_default(),
+            email: user.email,
+            role_id: user.user_role_id,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        })
+        .collect()
+}
+
+/// Unknown scope keys (e.g. from a newer version) are skipped rather than failing the lookup
+fn parse_scope(scope_key: &str) -> Option<AdminScope> {
+    match scope_key.parse() {
+        Ok(scope) => Some(scope),
+        Err(e) => {
+            tracing::warn!("ignoring admin role scope: {e}");
+            None
+        }
+    }
+}
+
+fn into_roles(
+    roles: Vec<SelectionAdminRole>,
+    scopes: Vec<SelectionAdminRoleScope>,
+) -> Vec<AdminRole> {
+    let mut scopes_by_role = HashMap::<Uuid, Vec<AdminScope>>::new();
+    for scope in scopes {
+        if let Some(parsed) = parse_scope(&scope.scope_key) {
+            scopes_by_role
+                .entry(scope.role_id)
+                .or_default()
+                .push(parsed);
+        }
+    }
+
+    roles
+        .into_iter()
+        .map(|role| AdminRole {
+            id: role.id,
+            scopes: scopes_by_role.remove(&role.id).unwrap_or_default(),
+            name: role.role_name,
+            description: role.role_description,
+        })
+        .collect()
+}
+
+async fn insert_scopes(
+    tx: &mut Transaction<'_, MySql>,
+    role_id: Uuid,
+    scopes: &[AdminScope],
+) -> anyhow::Result<()> {
+    for scope in scopes {
+        sqlx::query("INSERT INTO admin_role_scopes (id, role_id, scope_key) VALUES (?, ?, ?)")
+            .bind(Uuid::now_v7())
+            .bind(role_id)
+            .bind(scope.as_str())
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+fn map_duplicate_name(e: sqlx::Error) -> anyhow::Error {
+    match e.as_database_error() {
+        Some(de) if de.is_unique_violation() => anyhow::Error::from(ServiceError::BadRequest(
+            "a role with the same name already exists".into(),
+        )),
+        _ => e.into(),
+    }
+}
+
+#[async_trait::async_trait]
+impl AdminRoleRepository for AdminRoleRepositoryImpl {
+    async fn get_roles(&self) -> anyhow::Result<Vec<AdminRole>> {
+        let roles = sqlx::query_as::<_, SelectionAdminRole>(
+            "SELECT id, role_name, role_description FROM admin_roles ORDER BY role_name",
+        )
+        .fetch_all(&self.0)
+        .await?;
+        let scopes = sqlx::query_as::<_, SelectionAdminRoleScope>(
+            "SELECT role_id, scope_key FROM admin_role_scopes ORDER BY scope_key",
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(into_roles(roles, scopes))
+    }
+
+    async fn get_role(&self, id: Uuid) -> anyhow::Result<Option<AdminRole>> {
+        let Some(role) = sqlx::query_as::<_, SelectionAdminRole>(
+            "SELECT id, role_name, role_description FROM admin_roles WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.0)
+        .await?
+        else {
+            return Ok(None);
+        };
+        let scopes = sqlx::query_as::<_, SelectionAdminRoleScope>(
+            "SELECT role_id, scope_key FROM admin_role_scopes WHERE role_id = ? ORDER BY scope_key",
+        )
+        .bind(id)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(into_roles(vec![role], scopes).pop())
+    }
+
+    async fn create_role(&self, role: &AdminRole) -> anyhow::Result<()> {
+        let mut tx = self.0.begin().await?;
+
+        sqlx::query("INSERT INTO admin_roles (id, role_name, role_description) VALUES (?, ?, ?)")
+            .bind(role.id)
+            .bind(&role.name)
+            .bind(&role.description)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_duplicate_name)?;
+        insert_scopes(&mut tx, role.id, &role.scopes).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn update_role(&self, role: &AdminRole) -> anyhow::Result<()> {
+        let mut tx = self.0.begin().await?;
+
+        sqlx::query("UPDATE admin_roles SET role_name = ?, role_description = ? WHERE id = ?")
+            .bind(&role.name)
+            .bind(&role.description)
+            .bind(role.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_duplicate_name)?;
+        sqlx::query("DELETE FROM admin_role_scopes WHERE role_id = ?")
+            .bind(role.id)
+            .execute(&mut *tx)
+            .await?;
+        insert_scopes(&mut tx, role.id, &role.scopes).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_role(&self, id: Uuid) -> anyhow::Result<()> {
+        let mut tx = self.0.begin().await?;
+
+        let assigned =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM admin_users WHERE user_role_id = ?")
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?;
+        if assigned > 0 {
+            return Err(ServiceError::BadRequest(format!(
+                "role is assigned to {assigned} admin user(s)"
+            ))
+            .into());
+        }
+
+        sqlx::query("DELETE FROM admin_role_scopes WHERE role_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM admin_roles WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_assignments(&self) -> anyhow::Result<Vec<AdminUserAssignment>> {
+        let users = sqlx::query_as::<_, SelectionAdminUser>(
+            "SELECT id, email, user_role_id, created_at, updated_at FROM admin_users ORDER BY email",
+        )
+        .fetch_all(&self.0)
+        .await?;
+        let boards = sqlx::query_as::<_, SelectionAdminUserBoard>(
+            "SELECT admin_user_id, board_id FROM admin_user_boards",
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(into_assignments(users, boards))
+    }
+
+    async fn get_assignment_by_email(
+        &self,
+        email: &str,
+    ) -> anyhow::Result<Option<AdminUserAssignment>> {
+        let Some(user) = sqlx::query_as::<_, SelectionAdminUser>(
+            "SELECT id, email, user_role_id, created_at, updated_at FROM admin_users WHERE email = ?",
+        )
+        .bind(email)
+        .fetch_optional(&self.0)
+        .await?
+        else {
+            return Ok(None);
+        };
+        let boards = sqlx::query_as::<_, SelectionAdminUserBoard>(
+            "SELECT admin_user_id, board_id FROM admin_user_boards WHERE admin_user_id = ?",
+        )
+        .bind(user.id)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(into_assignments(vec![user], boards).pop())
+    }
+
+    async fn upsert_assignment(&self, assignment: &AdminUserAssignment) -> anyhow::Result<()> {
+        let mut tx = self.0.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO admin_users
+                (id, email, user_role_id, created_at, updated_at)
+            VALUES
+                (?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE
+                user_role_id = VALUES(user_role_id),
+                updated_at = VALUES(updated_at)
+            "#,
+        )
+        .bind(assignment.id)
+        .bind(&assignment.email)
+        .bind(assignment.role_id)
+        .bind(assignment.created_at)
+        .bind(assignment.updated_at)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM admin_user_boards WHERE admin_user_id = ?")
+            .bind(assignment.id)
+            .execute(&mut *tx)
+            .await?;
+        for board_id in &assignment.board_ids {
+            sqlx::query("INSERT INTO admin_user_boards (admin_user_id, board_id) VALUES (?, ?)")
+                .bind(assignment.id)
+                .bind(board_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| match e.as_database_error() {
+                    Some(de) if de.is_foreign_key_violation() => anyhow::Error::from(
+                        ServiceError::BadRequest(format!("board not found: {board_id}")),
+                    ),
+                    _ => e.into(),
+                })?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_assignment(&self, id: Uuid) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM admin_users WHERE id = ?")
+            .bind(id)
+            .execute(&self.0)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_scopes_by_email(&self, email: &str) -> anyhow::Result<Vec<AdminScope>> {
+        let scope_keys = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT
+                ars.scope_key
+            FROM
+                admin_users AS au
+                JOIN admin_role_scopes AS ars ON ars.role_id = au.user_role_id
+            WHERE
+                au.email = ?
+            "#,
+        )
+        .bind(email)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(scope_keys.iter().filter_map(|k| parse_scope(k)).collect())
+    }
+
+    async fn get_boards_by_email(&self, email: &str) -> anyhow::Result<Vec<(Uuid, String)>> {
+        let boards = sqlx::query_as::<_, SelectionAssignedBoard>(
+            r#"
+            SELECT
+                b.id,
+                b.board_key
+            FROM
+                admin_users AS au
+                JOIN admin_user_boards AS aub ON aub.admin_user_id = au.id
+                JOIN boards AS b ON b.id = aub.board_id
+            WHERE
+                au.email = ?
+            "#,
+        )
+        .bind(email)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(boards.into_iter().map(|b| (b.id, b.board_key)).collect())
+    }
+}
@@ -3,7 +3,7 @@ use chrono::Utc;
 use sqlx::MySqlPool;
 use uuid::Uuid;
 
-use crate::models::Thread;
+use crate::{error::ServiceError, models::Thread};
 
 use super::admin_bbs_repository::SelectionThread;
 
@@ -28,6 +28,10 @@ pub trait AdminThreadRepository: Send + Sync {
         limit: u64,
     ) -> anyhow::Result<Vec<Thread>>;
     async fn compact_threads(&self, board_key: &str, target_count: u32) -> anyhow::Result<()>;
+    async fn update_thread_active(&self, id: Uuid, active: bool) -> anyhow::Result<()>;
+    async fn update_thread_no_pool(&self, id: Uuid, no_pool: bool) -> anyhow::Result<()>;
+    /// Moves the thread and its responses to another board, returning the id of that board
+    async fn move_thread(&self, id: Uuid, to_board_key: &str) -> anyhow::Result<Uuid>;
 }
 
 #[derive(Clone)]
@@ -53,6 +57,7 @@ fn selection_thread_to_thread(thread: SelectionThread) -> Thread {
         no_pool: thread.no_pool,
         archived: thread.archived,
         active: thread.active,
+        momentum: 0,
     }
 }
 
@@ -237,6 +242,7 @@ impl AdminThreadRepository for AdminThreadRepositoryImpl {
                     FROM threads
                     WHERE board_id = (SELECT id FROM boards WHERE board_key = ?)
                     AND archived = 0
+                    AND no_pool = 0
                     ORDER BY last_modified_at DESC
                     LIMIT 1000000 OFFSET ?
                 ) AS tmp
@@ -250,6 +256,74 @@ impl AdminThreadRepository for AdminThreadRepositoryImpl {
 
         Ok(())
     }
+
+    async fn update_thread_active(&self, id: Uuid, active: bool) -> anyhow::Result<()> {
+        sqlx::query("UPDATE threads SET active = ? WHERE id = ? AND archived = 0")
+            .bind(active)
+            .bind(id)
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_thread_no_pool(&self, id: Uuid, no_pool: bool) -> anyhow::Result<()> {
+        sqlx::query("UPDATE threads SET no_pool = ? WHERE id = ?")
+            .bind(no_pool)
+            .bind(id)
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    async fn move_thread(&self, id: Uuid, to_board_key: &str) -> anyhow::Result<Uuid> {
+        let mut tx = self.0.begin().await?;
+
+        let board_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM boards WHERE board_key = ?")
+            .bind(to_board_key)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("board not found: {to_board_key}")))?;
+
+        // Thread numbers are only unique per board
+        let conflicted = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*)
+            FROM threads
+            WHERE board_id = ?
+            AND thread_number = (SELECT thread_number FROM threads WHERE id = ?)
+            "#,
+        )
+        .bind(board_id)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if conflicted > 0 {
+            return Err(ServiceError::BadRequest(format!(
+                "a thread with the same number already exists in board: {to_board_key}"
+            ))
+            .into());
+        }
+
+        sqlx::query("UPDATE threads SET board_id = ? WHERE id = ?")
+            .bind(board_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE responses SET board_id = ? WHERE thread_id = ?")
+            .bind(board_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE search_documents SET board_id = ?, board_key = ? WHERE thread_id = ?")
+            .bind(board_id)
+            .bind(to_board_key)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(board_id)
+    }
 }
 
 transaction_repository!(AdminThreadRepositoryImpl, 0, MySql);
@@ -0,0 +1,159 @@
+use chrono::{DateTime, Utc};
+use sqlx::{MySql, MySqlPool, QueryBuilder, Row};
+use uuid::Uuid;
+
+use crate::models::AuditLog;
+
+pub struct ListAuditLogsParams<'a> {
+    pub offset: u64,
+    pub limit: u32,
+    pub actor_email: Option<&'a str>,
+    pub action: Option<&'a str>,
+    pub target_type: Option<&'a str>,
+    pub target_id: Option<&'a str>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[async_trait::async_trait]
+pub trait AuditLogRepository: Send + Sync {
+    async fn create_audit_log(&self, log: &AuditLog) -> anyhow::Result<()>;
+    async fn list_audit_logs(
+        &self,
+        params: ListAuditLogsParams<'_>,
+    ) -> anyhow::Result<(Vec<AuditLog>, u64)>;
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditLogRepositoryImpl(pub MySqlPool);
+
+impl AuditLogRepositoryImpl {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self(pool)
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionAuditLog {
+    id: Uuid,
+    actor_sub: String,
+    actor_email: String,
+    action: String,
+    target_type: String,
+    target_id: String,
+    before_state: Option<serde_json::Value>,
+    after_state: Option<serde_json::Value>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<SelectionAuditLog> for AuditLog {
+    fn from(value: SelectionAuditLog) -> Self {
+        Self {
+            id: value.id,
+            actor_sub: value.actor_sub,
+            actor_email: value.actor_email,
+            action: value.action,
+            target_type: value.target_type,
+            target_id: value.target_id,
+            before: value.before_state,
+            after: value.after_state,
+            created_at: value.created_at,
+        }
+    }
+}
+
+fn push_filters(builder: &mut QueryBuilder<'_, MySql>, params: &ListAuditLogsParams<'_>) {
+    if let Some(actor_email) = params.actor_email {
+        builder.push(" AND actor_email = ");
+        builder.push_bind(actor_email.to_string());
+    }
+    if let Some(action) = params.action {
+        builder.push(" AND action = ");
+        builder.push_bind(action.to_string());
+    }
+    if let Some(target_type) = params.target_type {
+        builder.push(" AND target_type = ");
+        builder.push_bind(target_type.to_string());
+    }
+    if let Some(target_id) = params.target_id {
+        builder.push(" AND target_id = ");
+        builder.push_bind(target_id.to_string());
+    }
+    if let Some(since) = params.since {
+        builder.push(" AND created_at >= ");
+        builder.push_bind(since);
+    }
+    if let Some(until) = params.until {
+        builder.push(" AND created_at < ");
+        builder.push_bind(until);
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditLogRepository for AuditLogRepositoryImpl {
+    async fn create_audit_log(&self, log: &AuditLog) -> anyhow::Result<()> {
+        let before = log.before.as_ref().map(serde_json::to_string).transpose()?;
+        let after = log.after.as_ref().map(serde_json::to_string).transpose()?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO admin_audit_logs
+                (
+                    id,
+                    actor_sub,
+                    actor_email,
+                    action,
+                    target_type,
+                    target_id,
+                    before_state,
+                    after_state,
+                    created_at
+                )
+            VALUES
+                (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(log.id)
+        .bind(&log.actor_sub)
+        .bind(&log.actor_email)
+        .bind(&log.action)
+        .bind(&log.target_type)
+        .bind(&log.target_id)
+        .bind(before)
+        .bind(after)
+        .bind(log.created_at)
+        .execute(&self.0)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_audit_logs(
+        &self,
+        params: ListAuditLogsParams<'_>,
+    ) -> anyhow::Result<(Vec<AuditLog>, u64)> {
+        let mut count_builder: QueryBuilder<MySql> =
+            QueryBuilder::new("SELECT COUNT(*) as cnt FROM admin_audit_logs WHERE 1=1");
+        let mut data_builder: QueryBuilder<MySql> = QueryBuilder::new(
+            "SELECT id, actor_sub, actor_email, action, target_type, target_id, before_state, after_state, created_at FROM admin_audit_logs WHERE 1=1",
+        );
+        push_filters(&mut count_builder, &params);
+        push_filters(&mut data_builder, &params);
+
+        data_builder.push(" ORDER BY created_at DESC, id DESC");
+        data_builder.push(" LIMIT ");
+        data_builder.push_bind(params.limit as i64);
+        data_builder.push(" OFFSET ");
+        data_builder.push_bind(params.offset as i64);
+
+        let count_row = count_builder.build().fetch_one(&self.0).await?;
+        let total: i64 = count_row.get("cnt");
+
+        let rows = data_builder
+            .build_query_as::<SelectionAuditLog>()
+            .fetch_all(&self.0)
+            .await?;
+
+        Ok((rows.into_iter().map(AuditLog::from).collect(), total as u64))
+    }
+}
@@ -50,6 +50,8 @@ pub struct ListAuthedTokensParams<'a> {
     pub authed_ua: Option<&'a str>,
     pub asn_num: Option<i32>,
     pub validity: Option<bool>,
+    /// Only tokens that have posted on one of these boards
+    pub board_ids: Option<&'a [Uuid]>,
     pub sort_column: &'a str,
     pub sort_asc: bool,
 }
@@ -65,6 +67,8 @@ pub trait AuthedTokenRepository: Send + Sync {
     ) -> anyhow::Result<(Vec<AuthedToken>, u64)>;
     async fn set_require_reauth(&self, id: Uuid) -> anyhow::Result<()>;
     async fn clear_require_reauth(&self, id: Uuid) -> anyhow::Result<()>;
+    /// Boards the token has posted on
+    async fn get_posted_board_ids(&self, id: Uuid) -> anyhow::Result<Vec<Uuid>>;
 }
 
 #[derive(Clone)]
@@ -185,6 +189,7 @@ impl AuthedTokenRepository for AuthedTokenRepositoryImpl {
             authed_ua,
             asn_num,
             validity,
+            board_ids,
             sort_column,
             sort_asc,
         } = params;
@@ -224,6 +229,21 @@ impl AuthedTokenRepository for AuthedTokenRepositoryImpl {
             data_builder.push(" AND validity = ");
             data_builder.push_bind(v);
         }
+        if let Some(board_ids) = board_ids {
+            if board_ids.is_empty() {
+                return Ok((Vec::new(), 0));
+            }
+            for builder in [&mut count_builder, &mut data_builder] {
+                builder.push(
+                    " AND EXISTS (SELECT 1 FROM responses AS r WHERE r.authed_token_id = authed_tokens.id AND r.board_id IN (",
+                );
+                let mut separated = builder.separated(", ");
+                for board_id in board_ids {
+                    separated.push_bind(*board_id);
+                }
+                builder.push("))");
+            }
+        }
 
         let direction = if sort_asc { " ASC" } else { " DESC" };
         let safe_column = match sort_column {
@@ -268,6 +288,17 @@ impl AuthedTokenRepository for AuthedTokenRepositoryImpl {
         .await?;
         Ok(())
     }
+
+    async fn get_posted_board_ids(&self, id: Uuid) -> anyhow::Result<Vec<Uuid>> {
+        let board_ids = sqlx::query_scalar::<_, Uuid>(
+            "SELECT DISTINCT board_id FROM responses WHERE authed_token_id = ?",
+        )
+        .bind(id)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(board_ids)
+    }
 }
 
 transaction_repository!(AuthedTokenRepositoryImpl, 0, MySql);
@@ -0,0 +1,379 @@
+use crate::transaction_repository;
+use chrono::{DateTime, Utc};
+use eddist_core::domain::{
+    moderation_queue::{HeldPost, HoldReason, ModerationQueueStatus},
+    pubsub_repository::{CreatingRes, CreatingThread},
+};
+use sqlx::{MySql, MySqlPool, Transaction, types::Json};
+use uuid::Uuid;
+
+use crate::error::ServiceError;
+
+const SELECT_HELD_POSTS: &str = r#"
+    SELECT
+        mq.id AS id,
+        mq.board_id AS board_id,
+        b.board_key AS board_key,
+        b.default_name AS default_name,
+        th.thread_number AS thread_number,
+        th.active AS thread_active,
+        mq.payload AS payload,
+        mq.reason AS reason,
+        mq.ng_word_id AS ng_word_id,
+        mq.status AS status,
+        mq.created_at AS created_at,
+        mq.reviewed_at AS reviewed_at,
+        mq.reviewed_by AS reviewed_by
+    FROM
+        moderation_queue AS mq
+        JOIN boards AS b ON mq.board_id = b.id
+        LEFT OUTER JOIN threads AS th ON mq.thread_id = th.id
+"#;
+
+#[derive(Debug, Clone)]
+pub struct HeldPostRecord {
+    pub id: Uuid,
+    pub board_id: Uuid,
+    pub board_key: String,
+    pub default_name: String,
+    /// The number of the thread the post belongs to, which does not exist yet for held threads
+    pub thread_number: Option<u64>,
+    pub thread_active: Option<bool>,
+    pub post: HeldPost,
+    pub reason: HoldReason,
+    pub ng_word_id: Option<Uuid>,
+    pub status: ModerationQueueStatus,
+    pub created_at: DateTime<Utc>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub reviewed_by: Option<String>,
+}
+
+#[async_trait::async_trait]
+pub trait ModerationQueueRepository: Send + Sync {
+    async fn get_held_posts(
+        &self,
+        status: ModerationQueueStatus,
+        board_ids: Option<&[Uuid]>,
+    ) -> anyhow::Result<Vec<HeldPostRecord>>;
+    async fn get_held_post(&self, id: Uuid) -> anyhow::Result<Option<HeldPostRecord>>;
+    /// Claims a pending entry for approval. The row stays locked, so concurrent reviews of the
+    /// same entry wait for the claim and then fail as already reviewed.
+    async fn claim_for_approval(
+        &self,
+        id: Uuid,
+        reviewed_by: &str,
+    ) -> anyhow::Result<Box<dyn HeldPostApproval>>;
+    async fn reject(&self, id: Uuid, reviewed_by: &str) -> anyhow::Result<()>;
+}
+
+/// An approval in progress, rolled back unless committed
+#[async_trait::async_trait]
+pub trait HeldPostApproval: Send {
+    async fn insert_response(&mut self, res: &CreatingRes) -> anyhow::Result<()>;
+    async fn insert_thread(&mut self, thread: &CreatingThread) -> anyhow::Result<()>;
+    async fn commit(self: Box<Self>) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, Clone)]
+pub struct ModerationQueueRepositoryImpl(pub MySqlPool);
+
+impl ModerationQueueRepositoryImpl {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self(pool)
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionHeldPost {
+    id: Uuid,
+    board_id: Uuid,
+    board_key: String,
+    default_name: String,
+    thread_number: Option<i64>,
+    thread_active: Option<bool>,
+    payload: Json<HeldPost>,
+    reason: String,
+    ng_word_id: Option<Uuid>,
+    status: String,
+    created_at: DateTime<Utc>,
+    reviewed_at: Option<DateTime<Utc>>,
+    reviewed_by: Option<String>,
+}
+
+impl TryFrom<SelectionHeldPost> for HeldPostRecord {
+    type Error = anyhow::Error;
+
+    fn try_from(value: SelectionHeldPost) -> Result<Self, Self::Error> {
+        let post = value.payload.0;
+        let thread_number = match &post {
+            HeldPost::Response(_) => value.thread_number.map(|n| n as u64),
+            HeldPost::Thread(th) => Some(th.unix_time),
+        };
+
+        Ok(HeldPostRecord {
+            id: value.id,
+            board_id: value.board_id,
+            board_key: value.board_key,
+            default_name: value.default_name,
+            thread_number,
+            thread_active: value.thread_active,
+            post,
+            reason: value.reason.parse().map_err(|e| anyhow::anyhow!("{e}"))?,
+            ng_word_id: value.ng_word_id,
+            status: value.status.parse().map_err(|e| anyhow::anyhow!("{e}"))?,
+            created_at: value.created_at,
+            reviewed_at: value.reviewed_at,
+            reviewed_by: value.reviewed_by,
+        })
+    }
+}
+
+/// Moves a pending entry to `status`, failing if someone else has already reviewed it
+async fn mark_reviewed(
+    tx: &mut Transaction<'_, MySql>,
+    id: Uuid,
+    status: ModerationQueueStatus,
+    reviewed_by: &str,
+) -> anyhow::Result<()> {
+    let result = sqlx::query(
+        r#"
+        UPDATE
+            moderation_queue
+        SET
+            status = ?,
+            reviewed_at = ?,
+            reviewed_by = ?
+        WHERE
+            id = ?
+            AND status = 'PENDING'
+        "#,
+    )
+    .bind(status.as_str())
+    .bind(Utc::now())
+    .bind(reviewed_by)
+    .bind(id)
+    .execute(&mut **tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ServiceError::BadRequest("held post is already reviewed".into()).into());
+    }
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl ModerationQueueRepository for ModerationQueueRepositoryImpl {
+    async fn get_held_posts(
+        &self,
+        status: ModerationQueueStatus,
+        board_ids: Option<&[Uuid]>,
+    ) -> anyhow::Result<Vec<HeldPostRecord>> {
+        let query = match board_ids {
+            Some([]) => return Ok(Vec::new()),
+            Some(board_ids) => format!(
+                "{SELECT_HELD_POSTS} WHERE mq.status = ? AND mq.board_id IN ({}) ORDER BY mq.created_at",
+                vec!["?"; board_ids.len()].join(", ")
+            ),
+            None => format!("{SELECT_HELD_POSTS} WHERE mq.status = ? ORDER BY mq.created_at"),
+        };
+
+        let mut query = sqlx::query_as::<_, SelectionHeldPost>(&query).bind(status.as_str());
+        for board_id in board_ids.unwrap_or_default() {
+            query = query.bind(board_id);
+        }
+        let selections = query.fetch_all(&self.0).await?;
+
+        selections.into_iter().map(TryInto::try_into).collect()
+    }
+
+    async fn get_held_post(&self, id: Uuid) -> anyhow::Result<Option<HeldPostRecord>> {
+        let selection =
+            sqlx::query_as::<_, SelectionHeldPost>(&format!("{SELECT_HELD_POSTS} WHERE mq.id = ?"))
+                .bind(id)
+                .fetch_optional(&self.0)
+                .await?;
+
+        selection.map(TryInto::try_into).transpose()
+    }
+
+    async fn claim_for_approval(
+        &self,
+        id: Uuid,
+        reviewed_by: &str,
+    ) -> anyhow::Result<Box<dyn HeldPostApproval>> {
+        let mut tx = self.0.begin().await?;
+        mark_reviewed(&mut tx, id, ModerationQueueStatus::Approved, reviewed_by).await?;
+        Ok(Box::new(MySqlHeldPostApproval(tx)))
+    }
+
+    async fn reject(&self, id: Uuid, reviewed_by: &str) -> anyhow::Result<()> {
+        let mut tx = self.0.begin().await?;
+        mark_reviewed(&mut tx, id, ModerationQueueStatus::Rejected, reviewed_by).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+struct MySqlHeldPostApproval(Transaction<'static, MySql>);
+
+#[async_trait::async_trait]
+impl HeldPostApproval for MySqlHeldPostApproval {
+    async fn insert_response(&mut self, res: &CreatingRes) -> anyhow::Result<()> {
+        let client_info_json = serde_json::to_string(&res.client_info)?;
+        let tx = &mut self.0;
+
+        sqlx::query(
+            r#"
+            UPDATE threads SET
+                last_modified_at = ?,
+                response_count = response_count + 1,
+                sage_last_modified_at = (
+                    CASE
+                        WHEN ? THEN sage_last_modified_at
+                        ELSE ?
+                    END
+                ),
+                active = (
+                    CASE
+                        WHEN response_count >= 1000 THEN 0
+                        ELSE 1
+                    END
+                )
+            WHERE id = ?
+            "#,
+        )
+        .bind(res.created_at)
+        .bind(res.is_sage)
+        .bind(res.created_at)
+        .bind(res.thread_id)
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO responses
+                (
+                    id,
+                    author_name,
+                    mail,
+                    author_id,
+                    body,
+                    thread_id,
+                    board_id,
+                    ip_addr,
+                    authed_token_id,
+                    created_at,
+                    client_info,
+                    is_abone,
+                    res_order
+                )
+            VALUES
+                (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(res.id)
+        .bind(&res.name)
+        .bind(&res.mail)
+        .bind(&res.author_ch5id)
+        .bind(&res.body)
+        .bind(res.thread_id)
+        .bind(res.board_id)
+        .bind(&res.ip_addr)
+        .bind(res.authed_token_id)
+        .bind(res.created_at)
+        .bind(client_info_json)
+        .bind(res.is_abone)
+        .bind(res.res_order)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_thread(&mut self, thread: &CreatingThread) -> anyhow::Result<()> {
+        let metadent = Option::<&str>::from(thread.metadent).unwrap_or("");
+        let client_info_json = serde_json::to_string(&thread.client_info)?;
+        let tx = &mut self.0;
+
+        sqlx::query(
+            r#"
+            INSERT INTO threads
+                (
+                    id,
+                    board_id,
+                    thread_number,
+                    last_modified_at,
+                    sage_last_modified_at,
+                    title,
+                    authed_token_id,
+                    metadent,
+                    response_count
+                )
+            VALUES
+                (?, ?, ?, ?, ?, ?, ?, ?, 1)
+            "#,
+        )
+        .bind(thread.thread_id)
+        .bind(thread.board_id)
+        .bind(thread.unix_time as i64)
+        .bind(thread.created_at)
+        .bind(thread.created_at)
+        .bind(&thread.title)
+        .bind(thread.authed_token_id)
+        .bind(metadent)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(de) if de.is_unique_violation() => anyhow::Error::from(ServiceError::BadRequest(
+                "a thread with the same number already exists".into(),
+            )),
+            _ => e.into(),
+        })?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO responses
+                (
+                    id,
+                    author_name,
+                    mail,
+                    author_id,
+                    body,
+                    thread_id,
+                    board_id,
+                    ip_addr,
+                    authed_token_id,
+                    created_at,
+                    client_info,
+                    is_abone,
+                    res_order
+                )
+            VALUES
+                (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 1)
+            "#,
+        )
+        .bind(thread.response_id)
+        .bind(&thread.name)
+        .bind(&thread.mail)
+        .bind(&thread.author_ch5id)
+        .bind(&thread.body)
+        .bind(thread.thread_id)
+        .bind(thread.board_id)
+        .bind(&thread.ip_addr)
+        .bind(thread.authed_token_id)
+        .bind(thread.created_at)
+        .bind(client_info_json)
+        .bind(thread.is_abone)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn commit(self: Box<Self>) -> anyhow::Result<()> {
+        self.0.commit().await?;
+        Ok(())
+    }
+}
+
+transaction_repository!(ModerationQueueRepositoryImpl, 0, MySql);
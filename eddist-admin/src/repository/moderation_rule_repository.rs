@@ -0,0 +1,319 @@
+use chrono::{DateTime, Utc};
+use eddist_core::domain::moderation_rule::{ModerationActionLog, ModerationRule};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::error::ServiceError;
+
+const SELECT_MODERATION_RULES: &str = r#"
+    SELECT
+        id,
+        name,
+        board_id,
+        category,
+        threshold,
+        action,
+        duration_seconds,
+        internal_level,
+        enabled,
+        created_at,
+        updated_at
+    FROM
+        moderation_rules
+"#;
+
+const SELECT_MODERATION_ACTION_LOGS: &str = r#"
+    SELECT
+        id,
+        rule_id,
+        action,
+        board_id,
+        thread_number,
+        response_id,
+        authed_token_id,
+        category,
+        score,
+        expires_at,
+        created_at,
+        reverted_at,
+        reverted_by
+    FROM
+        moderation_action_logs
+"#;
+
+#[async_trait::async_trait]
+pub trait ModerationRuleRepository: Send + Sync {
+    async fn get_rules(&self) -> anyhow::Result<Vec<ModerationRule>>;
+    async fn get_rule(&self, id: Uuid) -> anyhow::Result<Option<ModerationRule>>;
+    async fn create_rule(&self, rule: &ModerationRule) -> anyhow::Result<()>;
+    async fn update_rule(&self, rule: &ModerationRule) -> anyhow::Result<()>;
+    async fn delete_rule(&self, id: Uuid) -> anyhow::Result<()>;
+    async fn get_action_logs(
+        &self,
+        board_ids: Option<&[Uuid]>,
+        reverted: Option<bool>,
+        limit: u32,
+    ) -> anyhow::Result<Vec<ModerationActionLog>>;
+    async fn get_action_log(&self, id: Uuid) -> anyhow::Result<Option<ModerationActionLog>>;
+    async fn mark_reverted(&self, id: Uuid, reverted_by: &str) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, Clone)]
+pub struct ModerationRuleRepositoryImpl(pub MySqlPool);
+
+impl ModerationRuleRepositoryImpl {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self(pool)
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionModerationRule {
+    id: Uuid,
+    name: String,
+    board_id: Option<Uuid>,
+    category: String,
+    threshold: f64,
+    action: String,
+    duration_seconds: u64,
+    internal_level: u32,
+    enabled: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<SelectionModerationRule> for ModerationRule {
+    type Error = anyhow::Error;
+
+    fn try_from(value: SelectionModerationRule) -> Result<Self, Self::Error> {
+        Ok(ModerationRule {
+            id: value.id,
+            name: value.name,
+            board_id: value.board_id,
+            category: value.category,
+            threshold: value.threshold,
+            action: value.action.parse().map_err(|e| anyhow::anyhow!("{e}"))?,
+            duration_seconds: value.duration_seconds,
+            internal_level: value.internal_level,
+            enabled: value.enabled,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        })
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionModerationActionLog {
+    id: Uuid,
+    rule_id: Option<Uuid>,
+    action: String,
+    board_id: Uuid,
+    thread_number: u64,
+    response_id: Uuid,
+    authed_token_id: Uuid,
+    category: String,
+    score: f64,
+    expires_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    reverted_at: Option<DateTime<Utc>>,
+    reverted_by: Option<String>,
+}
+
+impl TryFrom<SelectionModerationActionLog> for ModerationActionLog {
+    type Error = anyhow::Error;
+
+    fn try_from(value: SelectionModerationActionLog) -> Result<Self, Self::Error> {
+        Ok(ModerationActionLog {
+            id: value.id,
+            rule_id: value.rule_id,
+            action: value.action.parse().map_err(|e| anyhow::anyhow!("{e}"))?,
+            board_id: value.board_id,
+            thread_number: value.thread_number,
+            response_id: value.response_id,
+            authed_token_id: value.authed_token_id,
+            category: value.category,
+            score: value.score,
+            expires_at: value.expires_at,
+            created_at: value.created_at,
+            reverted_at: value.reverted_at,
+            reverted_by: value.reverted_by,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl ModerationRuleRepository for ModerationRuleRepositoryImpl {
+    async fn get_rules(&self) -> anyhow::Result<Vec<ModerationRule>> {
+        let selections = sqlx::query_as::<_, SelectionModerationRule>(&format!(
+            "{SELECT_MODERATION_RULES} ORDER BY created_at"
+        ))
+        .fetch_all(&self.0)
+        .await?;
+
+        selections.into_iter().map(TryInto::try_into).collect()
+    }
+
+    async fn get_rule(&self, id: Uuid) -> anyhow::Result<Option<ModerationRule>> {
+        let selection = sqlx::query_as::<_, SelectionModerationRule>(&format!(
+            "{SELECT_MODERATION_RULES} WHERE id = ?"
+        ))
+        .bind(id)
+        .fetch_optional(&self.0)
+        .await?;
+
+        selection.map(TryInto::try_into).transpose()
+    }
+
+    async fn create_rule(&self, rule: &ModerationRule) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO moderation_rules
+                (
+                    id,
+                    name,
+                    board_id,
+                    category,
+                    threshold,
+                    action,
+                    duration_seconds,
+                    internal_level,
+                    enabled,
+                    created_at,
+                    updated_at
+                )
+            VALUES
+                (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(rule.id)
+        .bind(&rule.name)
+        .bind(rule.board_id)
+        .bind(&rule.category)
+        .bind(rule.threshold)
+        .bind(rule.action.as_str())
+        .bind(rule.duration_seconds)
+        .bind(rule.internal_level)
+        .bind(rule.enabled)
+        .bind(rule.created_at)
+        .bind(rule.updated_at)
+        .execute(&self.0)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_rule(&self, rule: &ModerationRule) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE moderation_rules SET
+                name = ?,
+                board_id = ?,
+                category = ?,
+                threshold = ?,
+                action = ?,
+                duration_seconds = ?,
+                internal_level = ?,
+                enabled = ?,
+                updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&rule.name)
+        .bind(rule.board_id)
+        .bind(&rule.category)
+        .bind(rule.threshold)
+        .bind(rule.action.as_str())
+        .bind(rule.duration_seconds)
+        .bind(rule.internal_level)
+        .bind(rule.enabled)
+        .bind(rule.updated_at)
+        .bind(rule.id)
+        .execute(&self.0)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_rule(&self, id: Uuid) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM moderation_rules WHERE id = ?")
+            .bind(id)
+            .execute(&self.0)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_action_logs(
+        &self,
+        board_ids: Option<&[Uuid]>,
+        reverted: Option<bool>,
+        limit: u32,
+    ) -> anyhow::Result<Vec<ModerationActionLog>> {
+        let mut conditions = Vec::new();
+        match board_ids {
+            Some([]) => return Ok(Vec::new()),
+            Some(board_ids) => conditions.push(format!(
+                "board_id IN ({})",
+                vec!["?"; board_ids.len()].join(", ")
+            )),
+            None => {}
+        }
+        match reverted {
+            Some(true) => conditions.push("reverted_at IS NOT NULL".to_string()),
+            Some(false) => conditions.push("reverted_at IS NULL".to_string()),
+            None => {}
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let query = format!(
+            "{SELECT_MODERATION_ACTION_LOGS} {where_clause} ORDER BY created_at DESC LIMIT ?"
+        );
+        let mut query = sqlx::query_as::<_, SelectionModerationActionLog>(&query);
+        for board_id in board_ids.unwrap_or_default() {
+            query = query.bind(board_id);
+        }
+        let selections = query.bind(limit).fetch_all(&self.0).await?;
+
+        selections.into_iter().map(TryInto::try_into).collect()
+    }
+
+    async fn get_action_log(&self, id: Uuid) -> anyhow::Result<Option<ModerationActionLog>> {
+        let selection = sqlx::query_as::<_, SelectionModerationActionLog>(&format!(
+            "{SELECT_MODERATION_ACTION_LOGS} WHERE id = ?"
+        ))
+        .bind(id)
+        .fetch_optional(&self.0)
+        .await?;
+
+        selection.map(TryInto::try_into).transpose()
+    }
+
+    async fn mark_reverted(&self, id: Uuid, reverted_by: &str) -> anyhow::Result<()> {
+        let result = sqlx::query(
+            r#"
+            UPDATE
+                moderation_action_logs
+            SET
+                reverted_at = ?,
+                reverted_by = ?
+            WHERE
+                id = ?
+                AND reverted_at IS NULL
+            "#,
+        )
+        .bind(Utc::now())
+        .bind(reverted_by)
+        .bind(id)
+        .execute(&self.0)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(ServiceError::BadRequest("action is already reverted".into()).into());
+        }
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ use crate::transaction_repository;
 use std::collections::HashMap;
 
 use chrono::Utc;
+use eddist_core::domain::ng_word::{NgWordAction, NgWordMatchType, NgWordTarget};
 use sqlx::{Executor, MySqlPool, query, query_as};
 use uuid::Uuid;
 
@@ -10,17 +11,31 @@ use crate::models::NgWord;
 #[async_trait::async_trait]
 pub trait NgWordRepository: Send + Sync {
     async fn get_ng_words(&self) -> anyhow::Result<Vec<NgWord>>;
-    async fn update_ng_word(
+    async fn update_ng_word(&self, id: Uuid, changes: NgWordChanges<'_>) -> anyhow::Result<NgWord>;
+    async fn create_ng_word(
         &self,
-        id: Uuid,
-        name: Option<&str>,
-        word: Option<&str>,
-        board_ids: Option<Vec<Uuid>>,
+        name: &str,
+        word: &str,
+        match_type: NgWordMatchType,
+        targets: &[NgWordTarget],
+        action: NgWordAction,
+        penalty_seconds: u32,
     ) -> anyhow::Result<NgWord>;
-    async fn create_ng_word(&self, name: &str, word: &str) -> anyhow::Result<NgWord>;
     async fn delete_ng_word(&self, ng_word_id: Uuid) -> anyhow::Result<()>;
 }
 
+/// Fields to overwrite on an NG word, `None` keeps the current value
+#[derive(Debug, Default)]
+pub struct NgWordChanges<'a> {
+    pub name: Option<&'a str>,
+    pub word: Option<&'a str>,
+    pub match_type: Option<NgWordMatchType>,
+    pub targets: Option<&'a [NgWordTarget]>,
+    pub action: Option<NgWordAction>,
+    pub penalty_seconds: Option<u32>,
+    pub board_ids: Option<Vec<Uuid>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct NgWordRepositoryImpl(pub MySqlPool);
 
@@ -35,11 +50,46 @@ pub struct SelectionNgWord {
     pub id: Uuid,
     pub name: String,
     pub word: String,
+    pub match_type: String,
+    pub target_fields: String,
+    pub action: String,
+    pub penalty_seconds: u32,
     pub created_at: chrono::DateTime<Utc>,
     pub updated_at: chrono::DateTime<Utc>,
     pub board_id: Option<Uuid>,
 }
 
+impl SelectionNgWord {
+    fn into_ng_word(self, board_ids: Vec<Uuid>) -> anyhow::Result<NgWord> {
+        let match_type = self
+            .match_type
+            .parse::<NgWordMatchType>()
+            .map_err(|e| anyhow::anyhow!(e))?;
+        let targets = NgWordTarget::parse_list(&self.target_fields)
+            .map_err(|e| anyhow::anyhow!(e))?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        let action = self
+            .action
+            .parse::<NgWordAction>()
+            .map_err(|e| anyhow::anyhow!(e))?;
+
+        Ok(NgWord {
+            id: self.id,
+            name: self.name,
+            word: self.word,
+            match_type: match_type.into(),
+            targets,
+            action: action.into(),
+            penalty_seconds: self.penalty_seconds,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            board_ids,
+        })
+    }
+}
+
 #[async_trait::async_trait]
 impl NgWordRepository for NgWordRepositoryImpl {
     async fn get_ng_words(&self) -> anyhow::Result<Vec<NgWord>> {
@@ -50,6 +100,10 @@ impl NgWordRepository for NgWordRepositoryImpl {
                 ng.id AS "id!: Uuid",
                 name AS "name!: String",
                 word AS "word!: String",
+                match_type AS "match_type!: String",
+                target_fields AS "target_fields!: String",
+                action AS "action!: String",
+                penalty_seconds AS "penalty_seconds!: u32",
                 created_at AS "created_at!: chrono::DateTime<Utc>",
                 updated_at AS "updated_at!: chrono::DateTime<Utc>",
                 board_id AS "board_id: Uuid"
@@ -64,43 +118,54 @@ impl NgWordRepository for NgWordRepositoryImpl {
 
         let mut ng_words_map = HashMap::<_, NgWord>::new();
         for selection in selections {
-            ng_words_map
-                .entry(selection.id)
-                .and_modify(|x| {
-                    if let Some(board_id) = selection.board_id {
-                        x.board_ids.push(board_id);
-                    }
-                })
-                .or_insert(NgWord {
-                    id: selection.id,
-                    name: selection.name,
-                    word: selection.word,
-                    created_at: selection.created_at,
-                    updated_at: selection.updated_at,
-                    board_ids: if let Some(board_id) = selection.board_id {
-                        vec![board_id]
-                    } else {
-                        Vec::new()
-                    },
-                });
+            if let Some(ng_word) = ng_words_map.get_mut(&selection.id) {
+                if let Some(board_id) = selection.board_id {
+                    ng_word.board_ids.push(board_id);
+                }
+                continue;
+            }
+            let board_ids = selection.board_id.into_iter().collect();
+            ng_words_map.insert(selection.id, selection.into_ng_word(board_ids)?);
         }
 
         Ok(ng_words_map.into_values().collect())
     }
 
-    async fn create_ng_word(&self, name: &str, word: &str) -> anyhow::Result<NgWord> {
+    async fn create_ng_word(
+        &self,
+        name: &str,
+        word: &str,
+        match_type: NgWordMatchType,
+        targets: &[NgWordTarget],
+        action: NgWordAction,
+        penalty_seconds: u32,
+    ) -> anyhow::Result<NgWord> {
         let id = Uuid::now_v7();
 
         let query = query!(
             r#"
             INSERT INTO
-                ng_words (id, name, word, created_at, updated_at)
+                ng_words (
+                    id,
+                    name,
+                    word,
+                    match_type,
+                    target_fields,
+                    action,
+                    penalty_seconds,
+                    created_at,
+                    updated_at
+                )
             VALUES
-                (?, ?, ?, NOW(), NOW())
+                (?, ?, ?, ?, ?, ?, ?, NOW(), NOW())
         "#,
             id,
             name,
-            word
+            word,
+            match_type.as_str(),
+            NgWordTarget::join_list(targets),
+            action.as_str(),
+            penalty_seconds
         );
         self.0.execute(query).await?;
 
@@ -111,6 +176,10 @@ impl NgWordRepository for NgWordRepositoryImpl {
                 ng.id AS "id!: Uuid",
                 name AS "name!: String",
                 word AS "word!: String",
+                match_type AS "match_type!: String",
+                target_fields AS "target_fields!: String",
+                action AS "action!: String",
+                penalty_seconds AS "penalty_seconds!: u32",
                 created_at AS "created_at!: chrono::DateTime<Utc>",
                 updated_at AS "updated_at!: chrono::DateTime<Utc>",
                 board_id AS "board_id: Uuid"
@@ -126,18 +195,8 @@ impl NgWordRepository for NgWordRepositoryImpl {
 
         let selection = query.fetch_one(&self.0).await?;
 
-        Ok(NgWord {
-            id: selection.id,
-            name: selection.name,
-            word: selection.word,
-            created_at: selection.created_at,
-            updated_at: selection.updated_at,
-            board_ids: if let Some(board_id) = selection.board_id {
-                vec![board_id]
-            } else {
-                Vec::new()
-            },
-        })
+        let board_ids = selection.board_id.into_iter().collect();
+        selection.into_ng_word(board_ids)
     }
 
     async fn delete_ng_word(&self, ng_word_id: Uuid) -> anyhow::Result<()> {
@@ -156,13 +215,17 @@ impl NgWordRepository for NgWordRepositoryImpl {
         Ok(())
     }
 
-    async fn update_ng_word(
-        &self,
-        id: Uuid,
-        name: Option<&str>,
-        word: Option<&str>,
-        board_ids: Option<Vec<Uuid>>,
-    ) -> anyhow::Result<NgWord> {
+    async fn update_ng_word(&self, id: Uuid, changes: NgWordChanges<'_>) -> anyhow::Result<NgWord> {
+        let NgWordChanges {
+            name,
+            word,
+            match_type,
+            targets,
+            action,
+            penalty_seconds,
+            board_ids,
+        } = changes;
+
         let mut sets = Vec::new();
         if name.is_some() {
             sets.push("name = ?");
@@ -170,6 +233,18 @@ impl NgWordRepository for NgWordRepositoryImpl {
         if word.is_some() {
             sets.push("word = ?");
         }
+        if match_type.is_some() {
+            sets.push("match_type = ?");
+        }
+        if targets.is_some() {
+            sets.push("target_fields = ?");
+        }
+        if action.is_some() {
+            sets.push("action = ?");
+        }
+        if penalty_seconds.is_some() {
+            sets.push("penalty_seconds = ?");
+        }
         sets.push("updated_at = ?");
 
         let query = format!(
@@ -191,6 +266,18 @@ impl NgWordRepository for NgWordRepositoryImpl {
         if let Some(word) = word {
             query = query.bind(word);
         }
+        if let Some(match_type) = match_type {
+            query = query.bind(match_type.as_str());
+        }
+        if let Some(targets) = targets {
+            query = query.bind(NgWordTarget::join_list(targets));
+        }
+        if let Some(action) = action {
+            query = query.bind(action.as_str());
+        }
+        if let Some(penalty_seconds) = penalty_seconds {
+            query = query.bind(penalty_seconds);
+        }
         let query = query.bind(Utc::now()).bind(id);
         query.execute(&self.0).await?;
 
@@ -234,6 +321,10 @@ impl NgWordRepository for NgWordRepositoryImpl {
                 ng.id AS "id!: Uuid",
                 name AS "name!: String",
                 word AS "word!: String",
+                match_type AS "match_type!: String",
+                target_fields AS "target_fields!: String",
+                action AS "action!: String",
+                penalty_seconds AS "penalty_seconds!: u32",
                 created_at AS "created_at!: chrono::DateTime<Utc>",
                 updated_at AS "updated_at!: chrono::DateTime<Utc>",
                 board_id AS "board_id: Uuid"
@@ -257,14 +348,7 @@ impl NgWordRepository for NgWordRepositoryImpl {
             .next()
             .ok_or_else(|| anyhow::anyhow!("ng word not found: {id}"))?;
 
-        Ok(NgWord {
-            id: selection.id,
-            name: selection.name,
-            word: selection.word,
-            created_at: selection.created_at,
-            updated_at: selection.updated_at,
-            board_ids,
-        })
+        selection.into_ng_word(board_ids)
     }
 }
 
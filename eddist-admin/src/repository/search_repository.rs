@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use eddist_core::domain::search::rarest_ngrams;
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use uuid::Uuid;
+
+use crate::models::SearchHit;
+
+/// Filter of a search, the posts must have every n-gram and match every given field
+pub struct SearchPostsParams<'a> {
+    pub ngrams: &'a [String],
+    pub board_ids: Option<&'a [Uuid]>,
+    pub author_id: Option<&'a str>,
+    pub authed_token_id: Option<Uuid>,
+    pub ip_addr: Option<&'a str>,
+    pub include_abone: bool,
+    pub limit: u32,
+}
+
+#[async_trait::async_trait]
+pub trait AdminSearchRepository: Send + Sync {
+    async fn search_posts(&self, params: SearchPostsParams<'_>) -> anyhow::Result<Vec<SearchHit>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct AdminSearchRepositoryImpl(pub MySqlPool);
+
+impl AdminSearchRepositoryImpl {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self(pool)
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionSearchHit {
+    response_id: Uuid,
+    board_id: Uuid,
+    board_key: String,
+    thread_number: i64,
+    thread_title: String,
+    res_order: i32,
+    body: String,
+    author_id: String,
+    authed_token_id: Option<Uuid>,
+    ip_addr: Option<String>,
+    is_abone: bool,
+    created_at: DateTime<Utc>,
+}
+
+impl From<SelectionSearchHit> for SearchHit {
+    fn from(value: SelectionSearchHit) -> Self {
+        Self {
+            response_id: value.response_id,
+            board_id: value.board_id,
+            board_key: value.board_key,
+            thread_number: value.thread_number as u64,
+            thread_title: value.thread_title,
+            res_order: value.res_order,
+            body: value.body,
+            author_id: value.author_id,
+            authed_token_id: value.authed_token_id,
+            ip_addr: value.ip_addr,
+            is_abone: value.is_abone,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl AdminSearchRepository for AdminSearchRepositoryImpl {
+    async fn search_posts(&self, params: SearchPostsParams<'_>) -> anyhow::Result<Vec<SearchHit>> {
+        let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+            r#"
+            SELECT
+                d.response_id,
+                d.board_id,
+                d.board_key,
+                d.thread_number,
+                d.thread_title,
+                d.res_order,
+                d.body,
+                d.author_id,
+                d.authed_token_id,
+                d.ip_addr,
+                d.is_abone,
+                d.created_at
+            FROM
+                search_documents AS d
+            "#,
+        );
+        let ngrams = if params.ngrams.is_empty() {
+            Vec::new()
+        } else {
+            match lookup_ngrams(&self.0, params.ngrams).await? {
+                Some(ngrams) => ngrams,
+                None => return Ok(Vec::new()),
+            }
+        };
+        // Driven by the postings of the rarest n-gram, the others only being probed
+        if let Some((rarest, others)) = ngrams.split_first() {
+            builder
+                .push(" JOIN search_ngrams AS n ON n.response_id = d.response_id AND n.ngram = ");
+            builder.push_bind(rarest.clone());
+            builder.push(" WHERE 1 = 1");
+            for ngram in others {
+                builder.push(
+                    " AND EXISTS (SELECT 1 FROM search_ngrams AS o WHERE o.response_id = d.response_id AND o.ngram = ",
+                );
+                builder.push_bind(ngram.clone());
+                builder.push(")");
+            }
+        } else {
+            builder.push(" WHERE 1 = 1");
+        }
+
+        if !params.include_abone {
+            builder.push(" AND d.is_abone = 0");
+        }
+        if let Some(author_id) = params.author_id {
+            builder.push(" AND d.author_id = ");
+            builder.push_bind(author_id.to_string());
+        }
+        if let Some(authed_token_id) = params.authed_token_id {
+            builder.push(" AND d.authed_token_id = ");
+            builder.push_bind(authed_token_id);
+        }
+        if let Some(ip_addr) = params.ip_addr {
+            builder.push(" AND d.ip_addr = ");
+            builder.push_bind(ip_addr.to_string());
+        }
+        if let Some(board_ids) = params.board_ids {
+            if board_ids.is_empty() {
+                return Ok(Vec::new());
+            }
+            builder.push(" AND d.board_id IN (");
+            let mut separated = builder.separated(", ");
+            for board_id in board_ids {
+                separated.push_bind(*board_id);
+            }
+            builder.push(")");
+        }
+        builder.push(" ORDER BY d.created_at DESC LIMIT ");
+        builder.push_bind(params.limit);
+
+        let hits = builder
+            .build_query_as::<SelectionSearchHit>()
+            .fetch_all(&self.0)
+            .await?;
+        Ok(hits.into_iter().map(SearchHit::from).collect())
+    }
+}
+
+/// The n-grams to look up, rarest first, `None` when one of them is in no document
+async fn lookup_ngrams(pool: &MySqlPool, ngrams: &[String]) -> anyhow::Result<Option<Vec<String>>> {
+    let mut builder: QueryBuilder<MySql> =
+        QueryBuilder::new("SELECT ngram, doc_count FROM search_ngram_stats WHERE ngram IN (");
+    let mut separated = builder.separated(", ");
+    for ngram in ngrams {
+        separated.push_bind(ngram.clone());
+    }
+    builder.push(")");
+    let doc_counts = builder
+        .build_query_as::<(String, i64)>()
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|(ngram, count)| (ngram, count.max(0) as u64))
+        .collect::<HashMap<_, _>>();
+
+    Ok(rarest_ngrams(ngrams, &doc_counts))
+}
@@ -1,10 +1,17 @@
 use crate::transaction_repository;
 use async_trait::async_trait;
-use eddist_core::domain::user_restriction::{
-    CreateUserRestrictionRuleInput, RestrictionRuleType, UpdateUserRestrictionRuleInput,
-    UserRestrictionRule,
+use chrono::NaiveDateTime;
+use eddist_core::{
+    domain::{
+        user_restriction::{
+            CreateUserRestrictionRuleInput, RestrictionAction, RestrictionRuleType,
+            RestrictionTarget, UpdateUserRestrictionRuleInput, UserRestrictionRule,
+        },
+        user_restriction_bulk::{RestrictionRuleFormat, UserRestrictionSubscription},
+    },
+    user_restriction_hits::RestrictionRuleHits,
 };
-use sqlx::{MySql, Pool};
+use sqlx::{MySql, Pool, QueryBuilder};
 use uuid::Uuid;
 
 #[async_trait]
@@ -17,6 +24,29 @@ pub trait UserRestrictionRepository: Send + Sync {
     async fn update_rule(&self, input: UpdateUserRestrictionRuleInput) -> anyhow::Result<()>;
     async fn delete_rule(&self, id: Uuid) -> anyhow::Result<()>;
     async fn get_rule_by_id(&self, id: Uuid) -> anyhow::Result<Option<UserRestrictionRule>>;
+    /// Hits of every rule, without the samples
+    async fn get_all_rule_hits(&self) -> anyhow::Result<Vec<RestrictionRuleHits>>;
+    async fn get_rule_hits(&self, id: Uuid) -> anyhow::Result<Option<RestrictionRuleHits>>;
+    /// Creates the rules of a bulk import all at once
+    async fn create_rules(
+        &self,
+        inputs: Vec<CreateUserRestrictionRuleInput>,
+    ) -> anyhow::Result<Vec<UserRestrictionRule>>;
+    async fn get_all_subscriptions(&self) -> anyhow::Result<Vec<UserRestrictionSubscription>>;
+    async fn get_subscription_by_id(
+        &self,
+        id: Uuid,
+    ) -> anyhow::Result<Option<UserRestrictionSubscription>>;
+    async fn create_subscription(
+        &self,
+        subscription: &UserRestrictionSubscription,
+    ) -> anyhow::Result<()>;
+    async fn update_subscription(
+        &self,
+        subscription: &UserRestrictionSubscription,
+    ) -> anyhow::Result<()>;
+    /// Deletes the subscription along with its rules
+    async fn delete_subscription(&self, id: Uuid) -> anyhow::Result<()>;
 }
 
 #[derive(Clone)]
@@ -30,17 +60,183 @@ impl UserRestrictionRepositoryImpl {
     }
 }
 
+#[derive(Debug)]
+struct SelectionUserRestrictionRule {
+    id: Uuid,
+    name: String,
+    rule_type: String,
+    rule_value: String,
+    board_keys: Option<serde_json::Value>,
+    targets: String,
+    action: String,
+    captcha_provider: Option<String>,
+    slow_down_seconds: u32,
+    expires_at: Option<NaiveDateTime>,
+    subscription_id: Option<Uuid>,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    created_by_email: String,
+}
+
+impl TryFrom<SelectionUserRestrictionRule> for UserRestrictionRule {
+    type Error = anyhow::Error;
+
+    fn try_from(x: SelectionUserRestrictionRule) -> anyhow::Result<Self> {
+        Ok(UserRestrictionRule {
+            id: x.id,
+            name: x.name,
+            rule_type: x
+                .rule_type
+                .parse::<RestrictionRuleType>()
+                .map_err(|e| anyhow::anyhow!("Invalid rule type '{}': {}", x.rule_type, e))?,
+            rule_value: x.rule_value,
+            board_keys: x.board_keys.map(serde_json::from_value).transpose()?,
+            targets: RestrictionTarget::parse_list(&x.targets).map_err(anyhow::Error::msg)?,
+            action: x
+                .action
+                .parse::<RestrictionAction>()
+                .map_err(anyhow::Error::msg)?,
+            captcha_provider: x.captcha_provider,
+            slow_down_seconds: x.slow_down_seconds,
+            expires_at: x.expires_at.map(|dt| dt.and_utc()),
+            subscription_id: x.subscription_id,
+            created_at: x.created_at.and_utc(),
+            updated_at: x.updated_at.and_utc(),
+            created_by_email: x.created_by_email,
+        })
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionRestrictionRuleHits {
+    id: Uuid,
+    hit_count: u64,
+    last_hit_at: Option<NaiveDateTime>,
+    /// Left out of the list of every rule
+    #[sqlx(default)]
+    recent_hit_samples: Option<serde_json::Value>,
+}
+
+impl TryFrom<SelectionRestrictionRuleHits> for RestrictionRuleHits {
+    type Error = anyhow::Error;
+
+    fn try_from(x: SelectionRestrictionRuleHits) -> anyhow::Result<Self> {
+        Ok(RestrictionRuleHits {
+            rule_id: x.id,
+            hit_count: x.hit_count,
+            last_hit_at: x.last_hit_at.map(|dt| dt.and_utc()),
+            recent_samples: x
+                .recent_hit_samples
+                .map(serde_json::from_value)
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+const SELECT_SUBSCRIPTION_COLUMNS: &str = r#"
+    SELECT
+        id,
+        name,
+        source,
+        format,
+        board_keys,
+        targets,
+        action,
+        captcha_provider,
+        slow_down_seconds,
+        refresh_interval_seconds,
+        expires_after_seconds,
+        enabled,
+        last_refreshed_at,
+        last_error,
+        rule_count,
+        created_at,
+        updated_at,
+        created_by_email
+    FROM user_restriction_subscriptions
+"#;
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionUserRestrictionSubscription {
+    id: Uuid,
+    name: String,
+    source: String,
+    format: String,
+    board_keys: Option<serde_json::Value>,
+    targets: String,
+    action: String,
+    captcha_provider: Option<String>,
+    slow_down_seconds: u32,
+    refresh_interval_seconds: u32,
+    expires_after_seconds: u32,
+    enabled: bool,
+    last_refreshed_at: Option<NaiveDateTime>,
+    last_error: Option<String>,
+    rule_count: u32,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    created_by_email: String,
+}
+
+impl TryFrom<SelectionUserRestrictionSubscription> for UserRestrictionSubscription {
+    type Error = anyhow::Error;
+
+    fn try_from(x: SelectionUserRestrictionSubscription) -> anyhow::Result<Self> {
+        Ok(UserRestrictionSubscription {
+            id: x.id,
+            name: x.name,
+            source: x.source,
+            format: x
+                .format
+                .parse::<RestrictionRuleFormat>()
+                .map_err(anyhow::Error::msg)?,
+            board_keys: x.board_keys.map(serde_json::from_value).transpose()?,
+            targets: RestrictionTarget::parse_list(&x.targets).map_err(anyhow::Error::msg)?,
+            action: x
+                .action
+                .parse::<RestrictionAction>()
+                .map_err(anyhow::Error::msg)?,
+            captcha_provider: x.captcha_provider,
+            slow_down_seconds: x.slow_down_seconds,
+            refresh_interval_seconds: x.refresh_interval_seconds,
+            expires_after_seconds: x.expires_after_seconds,
+            enabled: x.enabled,
+            last_refreshed_at: x.last_refreshed_at.map(|dt| dt.and_utc()),
+            last_error: x.last_error,
+            rule_count: x.rule_count,
+            created_at: x.created_at.and_utc(),
+            updated_at: x.updated_at.and_utc(),
+            created_by_email: x.created_by_email,
+        })
+    }
+}
+
+/// Rows per INSERT of a bulk import, well below the placeholder limit of MySQL
+const INSERT_RULES_CHUNK_SIZE: usize = 1000;
+
+fn board_keys_to_json(board_keys: &Option<Vec<String>>) -> Option<serde_json::Value> {
+    board_keys.as_ref().map(|keys| serde_json::json!(keys))
+}
+
 #[async_trait]
 impl UserRestrictionRepository for UserRestrictionRepositoryImpl {
     async fn get_all_rules(&self) -> anyhow::Result<Vec<UserRestrictionRule>> {
-        let rows = sqlx::query!(
+        let rows = sqlx::query_as!(
+            SelectionUserRestrictionRule,
             r#"
-            SELECT 
-                BIN_TO_UUID(id) as id,
+            SELECT
+                id AS "id: Uuid",
                 name,
                 rule_type,
                 rule_value,
+                board_keys AS "board_keys: serde_json::Value",
+                targets,
+                action,
+                captcha_provider,
+                slow_down_seconds,
                 expires_at,
+                subscription_id AS "subscription_id: Uuid",
                 created_at,
                 updated_at,
                 created_by_email
@@ -51,65 +247,45 @@ impl UserRestrictionRepository for UserRestrictionRepositoryImpl {
         .fetch_all(&self.pool)
         .await?;
 
-        let mut rules = Vec::new();
-        for row in rows {
-            if let Some(id) = row.id {
-                let rule_type = row
-                    .rule_type
-                    .parse::<RestrictionRuleType>()
-                    .map_err(|e| anyhow::anyhow!("Invalid rule type '{}': {}", row.rule_type, e))?;
-
-                rules.push(UserRestrictionRule {
-                    id: Uuid::parse_str(&id)?,
-                    name: row.name,
-                    rule_type,
-                    rule_value: row.rule_value,
-                    expires_at: row.expires_at.map(|dt| dt.and_utc()),
-                    created_at: row.created_at.and_utc(),
-                    updated_at: row.updated_at.and_utc(),
-                    created_by_email: row.created_by_email,
-                });
-            }
-        }
-
-        Ok(rules)
+        rows.into_iter()
+            .map(UserRestrictionRule::try_from)
+            .collect()
     }
 
     async fn create_rule(
         &self,
         input: CreateUserRestrictionRuleInput,
     ) -> anyhow::Result<UserRestrictionRule> {
-        let id = Uuid::now_v7();
         let now = chrono::Utc::now().naive_utc();
+        let rule = input.into_rule(Uuid::now_v7(), now.and_utc());
 
         sqlx::query!(
             r#"
-            INSERT INTO user_restriction_rules 
-            (id, name, rule_type, rule_value, expires_at, created_at, updated_at, created_by_email)
-            VALUES (UUID_TO_BIN(?), ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO user_restriction_rules
+            (id, name, rule_type, rule_value, board_keys, targets, action, captcha_provider,
+             slow_down_seconds, expires_at, subscription_id, created_at, updated_at,
+             created_by_email)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
-            id.to_string(),
-            input.name,
-            input.rule_type.as_str(),
-            input.rule_value,
-            input.expires_at.map(|dt| dt.naive_utc()),
+            rule.id,
+            rule.name,
+            rule.rule_type.as_str(),
+            rule.rule_value,
+            board_keys_to_json(&rule.board_keys),
+            RestrictionTarget::join_list(&rule.targets),
+            rule.action.as_str(),
+            rule.captcha_provider,
+            rule.slow_down_seconds,
+            rule.expires_at.map(|dt| dt.naive_utc()),
+            rule.subscription_id,
             now,
             now,
-            input.created_by_email
+            rule.created_by_email,
         )
         .execute(&self.pool)
         .await?;
 
-        Ok(UserRestrictionRule {
-            id,
-            name: input.name,
-            rule_type: input.rule_type,
-            rule_value: input.rule_value,
-            expires_at: input.expires_at,
-            created_at: now.and_utc(),
-            updated_at: now.and_utc(),
-            created_by_email: input.created_by_email,
-        })
+        Ok(rule)
     }
 
     async fn update_rule(&self, input: UpdateUserRestrictionRuleInput) -> anyhow::Result<()> {
@@ -119,24 +295,26 @@ impl UserRestrictionRepository for UserRestrictionRepositoryImpl {
             .get_rule_by_id(input.id)
             .await?
             .ok_or_else(|| anyhow::anyhow!("restriction rule not found: {}", input.id))?;
-
-        let name = input.name.unwrap_or(current.name);
-        let rule_type = input.rule_type.unwrap_or(current.rule_type);
-        let rule_value = input.rule_value.unwrap_or(current.rule_value);
-        let expires_at = input.expires_at.unwrap_or(current.expires_at);
+        let rule = input.apply(current);
 
         sqlx::query!(
             r#"
             UPDATE user_restriction_rules
-            SET name = ?, rule_type = ?, rule_value = ?, expires_at = ?, updated_at = ?
-            WHERE id = UUID_TO_BIN(?)
+            SET name = ?, rule_type = ?, rule_value = ?, board_keys = ?, targets = ?, action = ?,
+                captcha_provider = ?, slow_down_seconds = ?, expires_at = ?, updated_at = ?
+            WHERE id = ?
             "#,
-            name,
-            rule_type.as_str(),
-            rule_value,
-            expires_at.map(|dt| dt.naive_utc()),
+            rule.name,
+            rule.rule_type.as_str(),
+            rule.rule_value,
+            board_keys_to_json(&rule.board_keys),
+            RestrictionTarget::join_list(&rule.targets),
+            rule.action.as_str(),
+            rule.captcha_provider,
+            rule.slow_down_seconds,
+            rule.expires_at.map(|dt| dt.naive_utc()),
             now,
-            input.id.to_string()
+            rule.id,
         )
         .execute(&self.pool)
         .await?;
@@ -145,57 +323,222 @@ impl UserRestrictionRepository for UserRestrictionRepositoryImpl {
     }
 
     async fn delete_rule(&self, id: Uuid) -> anyhow::Result<()> {
-        sqlx::query!(
-            "DELETE FROM user_restriction_rules WHERE id = UUID_TO_BIN(?)",
-            id.to_string()
-        )
-        .execute(&self.pool)
-        .await?;
+        sqlx::query!("DELETE FROM user_restriction_rules WHERE id = ?", id)
+            .execute(&self.pool)
+            .await?;
 
         Ok(())
     }
 
     async fn get_rule_by_id(&self, id: Uuid) -> anyhow::Result<Option<UserRestrictionRule>> {
-        let row = sqlx::query!(
+        let row = sqlx::query_as!(
+            SelectionUserRestrictionRule,
             r#"
-            SELECT 
-                BIN_TO_UUID(id) as id,
+            SELECT
+                id AS "id: Uuid",
                 name,
                 rule_type,
                 rule_value,
+                board_keys AS "board_keys: serde_json::Value",
+                targets,
+                action,
+                captcha_provider,
+                slow_down_seconds,
                 expires_at,
+                subscription_id AS "subscription_id: Uuid",
                 created_at,
                 updated_at,
                 created_by_email
             FROM user_restriction_rules
-            WHERE id = UUID_TO_BIN(?)
+            WHERE id = ?
             "#,
-            id.to_string()
+            id
         )
         .fetch_optional(&self.pool)
         .await?;
 
-        if let Some(row) = row
-            && let Some(id_str) = row.id
-        {
-            let rule_type = row
-                .rule_type
-                .parse::<RestrictionRuleType>()
-                .map_err(|e| anyhow::anyhow!("Invalid rule type '{}': {}", row.rule_type, e))?;
+        row.map(UserRestrictionRule::try_from).transpose()
+    }
 
-            return Ok(Some(UserRestrictionRule {
-                id: Uuid::parse_str(&id_str)?,
-                name: row.name,
-                rule_type,
-                rule_value: row.rule_value,
-                expires_at: row.expires_at.map(|dt| dt.and_utc()),
-                created_at: row.created_at.and_utc(),
-                updated_at: row.updated_at.and_utc(),
-                created_by_email: row.created_by_email,
-            }));
+    async fn get_all_rule_hits(&self) -> anyhow::Result<Vec<RestrictionRuleHits>> {
+        let rows = sqlx::query_as::<_, SelectionRestrictionRuleHits>(
+            r#"
+            SELECT id, hit_count, last_hit_at
+            FROM user_restriction_rules
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(RestrictionRuleHits::try_from)
+            .collect()
+    }
+
+    async fn get_rule_hits(&self, id: Uuid) -> anyhow::Result<Option<RestrictionRuleHits>> {
+        let row = sqlx::query_as::<_, SelectionRestrictionRuleHits>(
+            r#"
+            SELECT id, hit_count, last_hit_at, recent_hit_samples
+            FROM user_restriction_rules
+            WHERE id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(RestrictionRuleHits::try_from).transpose()
+    }
+
+    async fn create_rules(
+        &self,
+        inputs: Vec<CreateUserRestrictionRuleInput>,
+    ) -> anyhow::Result<Vec<UserRestrictionRule>> {
+        let now = chrono::Utc::now().naive_utc();
+        let rules = inputs
+            .into_iter()
+            .map(|input| input.into_rule(Uuid::now_v7(), now.and_utc()))
+            .collect::<Vec<_>>();
+
+        let mut tx = self.pool.begin().await?;
+        for chunk in rules.chunks(INSERT_RULES_CHUNK_SIZE) {
+            let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+                r#"
+                INSERT INTO user_restriction_rules
+                (id, name, rule_type, rule_value, board_keys, targets, action, captcha_provider,
+                 slow_down_seconds, expires_at, subscription_id, created_at, updated_at,
+                 created_by_email)
+                "#,
+            );
+            builder.push_values(chunk, |mut b, rule| {
+                b.push_bind(rule.id)
+                    .push_bind(&rule.name)
+                    .push_bind(rule.rule_type.as_str())
+                    .push_bind(&rule.rule_value)
+                    .push_bind(board_keys_to_json(&rule.board_keys))
+                    .push_bind(RestrictionTarget::join_list(&rule.targets))
+                    .push_bind(rule.action.as_str())
+                    .push_bind(&rule.captcha_provider)
+                    .push_bind(rule.slow_down_seconds)
+                    .push_bind(rule.expires_at.map(|dt| dt.naive_utc()))
+                    .push_bind(rule.subscription_id)
+                    .push_bind(now)
+                    .push_bind(now)
+                    .push_bind(&rule.created_by_email);
+            });
+            builder.build().execute(&mut *tx).await?;
         }
+        tx.commit().await?;
 
-        Ok(None)
+        Ok(rules)
+    }
+
+    async fn get_all_subscriptions(&self) -> anyhow::Result<Vec<UserRestrictionSubscription>> {
+        let rows = sqlx::query_as::<_, SelectionUserRestrictionSubscription>(&format!(
+            "{SELECT_SUBSCRIPTION_COLUMNS} ORDER BY name"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(UserRestrictionSubscription::try_from)
+            .collect()
+    }
+
+    async fn get_subscription_by_id(
+        &self,
+        id: Uuid,
+    ) -> anyhow::Result<Option<UserRestrictionSubscription>> {
+        let row = sqlx::query_as::<_, SelectionUserRestrictionSubscription>(&format!(
+            "{SELECT_SUBSCRIPTION_COLUMNS} WHERE id = ?"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(UserRestrictionSubscription::try_from).transpose()
+    }
+
+    async fn create_subscription(
+        &self,
+        subscription: &UserRestrictionSubscription,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_restriction_subscriptions
+            (id, name, source, format, board_keys, targets, action, captcha_provider,
+             slow_down_seconds, refresh_interval_seconds, expires_after_seconds, enabled,
+             created_at, updated_at, created_by_email)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(subscription.id)
+        .bind(&subscription.name)
+        .bind(&subscription.source)
+        .bind(subscription.format.as_str())
+        .bind(board_keys_to_json(&subscription.board_keys))
+        .bind(RestrictionTarget::join_list(&subscription.targets))
+        .bind(subscription.action.as_str())
+        .bind(&subscription.captcha_provider)
+        .bind(subscription.slow_down_seconds)
+        .bind(subscription.refresh_interval_seconds)
+        .bind(subscription.expires_after_seconds)
+        .bind(subscription.enabled)
+        .bind(subscription.created_at.naive_utc())
+        .bind(subscription.updated_at.naive_utc())
+        .bind(&subscription.created_by_email)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_subscription(
+        &self,
+        subscription: &UserRestrictionSubscription,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE user_restriction_subscriptions
+            SET name = ?, source = ?, format = ?, board_keys = ?, targets = ?, action = ?,
+                captcha_provider = ?, slow_down_seconds = ?, refresh_interval_seconds = ?,
+                expires_after_seconds = ?, enabled = ?, last_refreshed_at = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&subscription.name)
+        .bind(&subscription.source)
+        .bind(subscription.format.as_str())
+        .bind(board_keys_to_json(&subscription.board_keys))
+        .bind(RestrictionTarget::join_list(&subscription.targets))
+        .bind(subscription.action.as_str())
+        .bind(&subscription.captcha_provider)
+        .bind(subscription.slow_down_seconds)
+        .bind(subscription.refresh_interval_seconds)
+        .bind(subscription.expires_after_seconds)
+        .bind(subscription.enabled)
+        .bind(subscription.last_refreshed_at.map(|dt| dt.naive_utc()))
+        .bind(chrono::Utc::now().naive_utc())
+        .bind(subscription.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_subscription(&self, id: Uuid) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM user_restriction_rules WHERE subscription_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM user_restriction_subscriptions WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
     }
 }
 
@@ -0,0 +1,200 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get, patch, post, put},
+};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    auth::AdminIdentity,
+    error::ApiError,
+    models::{
+        AdminRole, AdminScope, AdminUserAssignment, AssignAdminRoleInput, CreateAdminRoleInput,
+        CurrentAdmin, UpdateAdminRoleInput,
+    },
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/admin_roles", get(get_admin_roles))
+        .route("/admin_roles", post(create_admin_role))
+        .route("/admin_roles/{roleId}", patch(update_admin_role))
+        .route("/admin_roles/{roleId}", delete(delete_admin_role))
+        .route("/admin_users", get(get_admin_users))
+        .route("/admin_users", put(assign_admin_role))
+        .route("/admin_users/me", get(get_current_admin))
+        .route("/admin_users/{adminUserId}", delete(delete_admin_user))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin_roles/",
+    responses(
+        (status = 200, description = "List admin roles successfully", body = Vec<AdminRole>),
+    )
+)]
+pub async fn get_admin_roles(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+) -> Result<Json<Vec<AdminRole>>, ApiError> {
+    identity.require(AdminScope::RolesRead)?;
+    let roles = state.services.admin_role.get_roles().await?;
+    Ok(Json(roles))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin_roles/",
+    responses(
+        (status = 200, description = "Create admin role successfully", body = AdminRole),
+    ),
+    request_body = CreateAdminRoleInput
+)]
+pub async fn create_admin_role(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Json(body): Json<CreateAdminRoleInput>,
+) -> Result<Json<AdminRole>, ApiError> {
+    identity.require(AdminScope::RolesWrite)?;
+    identity.require_all_boards()?;
+    let role = state
+        .services
+        .admin_role
+        .create_role(&identity, body)
+        .await?;
+    Ok(Json(role))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/admin_roles/{role_id}/",
+    responses(
+        (status = 200, description = "Update admin role successfully", body = AdminRole),
+    ),
+    params(
+        ("role_id" = Uuid, Path, description = "Admin role ID"),
+    ),
+    request_body = UpdateAdminRoleInput
+)]
+pub async fn update_admin_role(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Path(role_id): Path<Uuid>,
+    Json(body): Json<UpdateAdminRoleInput>,
+) -> Result<Json<AdminRole>, ApiError> {
+    identity.require(AdminScope::RolesWrite)?;
+    identity.require_all_boards()?;
+    let role = state
+        .services
+        .admin_role
+        .update_role(&identity, role_id, body)
+        .await?;
+    Ok(Json(role))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin_roles/{role_id}/",
+    responses(
+        (status = 200, description = "Delete admin role successfully"),
+    ),
+    params(
+        ("role_id" = Uuid, Path, description = "Admin role ID"),
+    ),
+)]
+pub async fn delete_admin_role(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Path(role_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::RolesWrite)?;
+    identity.require_all_boards()?;
+    state
+        .services
+        .admin_role
+        .delete_role(&identity, role_id)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin_users/",
+    responses(
+        (status = 200, description = "List admin role assignments successfully", body = Vec<AdminUserAssignment>),
+    )
+)]
+pub async fn get_admin_users(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+) -> Result<Json<Vec<AdminUserAssignment>>, ApiError> {
+    identity.require(AdminScope::RolesRead)?;
+    let assignments = state.services.admin_role.get_assignments().await?;
+    Ok(Json(assignments))
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin_users/",
+    responses(
+        (status = 200, description = "Assign admin role successfully", body = AdminUserAssignment),
+    ),
+    request_body = AssignAdminRoleInput
+)]
+pub async fn assign_admin_role(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Json(body): Json<AssignAdminRoleInput>,
+) -> Result<Json<AdminUserAssignment>, ApiError> {
+    identity.require(AdminScope::RolesWrite)?;
+    identity.require_all_boards()?;
+    let assignment = state
+        .services
+        .admin_role
+        .assign_role(&identity, body)
+        .await?;
+    Ok(Json(assignment))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin_users/me",
+    responses(
+        (status = 200, description = "Get current admin successfully", body = CurrentAdmin),
+    )
+)]
+pub async fn get_current_admin(identity: AdminIdentity) -> Json<CurrentAdmin> {
+    Json(CurrentAdmin {
+        scopes: identity.scopes.to_vec(),
+        board_ids: identity.boards.ids(),
+        email: identity.email,
+        username: identity.username,
+    })
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin_users/{admin_user_id}/",
+    responses(
+        (status = 200, description = "Delete admin role assignment successfully"),
+    ),
+    params(
+        ("admin_user_id" = Uuid, Path, description = "Admin user ID"),
+    ),
+)]
+pub async fn delete_admin_user(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Path(admin_user_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::RolesWrite)?;
+    identity.require_all_boards()?;
+    state
+        .services
+        .admin_role
+        .delete_assignment(&identity, admin_user_id)
+        .await?;
+    Ok(StatusCode::OK)
+}
@@ -12,7 +12,7 @@ use crate::{
     AppState,
     auth::AdminIdentity,
     error::ApiError,
-    models::{Res, Thread},
+    models::{AdminScope, Res, Thread},
     repository::admin_archive_repository::ArchivedResUpdate,
 };
 
@@ -71,6 +71,7 @@ pub struct GetArchivedThreadsQuery {
 )]
 pub async fn get_archived_threads(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path(board_key): Path<String>,
     Query(GetArchivedThreadsQuery {
         keyword,
@@ -80,6 +81,7 @@ pub async fn get_archived_threads(
         limit,
     }): Query<GetArchivedThreadsQuery>,
 ) -> Result<Json<Vec<Thread>>, ApiError> {
+    identity.require_board_key(&board_key)?;
     let threads = state
         .services
         .archive
@@ -111,8 +113,10 @@ pub async fn get_archived_threads(
 )]
 pub async fn get_archived_thread(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path((board_key, thread_id)): Path<(String, u64)>,
 ) -> Result<Json<Thread>, ApiError> {
+    identity.require_board_key(&board_key)?;
     let thread = state
         .services
         .archive
@@ -135,8 +139,10 @@ pub async fn get_archived_thread(
 )]
 pub async fn get_archived_responses(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path((board_key, thread_id)): Path<(String, u64)>,
 ) -> Result<Json<Vec<Res>>, ApiError> {
+    identity.require_board_key(&board_key)?;
     let responses = state
         .services
         .archive
@@ -158,8 +164,10 @@ pub async fn get_archived_responses(
 )]
 pub async fn get_dat_archived_thread(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path((board_key, thread_number)): Path<(String, u64)>,
 ) -> Result<Json<crate::repository::admin_archive_repository::ArchivedThread>, ApiError> {
+    identity.require_board_key(&board_key)?;
     let thread = state
         .services
         .archive
@@ -181,8 +189,10 @@ pub async fn get_dat_archived_thread(
 )]
 pub async fn get_admin_dat_archived_thread(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path((board_key, thread_number)): Path<(String, u64)>,
 ) -> Result<Json<crate::repository::admin_archive_repository::ArchivedAdminThread>, ApiError> {
+    identity.require_board_key(&board_key)?;
     let thread = state
         .services
         .archive
@@ -209,6 +219,8 @@ pub async fn update_archived_res(
     Path((board_key, thread_number)): Path<(String, u64)>,
     Json(body): Json<Vec<ArchivedResUpdate>>,
 ) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::ArchivesWrite)?;
+    identity.require_board_key(&board_key)?;
     state
         .services
         .archive
@@ -234,6 +246,8 @@ pub async fn delete_archived_res(
     identity: AdminIdentity,
     Path((board_key, thread_number, res_order)): Path<(String, u64, u64)>,
 ) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::ArchivesDelete)?;
+    identity.require_board_key(&board_key)?;
     state
         .services
         .archive
@@ -258,6 +272,8 @@ pub async fn delete_archived_thread(
     identity: AdminIdentity,
     Path((board_key, thread_number)): Path<(String, u64)>,
 ) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::ArchivesDelete)?;
+    identity.require_board_key(&board_key)?;
     state
         .services
         .archive
@@ -0,0 +1,36 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    routing::get,
+};
+
+use crate::{
+    AppState,
+    auth::AdminIdentity,
+    error::ApiError,
+    models::{AdminScope, ListAuditLogsQuery, PaginatedAuditLogs},
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/audit-logs", get(list_audit_logs))
+}
+
+#[utoipa::path(
+    get,
+    path = "/audit-logs",
+    responses(
+        (status = 200, description = "List audit logs successfully", body = PaginatedAuditLogs),
+    ),
+    params(
+        ListAuditLogsQuery,
+    ),
+)]
+pub async fn list_audit_logs(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Query(query): Query<ListAuditLogsQuery>,
+) -> Result<Json<PaginatedAuditLogs>, ApiError> {
+    identity.require(AdminScope::AuditLogsRead)?;
+    let result = state.services.audit_log.list_audit_logs(query).await?;
+    Ok(Json(result))
+}
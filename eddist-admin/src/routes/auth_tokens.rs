@@ -10,7 +10,10 @@ use crate::{
     AppState,
     auth::AdminIdentity,
     error::ApiError,
-    models::{DeleteAuthedTokenInput, ListAuthedTokensQuery, PaginatedAuthedTokens},
+    models::{
+        AdminScope, DeleteAuthedTokenInput, ListAuthedTokensQuery, PaginatedAuthedTokens,
+        SuspendAuthedTokenInput,
+    },
 };
 
 pub fn routes() -> Router<AppState> {
@@ -21,6 +24,10 @@ pub fn routes() -> Router<AppState> {
             "/authed_tokens/{authedTokenId}",
             delete(delete_authed_token),
         )
+        .route(
+            "/authed_tokens/{authedTokenId}/suspend",
+            post(suspend_authed_token),
+        )
         .route(
             "/authed_tokens/{authedTokenId}/require-reauth",
             post(require_reauth_token).delete(clear_require_reauth_token),
@@ -39,12 +46,15 @@ pub fn routes() -> Router<AppState> {
 )]
 pub async fn list_authed_tokens(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Query(query): Query<ListAuthedTokensQuery>,
 ) -> Result<Json<PaginatedAuthedTokens>, ApiError> {
+    identity.require(AdminScope::TokensRead)?;
+    let board_ids = identity.board_filter(None)?;
     let result = state
         .services
         .authed_token
-        .list_authed_tokens(query)
+        .list_authed_tokens(query, board_ids)
         .await?;
     Ok(Json(result))
 }
@@ -61,12 +71,14 @@ pub async fn list_authed_tokens(
 )]
 pub async fn get_authed_token(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path(authed_token_id): Path<Uuid>,
 ) -> Result<Json<crate::models::AuthedToken>, ApiError> {
+    identity.require(AdminScope::TokensRead)?;
     let authed_token = state
         .services
         .authed_token
-        .get_authed_token(authed_token_id)
+        .get_authed_token(&identity, authed_token_id)
         .await?;
     Ok(Json(authed_token))
 }
@@ -88,6 +100,7 @@ pub async fn delete_authed_token(
     Path(authed_token_id): Path<Uuid>,
     Query(options): Query<DeleteAuthedTokenInput>,
 ) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::TokensRevoke)?;
     state
         .services
         .authed_token
@@ -96,6 +109,35 @@ pub async fn delete_authed_token(
     Ok(StatusCode::OK)
 }
 
+#[utoipa::path(
+    post,
+    path = "/authed_tokens/{authed_token_id}/suspend",
+    responses(
+        (status = 204, description = "Suspend authed token successfully"),
+    ),
+    params(
+        ("authed_token_id" = Uuid, Path, description = "Authed token ID"),
+    ),
+    request_body = SuspendAuthedTokenInput
+)]
+pub async fn suspend_authed_token(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Path(authed_token_id): Path<Uuid>,
+    Json(body): Json<SuspendAuthedTokenInput>,
+) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::TokensSuspend)?;
+    if body.ttl_seconds == 0 {
+        return Err(ApiError::bad_request("ttl_seconds must be greater than 0"));
+    }
+    state
+        .services
+        .authed_token
+        .suspend_authed_token(&identity, authed_token_id, body.ttl_seconds)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 #[utoipa::path(
     post,
     path = "/authed_tokens/{authed_token_id}/require-reauth",
@@ -108,12 +150,14 @@ pub async fn delete_authed_token(
 )]
 pub async fn require_reauth_token(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path(authed_token_id): Path<Uuid>,
 ) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::TokensRevoke)?;
     state
         .services
         .authed_token
-        .set_require_reauth(authed_token_id)
+        .set_require_reauth(&identity, authed_token_id)
         .await?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -130,12 +174,14 @@ pub async fn require_reauth_token(
 )]
 pub async fn clear_require_reauth_token(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path(authed_token_id): Path<Uuid>,
 ) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::TokensRevoke)?;
     state
         .services
         .authed_token
-        .clear_require_reauth(authed_token_id)
+        .clear_require_reauth(&identity, authed_token_id)
         .await?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -9,7 +9,7 @@ use crate::{
     AppState,
     auth::AdminIdentity,
     error::ApiError,
-    models::{Board, BoardInfo, CreateBoardInput, EditBoardInput},
+    models::{AdminScope, Board, BoardInfo, CreateBoardInput, EditBoardInput},
 };
 
 pub fn routes() -> Router<AppState> {
@@ -28,8 +28,12 @@ pub fn routes() -> Router<AppState> {
         (status = 200, description = "List boards successfully", body = Vec<Board>),
     )
 )]
-pub async fn get_boards(State(state): State<AppState>) -> Result<Json<Vec<Board>>, ApiError> {
-    let boards = state.services.board.get_boards(None).await?;
+pub async fn get_boards(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+) -> Result<Json<Vec<Board>>, ApiError> {
+    let mut boards = state.services.board.get_boards(None).await?;
+    boards.retain(|board| identity.boards.allows_id(board.id));
     Ok(Json(boards))
 }
 
@@ -46,8 +50,10 @@ pub async fn get_boards(State(state): State<AppState>) -> Result<Json<Vec<Board>
 )]
 pub async fn get_board(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path(board_key): Path<String>,
 ) -> Result<Json<Board>, ApiError> {
+    identity.require_board_key(&board_key)?;
     let board = state
         .services
         .board
@@ -72,8 +78,10 @@ pub async fn get_board(
 ]
 pub async fn get_board_info(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path(board_key): Path<String>,
 ) -> Result<Json<BoardInfo>, ApiError> {
+    identity.require_board_key(&board_key)?;
     let board = state
         .services
         .board
@@ -100,6 +108,8 @@ pub async fn create_board(
     identity: AdminIdentity,
     Json(body): Json<CreateBoardInput>,
 ) -> Result<Json<Board>, ApiError> {
+    identity.require(AdminScope::BoardsWrite)?;
+    identity.require_all_boards()?;
     if validate_board_key(&body.board_key).is_err() {
         return Err(ApiError::bad_request(
             "board_key must be ascii lower alphabetic or numeric",
@@ -127,6 +137,8 @@ pub async fn edit_board(
     Path(board_key): Path<String>,
     Json(body): Json<EditBoardInput>,
 ) -> Result<Json<Board>, ApiError> {
+    identity.require(AdminScope::BoardsWrite)?;
+    identity.require_board_key(&board_key)?;
     let board = state
         .services
         .board
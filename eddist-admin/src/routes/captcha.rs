@@ -10,7 +10,7 @@ use crate::{
     AppState,
     auth::AdminIdentity,
     error::ApiError,
-    models::{CaptchaConfig, CreateCaptchaConfigInput, UpdateCaptchaConfigInput},
+    models::{AdminScope, CaptchaConfig, CreateCaptchaConfigInput, UpdateCaptchaConfigInput},
 };
 
 pub fn routes() -> Router<AppState> {
@@ -32,7 +32,9 @@ pub fn routes() -> Router<AppState> {
 )]
 pub async fn list_captcha_configs(
     State(state): State<AppState>,
+    identity: AdminIdentity,
 ) -> Result<Json<Vec<CaptchaConfig>>, ApiError> {
+    identity.require(AdminScope::SettingsRead)?;
     let configs = state.services.content_admin.list_captcha_configs().await?;
     Ok(Json(configs))
 }
@@ -51,8 +53,10 @@ pub async fn list_captcha_configs(
 )]
 pub async fn get_captcha_config(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path(id): Path<Uuid>,
 ) -> Result<Json<CaptchaConfig>, ApiError> {
+    identity.require(AdminScope::SettingsRead)?;
     let config = state
         .services
         .content_admin
@@ -78,6 +82,7 @@ pub async fn create_captcha_config(
     identity: AdminIdentity,
     Json(input): Json<CreateCaptchaConfigInput>,
 ) -> Result<(StatusCode, Json<CaptchaConfig>), ApiError> {
+    identity.require(AdminScope::SettingsWrite)?;
     let config = state
         .services
         .content_admin
@@ -107,6 +112,7 @@ pub async fn update_captcha_config(
     Path(id): Path<Uuid>,
     Json(input): Json<UpdateCaptchaConfigInput>,
 ) -> Result<Json<CaptchaConfig>, ApiError> {
+    identity.require(AdminScope::SettingsWrite)?;
     let config = state
         .services
         .content_admin
@@ -133,6 +139,7 @@ pub async fn delete_captcha_config(
     identity: AdminIdentity,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::SettingsWrite)?;
     state
         .services
         .content_admin
@@ -10,7 +10,10 @@ use crate::{
     AppState,
     auth::AdminIdentity,
     error::ApiError,
-    models::idp::{CreateIdpInput, Idp, UpdateIdpInput},
+    models::{
+        AdminScope,
+        idp::{CreateIdpInput, Idp, UpdateIdpInput},
+    },
 };
 
 pub fn routes() -> Router<AppState> {
@@ -30,7 +33,11 @@ pub fn routes() -> Router<AppState> {
         (status = 200, description = "List all IdPs", body = Vec<Idp>),
     )
 )]
-pub async fn list_idps(State(state): State<AppState>) -> Result<Json<Vec<Idp>>, ApiError> {
+pub async fn list_idps(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+) -> Result<Json<Vec<Idp>>, ApiError> {
+    identity.require(AdminScope::SettingsRead)?;
     let idps = state.services.content_admin.list_idps().await?;
     Ok(Json(idps))
 }
@@ -49,8 +56,10 @@ pub async fn list_idps(State(state): State<AppState>) -> Result<Json<Vec<Idp>>,
 )]
 pub async fn get_idp(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path(id): Path<Uuid>,
 ) -> Result<Json<Idp>, ApiError> {
+    identity.require(AdminScope::SettingsRead)?;
     let idp = state
         .services
         .content_admin
@@ -75,6 +84,7 @@ pub async fn create_idp(
     identity: AdminIdentity,
     Json(input): Json<CreateIdpInput>,
 ) -> Result<(StatusCode, Json<Idp>), ApiError> {
+    identity.require(AdminScope::SettingsWrite)?;
     let idp = state
         .services
         .content_admin
@@ -103,6 +113,7 @@ pub async fn update_idp(
     Path(id): Path<Uuid>,
     Json(input): Json<UpdateIdpInput>,
 ) -> Result<Json<Idp>, ApiError> {
+    identity.require(AdminScope::SettingsWrite)?;
     let idp = state
         .services
         .content_admin
@@ -128,6 +139,7 @@ pub async fn delete_idp(
     identity: AdminIdentity,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::SettingsWrite)?;
     state
         .services
         .content_admin
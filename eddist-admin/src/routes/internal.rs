@@ -1,10 +1,13 @@
-use axum::{Json, Router, extract::State, http::StatusCode, routing::post};
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::post,
+};
 use serde::Deserialize;
 use uuid::Uuid;
 
-use crate::{AppState, error::ApiError};
-
-use super::auth_tokens::{clear_require_reauth_token, require_reauth_token};
+use crate::{AppState, auth::AdminIdentity, error::ApiError};
 
 pub fn create_internal_routes() -> Router<AppState> {
     Router::new()
@@ -33,7 +36,11 @@ pub async fn suspend_authed_token(
     state
         .services
         .authed_token
-        .suspend_authed_token(input.authed_token_id, input.ttl_seconds)
+        .suspend_authed_token(
+            &AdminIdentity::system(),
+            input.authed_token_id,
+            input.ttl_seconds,
+        )
         .await?;
 
     Ok(StatusCode::NO_CONTENT)
@@ -48,17 +55,35 @@ pub async fn revoke_authed_token(
     State(state): State<AppState>,
     Json(input): Json<RevokeAuthedTokenInput>,
 ) -> Result<StatusCode, ApiError> {
-    // Internal routes don't have a session-based actor; use a system placeholder.
-    let system_actor = crate::auth::AdminIdentity {
-        sub: "system".to_string(),
-        email: "system@internal".to_string(),
-        username: "system".to_string(),
-    };
     state
         .services
         .authed_token
-        .revoke_authed_token(&system_actor, input.authed_token_id)
+        .revoke_authed_token(&AdminIdentity::system(), input.authed_token_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn require_reauth_token(
+    State(state): State<AppState>,
+    Path(authed_token_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .services
+        .authed_token
+        .set_require_reauth(&AdminIdentity::system(), authed_token_id)
         .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
 
+pub async fn clear_require_reauth_token(
+    State(state): State<AppState>,
+    Path(authed_token_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .services
+        .authed_token
+        .clear_require_reauth(&AdminIdentity::system(), authed_token_id)
+        .await?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -2,14 +2,19 @@ use axum::Router;
 
 use crate::AppState;
 
+pub mod admin_roles;
 pub mod archives;
+pub mod audit_logs;
 pub mod auth_tokens;
 pub mod boards;
 pub mod captcha;
 pub mod idps;
 pub mod internal;
 pub mod moderation;
+pub mod moderation_queue;
+pub mod moderation_rules;
 pub mod notices;
+pub mod search;
 pub mod server_settings;
 pub mod terms;
 pub mod threads;
@@ -21,14 +26,19 @@ pub fn create_internal_routes() -> Router<AppState> {
 
 pub fn create_api_routes() -> Router<AppState> {
     Router::new()
+        .merge(admin_roles::routes())
         .merge(boards::routes())
         .merge(threads::routes())
         .merge(archives::routes())
+        .merge(audit_logs::routes())
         .merge(auth_tokens::routes())
         .merge(captcha::routes())
         .merge(idps::routes())
         .merge(moderation::routes())
+        .merge(moderation_queue::routes())
+        .merge(moderation_rules::routes())
         .merge(notices::routes())
+        .merge(search::routes())
         .merge(server_settings::routes())
         .merge(terms::routes())
         .merge(users::routes())
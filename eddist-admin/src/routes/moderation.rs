@@ -1,10 +1,22 @@
 use axum::{
     Json, Router,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     routing::{delete, get, patch, post},
 };
-use eddist_core::domain::user_restriction::{UpdateUserRestrictionRuleInput, UserRestrictionRule};
+use eddist_core::{
+    domain::{
+        user_restriction::{
+            CreateUserRestrictionRuleInput, RestrictionTarget, UpdateUserRestrictionRuleInput,
+            UserRestrictionRule,
+        },
+        user_restriction_bulk::{
+            CreateUserRestrictionSubscriptionInput, RestrictionImportDefaults,
+            UpdateUserRestrictionSubscriptionInput, UserRestrictionSubscription,
+        },
+    },
+    user_restriction_hits::RestrictionRuleHits,
+};
 use uuid::Uuid;
 
 use crate::{
@@ -12,9 +24,13 @@ use crate::{
     auth::AdminIdentity,
     error::ApiError,
     models::{
-        Cap, CreateRestrictionRuleRequest, CreationCapInput, CreationNgWordInput, NgWord,
-        UpdateCapInput, UpdateNgWordInput, UpdateRestrictionRuleRequest,
+        AdminScope, Cap, CreateRestrictionRuleRequest, CreateRestrictionSubscriptionRequest,
+        CreationCapInput, CreationNgWordInput, ExportRestrictionRulesQuery,
+        ExportRestrictionRulesResponse, ImportRestrictionRulesRequest, ListRestrictionRulesQuery,
+        NgWord, UpdateCapInput, UpdateNgWordInput, UpdateRestrictionRuleRequest,
+        UpdateRestrictionSubscriptionRequest,
     },
+    services::moderation_service::{RestrictionImportResult, RestrictionRuleWithHits},
 };
 
 pub fn routes() -> Router<AppState> {
@@ -32,7 +48,13 @@ pub fn routes() -> Router<AppState> {
         // User Restrictions
         .route("/restriction_rules", get(get_restriction_rules))
         .route("/restriction_rules", post(create_restriction_rule))
+        .route("/restriction_rules/import", post(import_restriction_rules))
+        .route("/restriction_rules/export", get(export_restriction_rules))
         .route("/restriction_rules/{rule_id}", get(get_restriction_rule))
+        .route(
+            "/restriction_rules/{rule_id}/hits",
+            get(get_restriction_rule_hits),
+        )
         .route(
             "/restriction_rules/{rule_id}",
             patch(update_restriction_rule),
@@ -41,6 +63,23 @@ pub fn routes() -> Router<AppState> {
             "/restriction_rules/{rule_id}",
             delete(delete_restriction_rule),
         )
+        // Blocklist subscriptions
+        .route(
+            "/restriction_subscriptions",
+            get(get_restriction_subscriptions),
+        )
+        .route(
+            "/restriction_subscriptions",
+            post(create_restriction_subscription),
+        )
+        .route(
+            "/restriction_subscriptions/{subscription_id}",
+            patch(update_restriction_subscription),
+        )
+        .route(
+            "/restriction_subscriptions/{subscription_id}",
+            delete(delete_restriction_subscription),
+        )
 }
 
 // NgWord handlers
@@ -51,7 +90,11 @@ pub fn routes() -> Router<AppState> {
         (status = 200, description = "List ng words successfully", body = Vec<NgWord>),
     )
 )]
-pub async fn get_ng_words(State(state): State<AppState>) -> Result<Json<Vec<NgWord>>, ApiError> {
+pub async fn get_ng_words(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+) -> Result<Json<Vec<NgWord>>, ApiError> {
+    identity.require(AdminScope::ModerationRead)?;
     let ng_words = state.services.moderation.get_ng_words().await?;
     Ok(Json(ng_words))
 }
@@ -69,6 +112,8 @@ pub async fn create_ng_word(
     identity: AdminIdentity,
     Json(body): Json<CreationNgWordInput>,
 ) -> Result<Json<NgWord>, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    identity.require_all_boards()?;
     let ng_word = state
         .services
         .moderation
@@ -94,6 +139,8 @@ pub async fn update_ng_word(
     Path(ng_word_id): Path<Uuid>,
     Json(body): Json<UpdateNgWordInput>,
 ) -> Result<Json<NgWord>, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    identity.require_all_boards()?;
     let ng_word = state
         .services
         .moderation
@@ -117,6 +164,8 @@ pub async fn delete_ng_word(
     identity: AdminIdentity,
     Path(ng_word_id): Path<Uuid>,
 ) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    identity.require_all_boards()?;
     state
         .services
         .moderation
@@ -133,7 +182,11 @@ pub async fn delete_ng_word(
         (status = 200, description = "List cap words successfully", body = Vec<Cap>),
     )
 )]
-pub async fn get_caps(State(state): State<AppState>) -> Result<Json<Vec<Cap>>, ApiError> {
+pub async fn get_caps(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+) -> Result<Json<Vec<Cap>>, ApiError> {
+    identity.require(AdminScope::ModerationRead)?;
     let caps = state.services.moderation.get_caps().await?;
     Ok(Json(caps))
 }
@@ -151,6 +204,8 @@ pub async fn create_cap(
     identity: AdminIdentity,
     Json(body): Json<CreationCapInput>,
 ) -> Result<Json<Cap>, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    identity.require_all_boards()?;
     let cap = state
         .services
         .moderation
@@ -176,6 +231,8 @@ pub async fn update_cap(
     Path(cap_id): Path<Uuid>,
     Json(body): Json<UpdateCapInput>,
 ) -> Result<Json<Cap>, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    identity.require_all_boards()?;
     let cap = state
         .services
         .moderation
@@ -199,6 +256,8 @@ pub async fn delete_cap(
     identity: AdminIdentity,
     Path(cap_id): Path<Uuid>,
 ) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    identity.require_all_boards()?;
     state
         .services
         .moderation
@@ -211,17 +270,24 @@ pub async fn delete_cap(
 #[utoipa::path(
     get,
     path = "/restriction_rules",
+    params(ListRestrictionRulesQuery),
     responses(
-        (status = 200, description = "List all restriction rules", body = Vec<crate::models::UserRestrictionRuleSchema>)
+        (status = 200, description = "List all restriction rules with their hits", body = Vec<crate::models::UserRestrictionRuleWithHitsSchema>)
     )
 )]
 pub async fn get_restriction_rules(
     State(app_state): State<AppState>,
-) -> Result<Json<Vec<UserRestrictionRule>>, ApiError> {
+    identity: AdminIdentity,
+    Query(query): Query<ListRestrictionRulesQuery>,
+) -> Result<Json<Vec<RestrictionRuleWithHits>>, ApiError> {
+    identity.require(AdminScope::ModerationRead)?;
     let rules = app_state
         .services
         .moderation
-        .get_restriction_rules()
+        .get_restriction_rules(
+            query.sort.unwrap_or_default().into(),
+            !query.ascending.unwrap_or(false),
+        )
         .await?;
     Ok(Json(rules))
 }
@@ -239,15 +305,29 @@ pub async fn create_restriction_rule(
     identity: AdminIdentity,
     Json(req): Json<CreateRestrictionRuleRequest>,
 ) -> Result<(StatusCode, Json<UserRestrictionRule>), ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    identity.require_all_boards()?;
     let rule = app_state
         .services
         .moderation
         .create_restriction_rule(
             &identity,
-            req.name,
-            req.rule_type.into(),
-            req.rule_value,
-            req.expires_at,
+            CreateUserRestrictionRuleInput {
+                name: req.name,
+                rule_type: req.rule_type.into(),
+                rule_value: req.rule_value,
+                board_keys: req.board_keys,
+                targets: req
+                    .targets
+                    .map(|targets| targets.into_iter().map(Into::into).collect())
+                    .unwrap_or_else(|| RestrictionTarget::ALL.to_vec()),
+                action: req.action.map(Into::into).unwrap_or_default(),
+                captcha_provider: req.captcha_provider,
+                slow_down_seconds: req.slow_down_seconds.unwrap_or(0),
+                expires_at: req.expires_at,
+                subscription_id: None,
+                created_by_email: identity.email.clone(),
+            },
         )
         .await?;
     Ok((StatusCode::CREATED, Json(rule)))
@@ -270,11 +350,20 @@ pub async fn update_restriction_rule(
     identity: AdminIdentity,
     Json(req): Json<UpdateRestrictionRuleRequest>,
 ) -> Result<Json<()>, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    identity.require_all_boards()?;
     let input = UpdateUserRestrictionRuleInput {
         id: rule_id,
         name: req.name,
         rule_type: req.rule_type.map(|rt| rt.into()),
         rule_value: req.rule_value,
+        board_keys: req.board_keys,
+        targets: req
+            .targets
+            .map(|targets| targets.into_iter().map(Into::into).collect()),
+        action: req.action.map(Into::into),
+        captcha_provider: req.captcha_provider,
+        slow_down_seconds: req.slow_down_seconds,
         expires_at: req.expires_at,
     };
     app_state
@@ -300,6 +389,8 @@ pub async fn delete_restriction_rule(
     State(app_state): State<AppState>,
     identity: AdminIdentity,
 ) -> Result<Json<()>, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    identity.require_all_boards()?;
     app_state
         .services
         .moderation
@@ -321,7 +412,9 @@ pub async fn delete_restriction_rule(
 pub async fn get_restriction_rule(
     Path(rule_id): Path<Uuid>,
     State(app_state): State<AppState>,
+    identity: AdminIdentity,
 ) -> Result<Json<Option<UserRestrictionRule>>, ApiError> {
+    identity.require(AdminScope::ModerationRead)?;
     let rule = app_state
         .services
         .moderation
@@ -329,3 +422,227 @@ pub async fn get_restriction_rule(
         .await?;
     Ok(Json(rule))
 }
+
+#[utoipa::path(
+    get,
+    path = "/restriction_rules/{rule_id}/hits",
+    responses(
+        (status = 200, description = "Get the hits of a restriction rule and the clients it matched lately", body = crate::models::RestrictionRuleHitsSchema),
+        (status = 404, description = "Restriction rule not found")
+    ),
+    params(
+        ("rule_id" = Uuid, Path, description = "Rule ID")
+    )
+)]
+pub async fn get_restriction_rule_hits(
+    Path(rule_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    identity: AdminIdentity,
+) -> Result<Json<RestrictionRuleHits>, ApiError> {
+    identity.require(AdminScope::ModerationRead)?;
+    let hits = app_state
+        .services
+        .moderation
+        .get_restriction_rule_hits(rule_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Restriction rule not found"))?;
+    Ok(Json(hits))
+}
+
+#[utoipa::path(
+    post,
+    path = "/restriction_rules/import",
+    request_body = ImportRestrictionRulesRequest,
+    responses(
+        (status = 200, description = "Import restriction rules, or validate them with dry_run", body = crate::models::ImportRestrictionRulesResponse)
+    )
+)]
+pub async fn import_restriction_rules(
+    State(app_state): State<AppState>,
+    identity: AdminIdentity,
+    Json(req): Json<ImportRestrictionRulesRequest>,
+) -> Result<Json<RestrictionImportResult>, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    identity.require_all_boards()?;
+    let defaults = RestrictionImportDefaults {
+        name: req.name.unwrap_or_else(|| "import".to_string()),
+        board_keys: req.board_keys,
+        targets: req
+            .targets
+            .map(|targets| targets.into_iter().map(Into::into).collect())
+            .unwrap_or_else(|| RestrictionTarget::ALL.to_vec()),
+        action: req.action.map(Into::into).unwrap_or_default(),
+        captcha_provider: req.captcha_provider,
+        slow_down_seconds: req.slow_down_seconds.unwrap_or(0),
+        expires_at: req.expires_at,
+        subscription_id: None,
+        created_by_email: identity.email.clone(),
+    };
+    let result = app_state
+        .services
+        .moderation
+        .import_restriction_rules(
+            &identity,
+            req.format.into(),
+            &req.content,
+            defaults,
+            req.dry_run.unwrap_or(false),
+        )
+        .await?;
+    Ok(Json(result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/restriction_rules/export",
+    params(ExportRestrictionRulesQuery),
+    responses(
+        (status = 200, description = "Export all restriction rules", body = ExportRestrictionRulesResponse)
+    )
+)]
+pub async fn export_restriction_rules(
+    State(app_state): State<AppState>,
+    identity: AdminIdentity,
+    Query(query): Query<ExportRestrictionRulesQuery>,
+) -> Result<Json<ExportRestrictionRulesResponse>, ApiError> {
+    identity.require(AdminScope::ModerationRead)?;
+    let content = app_state
+        .services
+        .moderation
+        .export_restriction_rules(query.format.into())
+        .await?;
+    Ok(Json(ExportRestrictionRulesResponse {
+        format: query.format,
+        content,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/restriction_subscriptions",
+    responses(
+        (status = 200, description = "List blocklist subscriptions", body = Vec<crate::models::UserRestrictionSubscriptionSchema>)
+    )
+)]
+pub async fn get_restriction_subscriptions(
+    State(app_state): State<AppState>,
+    identity: AdminIdentity,
+) -> Result<Json<Vec<UserRestrictionSubscription>>, ApiError> {
+    identity.require(AdminScope::ModerationRead)?;
+    let subscriptions = app_state
+        .services
+        .moderation
+        .get_restriction_subscriptions()
+        .await?;
+    Ok(Json(subscriptions))
+}
+
+#[utoipa::path(
+    post,
+    path = "/restriction_subscriptions",
+    request_body = CreateRestrictionSubscriptionRequest,
+    responses(
+        (status = 201, description = "Create blocklist subscription", body = crate::models::UserRestrictionSubscriptionSchema)
+    )
+)]
+pub async fn create_restriction_subscription(
+    State(app_state): State<AppState>,
+    identity: AdminIdentity,
+    Json(req): Json<CreateRestrictionSubscriptionRequest>,
+) -> Result<(StatusCode, Json<UserRestrictionSubscription>), ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    identity.require_all_boards()?;
+    let subscription = app_state
+        .services
+        .moderation
+        .create_restriction_subscription(
+            &identity,
+            CreateUserRestrictionSubscriptionInput {
+                name: req.name,
+                source: req.source,
+                format: req.format.into(),
+                board_keys: req.board_keys,
+                targets: req
+                    .targets
+                    .map(|targets| targets.into_iter().map(Into::into).collect())
+                    .unwrap_or_else(|| RestrictionTarget::ALL.to_vec()),
+                action: req.action.map(Into::into).unwrap_or_default(),
+                captcha_provider: req.captcha_provider,
+                slow_down_seconds: req.slow_down_seconds.unwrap_or(0),
+                refresh_interval_seconds: req.refresh_interval_seconds,
+                expires_after_seconds: req.expires_after_seconds,
+                enabled: req.enabled.unwrap_or(true),
+                created_by_email: identity.email.clone(),
+            },
+        )
+        .await?;
+    Ok((StatusCode::CREATED, Json(subscription)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/restriction_subscriptions/{subscription_id}",
+    request_body = UpdateRestrictionSubscriptionRequest,
+    responses(
+        (status = 200, description = "Update blocklist subscription, which is refreshed on the next cron run", body = crate::models::UserRestrictionSubscriptionSchema)
+    ),
+    params(
+        ("subscription_id" = Uuid, Path, description = "Subscription ID")
+    )
+)]
+pub async fn update_restriction_subscription(
+    Path(subscription_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    identity: AdminIdentity,
+    Json(req): Json<UpdateRestrictionSubscriptionRequest>,
+) -> Result<Json<UserRestrictionSubscription>, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    identity.require_all_boards()?;
+    let input = UpdateUserRestrictionSubscriptionInput {
+        id: subscription_id,
+        name: req.name,
+        source: req.source,
+        format: req.format.map(Into::into),
+        board_keys: req.board_keys,
+        targets: req
+            .targets
+            .map(|targets| targets.into_iter().map(Into::into).collect()),
+        action: req.action.map(Into::into),
+        captcha_provider: req.captcha_provider,
+        slow_down_seconds: req.slow_down_seconds,
+        refresh_interval_seconds: req.refresh_interval_seconds,
+        expires_after_seconds: req.expires_after_seconds,
+        enabled: req.enabled,
+    };
+    let subscription = app_state
+        .services
+        .moderation
+        .update_restriction_subscription(&identity, input)
+        .await?;
+    Ok(Json(subscription))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/restriction_subscriptions/{subscription_id}",
+    responses(
+        (status = 200, description = "Delete blocklist subscription and its rules")
+    ),
+    params(
+        ("subscription_id" = Uuid, Path, description = "Subscription ID")
+    )
+)]
+pub async fn delete_restriction_subscription(
+    Path(subscription_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    identity: AdminIdentity,
+) -> Result<Json<()>, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    identity.require_all_boards()?;
+    app_state
+        .services
+        .moderation
+        .delete_restriction_subscription(&identity, subscription_id)
+        .await?;
+    Ok(Json(()))
+}
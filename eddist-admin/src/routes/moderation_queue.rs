@@ -0,0 +1,98 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    auth::AdminIdentity,
+    error::ApiError,
+    models::{AdminScope, HeldPost, ListHeldPostsQuery},
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/moderation_queue", get(get_held_posts))
+        .route(
+            "/moderation_queue/{heldPostId}/approve",
+            post(approve_held_post),
+        )
+        .route(
+            "/moderation_queue/{heldPostId}/reject",
+            post(reject_held_post),
+        )
+}
+
+#[utoipa::path(
+    get,
+    path = "/moderation_queue/",
+    responses(
+        (status = 200, description = "List held posts successfully", body = Vec<HeldPost>),
+    ),
+    params(ListHeldPostsQuery),
+)]
+pub async fn get_held_posts(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Query(query): Query<ListHeldPostsQuery>,
+) -> Result<Json<Vec<HeldPost>>, ApiError> {
+    identity.require(AdminScope::ModerationRead)?;
+    let board_ids = identity.board_filter(query.board_id)?;
+    let held_posts = state
+        .services
+        .moderation_queue
+        .get_held_posts(query, board_ids)
+        .await?;
+    Ok(Json(held_posts))
+}
+
+#[utoipa::path(
+    post,
+    path = "/moderation_queue/{held_post_id}/approve",
+    responses(
+        (status = 200, description = "Approve held post successfully"),
+    ),
+    params(
+        ("held_post_id" = Uuid, Path, description = "Held post ID"),
+    ),
+)]
+pub async fn approve_held_post(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Path(held_post_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    state
+        .services
+        .moderation_queue
+        .approve_held_post(&identity, held_post_id)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    post,
+    path = "/moderation_queue/{held_post_id}/reject",
+    responses(
+        (status = 200, description = "Reject held post successfully"),
+    ),
+    params(
+        ("held_post_id" = Uuid, Path, description = "Held post ID"),
+    ),
+)]
+pub async fn reject_held_post(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Path(held_post_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    state
+        .services
+        .moderation_queue
+        .reject_held_post(&identity, held_post_id)
+        .await?;
+    Ok(StatusCode::OK)
+}
@@ -0,0 +1,169 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{delete, get, patch, post},
+};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    auth::AdminIdentity,
+    error::ApiError,
+    models::{
+        AdminScope, CreateModerationRuleInput, ListModerationActionLogsQuery, ModerationActionLog,
+        ModerationRule, UpdateModerationRuleInput,
+    },
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/moderation_rules", get(get_moderation_rules))
+        .route("/moderation_rules", post(create_moderation_rule))
+        .route("/moderation_rules/{ruleId}", patch(update_moderation_rule))
+        .route("/moderation_rules/{ruleId}", delete(delete_moderation_rule))
+        .route("/moderation_actions", get(get_moderation_actions))
+        .route(
+            "/moderation_actions/{actionId}/revert",
+            post(revert_moderation_action),
+        )
+}
+
+#[utoipa::path(
+    get,
+    path = "/moderation_rules/",
+    responses(
+        (status = 200, description = "List moderation rules successfully", body = Vec<ModerationRule>),
+    )
+)]
+pub async fn get_moderation_rules(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+) -> Result<Json<Vec<ModerationRule>>, ApiError> {
+    identity.require(AdminScope::ModerationRead)?;
+    let mut rules = state.services.moderation_rule.get_rules().await?;
+    rules.retain(|rule| {
+        rule.board_id
+            .is_none_or(|board_id| identity.boards.allows_id(board_id))
+    });
+    Ok(Json(rules))
+}
+
+#[utoipa::path(
+    post,
+    path = "/moderation_rules/",
+    responses(
+        (status = 200, description = "Create moderation rule successfully", body = ModerationRule),
+    ),
+    request_body = CreateModerationRuleInput
+)]
+pub async fn create_moderation_rule(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Json(body): Json<CreateModerationRuleInput>,
+) -> Result<Json<ModerationRule>, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    let rule = state
+        .services
+        .moderation_rule
+        .create_rule(&identity, body)
+        .await?;
+    Ok(Json(rule))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/moderation_rules/{rule_id}/",
+    responses(
+        (status = 200, description = "Update moderation rule successfully", body = ModerationRule),
+    ),
+    params(
+        ("rule_id" = Uuid, Path, description = "Moderation rule ID"),
+    ),
+    request_body = UpdateModerationRuleInput
+)]
+pub async fn update_moderation_rule(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Path(rule_id): Path<Uuid>,
+    Json(body): Json<UpdateModerationRuleInput>,
+) -> Result<Json<ModerationRule>, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    let rule = state
+        .services
+        .moderation_rule
+        .update_rule(&identity, rule_id, body)
+        .await?;
+    Ok(Json(rule))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/moderation_rules/{rule_id}/",
+    responses(
+        (status = 200, description = "Delete moderation rule successfully"),
+    ),
+    params(
+        ("rule_id" = Uuid, Path, description = "Moderation rule ID"),
+    ),
+)]
+pub async fn delete_moderation_rule(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Path(rule_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    state
+        .services
+        .moderation_rule
+        .delete_rule(&identity, rule_id)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    get,
+    path = "/moderation_actions/",
+    responses(
+        (status = 200, description = "List moderation actions successfully", body = Vec<ModerationActionLog>),
+    ),
+    params(ListModerationActionLogsQuery),
+)]
+pub async fn get_moderation_actions(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Query(query): Query<ListModerationActionLogsQuery>,
+) -> Result<Json<Vec<ModerationActionLog>>, ApiError> {
+    identity.require(AdminScope::ModerationRead)?;
+    let board_ids = identity.board_filter(query.board_id)?;
+    let logs = state
+        .services
+        .moderation_rule
+        .get_action_logs(query, board_ids)
+        .await?;
+    Ok(Json(logs))
+}
+
+#[utoipa::path(
+    post,
+    path = "/moderation_actions/{action_id}/revert",
+    responses(
+        (status = 200, description = "Revert moderation action successfully"),
+    ),
+    params(
+        ("action_id" = Uuid, Path, description = "Moderation action ID"),
+    ),
+)]
+pub async fn revert_moderation_action(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Path(action_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::ModerationWrite)?;
+    state
+        .services
+        .moderation_rule
+        .revert_action(&identity, action_id)
+        .await?;
+    Ok(StatusCode::OK)
+}
@@ -11,7 +11,7 @@ use crate::{
     AppState,
     auth::AdminIdentity,
     error::ApiError,
-    models::Notice,
+    models::{AdminScope, Notice},
     repository::notice_repository::{CreateNoticeInput, UpdateNoticeInput},
 };
 
@@ -95,6 +95,7 @@ pub async fn create_notice(
     identity: AdminIdentity,
     Json(input): Json<CreateNoticeInput>,
 ) -> Result<(StatusCode, Json<Notice>), ApiError> {
+    identity.require(AdminScope::NoticesWrite)?;
     if input.slug == "latest" {
         return Err(ApiError::bad_request("'latest' is a reserved slug"));
     }
@@ -128,6 +129,7 @@ pub async fn update_notice(
     Path(id): Path<Uuid>,
     Json(input): Json<UpdateNoticeInput>,
 ) -> Result<Json<Notice>, ApiError> {
+    identity.require(AdminScope::NoticesWrite)?;
     state
         .services
         .content_admin
@@ -164,6 +166,7 @@ pub async fn delete_notice(
     identity: AdminIdentity,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::NoticesWrite)?;
     state
         .services
         .content_admin
@@ -0,0 +1,39 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    routing::get,
+};
+
+use crate::{
+    AppState,
+    auth::AdminIdentity,
+    error::ApiError,
+    models::{AdminScope, SearchHit, SearchPostsQuery},
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/search", get(search_posts))
+}
+
+#[utoipa::path(
+    get,
+    path = "/search",
+    responses(
+        (status = 200, description = "Search posts successfully", body = Vec<SearchHit>),
+        (status = 400, description = "Query too short or long, or no filter given"),
+    ),
+    params(
+        SearchPostsQuery,
+    ),
+)]
+pub async fn search_posts(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Query(query): Query<SearchPostsQuery>,
+) -> Result<Json<Vec<SearchHit>>, ApiError> {
+    // Hits carry the token and the IP address of each post
+    identity.require(AdminScope::TokensRead)?;
+    let board_ids = identity.board_filter(query.board_id)?;
+    let hits = state.services.search.search_posts(query, board_ids).await?;
+    Ok(Json(hits))
+}
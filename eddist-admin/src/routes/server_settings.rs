@@ -8,7 +8,10 @@ use crate::{
     AppState,
     auth::AdminIdentity,
     error::ApiError,
-    models::server_settings::{ServerSetting, UpsertServerSettingInput},
+    models::{
+        AdminScope,
+        server_settings::{ServerSetting, UpsertServerSettingInput},
+    },
 };
 
 use eddist_core::server_settings::ServerSettingKey;
@@ -29,7 +32,9 @@ pub fn routes() -> Router<AppState> {
 )]
 pub async fn list_server_settings(
     State(state): State<AppState>,
+    identity: AdminIdentity,
 ) -> Result<Json<Vec<ServerSetting>>, ApiError> {
+    identity.require(AdminScope::SettingsRead)?;
     let settings = state.services.content_admin.list_server_settings().await?;
     Ok(Json(settings))
 }
@@ -49,6 +54,7 @@ pub async fn upsert_server_setting(
     identity: AdminIdentity,
     Json(input): Json<UpsertServerSettingInput>,
 ) -> Result<Json<ServerSetting>, ApiError> {
+    identity.require(AdminScope::SettingsWrite)?;
     if !ServerSettingKey::ALL
         .iter()
         .any(|k| k.as_str() == input.setting_key)
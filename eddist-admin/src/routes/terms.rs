@@ -5,7 +5,10 @@ use axum::{
 };
 
 use crate::{
-    AppState, auth::AdminIdentity, error::ApiError, models::Terms,
+    AppState,
+    auth::AdminIdentity,
+    error::ApiError,
+    models::{AdminScope, Terms},
     repository::terms_repository::UpdateTermsInput,
 };
 
@@ -49,6 +52,7 @@ pub async fn update_terms(
     identity: AdminIdentity,
     Json(input): Json<UpdateTermsInput>,
 ) -> Result<Json<Terms>, ApiError> {
+    identity.require(AdminScope::TermsWrite)?;
     let terms = state
         .services
         .content_admin
@@ -10,7 +10,10 @@ use crate::{
     AppState,
     auth::AdminIdentity,
     error::ApiError,
-    models::{Res, Thread, ThreadCompactionInput, UpdateResInput},
+    models::{
+        AdminScope, BulkModerateResponsesInput, BulkModerateResponsesResult, MomentumAlert,
+        MoveThreadInput, Res, Thread, ThreadCompactionInput, UpdateResInput,
+    },
 };
 
 pub fn routes() -> Router<AppState> {
@@ -25,10 +28,32 @@ pub fn routes() -> Router<AppState> {
             "/boards/{boardKey}/threads/{threadId}/responses/{resId}",
             patch(update_response),
         )
+        .route(
+            "/boards/{boardKey}/threads/{threadId}/stop",
+            post(stop_thread),
+        )
+        .route(
+            "/boards/{boardKey}/threads/{threadId}/reopen",
+            post(reopen_thread),
+        )
+        .route(
+            "/boards/{boardKey}/threads/{threadId}/pin",
+            post(pin_thread),
+        )
+        .route(
+            "/boards/{boardKey}/threads/{threadId}/unpin",
+            post(unpin_thread),
+        )
+        .route(
+            "/boards/{boardKey}/threads/{threadId}/move",
+            post(move_thread),
+        )
         .route(
             "/boards/{boardKey}/threads-compaction",
             post(threads_compaction),
         )
+        .route("/responses-bulk-moderation", post(bulk_moderate_responses))
+        .route("/momentum-alerts", get(get_momentum_alerts))
 }
 
 #[utoipa::path(
@@ -40,12 +65,34 @@ pub fn routes() -> Router<AppState> {
 )]
 pub async fn get_threads(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path(board_key): Path<String>,
 ) -> Result<Json<Vec<Thread>>, ApiError> {
+    identity.require_board_key(&board_key)?;
     let threads = state.services.thread.get_threads(&board_key).await?;
     Ok(Json(threads))
 }
 
+#[utoipa::path(
+    get,
+    path = "/momentum-alerts",
+    responses(
+        (status = 200, description = "List momentum alerts successfully", body = Vec<MomentumAlert>),
+    )
+)]
+pub async fn get_momentum_alerts(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+) -> Result<Json<Vec<MomentumAlert>>, ApiError> {
+    let alerts = state.services.thread.get_momentum_alerts().await?;
+    // Board moderators only see the alerts of their boards
+    let alerts = alerts
+        .into_iter()
+        .filter(|alert| identity.boards.allows_key(&alert.board_key))
+        .collect();
+    Ok(Json(alerts))
+}
+
 #[utoipa::path(
     get,
     path = "/boards/{board_key}/threads/{thread_id}/",
@@ -60,8 +107,10 @@ pub async fn get_threads(
 )]
 pub async fn get_thread(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path((board_key, thread_id)): Path<(String, u64)>,
 ) -> Result<Json<Thread>, ApiError> {
+    identity.require_board_key(&board_key)?;
     let thread = state
         .services
         .thread
@@ -84,8 +133,10 @@ pub async fn get_thread(
 )]
 pub async fn get_responses(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Path((board_key, thread_id)): Path<(String, u64)>,
 ) -> Result<Json<Vec<Res>>, ApiError> {
+    identity.require_board_key(&board_key)?;
     let responses = state
         .services
         .thread
@@ -113,6 +164,8 @@ pub async fn update_response(
     Path((board_key, thread_id, res_id)): Path<(String, u64, Uuid)>,
     Json(body): Json<UpdateResInput>,
 ) -> Result<Json<Res>, ApiError> {
+    identity.require(AdminScope::ThreadsWrite)?;
+    identity.require_board_key(&board_key)?;
     let res = state
         .services
         .thread
@@ -138,6 +191,8 @@ pub async fn threads_compaction(
     Path(board_key): Path<String>,
     Json(body): Json<ThreadCompactionInput>,
 ) -> Result<StatusCode, ApiError> {
+    identity.require(AdminScope::ThreadsWrite)?;
+    identity.require_board_key(&board_key)?;
     state
         .services
         .thread
@@ -145,3 +200,167 @@ pub async fn threads_compaction(
         .await?;
     Ok(StatusCode::OK)
 }
+
+#[utoipa::path(
+    post,
+    path = "/boards/{board_key}/threads/{thread_id}/stop/",
+    responses(
+        (status = 200, description = "Stop thread successfully", body = Thread),
+        (status = 404, description = "Thread not found"),
+    ),
+    params(
+        ("board_key" = String, Path, description = "Board Key"),
+        ("thread_id" = u64, Path, description = "Thread ID"),
+    )
+)]
+pub async fn stop_thread(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Path((board_key, thread_id)): Path<(String, u64)>,
+) -> Result<Json<Thread>, ApiError> {
+    identity.require(AdminScope::ThreadsWrite)?;
+    identity.require_board_key(&board_key)?;
+    let thread = state
+        .services
+        .thread
+        .stop_thread(&identity, &board_key, thread_id)
+        .await?;
+    Ok(Json(thread))
+}
+
+#[utoipa::path(
+    post,
+    path = "/boards/{board_key}/threads/{thread_id}/reopen/",
+    responses(
+        (status = 200, description = "Reopen thread successfully", body = Thread),
+        (status = 400, description = "Thread is archived or has reached the response limit"),
+        (status = 404, description = "Thread not found"),
+    ),
+    params(
+        ("board_key" = String, Path, description = "Board Key"),
+        ("thread_id" = u64, Path, description = "Thread ID"),
+    )
+)]
+pub async fn reopen_thread(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Path((board_key, thread_id)): Path<(String, u64)>,
+) -> Result<Json<Thread>, ApiError> {
+    identity.require(AdminScope::ThreadsWrite)?;
+    identity.require_board_key(&board_key)?;
+    let thread = state
+        .services
+        .thread
+        .reopen_thread(&identity, &board_key, thread_id)
+        .await?;
+    Ok(Json(thread))
+}
+
+#[utoipa::path(
+    post,
+    path = "/boards/{board_key}/threads/{thread_id}/pin/",
+    responses(
+        (status = 200, description = "Pin thread successfully", body = Thread),
+        (status = 404, description = "Thread not found"),
+    ),
+    params(
+        ("board_key" = String, Path, description = "Board Key"),
+        ("thread_id" = u64, Path, description = "Thread ID"),
+    )
+)]
+pub async fn pin_thread(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Path((board_key, thread_id)): Path<(String, u64)>,
+) -> Result<Json<Thread>, ApiError> {
+    identity.require(AdminScope::ThreadsWrite)?;
+    identity.require_board_key(&board_key)?;
+    let thread = state
+        .services
+        .thread
+        .pin_thread(&identity, &board_key, thread_id, true)
+        .await?;
+    Ok(Json(thread))
+}
+
+#[utoipa::path(
+    post,
+    path = "/boards/{board_key}/threads/{thread_id}/unpin/",
+    responses(
+        (status = 200, description = "Unpin thread successfully", body = Thread),
+        (status = 404, description = "Thread not found"),
+    ),
+    params(
+        ("board_key" = String, Path, description = "Board Key"),
+        ("thread_id" = u64, Path, description = "Thread ID"),
+    )
+)]
+pub async fn unpin_thread(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Path((board_key, thread_id)): Path<(String, u64)>,
+) -> Result<Json<Thread>, ApiError> {
+    identity.require(AdminScope::ThreadsWrite)?;
+    identity.require_board_key(&board_key)?;
+    let thread = state
+        .services
+        .thread
+        .pin_thread(&identity, &board_key, thread_id, false)
+        .await?;
+    Ok(Json(thread))
+}
+
+#[utoipa::path(
+    post,
+    path = "/boards/{board_key}/threads/{thread_id}/move/",
+    responses(
+        (status = 200, description = "Move thread successfully", body = Thread),
+        (status = 400, description = "Thread number already exists in the target board"),
+        (status = 404, description = "Thread or board not found"),
+    ),
+    params(
+        ("board_key" = String, Path, description = "Board Key"),
+        ("thread_id" = u64, Path, description = "Thread ID"),
+    ),
+    request_body = MoveThreadInput
+)]
+pub async fn move_thread(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Path((board_key, thread_id)): Path<(String, u64)>,
+    Json(body): Json<MoveThreadInput>,
+) -> Result<Json<Thread>, ApiError> {
+    identity.require(AdminScope::ThreadsWrite)?;
+    identity.require_board_key(&board_key)?;
+    identity.require_board_key(&body.board_key)?;
+    let thread = state
+        .services
+        .thread
+        .move_thread(&identity, &board_key, thread_id, &body.board_key)
+        .await?;
+    Ok(Json(thread))
+}
+
+#[utoipa::path(
+    post,
+    path = "/responses-bulk-moderation/",
+    responses(
+        (status = 200, description = "Abone or delete matching responses successfully, or count them on a dry run", body = BulkModerateResponsesResult),
+        (status = 400, description = "Invalid filter or too many matching responses"),
+    ),
+    request_body = BulkModerateResponsesInput
+)]
+pub async fn bulk_moderate_responses(
+    State(state): State<AppState>,
+    identity: AdminIdentity,
+    Json(body): Json<BulkModerateResponsesInput>,
+) -> Result<Json<BulkModerateResponsesResult>, ApiError> {
+    identity.require(AdminScope::ThreadsWrite)?;
+    let board_ids = identity.board_filter(body.board_id)?;
+    let result = state
+        .services
+        .thread
+        .bulk_moderate_responses(&identity, body, board_ids)
+        .await?;
+    Ok(Json(result))
+}
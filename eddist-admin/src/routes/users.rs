@@ -9,7 +9,7 @@ use crate::{
     AppState,
     auth::AdminIdentity,
     error::ApiError,
-    models::{User, UserSearchQuery, UserStatusUpdateInput},
+    models::{AdminScope, User, UserSearchQuery, UserStatusUpdateInput},
 };
 
 pub fn routes() -> Router<AppState> {
@@ -30,8 +30,10 @@ pub fn routes() -> Router<AppState> {
 )]
 pub async fn search_users(
     State(state): State<AppState>,
+    identity: AdminIdentity,
     Query(query): Query<UserSearchQuery>,
 ) -> Result<Json<Vec<User>>, ApiError> {
+    identity.require(AdminScope::UsersRead)?;
     let users = state.services.user.search_users(query).await?;
     Ok(Json(users))
 }
@@ -53,6 +55,7 @@ pub async fn update_user_status(
     Path(user_id): Path<Uuid>,
     Json(body): Json<UserStatusUpdateInput>,
 ) -> Result<Json<User>, ApiError> {
+    identity.require(AdminScope::UsersWrite)?;
     let user = state
         .services
         .user
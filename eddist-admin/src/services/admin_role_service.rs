@@ -0,0 +1,259 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    auth::AdminIdentity,
+    error::ServiceError,
+    models::{
+        AdminBoards, AdminRole, AdminScope, AdminScopes, AdminUserAssignment, AssignAdminRoleInput,
+        CreateAdminRoleInput, UpdateAdminRoleInput,
+    },
+    repository::admin_role_repository::AdminRoleRepository,
+};
+
+use super::audit_log_service::{AuditEntry, AuditLogService};
+
+/// Comma-separated emails that are granted every scope regardless of their role, so that the
+/// first roles can be assigned on a fresh install
+const SUPERUSER_EMAILS_ENV: &str = "EDDIST_ADMIN_SUPERUSER_EMAILS";
+
+#[async_trait::async_trait]
+pub trait AdminRoleService: Send + Sync {
+    async fn get_scopes(&self, email: &str) -> anyhow::Result<AdminScopes>;
+    async fn get_boards(&self, email: &str) -> anyhow::Result<AdminBoards>;
+
+    async fn get_roles(&self) -> anyhow::Result<Vec<AdminRole>>;
+    async fn create_role(
+        &self,
+        actor: &AdminIdentity,
+        input: CreateAdminRoleInput,
+    ) -> anyhow::Result<AdminRole>;
+    async fn update_role(
+        &self,
+        actor: &AdminIdentity,
+        id: Uuid,
+        input: UpdateAdminRoleInput,
+    ) -> anyhow::Result<AdminRole>;
+    async fn delete_role(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()>;
+
+    async fn get_assignments(&self) -> anyhow::Result<Vec<AdminUserAssignment>>;
+    async fn assign_role(
+        &self,
+        actor: &AdminIdentity,
+        input: AssignAdminRoleInput,
+    ) -> anyhow::Result<AdminUserAssignment>;
+    async fn delete_assignment(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()>;
+}
+
+pub struct AdminRoleServiceImpl {
+    repo: Arc<dyn AdminRoleRepository>,
+    superuser_emails: Vec<String>,
+    audit: Arc<dyn AuditLogService>,
+}
+
+impl AdminRoleServiceImpl {
+    pub fn new(repo: Arc<dyn AdminRoleRepository>, audit: Arc<dyn AuditLogService>) -> Self {
+        let superuser_emails = std::env::var(SUPERUSER_EMAILS_ENV)
+            .map(|emails| {
+                emails
+                    .split(',')
+                    .map(|email| email.trim().to_lowercase())
+                    .filter(|email| !email.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            repo,
+            superuser_emails,
+            audit,
+        }
+    }
+}
+
+fn validate_role_name(name: &str) -> Result<(), ServiceError> {
+    if name.trim().is_empty() {
+        return Err(ServiceError::BadRequest("name must not be empty".into()));
+    }
+    Ok(())
+}
+
+fn normalize_scopes(mut scopes: Vec<AdminScope>) -> Vec<AdminScope> {
+    scopes.sort_by_key(|scope| scope.as_str());
+    scopes.dedup();
+    scopes
+}
+
+#[async_trait::async_trait]
+impl AdminRoleService for AdminRoleServiceImpl {
+    async fn get_scopes(&self, email: &str) -> anyhow::Result<AdminScopes> {
+        if self.superuser_emails.contains(&email.to_lowercase()) {
+            return Ok(AdminScopes::all());
+        }
+        let scopes = self.repo.get_scopes_by_email(email).await?;
+        Ok(scopes.into_iter().collect())
+    }
+
+    async fn get_boards(&self, email: &str) -> anyhow::Result<AdminBoards> {
+        if self.superuser_emails.contains(&email.to_lowercase()) {
+            return Ok(AdminBoards::All);
+        }
+        let boards = self.repo.get_boards_by_email(email).await?;
+        if boards.is_empty() {
+            return Ok(AdminBoards::All);
+        }
+        Ok(AdminBoards::Only(boards.into_iter().collect()))
+    }
+
+    async fn get_roles(&self) -> anyhow::Result<Vec<AdminRole>> {
+        self.repo.get_roles().await
+    }
+
+    async fn create_role(
+        &self,
+        actor: &AdminIdentity,
+        input: CreateAdminRoleInput,
+    ) -> anyhow::Result<AdminRole> {
+        validate_role_name(&input.name)?;
+
+        let role = AdminRole {
+            id: Uuid::now_v7(),
+            name: input.name,
+            description: input.description,
+            scopes: normalize_scopes(input.scopes),
+        };
+        self.repo.create_role(&role).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("admin_role", "create", role.id).after(&role),
+            )
+            .await;
+        Ok(role)
+    }
+
+    async fn update_role(
+        &self,
+        actor: &AdminIdentity,
+        id: Uuid,
+        input: UpdateAdminRoleInput,
+    ) -> anyhow::Result<AdminRole> {
+        let mut role = self
+            .repo
+            .get_role(id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("admin role not found: {id}")))?;
+        let before = role.clone();
+
+        if let Some(name) = input.name {
+            validate_role_name(&name)?;
+            role.name = name;
+        }
+        if let Some(description) = input.description {
+            role.description = description;
+        }
+        if let Some(scopes) = input.scopes {
+            role.scopes = normalize_scopes(scopes);
+        }
+        self.repo.update_role(&role).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("admin_role", "update", id)
+                    .before(&before)
+                    .after(&role),
+            )
+            .await;
+        Ok(role)
+    }
+
+    async fn delete_role(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        let before = self.repo.get_role(id).await?;
+        self.repo.delete_role(id).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("admin_role", "delete", id).before(&before),
+            )
+            .await;
+        Ok(())
+    }
+
+    async fn get_assignments(&self) -> anyhow::Result<Vec<AdminUserAssignment>> {
+        self.repo.get_assignments().await
+    }
+
+    async fn assign_role(
+        &self,
+        actor: &AdminIdentity,
+        input: AssignAdminRoleInput,
+    ) -> anyhow::Result<AdminUserAssignment> {
+        let email = input.email.trim().to_lowercase();
+        if email.is_empty() {
+            return Err(ServiceError::BadRequest("email must not be empty".into()).into());
+        }
+        if self.repo.get_role(input.role_id).await?.is_none() {
+            return Err(
+                ServiceError::NotFound(format!("admin role not found: {}", input.role_id)).into(),
+            );
+        }
+
+        let mut board_ids = input.board_ids;
+        board_ids.sort();
+        board_ids.dedup();
+
+        let before = self.repo.get_assignment_by_email(&email).await?;
+        let now = Utc::now();
+        let assignment = match &before {
+            Some(existing) => AdminUserAssignment {
+                role_id: input.role_id,
+                board_ids,
+                updated_at: now,
+                ..existing.clone()
+            },
+            None => AdminUserAssignment {
+                id: Uuid::now_v7(),
+                email,
+                role_id: input.role_id,
+                board_ids,
+                created_at: now,
+                updated_at: now,
+            },
+        };
+        self.repo.upsert_assignment(&assignment).await?;
+
+        let verb = if before.is_some() { "update" } else { "create" };
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("admin_user", verb, assignment.id)
+                    .before(&before)
+                    .after(&assignment),
+            )
+            .await;
+        Ok(assignment)
+    }
+
+    async fn delete_assignment(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        let before = self
+            .repo
+            .get_assignments()
+            .await?
+            .into_iter()
+            .find(|assignment| assignment.id == id);
+        self.repo.delete_assignment(id).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("admin_user", "delete", id).before(&before),
+            )
+            .await;
+        Ok(())
+    }
+}
@@ -14,6 +14,8 @@ use crate::{
     },
 };
 
+use super::audit_log_service::{AuditEntry, AuditLogService};
+
 #[async_trait::async_trait]
 pub trait AdminArchiveService: Send + Sync {
     async fn get_archived_threads(
@@ -70,6 +72,7 @@ pub struct ArchiveServiceImpl {
     thread_repo: Arc<dyn AdminThreadRepository>,
     response_repo: Arc<dyn AdminResponseRepository>,
     archive_repo: Arc<dyn AdminArchiveRepository>,
+    audit: Arc<dyn AuditLogService>,
 }
 
 impl ArchiveServiceImpl {
@@ -77,11 +80,13 @@ impl ArchiveServiceImpl {
         thread_repo: Arc<dyn AdminThreadRepository>,
         response_repo: Arc<dyn AdminResponseRepository>,
         archive_repo: Arc<dyn AdminArchiveRepository>,
+        audit: Arc<dyn AuditLogService>,
     ) -> Self {
         Self {
             thread_repo,
             response_repo,
             archive_repo,
+            audit,
         }
     }
 }
@@ -143,36 +148,85 @@ impl AdminArchiveService for ArchiveServiceImpl {
 
     async fn update_archived_res(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         board_key: &str,
         thread_number: u64,
         updates: &[ArchivedResUpdate],
     ) -> anyhow::Result<()> {
+        let before = self
+            .archive_repo
+            .get_thread(board_key, thread_number)
+            .await?;
         self.archive_repo
             .update_response(board_key, thread_number, updates)
-            .await
+            .await?;
+
+        let before = updates
+            .iter()
+            .filter_map(|update| before.responses.get(update.res_order as usize))
+            .collect::<Vec<_>>();
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new(
+                    "archived_response",
+                    "update",
+                    format!("{board_key}/{thread_number}"),
+                )
+                .before(&before)
+                .after(&updates),
+            )
+            .await;
+        Ok(())
     }
 
     async fn delete_archived_res(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         board_key: &str,
         thread_number: u64,
         res_order: u64,
     ) -> anyhow::Result<()> {
+        let thread = self
+            .archive_repo
+            .get_thread(board_key, thread_number)
+            .await?;
         self.archive_repo
             .delete_response(board_key, thread_number, res_order)
-            .await
+            .await?;
+
+        let mut entry = AuditEntry::new(
+            "archived_response",
+            "delete",
+            format!("{board_key}/{thread_number}/{res_order}"),
+        );
+        if let Some(res) = thread.responses.get(res_order as usize) {
+            entry = entry.before(res);
+        }
+        self.audit.record(actor, entry).await;
+        Ok(())
     }
 
     async fn delete_archived_thread(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         board_key: &str,
         thread_number: u64,
     ) -> anyhow::Result<()> {
         self.archive_repo
             .delete_thread(board_key, thread_number)
-            .await
+            .await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new(
+                    "archived_thread",
+                    "delete",
+                    format!("{board_key}/{thread_number}"),
+                ),
+            )
+            .await;
+        Ok(())
     }
 }
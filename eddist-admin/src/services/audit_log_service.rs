@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::{
+    auth::AdminIdentity,
+    models::{AuditLog, ListAuditLogsQuery, PaginatedAuditLogs},
+    repository::audit_log_repository::{AuditLogRepository, ListAuditLogsParams},
+};
+
+/// Fields whose values are never written to the audit log
+const REDACTED_FIELDS: &[&str] = &["secret", "client_secret", "password"];
+const REDACTED_VALUE: &str = "[REDACTED]";
+const CHANGED_SECRET_VALUE: &str = "[REDACTED: changed]";
+
+/// A mutating admin action to be recorded, built by the service performing it
+pub struct AuditEntry {
+    action: String,
+    target_type: String,
+    target_id: String,
+    before: Option<Value>,
+    after: Option<Value>,
+}
+
+impl AuditEntry {
+    /// `action` is `<target_type>.<verb>`, e.g. `ng_word.update`
+    pub fn new(target_type: &str, verb: &str, target_id: impl ToString) -> Self {
+        Self {
+            action: format!("{target_type}.{verb}"),
+            target_type: target_type.to_string(),
+            target_id: target_id.to_string(),
+            before: None,
+            after: None,
+        }
+    }
+
+    pub fn before(mut self, state: &impl Serialize) -> Self {
+        self.before = to_state(state);
+        self
+    }
+
+    pub fn after(mut self, state: &impl Serialize) -> Self {
+        self.after = to_state(state);
+        self
+    }
+
+    /// Notes that a secret changed without recording its value, e.g. a cap password
+    pub fn changed_secret(mut self, field: &str) -> Self {
+        if let Some(Value::Object(fields)) = &mut self.after {
+            fields.insert(
+                field.to_string(),
+                Value::String(CHANGED_SECRET_VALUE.to_string()),
+            );
+        }
+        self
+    }
+}
+
+fn to_state(state: &impl Serialize) -> Option<Value> {
+    let mut value = serde_json::to_value(state).ok().filter(|v| !v.is_null())?;
+    if let Value::Object(fields) = &mut value {
+        for field in REDACTED_FIELDS {
+            if let Some(v) = fields.get_mut(*field) {
+                *v = Value::String(REDACTED_VALUE.to_string());
+            }
+        }
+    }
+    Some(value)
+}
+
+/// Drops the fields that are the same in both states, so an update only keeps what changed
+fn diff(before: Option<Value>, after: Option<Value>) -> (Option<Value>, Option<Value>) {
+    match (before, after) {
+        (Some(Value::Object(before)), Some(Value::Object(after))) => {
+            let changed = |from: &Map<String, Value>, to: &Map<String, Value>| {
+                from.iter()
+                    .filter(|(k, v)| to.get(*k) != Some(*v))
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect::<Map<_, _>>()
+            };
+            (
+                Some(Value::Object(changed(&before, &after))),
+                Some(Value::Object(changed(&after, &before))),
+            )
+        }
+        other => other,
+    }
+}
+
+#[async_trait::async_trait]
+pub trait AuditLogService: Send + Sync {
+    /// Records an action that has already been performed. Failures are logged, not returned,
+    /// so that a successful change is never reported as failed.
+    async fn record(&self, actor: &AdminIdentity, entry: AuditEntry);
+    async fn list_audit_logs(
+        &self,
+        query: ListAuditLogsQuery,
+    ) -> anyhow::Result<PaginatedAuditLogs>;
+}
+
+pub struct AuditLogServiceImpl {
+    repo: Arc<dyn AuditLogRepository>,
+}
+
+impl AuditLogServiceImpl {
+    pub fn new(repo: Arc<dyn AuditLogRepository>) -> Self {
+        Self { repo }
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditLogService for AuditLogServiceImpl {
+    async fn record(&self, actor: &AdminIdentity, entry: AuditEntry) {
+        let (before, after) = diff(entry.before, entry.after);
+        let log = AuditLog {
+            id: Uuid::now_v7(),
+            actor_sub: actor.sub.clone(),
+            actor_email: actor.email.clone(),
+            action: entry.action,
+            target_type: entry.target_type,
+            target_id: entry.target_id,
+            before,
+            after,
+            created_at: Utc::now(),
+        };
+
+        if let Err(e) = self.repo.create_audit_log(&log).await {
+            tracing::error!(
+                action = %log.action,
+                target_id = %log.target_id,
+                actor = %log.actor_email,
+                "failed to record audit log: {e:?}"
+            );
+        }
+    }
+
+    async fn list_audit_logs(
+        &self,
+        query: ListAuditLogsQuery,
+    ) -> anyhow::Result<PaginatedAuditLogs> {
+        let page = query.page.unwrap_or(1).max(1);
+        let per_page = query.per_page.unwrap_or(50).clamp(1, 100);
+        let offset = (page - 1) as u64 * per_page as u64;
+
+        let (items, total) = self
+            .repo
+            .list_audit_logs(ListAuditLogsParams {
+                offset,
+                limit: per_page,
+                actor_email: query.actor_email.as_deref(),
+                action: query.action.as_deref(),
+                target_type: query.target_type.as_deref(),
+                target_id: query.target_id.as_deref(),
+                since: query.since,
+                until: query.until,
+            })
+            .await?;
+
+        let total_pages = ((total as f64) / (per_page as f64)).ceil() as u32;
+        Ok(PaginatedAuditLogs {
+            items,
+            total,
+            page,
+            per_page,
+            total_pages,
+        })
+    }
+}
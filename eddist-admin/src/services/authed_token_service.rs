@@ -7,46 +7,68 @@ use eddist_core::{
     utils::is_authed_token_backup_enabled,
 };
 use redis::AsyncCommands as _;
+use serde_json::json;
 use uuid::Uuid;
 
 use crate::{
     auth::AdminIdentity,
+    error::ServiceError,
     models::{AuthedToken, DeleteAuthedTokenInput, ListAuthedTokensQuery, PaginatedAuthedTokens},
     repository::authed_token_repository::{AuthedTokenRepository, ListAuthedTokensParams},
 };
 
+use super::audit_log_service::{AuditEntry, AuditLogService};
+
 const ALLOWED_SORT_COLUMNS: &[&str] = &["created_at", "authed_at", "last_wrote_at"];
 
 #[async_trait::async_trait]
 pub trait AuthedTokenService: Send + Sync {
+    /// `board_ids` restricts the listing to tokens that posted on these boards, `None` lists
+    /// every token
     async fn list_authed_tokens(
         &self,
         query: ListAuthedTokensQuery,
+        board_ids: Option<Vec<Uuid>>,
     ) -> anyhow::Result<PaginatedAuthedTokens>;
-    async fn get_authed_token(&self, id: Uuid) -> anyhow::Result<AuthedToken>;
+    async fn get_authed_token(
+        &self,
+        actor: &AdminIdentity,
+        id: Uuid,
+    ) -> anyhow::Result<AuthedToken>;
     async fn delete_authed_token(
         &self,
         actor: &AdminIdentity,
         id: Uuid,
         options: DeleteAuthedTokenInput,
     ) -> anyhow::Result<()>;
-    async fn set_require_reauth(&self, id: Uuid) -> anyhow::Result<()>;
-    async fn clear_require_reauth(&self, id: Uuid) -> anyhow::Result<()>;
-    async fn suspend_authed_token(&self, id: Uuid, ttl_seconds: u64) -> anyhow::Result<()>;
+    async fn set_require_reauth(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()>;
+    async fn clear_require_reauth(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()>;
+    async fn suspend_authed_token(
+        &self,
+        actor: &AdminIdentity,
+        id: Uuid,
+        ttl_seconds: u64,
+    ) -> anyhow::Result<()>;
     async fn revoke_authed_token(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()>;
 }
 
 pub struct AuthedTokenServiceImpl {
     repo: Arc<dyn AuthedTokenRepository>,
     redis_conn: redis::aio::ConnectionManager,
+    audit: Arc<dyn AuditLogService>,
 }
 
 impl AuthedTokenServiceImpl {
     pub fn new(
         repo: Arc<dyn AuthedTokenRepository>,
         redis_conn: redis::aio::ConnectionManager,
+        audit: Arc<dyn AuditLogService>,
     ) -> Self {
-        Self { repo, redis_conn }
+        Self {
+            repo,
+            redis_conn,
+            audit,
+        }
     }
 
     async fn publish_token_revoked(&self, id: Uuid) {
@@ -56,6 +78,19 @@ impl AuthedTokenServiceImpl {
         let mut conn = self.redis_conn.clone();
         let _: Result<(), _> = conn.publish(CHANNEL_AUTH_TOKEN_REVOKED, payload).await;
     }
+
+    /// Board moderators may only act on tokens that have posted on their boards
+    async fn check_token_board(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        if !actor.boards.is_restricted() {
+            return Ok(());
+        }
+        let board_ids = self.repo.get_posted_board_ids(id).await?;
+        if board_ids.into_iter().any(|b| actor.boards.allows_id(b)) {
+            Ok(())
+        } else {
+            Err(ServiceError::Forbidden("this token has not posted on your boards".into()).into())
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -63,6 +98,7 @@ impl AuthedTokenService for AuthedTokenServiceImpl {
     async fn list_authed_tokens(
         &self,
         query: ListAuthedTokensQuery,
+        board_ids: Option<Vec<Uuid>>,
     ) -> anyhow::Result<PaginatedAuthedTokens> {
         let page = query.page.unwrap_or(1).max(1);
         let per_page = query.per_page.unwrap_or(50).clamp(1, 100);
@@ -88,6 +124,7 @@ impl AuthedTokenService for AuthedTokenServiceImpl {
                 authed_ua: query.authed_ua.as_deref(),
                 asn_num: query.asn_num,
                 validity: query.validity,
+                board_ids: board_ids.as_deref(),
                 sort_column,
                 sort_asc,
             })
@@ -103,16 +140,29 @@ impl AuthedTokenService for AuthedTokenServiceImpl {
         })
     }
 
-    async fn get_authed_token(&self, id: Uuid) -> anyhow::Result<AuthedToken> {
+    async fn get_authed_token(
+        &self,
+        actor: &AdminIdentity,
+        id: Uuid,
+    ) -> anyhow::Result<AuthedToken> {
+        self.check_token_board(actor, id).await?;
         self.repo.get_authed_token(id).await
     }
 
     async fn delete_authed_token(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         id: Uuid,
         options: DeleteAuthedTokenInput,
     ) -> anyhow::Result<()> {
+        if options.using_origin_ip && actor.boards.is_restricted() {
+            return Err(ServiceError::Forbidden(
+                "board moderators cannot revoke tokens by origin IP".into(),
+            )
+            .into());
+        }
+        self.check_token_board(actor, id).await?;
+
         let affected_ids = if !options.using_origin_ip {
             self.repo.delete_authed_token(id).await?;
             vec![id]
@@ -120,6 +170,16 @@ impl AuthedTokenService for AuthedTokenServiceImpl {
             self.repo.delete_authed_token_by_origin_ip(id).await?
         };
 
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("authed_token", "delete", id).after(&json!({
+                    "using_origin_ip": options.using_origin_ip,
+                    "revoked_ids": affected_ids,
+                })),
+            )
+            .await;
+
         if is_authed_token_backup_enabled() {
             for affected_id in affected_ids {
                 self.publish_token_revoked(affected_id).await;
@@ -128,18 +188,44 @@ impl AuthedTokenService for AuthedTokenServiceImpl {
         Ok(())
     }
 
-    async fn set_require_reauth(&self, id: Uuid) -> anyhow::Result<()> {
-        self.repo.set_require_reauth(id).await
+    async fn set_require_reauth(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        self.check_token_board(actor, id).await?;
+        self.repo.set_require_reauth(id).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("authed_token", "require_reauth", id)
+                    .after(&json!({ "require_reauth": true })),
+            )
+            .await;
+        Ok(())
     }
 
-    async fn clear_require_reauth(&self, id: Uuid) -> anyhow::Result<()> {
-        self.repo.clear_require_reauth(id).await
+    async fn clear_require_reauth(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        self.check_token_board(actor, id).await?;
+        self.repo.clear_require_reauth(id).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("authed_token", "clear_require_reauth", id)
+                    .after(&json!({ "require_reauth": false })),
+            )
+            .await;
+        Ok(())
     }
 
-    async fn suspend_authed_token(&self, id: Uuid, ttl_seconds: u64) -> anyhow::Result<()> {
+    async fn suspend_authed_token(
+        &self,
+        actor: &AdminIdentity,
+        id: Uuid,
+        ttl_seconds: u64,
+    ) -> anyhow::Result<()> {
+        self.check_token_board(actor, id).await?;
         let token = self.repo.get_authed_token(id).await?;
         if !token.validity {
-            return Err(crate::error::ServiceError::BadRequest(
+            return Err(ServiceError::BadRequest(
                 "this token has already been permanently revoked".into(),
             )
             .into());
@@ -148,11 +234,28 @@ impl AuthedTokenService for AuthedTokenServiceImpl {
         let mut conn = self.redis_conn.clone();
         conn.set_ex::<_, _, ()>(&key, "1", ttl_seconds)
             .await
-            .map_err(|e| anyhow::anyhow!(e))
+            .map_err(|e| anyhow::anyhow!(e))?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("authed_token", "suspend", id)
+                    .after(&json!({ "ttl_seconds": ttl_seconds })),
+            )
+            .await;
+        Ok(())
     }
 
-    async fn revoke_authed_token(&self, _actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+    async fn revoke_authed_token(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        self.check_token_board(actor, id).await?;
         self.repo.delete_authed_token(id).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("authed_token", "revoke", id).after(&json!({ "validity": false })),
+            )
+            .await;
         if is_authed_token_backup_enabled() {
             self.publish_token_revoked(id).await;
         }
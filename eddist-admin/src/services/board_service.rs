@@ -1,11 +1,26 @@
 use std::sync::Arc;
 
+use eddist_core::domain::moderation_provider::BoardModerationConfig;
+use serde::Serialize;
+
 use crate::{
     auth::AdminIdentity,
+    error::ServiceError,
     models::{Board, BoardInfo, CreateBoardInput, EditBoardInput},
     repository::admin_board_repository::AdminBoardRepository,
 };
 
+use super::audit_log_service::{AuditEntry, AuditLogService};
+
+/// A board and its settings as a single audit log state
+#[derive(Serialize)]
+struct BoardState<'a> {
+    #[serde(flatten)]
+    board: &'a Board,
+    #[serde(flatten)]
+    info: &'a BoardInfo,
+}
+
 #[async_trait::async_trait]
 pub trait BoardService: Send + Sync {
     async fn get_boards(&self, keys: Option<Vec<String>>) -> anyhow::Result<Vec<Board>>;
@@ -25,11 +40,12 @@ pub trait BoardService: Send + Sync {
 
 pub struct BoardServiceImpl {
     repo: Arc<dyn AdminBoardRepository>,
+    audit: Arc<dyn AuditLogService>,
 }
 
 impl BoardServiceImpl {
-    pub fn new(repo: Arc<dyn AdminBoardRepository>) -> Self {
-        Self { repo }
+    pub fn new(repo: Arc<dyn AdminBoardRepository>, audit: Arc<dyn AuditLogService>) -> Self {
+        Self { repo, audit }
     }
 }
 
@@ -45,18 +61,56 @@ impl BoardService for BoardServiceImpl {
 
     async fn create_board(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         input: CreateBoardInput,
     ) -> anyhow::Result<Board> {
-        self.repo.create_board(input).await
+        let board = self.repo.create_board(input).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("board", "create", board.id).after(&board),
+            )
+            .await;
+        Ok(board)
     }
 
     async fn edit_board(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         board_key: &str,
         input: EditBoardInput,
     ) -> anyhow::Result<Board> {
-        self.repo.edit_board(board_key, input).await
+        if let Some(moderation_config) = &input.moderation_config {
+            BoardModerationConfig::from(moderation_config.clone())
+                .validate()
+                .map_err(ServiceError::BadRequest)?;
+        }
+        let before = self
+            .repo
+            .get_boards_by_key(Some(vec![board_key.to_string()]))
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ServiceError::NotFound(format!("board not found: {board_key}")))?;
+        let before_info = self.repo.get_board_info(before.id).await?;
+        let board = self.repo.edit_board(board_key, input).await?;
+        let after_info = self.repo.get_board_info(board.id).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("board", "update", board.id)
+                    .before(&BoardState {
+                        board: &before,
+                        info: &before_info,
+                    })
+                    .after(&BoardState {
+                        board: &board,
+                        info: &after_info,
+                    }),
+            )
+            .await;
+        Ok(board)
     }
 }
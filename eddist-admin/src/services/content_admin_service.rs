@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use eddist_core::server_settings::KEY_AI_OPENAI_API_KEY;
 use uuid::Uuid;
 
 use crate::{
@@ -18,6 +19,8 @@ use crate::{
     },
 };
 
+use super::audit_log_service::{AuditEntry, AuditLogService};
+
 #[async_trait::async_trait]
 pub trait ContentAdminService: Send + Sync {
     // Notices
@@ -89,6 +92,7 @@ pub struct ContentAdminServiceImpl {
     server_settings_repo: Arc<dyn ServerSettingsRepository>,
     idp_repo: Arc<dyn IdpAdminRepository>,
     captcha_config_repo: Arc<dyn CaptchaConfigRepository>,
+    audit: Arc<dyn AuditLogService>,
 }
 
 impl ContentAdminServiceImpl {
@@ -98,6 +102,7 @@ impl ContentAdminServiceImpl {
         server_settings_repo: Arc<dyn ServerSettingsRepository>,
         idp_repo: Arc<dyn IdpAdminRepository>,
         captcha_config_repo: Arc<dyn CaptchaConfigRepository>,
+        audit: Arc<dyn AuditLogService>,
     ) -> Self {
         Self {
             notice_repo,
@@ -105,6 +110,7 @@ impl ContentAdminServiceImpl {
             server_settings_repo,
             idp_repo,
             captcha_config_repo,
+            audit,
         }
     }
 }
@@ -133,21 +139,47 @@ impl ContentAdminService for ContentAdminServiceImpl {
             .notice_repo
             .create_notice(input, Some(actor.email.clone()))
             .await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("notice", "create", notice.id).after(&notice),
+            )
+            .await;
         Ok(notice.into())
     }
 
     async fn update_notice(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         id: Uuid,
         input: UpdateNoticeInput,
     ) -> anyhow::Result<Notice> {
+        let before = self.notice_repo.get_notice_by_id(id).await?;
         let notice = self.notice_repo.update_notice(id, input).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("notice", "update", id)
+                    .before(&before)
+                    .after(&notice),
+            )
+            .await;
         Ok(notice.into())
     }
 
-    async fn delete_notice(&self, _actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
-        self.notice_repo.delete_notice(id).await
+    async fn delete_notice(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        let before = self.notice_repo.get_notice_by_id(id).await?;
+        self.notice_repo.delete_notice(id).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("notice", "delete", id).before(&before),
+            )
+            .await;
+        Ok(())
     }
 
     async fn check_notice_author(
@@ -178,10 +210,20 @@ impl ContentAdminService for ContentAdminServiceImpl {
         actor: &AdminIdentity,
         input: UpdateTermsInput,
     ) -> anyhow::Result<Terms> {
+        let before = self.terms_repo.get_terms().await?;
         let terms = self
             .terms_repo
             .update_terms(input, Some(actor.email.clone()))
             .await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("terms", "update", terms.id)
+                    .before(&before)
+                    .after(&terms),
+            )
+            .await;
         Ok(terms.into())
     }
 
@@ -191,10 +233,26 @@ impl ContentAdminService for ContentAdminServiceImpl {
 
     async fn upsert_server_setting(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         input: UpsertServerSettingInput,
     ) -> anyhow::Result<ServerSetting> {
-        self.server_settings_repo.upsert(input).await
+        let before = self
+            .server_settings_repo
+            .get_all()
+            .await?
+            .into_iter()
+            .find(|setting| setting.setting_key == input.setting_key);
+        let setting = self.server_settings_repo.upsert(input).await?;
+
+        // The API key is masked in both states, so only note that it changed
+        let mut entry = AuditEntry::new("server_setting", "upsert", &setting.setting_key)
+            .before(&before)
+            .after(&setting);
+        if setting.setting_key == KEY_AI_OPENAI_API_KEY {
+            entry = entry.changed_secret("value");
+        }
+        self.audit.record(actor, entry).await;
+        Ok(setting)
     }
 
     async fn list_idps(&self) -> anyhow::Result<Vec<Idp>> {
@@ -207,23 +265,45 @@ impl ContentAdminService for ContentAdminServiceImpl {
 
     async fn create_idp(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         input: CreateIdpInput,
     ) -> anyhow::Result<Idp> {
-        self.idp_repo.create(input).await
+        let idp = self.idp_repo.create(input).await?;
+
+        self.audit
+            .record(actor, AuditEntry::new("idp", "create", idp.id).after(&idp))
+            .await;
+        Ok(idp)
     }
 
     async fn update_idp(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         id: Uuid,
         input: UpdateIdpInput,
     ) -> anyhow::Result<Idp> {
-        self.idp_repo.update(id, input).await
+        let before = self.idp_repo.get_by_id(id).await?;
+        let secret_changed = input.client_secret.is_some();
+        let idp = self.idp_repo.update(id, input).await?;
+
+        let mut entry = AuditEntry::new("idp", "update", id)
+            .before(&before)
+            .after(&idp);
+        if secret_changed {
+            entry = entry.changed_secret("client_secret");
+        }
+        self.audit.record(actor, entry).await;
+        Ok(idp)
     }
 
-    async fn delete_idp(&self, _actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
-        self.idp_repo.delete(id).await
+    async fn delete_idp(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        let before = self.idp_repo.get_by_id(id).await?;
+        self.idp_repo.delete(id).await?;
+
+        self.audit
+            .record(actor, AuditEntry::new("idp", "delete", id).before(&before))
+            .await;
+        Ok(())
     }
 
     async fn list_captcha_configs(&self) -> anyhow::Result<Vec<CaptchaConfig>> {
@@ -239,9 +319,18 @@ impl ContentAdminService for ContentAdminServiceImpl {
         actor: &AdminIdentity,
         input: CreateCaptchaConfigInput,
     ) -> anyhow::Result<CaptchaConfig> {
-        self.captcha_config_repo
+        let config = self
+            .captcha_config_repo
             .create(input, Some(actor.email.clone()))
-            .await
+            .await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("captcha_config", "create", config.id).after(&config),
+            )
+            .await;
+        Ok(config)
     }
 
     async fn update_captcha_config(
@@ -250,12 +339,33 @@ impl ContentAdminService for ContentAdminServiceImpl {
         id: Uuid,
         input: UpdateCaptchaConfigInput,
     ) -> anyhow::Result<CaptchaConfig> {
-        self.captcha_config_repo
+        let before = self.captcha_config_repo.get_by_id(id).await?;
+        let secret_changed = input.secret.is_some();
+        let config = self
+            .captcha_config_repo
             .update(id, input, Some(actor.email.clone()))
-            .await
+            .await?;
+
+        let mut entry = AuditEntry::new("captcha_config", "update", id)
+            .before(&before)
+            .after(&config);
+        if secret_changed {
+            entry = entry.changed_secret("secret");
+        }
+        self.audit.record(actor, entry).await;
+        Ok(config)
     }
 
-    async fn delete_captcha_config(&self, _actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
-        self.captcha_config_repo.delete(id).await
+    async fn delete_captcha_config(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        let before = self.captcha_config_repo.get_by_id(id).await?;
+        self.captcha_config_repo.delete(id).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("captcha_config", "delete", id).before(&before),
+            )
+            .await;
+        Ok(())
     }
 }
@@ -1,8 +1,13 @@
+pub mod admin_role_service;
 pub mod archive_service;
+pub mod audit_log_service;
 pub mod authed_token_service;
 pub mod board_service;
 pub mod content_admin_service;
+pub mod moderation_queue_service;
+pub mod moderation_rule_service;
 pub mod moderation_service;
+pub mod search_service;
 pub mod thread_service;
 pub mod user_service;
 
@@ -11,11 +16,16 @@ use std::sync::Arc;
 use crate::{AdminRepos, ContentRepos, ModerationRepos};
 
 use self::{
+    admin_role_service::{AdminRoleService, AdminRoleServiceImpl},
     archive_service::{AdminArchiveService, ArchiveServiceImpl},
+    audit_log_service::{AuditLogService, AuditLogServiceImpl},
     authed_token_service::{AuthedTokenService, AuthedTokenServiceImpl},
     board_service::{BoardService, BoardServiceImpl},
     content_admin_service::{ContentAdminService, ContentAdminServiceImpl},
+    moderation_queue_service::{ModerationQueueService, ModerationQueueServiceImpl},
+    moderation_rule_service::{ModerationRuleService, ModerationRuleServiceImpl},
     moderation_service::{ModerationService, ModerationServiceImpl},
+    search_service::{SearchService, SearchServiceImpl},
     thread_service::{ThreadService, ThreadServiceImpl},
     user_service::{UserService, UserServiceImpl},
 };
@@ -27,9 +37,14 @@ pub struct AppServiceContainer {
     pub thread: Arc<dyn ThreadService>,
     pub archive: Arc<dyn AdminArchiveService>,
     pub moderation: Arc<dyn ModerationService>,
+    pub moderation_queue: Arc<dyn ModerationQueueService>,
+    pub moderation_rule: Arc<dyn ModerationRuleService>,
+    pub search: Arc<dyn SearchService>,
     pub authed_token: Arc<dyn AuthedTokenService>,
     pub user: Arc<dyn UserService>,
     pub content_admin: Arc<dyn ContentAdminService>,
+    pub audit_log: Arc<dyn AuditLogService>,
+    pub admin_role: Arc<dyn AdminRoleService>,
 }
 
 impl AppServiceContainer {
@@ -39,35 +54,64 @@ impl AppServiceContainer {
         admin: AdminRepos,
         redis_conn: redis::aio::ConnectionManager,
     ) -> Self {
+        // Shared by every service so that each mutating action is recorded
+        let audit_log: Arc<dyn AuditLogService> =
+            Arc::new(AuditLogServiceImpl::new(admin.audit_log.clone()));
+
         Self {
-            board: Arc::new(BoardServiceImpl::new(content.board.clone())),
+            board: Arc::new(BoardServiceImpl::new(
+                content.board.clone(),
+                audit_log.clone(),
+            )),
             thread: Arc::new(ThreadServiceImpl::new(
                 content.thread.clone(),
                 content.response.clone(),
                 redis_conn.clone(),
+                audit_log.clone(),
             )),
             archive: Arc::new(ArchiveServiceImpl::new(
                 content.thread.clone(),
                 content.response.clone(),
                 content.archive.clone(),
+                audit_log.clone(),
             )),
             moderation: Arc::new(ModerationServiceImpl::new(
                 moderation.ng_word.clone(),
                 moderation.cap.clone(),
                 moderation.user_restriction.clone(),
+                audit_log.clone(),
+            )),
+            moderation_queue: Arc::new(ModerationQueueServiceImpl::new(
+                moderation.moderation_queue.clone(),
+                redis_conn.clone(),
+                audit_log.clone(),
             )),
+            moderation_rule: Arc::new(ModerationRuleServiceImpl::new(
+                moderation.moderation_rule.clone(),
+                content.response.clone(),
+                redis_conn.clone(),
+                audit_log.clone(),
+            )),
+            search: Arc::new(SearchServiceImpl::new(content.search.clone())),
             authed_token: Arc::new(AuthedTokenServiceImpl::new(
                 moderation.authed_token.clone(),
                 redis_conn,
+                audit_log.clone(),
             )),
-            user: Arc::new(UserServiceImpl::new(admin.user.clone())),
+            user: Arc::new(UserServiceImpl::new(admin.user.clone(), audit_log.clone())),
             content_admin: Arc::new(ContentAdminServiceImpl::new(
                 admin.notice.clone(),
                 admin.terms.clone(),
                 admin.server_settings.clone(),
                 admin.idp.clone(),
                 admin.captcha_config.clone(),
+                audit_log.clone(),
+            )),
+            admin_role: Arc::new(AdminRoleServiceImpl::new(
+                admin.admin_role.clone(),
+                audit_log.clone(),
             )),
+            audit_log,
         }
     }
 }
@@ -0,0 +1,264 @@
+use std::sync::Arc;
+
+use eddist_core::{
+    domain::{
+        moderation_queue::{HeldPost, ModerationQueueStatus},
+        res::ResView,
+    },
+    proto::{encode_creating_res, encode_creating_thread},
+    redis_keys::{CHANNEL_RES_CREATED, CHANNEL_THREAD_CREATED, thread_cache_key},
+    utils::{is_res_pub_enabled, is_thread_pub_enabled},
+};
+use redis::{AsyncCommands, Cmd, Value};
+use uuid::Uuid;
+
+use crate::{
+    auth::AdminIdentity,
+    error::ServiceError,
+    models::{HeldPost as HeldPostModel, HeldPostTypeSchema, ListHeldPostsQuery},
+    repository::moderation_queue_repository::{HeldPostRecord, ModerationQueueRepository},
+};
+
+use super::audit_log_service::{AuditEntry, AuditLogService};
+
+/// Same lifetime the bbs server gives to a new thread cache
+const THREAD_CACHE_TTL_SECS: i64 = 60 * 60 * 24 * 7;
+
+#[async_trait::async_trait]
+pub trait ModerationQueueService: Send + Sync {
+    /// `board_ids` restricts the listing to these boards, `None` lists every board
+    async fn get_held_posts(
+        &self,
+        query: ListHeldPostsQuery,
+        board_ids: Option<Vec<Uuid>>,
+    ) -> anyhow::Result<Vec<HeldPostModel>>;
+    async fn approve_held_post(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()>;
+    async fn reject_held_post(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()>;
+}
+
+pub struct ModerationQueueServiceImpl {
+    repo: Arc<dyn ModerationQueueRepository>,
+    redis_conn: redis::aio::ConnectionManager,
+    audit: Arc<dyn AuditLogService>,
+}
+
+impl ModerationQueueServiceImpl {
+    pub fn new(
+        repo: Arc<dyn ModerationQueueRepository>,
+        redis_conn: redis::aio::ConnectionManager,
+        audit: Arc<dyn AuditLogService>,
+    ) -> Self {
+        Self {
+            repo,
+            redis_conn,
+            audit,
+        }
+    }
+
+    async fn get_pending(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<HeldPostRecord> {
+        let record = self
+            .repo
+            .get_held_post(id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("held post not found: {id}")))?;
+        if !actor.boards.allows_id(record.board_id) {
+            return Err(ServiceError::Forbidden(format!(
+                "no access to board: {}",
+                record.board_key
+            ))
+            .into());
+        }
+        if record.status != ModerationQueueStatus::Pending {
+            return Err(ServiceError::BadRequest("held post is already reviewed".into()).into());
+        }
+        Ok(record)
+    }
+}
+
+fn to_model(record: HeldPostRecord) -> HeldPostModel {
+    let (post_type, thread_id, title, author_name, mail, body, author_id, ip_addr, authed_token_id) =
+        match record.post {
+            HeldPost::Response(res) => (
+                HeldPostTypeSchema::Response,
+                res.thread_id,
+                None,
+                res.name,
+                res.mail,
+                res.body,
+                res.author_ch5id,
+                res.ip_addr,
+                res.authed_token_id,
+            ),
+            HeldPost::Thread(th) => (
+                HeldPostTypeSchema::Thread,
+                th.thread_id,
+                Some(th.title),
+                th.name,
+                th.mail,
+                th.body,
+                th.author_ch5id,
+                th.ip_addr,
+                th.authed_token_id,
+            ),
+        };
+
+    HeldPostModel {
+        id: record.id,
+        board_id: record.board_id,
+        board_key: record.board_key,
+        thread_id,
+        thread_number: record.thread_number,
+        post_type,
+        reason: record.reason.into(),
+        ng_word_id: record.ng_word_id,
+        status: record.status.into(),
+        title,
+        author_name,
+        mail,
+        body,
+        author_id,
+        ip_addr,
+        authed_token_id,
+        created_at: record.created_at,
+        reviewed_at: record.reviewed_at,
+        reviewed_by: record.reviewed_by,
+    }
+}
+
+#[async_trait::async_trait]
+impl ModerationQueueService for ModerationQueueServiceImpl {
+    async fn get_held_posts(
+        &self,
+        query: ListHeldPostsQuery,
+        board_ids: Option<Vec<Uuid>>,
+    ) -> anyhow::Result<Vec<HeldPostModel>> {
+        let status = query
+            .status
+            .map_or(ModerationQueueStatus::Pending, Into::into);
+        let records = self
+            .repo
+            .get_held_posts(status, board_ids.as_deref())
+            .await?;
+
+        Ok(records.into_iter().map(to_model).collect())
+    }
+
+    async fn approve_held_post(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        let record = self.get_pending(actor, id).await?;
+        let mut redis_conn = self.redis_conn.clone();
+
+        match record.post {
+            HeldPost::Response(mut res) => {
+                let (Some(thread_number), Some(true)) =
+                    (record.thread_number, record.thread_active)
+                else {
+                    return Err(ServiceError::BadRequest(
+                        "the thread of the held response is no longer writable".into(),
+                    )
+                    .into());
+                };
+
+                // Claimed before touching the cache, so a concurrent approval cannot append
+                // the line twice
+                let mut approval = self.repo.claim_for_approval(id, &actor.email).await?;
+
+                let line = ResView {
+                    author_name: res.name.clone(),
+                    mail: res.mail.clone(),
+                    body: res.body.clone(),
+                    created_at: res.created_at,
+                    author_id: res.author_ch5id.clone(),
+                    is_abone: res.is_abone,
+                }
+                .get_sjis_bytes(&record.default_name, None)
+                .get_inner();
+                let key = thread_cache_key(&record.board_key, thread_number);
+
+                // Same as the bbs server: append only when the thread is cached, otherwise
+                // the response is sorted after the cached ones by its id
+                let Value::Int(order) = redis_conn
+                    .send_packed_command(&Cmd::rpush_exists(&key, &line))
+                    .await?
+                else {
+                    anyhow::bail!("failed to parse redis response");
+                };
+                res.res_order = if order > 0 { order as i32 } else { 10000 };
+
+                let persisted = match approval.insert_response(&res).await {
+                    Ok(()) => approval.commit().await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = persisted {
+                    if order > 0
+                        && let Err(undo_err) = redis_conn
+                            .send_packed_command(&Cmd::lrem(&key, -1, &line))
+                            .await
+                    {
+                        log::error!("failed to remove the line of an unapproved post: {undo_err}");
+                    }
+                    return Err(e);
+                }
+
+                // Same events as a post that was never held, for the persistence and
+                // streaming subscribers
+                if is_res_pub_enabled()
+                    && let Err(e) = redis_conn
+                        .publish::<_, _, ()>(CHANNEL_RES_CREATED, encode_creating_res(&res))
+                        .await
+                {
+                    log::error!("failed to publish an approved response: {e}");
+                }
+            }
+            HeldPost::Thread(th) => {
+                let mut approval = self.repo.claim_for_approval(id, &actor.email).await?;
+                approval.insert_thread(&th).await?;
+                approval.commit().await?;
+
+                let line = ResView {
+                    author_name: th.name.clone(),
+                    mail: th.mail.clone(),
+                    body: th.body.clone(),
+                    created_at: th.created_at,
+                    author_id: th.author_ch5id.clone(),
+                    is_abone: th.is_abone,
+                }
+                .get_sjis_bytes(&record.default_name, Some(&th.title));
+
+                // The thread is persisted already, without the cache it is served from the DB
+                let key = thread_cache_key(&record.board_key, th.unix_time);
+                if let Err(e) = redis::pipe()
+                    .atomic()
+                    .rpush(&key, line.get_inner())
+                    .expire(&key, THREAD_CACHE_TTL_SECS)
+                    .query_async::<()>(&mut redis_conn)
+                    .await
+                {
+                    log::error!("failed to cache an approved thread: {e}");
+                }
+
+                if is_thread_pub_enabled()
+                    && let Err(e) = redis_conn
+                        .publish::<_, _, ()>(CHANNEL_THREAD_CREATED, encode_creating_thread(&th))
+                        .await
+                {
+                    log::error!("failed to publish an approved thread: {e}");
+                }
+            }
+        }
+
+        self.audit
+            .record(actor, AuditEntry::new("held_post", "approve", id))
+            .await;
+        Ok(())
+    }
+
+    async fn reject_held_post(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        self.get_pending(actor, id).await?;
+        self.repo.reject(id, &actor.email).await?;
+
+        self.audit
+            .record(actor, AuditEntry::new("held_post", "reject", id))
+            .await;
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use eddist_core::{
+    domain::{
+        moderation_rule::{ModerationRule as CoreModerationRule, ModerationRuleAction},
+        res::ResView,
+    },
+    redis_keys::{
+        authed_token_internal_level_cap_key, authed_token_suspended_key, thread_cache_key,
+        unsafe_threads_key,
+    },
+};
+use redis::{AsyncCommands, Cmd};
+use uuid::Uuid;
+
+use crate::{
+    auth::AdminIdentity,
+    error::ServiceError,
+    models::{
+        CreateModerationRuleInput, ListModerationActionLogsQuery, ModerationActionLog,
+        ModerationRule, UpdateModerationRuleInput,
+    },
+    repository::{
+        admin_response_repository::AdminResponseRepository,
+        moderation_rule_repository::ModerationRuleRepository,
+    },
+};
+
+use super::audit_log_service::{AuditEntry, AuditLogService};
+
+const DEFAULT_ACTION_LOG_LIMIT: u32 = 100;
+
+#[async_trait::async_trait]
+pub trait ModerationRuleService: Send + Sync {
+    async fn get_rules(&self) -> anyhow::Result<Vec<ModerationRule>>;
+    async fn create_rule(
+        &self,
+        actor: &AdminIdentity,
+        input: CreateModerationRuleInput,
+    ) -> anyhow::Result<ModerationRule>;
+    async fn update_rule(
+        &self,
+        actor: &AdminIdentity,
+        id: Uuid,
+        input: UpdateModerationRuleInput,
+    ) -> anyhow::Result<ModerationRule>;
+    async fn delete_rule(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()>;
+    /// `board_ids` restricts the listing to these boards, `None` lists every board
+    async fn get_action_logs(
+        &self,
+        query: ListModerationActionLogsQuery,
+        board_ids: Option<Vec<Uuid>>,
+    ) -> anyhow::Result<Vec<ModerationActionLog>>;
+    async fn revert_action(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()>;
+}
+
+pub struct ModerationRuleServiceImpl {
+    repo: Arc<dyn ModerationRuleRepository>,
+    response_repo: Arc<dyn AdminResponseRepository>,
+    redis_conn: redis::aio::ConnectionManager,
+    audit: Arc<dyn AuditLogService>,
+}
+
+impl ModerationRuleServiceImpl {
+    pub fn new(
+        repo: Arc<dyn ModerationRuleRepository>,
+        response_repo: Arc<dyn AdminResponseRepository>,
+        redis_conn: redis::aio::ConnectionManager,
+        audit: Arc<dyn AuditLogService>,
+    ) -> Self {
+        Self {
+            repo,
+            response_repo,
+            redis_conn,
+            audit,
+        }
+    }
+
+    /// Clears the abone flag of a response and rewrites its line in the dat cache
+    async fn unabone_response(&self, res_id: Uuid) -> anyhow::Result<()> {
+        let (res, default_name, board_key, thread_number, thread_title) =
+            self.response_repo.get_res(res_id).await?;
+        self.response_repo
+            .update_res(res_id, None, None, None, Some(false))
+            .await?;
+
+        let res_view = ResView {
+            author_name: res.author_name.unwrap_or(default_name.clone()),
+            mail: res.mail.unwrap_or_default(),
+            body: res.body,
+            created_at: res.created_at,
+            author_id: res.author_id,
+            is_abone: false,
+        }
+        .get_sjis_bytes(
+            &default_name,
+            thread_title.as_deref().filter(|_| res.res_order == 1),
+        );
+
+        // The cache may already be gone (e.g. archived), in which case the database is enough
+        let key = thread_cache_key(&board_key, thread_number);
+        let mut conn = self.redis_conn.clone();
+        if conn.exists::<_, bool>(&key).await? {
+            conn.send_packed_command(&Cmd::lset(
+                key,
+                res.res_order as isize - 1,
+                res_view.get_inner(),
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Board moderators may only manage the rules of their boards, not the site-wide ones
+fn check_rule_board(actor: &AdminIdentity, board_id: Option<Uuid>) -> Result<(), ServiceError> {
+    let allowed = match board_id {
+        Some(board_id) => actor.boards.allows_id(board_id),
+        None => !actor.boards.is_restricted(),
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(ServiceError::Forbidden(
+            "no access to the board of this moderation rule".into(),
+        ))
+    }
+}
+
+fn validate_rule(rule: &CoreModerationRule) -> Result<(), ServiceError> {
+    if rule.name.trim().is_empty() {
+        return Err(ServiceError::BadRequest("name must not be empty".into()));
+    }
+    if rule.category.trim().is_empty() {
+        return Err(ServiceError::BadRequest(
+            "category must not be empty".into(),
+        ));
+    }
+    if !(0.0..=1.0).contains(&rule.threshold) {
+        return Err(ServiceError::BadRequest(
+            "threshold must be between 0 and 1".into(),
+        ));
+    }
+    if rule.action.is_timed() && rule.duration_seconds == 0 {
+        return Err(ServiceError::BadRequest(format!(
+            "duration_seconds is required for {}",
+            rule.action
+        )));
+    }
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl ModerationRuleService for ModerationRuleServiceImpl {
+    async fn get_rules(&self) -> anyhow::Result<Vec<ModerationRule>> {
+        let rules = self.repo.get_rules().await?;
+        Ok(rules.into_iter().map(Into::into).collect())
+    }
+
+    async fn create_rule(
+        &self,
+        actor: &AdminIdentity,
+        input: CreateModerationRuleInput,
+    ) -> anyhow::Result<ModerationRule> {
+        let now = Utc::now();
+        let rule = CoreModerationRule {
+            id: Uuid::now_v7(),
+            name: input.name,
+            board_id: input.board_id,
+            category: input.category,
+            threshold: input.threshold,
+            action: input.action.into(),
+            duration_seconds: input.duration_seconds,
+            internal_level: input.internal_level,
+            enabled: input.enabled,
+            created_at: now,
+            updated_at: now,
+        };
+        check_rule_board(actor, rule.board_id)?;
+        validate_rule(&rule)?;
+
+        self.repo.create_rule(&rule).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("moderation_rule", "create", rule.id).after(&rule),
+            )
+            .await;
+        Ok(rule.into())
+    }
+
+    async fn update_rule(
+        &self,
+        actor: &AdminIdentity,
+        id: Uuid,
+        input: UpdateModerationRuleInput,
+    ) -> anyhow::Result<ModerationRule> {
+        let mut rule =
+            self.repo.get_rule(id).await?.ok_or_else(|| {
+                ServiceError::NotFound(format!("moderation rule not found: {id}"))
+            })?;
+        check_rule_board(actor, rule.board_id)?;
+        let before = rule.clone();
+
+        if let Some(name) = input.name {
+            rule.name = name;
+        }
+        if let Some(board_id) = input.board_id {
+            rule.board_id = board_id;
+        }
+        if let Some(category) = input.category {
+            rule.category = category;
+        }
+        if let Some(threshold) = input.threshold {
+            rule.threshold = threshold;
+        }
+        if let Some(action) = input.action {
+            rule.action = action.into();
+        }
+        if let Some(duration_seconds) = input.duration_seconds {
+            rule.duration_seconds = duration_seconds;
+        }
+        if let Some(internal_level) = input.internal_level {
+            rule.internal_level = internal_level;
+        }
+        if let Some(enabled) = input.enabled {
+            rule.enabled = enabled;
+        }
+        rule.updated_at = Utc::now();
+        check_rule_board(actor, rule.board_id)?;
+        validate_rule(&rule)?;
+
+        self.repo.update_rule(&rule).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("moderation_rule", "update", id)
+                    .before(&before)
+                    .after(&rule),
+            )
+            .await;
+        Ok(rule.into())
+    }
+
+    async fn delete_rule(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        let before = self.repo.get_rule(id).await?;
+        if let Some(rule) = &before {
+            check_rule_board(actor, rule.board_id)?;
+        }
+        self.repo.delete_rule(id).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("moderation_rule", "delete", id).before(&before),
+            )
+            .await;
+        Ok(())
+    }
+
+    async fn get_action_logs(
+        &self,
+        query: ListModerationActionLogsQuery,
+        board_ids: Option<Vec<Uuid>>,
+    ) -> anyhow::Result<Vec<ModerationActionLog>> {
+        let logs = self
+            .repo
+            .get_action_logs(
+                board_ids.as_deref(),
+                query.reverted,
+                query.limit.unwrap_or(DEFAULT_ACTION_LOG_LIMIT),
+            )
+            .await?;
+        Ok(logs.into_iter().map(Into::into).collect())
+    }
+
+    async fn revert_action(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        let log =
+            self.repo.get_action_log(id).await?.ok_or_else(|| {
+                ServiceError::NotFound(format!("moderation action not found: {id}"))
+            })?;
+        if !actor.boards.allows_id(log.board_id) {
+            return Err(ServiceError::Forbidden(
+                "no access to the board of this moderation action".into(),
+            )
+            .into());
+        }
+        if log.reverted_at.is_some() {
+            return Err(ServiceError::BadRequest("action is already reverted".into()).into());
+        }
+
+        let mut conn = self.redis_conn.clone();
+        match log.action {
+            ModerationRuleAction::AboneResponse => {
+                self.unabone_response(log.response_id).await?;
+            }
+            ModerationRuleAction::SuspendToken => {
+                conn.del::<_, ()>(authed_token_suspended_key(&log.authed_token_id.to_string()))
+                    .await?;
+            }
+            ModerationRuleAction::LowerInternalLevel => {
+                conn.del::<_, ()>(authed_token_internal_level_cap_key(
+                    &log.authed_token_id.to_string(),
+                ))
+                .await?;
+            }
+            ModerationRuleAction::MarkThreadUnsafe => {
+                conn.srem::<_, _, ()>(unsafe_threads_key(log.board_id), log.thread_number)
+                    .await?;
+            }
+        }
+
+        self.repo.mark_reverted(id, &actor.email).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("moderation_action", "revert", id).before(&log),
+            )
+            .await;
+        Ok(())
+    }
+}
@@ -1,22 +1,41 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use chrono::{DateTime, Utc};
-use eddist_core::domain::user_restriction::{
-    CreateUserRestrictionRuleInput, UpdateUserRestrictionRuleInput, UserRestrictionRule,
+use eddist_core::{
+    domain::{
+        ng_word::{CompiledNgWord, NgWordAction, NgWordMatchType, NgWordTarget},
+        user_restriction::{
+            CreateUserRestrictionRuleInput, UpdateUserRestrictionRuleInput, UserRestrictionRule,
+        },
+        user_restriction_bulk::{
+            CreateUserRestrictionSubscriptionInput, RestrictionImportDefaults,
+            RestrictionImportDuplicate, RestrictionImportError, RestrictionRuleFormat,
+            UpdateUserRestrictionSubscriptionInput, UserRestrictionSubscription,
+            export_restriction_rules, plan_restriction_import,
+        },
+    },
+    user_restriction_hits::RestrictionRuleHits,
 };
+use serde::Serialize;
+use serde_json::json;
 use uuid::Uuid;
 
 use crate::{
     auth::AdminIdentity,
+    error::ServiceError,
     models::{
-        Cap, CreationCapInput, CreationNgWordInput, NgWord, UpdateCapInput, UpdateNgWordInput,
+        Cap, CreationCapInput, CreationNgWordInput, NgWord, RestrictionRuleSortSchema,
+        UpdateCapInput, UpdateNgWordInput,
     },
     repository::{
-        cap_repository::CapRepository, ngword_repository::NgWordRepository,
+        cap_repository::CapRepository,
+        ngword_repository::{NgWordChanges, NgWordRepository},
         user_restriction_repository::UserRestrictionRepository,
     },
 };
 
+use super::audit_log_service::{AuditEntry, AuditLogService};
+
 #[async_trait::async_trait]
 pub trait ModerationService: Send + Sync {
     // NG words
@@ -48,15 +67,20 @@ pub trait ModerationService: Send + Sync {
     ) -> anyhow::Result<Cap>;
     async fn delete_cap(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()>;
     // Restriction rules
-    async fn get_restriction_rules(&self) -> anyhow::Result<Vec<UserRestrictionRule>>;
+    async fn get_restriction_rules(
+        &self,
+        sort: RestrictionRuleSort,
+        descending: bool,
+    ) -> anyhow::Result<Vec<RestrictionRuleWithHits>>;
     async fn get_restriction_rule(&self, id: Uuid) -> anyhow::Result<Option<UserRestrictionRule>>;
+    async fn get_restriction_rule_hits(
+        &self,
+        id: Uuid,
+    ) -> anyhow::Result<Option<RestrictionRuleHits>>;
     async fn create_restriction_rule(
         &self,
         actor: &AdminIdentity,
-        name: String,
-        rule_type: eddist_core::domain::user_restriction::RestrictionRuleType,
-        rule_value: String,
-        expires_at: Option<DateTime<Utc>>,
+        input: CreateUserRestrictionRuleInput,
     ) -> anyhow::Result<UserRestrictionRule>;
     async fn update_restriction_rule(
         &self,
@@ -64,12 +88,103 @@ pub trait ModerationService: Send + Sync {
         input: UpdateUserRestrictionRuleInput,
     ) -> anyhow::Result<()>;
     async fn delete_restriction_rule(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()>;
+    async fn export_restriction_rules(
+        &self,
+        format: RestrictionRuleFormat,
+    ) -> anyhow::Result<String>;
+    /// `defaults.created_by_email` is replaced with the actor's
+    async fn import_restriction_rules(
+        &self,
+        actor: &AdminIdentity,
+        format: RestrictionRuleFormat,
+        content: &str,
+        defaults: RestrictionImportDefaults,
+        dry_run: bool,
+    ) -> anyhow::Result<RestrictionImportResult>;
+    // Blocklist subscriptions
+    async fn get_restriction_subscriptions(
+        &self,
+    ) -> anyhow::Result<Vec<UserRestrictionSubscription>>;
+    async fn create_restriction_subscription(
+        &self,
+        actor: &AdminIdentity,
+        input: CreateUserRestrictionSubscriptionInput,
+    ) -> anyhow::Result<UserRestrictionSubscription>;
+    async fn update_restriction_subscription(
+        &self,
+        actor: &AdminIdentity,
+        input: UpdateUserRestrictionSubscriptionInput,
+    ) -> anyhow::Result<UserRestrictionSubscription>;
+    async fn delete_restriction_subscription(
+        &self,
+        actor: &AdminIdentity,
+        id: Uuid,
+    ) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestrictionImportResult {
+    pub dry_run: bool,
+    /// Rules created, or with nil ids those a dry run would create
+    pub rules: Vec<UserRestrictionRule>,
+    pub duplicates: Vec<RestrictionImportDuplicate>,
+    pub errors: Vec<RestrictionImportError>,
+}
+
+/// Order of the restriction rule list, ties going by creation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RestrictionRuleSort {
+    #[default]
+    CreatedAt,
+    HitCount,
+    /// Rules never hit come before every other rule
+    LastHitAt,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestrictionRuleWithHits {
+    #[serde(flatten)]
+    pub rule: UserRestrictionRule,
+    /// As of the last flush by `eddist-cron flush-restriction-hits`
+    pub hit_count: u64,
+    pub last_hit_at: Option<DateTime<Utc>>,
+}
+
+impl From<RestrictionRuleSortSchema> for RestrictionRuleSort {
+    fn from(value: RestrictionRuleSortSchema) -> Self {
+        match value {
+            RestrictionRuleSortSchema::CreatedAt => RestrictionRuleSort::CreatedAt,
+            RestrictionRuleSortSchema::HitCount => RestrictionRuleSort::HitCount,
+            RestrictionRuleSortSchema::LastHitAt => RestrictionRuleSort::LastHitAt,
+        }
+    }
+}
+
+fn sort_restriction_rules(
+    rules: &mut [RestrictionRuleWithHits],
+    sort: RestrictionRuleSort,
+    descending: bool,
+) {
+    rules.sort_by(|a, b| {
+        let ordering = match sort {
+            RestrictionRuleSort::CreatedAt => std::cmp::Ordering::Equal,
+            RestrictionRuleSort::HitCount => a.hit_count.cmp(&b.hit_count),
+            RestrictionRuleSort::LastHitAt => a.last_hit_at.cmp(&b.last_hit_at),
+        }
+        .then_with(|| a.rule.created_at.cmp(&b.rule.created_at));
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
 }
 
 pub struct ModerationServiceImpl {
     ng_word_repo: Arc<dyn NgWordRepository>,
     cap_repo: Arc<dyn CapRepository>,
     user_restriction_repo: Arc<dyn UserRestrictionRepository>,
+    audit: Arc<dyn AuditLogService>,
 }
 
 impl ModerationServiceImpl {
@@ -77,13 +192,59 @@ impl ModerationServiceImpl {
         ng_word_repo: Arc<dyn NgWordRepository>,
         cap_repo: Arc<dyn CapRepository>,
         user_restriction_repo: Arc<dyn UserRestrictionRepository>,
+        audit: Arc<dyn AuditLogService>,
     ) -> Self {
         Self {
             ng_word_repo,
             cap_repo,
             user_restriction_repo,
+            audit,
         }
     }
+
+    async fn get_ng_word(&self, id: Uuid) -> anyhow::Result<NgWord> {
+        self.ng_word_repo
+            .get_ng_words()
+            .await?
+            .into_iter()
+            .find(|ng_word| ng_word.id == id)
+            .ok_or_else(|| ServiceError::NotFound(format!("ng word not found: {id}")).into())
+    }
+
+    async fn get_cap(&self, id: Uuid) -> anyhow::Result<Cap> {
+        self.cap_repo
+            .get_caps()
+            .await?
+            .into_iter()
+            .find(|cap| cap.id == id)
+            .ok_or_else(|| ServiceError::NotFound(format!("cap not found: {id}")).into())
+    }
+}
+
+/// Rejects NG words that would be skipped by the matcher on the bbs side
+fn validate_ng_word(
+    word: &str,
+    match_type: NgWordMatchType,
+    targets: &[NgWordTarget],
+    action: NgWordAction,
+    penalty_seconds: u32,
+) -> Result<(), ServiceError> {
+    if word.trim().is_empty() {
+        return Err(ServiceError::BadRequest("word must not be empty".into()));
+    }
+    if targets.is_empty() {
+        return Err(ServiceError::BadRequest(
+            "at least one target must be specified".into(),
+        ));
+    }
+    if action == NgWordAction::Penalize && penalty_seconds == 0 {
+        return Err(ServiceError::BadRequest(
+            "penalty_seconds must be positive for the PENALIZE action".into(),
+        ));
+    }
+    CompiledNgWord::compile(Uuid::nil(), "", word, match_type, targets)
+        .map_err(|e| ServiceError::BadRequest(format!("invalid regex pattern: {e}")))?;
+    Ok(())
 }
 
 #[async_trait::async_trait]
@@ -94,32 +255,110 @@ impl ModerationService for ModerationServiceImpl {
 
     async fn create_ng_word(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         input: CreationNgWordInput,
     ) -> anyhow::Result<NgWord> {
-        self.ng_word_repo
-            .create_ng_word(&input.name, &input.word)
-            .await
+        let match_type = input.match_type.map(Into::into).unwrap_or_default();
+        let targets = input.targets.map_or_else(
+            || NgWordTarget::DEFAULT.to_vec(),
+            |targets| targets.into_iter().map(Into::into).collect(),
+        );
+        let action = input.action.map(Into::into).unwrap_or_default();
+        let penalty_seconds = input.penalty_seconds.unwrap_or_default();
+        validate_ng_word(&input.word, match_type, &targets, action, penalty_seconds)?;
+
+        let ng_word = self
+            .ng_word_repo
+            .create_ng_word(
+                &input.name,
+                &input.word,
+                match_type,
+                &targets,
+                action,
+                penalty_seconds,
+            )
+            .await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("ng_word", "create", ng_word.id).after(&ng_word),
+            )
+            .await;
+        Ok(ng_word)
     }
 
     async fn update_ng_word(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         id: Uuid,
         input: UpdateNgWordInput,
     ) -> anyhow::Result<NgWord> {
-        self.ng_word_repo
+        let match_type = input.match_type.map(NgWordMatchType::from);
+        let targets = input.targets.map(|targets| {
+            targets
+                .into_iter()
+                .map(NgWordTarget::from)
+                .collect::<Vec<_>>()
+        });
+        let action = input.action.map(NgWordAction::from);
+
+        let current = self.get_ng_word(id).await?;
+        if input.word.is_some()
+            || match_type.is_some()
+            || targets.is_some()
+            || action.is_some()
+            || input.penalty_seconds.is_some()
+        {
+            validate_ng_word(
+                input.word.as_deref().unwrap_or(&current.word),
+                match_type.unwrap_or(current.match_type.into()),
+                &targets
+                    .clone()
+                    .unwrap_or_else(|| current.targets.iter().copied().map(Into::into).collect()),
+                action.unwrap_or(current.action.into()),
+                input.penalty_seconds.unwrap_or(current.penalty_seconds),
+            )?;
+        }
+
+        let ng_word = self
+            .ng_word_repo
             .update_ng_word(
                 id,
-                input.name.as_deref(),
-                input.word.as_deref(),
-                input.board_ids,
+                NgWordChanges {
+                    name: input.name.as_deref(),
+                    word: input.word.as_deref(),
+                    match_type,
+                    targets: targets.as_deref(),
+                    action,
+                    penalty_seconds: input.penalty_seconds,
+                    board_ids: input.board_ids,
+                },
             )
-            .await
+            .await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("ng_word", "update", id)
+                    .before(&current)
+                    .after(&ng_word),
+            )
+            .await;
+        Ok(ng_word)
     }
 
-    async fn delete_ng_word(&self, _actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
-        self.ng_word_repo.delete_ng_word(id).await
+    async fn delete_ng_word(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        let current = self.get_ng_word(id).await?;
+        self.ng_word_repo.delete_ng_word(id).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("ng_word", "delete", id).before(&current),
+            )
+            .await;
+        Ok(())
     }
 
     async fn get_caps(&self) -> anyhow::Result<Vec<Cap>> {
@@ -128,32 +367,41 @@ impl ModerationService for ModerationServiceImpl {
 
     async fn create_cap(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         input: CreationCapInput,
     ) -> anyhow::Result<Cap> {
         let tinker_secret = std::env::var("TINKER_SECRET").map_err(|_| {
             crate::error::ServiceError::ConfigError("TINKER_SECRET not configured".into())
         })?;
-        self.cap_repo
+        let cap = self
+            .cap_repo
             .create_cap(
                 &input.name,
                 &input.description,
                 &eddist_core::domain::cap::calculate_cap_hash(&input.password, &tinker_secret),
             )
-            .await
+            .await?;
+
+        self.audit
+            .record(actor, AuditEntry::new("cap", "create", cap.id).after(&cap))
+            .await;
+        Ok(cap)
     }
 
     async fn update_cap(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         id: Uuid,
         input: UpdateCapInput,
     ) -> anyhow::Result<Cap> {
+        let current = self.get_cap(id).await?;
+        let password_changed = input.password.is_some();
         let hashed_password = input.password.map(|p| {
             let secret = std::env::var("TINKER_SECRET").unwrap_or_default();
             eddist_core::domain::cap::calculate_cap_hash(&p, &secret)
         });
-        self.cap_repo
+        let cap = self
+            .cap_repo
             .update_cap(
                 id,
                 input.name.as_deref(),
@@ -161,56 +409,282 @@ impl ModerationService for ModerationServiceImpl {
                 hashed_password.as_deref(),
                 input.board_ids,
             )
-            .await
+            .await?;
+
+        let mut entry = AuditEntry::new("cap", "update", id)
+            .before(&current)
+            .after(&cap);
+        if password_changed {
+            entry = entry.changed_secret("password");
+        }
+        self.audit.record(actor, entry).await;
+        Ok(cap)
     }
 
-    async fn delete_cap(&self, _actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
-        self.cap_repo.delete_cap(id).await
+    async fn delete_cap(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        let current = self.get_cap(id).await?;
+        self.cap_repo.delete_cap(id).await?;
+
+        self.audit
+            .record(actor, AuditEntry::new("cap", "delete", id).before(&current))
+            .await;
+        Ok(())
     }
 
-    async fn get_restriction_rules(&self) -> anyhow::Result<Vec<UserRestrictionRule>> {
-        Ok(self
+    async fn get_restriction_rules(
+        &self,
+        sort: RestrictionRuleSort,
+        descending: bool,
+    ) -> anyhow::Result<Vec<RestrictionRuleWithHits>> {
+        let rules = self
             .user_restriction_repo
             .get_all_rules()
             .await
-            .unwrap_or_default())
+            .unwrap_or_default();
+        let hits = self
+            .user_restriction_repo
+            .get_all_rule_hits()
+            .await?
+            .into_iter()
+            .map(|hits| (hits.rule_id, hits))
+            .collect::<HashMap<_, _>>();
+
+        let mut rules = rules
+            .into_iter()
+            .map(|rule| {
+                let hits = hits.get(&rule.id);
+                RestrictionRuleWithHits {
+                    hit_count: hits.map_or(0, |hits| hits.hit_count),
+                    last_hit_at: hits.and_then(|hits| hits.last_hit_at),
+                    rule,
+                }
+            })
+            .collect::<Vec<_>>();
+        sort_restriction_rules(&mut rules, sort, descending);
+        Ok(rules)
     }
 
     async fn get_restriction_rule(&self, id: Uuid) -> anyhow::Result<Option<UserRestrictionRule>> {
         self.user_restriction_repo.get_rule_by_id(id).await
     }
 
+    async fn get_restriction_rule_hits(
+        &self,
+        id: Uuid,
+    ) -> anyhow::Result<Option<RestrictionRuleHits>> {
+        self.user_restriction_repo.get_rule_hits(id).await
+    }
+
     async fn create_restriction_rule(
         &self,
         actor: &AdminIdentity,
-        name: String,
-        rule_type: eddist_core::domain::user_restriction::RestrictionRuleType,
-        rule_value: String,
-        expires_at: Option<DateTime<Utc>>,
+        input: CreateUserRestrictionRuleInput,
     ) -> anyhow::Result<UserRestrictionRule> {
         let input = CreateUserRestrictionRuleInput {
-            name,
-            rule_type,
-            rule_value,
-            expires_at,
             created_by_email: actor.email.clone(),
+            ..input
         };
-        self.user_restriction_repo.create_rule(input).await
+        input
+            .clone()
+            .into_rule(Uuid::nil(), Utc::now())
+            .validate()
+            .map_err(ServiceError::BadRequest)?;
+        let rule = self.user_restriction_repo.create_rule(input).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("restriction_rule", "create", rule.id).after(&rule),
+            )
+            .await;
+        Ok(rule)
     }
 
     async fn update_restriction_rule(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         input: UpdateUserRestrictionRuleInput,
     ) -> anyhow::Result<()> {
-        self.user_restriction_repo.update_rule(input).await
+        let id = input.id;
+        let before = self
+            .user_restriction_repo
+            .get_rule_by_id(id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("restriction rule not found: {id}")))?;
+        input
+            .apply(before.clone())
+            .validate()
+            .map_err(ServiceError::BadRequest)?;
+        self.user_restriction_repo.update_rule(input).await?;
+        let after = self.user_restriction_repo.get_rule_by_id(id).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("restriction_rule", "update", id)
+                    .before(&before)
+                    .after(&after),
+            )
+            .await;
+        Ok(())
+    }
+
+    async fn delete_restriction_rule(&self, actor: &AdminIdentity, id: Uuid) -> anyhow::Result<()> {
+        let before = self.user_restriction_repo.get_rule_by_id(id).await?;
+        self.user_restriction_repo.delete_rule(id).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("restriction_rule", "delete", id).before(&before),
+            )
+            .await;
+        Ok(())
+    }
+
+    async fn export_restriction_rules(
+        &self,
+        format: RestrictionRuleFormat,
+    ) -> anyhow::Result<String> {
+        let rules = self.user_restriction_repo.get_all_rules().await?;
+        export_restriction_rules(format, &rules)
     }
 
-    async fn delete_restriction_rule(
+    async fn import_restriction_rules(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
+        format: RestrictionRuleFormat,
+        content: &str,
+        defaults: RestrictionImportDefaults,
+        dry_run: bool,
+    ) -> anyhow::Result<RestrictionImportResult> {
+        let defaults = RestrictionImportDefaults {
+            created_by_email: actor.email.clone(),
+            ..defaults
+        };
+        // Expired rules no longer restrict anything, so importing them again is not a duplicate
+        let existing = self
+            .user_restriction_repo
+            .get_all_rules()
+            .await?
+            .into_iter()
+            .filter(|rule| !rule.is_expired())
+            .collect::<Vec<_>>();
+        let plan = plan_restriction_import(format, content, &defaults, &existing)
+            .map_err(ServiceError::BadRequest)?;
+
+        let rules = if dry_run {
+            let now = Utc::now();
+            plan.rules
+                .into_iter()
+                .map(|input| input.into_rule(Uuid::nil(), now))
+                .collect()
+        } else if plan.rules.is_empty() {
+            Vec::new()
+        } else {
+            let rules = self.user_restriction_repo.create_rules(plan.rules).await?;
+            self.audit
+                .record(
+                    actor,
+                    AuditEntry::new("restriction_rule", "import", format).after(&json!({
+                        "rule_ids": rules.iter().map(|rule| rule.id).collect::<Vec<_>>(),
+                        "duplicates": plan.duplicates.len(),
+                        "errors": plan.errors.len(),
+                    })),
+                )
+                .await;
+            rules
+        };
+
+        Ok(RestrictionImportResult {
+            dry_run,
+            rules,
+            duplicates: plan.duplicates,
+            errors: plan.errors,
+        })
+    }
+
+    async fn get_restriction_subscriptions(
+        &self,
+    ) -> anyhow::Result<Vec<UserRestrictionSubscription>> {
+        self.user_restriction_repo.get_all_subscriptions().await
+    }
+
+    async fn create_restriction_subscription(
+        &self,
+        actor: &AdminIdentity,
+        input: CreateUserRestrictionSubscriptionInput,
+    ) -> anyhow::Result<UserRestrictionSubscription> {
+        let subscription = CreateUserRestrictionSubscriptionInput {
+            created_by_email: actor.email.clone(),
+            ..input
+        }
+        .into_subscription(Uuid::now_v7(), Utc::now());
+        subscription.validate().map_err(ServiceError::BadRequest)?;
+        self.user_restriction_repo
+            .create_subscription(&subscription)
+            .await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("restriction_subscription", "create", subscription.id)
+                    .after(&subscription),
+            )
+            .await;
+        Ok(subscription)
+    }
+
+    async fn update_restriction_subscription(
+        &self,
+        actor: &AdminIdentity,
+        input: UpdateUserRestrictionSubscriptionInput,
+    ) -> anyhow::Result<UserRestrictionSubscription> {
+        let id = input.id;
+        let before = self
+            .user_restriction_repo
+            .get_subscription_by_id(id)
+            .await?
+            .ok_or_else(|| {
+                ServiceError::NotFound(format!("restriction subscription not found: {id}"))
+            })?;
+        let subscription = input.apply(before.clone());
+        subscription.validate().map_err(ServiceError::BadRequest)?;
+        self.user_restriction_repo
+            .update_subscription(&subscription)
+            .await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("restriction_subscription", "update", id)
+                    .before(&before)
+                    .after(&subscription),
+            )
+            .await;
+        Ok(subscription)
+    }
+
+    async fn delete_restriction_subscription(
+        &self,
+        actor: &AdminIdentity,
         id: Uuid,
     ) -> anyhow::Result<()> {
-        self.user_restriction_repo.delete_rule(id).await
+        let before = self
+            .user_restriction_repo
+            .get_subscription_by_id(id)
+            .await?
+            .ok_or_else(|| {
+                ServiceError::NotFound(format!("restriction subscription not found: {id}"))
+            })?;
+        self.user_restriction_repo.delete_subscription(id).await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("restriction_subscription", "delete", id).before(&before),
+            )
+            .await;
+        Ok(())
     }
 }
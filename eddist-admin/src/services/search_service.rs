@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use eddist_core::domain::search::{MAX_SEARCH_QUERY_CHARS, matches_search_query, search_ngrams};
+use uuid::Uuid;
+
+use crate::{
+    error::ServiceError,
+    models::{SearchHit, SearchPostsQuery},
+    repository::search_repository::{AdminSearchRepository, SearchPostsParams},
+};
+
+const DEFAULT_SEARCH_LIMIT: u32 = 100;
+const MAX_SEARCH_LIMIT: u32 = 500;
+/// Candidates fetched per requested result when searching by words, as some of them only
+/// have the n-grams of the query without containing it
+const SEARCH_CANDIDATE_FACTOR: u32 = 4;
+
+#[async_trait::async_trait]
+pub trait SearchService: Send + Sync {
+    /// `board_ids` restricts the search to these boards, `None` searches every board
+    async fn search_posts(
+        &self,
+        query: SearchPostsQuery,
+        board_ids: Option<Vec<Uuid>>,
+    ) -> anyhow::Result<Vec<SearchHit>>;
+}
+
+pub struct SearchServiceImpl {
+    repo: Arc<dyn AdminSearchRepository>,
+}
+
+impl SearchServiceImpl {
+    pub fn new(repo: Arc<dyn AdminSearchRepository>) -> Self {
+        Self { repo }
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchService for SearchServiceImpl {
+    async fn search_posts(
+        &self,
+        query: SearchPostsQuery,
+        board_ids: Option<Vec<Uuid>>,
+    ) -> anyhow::Result<Vec<SearchHit>> {
+        let q = query.q.as_deref().filter(|q| !q.trim().is_empty());
+        let ngrams = match q {
+            Some(q) if q.chars().count() > MAX_SEARCH_QUERY_CHARS => {
+                return Err(ServiceError::BadRequest("query too long".into()).into());
+            }
+            Some(q) => {
+                let ngrams = search_ngrams(q);
+                if ngrams.is_empty() {
+                    return Err(ServiceError::BadRequest("query too short".into()).into());
+                }
+                ngrams
+            }
+            None => Vec::new(),
+        };
+        if ngrams.is_empty()
+            && query.author_id.is_none()
+            && query.authed_token_id.is_none()
+            && query.ip_addr.is_none()
+        {
+            return Err(ServiceError::BadRequest(
+                "either a query, an author ID, a token or an IP address is required".into(),
+            )
+            .into());
+        }
+
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_SEARCH_LIMIT);
+        let hits = self
+            .repo
+            .search_posts(SearchPostsParams {
+                ngrams: &ngrams,
+                board_ids: board_ids.as_deref(),
+                author_id: query.author_id.as_deref(),
+                authed_token_id: query.authed_token_id,
+                ip_addr: query.ip_addr.as_deref(),
+                include_abone: query.include_abone,
+                limit: if q.is_some() {
+                    limit * SEARCH_CANDIDATE_FACTOR
+                } else {
+                    limit
+                },
+            })
+            .await?;
+
+        Ok(hits
+            .into_iter()
+            .filter(|hit| {
+                q.is_none_or(|q| {
+                    matches_search_query(&hit.body, q)
+                        || (hit.res_order == 1 && matches_search_query(&hit.thread_title, q))
+                })
+            })
+            .take(limit as usize)
+            .collect())
+    }
+}
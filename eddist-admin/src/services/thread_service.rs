@@ -1,18 +1,45 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+};
 
+use chrono::{Duration, NaiveTime, Utc};
+use eddist_core::{
+    domain::{
+        ip_addr::ReducedIpAddr,
+        res::{ResViewRef, get_sjis_bytes},
+    },
+    redis_keys::{shadow_abone_key, thread_cache_key, unsafe_threads_key},
+    thread_momentum::{get_momentum_alerts, get_threads_momentum},
+};
+use redis::{AsyncCommands, Cmd};
 use uuid::Uuid;
 
 use crate::{
     auth::AdminIdentity,
-    models::{Res, Thread, UpdateResInput},
+    error::ServiceError,
+    models::{
+        BulkModerateResponsesInput, BulkModerateResponsesResult, BulkResponseAction,
+        BulkResponseFilterType, MomentumAlert, Res, Thread, UpdateResInput,
+    },
     repository::{
-        admin_response_repository::AdminResponseRepository,
+        admin_response_repository::{
+            AdminResponseRepository, BulkModerationTarget, BulkResponseFilterParams,
+        },
         admin_thread_repository::AdminThreadRepository,
     },
 };
 
+use super::audit_log_service::{AuditEntry, AuditLogService};
+
+/// Most responses a single bulk moderation may change
+const BULK_MODERATION_LIMIT: u32 = 10_000;
+/// Longest time window of a single bulk moderation
+const BULK_MODERATION_MAX_WINDOW_DAYS: i64 = 31;
+
 #[async_trait::async_trait]
 pub trait ThreadService: Send + Sync {
+    /// Unarchived threads, with their momentum
     async fn get_threads(&self, board_key: &str) -> anyhow::Result<Vec<Thread>>;
     async fn get_thread(&self, board_key: &str, thread_id: u64) -> anyhow::Result<Option<Thread>>;
     async fn get_responses(&self, board_key: &str, thread_id: u64) -> anyhow::Result<Vec<Res>>;
@@ -30,12 +57,51 @@ pub trait ThreadService: Send + Sync {
         board_key: &str,
         target_count: u32,
     ) -> anyhow::Result<()>;
+    /// Stops a thread so that it can't be posted to, without archiving it
+    async fn stop_thread(
+        &self,
+        actor: &AdminIdentity,
+        board_key: &str,
+        thread_id: u64,
+    ) -> anyhow::Result<Thread>;
+    async fn reopen_thread(
+        &self,
+        actor: &AdminIdentity,
+        board_key: &str,
+        thread_id: u64,
+    ) -> anyhow::Result<Thread>;
+    /// Pinned threads are listed first in subject.txt and are never archived for overflow
+    async fn pin_thread(
+        &self,
+        actor: &AdminIdentity,
+        board_key: &str,
+        thread_id: u64,
+        pinned: bool,
+    ) -> anyhow::Result<Thread>;
+    async fn move_thread(
+        &self,
+        actor: &AdminIdentity,
+        board_key: &str,
+        thread_id: u64,
+        to_board_key: &str,
+    ) -> anyhow::Result<Thread>;
+    /// Threads that crossed the momentum alert threshold, latest first
+    async fn get_momentum_alerts(&self) -> anyhow::Result<Vec<MomentumAlert>>;
+    /// Abones or deletes every matching response of the unarchived threads.
+    /// `board_ids` restricts the target to these boards, `None` targets every board.
+    async fn bulk_moderate_responses(
+        &self,
+        actor: &AdminIdentity,
+        input: BulkModerateResponsesInput,
+        board_ids: Option<Vec<Uuid>>,
+    ) -> anyhow::Result<BulkModerateResponsesResult>;
 }
 
 pub struct ThreadServiceImpl {
     thread_repo: Arc<dyn AdminThreadRepository>,
     response_repo: Arc<dyn AdminResponseRepository>,
     redis_conn: redis::aio::ConnectionManager,
+    audit: Arc<dyn AuditLogService>,
 }
 
 impl ThreadServiceImpl {
@@ -43,21 +109,261 @@ impl ThreadServiceImpl {
         thread_repo: Arc<dyn AdminThreadRepository>,
         response_repo: Arc<dyn AdminResponseRepository>,
         redis_conn: redis::aio::ConnectionManager,
+        audit: Arc<dyn AuditLogService>,
     ) -> Self {
         Self {
             thread_repo,
             response_repo,
             redis_conn,
+            audit,
+        }
+    }
+
+    /// Threads already archived are handled by the archive routes instead
+    async fn get_unarchived_thread(
+        &self,
+        board_key: &str,
+        thread_id: u64,
+    ) -> anyhow::Result<Thread> {
+        let thread = self
+            .get_thread(board_key, thread_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("thread not found: {thread_id}")))?;
+        if thread.archived {
+            return Err(ServiceError::BadRequest(format!(
+                "thread is already archived: {thread_id}"
+            ))
+            .into());
+        }
+        Ok(thread)
+    }
+
+    async fn update_active(
+        &self,
+        actor: &AdminIdentity,
+        board_key: &str,
+        thread_id: u64,
+        active: bool,
+    ) -> anyhow::Result<Thread> {
+        let before = self.get_unarchived_thread(board_key, thread_id).await?;
+        if active && before.response_count >= 1000 {
+            return Err(ServiceError::BadRequest(format!(
+                "thread has reached the response limit: {thread_id}"
+            ))
+            .into());
+        }
+        self.thread_repo
+            .update_thread_active(before.id, active)
+            .await?;
+        let after = Thread {
+            active,
+            ..before.clone()
+        };
+
+        let verb = if active { "reopen" } else { "stop" };
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("thread", verb, before.id)
+                    .before(&before)
+                    .after(&after),
+            )
+            .await;
+        Ok(after)
+    }
+
+    /// Carries the dat cache, the shadow-aboned lines and the safe-mode flag over to the new
+    /// board, since they are all keyed by the board
+    async fn move_thread_cache(
+        &self,
+        from: (&str, Uuid),
+        to: (&str, Uuid),
+        thread_number: u64,
+    ) -> anyhow::Result<()> {
+        let mut conn = self.redis_conn.clone();
+
+        let mut keys = vec![(
+            thread_cache_key(from.0, thread_number),
+            thread_cache_key(to.0, thread_number),
+        )];
+        let pattern = shadow_abone_key(from.0, thread_number, "*");
+        let mut shadow_abone_keys = Vec::new();
+        {
+            let mut iter = conn.scan_match::<_, String>(&pattern).await?;
+            while let Some(key) = iter.next_item().await {
+                shadow_abone_keys.push(key?);
+            }
+        }
+        for key in shadow_abone_keys {
+            let digest = key.rsplit(':').next().unwrap_or_default();
+            let new_key = shadow_abone_key(to.0, thread_number, digest);
+            keys.push((key, new_key));
+        }
+
+        // The cache may already be gone, in which case the database is enough
+        for (key, new_key) in keys {
+            if conn.exists::<_, bool>(&key).await? {
+                conn.rename::<_, _, ()>(&key, &new_key).await?;
+            }
+        }
+
+        conn.smove::<_, _, _, ()>(
+            unsafe_threads_key(from.1),
+            unsafe_threads_key(to.1),
+            thread_number,
+        )
+        .await?;
+        Ok(())
+    }
+    /// Rewrites the lines of the aboned responses in the dat caches, grouped by thread
+    async fn abone_cached_reses(&self, targets: &[BulkModerationTarget]) {
+        let mut threads = BTreeMap::<(&str, i64), Vec<&BulkModerationTarget>>::new();
+        for target in targets {
+            threads
+                .entry((&target.board_key, target.thread_number))
+                .or_default()
+                .push(target);
+        }
+
+        let mut conn = self.redis_conn.clone();
+        for ((board_key, thread_number), targets) in threads {
+            let key = thread_cache_key(board_key, thread_number as u64);
+            let result: anyhow::Result<()> = async {
+                // The cache may already be gone, in which case the database is enough
+                if !conn.exists::<_, bool>(&key).await? {
+                    return Ok(());
+                }
+                for target in targets {
+                    // Only the thread title is rendered in an aboned line
+                    let line = get_sjis_bytes(
+                        ResViewRef {
+                            author_name: "",
+                            mail: "",
+                            body: "",
+                            created_at: Utc::now(),
+                            author_id: "",
+                            is_abone: true,
+                        },
+                        "",
+                        (target.res_order == 1).then_some(target.thread_title.as_str()),
+                    );
+                    conn.send_packed_command(&Cmd::lset(
+                        &key,
+                        target.res_order as isize - 1,
+                        line.get_inner(),
+                    ))
+                    .await?;
+                }
+                Ok(())
+            }
+            .await;
+
+            if let Err(e) = result {
+                tracing::error!("failed to abone responses in thread cache {key}: {e:?}");
+            }
+        }
+    }
+}
+
+/// Builds the repository filter, narrowing the time window to the day of an author ID
+fn bulk_filter_params<'a>(
+    input: &'a BulkModerateResponsesInput,
+    board_ids: Option<&'a [Uuid]>,
+) -> Result<BulkResponseFilterParams<'a>, ServiceError> {
+    let value = input.filter_value.trim();
+    if value.is_empty() {
+        return Err(ServiceError::BadRequest(
+            "filter_value must not be empty".into(),
+        ));
+    }
+    if input.since >= input.until {
+        return Err(ServiceError::BadRequest(
+            "since must be before until".into(),
+        ));
+    }
+    if input.until - input.since > Duration::days(BULK_MODERATION_MAX_WINDOW_DAYS) {
+        return Err(ServiceError::BadRequest(format!(
+            "time window must not exceed {BULK_MODERATION_MAX_WINDOW_DAYS} days"
+        )));
+    }
+
+    let mut params = BulkResponseFilterParams {
+        authed_token_id: None,
+        author_id: None,
+        ip_addr: None,
+        ip_v6_prefix: None,
+        asn_num: None,
+        since: input.since,
+        until: input.until,
+        board_ids,
+        limit: BULK_MODERATION_LIMIT + 1,
+    };
+    match input.filter_type {
+        BulkResponseFilterType::AuthedTokenId => {
+            let id = value.parse().map_err(|_| {
+                ServiceError::BadRequest(format!("invalid authed token id: {value}"))
+            })?;
+            params.authed_token_id = Some(id);
+        }
+        BulkResponseFilterType::AuthorId => {
+            let date = input.author_id_date.ok_or_else(|| {
+                ServiceError::BadRequest("author_id_date is required for AuthorId".into())
+            })?;
+            let day_start = date.and_time(NaiveTime::MIN).and_utc() - Duration::hours(9); // JST
+            params.author_id = Some(value);
+            params.since = params.since.max(day_start);
+            params.until = params.until.min(day_start + Duration::days(1));
+        }
+        BulkResponseFilterType::Ip => params.ip_addr = Some(value),
+        BulkResponseFilterType::ReducedIp => match ReducedIpAddr::from(value.to_string()) {
+            ReducedIpAddr::V4(_) => params.ip_addr = Some(value),
+            ReducedIpAddr::V6(segments) => {
+                let mut prefix = [0u8; 8];
+                for (bytes, segment) in prefix.chunks_exact_mut(2).zip(&segments) {
+                    let segment = u16::from_str_radix(segment, 16).map_err(|_| {
+                        ServiceError::BadRequest(format!("invalid reduced IP: {value}"))
+                    })?;
+                    bytes.copy_from_slice(&segment.to_be_bytes());
+                }
+                params.ip_v6_prefix = Some(prefix);
+            }
+        },
+        BulkResponseFilterType::Asn => {
+            let asn_num = value
+                .parse()
+                .map_err(|_| ServiceError::BadRequest(format!("invalid ASN: {value}")))?;
+            params.asn_num = Some(asn_num);
         }
     }
+    Ok(params)
 }
 
 #[async_trait::async_trait]
 impl ThreadService for ThreadServiceImpl {
     async fn get_threads(&self, board_key: &str) -> anyhow::Result<Vec<Thread>> {
-        self.thread_repo
+        let mut threads = self
+            .thread_repo
             .get_threads_by_thread_id(board_key, None)
-            .await
+            .await?;
+
+        let mut conn = self.redis_conn.clone();
+        let thread_ids = threads.iter().map(|th| th.id).collect::<Vec<_>>();
+        match get_threads_momentum(&mut conn, &thread_ids, Utc::now()).await {
+            Ok(momentum) => {
+                for thread in &mut threads {
+                    thread.momentum = momentum.get(&thread.id).copied().unwrap_or(0);
+                }
+            }
+            // The list is still useful without the momentum
+            Err(e) => tracing::error!("failed to get thread momentum: {e}"),
+        }
+        Ok(threads)
+    }
+
+    async fn get_momentum_alerts(&self) -> anyhow::Result<Vec<MomentumAlert>> {
+        let mut conn = self.redis_conn.clone();
+        let alerts = get_momentum_alerts(&mut conn).await?;
+        Ok(alerts.into_iter().map(MomentumAlert::from).collect())
     }
 
     async fn get_thread(&self, board_key: &str, thread_id: u64) -> anyhow::Result<Option<Thread>> {
@@ -76,7 +382,7 @@ impl ThreadService for ThreadServiceImpl {
 
     async fn update_response(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         board_key: &str,
         _thread_id: u64,
         res_id: Uuid,
@@ -86,6 +392,11 @@ impl ThreadService for ThreadServiceImpl {
 
         let (res, default_name, board_key_actual, thread_number, thread_title) =
             self.response_repo.get_res(res_id).await?;
+        if !actor.boards.allows_key(&board_key_actual) {
+            return Err(
+                ServiceError::Forbidden(format!("no access to board: {board_key_actual}")).into(),
+            );
+        }
 
         let updated_res = self
             .response_repo
@@ -113,7 +424,7 @@ impl ThreadService for ThreadServiceImpl {
             mail,
             body,
             created_at: res.created_at,
-            author_id: res.author_id,
+            author_id: res.author_id.clone(),
             is_abone,
         };
         let title_for_view = if res_order == 1 {
@@ -132,17 +443,178 @@ impl ThreadService for ThreadServiceImpl {
         ))
         .await?;
 
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("response", "update", res_id)
+                    .before(&res)
+                    .after(&updated_res),
+            )
+            .await;
         Ok(updated_res)
     }
 
     async fn compact_threads(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         board_key: &str,
         target_count: u32,
     ) -> anyhow::Result<()> {
         self.thread_repo
             .compact_threads(board_key, target_count)
+            .await?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("board", "compact_threads", board_key)
+                    .after(&serde_json::json!({ "target_count": target_count })),
+            )
+            .await;
+        Ok(())
+    }
+
+    async fn stop_thread(
+        &self,
+        actor: &AdminIdentity,
+        board_key: &str,
+        thread_id: u64,
+    ) -> anyhow::Result<Thread> {
+        self.update_active(actor, board_key, thread_id, false).await
+    }
+
+    async fn reopen_thread(
+        &self,
+        actor: &AdminIdentity,
+        board_key: &str,
+        thread_id: u64,
+    ) -> anyhow::Result<Thread> {
+        self.update_active(actor, board_key, thread_id, true).await
+    }
+
+    async fn pin_thread(
+        &self,
+        actor: &AdminIdentity,
+        board_key: &str,
+        thread_id: u64,
+        pinned: bool,
+    ) -> anyhow::Result<Thread> {
+        let before = self.get_unarchived_thread(board_key, thread_id).await?;
+        self.thread_repo
+            .update_thread_no_pool(before.id, pinned)
+            .await?;
+        let after = Thread {
+            no_pool: pinned,
+            ..before.clone()
+        };
+
+        let verb = if pinned { "pin" } else { "unpin" };
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("thread", verb, before.id)
+                    .before(&before)
+                    .after(&after),
+            )
+            .await;
+        Ok(after)
+    }
+
+    async fn move_thread(
+        &self,
+        actor: &AdminIdentity,
+        board_key: &str,
+        thread_id: u64,
+        to_board_key: &str,
+    ) -> anyhow::Result<Thread> {
+        if board_key == to_board_key {
+            return Err(ServiceError::BadRequest("thread is already in this board".into()).into());
+        }
+        let before = self.get_unarchived_thread(board_key, thread_id).await?;
+
+        let board_id = self
+            .thread_repo
+            .move_thread(before.id, to_board_key)
+            .await?;
+        let after = Thread {
+            board_id,
+            ..before.clone()
+        };
+
+        if let Err(e) = self
+            .move_thread_cache(
+                (board_key, before.board_id),
+                (to_board_key, board_id),
+                thread_id,
+            )
             .await
+        {
+            // The database is the source of truth, the dat is rebuilt from it on a cache miss
+            tracing::error!(
+                thread_id = %before.id,
+                "failed to move thread cache from {board_key} to {to_board_key}: {e:?}"
+            );
+        }
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("thread", "move", before.id)
+                    .before(&before)
+                    .after(&after),
+            )
+            .await;
+        Ok(after)
+    }
+    async fn bulk_moderate_responses(
+        &self,
+        actor: &AdminIdentity,
+        input: BulkModerateResponsesInput,
+        board_ids: Option<Vec<Uuid>>,
+    ) -> anyhow::Result<BulkModerateResponsesResult> {
+        let params = bulk_filter_params(&input, board_ids.as_deref())?;
+        let targets = self
+            .response_repo
+            .get_bulk_moderation_targets(params)
+            .await?;
+        if targets.len() > BULK_MODERATION_LIMIT as usize {
+            return Err(ServiceError::BadRequest(format!(
+                "more than {BULK_MODERATION_LIMIT} responses match, narrow the time window"
+            ))
+            .into());
+        }
+
+        let thread_count = targets
+            .iter()
+            .map(|target| (&target.board_key, target.thread_number))
+            .collect::<HashSet<_>>()
+            .len();
+        let result = BulkModerateResponsesResult {
+            dry_run: input.dry_run,
+            response_count: targets.len(),
+            thread_count,
+        };
+        if input.dry_run {
+            return Ok(result);
+        }
+
+        let ids = targets.iter().map(|target| target.id).collect::<Vec<_>>();
+        let erase = input.action == BulkResponseAction::Delete;
+        self.response_repo.abone_reses(&ids, erase).await?;
+        self.abone_cached_reses(&targets).await;
+
+        let verb = if erase { "bulk_delete" } else { "bulk_abone" };
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("response", verb, input.filter_value.trim()).after(
+                    &serde_json::json!({
+                        "filter": input,
+                        "response_ids": ids,
+                    }),
+                ),
+            )
+            .await;
+        Ok(result)
     }
 }
@@ -8,6 +8,8 @@ use crate::{
     repository::admin_user_repository::AdminUserRepository,
 };
 
+use super::audit_log_service::{AuditEntry, AuditLogService};
+
 #[async_trait::async_trait]
 pub trait UserService: Send + Sync {
     async fn search_users(&self, query: UserSearchQuery) -> anyhow::Result<Vec<User>>;
@@ -21,11 +23,12 @@ pub trait UserService: Send + Sync {
 
 pub struct UserServiceImpl {
     repo: Arc<dyn AdminUserRepository>,
+    audit: Arc<dyn AuditLogService>,
 }
 
 impl UserServiceImpl {
-    pub fn new(repo: Arc<dyn AdminUserRepository>) -> Self {
-        Self { repo }
+    pub fn new(repo: Arc<dyn AdminUserRepository>, audit: Arc<dyn AuditLogService>) -> Self {
+        Self { repo, audit }
     }
 }
 
@@ -39,14 +42,23 @@ impl UserService for UserServiceImpl {
 
     async fn update_user_status(
         &self,
-        _actor: &AdminIdentity,
+        actor: &AdminIdentity,
         user_id: Uuid,
         enabled: bool,
     ) -> anyhow::Result<User> {
         self.repo.update_user_status(user_id, enabled).await?;
         let users = self.repo.search_users(Some(user_id), None, None).await?;
-        users.into_iter().next().ok_or_else(|| {
-            crate::error::ServiceError::NotFound("User not found after update".into()).into()
-        })
+        let user = users.into_iter().next().ok_or_else(|| {
+            crate::error::ServiceError::NotFound("User not found after update".into())
+        })?;
+
+        self.audit
+            .record(
+                actor,
+                AuditEntry::new("user", "update_status", user_id)
+                    .after(&serde_json::json!({ "enabled": enabled })),
+            )
+            .await;
+        Ok(user)
     }
 }
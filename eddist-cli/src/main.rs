@@ -1,11 +1,9 @@
 use anyhow::Result;
-use aws_sdk_s3::{
-    Client,
-    config::{Credentials, Region},
-    primitives::ByteStream,
-};
 use clap::{Parser, Subcommand};
-use eddist_core::domain::authed_token_backup::{AUTHED_TOKENS_S3_PREFIX, AuthedTokenBackup};
+use eddist_core::{
+    archive_storage::archive_storage_from_env,
+    domain::authed_token_backup::{AUTHED_TOKENS_S3_PREFIX, AuthedTokenBackup},
+};
 use futures::StreamExt;
 use std::{collections::HashSet, env};
 use uuid::Uuid;
@@ -52,29 +50,9 @@ async fn main() -> Result<()> {
     }
 }
 
-fn make_s3_client() -> Result<(Client, String)> {
-    let account_id = env::var("R2_ACCOUNT_ID")?;
-    let bucket_name = env::var("S3_BUCKET_NAME")?;
-    let endpoint = format!("https://{}.r2.cloudflarestorage.com", account_id.trim());
-    let creds = Credentials::new(
-        env::var("S3_ACCESS_KEY")?.trim(),
-        env::var("S3_ACCESS_SECRET_KEY")?.trim(),
-        None,
-        None,
-        "custom",
-    );
-    let config = aws_sdk_s3::Config::builder()
-        .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
-        .credentials_provider(creds)
-        .region(Region::new("auto"))
-        .endpoint_url(endpoint)
-        .build();
-    Ok((Client::from_conf(config), bucket_name.trim().to_string()))
-}
-
 async fn backup() -> Result<()> {
     let pool = sqlx::MySqlPool::connect(&env::var("DATABASE_URL")?).await?;
-    let (client, bucket_name) = make_s3_client()?;
+    let storage = archive_storage_from_env()?;
 
     let rows = sqlx::query_as!(
         AuthedTokenBackup,
@@ -102,18 +80,15 @@ async fn backup() -> Result<()> {
 
     let results = futures::stream::iter(rows)
         .map(|token| {
-            let client = client.clone();
-            let bucket_name = bucket_name.clone();
+            let storage = storage.clone();
             async move {
                 let bytes = serde_json::to_vec(&token)?;
-                client
-                    .put_object()
-                    .bucket(&bucket_name)
-                    .key(format!("{AUTHED_TOKENS_S3_PREFIX}/{}.json", token.id))
-                    .body(ByteStream::from(bytes))
-                    .send()
-                    .await?;
-                anyhow::Ok(())
+                storage
+                    .put(
+                        &format!("{AUTHED_TOKENS_S3_PREFIX}/{}.json", token.id),
+                        bytes,
+                    )
+                    .await
             }
         })
         .buffer_unordered(CONCURRENCY)
@@ -130,7 +105,7 @@ async fn backup() -> Result<()> {
 
 async fn validate() -> Result<()> {
     let pool = sqlx::MySqlPool::connect(&env::var("DATABASE_URL")?).await?;
-    let (client, bucket_name) = make_s3_client()?;
+    let storage = archive_storage_from_env()?;
 
     let db_ids =
         sqlx::query_scalar!(r#"SELECT id AS "id!: Uuid" FROM authed_tokens WHERE validity = 1"#)
@@ -140,23 +115,14 @@ async fn validate() -> Result<()> {
             .collect::<HashSet<_>>();
 
     let prefix = format!("{AUTHED_TOKENS_S3_PREFIX}/");
-    let mut pages = client
-        .list_objects_v2()
-        .bucket(&bucket_name)
-        .prefix(&prefix)
-        .into_paginator()
-        .send();
     let mut s3_ids = HashSet::new();
-    while let Some(page) = pages.next().await {
-        for obj in page?.contents.unwrap_or_default() {
-            if let Some(key) = obj.key
-                && let Some(name) = key
-                    .strip_prefix(&prefix)
-                    .and_then(|n| n.strip_suffix(".json"))
-                && let Ok(id) = Uuid::parse_str(name)
-            {
-                s3_ids.insert(id);
-            }
+    for key in storage.list(&prefix).await? {
+        if let Some(name) = key
+            .strip_prefix(&prefix)
+            .and_then(|n| n.strip_suffix(".json"))
+            && let Ok(id) = Uuid::parse_str(name)
+        {
+            s3_ids.insert(id);
         }
     }
 
@@ -193,39 +159,22 @@ async fn validate() -> Result<()> {
 
 async fn recover() -> Result<()> {
     let pool = sqlx::MySqlPool::connect(&env::var("DATABASE_URL")?).await?;
-    let (client, bucket_name) = make_s3_client()?;
+    let storage = archive_storage_from_env()?;
 
-    let mut pages = client
-        .list_objects_v2()
-        .bucket(&bucket_name)
-        .prefix(format!("{AUTHED_TOKENS_S3_PREFIX}/"))
-        .into_paginator()
-        .send();
-    let mut keys = Vec::new();
-    while let Some(page) = pages.next().await {
-        for obj in page?.contents.unwrap_or_default() {
-            if let Some(key) = obj.key {
-                keys.push(key);
-            }
-        }
-    }
+    let keys = storage.list(&format!("{AUTHED_TOKENS_S3_PREFIX}/")).await?;
 
     let total = keys.len();
     println!("Recovering {total} tokens from S3...");
 
     let results = futures::stream::iter(keys)
         .map(|key| {
-            let client = client.clone();
-            let bucket_name = bucket_name.clone();
+            let storage = storage.clone();
             let pool = pool.clone();
             async move {
-                let output = client
-                    .get_object()
-                    .bucket(&bucket_name)
-                    .key(&key)
-                    .send()
-                    .await?;
-                let data = output.body.collect().await?.into_bytes();
+                let data = storage
+                    .get(&key)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("backup disappeared: {key}"))?;
                 let token: AuthedTokenBackup = serde_json::from_slice(&data)?;
 
                 let auth_code = token.auth_code.as_deref().unwrap_or("000000");
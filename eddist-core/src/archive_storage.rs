@@ -0,0 +1,327 @@
+use std::{
+    env,
+    fmt::Debug,
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context, bail};
+use aws_sdk_s3::{
+    Client,
+    config::{BehaviorVersion, Credentials, Region},
+    error::SdkError,
+    operation::{get_object::GetObjectError, head_object::HeadObjectError},
+    primitives::ByteStream,
+};
+
+/// Object storage holding the archived (kako) dats and the authed token backups.
+///
+/// Keys are `/`-separated paths such as `{board_key}/dat/{thread_number}.dat`.
+#[async_trait::async_trait]
+pub trait ArchiveStorage: Send + Sync + Debug {
+    /// Returns `None` when the object does not exist
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>>;
+    async fn put(&self, key: &str, data: Vec<u8>) -> anyhow::Result<()>;
+    /// Deleting a missing object is not an error
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+    async fn exists(&self, key: &str) -> anyhow::Result<bool>;
+    /// Returns the keys starting with `prefix`
+    async fn list(&self, prefix: &str) -> anyhow::Result<Vec<String>>;
+}
+
+/// S3-compatible storage, e.g. Cloudflare R2 or MinIO
+#[derive(Debug, Clone)]
+pub struct S3ArchiveStorage {
+    client: Client,
+    bucket: String,
+}
+
+impl S3ArchiveStorage {
+    pub fn new(client: Client, bucket: String) -> Self {
+        Self { client, bucket }
+    }
+
+    /// Reads `S3_BUCKET_NAME`, `S3_ACCESS_KEY` and `S3_ACCESS_SECRET_KEY`. The endpoint is
+    /// `S3_ENDPOINT` when set (with path-style addressing, as MinIO requires), otherwise the
+    /// R2 endpoint of `R2_ACCOUNT_ID`.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let bucket = env::var("S3_BUCKET_NAME").context("S3_BUCKET_NAME is not set")?;
+        let creds = Credentials::new(
+            env::var("S3_ACCESS_KEY")
+                .context("S3_ACCESS_KEY is not set")?
+                .trim(),
+            env::var("S3_ACCESS_SECRET_KEY")
+                .context("S3_ACCESS_SECRET_KEY is not set")?
+                .trim(),
+            None,
+            None,
+            "custom",
+        );
+
+        let (endpoint, force_path_style) = match env::var("S3_ENDPOINT") {
+            Ok(endpoint) => (endpoint.trim().to_string(), true),
+            Err(_) => {
+                let account_id = env::var("R2_ACCOUNT_ID")
+                    .context("either S3_ENDPOINT or R2_ACCOUNT_ID must be set")?;
+                (
+                    format!("https://{}.r2.cloudflarestorage.com", account_id.trim()),
+                    false,
+                )
+            }
+        };
+        let region = env::var("S3_REGION").unwrap_or_else(|_| "auto".to_string());
+
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .credentials_provider(creds)
+            .region(Region::new(region))
+            .endpoint_url(endpoint)
+            .force_path_style(force_path_style)
+            .build();
+
+        Ok(Self::new(
+            Client::from_conf(config),
+            bucket.trim().to_string(),
+        ))
+    }
+}
+
+#[async_trait::async_trait]
+impl ArchiveStorage for S3ArchiveStorage {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        match self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(output) => Ok(Some(output.body.collect().await?.into_bytes().to_vec())),
+            Err(SdkError::ServiceError(e)) if matches!(e.err(), GetObjectError::NoSuchKey(_)) => {
+                Ok(None)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(ByteStream::from(data))
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        match self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(SdkError::ServiceError(e)) if matches!(e.err(), HeadObjectError::NotFound(_)) => {
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(prefix)
+            .into_paginator()
+            .send();
+        let mut keys = Vec::new();
+        while let Some(page) = pages.next().await {
+            keys.extend(
+                page?
+                    .contents
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|obj| obj.key),
+            );
+        }
+        Ok(keys)
+    }
+}
+
+/// Storage in a local directory, for deployments without object storage
+#[derive(Debug, Clone)]
+pub struct LocalArchiveStorage {
+    root: PathBuf,
+}
+
+impl LocalArchiveStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Rejects keys that would escape the root directory
+    fn path(&self, key: &str) -> anyhow::Result<PathBuf> {
+        let relative = Path::new(key);
+        if key.is_empty()
+            || !relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        {
+            bail!("invalid archive key: {key}");
+        }
+        Ok(self.root.join(relative))
+    }
+
+    fn key(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let parts = relative
+            .components()
+            .map(|component| component.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?;
+        Some(parts.join("/"))
+    }
+}
+
+#[async_trait::async_trait]
+impl ArchiveStorage for LocalArchiveStorage {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Written to a temporary file first, so that readers never see a partial object
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(format!(".{}.tmp", uuid::Uuid::now_v7()));
+        tokio::fs::write(&tmp, data).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        Ok(tokio::fs::try_exists(self.path(key)?).await?)
+    }
+
+    async fn list(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut dirs = vec![self.root.clone()];
+        while let Some(dir) = dirs.pop() {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if entry.file_type().await?.is_dir() {
+                    dirs.push(path);
+                } else if let Some(key) = self.key(&path)
+                    && key.starts_with(prefix)
+                    && !key.ends_with(".tmp")
+                {
+                    keys.push(key);
+                }
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// Builds the storage selected by `ARCHIVE_STORAGE`: `s3` (default) or `local`, which
+/// stores the objects under `ARCHIVE_LOCAL_DIR`
+pub fn archive_storage_from_env() -> anyhow::Result<Arc<dyn ArchiveStorage>> {
+    match env::var("ARCHIVE_STORAGE").as_deref() {
+        Ok("local") => {
+            let dir = env::var("ARCHIVE_LOCAL_DIR")
+                .context("ARCHIVE_LOCAL_DIR must be set for the local archive storage")?;
+            Ok(Arc::new(LocalArchiveStorage::new(dir.trim())))
+        }
+        Ok("s3") | Err(_) => Ok(Arc::new(S3ArchiveStorage::from_env()?)),
+        Ok(other) => bail!("unknown ARCHIVE_STORAGE: {other}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_storage() -> LocalArchiveStorage {
+        LocalArchiveStorage::new(
+            env::temp_dir().join(format!("eddist-archive-{}", uuid::Uuid::now_v7())),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_round_trip() {
+        let storage = temp_storage();
+
+        assert_eq!(storage.get("board/dat/1.dat").await.unwrap(), None);
+        assert!(!storage.exists("board/dat/1.dat").await.unwrap());
+
+        storage
+            .put("board/dat/1.dat", b"dat".to_vec())
+            .await
+            .unwrap();
+        storage
+            .put("board/admin/1.dat", b"admin".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.get("board/dat/1.dat").await.unwrap(),
+            Some(b"dat".to_vec())
+        );
+        assert!(storage.exists("board/dat/1.dat").await.unwrap());
+        assert_eq!(
+            storage.list("board/dat/").await.unwrap(),
+            vec!["board/dat/1.dat".to_string()]
+        );
+
+        storage.delete("board/dat/1.dat").await.unwrap();
+        storage.delete("board/dat/1.dat").await.unwrap();
+        assert_eq!(storage.get("board/dat/1.dat").await.unwrap(), None);
+
+        tokio::fs::remove_dir_all(&storage.root).await.unwrap();
+    }
+
+    #[test]
+    fn test_local_storage_rejects_escaping_keys() {
+        let storage = temp_storage();
+        assert!(storage.path("../secret").is_err());
+        assert!(storage.path("/etc/passwd").is_err());
+        assert!(storage.path("").is_err());
+        assert!(storage.path("board/dat/1.dat").is_ok());
+    }
+}
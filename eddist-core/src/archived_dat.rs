@@ -0,0 +1,240 @@
+use std::{
+    fmt::Display,
+    io::{self, Read, Write},
+    sync::OnceLock,
+};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+
+use crate::archive_storage::ArchiveStorage;
+
+static DAT_COMPRESSION: OnceLock<DatCompression> = OnceLock::new();
+
+/// Compression of an archived dat, encoded in its key as `.dat.gz` or `.dat.br`.
+///
+/// Compressed dats hold the Shift_JIS bytes served to the clients, so that they can be
+/// sent as-is with the matching `Content-Encoding`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatCompression {
+    None,
+    Gzip,
+    Brotli,
+}
+
+impl DatCompression {
+    pub const VALUES: [DatCompression; 3] = [
+        DatCompression::None,
+        DatCompression::Gzip,
+        DatCompression::Brotli,
+    ];
+
+    /// Compression of the newly archived dats, from `ARCHIVE_DAT_COMPRESSION`
+    /// (`none`, `gzip` or `br`, defaults to `gzip`)
+    pub fn configured() -> Self {
+        *DAT_COMPRESSION.get_or_init(
+            || match std::env::var("ARCHIVE_DAT_COMPRESSION").as_deref() {
+                Ok("none") => DatCompression::None,
+                Ok("br") => DatCompression::Brotli,
+                _ => DatCompression::Gzip,
+            },
+        )
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            DatCompression::None => "",
+            DatCompression::Gzip => ".gz",
+            DatCompression::Brotli => ".br",
+        }
+    }
+
+    /// Value of the `Content-Encoding` header, and the token to look for in `Accept-Encoding`
+    pub fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            DatCompression::None => None,
+            DatCompression::Gzip => Some("gzip"),
+            DatCompression::Brotli => Some("br"),
+        }
+    }
+
+    pub fn compress(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            DatCompression::None => data.to_vec(),
+            DatCompression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            DatCompression::Brotli => {
+                let mut compressed = Vec::new();
+                let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+                encoder.write_all(data).unwrap();
+                drop(encoder);
+                compressed
+            }
+        }
+    }
+
+    pub fn decompress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut decompressed = Vec::new();
+        match self {
+            DatCompression::None => decompressed.extend_from_slice(data),
+            DatCompression::Gzip => {
+                GzDecoder::new(data).read_to_end(&mut decompressed)?;
+            }
+            DatCompression::Brotli => {
+                brotli::Decompressor::new(data, 4096).read_to_end(&mut decompressed)?;
+            }
+        }
+        Ok(decompressed)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchivedDatKind {
+    /// The dat served to the clients
+    Dat,
+    /// The dat with the IP addresses and authed token ids, for the admin
+    Admin,
+}
+
+impl ArchivedDatKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ArchivedDatKind::Dat => "dat",
+            ArchivedDatKind::Admin => "admin",
+        }
+    }
+}
+
+/// Keys of every compression of an archived dat, the configured compression first
+pub fn archived_dat_keys(
+    board_key: &str,
+    kind: ArchivedDatKind,
+    thread_number: impl Display,
+) -> Vec<(DatCompression, String)> {
+    let configured = DatCompression::configured();
+    std::iter::once(configured)
+        .chain(
+            DatCompression::VALUES
+                .into_iter()
+                .filter(|compression| *compression != configured),
+        )
+        .map(|compression| {
+            (
+                compression,
+                format!(
+                    "{board_key}/{}/{thread_number}.dat{}",
+                    kind.as_str(),
+                    compression.extension()
+                ),
+            )
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchivedDat {
+    /// The stored bytes, compressed with `compression`
+    pub data: Vec<u8>,
+    pub compression: DatCompression,
+}
+
+impl ArchivedDat {
+    pub fn decompress(self) -> io::Result<Vec<u8>> {
+        match self.compression {
+            DatCompression::None => Ok(self.data),
+            compression => compression.decompress(&self.data),
+        }
+    }
+}
+
+/// Returns `None` when the dat is archived with none of the compressions
+pub async fn get_archived_dat(
+    storage: &dyn ArchiveStorage,
+    board_key: &str,
+    kind: ArchivedDatKind,
+    thread_number: impl Display,
+) -> anyhow::Result<Option<ArchivedDat>> {
+    for (compression, key) in archived_dat_keys(board_key, kind, thread_number) {
+        if let Some(data) = storage.get(&key).await? {
+            return Ok(Some(ArchivedDat { data, compression }));
+        }
+    }
+    Ok(None)
+}
+
+pub async fn archived_dat_exists(
+    storage: &dyn ArchiveStorage,
+    board_key: &str,
+    kind: ArchivedDatKind,
+    thread_number: impl Display,
+) -> anyhow::Result<bool> {
+    for (_, key) in archived_dat_keys(board_key, kind, thread_number) {
+        if storage.exists(&key).await? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Stores the Shift_JIS dat with the configured compression and removes its other
+/// compressions, which would otherwise be read instead once the configuration changes.
+///
+/// Uncompressed dats are stored in UTF-8 as before.
+pub async fn put_archived_dat(
+    storage: &dyn ArchiveStorage,
+    board_key: &str,
+    kind: ArchivedDatKind,
+    thread_number: impl Display,
+    sjis_dat: &[u8],
+) -> anyhow::Result<()> {
+    let mut keys = archived_dat_keys(board_key, kind, thread_number).into_iter();
+    let (compression, key) = keys.next().unwrap();
+    let data = match compression {
+        // TODO: sjis to utf-8 workarounds for now
+        DatCompression::None => encoding_rs::SHIFT_JIS
+            .decode(sjis_dat)
+            .0
+            .into_owned()
+            .into_bytes(),
+        compression => compression.compress(sjis_dat),
+    };
+    storage.put(&key, data).await?;
+    for (_, key) in keys {
+        storage.delete(&key).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression_round_trip() {
+        let dat = "名無しさん<><>2024/06/28(金) 12:34:56.789 ID:abcd1234<> test <>スレ\n"
+            .repeat(100)
+            .into_bytes();
+        for compression in DatCompression::VALUES {
+            let compressed = compression.compress(&dat);
+            if compression != DatCompression::None {
+                assert!(compressed.len() < dat.len());
+            }
+            assert_eq!(compression.decompress(&compressed).unwrap(), dat);
+        }
+    }
+
+    #[test]
+    fn test_archived_dat_keys() {
+        let keys = archived_dat_keys("news", ArchivedDatKind::Admin, 1719543845);
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys[0].0, DatCompression::configured());
+        assert!(keys.iter().any(|(compression, key)| {
+            *compression == DatCompression::None && key == "news/admin/1719543845.dat"
+        }));
+        assert!(keys.iter().any(|(compression, key)| {
+            *compression == DatCompression::Brotli && key == "news/admin/1719543845.dat.br"
+        }));
+    }
+}
@@ -0,0 +1,524 @@
+use std::{
+    env,
+    fs::File,
+    io::Read,
+    net::{IpAddr, SocketAddr},
+    path::Path,
+};
+
+use anyhow::{Context, bail};
+use flate2::read::GzDecoder;
+use http::HeaderMap;
+use ipnet::IpNet;
+
+/// Where the client IP of a request is taken from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClientIpSource {
+    /// The header set by the CDN (`Cf-Connecting-IP` by default), falling back to the
+    /// leftmost `X-Forwarded-For` entry. A request without them bypassed the CDN and
+    /// has no client IP.
+    #[default]
+    Cdn,
+    /// The rightmost address of `Forwarded` (or `X-Forwarded-For` without it) that is not
+    /// a trusted proxy, for nginx or HAProxy appending the addresses they see
+    Forwarded,
+    /// The address of the connection, as told by the PROXY protocol header if enabled
+    Peer,
+}
+
+impl std::str::FromStr for ClientIpSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "cdn" => Ok(Self::Cdn),
+            "forwarded" => Ok(Self::Forwarded),
+            "peer" => Ok(Self::Peer),
+            other => bail!("unknown CLIENT_IP_SOURCE: {other}"),
+        }
+    }
+}
+
+/// How the client IP and ASN are extracted from a request
+#[derive(Debug)]
+pub struct ClientAddrConfig {
+    pub source: ClientIpSource,
+    /// Header of the CDN giving the client IP, only read by [`ClientIpSource::Cdn`]
+    pub ip_header: String,
+    /// Proxies whose headers are trusted. With [`ClientIpSource::Cdn`], an empty list
+    /// trusts every connection.
+    pub trusted_proxies: Vec<IpNet>,
+    pub asn_header: String,
+    /// Resolves the ASN of requests from untrusted peers or without the ASN header
+    pub asn_database: Option<AsnDatabase>,
+}
+
+impl Default for ClientAddrConfig {
+    fn default() -> Self {
+        Self {
+            source: ClientIpSource::default(),
+            ip_header: "Cf-Connecting-IP".to_string(),
+            trusted_proxies: Vec::new(),
+            asn_header: "X-ASN-Num".to_string(),
+            asn_database: None,
+        }
+    }
+}
+
+impl ClientAddrConfig {
+    /// Reads `CLIENT_IP_SOURCE`, `CLIENT_IP_HEADER`, `TRUSTED_PROXIES` (comma-separated IPs
+    /// or CIDRs), `ASN_NUMBER_HEADER_NAME` and `ASN_DATABASE_PATH`, opening the database
+    pub fn from_env() -> anyhow::Result<Self> {
+        let default = Self::default();
+        let non_empty = |key: &str| env::var(key).ok().filter(|x| !x.trim().is_empty());
+
+        let source = non_empty("CLIENT_IP_SOURCE")
+            .map(|x| x.parse())
+            .transpose()?
+            .unwrap_or_default();
+        let trusted_proxies = non_empty("TRUSTED_PROXIES")
+            .map(|x| parse_trusted_proxies(&x))
+            .transpose()?
+            .unwrap_or_default();
+        let asn_database = non_empty("ASN_DATABASE_PATH")
+            .map(|path| AsnDatabase::open(path.trim()))
+            .transpose()?;
+
+        Ok(Self {
+            source,
+            ip_header: non_empty("CLIENT_IP_HEADER").unwrap_or(default.ip_header),
+            trusted_proxies,
+            asn_header: non_empty("ASN_NUMBER_HEADER_NAME").unwrap_or(default.asn_header),
+            asn_database,
+        })
+    }
+
+    /// Whether the headers of the connection from `peer` are trusted
+    pub fn is_trusted(&self, peer: Option<IpAddr>) -> bool {
+        match peer {
+            Some(peer) => {
+                (self.source == ClientIpSource::Cdn && self.trusted_proxies.is_empty())
+                    || self.is_trusted_proxy(peer)
+            }
+            // Behind a listener not telling the peer, only the CDN headers are left
+            None => self.source == ClientIpSource::Cdn && self.trusted_proxies.is_empty(),
+        }
+    }
+
+    fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(&ip))
+    }
+
+    /// The client IP of a request from `peer`, `None` if it cannot be told
+    pub fn client_ip(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<IpAddr> {
+        let peer = peer.map(|peer| peer.ip().to_canonical());
+        match self.source {
+            ClientIpSource::Cdn => {
+                if !self.is_trusted(peer) {
+                    return None;
+                }
+                header_str(headers, &self.ip_header)
+                    .and_then(|ip| ip.trim().parse().ok())
+                    .or_else(|| {
+                        header_str(headers, "X-Forwarded-For")
+                            .and_then(|x| x.split(',').next())
+                            .and_then(parse_node)
+                    })
+            }
+            ClientIpSource::Forwarded => {
+                rightmost_untrusted(&forwarded_chain(headers), peer?, |ip| {
+                    self.is_trusted_proxy(ip)
+                })
+            }
+            ClientIpSource::Peer => peer,
+        }
+    }
+
+    /// The ASN given by a trusted proxy, or else looked up in the database
+    pub fn client_asn(
+        &self,
+        headers: &HeaderMap,
+        peer: Option<SocketAddr>,
+        client_ip: Option<IpAddr>,
+    ) -> Option<u32> {
+        let peer = peer.map(|peer| peer.ip().to_canonical());
+        let from_header = header_str(headers, &self.asn_header)
+            .filter(|_| self.is_trusted(peer))
+            .and_then(|asn| asn.trim().parse::<u32>().ok());
+
+        from_header.or_else(|| {
+            let database = self.asn_database.as_ref()?;
+            database.lookup(client_ip?)
+        })
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|x| x.to_str().ok())
+}
+
+pub fn parse_trusted_proxies(value: &str) -> anyhow::Result<Vec<IpNet>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            x.parse::<IpNet>()
+                .or_else(|_| x.parse::<IpAddr>().map(IpNet::from))
+                .with_context(|| format!("invalid trusted proxy: {x}"))
+        })
+        .collect()
+}
+
+/// The hops of `Forwarded` (RFC 7239), or of `X-Forwarded-For` without it, client first.
+/// `None` stands for a hop hidden as `unknown` or an obfuscated identifier.
+pub fn forwarded_chain(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    let forwarded = headers
+        .get_all("Forwarded")
+        .iter()
+        .filter_map(|x| x.to_str().ok())
+        .collect::<Vec<_>>();
+    if !forwarded.is_empty() {
+        return forwarded
+            .iter()
+            .flat_map(|x| split_unquoted(x, ','))
+            .map(|element| {
+                split_unquoted(element, ';')
+                    .into_iter()
+                    .filter_map(|pair| pair.split_once('='))
+                    .find(|(key, _)| key.trim().eq_ignore_ascii_case("for"))
+                    .and_then(|(_, node)| parse_node(node))
+            })
+            .collect();
+    }
+
+    headers
+        .get_all("X-Forwarded-For")
+        .iter()
+        .filter_map(|x| x.to_str().ok())
+        .flat_map(|x| x.split(','))
+        .map(parse_node)
+        .collect()
+}
+
+/// Splits at the separators outside of quoted strings
+fn split_unquoted(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(&value[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+/// Parses a node such as `192.0.2.43`, `192.0.2.43:47011`, `"[2001:db8:cafe::17]:4711"`
+/// or a bare IPv6 address
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    let ip = match node.strip_prefix('[') {
+        Some(bracketed) => bracketed.split_once(']')?.0,
+        None if node.parse::<IpAddr>().is_ok() => node,
+        None => node.rsplit_once(':')?.0,
+    };
+    ip.parse::<IpAddr>().ok().map(|ip| ip.to_canonical())
+}
+
+/// Walks the chain from the peer to the client, skipping the trusted proxies
+fn rightmost_untrusted(
+    chain: &[Option<IpAddr>],
+    peer: IpAddr,
+    is_trusted: impl Fn(IpAddr) -> bool,
+) -> Option<IpAddr> {
+    if !is_trusted(peer) {
+        return Some(peer);
+    }
+    for hop in chain.iter().rev() {
+        let hop = (*hop)?;
+        if !is_trusted(hop) {
+            return Some(hop);
+        }
+    }
+    // Only trusted proxies, the request comes from one of them
+    Some(chain.first().copied().flatten().unwrap_or(peer))
+}
+
+/// A local database of the ASN of IP ranges
+pub enum AsnDatabase {
+    /// A MaxMind `GeoLite2-ASN`/`GeoIP2-ISP` compatible `.mmdb` file
+    MaxMind(maxminddb::Reader<Vec<u8>>),
+    /// The ranges of an iptoasn.com `ip2asn-combined.tsv(.gz)` style file, sorted by start
+    Ranges(Vec<AsnRange>),
+}
+
+impl std::fmt::Debug for AsnDatabase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MaxMind(_) => f.write_str("AsnDatabase::MaxMind"),
+            Self::Ranges(ranges) => write!(f, "AsnDatabase::Ranges({})", ranges.len()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsnRange {
+    pub start: IpAddr,
+    pub end: IpAddr,
+    pub asn: u32,
+}
+
+impl AsnDatabase {
+    /// Opens a `.mmdb` file, or else a tab-separated file of `start end asn ...` rows,
+    /// gzipped if ending with `.gz`
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|x| x.to_str()).unwrap_or("");
+        if extension.eq_ignore_ascii_case("mmdb") {
+            let reader = maxminddb::Reader::open_readfile(path)
+                .with_context(|| format!("failed to open ASN database {}", path.display()))?;
+            return Ok(Self::MaxMind(reader));
+        }
+
+        let mut file = File::open(path)
+            .with_context(|| format!("failed to open ASN database {}", path.display()))?;
+        let mut content = String::new();
+        if extension.eq_ignore_ascii_case("gz") {
+            GzDecoder::new(file).read_to_string(&mut content)?;
+        } else {
+            file.read_to_string(&mut content)?;
+        }
+        Ok(Self::Ranges(parse_asn_ranges(&content)?))
+    }
+
+    /// The ASN of the address, `None` if it is not routed or not in the database
+    pub fn lookup(&self, ip: IpAddr) -> Option<u32> {
+        match self {
+            Self::MaxMind(reader) => {
+                reader
+                    .lookup(ip)
+                    .ok()?
+                    .decode::<maxminddb::geoip2::Asn>()
+                    .ok()??
+                    .autonomous_system_number
+            }
+            Self::Ranges(ranges) => {
+                let index = ranges.partition_point(|range| range.start <= ip);
+                let range = ranges.get(index.checked_sub(1)?)?;
+                (ip <= range.end).then_some(range.asn)
+            }
+        }
+        .filter(|asn| *asn != 0)
+    }
+}
+
+fn parse_asn_ranges(content: &str) -> anyhow::Result<Vec<AsnRange>> {
+    let mut ranges = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| {
+            parse_asn_range(line).with_context(|| format!("invalid ASN database row {}", i + 1))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    // iptoasn lists the ranges not routed with the ASN 0
+    ranges.retain(|range| range.asn != 0);
+    ranges.sort_by_key(|range| range.start);
+    Ok(ranges)
+}
+
+fn parse_asn_range(line: &str) -> anyhow::Result<AsnRange> {
+    let mut columns = line.split('\t').map(str::trim);
+    let (Some(start), Some(end), Some(asn)) = (columns.next(), columns.next(), columns.next())
+    else {
+        bail!("expected the start, end and ASN columns");
+    };
+    Ok(AsnRange {
+        start: start.parse()?,
+        end: end.parse()?,
+        asn: asn.trim_start_matches("AS").parse()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn peer(ip: &str) -> Option<SocketAddr> {
+        Some(SocketAddr::new(ip.parse().unwrap(), 443))
+    }
+
+    fn ip(ip: &str) -> Option<IpAddr> {
+        Some(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn test_forwarded_chain() {
+        let forwarded = headers(&[
+            (
+                "Forwarded",
+                r#"for=192.0.2.60;proto=http;by=203.0.113.43, for="[2001:db8:cafe::17]:4711""#,
+            ),
+            ("Forwarded", "For=unknown, for=198.51.100.7:8080"),
+            ("X-Forwarded-For", "203.0.113.9"),
+        ]);
+        assert_eq!(
+            forwarded_chain(&forwarded),
+            vec![
+                ip("192.0.2.60"),
+                ip("2001:db8:cafe::17"),
+                None,
+                ip("198.51.100.7"),
+            ]
+        );
+
+        let x_forwarded_for = headers(&[
+            ("X-Forwarded-For", "203.0.113.9, 10.0.0.2"),
+            ("X-Forwarded-For", "10.0.0.1"),
+        ]);
+        assert_eq!(
+            forwarded_chain(&x_forwarded_for),
+            vec![ip("203.0.113.9"), ip("10.0.0.2"), ip("10.0.0.1")]
+        );
+    }
+
+    #[test]
+    fn test_client_ip_forwarded() {
+        let config = ClientAddrConfig {
+            source: ClientIpSource::Forwarded,
+            trusted_proxies: parse_trusted_proxies("10.0.0.0/8, 192.0.2.1").unwrap(),
+            ..Default::default()
+        };
+        // The spoofed leftmost entry is ignored
+        let spoofed = headers(&[("X-Forwarded-For", "1.1.1.1, 203.0.113.9, 10.0.0.2")]);
+        assert_eq!(
+            config.client_ip(&spoofed, peer("10.0.0.1")),
+            ip("203.0.113.9")
+        );
+        // Headers from an untrusted peer are ignored
+        assert_eq!(
+            config.client_ip(&spoofed, peer("198.51.100.1")),
+            ip("198.51.100.1")
+        );
+        // Only trusted proxies
+        let internal = headers(&[("X-Forwarded-For", "10.0.0.3, 192.0.2.1")]);
+        assert_eq!(
+            config.client_ip(&internal, peer("10.0.0.1")),
+            ip("10.0.0.3")
+        );
+        // A hidden hop hides the client
+        let hidden = headers(&[("Forwarded", "for=203.0.113.9, for=_hidden")]);
+        assert_eq!(config.client_ip(&hidden, peer("10.0.0.1")), None);
+        // The peer is needed to trust anything
+        assert_eq!(config.client_ip(&spoofed, None), None);
+    }
+
+    #[test]
+    fn test_client_ip_cdn() {
+        let config = ClientAddrConfig::default();
+        let cdn = headers(&[
+            ("Cf-Connecting-IP", "203.0.113.1"),
+            ("X-Forwarded-For", "198.51.100.1"),
+        ]);
+        assert_eq!(config.client_ip(&cdn, None), ip("203.0.113.1"));
+        assert_eq!(
+            config.client_ip(
+                &headers(&[("X-Forwarded-For", "198.51.100.1, 10.0.0.1")]),
+                None
+            ),
+            ip("198.51.100.1")
+        );
+        assert_eq!(config.client_ip(&HeaderMap::new(), peer("10.0.0.1")), None);
+
+        let restricted = ClientAddrConfig {
+            trusted_proxies: parse_trusted_proxies("173.245.48.0/20").unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(
+            restricted.client_ip(&cdn, peer("173.245.48.5")),
+            ip("203.0.113.1")
+        );
+        // Bypassed the CDN
+        assert_eq!(restricted.client_ip(&cdn, peer("198.51.100.1")), None);
+    }
+
+    #[test]
+    fn test_client_ip_peer() {
+        let config = ClientAddrConfig {
+            source: ClientIpSource::Peer,
+            ..Default::default()
+        };
+        let cdn = headers(&[("Cf-Connecting-IP", "203.0.113.1")]);
+        assert_eq!(
+            config.client_ip(&cdn, Some("[::ffff:198.51.100.1]:443".parse().unwrap())),
+            ip("198.51.100.1")
+        );
+        assert_eq!(config.client_ip(&cdn, None), None);
+    }
+
+    #[test]
+    fn test_client_asn() {
+        let config = ClientAddrConfig {
+            source: ClientIpSource::Forwarded,
+            trusted_proxies: parse_trusted_proxies("10.0.0.0/8").unwrap(),
+            asn_database: Some(AsnDatabase::Ranges(
+                parse_asn_ranges("203.0.113.0\t203.0.113.255\t64500\tJP\tEXAMPLE").unwrap(),
+            )),
+            ..Default::default()
+        };
+        let with_header = headers(&[("X-ASN-Num", "64501")]);
+        assert_eq!(
+            config.client_asn(&with_header, peer("10.0.0.1"), ip("203.0.113.9")),
+            Some(64501)
+        );
+        // The header of an untrusted peer is ignored
+        assert_eq!(
+            config.client_asn(&with_header, peer("203.0.113.9"), ip("203.0.113.9")),
+            Some(64500)
+        );
+        assert_eq!(
+            config.client_asn(&HeaderMap::new(), peer("198.51.100.1"), ip("198.51.100.1")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_asn_ranges_lookup() {
+        let database = AsnDatabase::Ranges(
+            parse_asn_ranges(
+                "# start\tend\tasn\n\
+                 1.0.0.0\t1.0.0.255\t13335\tUS\tCLOUDFLARENET\n\
+                 1.0.1.0\t1.0.3.255\t0\tNone\tNot routed\n\
+                 1.0.4.0\t1.0.7.255\tAS38803\tAU\tWPL-AS-AP\n\
+                 2001:200::\t2001:200:ffff:ffff:ffff:ffff:ffff:ffff\t2500\tJP\tWIDE-BB\n",
+            )
+            .unwrap(),
+        );
+        assert_eq!(database.lookup("1.0.0.1".parse().unwrap()), Some(13335));
+        assert_eq!(database.lookup("1.0.2.1".parse().unwrap()), None);
+        assert_eq!(database.lookup("1.0.7.255".parse().unwrap()), Some(38803));
+        assert_eq!(database.lookup("1.0.8.0".parse().unwrap()), None);
+        assert_eq!(database.lookup("0.0.0.1".parse().unwrap()), None);
+        assert_eq!(database.lookup("2001:200::1".parse().unwrap()), Some(2500));
+
+        assert!(parse_asn_ranges("1.0.0.0\tnot-an-ip\t13335").is_err());
+    }
+}
@@ -1,6 +1,10 @@
+use std::{fmt::Display, str::FromStr};
+
 use chrono::NaiveDateTime;
 use uuid::Uuid;
 
+use super::moderation_provider::BoardModerationConfig;
+
 #[derive(Debug, Clone)]
 pub struct Board {
     pub id: Uuid,
@@ -28,6 +32,65 @@ pub struct BoardInfo {
     pub force_metadent_type: Option<String>,
     pub enable_1001_message: bool,
     pub custom_1001_message: Option<String>,
+    pub moderation_mode: BoardModerationMode,
+    pub moderation_level_threshold: u32,
+    /// Unset boards use the OpenAI provider with its own verdicts
+    pub moderation_config: Option<BoardModerationConfig>,
+}
+
+impl BoardInfo {
+    /// Whether every post from a token at `internal_level` must wait in the moderation queue
+    pub fn holds_all_posts_at(&self, internal_level: u32) -> bool {
+        self.moderation_mode == BoardModerationMode::BelowLevel
+            && internal_level < self.moderation_level_threshold
+    }
+
+    /// Whether posts are checked by AI moderation before they are published
+    pub fn holds_flagged_posts(&self) -> bool {
+        self.moderation_mode != BoardModerationMode::Off
+    }
+}
+
+/// Which posts a board sends to the moderation queue.
+///
+/// NG words with the `HOLD` action queue posts regardless of the mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoardModerationMode {
+    #[default]
+    Off,
+    /// Posts flagged by AI moderation are held
+    FlaggedOnly,
+    /// Every post from tokens below `moderation_level_threshold` is held, as are flagged posts
+    BelowLevel,
+}
+
+impl BoardModerationMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BoardModerationMode::Off => "OFF",
+            BoardModerationMode::FlaggedOnly => "FLAGGED_ONLY",
+            BoardModerationMode::BelowLevel => "BELOW_LEVEL",
+        }
+    }
+}
+
+impl FromStr for BoardModerationMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "OFF" => Ok(BoardModerationMode::Off),
+            "FLAGGED_ONLY" => Ok(BoardModerationMode::FlaggedOnly),
+            "BELOW_LEVEL" => Ok(BoardModerationMode::BelowLevel),
+            _ => Err(format!("Invalid board moderation mode: {s}")),
+        }
+    }
+}
+
+impl Display for BoardModerationMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
 }
 
 pub fn validate_board_key(board_key: &str) -> anyhow::Result<()> {
@@ -68,4 +131,49 @@ mod tests {
             assert_eq!(expected.is_ok(), result.is_ok());
         }
     }
+
+    #[test]
+    fn test_board_moderation_mode() {
+        let mut board_info = BoardInfo {
+            id: Uuid::nil(),
+            local_rules: String::new(),
+            base_thread_creation_span_sec: 0,
+            base_response_creation_span_sec: 0,
+            max_thread_name_byte_length: 0,
+            max_author_name_byte_length: 0,
+            max_email_byte_length: 0,
+            max_response_body_byte_length: 0,
+            max_response_body_lines: 0,
+            threads_archive_cron: None,
+            threads_archive_trigger_thread_count: None,
+            created_at: NaiveDateTime::default(),
+            updated_at: NaiveDateTime::default(),
+            read_only: false,
+            force_metadent_type: None,
+            enable_1001_message: false,
+            custom_1001_message: None,
+            moderation_mode: BoardModerationMode::Off,
+            moderation_level_threshold: 3,
+            moderation_config: None,
+        };
+        assert!(!board_info.holds_flagged_posts());
+        assert!(!board_info.holds_all_posts_at(0));
+
+        board_info.moderation_mode = BoardModerationMode::FlaggedOnly;
+        assert!(board_info.holds_flagged_posts());
+        assert!(!board_info.holds_all_posts_at(0));
+
+        board_info.moderation_mode = BoardModerationMode::BelowLevel;
+        assert!(board_info.holds_flagged_posts());
+        assert!(board_info.holds_all_posts_at(2));
+        assert!(!board_info.holds_all_posts_at(3));
+
+        for mode in [
+            BoardModerationMode::Off,
+            BoardModerationMode::FlaggedOnly,
+            BoardModerationMode::BelowLevel,
+        ] {
+            assert_eq!(mode.as_str().parse::<BoardModerationMode>(), Ok(mode));
+        }
+    }
 }
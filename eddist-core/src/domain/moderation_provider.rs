@@ -0,0 +1,491 @@
+use std::collections::HashMap;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    ng_word::{NG_WORD_REGEX_SIZE_LIMIT, normalize_for_matching},
+    pubsub_repository::ModerationResult,
+};
+use crate::symmetric;
+
+/// Board-level choice of the content moderation backend and how its scores are judged
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardModerationConfig {
+    pub provider: ModerationProviderConfig,
+    /// Score at or above which a category is flagged.
+    /// Falls back to [`ModerationProviderConfig::default_threshold`] when unset.
+    #[serde(default)]
+    pub threshold: Option<f64>,
+    /// Per-category overrides of `threshold`
+    #[serde(default)]
+    pub category_thresholds: HashMap<String, f64>,
+}
+
+impl Default for BoardModerationConfig {
+    fn default() -> Self {
+        Self {
+            provider: ModerationProviderConfig::OpenAi,
+            threshold: None,
+            category_thresholds: HashMap::new(),
+        }
+    }
+}
+
+impl BoardModerationConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let thresholds = self
+            .threshold
+            .iter()
+            .chain(self.category_thresholds.values());
+        for threshold in thresholds {
+            if !(0.0..=1.0).contains(threshold) {
+                return Err(format!("threshold must be between 0 and 1: {threshold}"));
+            }
+        }
+
+        match &self.provider {
+            ModerationProviderConfig::OpenAi => {}
+            ModerationProviderConfig::HttpClassifier(http) => {
+                if !http.url.starts_with("http://") && !http.url.starts_with("https://") {
+                    return Err(format!("invalid classifier url: {}", http.url));
+                }
+                if http.score_path.trim().is_empty() {
+                    return Err("score_path must not be empty".to_string());
+                }
+                if !http.body_template.contains(MODERATION_TEXT_PLACEHOLDER) {
+                    return Err(format!(
+                        "body_template must contain {MODERATION_TEXT_PLACEHOLDER}"
+                    ));
+                }
+            }
+            ModerationProviderConfig::Keyword(keyword) => {
+                KeywordScorer::compile(keyword)
+                    .map_err(|e| format!("invalid regex pattern: {e}"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Encrypts the secrets of the provider for storage, keeping the `stored` ones where they
+    /// are left masked
+    pub fn seal_secrets(&mut self, stored: Option<&BoardModerationConfig>) {
+        if let ModerationProviderConfig::HttpClassifier(http) = &mut self.provider {
+            let stored = stored.and_then(|stored| match &stored.provider {
+                ModerationProviderConfig::HttpClassifier(stored) => Some(stored),
+                _ => None,
+            });
+            http.seal_headers(stored);
+        }
+    }
+
+    /// Hides the secrets of the provider from the admin API
+    pub fn mask_secrets(&mut self) {
+        if let ModerationProviderConfig::HttpClassifier(http) = &mut self.provider {
+            for value in http.headers.values_mut() {
+                *value = MASKED_SECRET.to_string();
+            }
+        }
+    }
+
+    /// Re-evaluates `flagged` and `categories` of a provider result against the configured thresholds.
+    ///
+    /// A result already flagged by the provider itself stays flagged.
+    pub fn apply_thresholds(&self, mut result: ModerationResult) -> ModerationResult {
+        let threshold = self.threshold.or(self.provider.default_threshold());
+        let Value::Object(scores) = &result.category_scores else {
+            return result;
+        };
+
+        let mut flagged_categories = Vec::new();
+        for (category, score) in scores {
+            let Some(score) = score.as_f64() else {
+                continue;
+            };
+            let Some(threshold) = self
+                .category_thresholds
+                .get(category)
+                .copied()
+                .or(threshold)
+            else {
+                continue;
+            };
+            if score >= threshold {
+                flagged_categories.push(category.clone());
+            }
+        }
+
+        if !flagged_categories.is_empty() {
+            result.flagged = true;
+            if !result.categories.is_object() {
+                result.categories = Value::Object(Default::default());
+            }
+            if let Value::Object(categories) = &mut result.categories {
+                for category in flagged_categories {
+                    categories.insert(category, Value::Bool(true));
+                }
+            }
+        }
+
+        result
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ModerationProviderConfig {
+    /// OpenAI moderation API, using the key in the `ai.openai_api_key` server setting
+    OpenAi,
+    /// Any classifier that takes JSON over HTTP and returns a score
+    HttpClassifier(HttpClassifierConfig),
+    /// Local keyword and regex scorer, no external calls
+    Keyword(KeywordScorerConfig),
+}
+
+impl ModerationProviderConfig {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModerationProviderConfig::OpenAi => "openai",
+            ModerationProviderConfig::HttpClassifier(_) => "http_classifier",
+            ModerationProviderConfig::Keyword(_) => "keyword",
+        }
+    }
+
+    /// OpenAI decides `flagged` on its own; scores from the other providers need a threshold
+    pub fn default_threshold(&self) -> Option<f64> {
+        match self {
+            ModerationProviderConfig::OpenAi => None,
+            ModerationProviderConfig::HttpClassifier(_) | ModerationProviderConfig::Keyword(_) => {
+                Some(0.5)
+            }
+        }
+    }
+}
+
+/// Shown in place of the stored classifier headers, sending it back keeps the stored value
+pub const MASKED_SECRET: &str = "***";
+
+/// Placeholder in [`HttpClassifierConfig::body_template`] replaced with the post text as a JSON string
+pub const MODERATION_TEXT_PLACEHOLDER: &str = "{{text}}";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpClassifierConfig {
+    pub url: String,
+    /// Encrypted with [`symmetric::encrypt`] once stored, as they usually hold credentials
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// JSON request body, `{{text}}` is replaced with the post text as a quoted JSON string
+    #[serde(default = "HttpClassifierConfig::default_body_template")]
+    pub body_template: String,
+    /// JSONPath to the overall score in the response, without the leading `$.` (e.g. `result.score`).
+    /// The score is reported as the `score` category.
+    pub score_path: String,
+    /// JSONPath to an object of per-category scores, without the leading `$.`
+    #[serde(default)]
+    pub category_scores_path: Option<String>,
+}
+
+impl HttpClassifierConfig {
+    pub fn default_body_template() -> String {
+        format!(r#"{{"text": {MODERATION_TEXT_PLACEHOLDER}}}"#)
+    }
+
+    fn seal_headers(&mut self, stored: Option<&HttpClassifierConfig>) {
+        self.headers = std::mem::take(&mut self.headers)
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let value = if value == MASKED_SECRET {
+                    stored?.headers.get(&name)?.clone()
+                } else {
+                    symmetric::encrypt(&value)
+                };
+                Some((name, value))
+            })
+            .collect();
+    }
+
+    /// The headers to send, decrypted. Values stored before they were encrypted are sent as
+    /// they are.
+    pub fn decrypted_headers(&self) -> anyhow::Result<Vec<(String, String)>> {
+        self.headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if value.starts_with("v1:") {
+                    symmetric::decrypt(value)?
+                } else {
+                    value.clone()
+                };
+                Ok((name.clone(), value))
+            })
+            .collect()
+    }
+
+    pub fn render_body(&self, text: &str) -> String {
+        let text = serde_json::to_string(text).unwrap_or_else(|_| "\"\"".to_string());
+        self.body_template
+            .replace(MODERATION_TEXT_PLACEHOLDER, &text)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeywordScorerConfig {
+    pub rules: Vec<KeywordScoreRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeywordScoreRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub is_regex: bool,
+    pub category: String,
+    /// Added to the category score for every match
+    pub score: f64,
+}
+
+enum KeywordPattern {
+    Literal(String),
+    Regex(Regex),
+}
+
+/// [`KeywordScorerConfig`] with its patterns compiled.
+/// Patterns are matched against normalized text, the same way NG words are.
+pub struct KeywordScorer {
+    rules: Vec<(KeywordPattern, String, f64)>,
+}
+
+impl KeywordScorer {
+    pub fn compile(config: &KeywordScorerConfig) -> Result<Self, regex::Error> {
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| {
+                let pattern = if rule.is_regex {
+                    KeywordPattern::Regex(
+                        RegexBuilder::new(&rule.pattern)
+                            .case_insensitive(true)
+                            .size_limit(NG_WORD_REGEX_SIZE_LIMIT)
+                            .build()?,
+                    )
+                } else {
+                    KeywordPattern::Literal(normalize_for_matching(&rule.pattern))
+                };
+                Ok((pattern, rule.category.clone(), rule.score))
+            })
+            .collect::<Result<Vec<_>, regex::Error>>()?;
+
+        Ok(Self { rules })
+    }
+
+    /// Sums the scores of every match per category, capped at 1.0
+    pub fn score(&self, text: &str) -> ModerationResult {
+        let normalized = normalize_for_matching(text);
+
+        let mut scores = serde_json::Map::new();
+        for (pattern, category, score) in &self.rules {
+            let matches = match pattern {
+                KeywordPattern::Literal(word) if word.is_empty() => 0,
+                KeywordPattern::Literal(word) => normalized.matches(word.as_str()).count(),
+                KeywordPattern::Regex(re) => re.find_iter(&normalized).count(),
+            };
+            if matches == 0 {
+                continue;
+            }
+
+            let current = scores
+                .get(category)
+                .and_then(Value::as_f64)
+                .unwrap_or_default();
+            let total = (current + score * matches as f64).min(1.0);
+            scores.insert(category.clone(), total.into());
+        }
+
+        ModerationResult {
+            flagged: false,
+            categories: Value::Object(Default::default()),
+            category_scores: Value::Object(scores),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_apply_thresholds() {
+        let result = ModerationResult {
+            flagged: false,
+            categories: json!({}),
+            category_scores: json!({"spam": 0.7, "harassment": 0.3}),
+        };
+
+        let config = BoardModerationConfig {
+            provider: ModerationProviderConfig::Keyword(KeywordScorerConfig { rules: vec![] }),
+            threshold: None,
+            category_thresholds: HashMap::new(),
+        };
+        let applied = config.apply_thresholds(result.clone());
+        assert!(applied.flagged);
+        assert_eq!(applied.categories, json!({"spam": true}));
+
+        let config = BoardModerationConfig {
+            category_thresholds: HashMap::from([("spam".to_string(), 0.9)]),
+            ..config
+        };
+        assert!(!config.apply_thresholds(result.clone()).flagged);
+
+        // OpenAI results are left as they are unless a threshold is configured
+        let config = BoardModerationConfig::default();
+        assert!(!config.apply_thresholds(result).flagged);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(BoardModerationConfig::default().validate().is_ok());
+
+        let config = BoardModerationConfig {
+            threshold: Some(1.5),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        let config: BoardModerationConfig = serde_json::from_value(json!({
+            "provider": {
+                "type": "HTTP_CLASSIFIER",
+                "url": "http://classifier.local/v1/classify",
+                "score_path": "result.score",
+            },
+            "threshold": 0.8,
+        }))
+        .unwrap();
+        assert!(config.validate().is_ok());
+
+        let config = BoardModerationConfig {
+            provider: ModerationProviderConfig::HttpClassifier(HttpClassifierConfig {
+                body_template: r#"{"text": "fixed"}"#.to_string(),
+                ..serde_json::from_value(json!({"url": "http://a", "score_path": "s"})).unwrap()
+            }),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_keyword_scorer() {
+        let scorer = KeywordScorer::compile(&KeywordScorerConfig {
+            rules: vec![
+                KeywordScoreRule {
+                    pattern: "SPAM".to_string(),
+                    is_regex: false,
+                    category: "spam".to_string(),
+                    score: 0.3,
+                },
+                KeywordScoreRule {
+                    pattern: r"buy\s*now".to_string(),
+                    is_regex: true,
+                    category: "spam".to_string(),
+                    score: 0.5,
+                },
+                KeywordScoreRule {
+                    pattern: "idiot".to_string(),
+                    is_regex: false,
+                    category: "harassment".to_string(),
+                    score: 0.4,
+                },
+            ],
+        })
+        .unwrap();
+
+        let result = scorer.score("ｓｐａｍ spam, BUY NOW");
+        assert!(!result.flagged);
+        assert_eq!(result.category_scores, json!({"spam": 1.0}));
+
+        let result = scorer.score("hello");
+        assert_eq!(result.category_scores, json!({}));
+
+        assert!(
+            KeywordScorer::compile(&KeywordScorerConfig {
+                rules: vec![KeywordScoreRule {
+                    pattern: "(".to_string(),
+                    is_regex: true,
+                    category: "spam".to_string(),
+                    score: 0.5,
+                }],
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_seal_secrets() {
+        unsafe { std::env::set_var("TINKER_SECRET", "a_very_secret_key_that_is_not_32_bytes!") };
+        let config = |headers: &[(&str, &str)]| BoardModerationConfig {
+            provider: ModerationProviderConfig::HttpClassifier(HttpClassifierConfig {
+                headers: headers
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+                ..serde_json::from_value(json!({"url": "http://a", "score_path": "s"})).unwrap()
+            }),
+            ..Default::default()
+        };
+        let headers = |config: &BoardModerationConfig| match &config.provider {
+            ModerationProviderConfig::HttpClassifier(http) => {
+                let mut headers = http.decrypted_headers().unwrap();
+                headers.sort();
+                headers
+            }
+            _ => unreachable!(),
+        };
+
+        let mut stored = config(&[("Authorization", "Bearer secret")]);
+        stored.seal_secrets(None);
+        assert!(!serde_json::to_string(&stored).unwrap().contains("secret"));
+        assert_eq!(
+            headers(&stored),
+            vec![("Authorization".to_string(), "Bearer secret".to_string())]
+        );
+
+        let mut masked = stored.clone();
+        masked.mask_secrets();
+        assert_eq!(masked, config(&[("Authorization", MASKED_SECRET)]));
+
+        // Masked values keep the stored ones, a masked header that was never stored is dropped
+        let mut edited = config(&[
+            ("Authorization", MASKED_SECRET),
+            ("X-Api-Key", "key"),
+            ("X-Unknown", MASKED_SECRET),
+        ]);
+        edited.seal_secrets(Some(&stored));
+        assert_eq!(
+            headers(&edited),
+            vec![
+                ("Authorization".to_string(), "Bearer secret".to_string()),
+                ("X-Api-Key".to_string(), "key".to_string()),
+            ]
+        );
+
+        // Stored before the headers were encrypted
+        assert_eq!(
+            headers(&config(&[("X-Api-Key", "plain")])),
+            vec![("X-Api-Key".to_string(), "plain".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_render_body() {
+        let config: HttpClassifierConfig = serde_json::from_value(json!({
+            "url": "http://localhost/classify",
+            "score_path": "score",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            config.render_body("say \"hi\"\n"),
+            r#"{"text": "say \"hi\"\n"}"#
+        );
+    }
+}
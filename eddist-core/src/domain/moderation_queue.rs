@@ -0,0 +1,94 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use super::pubsub_repository::{CreatingRes, CreatingThread};
+
+/// A post waiting in the moderation queue, stored as it would have been created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "post")]
+pub enum HeldPost {
+    Response(Box<CreatingRes>),
+    Thread(Box<CreatingThread>),
+}
+
+impl HeldPost {
+    pub fn post_type(&self) -> &'static str {
+        match self {
+            HeldPost::Response(_) => "RESPONSE",
+            HeldPost::Thread(_) => "THREAD",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum HoldReason {
+    NgWord,
+    AiModeration,
+    LowLevel,
+}
+
+impl HoldReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HoldReason::NgWord => "NG_WORD",
+            HoldReason::AiModeration => "AI_MODERATION",
+            HoldReason::LowLevel => "LOW_LEVEL",
+        }
+    }
+}
+
+impl FromStr for HoldReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "NG_WORD" => Ok(HoldReason::NgWord),
+            "AI_MODERATION" => Ok(HoldReason::AiModeration),
+            "LOW_LEVEL" => Ok(HoldReason::LowLevel),
+            _ => Err(format!("Invalid hold reason: {s}")),
+        }
+    }
+}
+
+impl Display for HoldReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ModerationQueueStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ModerationQueueStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationQueueStatus::Pending => "PENDING",
+            ModerationQueueStatus::Approved => "APPROVED",
+            ModerationQueueStatus::Rejected => "REJECTED",
+        }
+    }
+}
+
+impl FromStr for ModerationQueueStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "PENDING" => Ok(ModerationQueueStatus::Pending),
+            "APPROVED" => Ok(ModerationQueueStatus::Approved),
+            "REJECTED" => Ok(ModerationQueueStatus::Rejected),
+            _ => Err(format!("Invalid moderation queue status: {s}")),
+        }
+    }
+}
+
+impl Display for ModerationQueueStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
@@ -0,0 +1,188 @@
+use std::{fmt::Display, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::pubsub_repository::ModerationResult;
+
+/// Category of a [`ModerationRule`] that matches the highest score of any category
+pub const ANY_MODERATION_CATEGORY: &str = "*";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ModerationRuleAction {
+    /// Abone the response (or the first response of a thread)
+    AboneResponse,
+    /// Suspend the poster's authed token for `duration_seconds`
+    SuspendToken,
+    /// Cap the poster's tinker internal level at `internal_level` for `duration_seconds`
+    LowerInternalLevel,
+    /// Add the thread to the board's safe-mode unsafe thread set
+    MarkThreadUnsafe,
+}
+
+impl ModerationRuleAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationRuleAction::AboneResponse => "ABONE_RESPONSE",
+            ModerationRuleAction::SuspendToken => "SUSPEND_TOKEN",
+            ModerationRuleAction::LowerInternalLevel => "LOWER_INTERNAL_LEVEL",
+            ModerationRuleAction::MarkThreadUnsafe => "MARK_THREAD_UNSAFE",
+        }
+    }
+
+    /// Whether the action expires after the rule's `duration_seconds`
+    pub fn is_timed(&self) -> bool {
+        matches!(
+            self,
+            ModerationRuleAction::SuspendToken | ModerationRuleAction::LowerInternalLevel
+        )
+    }
+}
+
+impl FromStr for ModerationRuleAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ABONE_RESPONSE" => Ok(ModerationRuleAction::AboneResponse),
+            "SUSPEND_TOKEN" => Ok(ModerationRuleAction::SuspendToken),
+            "LOWER_INTERNAL_LEVEL" => Ok(ModerationRuleAction::LowerInternalLevel),
+            "MARK_THREAD_UNSAFE" => Ok(ModerationRuleAction::MarkThreadUnsafe),
+            _ => Err(format!("Invalid moderation rule action: {s}")),
+        }
+    }
+}
+
+impl Display for ModerationRuleAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Action taken automatically when a moderation result scores a category at or above `threshold`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModerationRule {
+    pub id: Uuid,
+    pub name: String,
+    /// Applies to every board when unset
+    pub board_id: Option<Uuid>,
+    /// Category in `category_scores`, or `*` for any category
+    pub category: String,
+    pub threshold: f64,
+    pub action: ModerationRuleAction,
+    /// Only used by timed actions
+    pub duration_seconds: u64,
+    /// Only used by `LowerInternalLevel`
+    pub internal_level: u32,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ModerationRule {
+    /// Category and score that triggered the rule for a post on `board_id`, if any
+    pub fn matched_score(
+        &self,
+        board_id: Uuid,
+        result: &ModerationResult,
+    ) -> Option<(String, f64)> {
+        if !self.enabled || self.board_id.is_some_and(|id| id != board_id) {
+            return None;
+        }
+        let Value::Object(scores) = &result.category_scores else {
+            return None;
+        };
+
+        scores
+            .iter()
+            .filter(|(category, _)| {
+                self.category == ANY_MODERATION_CATEGORY || **category == self.category
+            })
+            .filter_map(|(category, score)| Some((category.clone(), score.as_f64()?)))
+            .filter(|(_, score)| *score >= self.threshold)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+}
+
+/// Record of an action taken by a [`ModerationRule`], kept so that it can be reviewed and reverted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModerationActionLog {
+    pub id: Uuid,
+    /// Unset once the rule is deleted
+    pub rule_id: Option<Uuid>,
+    pub action: ModerationRuleAction,
+    pub board_id: Uuid,
+    pub thread_number: u64,
+    pub response_id: Uuid,
+    pub authed_token_id: Uuid,
+    pub category: String,
+    pub score: f64,
+    /// When the suspension or level cap ends, for timed actions
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub reverted_at: Option<DateTime<Utc>>,
+    pub reverted_by: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_matched_score() {
+        let board_id = Uuid::now_v7();
+        let mut rule = ModerationRule {
+            id: Uuid::now_v7(),
+            name: "harassment".to_string(),
+            board_id: None,
+            category: "harassment".to_string(),
+            threshold: 0.8,
+            action: ModerationRuleAction::AboneResponse,
+            duration_seconds: 0,
+            internal_level: 0,
+            enabled: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let result = ModerationResult {
+            flagged: true,
+            categories: json!({}),
+            category_scores: json!({"harassment": 0.85, "violence": 0.95, "spam": 0.1}),
+        };
+
+        assert_eq!(
+            rule.matched_score(board_id, &result),
+            Some(("harassment".to_string(), 0.85))
+        );
+
+        rule.category = ANY_MODERATION_CATEGORY.to_string();
+        assert_eq!(
+            rule.matched_score(board_id, &result),
+            Some(("violence".to_string(), 0.95))
+        );
+
+        rule.threshold = 0.99;
+        assert_eq!(rule.matched_score(board_id, &result), None);
+
+        rule.threshold = 0.5;
+        rule.board_id = Some(Uuid::now_v7());
+        assert_eq!(rule.matched_score(board_id, &result), None);
+
+        rule.board_id = Some(board_id);
+        rule.enabled = false;
+        assert_eq!(rule.matched_score(board_id, &result), None);
+
+        for action in [
+            ModerationRuleAction::AboneResponse,
+            ModerationRuleAction::SuspendToken,
+            ModerationRuleAction::LowerInternalLevel,
+            ModerationRuleAction::MarkThreadUnsafe,
+        ] {
+            assert_eq!(action.as_str().parse::<ModerationRuleAction>(), Ok(action));
+        }
+    }
+}
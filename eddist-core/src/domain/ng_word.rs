@@ -0,0 +1,694 @@
+use std::{collections::HashSet, fmt::Display, str::FromStr, sync::LazyLock};
+
+use regex::{Regex, RegexBuilder};
+use regex_syntax::ast::{self, Ast, ClassSetItem};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use uuid::Uuid;
+
+/// Compiled regex size limit for a single NG word, to keep admin-provided
+/// patterns from blowing up memory on every post.
+pub(crate) const NG_WORD_REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+static URL_HOST_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"h?ttps?://([^/\s:?#<>"']+)"#).unwrap());
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum NgWordMatchType {
+    #[default]
+    Literal,
+    Regex,
+}
+
+impl NgWordMatchType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NgWordMatchType::Literal => "LITERAL",
+            NgWordMatchType::Regex => "REGEX",
+        }
+    }
+}
+
+impl FromStr for NgWordMatchType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "LITERAL" => Ok(NgWordMatchType::Literal),
+            "REGEX" => Ok(NgWordMatchType::Regex),
+            _ => Err(format!("Invalid ng word match type: {s}")),
+        }
+    }
+}
+
+impl Display for NgWordMatchType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// What happens to a post that hits an NG word
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum NgWordAction {
+    /// Refuse the post with an error, as NG words always did
+    #[default]
+    Reject,
+    /// Accept the post but store it as abone, visible only to the poster
+    ShadowAbone,
+    /// Accept the post into the moderation queue instead of the thread
+    Hold,
+    /// Accept the post and extend the poster's creation span
+    Penalize,
+}
+
+impl NgWordAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NgWordAction::Reject => "REJECT",
+            NgWordAction::ShadowAbone => "SHADOW_ABONE",
+            NgWordAction::Hold => "HOLD",
+            NgWordAction::Penalize => "PENALIZE",
+        }
+    }
+
+    /// Higher wins when a post hits several NG words
+    fn severity(&self) -> u8 {
+        match self {
+            NgWordAction::Reject => 3,
+            NgWordAction::Hold => 2,
+            NgWordAction::ShadowAbone => 1,
+            NgWordAction::Penalize => 0,
+        }
+    }
+}
+
+impl FromStr for NgWordAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "REJECT" => Ok(NgWordAction::Reject),
+            "SHADOW_ABONE" => Ok(NgWordAction::ShadowAbone),
+            "HOLD" => Ok(NgWordAction::Hold),
+            "PENALIZE" => Ok(NgWordAction::Penalize),
+            _ => Err(format!("Invalid ng word action: {s}")),
+        }
+    }
+}
+
+impl Display for NgWordAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum NgWordTarget {
+    Body,
+    Name,
+    Mail,
+    Title,
+    UrlHost,
+}
+
+impl NgWordTarget {
+    /// Targets applied to NG words created before per-field targeting existed
+    pub const DEFAULT: [NgWordTarget; 4] = [
+        NgWordTarget::Body,
+        NgWordTarget::Name,
+        NgWordTarget::Mail,
+        NgWordTarget::Title,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NgWordTarget::Body => "BODY",
+            NgWordTarget::Name => "NAME",
+            NgWordTarget::Mail => "MAIL",
+            NgWordTarget::Title => "TITLE",
+            NgWordTarget::UrlHost => "URL_HOST",
+        }
+    }
+
+    /// Parses the comma-separated form stored in `ng_words.target_fields`
+    pub fn parse_list(s: &str) -> Result<Vec<NgWordTarget>, String> {
+        let mut targets = Vec::new();
+        for target in s.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+            let target = target.parse::<NgWordTarget>()?;
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        Ok(targets)
+    }
+
+    pub fn join_list(targets: &[NgWordTarget]) -> String {
+        targets
+            .iter()
+            .map(NgWordTarget::as_str)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+impl FromStr for NgWordTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "BODY" => Ok(NgWordTarget::Body),
+            "NAME" => Ok(NgWordTarget::Name),
+            "MAIL" => Ok(NgWordTarget::Mail),
+            "TITLE" => Ok(NgWordTarget::Title),
+            "URL_HOST" => Ok(NgWordTarget::UrlHost),
+            _ => Err(format!("Invalid ng word target: {s}")),
+        }
+    }
+}
+
+impl Display for NgWordTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}' | '\u{180E}' | '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}' | '\u{FEFF}'
+    )
+}
+
+fn fold_kana(c: char) -> char {
+    // Hiragana (U+3041..=U+3096) and their iteration marks map onto katakana
+    match c {
+        '\u{3041}'..='\u{3096}' | '\u{309D}' | '\u{309E}' => {
+            char::from_u32(c as u32 + 0x60).unwrap_or(c)
+        }
+        _ => c,
+    }
+}
+
+/// NFKC, lowercase, drop invisible characters and fold hiragana into katakana,
+/// keeping whitespace so that URLs can still be split out of the text.
+fn fold(text: &str) -> String {
+    text.nfkc()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !is_invisible(*c))
+        .map(fold_kana)
+        .collect()
+}
+
+/// Normalized form NG words are matched against.
+///
+/// Applies NFKC, case folding and hiragana-to-katakana folding, and strips
+/// whitespace and zero-width characters.
+pub fn normalize_for_matching(text: &str) -> String {
+    fold(text).chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Hosts of URLs appearing in `text` (including the `ttp://` form), normalized
+pub fn extract_url_hosts(text: &str) -> Vec<String> {
+    URL_HOST_REGEX
+        .captures_iter(&fold(text))
+        .filter_map(|c| c.get(1))
+        .map(|host| {
+            let host = host.as_str();
+            let host = host.rsplit_once('@').map_or(host, |(_, h)| h);
+            host.trim_end_matches('.').to_string()
+        })
+        .filter(|host| !host.is_empty())
+        .collect()
+}
+
+/// Rewrites the literal parts of a regex pattern into the normalized form, so that
+/// e.g. `すぱむ\d+` matches the `スパム123` it is run against.
+///
+/// Runs of adjacent literals are normalized together so that composed characters
+/// (e.g. half-width `ﾊﾟ`) fold the same way as in the text. Class literals and
+/// ranges keep their original form and gain the folded one when it is a single
+/// character. Patterns that fail to parse are returned unchanged and left for the
+/// regex compiler to report.
+fn normalize_regex_literals(pattern: &str) -> String {
+    let Ok(ast) = ast::parse::Parser::new().parse(pattern) else {
+        return pattern.to_string();
+    };
+    let Ok(mut edits) = ast::visit(
+        &ast,
+        LiteralNormalizer {
+            pattern,
+            in_concat: HashSet::new(),
+            edits: Vec::new(),
+        },
+    ) else {
+        return pattern.to_string();
+    };
+
+    edits.sort_by_key(|(span, _)| span.start);
+    let mut normalized = String::with_capacity(pattern.len());
+    let mut last = 0;
+    for (span, replacement) in edits {
+        normalized.push_str(&pattern[last..span.start]);
+        normalized.push_str(&replacement);
+        last = span.end;
+    }
+    normalized.push_str(&pattern[last..]);
+    normalized
+}
+
+struct LiteralNormalizer<'a> {
+    pattern: &'a str,
+    /// Start offsets of literals already rewritten as part of a run in a concatenation
+    in_concat: HashSet<usize>,
+    edits: Vec<(std::ops::Range<usize>, String)>,
+}
+
+impl LiteralNormalizer<'_> {
+    fn fold_char(c: char) -> Option<char> {
+        let folded = normalize_for_matching(&c.to_string());
+        let mut chars = folded.chars();
+        match (chars.next(), chars.next()) {
+            (Some(folded), None) => Some(folded),
+            _ => None,
+        }
+    }
+
+    fn push_run(&mut self, run: &[&ast::Literal]) {
+        let (Some(first), Some(last)) = (run.first(), run.last()) else {
+            return;
+        };
+        let text = run.iter().map(|lit| lit.c).collect::<String>();
+        let normalized = regex_syntax::escape(&normalize_for_matching(&text));
+        self.edits
+            .push((first.span.start.offset..last.span.end.offset, normalized));
+    }
+}
+
+impl ast::Visitor for LiteralNormalizer<'_> {
+    type Output = Vec<(std::ops::Range<usize>, String)>;
+    type Err = ();
+
+    fn finish(self) -> Result<Self::Output, Self::Err> {
+        Ok(self.edits)
+    }
+
+    fn visit_pre(&mut self, ast: &Ast) -> Result<(), Self::Err> {
+        match ast {
+            Ast::Concat(concat) => {
+                let mut run = Vec::new();
+                for item in &concat.asts {
+                    if let Ast::Literal(lit) = item {
+                        self.in_concat.insert(lit.span.start.offset);
+                        run.push(lit.as_ref());
+                    } else {
+                        self.push_run(&run);
+                        run.clear();
+                    }
+                }
+                self.push_run(&run);
+            }
+            Ast::Literal(lit) if !self.in_concat.contains(&lit.span.start.offset) => {
+                // A lone literal may be the operand of a repetition, so keep it a single item
+                let normalized = normalize_for_matching(&lit.c.to_string());
+                let replacement = if normalized.chars().count() == 1 {
+                    regex_syntax::escape(&normalized)
+                } else {
+                    format!("(?:{})", regex_syntax::escape(&normalized))
+                };
+                self.edits
+                    .push((lit.span.start.offset..lit.span.end.offset, replacement));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn visit_class_set_item_pre(&mut self, item: &ClassSetItem) -> Result<(), Self::Err> {
+        match item {
+            ClassSetItem::Literal(lit) => {
+                if let Some(folded) = Self::fold_char(lit.c).filter(|&folded| folded != lit.c) {
+                    let original = &self.pattern[lit.span.start.offset..lit.span.end.offset];
+                    self.edits.push((
+                        lit.span.start.offset..lit.span.end.offset,
+                        format!("{original}{}", regex_syntax::escape(&folded.to_string())),
+                    ));
+                }
+            }
+            ClassSetItem::Range(range) => {
+                let folded = Self::fold_char(range.start.c).zip(Self::fold_char(range.end.c));
+                if let Some((start, end)) = folded.filter(|&(start, end)| {
+                    start <= end && (start, end) != (range.start.c, range.end.c)
+                }) {
+                    let original = &self.pattern[range.span.start.offset..range.span.end.offset];
+                    self.edits.push((
+                        range.span.start.offset..range.span.end.offset,
+                        format!(
+                            "{original}{}-{}",
+                            regex_syntax::escape(&start.to_string()),
+                            regex_syntax::escape(&end.to_string())
+                        ),
+                    ));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+enum NgWordPattern {
+    Literal(String),
+    Regex(Regex),
+}
+
+#[derive(Debug, Clone)]
+pub struct CompiledNgWord {
+    pub id: Uuid,
+    pub name: String,
+    pub action: NgWordAction,
+    pub penalty_seconds: u32,
+    pattern: NgWordPattern,
+    targets: Vec<NgWordTarget>,
+}
+
+impl CompiledNgWord {
+    /// Literal words are normalized the same way as the text they are matched against.
+    /// Regex patterns are compiled case-insensitively with their literal parts normalized
+    /// and run on the normalized text, so they should not rely on whitespace being present.
+    pub fn compile(
+        id: Uuid,
+        name: &str,
+        word: &str,
+        match_type: NgWordMatchType,
+        targets: &[NgWordTarget],
+    ) -> Result<Self, regex::Error> {
+        let pattern = match match_type {
+            NgWordMatchType::Literal => NgWordPattern::Literal(normalize_for_matching(word)),
+            NgWordMatchType::Regex => NgWordPattern::Regex(
+                RegexBuilder::new(&normalize_regex_literals(word))
+                    .case_insensitive(true)
+                    .size_limit(NG_WORD_REGEX_SIZE_LIMIT)
+                    .build()?,
+            ),
+        };
+
+        Ok(Self {
+            id,
+            name: name.to_string(),
+            action: NgWordAction::default(),
+            penalty_seconds: 0,
+            pattern,
+            targets: targets.to_vec(),
+        })
+    }
+
+    pub fn with_action(mut self, action: NgWordAction, penalty_seconds: u32) -> Self {
+        self.action = action;
+        self.penalty_seconds = penalty_seconds;
+        self
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        match &self.pattern {
+            // An empty literal would match every post
+            NgWordPattern::Literal(word) => !word.is_empty() && text.contains(word.as_str()),
+            NgWordPattern::Regex(regex) => regex.is_match(text),
+        }
+    }
+
+    pub fn matches(&self, subject: &NgWordSubject) -> bool {
+        self.targets.iter().any(|target| match target {
+            NgWordTarget::Body => self.is_match(&subject.body),
+            NgWordTarget::Name => self.is_match(&subject.name),
+            NgWordTarget::Mail => self.is_match(&subject.mail),
+            NgWordTarget::Title => subject.title.as_deref().is_some_and(|t| self.is_match(t)),
+            NgWordTarget::UrlHost => subject.url_hosts.iter().any(|h| self.is_match(h)),
+        })
+    }
+}
+
+/// Fields of a post in normalized form, computed once per post
+#[derive(Debug, Clone)]
+pub struct NgWordSubject {
+    body: String,
+    name: String,
+    mail: String,
+    title: Option<String>,
+    url_hosts: Vec<String>,
+}
+
+impl NgWordSubject {
+    pub fn new(body: &str, name: &str, mail: &str, title: Option<&str>) -> Self {
+        Self {
+            body: normalize_for_matching(body),
+            name: normalize_for_matching(name),
+            mail: normalize_for_matching(mail),
+            title: title.map(normalize_for_matching),
+            url_hosts: extract_url_hosts(body),
+        }
+    }
+}
+
+/// Outcome of matching a post against all NG words of a board
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NgWordVerdict {
+    /// The most severe action among the hit NG words
+    pub action: NgWordAction,
+    /// The NG word that decided `action`
+    pub ng_word_id: Uuid,
+    /// Sum of the penalties of every hit `Penalize` NG word
+    pub penalty_seconds: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NgWordMatcher {
+    words: Vec<CompiledNgWord>,
+}
+
+impl NgWordMatcher {
+    pub fn new(words: Vec<CompiledNgWord>) -> Self {
+        Self { words }
+    }
+
+    pub fn find(&self, subject: &NgWordSubject) -> Option<&CompiledNgWord> {
+        self.words.iter().find(|word| word.matches(subject))
+    }
+
+    pub fn verdict(&self, subject: &NgWordSubject) -> Option<NgWordVerdict> {
+        let mut verdict: Option<NgWordVerdict> = None;
+        for word in self.words.iter().filter(|word| word.matches(subject)) {
+            let penalty_seconds = if word.action == NgWordAction::Penalize {
+                word.penalty_seconds
+            } else {
+                0
+            };
+            match &mut verdict {
+                Some(v) => {
+                    v.penalty_seconds = v.penalty_seconds.saturating_add(penalty_seconds);
+                    if word.action.severity() > v.action.severity() {
+                        v.action = word.action;
+                        v.ng_word_id = word.id;
+                    }
+                }
+                None => {
+                    verdict = Some(NgWordVerdict {
+                        action: word.action,
+                        ng_word_id: word.id,
+                        penalty_seconds,
+                    })
+                }
+            }
+            if word.action == NgWordAction::Reject {
+                // Nothing outranks a rejection
+                break;
+            }
+        }
+        verdict
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(word: &str, match_type: NgWordMatchType, targets: &[NgWordTarget]) -> NgWordMatcher {
+        NgWordMatcher::new(vec![
+            CompiledNgWord::compile(Uuid::nil(), "test", word, match_type, targets).unwrap(),
+        ])
+    }
+
+    fn body(text: &str) -> NgWordSubject {
+        NgWordSubject::new(text, "", "", None)
+    }
+
+    #[test]
+    fn test_normalize_width_case_and_kana() {
+        assert_eq!(normalize_for_matching("ＳＰＡＭ"), "spam");
+        assert_eq!(normalize_for_matching("ｽﾊﾟﾑ"), "スパム");
+        assert_eq!(normalize_for_matching("すぱむ"), "スパム");
+        assert_eq!(
+            normalize_for_matching("s p\u{200B}a\u{FEFF}m\u{3000}"),
+            "spam"
+        );
+    }
+
+    #[test]
+    fn test_literal_matches_variants() {
+        let matcher = compile("すぱむ", NgWordMatchType::Literal, &NgWordTarget::DEFAULT);
+        assert!(matcher.find(&body("これはスパムです")).is_some());
+        assert!(matcher.find(&body("これはｽﾊﾟﾑです")).is_some());
+        assert!(matcher.find(&body("これはす\u{200B}ぱ む")).is_some());
+        assert!(matcher.find(&body("普通の書き込み")).is_none());
+    }
+
+    #[test]
+    fn test_empty_literal_never_matches() {
+        let matcher = compile(" ", NgWordMatchType::Literal, &NgWordTarget::DEFAULT);
+        assert!(matcher.find(&body("anything")).is_none());
+    }
+
+    #[test]
+    fn test_regex_runs_on_normalized_text() {
+        let matcher = compile(r"bu[yi]\d+", NgWordMatchType::Regex, &NgWordTarget::DEFAULT);
+        assert!(matcher.find(&body("ＢＵＹ１２３")).is_some());
+        assert!(matcher.find(&body("b u i 9")).is_some());
+        assert!(matcher.find(&body("buy now")).is_none());
+    }
+
+    #[test]
+    fn test_regex_literals_are_normalized() {
+        let matcher = compile(r"すぱむ\d+", NgWordMatchType::Regex, &NgWordTarget::DEFAULT);
+        assert!(matcher.find(&body("スパム123")).is_some());
+        assert!(matcher.find(&body("すぱむ１２３")).is_some());
+
+        let matcher = compile(
+            "ｽﾊﾟﾑ|ＢＵＹ NOW",
+            NgWordMatchType::Regex,
+            &NgWordTarget::DEFAULT,
+        );
+        assert!(matcher.find(&body("スパム")).is_some());
+        assert!(matcher.find(&body("buy now")).is_some());
+
+        let matcher = compile(
+            "[ぁ-ん]{3}ｗ+",
+            NgWordMatchType::Regex,
+            &NgWordTarget::DEFAULT,
+        );
+        assert!(matcher.find(&body("あいうwww")).is_some());
+        assert!(matcher.find(&body("abcwww")).is_none());
+
+        assert_eq!(normalize_regex_literals(r"a.b\.c"), r"a.b\.c");
+        assert_eq!(normalize_regex_literals("a +"), "a(?:)+");
+        assert_eq!(normalize_regex_literals("(unclosed"), "(unclosed");
+    }
+
+    #[test]
+    fn test_targets_are_respected() {
+        let matcher = compile("spam", NgWordMatchType::Literal, &[NgWordTarget::Name]);
+        assert!(matcher.find(&body("spam")).is_none());
+        assert!(
+            matcher
+                .find(&NgWordSubject::new("hello", "SPAM", "", None))
+                .is_some()
+        );
+
+        let matcher = compile("spam", NgWordMatchType::Literal, &[NgWordTarget::Title]);
+        assert!(
+            matcher
+                .find(&NgWordSubject::new("", "", "", Some("spam thread")))
+                .is_some()
+        );
+        assert!(
+            matcher
+                .find(&NgWordSubject::new("spam", "", "", None))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_url_host_target() {
+        assert_eq!(
+            extract_url_hosts("見て ttp://User@Evil.Example.com:8080/x と https://ok.example/"),
+            vec!["evil.example.com", "ok.example"]
+        );
+
+        let matcher = compile(
+            r"(^|\.)evil\.example\.com$",
+            NgWordMatchType::Regex,
+            &[NgWordTarget::UrlHost],
+        );
+        assert!(
+            matcher
+                .find(&body("https://www.EVIL.example.com/path"))
+                .is_some()
+        );
+        assert!(matcher.find(&body("evil.example.com")).is_none());
+        assert!(
+            matcher
+                .find(&body("https://notevil.example.com.jp/"))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_verdict_picks_most_severe_action() {
+        let word = |id: u128, word: &str, action, penalty| {
+            CompiledNgWord::compile(
+                Uuid::from_u128(id),
+                "test",
+                word,
+                NgWordMatchType::Literal,
+                &NgWordTarget::DEFAULT,
+            )
+            .unwrap()
+            .with_action(action, penalty)
+        };
+        let matcher = NgWordMatcher::new(vec![
+            word(1, "foo", NgWordAction::Penalize, 10),
+            word(2, "bar", NgWordAction::ShadowAbone, 0),
+            word(3, "baz", NgWordAction::Hold, 0),
+            word(4, "fo", NgWordAction::Penalize, 5),
+        ]);
+
+        assert_eq!(matcher.verdict(&body("nothing")), None);
+        assert_eq!(
+            matcher.verdict(&body("foo")),
+            Some(NgWordVerdict {
+                action: NgWordAction::Penalize,
+                ng_word_id: Uuid::from_u128(1),
+                penalty_seconds: 15,
+            })
+        );
+        assert_eq!(
+            matcher.verdict(&body("foo bar baz")),
+            Some(NgWordVerdict {
+                action: NgWordAction::Hold,
+                ng_word_id: Uuid::from_u128(3),
+                penalty_seconds: 15,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_target_list() {
+        assert_eq!(
+            NgWordTarget::parse_list("BODY, URL_HOST,BODY").unwrap(),
+            vec![NgWordTarget::Body, NgWordTarget::UrlHost]
+        );
+        assert_eq!(
+            NgWordTarget::join_list(&NgWordTarget::DEFAULT),
+            "BODY,NAME,MAIL,TITLE"
+        );
+        assert!(NgWordTarget::parse_list("BODY,FOO").is_err());
+    }
+}
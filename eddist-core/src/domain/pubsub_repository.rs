@@ -35,6 +35,8 @@ pub struct CreatingRes {
     pub res_order: i32,
     pub is_sage: bool,
     pub moderation_result: Option<ModerationResult>,
+    #[serde(default)]
+    pub is_abone: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -92,4 +94,6 @@ pub struct CreatingThread {
     pub metadent: MetadentType,
     pub client_info: ClientInfo,
     pub moderation_result: Option<ModerationResult>,
+    #[serde(default)]
+    pub is_abone: bool,
 }
@@ -0,0 +1,117 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::ng_word::normalize_for_matching;
+
+/// Longest query accepted, in characters
+pub const MAX_SEARCH_QUERY_CHARS: usize = 64;
+
+/// Characters per n-gram of the search index
+pub const SEARCH_NGRAM_SIZE: usize = 2;
+
+/// N-grams of a query looked up in the index, the rarest ones. The candidates are checked
+/// against the whole query anyway, so the others would only make the lookup slower.
+pub const MAX_SEARCH_NGRAMS: usize = 8;
+
+/// Words of a text in the normalized form of NG words, split on whitespace and on the
+/// `<br>` of dat bodies, with the escaped characters of the body restored
+fn normalized_words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split("<br>")
+        .flat_map(str::split_whitespace)
+        .map(|word| {
+            normalize_for_matching(
+                &word
+                    .replace("&lt;", "<")
+                    .replace("&gt;", ">")
+                    .replace("&quot;", "\"")
+                    .replace("&amp;", "&"),
+            )
+        })
+        .filter(|word| !word.is_empty())
+}
+
+/// Bi-grams of every word of the text, deduplicated. Words of a single character have
+/// none, so they cannot be searched on their own.
+pub fn search_ngrams(text: &str) -> Vec<String> {
+    let mut ngrams = BTreeSet::new();
+    for word in normalized_words(text) {
+        let chars = word.chars().collect::<Vec<_>>();
+        for window in chars.windows(SEARCH_NGRAM_SIZE) {
+            ngrams.insert(window.iter().collect::<String>());
+        }
+    }
+    ngrams.into_iter().collect()
+}
+
+/// The n-grams of a query to look up, rarest first, from the number of documents having each
+/// of them. `None` when one of them is in no document, so that nothing can match.
+pub fn rarest_ngrams(ngrams: &[String], doc_counts: &HashMap<String, u64>) -> Option<Vec<String>> {
+    let mut counted = ngrams
+        .iter()
+        .map(|ngram| match doc_counts.get(ngram) {
+            Some(&count) if count > 0 => Some((count, ngram)),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    counted.sort();
+    Some(
+        counted
+            .into_iter()
+            .take(MAX_SEARCH_NGRAMS)
+            .map(|(_, ngram)| ngram.clone())
+            .collect(),
+    )
+}
+
+/// Whether every word of the query appears in the text. The n-grams of a query only find
+/// candidates, whose text is then checked with this.
+pub fn matches_search_query(text: &str, query: &str) -> bool {
+    let text = normalized_words(text).collect::<Vec<_>>().join(" ");
+    normalized_words(query).all(|word| text.contains(&word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_ngrams() {
+        assert_eq!(search_ngrams("テスト"), vec!["スト", "テス"]);
+        assert_eq!(
+            search_ngrams("ｔｅｓｔ<br>てすと"),
+            vec!["es", "st", "te", "スト", "テス"]
+        );
+        assert_eq!(search_ngrams("a b"), Vec::<String>::new());
+        assert_eq!(search_ngrams("&gt;&gt;1"), vec![">1", ">>"]);
+    }
+
+    #[test]
+    fn test_matches_search_query() {
+        assert!(matches_search_query("これはテストです", "てすと"));
+        assert!(matches_search_query(
+            "これは<br>テストです",
+            "これは テスト"
+        ));
+        assert!(matches_search_query("&gt;&gt;1 乙", ">>1"));
+        // All the bi-grams of the query appear, but not the query itself
+        assert!(!matches_search_query("ストテス", "テスト"));
+        assert!(!matches_search_query("これはテストです", "テスト 本番"));
+    }
+
+    #[test]
+    fn test_rarest_ngrams() {
+        let ngrams = search_ngrams("テスト本番環境");
+        let mut doc_counts = ngrams
+            .iter()
+            .enumerate()
+            .map(|(i, ngram)| (ngram.clone(), 1000 - i as u64))
+            .collect::<HashMap<_, _>>();
+        doc_counts.insert("本番".to_string(), 3);
+
+        let rarest = rarest_ngrams(&ngrams, &doc_counts).unwrap();
+        assert_eq!(rarest.len(), ngrams.len().min(MAX_SEARCH_NGRAMS));
+        assert_eq!(rarest[0], "本番");
+
+        doc_counts.remove("環境");
+        assert_eq!(rarest_ngrams(&ngrams, &doc_counts), None);
+    }
+}
@@ -12,6 +12,9 @@ pub struct Tinker {
     last_level_up_at: u64,
     last_wrote_at: u64,
     last_created_thread_at: Option<u64>,
+    /// Temporary cap applied by automated moderation, never written to the cookie
+    #[serde(skip)]
+    internal_level_cap: Option<u32>,
 }
 
 impl Tinker {
@@ -34,6 +37,7 @@ impl Tinker {
             last_level_up_at,
             last_wrote_at,
             last_created_thread_at,
+            internal_level_cap: None,
         }
     }
 
@@ -47,6 +51,7 @@ impl Tinker {
             last_level_up_at: datetime.timestamp() as u64,
             last_wrote_at: 0,
             last_created_thread_at: None,
+            internal_level_cap: None,
         }
     }
 
@@ -72,6 +77,7 @@ impl Tinker {
             },
             last_wrote_at: timestamp,
             last_created_thread_at: self.last_created_thread_at,
+            internal_level_cap: self.internal_level_cap,
         }
     }
 
@@ -98,6 +104,7 @@ impl Tinker {
             },
             last_wrote_at: timestamp,
             last_created_thread_at: Some(timestamp),
+            internal_level_cap: self.internal_level_cap,
         }
     }
 
@@ -106,7 +113,17 @@ impl Tinker {
     }
 
     pub fn internal_level(&self) -> u32 {
-        self.internal_level.unwrap_or(0)
+        let internal_level = self.internal_level.unwrap_or(0);
+        self.internal_level_cap
+            .map_or(internal_level, |cap| internal_level.min(cap))
+    }
+
+    /// Caps `internal_level()` without changing the level stored in the cookie
+    pub fn with_internal_level_cap(self, cap: Option<u32>) -> Self {
+        Self {
+            internal_level_cap: cap,
+            ..self
+        }
     }
 
     /// Patches `internal_level` to `v` only when it is absent (legacy JWT without `ilvl`).
@@ -181,6 +198,20 @@ mod tests {
         assert_eq!(patched.internal_level(), 2); // not overwritten
     }
 
+    #[test]
+    fn internal_level_cap_is_not_serialized() {
+        let t = Tinker::from_parts("tok".into(), 0, 0, 5, 5, 0, 0, None)
+            .with_internal_level_cap(Some(1));
+        assert_eq!(t.internal_level(), 1);
+
+        let updated = t.action_on_write(far_future());
+        assert_eq!(updated.internal_level(), 1);
+
+        let restored: Tinker =
+            serde_json::from_str(&serde_json::to_string(&updated).unwrap()).unwrap();
+        assert_eq!(restored.internal_level(), 6);
+    }
+
     #[test]
     fn action_on_write_caps_level_and_internal_level_at_20() {
         let t = Tinker::from_parts("tok".into(), 0, 0, 20, 20, 0, 0, None);
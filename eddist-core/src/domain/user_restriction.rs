@@ -1,15 +1,20 @@
+use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use std::net::IpAddr;
 use std::str::FromStr;
 use uuid::Uuid;
 
+use super::{board::validate_board_key, ng_word::NG_WORD_REGEX_SIZE_LIMIT};
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum RestrictionRuleType {
     Asn,
     IP,
     IPCidr,
     UserAgent,
+    /// `rule_value` holds a [`RestrictionCondition`] as JSON
+    Composite,
 }
 
 impl RestrictionRuleType {
@@ -19,6 +24,7 @@ impl RestrictionRuleType {
             RestrictionRuleType::IP => "IP",
             RestrictionRuleType::IPCidr => "IP_CIDR",
             RestrictionRuleType::UserAgent => "USER_AGENT",
+            RestrictionRuleType::Composite => "COMPOSITE",
         }
     }
 }
@@ -32,6 +38,7 @@ impl FromStr for RestrictionRuleType {
             "IP" => Ok(RestrictionRuleType::IP),
             "IP_CIDR" => Ok(RestrictionRuleType::IPCidr),
             "USER_AGENT" => Ok(RestrictionRuleType::UserAgent),
+            "COMPOSITE" => Ok(RestrictionRuleType::Composite),
             _ => Err(format!("Invalid restriction rule type: {}", s)),
         }
     }
@@ -43,13 +50,283 @@ impl Display for RestrictionRuleType {
     }
 }
 
+/// Condition tree of a composite rule, e.g.
+/// `{"type":"all","conditions":[{"type":"asn","value":1234},{"type":"user_agent","value":"Y"}]}`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RestrictionCondition {
+    Asn {
+        value: u32,
+    },
+    Ip {
+        value: String,
+    },
+    IpCidr {
+        value: String,
+    },
+    /// Matches when the user agent contains the value
+    UserAgent {
+        value: String,
+    },
+    UserAgentRegex {
+        value: String,
+    },
+    All {
+        conditions: Vec<RestrictionCondition>,
+    },
+    Any {
+        conditions: Vec<RestrictionCondition>,
+    },
+    Not {
+        condition: Box<RestrictionCondition>,
+    },
+}
+
+impl RestrictionCondition {
+    pub fn compile(&self) -> Result<CompiledRestrictionCondition, String> {
+        Ok(match self {
+            RestrictionCondition::Asn { value } => CompiledRestrictionCondition::Asn(*value),
+            RestrictionCondition::Ip { value } => CompiledRestrictionCondition::Ip(
+                value
+                    .parse::<IpAddr>()
+                    .map_err(|e| format!("invalid IP address '{value}': {e}"))?,
+            ),
+            RestrictionCondition::IpCidr { value } => CompiledRestrictionCondition::IpCidr(
+                value
+                    .parse::<ipnet::IpNet>()
+                    .map_err(|e| format!("invalid CIDR '{value}': {e}"))?,
+            ),
+            RestrictionCondition::UserAgent { value } => {
+                if value.is_empty() {
+                    return Err("user agent must not be empty".to_string());
+                }
+                CompiledRestrictionCondition::UserAgent(value.clone())
+            }
+            RestrictionCondition::UserAgentRegex { value } => {
+                CompiledRestrictionCondition::UserAgentRegex(
+                    RegexBuilder::new(value)
+                        .size_limit(NG_WORD_REGEX_SIZE_LIMIT)
+                        .build()
+                        .map_err(|e| format!("invalid user agent regex '{value}': {e}"))?,
+                )
+            }
+            RestrictionCondition::All { conditions } | RestrictionCondition::Any { conditions } => {
+                // An empty `all` would match every request
+                if conditions.is_empty() {
+                    return Err("all/any must have at least one condition".to_string());
+                }
+                let compiled = conditions
+                    .iter()
+                    .map(RestrictionCondition::compile)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if matches!(self, RestrictionCondition::All { .. }) {
+                    CompiledRestrictionCondition::All(compiled)
+                } else {
+                    CompiledRestrictionCondition::Any(compiled)
+                }
+            }
+            RestrictionCondition::Not { condition } => {
+                CompiledRestrictionCondition::Not(Box::new(condition.compile()?))
+            }
+        })
+    }
+}
+
+/// [`RestrictionCondition`] with its addresses and regexes parsed up front
+#[derive(Debug, Clone)]
+pub enum CompiledRestrictionCondition {
+    Asn(u32),
+    Ip(IpAddr),
+    IpCidr(ipnet::IpNet),
+    UserAgent(String),
+    UserAgentRegex(Regex),
+    All(Vec<CompiledRestrictionCondition>),
+    Any(Vec<CompiledRestrictionCondition>),
+    Not(Box<CompiledRestrictionCondition>),
+}
+
+impl CompiledRestrictionCondition {
+    pub fn matches(&self, client: &RestrictionClient) -> bool {
+        match self {
+            CompiledRestrictionCondition::Asn(asn) => *asn == client.asn,
+            CompiledRestrictionCondition::Ip(ip) => client.ip == Some(*ip),
+            CompiledRestrictionCondition::IpCidr(cidr) => {
+                client.ip.is_some_and(|ip| cidr.contains(&ip))
+            }
+            CompiledRestrictionCondition::UserAgent(ua) => client.user_agent.contains(ua.as_str()),
+            CompiledRestrictionCondition::UserAgentRegex(regex) => {
+                regex.is_match(&client.user_agent)
+            }
+            CompiledRestrictionCondition::All(conditions) => {
+                conditions.iter().all(|c| c.matches(client))
+            }
+            CompiledRestrictionCondition::Any(conditions) => {
+                conditions.iter().any(|c| c.matches(client))
+            }
+            CompiledRestrictionCondition::Not(condition) => !condition.matches(client),
+        }
+    }
+}
+
+/// Client of a restricted request
+#[derive(Debug, Clone)]
+pub struct RestrictionClient {
+    /// `None` if the origin IP could not be parsed, it never matches IP conditions then
+    pub ip: Option<IpAddr>,
+    pub asn: u32,
+    pub user_agent: String,
+}
+
+impl RestrictionClient {
+    pub fn new(ip: &str, asn: u32, user_agent: &str) -> Self {
+        Self {
+            ip: ip.parse().ok(),
+            asn,
+            user_agent: user_agent.to_string(),
+        }
+    }
+}
+
+/// Endpoint a rule is applied to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RestrictionTarget {
+    /// Thread creation on bbs.cgi
+    Thread,
+    /// Responses on bbs.cgi
+    Response,
+    /// `/auth-code`, which is not tied to a board
+    AuthCode,
+}
+
+impl RestrictionTarget {
+    /// Targets of rules created before per-endpoint targeting existed
+    pub const ALL: [RestrictionTarget; 3] = [
+        RestrictionTarget::Thread,
+        RestrictionTarget::Response,
+        RestrictionTarget::AuthCode,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestrictionTarget::Thread => "THREAD",
+            RestrictionTarget::Response => "RESPONSE",
+            RestrictionTarget::AuthCode => "AUTH_CODE",
+        }
+    }
+
+    /// Parses the comma-separated form stored in `user_restriction_rules.targets`
+    pub fn parse_list(s: &str) -> Result<Vec<RestrictionTarget>, String> {
+        let mut targets = Vec::new();
+        for target in s.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+            let target = target.parse::<RestrictionTarget>()?;
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        Ok(targets)
+    }
+
+    pub fn join_list(targets: &[RestrictionTarget]) -> String {
+        targets
+            .iter()
+            .map(RestrictionTarget::as_str)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+impl FromStr for RestrictionTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "THREAD" => Ok(RestrictionTarget::Thread),
+            "RESPONSE" => Ok(RestrictionTarget::Response),
+            "AUTH_CODE" => Ok(RestrictionTarget::AuthCode),
+            _ => Err(format!("Invalid restriction target: {s}")),
+        }
+    }
+}
+
+impl Display for RestrictionTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// What happens to a request matching a rule
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum RestrictionAction {
+    /// Refuse the request with a 403, as rules always did
+    #[default]
+    Block,
+    /// Make the authed token pass the re-auth page once before posting again
+    ReAuth,
+    /// Require `captcha_provider` on the auth-code page in addition to the usual ones
+    Captcha,
+    /// Allow one request per `slow_down_seconds` from the same IP
+    SlowDown,
+}
+
+impl RestrictionAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestrictionAction::Block => "BLOCK",
+            RestrictionAction::ReAuth => "REAUTH",
+            RestrictionAction::Captcha => "CAPTCHA",
+            RestrictionAction::SlowDown => "SLOW_DOWN",
+        }
+    }
+
+    /// Lower is stricter, a client matching several rules gets the strictest action so that
+    /// a newer lenient rule never weakens an older `BLOCK`
+    pub fn precedence(&self) -> u8 {
+        match self {
+            RestrictionAction::Block => 0,
+            RestrictionAction::ReAuth => 1,
+            RestrictionAction::Captcha => 2,
+            RestrictionAction::SlowDown => 3,
+        }
+    }
+}
+
+impl FromStr for RestrictionAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "BLOCK" => Ok(RestrictionAction::Block),
+            "REAUTH" => Ok(RestrictionAction::ReAuth),
+            "CAPTCHA" => Ok(RestrictionAction::Captcha),
+            "SLOW_DOWN" => Ok(RestrictionAction::SlowDown),
+            _ => Err(format!("Invalid restriction action: {s}")),
+        }
+    }
+}
+
+impl Display for RestrictionAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserRestrictionRule {
     pub id: Uuid,
     pub name: String,
     pub rule_type: RestrictionRuleType,
     pub rule_value: String,
+    /// `None` applies the rule to every board
+    pub board_keys: Option<Vec<String>>,
+    pub targets: Vec<RestrictionTarget>,
+    pub action: RestrictionAction,
+    /// Name of the captcha config forced by the `Captcha` action
+    pub captcha_provider: Option<String>,
+    /// Interval enforced by the `SlowDown` action
+    pub slow_down_seconds: u32,
     pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Blocklist subscription the rule was imported by, which replaces it on every refresh
+    pub subscription_id: Option<Uuid>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub created_by_email: String,
@@ -64,35 +341,123 @@ impl UserRestrictionRule {
         }
     }
 
-    pub fn matches(&self, ip: &str, asn: u32, user_agent: &str) -> bool {
-        if self.is_expired() {
-            return false;
-        }
+    /// The rule value as a condition, single-condition rule types being leaves
+    pub fn condition(&self) -> Result<RestrictionCondition, String> {
+        let value = self.rule_value.clone();
+        Ok(match self.rule_type {
+            RestrictionRuleType::Asn => RestrictionCondition::Asn {
+                value: value
+                    .trim()
+                    .parse()
+                    .map_err(|e| format!("invalid ASN '{value}': {e}"))?,
+            },
+            RestrictionRuleType::IP => RestrictionCondition::Ip { value },
+            RestrictionRuleType::IPCidr => RestrictionCondition::IpCidr { value },
+            RestrictionRuleType::UserAgent => RestrictionCondition::UserAgent { value },
+            RestrictionRuleType::Composite => serde_json::from_str(&value)
+                .map_err(|e| format!("invalid composite condition: {e}"))?,
+        })
+    }
 
-        match self.rule_type {
-            RestrictionRuleType::Asn => {
-                if let Ok(rule_asn) = self.rule_value.parse::<u32>() {
-                    rule_asn == asn
-                } else {
-                    false
+    pub fn compile(&self) -> Result<CompiledRestrictionRule, String> {
+        Ok(CompiledRestrictionRule {
+            condition: self.condition()?.compile()?,
+            rule: self.clone(),
+        })
+    }
+
+    /// Rejects rules the middleware could not apply as configured
+    pub fn validate(&self) -> Result<(), String> {
+        self.compile()?;
+        if self.targets.is_empty() {
+            return Err("at least one target must be specified".to_string());
+        }
+        if let Some(board_keys) = &self.board_keys {
+            if board_keys.is_empty() {
+                return Err("board_keys must not be empty, use null for all boards".to_string());
+            }
+            for board_key in board_keys {
+                validate_board_key(board_key)
+                    .map_err(|_| format!("invalid board key: {board_key}"))?;
+            }
+            if self.targets == [RestrictionTarget::AuthCode] {
+                return Err("board-scoped rules never match the auth-code target".to_string());
+            }
+        }
+        match self.action {
+            RestrictionAction::Block => {}
+            RestrictionAction::ReAuth => {
+                if self.targets.contains(&RestrictionTarget::AuthCode) {
+                    return Err("the REAUTH action cannot target auth-code".to_string());
                 }
             }
-            RestrictionRuleType::IP => self.rule_value == ip,
-            RestrictionRuleType::IPCidr => {
-                if let Ok(ip_addr) = ip.parse::<IpAddr>() {
-                    if let Ok(cidr) = self.rule_value.parse::<ipnet::IpNet>() {
-                        cidr.contains(&ip_addr)
-                    } else {
-                        false
-                    }
-                } else {
-                    false
+            RestrictionAction::Captcha => {
+                if self.captcha_provider.as_deref().is_none_or(str::is_empty) {
+                    return Err("captcha_provider is required for the CAPTCHA action".to_string());
+                }
+                if self.targets != [RestrictionTarget::AuthCode] {
+                    return Err("the CAPTCHA action can only target auth-code".to_string());
                 }
             }
-            RestrictionRuleType::UserAgent => {
-                self.rule_value == user_agent || user_agent.contains(&self.rule_value)
+            RestrictionAction::SlowDown => {
+                if self.slow_down_seconds == 0 {
+                    return Err(
+                        "slow_down_seconds must be positive for the SLOW_DOWN action".to_string(),
+                    );
+                }
             }
         }
+        Ok(())
+    }
+
+    /// Whether the rule covers the endpoint, `board_key` being `None` for auth-code
+    pub fn applies_to(&self, board_key: Option<&str>, target: RestrictionTarget) -> bool {
+        if !self.targets.contains(&target) {
+            return false;
+        }
+        match (&self.board_keys, board_key) {
+            (None, _) => true,
+            (Some(board_keys), Some(board_key)) => board_keys.iter().any(|k| k == board_key),
+            (Some(_), None) => false,
+        }
+    }
+
+    pub fn matches(&self, ip: &str, asn: u32, user_agent: &str) -> bool {
+        if self.is_expired() {
+            return false;
+        }
+
+        self.compile().is_ok_and(|rule| {
+            rule.condition
+                .matches(&RestrictionClient::new(ip, asn, user_agent))
+        })
+    }
+}
+
+/// A rule with its condition compiled, as kept by the restriction cache
+#[derive(Debug, Clone)]
+pub struct CompiledRestrictionRule {
+    pub rule: UserRestrictionRule,
+    condition: CompiledRestrictionCondition,
+}
+
+impl CompiledRestrictionRule {
+    pub fn condition(&self) -> &CompiledRestrictionCondition {
+        &self.condition
+    }
+
+    /// Whether the rule is in effect on the endpoint, leaving aside its condition
+    pub fn is_applicable(&self, board_key: Option<&str>, target: RestrictionTarget) -> bool {
+        !self.rule.is_expired() && self.rule.applies_to(board_key, target)
+    }
+
+    pub fn matches(
+        &self,
+        client: &RestrictionClient,
+        board_key: Option<&str>,
+        target: RestrictionTarget,
+    ) -> bool {
+        self.is_applicable(board_key, target) && self.condition.matches(client)
     }
 }
 
@@ -101,15 +466,205 @@ pub struct CreateUserRestrictionRuleInput {
     pub name: String,
     pub rule_type: RestrictionRuleType,
     pub rule_value: String,
+    pub board_keys: Option<Vec<String>>,
+    pub targets: Vec<RestrictionTarget>,
+    pub action: RestrictionAction,
+    pub captcha_provider: Option<String>,
+    pub slow_down_seconds: u32,
     pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub subscription_id: Option<Uuid>,
     pub created_by_email: String,
 }
 
+impl CreateUserRestrictionRuleInput {
+    pub fn into_rule(self, id: Uuid, now: chrono::DateTime<chrono::Utc>) -> UserRestrictionRule {
+        UserRestrictionRule {
+            id,
+            name: self.name,
+            rule_type: self.rule_type,
+            rule_value: self.rule_value,
+            board_keys: self.board_keys,
+            targets: self.targets,
+            action: self.action,
+            captcha_provider: self.captcha_provider,
+            slow_down_seconds: self.slow_down_seconds,
+            expires_at: self.expires_at,
+            subscription_id: self.subscription_id,
+            created_at: now,
+            updated_at: now,
+            created_by_email: self.created_by_email,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateUserRestrictionRuleInput {
     pub id: Uuid,
     pub name: Option<String>,
     pub rule_type: Option<RestrictionRuleType>,
     pub rule_value: Option<String>,
+    pub board_keys: Option<Option<Vec<String>>>,
+    pub targets: Option<Vec<RestrictionTarget>>,
+    pub action: Option<RestrictionAction>,
+    pub captcha_provider: Option<Option<String>>,
+    pub slow_down_seconds: Option<u32>,
     pub expires_at: Option<Option<chrono::DateTime<chrono::Utc>>>,
 }
+
+impl UpdateUserRestrictionRuleInput {
+    /// The rule with the given fields replaced
+    pub fn apply(&self, current: UserRestrictionRule) -> UserRestrictionRule {
+        UserRestrictionRule {
+            name: self.name.clone().unwrap_or(current.name),
+            rule_type: self.rule_type.clone().unwrap_or(current.rule_type),
+            rule_value: self.rule_value.clone().unwrap_or(current.rule_value),
+            board_keys: self.board_keys.clone().unwrap_or(current.board_keys),
+            targets: self.targets.clone().unwrap_or(current.targets),
+            action: self.action.unwrap_or(current.action),
+            captcha_provider: self
+                .captcha_provider
+                .clone()
+                .unwrap_or(current.captcha_provider),
+            slow_down_seconds: self.slow_down_seconds.unwrap_or(current.slow_down_seconds),
+            expires_at: self.expires_at.unwrap_or(current.expires_at),
+            ..current
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(rule_type: RestrictionRuleType, rule_value: &str) -> UserRestrictionRule {
+        UserRestrictionRule {
+            id: Uuid::nil(),
+            name: "test".to_string(),
+            rule_type,
+            rule_value: rule_value.to_string(),
+            board_keys: None,
+            targets: RestrictionTarget::ALL.to_vec(),
+            action: RestrictionAction::Block,
+            captcha_provider: None,
+            slow_down_seconds: 0,
+            expires_at: None,
+            subscription_id: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            created_by_email: "admin@example.com".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_single_condition_rules() {
+        assert!(rule(RestrictionRuleType::Asn, "1234").matches("192.0.2.1", 1234, "UA"));
+        assert!(!rule(RestrictionRuleType::Asn, "1234").matches("192.0.2.1", 1, "UA"));
+        assert!(rule(RestrictionRuleType::IP, "192.0.2.1").matches("192.0.2.1", 1, "UA"));
+        assert!(rule(RestrictionRuleType::IPCidr, "192.0.2.0/24").matches("192.0.2.200", 1, "UA"));
+        assert!(!rule(RestrictionRuleType::IPCidr, "192.0.2.0/24").matches(
+            "198.51.100.1",
+            1,
+            "UA"
+        ));
+        assert!(rule(RestrictionRuleType::UserAgent, "Bot").matches("192.0.2.1", 1, "FooBot/1.0"));
+    }
+
+    #[test]
+    fn test_composite_condition() {
+        let asn_and_ua = rule(
+            RestrictionRuleType::Composite,
+            r#"{"type":"all","conditions":[{"type":"asn","value":1234},{"type":"user_agent","value":"Y"}]}"#,
+        );
+        assert!(asn_and_ua.matches("192.0.2.1", 1234, "XYZ"));
+        assert!(!asn_and_ua.matches("192.0.2.1", 1234, "XZ"));
+        assert!(!asn_and_ua.matches("192.0.2.1", 1, "XYZ"));
+
+        let cidr_and_not_ua = rule(
+            RestrictionRuleType::Composite,
+            r#"{"type":"all","conditions":[
+                {"type":"ip_cidr","value":"2001:db8::/32"},
+                {"type":"not","condition":{"type":"user_agent_regex","value":"^Monazilla/"}}
+            ]}"#,
+        );
+        assert!(cidr_and_not_ua.matches("2001:db8::1", 1, "Mozilla/5.0"));
+        assert!(!cidr_and_not_ua.matches("2001:db8::1", 1, "Monazilla/1.00"));
+        assert!(!cidr_and_not_ua.matches("192.0.2.1", 1, "Mozilla/5.0"));
+    }
+
+    #[test]
+    fn test_invalid_conditions() {
+        assert!(rule(RestrictionRuleType::Composite, "{").compile().is_err());
+        assert!(
+            rule(
+                RestrictionRuleType::Composite,
+                r#"{"type":"any","conditions":[]}"#
+            )
+            .compile()
+            .is_err()
+        );
+        assert!(
+            rule(RestrictionRuleType::IPCidr, "192.0.2.0/33")
+                .compile()
+                .is_err()
+        );
+        assert!(
+            rule(
+                RestrictionRuleType::Composite,
+                r#"{"type":"user_agent_regex","value":"("}"#
+            )
+            .compile()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_applies_to() {
+        let mut rule = rule(RestrictionRuleType::Asn, "1234");
+        assert!(rule.applies_to(None, RestrictionTarget::AuthCode));
+
+        rule.board_keys = Some(vec!["news".to_string()]);
+        rule.targets = vec![RestrictionTarget::Thread];
+        assert!(rule.applies_to(Some("news"), RestrictionTarget::Thread));
+        assert!(!rule.applies_to(Some("news"), RestrictionTarget::Response));
+        assert!(!rule.applies_to(Some("other"), RestrictionTarget::Thread));
+        assert!(!rule.applies_to(None, RestrictionTarget::AuthCode));
+    }
+
+    #[test]
+    fn test_validate_actions() {
+        let mut rule = rule(RestrictionRuleType::Asn, "1234");
+        assert!(rule.validate().is_ok());
+
+        rule.action = RestrictionAction::ReAuth;
+        assert!(rule.validate().is_err());
+        rule.targets = vec![RestrictionTarget::Thread, RestrictionTarget::Response];
+        assert!(rule.validate().is_ok());
+
+        rule.action = RestrictionAction::Captcha;
+        rule.targets = vec![RestrictionTarget::AuthCode];
+        assert!(rule.validate().is_err());
+        rule.captcha_provider = Some("turnstile".to_string());
+        assert!(rule.validate().is_ok());
+
+        rule.action = RestrictionAction::SlowDown;
+        assert!(rule.validate().is_err());
+        rule.slow_down_seconds = 30;
+        assert!(rule.validate().is_ok());
+
+        rule.board_keys = Some(vec![]);
+        assert!(rule.validate().is_err());
+    }
+
+    #[test]
+    fn test_target_list() {
+        assert_eq!(
+            RestrictionTarget::parse_list("THREAD, AUTH_CODE,THREAD").unwrap(),
+            vec![RestrictionTarget::Thread, RestrictionTarget::AuthCode]
+        );
+        assert_eq!(
+            RestrictionTarget::join_list(&RestrictionTarget::ALL),
+            "THREAD,RESPONSE,AUTH_CODE"
+        );
+        assert!(RestrictionTarget::parse_list("POST").is_err());
+    }
+}
@@ -0,0 +1,826 @@
+use std::{collections::HashMap, fmt::Display, net::IpAddr, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::user_restriction::{
+    CreateUserRestrictionRuleInput, RestrictionAction, RestrictionCondition, RestrictionRuleType,
+    RestrictionTarget, UserRestrictionRule,
+};
+
+/// Format of bulk imported and exported restriction rules
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RestrictionRuleFormat {
+    /// Header row followed by one rule per row, with the columns of [`RestrictionRuleCsvRow`]
+    Csv,
+    /// Array of [`RestrictionRuleRecord`]
+    Json,
+    /// One IP address or CIDR per line, `#` and `;` starting comments as in most blocklists
+    Cidr,
+}
+
+impl RestrictionRuleFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestrictionRuleFormat::Csv => "CSV",
+            RestrictionRuleFormat::Json => "JSON",
+            RestrictionRuleFormat::Cidr => "CIDR",
+        }
+    }
+}
+
+impl FromStr for RestrictionRuleFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "CSV" => Ok(RestrictionRuleFormat::Csv),
+            "JSON" => Ok(RestrictionRuleFormat::Json),
+            "CIDR" => Ok(RestrictionRuleFormat::Cidr),
+            _ => Err(format!("Invalid restriction rule format: {s}")),
+        }
+    }
+}
+
+impl Display for RestrictionRuleFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Rule of the JSON format, the omitted fields are taken from [`RestrictionImportDefaults`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestrictionRuleRecord {
+    pub name: Option<String>,
+    pub rule_type: RestrictionRuleType,
+    pub rule_value: String,
+    pub board_keys: Option<Vec<String>>,
+    pub targets: Option<Vec<RestrictionTarget>>,
+    pub action: Option<RestrictionAction>,
+    pub captcha_provider: Option<String>,
+    pub slow_down_seconds: Option<u32>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Row of the CSV format, using the values stored in the database, e.g. `IP_CIDR` and
+/// `THREAD,RESPONSE`. Empty cells are taken from [`RestrictionImportDefaults`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RestrictionRuleCsvRow {
+    #[serde(default)]
+    pub name: String,
+    pub rule_type: String,
+    pub rule_value: String,
+    /// Comma-separated
+    #[serde(default)]
+    pub board_keys: String,
+    /// Comma-separated
+    #[serde(default)]
+    pub targets: String,
+    #[serde(default)]
+    pub action: String,
+    #[serde(default)]
+    pub captcha_provider: String,
+    #[serde(default)]
+    pub slow_down_seconds: String,
+    /// RFC 3339
+    #[serde(default)]
+    pub expires_at: String,
+}
+
+/// Settings of the imported rules that rows leave out
+#[derive(Debug, Clone)]
+pub struct RestrictionImportDefaults {
+    /// Rules without a name are named `"{name} {rule_value}"`
+    pub name: String,
+    pub board_keys: Option<Vec<String>>,
+    pub targets: Vec<RestrictionTarget>,
+    pub action: RestrictionAction,
+    pub captcha_provider: Option<String>,
+    pub slow_down_seconds: u32,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub subscription_id: Option<Uuid>,
+    pub created_by_email: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RestrictionImportError {
+    /// 1-based line of CIDR lists, record of CSV and index of JSON
+    pub row: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RestrictionImportDuplicate {
+    pub row: usize,
+    pub rule_value: String,
+    /// `None` when the rule repeats an earlier row of the same import
+    pub existing_rule_id: Option<Uuid>,
+}
+
+/// Outcome of parsing an import, the rules being ready to be created
+#[derive(Debug, Clone, Default)]
+pub struct RestrictionImportPlan {
+    pub rules: Vec<CreateUserRestrictionRuleInput>,
+    pub duplicates: Vec<RestrictionImportDuplicate>,
+    pub errors: Vec<RestrictionImportError>,
+}
+
+/// Parses and validates `content`, leaving out the rules already in `existing` or earlier in
+/// the import. Only malformed documents fail as a whole, bad rows are reported in the plan.
+pub fn plan_restriction_import(
+    format: RestrictionRuleFormat,
+    content: &str,
+    defaults: &RestrictionImportDefaults,
+    existing: &[UserRestrictionRule],
+) -> Result<RestrictionImportPlan, String> {
+    let rows = match format {
+        RestrictionRuleFormat::Csv => parse_csv(content, defaults)?,
+        RestrictionRuleFormat::Json => parse_json(content, defaults)?,
+        RestrictionRuleFormat::Cidr => parse_cidr(content, defaults),
+    };
+
+    let mut seen = existing
+        .iter()
+        .map(|rule| (rule_key(rule), Some(rule.id)))
+        .collect::<HashMap<_, _>>();
+    let mut plan = RestrictionImportPlan::default();
+    for (row, input) in rows {
+        let input = match input.and_then(validate_input) {
+            Ok(input) => input,
+            Err(message) => {
+                plan.errors.push(RestrictionImportError { row, message });
+                continue;
+            }
+        };
+        let rule = input.clone().into_rule(Uuid::nil(), Utc::now());
+        match seen.get(&rule_key(&rule)) {
+            Some(existing_rule_id) => plan.duplicates.push(RestrictionImportDuplicate {
+                row,
+                rule_value: input.rule_value,
+                existing_rule_id: *existing_rule_id,
+            }),
+            None => {
+                seen.insert(rule_key(&rule), None);
+                plan.rules.push(input);
+            }
+        }
+    }
+
+    Ok(plan)
+}
+
+/// Serializes the rules so that importing the output recreates them. CIDR lists only hold
+/// the IP and CIDR rules, their other settings being left to the import.
+pub fn export_restriction_rules(
+    format: RestrictionRuleFormat,
+    rules: &[UserRestrictionRule],
+) -> anyhow::Result<String> {
+    Ok(match format {
+        RestrictionRuleFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for rule in rules {
+                writer.serialize(RestrictionRuleCsvRow::from(rule))?;
+            }
+            String::from_utf8(writer.into_inner()?)?
+        }
+        RestrictionRuleFormat::Json => serde_json::to_string_pretty(
+            &rules
+                .iter()
+                .map(RestrictionRuleRecord::from)
+                .collect::<Vec<_>>(),
+        )?,
+        RestrictionRuleFormat::Cidr => rules
+            .iter()
+            .filter(|rule| {
+                matches!(
+                    rule.rule_type,
+                    RestrictionRuleType::IP | RestrictionRuleType::IPCidr
+                )
+            })
+            .map(|rule| format!("{}\n", rule.rule_value))
+            .collect(),
+    })
+}
+
+impl From<&UserRestrictionRule> for RestrictionRuleRecord {
+    fn from(rule: &UserRestrictionRule) -> Self {
+        Self {
+            name: Some(rule.name.clone()),
+            rule_type: rule.rule_type.clone(),
+            rule_value: rule.rule_value.clone(),
+            board_keys: rule.board_keys.clone(),
+            targets: Some(rule.targets.clone()),
+            action: Some(rule.action),
+            captcha_provider: rule.captcha_provider.clone(),
+            slow_down_seconds: Some(rule.slow_down_seconds),
+            expires_at: rule.expires_at,
+        }
+    }
+}
+
+impl From<&UserRestrictionRule> for RestrictionRuleCsvRow {
+    fn from(rule: &UserRestrictionRule) -> Self {
+        Self {
+            name: rule.name.clone(),
+            rule_type: rule.rule_type.as_str().to_string(),
+            rule_value: rule.rule_value.clone(),
+            board_keys: rule.board_keys.as_deref().unwrap_or_default().join(","),
+            targets: RestrictionTarget::join_list(&rule.targets),
+            action: rule.action.as_str().to_string(),
+            captcha_provider: rule.captcha_provider.clone().unwrap_or_default(),
+            slow_down_seconds: rule.slow_down_seconds.to_string(),
+            expires_at: rule
+                .expires_at
+                .map(|expires_at| expires_at.to_rfc3339())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Blocklist whose rules `eddist-cron refresh-blocklists` replaces on every refresh. The
+/// rules share the settings of the subscription and expire together unless refreshed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserRestrictionSubscription {
+    pub id: Uuid,
+    pub name: String,
+    /// `http(s)://` URL of a public host, or a file in `BLOCKLIST_SOURCE_DIR` of the cron host
+    /// as an absolute path or `file://` URL
+    pub source: String,
+    pub format: RestrictionRuleFormat,
+    pub board_keys: Option<Vec<String>>,
+    pub targets: Vec<RestrictionTarget>,
+    pub action: RestrictionAction,
+    pub captcha_provider: Option<String>,
+    pub slow_down_seconds: u32,
+    pub refresh_interval_seconds: u32,
+    /// Rules expire this long after the refresh that imported them
+    pub expires_after_seconds: u32,
+    pub enabled: bool,
+    pub last_refreshed_at: Option<DateTime<Utc>>,
+    /// Why the last refresh failed or skipped rows, `None` after a clean refresh
+    pub last_error: Option<String>,
+    pub rule_count: u32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub created_by_email: String,
+}
+
+/// Shortest refresh interval, blocklists rarely change more often than this
+pub const MIN_SUBSCRIPTION_REFRESH_INTERVAL_SECS: u32 = 300;
+
+impl UserRestrictionSubscription {
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.enabled
+            && self.last_refreshed_at.is_none_or(|last_refreshed_at| {
+                now - last_refreshed_at
+                    >= chrono::Duration::seconds(self.refresh_interval_seconds.into())
+            })
+    }
+
+    /// The local path of file sources, `None` for URLs
+    pub fn local_path(&self) -> Option<&str> {
+        match self.source.strip_prefix("file://") {
+            Some(path) => Some(path),
+            None if self.source.starts_with('/') => Some(&self.source),
+            None => None,
+        }
+    }
+
+    pub fn import_defaults(&self, now: DateTime<Utc>) -> RestrictionImportDefaults {
+        RestrictionImportDefaults {
+            name: self.name.clone(),
+            board_keys: self.board_keys.clone(),
+            targets: self.targets.clone(),
+            action: self.action,
+            captcha_provider: self.captcha_provider.clone(),
+            slow_down_seconds: self.slow_down_seconds,
+            expires_at: Some(now + chrono::Duration::seconds(self.expires_after_seconds.into())),
+            subscription_id: Some(self.id),
+            created_by_email: self.created_by_email.clone(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.name.chars().count() > 255 {
+            return Err("name must be 1 to 255 characters".to_string());
+        }
+        let is_url = self.source.starts_with("http://") || self.source.starts_with("https://");
+        if !is_url && self.local_path().is_none_or(|path| !path.starts_with('/')) {
+            return Err("source must be an http(s) URL or an absolute file path".to_string());
+        }
+        // The cron host checks the directory again, after resolving symlinks
+        if self
+            .local_path()
+            .is_some_and(|path| path.split('/').any(|segment| segment == ".."))
+        {
+            return Err("source must not contain `..`".to_string());
+        }
+        if self.refresh_interval_seconds < MIN_SUBSCRIPTION_REFRESH_INTERVAL_SECS {
+            return Err(format!(
+                "refresh_interval_seconds must be at least {MIN_SUBSCRIPTION_REFRESH_INTERVAL_SECS}"
+            ));
+        }
+        // Otherwise the rules would lapse between two refreshes
+        if self.expires_after_seconds <= self.refresh_interval_seconds {
+            return Err(
+                "expires_after_seconds must be longer than refresh_interval_seconds".to_string(),
+            );
+        }
+        // Every imported rule shares these settings, so one of them stands for all
+        input_from_defaults(
+            &self.import_defaults(Utc::now()),
+            RestrictionRuleType::IPCidr,
+            "192.0.2.0/24",
+        )
+        .into_rule(Uuid::nil(), Utc::now())
+        .validate()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateUserRestrictionSubscriptionInput {
+    pub name: String,
+    pub source: String,
+    pub format: RestrictionRuleFormat,
+    pub board_keys: Option<Vec<String>>,
+    pub targets: Vec<RestrictionTarget>,
+    pub action: RestrictionAction,
+    pub captcha_provider: Option<String>,
+    pub slow_down_seconds: u32,
+    pub refresh_interval_seconds: u32,
+    pub expires_after_seconds: u32,
+    pub enabled: bool,
+    pub created_by_email: String,
+}
+
+impl CreateUserRestrictionSubscriptionInput {
+    pub fn into_subscription(self, id: Uuid, now: DateTime<Utc>) -> UserRestrictionSubscription {
+        UserRestrictionSubscription {
+            id,
+            name: self.name,
+            source: self.source,
+            format: self.format,
+            board_keys: self.board_keys,
+            targets: self.targets,
+            action: self.action,
+            captcha_provider: self.captcha_provider,
+            slow_down_seconds: self.slow_down_seconds,
+            refresh_interval_seconds: self.refresh_interval_seconds,
+            expires_after_seconds: self.expires_after_seconds,
+            enabled: self.enabled,
+            last_refreshed_at: None,
+            last_error: None,
+            rule_count: 0,
+            created_at: now,
+            updated_at: now,
+            created_by_email: self.created_by_email,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateUserRestrictionSubscriptionInput {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub source: Option<String>,
+    pub format: Option<RestrictionRuleFormat>,
+    pub board_keys: Option<Option<Vec<String>>>,
+    pub targets: Option<Vec<RestrictionTarget>>,
+    pub action: Option<RestrictionAction>,
+    pub captcha_provider: Option<Option<String>>,
+    pub slow_down_seconds: Option<u32>,
+    pub refresh_interval_seconds: Option<u32>,
+    pub expires_after_seconds: Option<u32>,
+    pub enabled: Option<bool>,
+}
+
+impl UpdateUserRestrictionSubscriptionInput {
+    /// The subscription with the given fields replaced, due for a refresh so that the rules
+    /// pick up the new settings
+    pub fn apply(&self, current: UserRestrictionSubscription) -> UserRestrictionSubscription {
+        UserRestrictionSubscription {
+            name: self.name.clone().unwrap_or(current.name),
+            source: self.source.clone().unwrap_or(current.source),
+            format: self.format.unwrap_or(current.format),
+            board_keys: self.board_keys.clone().unwrap_or(current.board_keys),
+            targets: self.targets.clone().unwrap_or(current.targets),
+            action: self.action.unwrap_or(current.action),
+            captcha_provider: self
+                .captcha_provider
+                .clone()
+                .unwrap_or(current.captcha_provider),
+            slow_down_seconds: self.slow_down_seconds.unwrap_or(current.slow_down_seconds),
+            refresh_interval_seconds: self
+                .refresh_interval_seconds
+                .unwrap_or(current.refresh_interval_seconds),
+            expires_after_seconds: self
+                .expires_after_seconds
+                .unwrap_or(current.expires_after_seconds),
+            enabled: self.enabled.unwrap_or(current.enabled),
+            last_refreshed_at: None,
+            ..current
+        }
+    }
+}
+
+type ParsedRow = (usize, Result<CreateUserRestrictionRuleInput, String>);
+
+fn parse_cidr(content: &str, defaults: &RestrictionImportDefaults) -> Vec<ParsedRow> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let value = line.split(['#', ';']).next().unwrap_or_default().trim();
+            // Some lists put more columns after the address
+            let value = value.split_whitespace().next()?;
+            let rule_type = if value.contains('/') {
+                RestrictionRuleType::IPCidr
+            } else {
+                RestrictionRuleType::IP
+            };
+            Some((idx + 1, Ok(input_from_defaults(defaults, rule_type, value))))
+        })
+        .collect()
+}
+
+fn parse_json(
+    content: &str,
+    defaults: &RestrictionImportDefaults,
+) -> Result<Vec<ParsedRow>, String> {
+    let records = serde_json::from_str::<Vec<serde_json::Value>>(content)
+        .map_err(|e| format!("JSON imports must be an array of rules: {e}"))?;
+
+    Ok(records
+        .into_iter()
+        .enumerate()
+        .map(|(idx, record)| {
+            let input = serde_json::from_value::<RestrictionRuleRecord>(record)
+                .map_err(|e| e.to_string())
+                .map(|record| CreateUserRestrictionRuleInput {
+                    name: record
+                        .name
+                        .unwrap_or_else(|| default_name(defaults, &record.rule_value)),
+                    board_keys: record.board_keys.or_else(|| defaults.board_keys.clone()),
+                    targets: record.targets.unwrap_or_else(|| defaults.targets.clone()),
+                    action: record.action.unwrap_or(defaults.action),
+                    captcha_provider: record
+                        .captcha_provider
+                        .or_else(|| defaults.captcha_provider.clone()),
+                    slow_down_seconds: record
+                        .slow_down_seconds
+                        .unwrap_or(defaults.slow_down_seconds),
+                    expires_at: record.expires_at.or(defaults.expires_at),
+                    ..input_from_defaults(defaults, record.rule_type, &record.rule_value)
+                });
+            (idx + 1, input)
+        })
+        .collect())
+}
+
+fn parse_csv(
+    content: &str,
+    defaults: &RestrictionImportDefaults,
+) -> Result<Vec<ParsedRow>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("failed to read the CSV header: {e}"))?;
+    for column in ["rule_type", "rule_value"] {
+        if !headers.iter().any(|header| header == column) {
+            return Err(format!("the CSV header must have a `{column}` column"));
+        }
+    }
+
+    Ok(reader
+        .deserialize::<RestrictionRuleCsvRow>()
+        .enumerate()
+        .map(|(idx, row)| {
+            let input = row
+                .map_err(|e| e.to_string())
+                .and_then(|row| csv_row_to_input(row, defaults));
+            (idx + 1, input)
+        })
+        .collect())
+}
+
+fn csv_row_to_input(
+    row: RestrictionRuleCsvRow,
+    defaults: &RestrictionImportDefaults,
+) -> Result<CreateUserRestrictionRuleInput, String> {
+    let non_empty = |value: String| Some(value).filter(|value| !value.is_empty());
+    let rule_type = row.rule_type.parse::<RestrictionRuleType>()?;
+    let base = input_from_defaults(defaults, rule_type, &row.rule_value);
+
+    Ok(CreateUserRestrictionRuleInput {
+        name: non_empty(row.name).unwrap_or(base.name),
+        board_keys: non_empty(row.board_keys)
+            .map(|keys| keys.split(',').map(|key| key.trim().to_string()).collect())
+            .or(base.board_keys),
+        targets: match non_empty(row.targets) {
+            Some(targets) => RestrictionTarget::parse_list(&targets)?,
+            None => base.targets,
+        },
+        action: match non_empty(row.action) {
+            Some(action) => action.parse()?,
+            None => base.action,
+        },
+        captcha_provider: non_empty(row.captcha_provider).or(base.captcha_provider),
+        slow_down_seconds: match non_empty(row.slow_down_seconds) {
+            Some(seconds) => seconds
+                .parse()
+                .map_err(|e| format!("invalid slow_down_seconds '{seconds}': {e}"))?,
+            None => base.slow_down_seconds,
+        },
+        expires_at: match non_empty(row.expires_at) {
+            Some(expires_at) => Some(
+                DateTime::parse_from_rfc3339(&expires_at)
+                    .map_err(|e| format!("invalid expires_at '{expires_at}': {e}"))?
+                    .with_timezone(&Utc),
+            ),
+            None => base.expires_at,
+        },
+        ..base
+    })
+}
+
+fn default_name(defaults: &RestrictionImportDefaults, rule_value: &str) -> String {
+    format!("{} {rule_value}", defaults.name)
+}
+
+fn input_from_defaults(
+    defaults: &RestrictionImportDefaults,
+    rule_type: RestrictionRuleType,
+    rule_value: &str,
+) -> CreateUserRestrictionRuleInput {
+    CreateUserRestrictionRuleInput {
+        name: default_name(defaults, rule_value),
+        rule_type,
+        rule_value: rule_value.to_string(),
+        board_keys: defaults.board_keys.clone(),
+        targets: defaults.targets.clone(),
+        action: defaults.action,
+        captcha_provider: defaults.captcha_provider.clone(),
+        slow_down_seconds: defaults.slow_down_seconds,
+        expires_at: defaults.expires_at,
+        subscription_id: defaults.subscription_id,
+        created_by_email: defaults.created_by_email.clone(),
+    }
+}
+
+/// Normalizes the value so that the same address written differently dedupes, then checks
+/// the rule as the admin API would
+fn validate_input(
+    input: CreateUserRestrictionRuleInput,
+) -> Result<CreateUserRestrictionRuleInput, String> {
+    let input = CreateUserRestrictionRuleInput {
+        rule_value: normalize_rule_value(&input.rule_type, &input.rule_value)?,
+        ..input
+    };
+    if input.name.chars().count() > 255 {
+        return Err("name must be at most 255 characters".to_string());
+    }
+    input
+        .clone()
+        .into_rule(Uuid::nil(), Utc::now())
+        .validate()?;
+    Ok(input)
+}
+
+fn normalize_rule_value(rule_type: &RestrictionRuleType, value: &str) -> Result<String, String> {
+    let value = value.trim();
+    Ok(match rule_type {
+        RestrictionRuleType::Asn => value
+            .parse::<u32>()
+            .map_err(|e| format!("invalid ASN '{value}': {e}"))?
+            .to_string(),
+        RestrictionRuleType::IP => value
+            .parse::<IpAddr>()
+            .map_err(|e| format!("invalid IP address '{value}': {e}"))?
+            .to_string(),
+        RestrictionRuleType::IPCidr => value
+            .parse::<ipnet::IpNet>()
+            .map_err(|e| format!("invalid CIDR '{value}': {e}"))?
+            .trunc()
+            .to_string(),
+        RestrictionRuleType::UserAgent => value.to_string(),
+        RestrictionRuleType::Composite => serde_json::to_string(
+            &serde_json::from_str::<RestrictionCondition>(value)
+                .map_err(|e| format!("invalid composite condition: {e}"))?,
+        )
+        .map_err(|e| e.to_string())?,
+    })
+}
+
+/// Rules with the same key match the same requests on the same boards
+fn rule_key(rule: &UserRestrictionRule) -> (String, String, Option<Vec<String>>) {
+    let value = normalize_rule_value(&rule.rule_type, &rule.rule_value)
+        .unwrap_or_else(|_| rule.rule_value.clone());
+    let board_keys = rule.board_keys.clone().map(|mut keys| {
+        keys.sort();
+        keys.dedup();
+        keys
+    });
+    (rule.rule_type.as_str().to_string(), value, board_keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> RestrictionImportDefaults {
+        RestrictionImportDefaults {
+            name: "import".to_string(),
+            board_keys: None,
+            targets: RestrictionTarget::ALL.to_vec(),
+            action: RestrictionAction::Block,
+            captcha_provider: None,
+            slow_down_seconds: 0,
+            expires_at: None,
+            subscription_id: None,
+            created_by_email: "admin@example.com".to_string(),
+        }
+    }
+
+    fn existing(rule_type: RestrictionRuleType, rule_value: &str) -> UserRestrictionRule {
+        CreateUserRestrictionRuleInput {
+            rule_type,
+            rule_value: rule_value.to_string(),
+            ..input_from_defaults(&defaults(), RestrictionRuleType::IP, rule_value)
+        }
+        .into_rule(Uuid::now_v7(), Utc::now())
+    }
+
+    #[test]
+    fn test_cidr_import() {
+        let existing = existing(RestrictionRuleType::IPCidr, "203.0.113.0/24");
+        let content = "# DROP list\n192.0.2.5/24 ; SBL1\n\n198.51.100.7\n192.0.2.0/24\n203.0.113.0/24\nnot-an-ip\n";
+        let plan = plan_restriction_import(
+            RestrictionRuleFormat::Cidr,
+            content,
+            &defaults(),
+            std::slice::from_ref(&existing),
+        )
+        .unwrap();
+
+        let values = plan
+            .rules
+            .iter()
+            .map(|rule| (rule.rule_type.clone(), rule.rule_value.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![
+                (RestrictionRuleType::IPCidr, "192.0.2.0/24"),
+                (RestrictionRuleType::IP, "198.51.100.7"),
+            ]
+        );
+        assert_eq!(plan.rules[0].name, "import 192.0.2.5/24");
+        assert_eq!(
+            plan.duplicates,
+            vec![
+                RestrictionImportDuplicate {
+                    row: 5,
+                    rule_value: "192.0.2.0/24".to_string(),
+                    existing_rule_id: None,
+                },
+                RestrictionImportDuplicate {
+                    row: 6,
+                    rule_value: "203.0.113.0/24".to_string(),
+                    existing_rule_id: Some(existing.id),
+                },
+            ]
+        );
+        assert_eq!(plan.errors.len(), 1);
+        assert_eq!(plan.errors[0].row, 7);
+    }
+
+    #[test]
+    fn test_csv_and_json_round_trip() {
+        let mut scoped = existing(RestrictionRuleType::UserAgent, "Bad, \"quoted\" Bot");
+        scoped.board_keys = Some(vec!["news".to_string(), "livejupiter".to_string()]);
+        scoped.targets = vec![RestrictionTarget::Thread, RestrictionTarget::Response];
+        scoped.action = RestrictionAction::SlowDown;
+        scoped.slow_down_seconds = 30;
+        scoped.expires_at = Some(Utc::now());
+        let rules = vec![
+            scoped,
+            existing(RestrictionRuleType::Asn, "64500"),
+            existing(
+                RestrictionRuleType::Composite,
+                r#"{"type":"not","condition":{"type":"asn","value":64501}}"#,
+            ),
+        ];
+
+        for format in [RestrictionRuleFormat::Csv, RestrictionRuleFormat::Json] {
+            let content = export_restriction_rules(format, &rules).unwrap();
+            let plan = plan_restriction_import(format, &content, &defaults(), &[]).unwrap();
+            assert!(plan.errors.is_empty(), "{format}: {:?}", plan.errors);
+            assert_eq!(plan.rules.len(), rules.len());
+            for (input, rule) in plan.rules.iter().zip(&rules) {
+                assert_eq!(input.name, rule.name);
+                assert_eq!(input.rule_type, rule.rule_type);
+                assert_eq!(input.board_keys, rule.board_keys);
+                assert_eq!(input.targets, rule.targets);
+                assert_eq!(input.action, rule.action);
+                assert_eq!(input.slow_down_seconds, rule.slow_down_seconds);
+                assert_eq!(
+                    input.expires_at.map(|x| x.timestamp_millis()),
+                    rule.expires_at.map(|x| x.timestamp_millis())
+                );
+            }
+
+            // Everything is already there the second time
+            let plan = plan_restriction_import(format, &content, &defaults(), &rules).unwrap();
+            assert!(plan.rules.is_empty());
+            assert_eq!(plan.duplicates.len(), rules.len());
+        }
+    }
+
+    #[test]
+    fn test_row_errors() {
+        let csv =
+            "rule_type,rule_value,action\nASN,abc,\nIP,192.0.2.1,CAPTCHA\nUNKNOWN,1,\nASN,64500,\n";
+        let plan =
+            plan_restriction_import(RestrictionRuleFormat::Csv, csv, &defaults(), &[]).unwrap();
+        assert_eq!(
+            plan.errors.iter().map(|e| e.row).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(plan.rules.len(), 1);
+
+        let json = r#"[{"rule_type":"Asn","rule_value":"1"},{"rule_value":"2"}]"#;
+        let plan =
+            plan_restriction_import(RestrictionRuleFormat::Json, json, &defaults(), &[]).unwrap();
+        assert_eq!(plan.rules.len(), 1);
+        assert_eq!(plan.errors[0].row, 2);
+
+        assert!(
+            plan_restriction_import(RestrictionRuleFormat::Json, "{}", &defaults(), &[]).is_err()
+        );
+        assert!(
+            plan_restriction_import(RestrictionRuleFormat::Csv, "name\nx\n", &defaults(), &[])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_subscription_validation() {
+        let now = Utc::now();
+        let subscription = CreateUserRestrictionSubscriptionInput {
+            name: "drop".to_string(),
+            source: "https://example.com/drop.txt".to_string(),
+            format: RestrictionRuleFormat::Cidr,
+            board_keys: None,
+            targets: RestrictionTarget::ALL.to_vec(),
+            action: RestrictionAction::Block,
+            captcha_provider: None,
+            slow_down_seconds: 0,
+            refresh_interval_seconds: 3600,
+            expires_after_seconds: 3 * 3600,
+            enabled: true,
+            created_by_email: "admin@example.com".to_string(),
+        }
+        .into_subscription(Uuid::now_v7(), now);
+        assert!(subscription.validate().is_ok());
+        assert!(subscription.is_due(now));
+        assert_eq!(subscription.local_path(), None);
+
+        let refreshed = UserRestrictionSubscription {
+            last_refreshed_at: Some(now - chrono::Duration::minutes(30)),
+            ..subscription.clone()
+        };
+        assert!(!refreshed.is_due(now));
+        assert!(refreshed.is_due(now + chrono::Duration::minutes(30)));
+
+        let file = UserRestrictionSubscription {
+            source: "file:///var/lib/eddist/drop.txt".to_string(),
+            ..subscription.clone()
+        };
+        assert!(file.validate().is_ok());
+        assert_eq!(file.local_path(), Some("/var/lib/eddist/drop.txt"));
+
+        for invalid in [
+            UserRestrictionSubscription {
+                source: "drop.txt".to_string(),
+                ..subscription.clone()
+            },
+            UserRestrictionSubscription {
+                source: "/var/lib/eddist/../../proc/self/environ".to_string(),
+                ..subscription.clone()
+            },
+            UserRestrictionSubscription {
+                expires_after_seconds: 3600,
+                ..subscription.clone()
+            },
+            UserRestrictionSubscription {
+                action: RestrictionAction::SlowDown,
+                ..subscription.clone()
+            },
+        ] {
+            assert!(invalid.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,447 @@
+use std::{collections::HashMap, net::IpAddr};
+
+use aho_corasick::AhoCorasick;
+
+use super::user_restriction::{
+    CompiledRestrictionCondition, CompiledRestrictionRule, RestrictionClient, RestrictionTarget,
+    UserRestrictionRule,
+};
+
+/// Binary trie over the leading bits of an address, each node holding the rules whose
+/// prefix ends there
+#[derive(Debug)]
+struct PrefixTrie {
+    nodes: Vec<PrefixTrieNode>,
+    max_len: u8,
+}
+
+#[derive(Debug, Default)]
+struct PrefixTrieNode {
+    /// 0 for no child, the root is never a child
+    children: [u32; 2],
+    rules: Vec<usize>,
+}
+
+impl PrefixTrie {
+    fn new(max_len: u8) -> Self {
+        Self {
+            nodes: vec![PrefixTrieNode::default()],
+            max_len,
+        }
+    }
+
+    /// `bits` is left-aligned, so IPv4 addresses take the top 32 bits
+    fn insert(&mut self, bits: u128, prefix_len: u8, rule: usize) {
+        let mut node = 0;
+        for depth in 0..prefix_len.min(self.max_len) {
+            let bit = ((bits >> (127 - depth)) & 1) as usize;
+            if self.nodes[node].children[bit] == 0 {
+                self.nodes[node].children[bit] = self.nodes.len() as u32;
+                self.nodes.push(PrefixTrieNode::default());
+            }
+            node = self.nodes[node].children[bit] as usize;
+        }
+        self.nodes[node].rules.push(rule);
+    }
+
+    fn collect(&self, bits: u128, out: &mut Vec<usize>) {
+        let mut node = 0;
+        out.extend_from_slice(&self.nodes[node].rules);
+        for depth in 0..self.max_len {
+            let bit = ((bits >> (127 - depth)) & 1) as usize;
+            match self.nodes[node].children[bit] {
+                0 => return,
+                child => node = child as usize,
+            }
+            out.extend_from_slice(&self.nodes[node].rules);
+        }
+    }
+}
+
+fn ipv4_bits(ip: std::net::Ipv4Addr) -> u128 {
+    (u32::from(ip) as u128) << 96
+}
+
+/// Cached restriction rules precompiled for lookup: ASN, IP and UA substring rules are
+/// found through indexes, whatever else is left is scanned in order.
+///
+/// Lookups return the same rule as scanning the rules in the given order would.
+#[derive(Debug)]
+pub struct RestrictionRuleIndex {
+    rules: Vec<CompiledRestrictionRule>,
+    asns: HashMap<u32, Vec<usize>>,
+    ipv4: PrefixTrie,
+    ipv6: PrefixTrie,
+    user_agents: Option<AhoCorasick>,
+    /// Rules of each pattern of `user_agents`
+    user_agent_rules: Vec<Vec<usize>>,
+    /// Rules with conditions the indexes can't answer, in order
+    fallback: Vec<usize>,
+}
+
+impl Default for RestrictionRuleIndex {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl RestrictionRuleIndex {
+    /// The strictest matching rule wins, `rules` ordering the rules of the same action
+    pub fn new(mut rules: Vec<CompiledRestrictionRule>) -> Self {
+        rules.sort_by_key(|rule| rule.rule.action.precedence());
+        let mut asns = HashMap::<u32, Vec<usize>>::new();
+        let mut ipv4 = PrefixTrie::new(32);
+        let mut ipv6 = PrefixTrie::new(128);
+        let mut patterns = HashMap::<&str, usize>::new();
+        let mut user_agent_rules = Vec::<Vec<usize>>::new();
+        let mut fallback = Vec::new();
+
+        for (idx, rule) in rules.iter().enumerate() {
+            let mut leaves = Vec::new();
+            if !indexable_leaves(rule.condition(), &mut leaves) {
+                fallback.push(idx);
+                continue;
+            }
+            for leaf in leaves {
+                match leaf {
+                    CompiledRestrictionCondition::Asn(asn) => {
+                        asns.entry(*asn).or_default().push(idx)
+                    }
+                    CompiledRestrictionCondition::Ip(IpAddr::V4(ip)) => {
+                        ipv4.insert(ipv4_bits(*ip), 32, idx)
+                    }
+                    CompiledRestrictionCondition::Ip(IpAddr::V6(ip)) => {
+                        ipv6.insert(u128::from(*ip), 128, idx)
+                    }
+                    CompiledRestrictionCondition::IpCidr(ipnet::IpNet::V4(net)) => {
+                        ipv4.insert(ipv4_bits(net.network()), net.prefix_len(), idx)
+                    }
+                    CompiledRestrictionCondition::IpCidr(ipnet::IpNet::V6(net)) => {
+                        ipv6.insert(u128::from(net.network()), net.prefix_len(), idx)
+                    }
+                    CompiledRestrictionCondition::UserAgent(pattern) => {
+                        let next = user_agent_rules.len();
+                        let pattern_idx = *patterns.entry(pattern.as_str()).or_insert(next);
+                        if pattern_idx == next {
+                            user_agent_rules.push(Vec::new());
+                        }
+                        user_agent_rules[pattern_idx].push(idx);
+                    }
+                    _ => unreachable!("only indexable leaves are collected"),
+                }
+            }
+        }
+
+        let mut patterns = patterns.into_iter().collect::<Vec<_>>();
+        patterns.sort_by_key(|(_, idx)| *idx);
+        let user_agents = if patterns.is_empty() {
+            None
+        } else {
+            match AhoCorasick::new(patterns.iter().map(|(pattern, _)| pattern)) {
+                Ok(automaton) => Some(automaton),
+                Err(e) => {
+                    // Keeps the rules working, at the cost of scanning them
+                    tracing::warn!("Failed to build the user agent automaton: {e}");
+                    fallback.extend(user_agent_rules.drain(..).flatten());
+                    fallback.sort_unstable();
+                    fallback.dedup();
+                    None
+                }
+            }
+        };
+
+        Self {
+            rules,
+            asns,
+            ipv4,
+            ipv6,
+            user_agents,
+            user_agent_rules,
+            fallback,
+        }
+    }
+
+    pub fn from_rules(rules: Vec<UserRestrictionRule>) -> Self {
+        Self::new(
+            rules
+                .into_iter()
+                .filter_map(|rule| {
+                    rule.compile()
+                        .inspect_err(|e| {
+                            tracing::warn!(
+                                "Skipping invalid user restriction rule {}: {e}",
+                                rule.id
+                            )
+                        })
+                        .ok()
+                })
+                .collect(),
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn rules(&self) -> &[CompiledRestrictionRule] {
+        &self.rules
+    }
+
+    /// The strictest rule matching the client on the endpoint
+    pub fn find(
+        &self,
+        client: &RestrictionClient,
+        board_key: Option<&str>,
+        target: RestrictionTarget,
+    ) -> Option<&CompiledRestrictionRule> {
+        let mut candidates = Vec::new();
+        if let Some(rules) = self.asns.get(&client.asn) {
+            candidates.extend_from_slice(rules);
+        }
+        match client.ip {
+            Some(IpAddr::V4(ip)) => self.ipv4.collect(ipv4_bits(ip), &mut candidates),
+            Some(IpAddr::V6(ip)) => self.ipv6.collect(u128::from(ip), &mut candidates),
+            None => {}
+        }
+        if let Some(automaton) = &self.user_agents {
+            for m in automaton.find_overlapping_iter(&client.user_agent) {
+                candidates.extend_from_slice(&self.user_agent_rules[m.pattern().as_usize()]);
+            }
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let indexed = candidates
+            .into_iter()
+            .find(|&idx| self.rules[idx].is_applicable(board_key, target));
+
+        // Only the fallback rules ranking above the indexed match can still win
+        let fallback = self
+            .fallback
+            .iter()
+            .copied()
+            .take_while(|&idx| indexed.is_none_or(|indexed| idx < indexed))
+            .find(|&idx| self.rules[idx].matches(client, board_key, target));
+
+        fallback.or(indexed).map(|idx| &self.rules[idx])
+    }
+}
+
+/// Collects the leaves of a condition that is matched when any of its leaves is, `false` if
+/// the condition needs evaluating as a whole
+fn indexable_leaves<'a>(
+    condition: &'a CompiledRestrictionCondition,
+    out: &mut Vec<&'a CompiledRestrictionCondition>,
+) -> bool {
+    match condition {
+        CompiledRestrictionCondition::Asn(_)
+        | CompiledRestrictionCondition::Ip(_)
+        | CompiledRestrictionCondition::IpCidr(_)
+        | CompiledRestrictionCondition::UserAgent(_) => {
+            out.push(condition);
+            true
+        }
+        CompiledRestrictionCondition::Any(conditions) => conditions
+            .iter()
+            .all(|condition| indexable_leaves(condition, out)),
+        CompiledRestrictionCondition::All(conditions) if conditions.len() == 1 => {
+            indexable_leaves(&conditions[0], out)
+        }
+        CompiledRestrictionCondition::UserAgentRegex(_)
+        | CompiledRestrictionCondition::All(_)
+        | CompiledRestrictionCondition::Not(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::domain::user_restriction::{
+        RestrictionAction, RestrictionRuleType, UserRestrictionRule,
+    };
+
+    fn rule(name: &str, rule_type: RestrictionRuleType, rule_value: &str) -> UserRestrictionRule {
+        UserRestrictionRule {
+            id: Uuid::now_v7(),
+            name: name.to_string(),
+            rule_type,
+            rule_value: rule_value.to_string(),
+            board_keys: None,
+            targets: RestrictionTarget::ALL.to_vec(),
+            action: RestrictionAction::Block,
+            captcha_provider: None,
+            slow_down_seconds: 0,
+            expires_at: None,
+            subscription_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            created_by_email: "admin@example.com".to_string(),
+        }
+    }
+
+    fn linear_scan<'a>(
+        rules: &'a [CompiledRestrictionRule],
+        client: &RestrictionClient,
+        board_key: Option<&str>,
+        target: RestrictionTarget,
+    ) -> Option<&'a str> {
+        rules
+            .iter()
+            .find(|rule| rule.matches(client, board_key, target))
+            .map(|rule| rule.rule.name.as_str())
+    }
+
+    #[test]
+    fn test_index_agrees_with_linear_scan() {
+        let mut expired = rule("expired", RestrictionRuleType::Asn, "64500");
+        expired.expires_at = Some(Utc::now() - Duration::hours(1));
+        let mut board_scoped = rule("board", RestrictionRuleType::IPCidr, "198.51.100.0/24");
+        board_scoped.board_keys = Some(vec!["news".to_string()]);
+        let mut thread_only = rule("thread", RestrictionRuleType::UserAgent, "Crawler");
+        thread_only.targets = vec![RestrictionTarget::Thread];
+
+        let rules = vec![
+            expired,
+            rule(
+                "asn_and_ua",
+                RestrictionRuleType::Composite,
+                r#"{"type":"all","conditions":[{"type":"asn","value":64501},{"type":"user_agent","value":"Mobile"}]}"#,
+            ),
+            rule(
+                "any_ip",
+                RestrictionRuleType::Composite,
+                r#"{"type":"any","conditions":[{"type":"ip","value":"203.0.113.7"},{"type":"ip_cidr","value":"2001:db8::/32"}]}"#,
+            ),
+            board_scoped,
+            thread_only,
+            rule("narrow", RestrictionRuleType::IPCidr, "198.51.100.128/25"),
+            rule("asn", RestrictionRuleType::Asn, "64500"),
+            rule("bot", RestrictionRuleType::UserAgent, "Bot"),
+            rule(
+                "regex",
+                RestrictionRuleType::Composite,
+                r#"{"type":"user_agent_regex","value":"^curl/"}"#,
+            ),
+            rule("wide", RestrictionRuleType::IPCidr, "198.51.0.0/16"),
+        ]
+        .into_iter()
+        .map(|rule| rule.compile().unwrap())
+        .collect::<Vec<_>>();
+        let index = RestrictionRuleIndex::new(rules.clone());
+        assert_eq!(index.len(), 10);
+
+        let ips = [
+            "198.51.100.1",
+            "198.51.100.200",
+            "198.51.7.7",
+            "203.0.113.7",
+            "2001:db8::1",
+            "192.0.2.1",
+            "invalid",
+        ];
+        let asns = [64500, 64501, 1];
+        let uas = [
+            "Mozilla/5.0 Mobile",
+            "FooBot Crawler",
+            "curl/8.0",
+            "Mozilla",
+        ];
+        let scopes = [
+            (Some("news"), RestrictionTarget::Thread),
+            (Some("news"), RestrictionTarget::Response),
+            (Some("other"), RestrictionTarget::Response),
+            (None, RestrictionTarget::AuthCode),
+        ];
+
+        for ip in ips {
+            for asn in asns {
+                for ua in uas {
+                    let client = RestrictionClient::new(ip, asn, ua);
+                    for (board_key, target) in scopes {
+                        assert_eq!(
+                            index
+                                .find(&client, board_key, target)
+                                .map(|rule| rule.rule.name.as_str()),
+                            linear_scan(&rules, &client, board_key, target),
+                            "ip={ip} asn={asn} ua={ua} board={board_key:?} target={target}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_index_prefers_earlier_rules() {
+        let index = RestrictionRuleIndex::from_rules(vec![
+            rule("ua", RestrictionRuleType::UserAgent, "Bot"),
+            rule("cidr", RestrictionRuleType::IPCidr, "192.0.2.0/24"),
+            rule("asn", RestrictionRuleType::Asn, "64500"),
+        ]);
+        let find = |ip, asn, ua| {
+            index
+                .find(
+                    &RestrictionClient::new(ip, asn, ua),
+                    Some("news"),
+                    RestrictionTarget::Response,
+                )
+                .map(|rule| rule.rule.name.clone())
+        };
+
+        assert_eq!(find("192.0.2.1", 64500, "Bot").as_deref(), Some("ua"));
+        assert_eq!(find("192.0.2.1", 64500, "Mozilla").as_deref(), Some("cidr"));
+        assert_eq!(
+            find("198.51.100.1", 64500, "Mozilla").as_deref(),
+            Some("asn")
+        );
+        assert_eq!(find("198.51.100.1", 1, "Mozilla"), None);
+    }
+
+    #[test]
+    fn test_index_prefers_stricter_actions() {
+        let mut slow_down = rule("slow_down", RestrictionRuleType::IPCidr, "192.0.2.0/24");
+        slow_down.action = RestrictionAction::SlowDown;
+        slow_down.slow_down_seconds = 10;
+        let mut captcha = rule("captcha", RestrictionRuleType::UserAgent, "Bot");
+        captcha.action = RestrictionAction::Captcha;
+        captcha.captcha_provider = Some("turnstile".to_string());
+        let mut regex_reauth = rule(
+            "reauth",
+            RestrictionRuleType::Composite,
+            r#"{"type":"user_agent_regex","value":"^Bot"}"#,
+        );
+        regex_reauth.action = RestrictionAction::ReAuth;
+
+        // Newest first, as the rules are loaded
+        let index = RestrictionRuleIndex::from_rules(vec![
+            slow_down,
+            captcha,
+            regex_reauth,
+            rule("block", RestrictionRuleType::Asn, "64500"),
+        ]);
+        let find = |ip, asn, ua| {
+            index
+                .find(
+                    &RestrictionClient::new(ip, asn, ua),
+                    Some("news"),
+                    RestrictionTarget::Response,
+                )
+                .map(|rule| rule.rule.name.clone())
+        };
+
+        assert_eq!(find("192.0.2.1", 64500, "Bot").as_deref(), Some("block"));
+        assert_eq!(find("192.0.2.1", 1, "Bot").as_deref(), Some("reauth"));
+        assert_eq!(find("192.0.2.1", 1, "A Bot").as_deref(), Some("captcha"));
+        assert_eq!(
+            find("192.0.2.1", 1, "Mozilla").as_deref(),
+            Some("slow_down")
+        );
+    }
+}
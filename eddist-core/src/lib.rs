@@ -5,20 +5,32 @@ pub mod domain {
     pub mod client_info;
     pub mod ip_addr;
     pub mod metadent;
+    pub mod moderation_provider;
+    pub mod moderation_queue;
+    pub mod moderation_rule;
+    pub mod ng_word;
     pub mod notice;
     pub mod pubsub_repository;
     pub mod res;
+    pub mod search;
     pub mod sjis_str;
     pub mod terms;
     pub mod tinker;
     pub mod user_restriction;
+    pub mod user_restriction_bulk;
+    pub mod user_restriction_index;
 }
 
+pub mod archive_storage;
+pub mod archived_dat;
 pub mod cache_aside;
+pub mod client_addr;
 pub mod proto;
 pub mod redis_keys;
 pub mod server_settings;
 pub mod simple_rate_limiter;
 pub mod symmetric;
+pub mod thread_momentum;
 pub mod tracing;
+pub mod user_restriction_hits;
 pub mod utils;
@@ -163,6 +163,7 @@ impl From<&CreatingThread> for events::CreatingThread {
                 .moderation_result
                 .as_ref()
                 .map(events::ModerationResult::from),
+            is_abone: e.is_abone,
         }
     }
 }
@@ -187,6 +188,7 @@ impl TryFrom<events::CreatingThread> for CreatingThread {
             metadent: i32_to_metadent(p.metadent),
             client_info: p.client_info.map(ClientInfo::from).unwrap_or_default(),
             moderation_result: p.moderation_result.map(ModerationResult::from),
+            is_abone: p.is_abone,
         })
     }
 }
@@ -213,6 +215,7 @@ impl From<&CreatingRes> for events::CreatingRes {
                 .moderation_result
                 .as_ref()
                 .map(events::ModerationResult::from),
+            is_abone: e.is_abone,
         }
     }
 }
@@ -236,6 +239,7 @@ impl TryFrom<events::CreatingRes> for CreatingRes {
             res_order: p.res_order,
             is_sage: p.is_sage,
             moderation_result: p.moderation_result.map(ModerationResult::from),
+            is_abone: p.is_abone,
         })
     }
 }
@@ -6,6 +6,11 @@ pub fn thread_cache_key(board_key: &str, thread_number: u64) -> String {
     format!("thread:{board_key}:{thread_number}")
 }
 
+/// Original dat lines of shadow-aboned responses, per thread and poster (digest of the edge-token)
+pub fn shadow_abone_key(board_key: &str, thread_number: u64, authed_token_digest: &str) -> String {
+    format!("shadow_abone:{board_key}:{thread_number}:{authed_token_digest}")
+}
+
 pub fn res_creation_span_key(authed_token: &str) -> String {
     format!("res_creation_span:{authed_token}")
 }
@@ -58,6 +63,11 @@ pub fn authed_token_suspended_key(authed_token_id: &str) -> String {
     format!("authed_token:suspended:{authed_token_id}")
 }
 
+/// Internal level cap set by an automated moderation action
+pub fn authed_token_internal_level_cap_key(authed_token_id: &str) -> String {
+    format!("authed_token:internal_level_cap:{authed_token_id}")
+}
+
 pub fn tripwire_uuid_seen_key(uuid: &str) -> String {
     format!("captcha:tripwire:uuid:{uuid}")
 }
@@ -70,18 +80,58 @@ pub fn reauth_lock_key(token_id: &str) -> String {
     format!("reauth:lock:{token_id}")
 }
 
+/// Set once a token has been sent to re-auth by a `REAUTH` restriction rule
+pub fn user_restriction_reauth_key(rule_id: &str, token_id: &str) -> String {
+    format!("user_restriction:reauth:{rule_id}:{token_id}")
+}
+
+/// Held for the interval of a `SLOW_DOWN` restriction rule after a request from the IP
+pub fn user_restriction_slow_down_key(rule_id: &str, ip: &str) -> String {
+    format!("user_restriction:slow_down:{rule_id}:{ip}")
+}
+
+/// Hits of each restriction rule by rule id, taken by `eddist-cron flush-restriction-hits`
+pub const USER_RESTRICTION_HITS_KEY: &str = "user_restriction:hits";
+/// Last hit of each restriction rule in Unix milliseconds, taken along with the hits
+pub const USER_RESTRICTION_LAST_HITS_KEY: &str = "user_restriction:last_hits";
+
+/// Latest clients matched by a restriction rule, kept after the flush
+pub fn user_restriction_hit_samples_key(rule_id: &str) -> String {
+    format!("user_restriction:hit_samples:{rule_id}")
+}
+
 pub fn unsafe_threads_key(board_id: impl std::fmt::Display) -> String {
     format!("bbs:safe_mode:unsafe_threads:{board_id}")
 }
 
+/// Responses to the thread within the window of [`crate::thread_momentum`], keyed by
+/// thread id so that they follow the thread across boards
+pub fn thread_momentum_key(thread_id: impl std::fmt::Display) -> String {
+    format!("bbs:momentum:thread:{thread_id}")
+}
+
+/// Set while a momentum alert of the thread is fresh, so that it is raised once
+pub fn thread_momentum_alerted_key(thread_id: impl std::fmt::Display) -> String {
+    format!("bbs:momentum:alerted:{thread_id}")
+}
+
+pub const MOMENTUM_ALERTS_KEY: &str = "bbs:momentum:alerts";
+
 pub fn not_found_access_count_key(ip: &str) -> String {
     format!("not_found:count:{ip}")
 }
 
+pub fn search_access_count_key(ip: &str) -> String {
+    format!("search:count:{ip}")
+}
+
 pub const DB_FAILED_CACHE_RES_KEY: &str = "bbs:db_failed_cache:res";
 
 pub const CHANNEL_RES_CREATED: &str = "bbs:event:res_created";
 pub const CHANNEL_THREAD_CREATED: &str = "bbs:event:thread_created";
+/// Posts with a moderation result, published regardless of ENABLE_RES_PUB/ENABLE_THREAD_PUB
+pub const CHANNEL_RES_MODERATED: &str = "bbs:event:res_moderated";
+pub const CHANNEL_THREAD_MODERATED: &str = "bbs:event:thread_moderated";
 pub use crate::domain::pubsub_repository::{
     CHANNEL_AUTH_TOKEN_INITIATED, CHANNEL_AUTH_TOKEN_REQUESTED, CHANNEL_AUTH_TOKEN_REVOKED,
     CHANNEL_AUTH_TOKEN_SUCCEEDED, CHANNEL_PUBSUB_ITEM,
@@ -4,6 +4,8 @@ pub const KEY_AI_OPENAI_API_KEY: &str = "ai.openai_api_key";
 pub const KEY_AI_MODERATION_ON_RES: &str = "ai.moderation_on_res";
 pub const KEY_AI_MODERATION_ON_THREAD: &str = "ai.moderation_on_thread";
 pub const KEY_ENABLE_SAFE_MODE: &str = "bbs.enable_safe_mode";
+pub const KEY_MOMENTUM_ALERT_THRESHOLD: &str = "bbs.momentum_alert_threshold";
+pub const KEY_RESTRICTION_RULE_IDLE_EXPIRY_DAYS: &str = "user.restriction_rule_idle_expiry_days";
 
 pub enum ServerSettingKey {
     EnableIdpLinking,
@@ -12,6 +14,8 @@ pub enum ServerSettingKey {
     AiModerationOnRes,
     AiModerationOnThread,
     EnableSafeMode,
+    MomentumAlertThreshold,
+    RestrictionRuleIdleExpiryDays,
 }
 
 impl ServerSettingKey {
@@ -23,6 +27,8 @@ impl ServerSettingKey {
             Self::AiModerationOnRes => KEY_AI_MODERATION_ON_RES,
             Self::AiModerationOnThread => KEY_AI_MODERATION_ON_THREAD,
             Self::EnableSafeMode => KEY_ENABLE_SAFE_MODE,
+            Self::MomentumAlertThreshold => KEY_MOMENTUM_ALERT_THRESHOLD,
+            Self::RestrictionRuleIdleExpiryDays => KEY_RESTRICTION_RULE_IDLE_EXPIRY_DAYS,
         }
     }
 
@@ -33,6 +39,8 @@ impl ServerSettingKey {
         ServerSettingKey::AiModerationOnRes,
         ServerSettingKey::AiModerationOnThread,
         ServerSettingKey::EnableSafeMode,
+        ServerSettingKey::MomentumAlertThreshold,
+        ServerSettingKey::RestrictionRuleIdleExpiryDays,
     ];
 
     pub const fn description(&self) -> &'static str {
@@ -53,6 +61,12 @@ impl ServerSettingKey {
             Self::EnableSafeMode => {
                 "Enable safe mode thread filtering — hides threads with unsafe content from clients that support it (true/false)"
             }
+            Self::MomentumAlertThreshold => {
+                "Responses to a single thread within the last 5 minutes that raise a momentum alert in the admin, at most once an hour per thread, often a sign of a raid (number, unset or 0 to disable)"
+            }
+            Self::RestrictionRuleIdleExpiryDays => {
+                "Days without a hit after which a restriction rule expires, rules of blocklist subscriptions excepted (number, unset or 0 to disable)"
+            }
         }
     }
 }
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionLike;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::redis_keys::{MOMENTUM_ALERTS_KEY, thread_momentum_alerted_key, thread_momentum_key};
+
+/// Window of the momentum (勢い) of a thread, the last hour
+pub const MOMENTUM_WINDOW_SECS: i64 = 3600;
+/// Window of the momentum alert
+pub const MOMENTUM_ALERT_WINDOW_SECS: i64 = 300;
+/// Alerts kept for the admin, older ones are dropped
+const MAX_MOMENTUM_ALERTS: isize = 200;
+
+/// Counts a response to the thread and returns the responses to it within the last
+/// `window_secs`, which must not exceed [`MOMENTUM_WINDOW_SECS`].
+///
+/// Each thread has a sorted set of its responses scored by creation time in milliseconds,
+/// trimmed to the momentum window on every write.
+pub async fn record_thread_response<C: ConnectionLike>(
+    conn: &mut C,
+    thread_id: Uuid,
+    response_id: Uuid,
+    now: DateTime<Utc>,
+    window_secs: i64,
+) -> redis::RedisResult<u64> {
+    let key = thread_momentum_key(thread_id);
+    let now_ms = now.timestamp_millis();
+    let (count,): (u64,) = redis::pipe()
+        .atomic()
+        .zadd(&key, response_id.to_string(), now_ms)
+        .ignore()
+        .zrembyscore(
+            &key,
+            "-inf",
+            format!("({}", now_ms - MOMENTUM_WINDOW_SECS * 1000),
+        )
+        .ignore()
+        .expire(&key, MOMENTUM_WINDOW_SECS)
+        .ignore()
+        .zcount(&key, now_ms - window_secs * 1000, "+inf")
+        .query_async(conn)
+        .await?;
+    Ok(count)
+}
+
+/// Responses to each of the threads within the last hour. Threads without any are left
+/// out.
+pub async fn get_threads_momentum<C: ConnectionLike>(
+    conn: &mut C,
+    thread_ids: &[Uuid],
+    now: DateTime<Utc>,
+) -> redis::RedisResult<HashMap<Uuid, u64>> {
+    if thread_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let since_ms = now.timestamp_millis() - MOMENTUM_WINDOW_SECS * 1000;
+    let mut pipe = redis::pipe();
+    for thread_id in thread_ids {
+        pipe.zcount(thread_momentum_key(thread_id), since_ms, "+inf");
+    }
+    let counts: Vec<u64> = pipe.query_async(conn).await?;
+
+    Ok(thread_ids
+        .iter()
+        .copied()
+        .zip(counts)
+        .filter(|(_, count)| *count > 0)
+        .collect())
+}
+
+/// Claims the alert of the thread, returning `false` when it has already been raised
+/// within the momentum window
+pub async fn claim_momentum_alert<C: ConnectionLike>(
+    conn: &mut C,
+    thread_id: Uuid,
+) -> redis::RedisResult<bool> {
+    let claimed: Option<String> = redis::cmd("SET")
+        .arg(thread_momentum_alerted_key(thread_id))
+        .arg(1)
+        .arg("NX")
+        .arg("EX")
+        .arg(MOMENTUM_WINDOW_SECS)
+        .query_async(conn)
+        .await?;
+    Ok(claimed.is_some())
+}
+
+/// A thread receiving responses faster than the alert threshold, which often means a raid
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MomentumAlert {
+    pub board_key: String,
+    pub thread_number: u64,
+    /// Responses to the thread within `window_secs`
+    pub responses: u64,
+    pub window_secs: i64,
+    pub detected_at: DateTime<Utc>,
+}
+
+pub async fn push_momentum_alert<C: ConnectionLike>(
+    conn: &mut C,
+    alert: &MomentumAlert,
+) -> anyhow::Result<()> {
+    let alert = serde_json::to_string(alert)?;
+    redis::pipe()
+        .atomic()
+        .lpush(MOMENTUM_ALERTS_KEY, alert)
+        .ignore()
+        .ltrim(MOMENTUM_ALERTS_KEY, 0, MAX_MOMENTUM_ALERTS - 1)
+        .ignore()
+        .query_async::<()>(conn)
+        .await?;
+    Ok(())
+}
+
+/// Latest alerts first
+pub async fn get_momentum_alerts<C: ConnectionLike>(
+    conn: &mut C,
+) -> redis::RedisResult<Vec<MomentumAlert>> {
+    let alerts: Vec<String> = redis::cmd("LRANGE")
+        .arg(MOMENTUM_ALERTS_KEY)
+        .arg(0)
+        .arg(-1)
+        .query_async(conn)
+        .await?;
+    Ok(alerts
+        .iter()
+        .filter_map(|alert| serde_json::from_str(alert).ok())
+        .collect())
+}
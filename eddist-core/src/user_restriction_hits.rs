@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionLike;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    domain::user_restriction::RestrictionTarget,
+    redis_keys::{
+        USER_RESTRICTION_HITS_KEY, USER_RESTRICTION_LAST_HITS_KEY, user_restriction_hit_samples_key,
+    },
+};
+
+/// Matched clients kept per rule, older ones are dropped
+pub const MAX_HIT_SAMPLES: isize = 20;
+/// Samples of a rule that has not been hit for a week are dropped from Redis, the last
+/// flushed ones staying in MySQL
+const HIT_SAMPLES_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// A client matched by a restriction rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RestrictionHitSample {
+    pub ip: String,
+    pub asn: u32,
+    pub user_agent: String,
+    /// `None` for auth-code
+    pub board_key: Option<String>,
+    pub target: RestrictionTarget,
+    pub hit_at: DateTime<Utc>,
+}
+
+/// Counts a hit of the rule and keeps the client among its latest samples
+pub async fn record_rule_hit<C: ConnectionLike>(
+    conn: &mut C,
+    rule_id: Uuid,
+    sample: &RestrictionHitSample,
+) -> anyhow::Result<()> {
+    let rule_id = rule_id.to_string();
+    let samples_key = user_restriction_hit_samples_key(&rule_id);
+    redis::pipe()
+        .atomic()
+        .hincr(USER_RESTRICTION_HITS_KEY, &rule_id, 1)
+        .ignore()
+        .hset(
+            USER_RESTRICTION_LAST_HITS_KEY,
+            &rule_id,
+            sample.hit_at.timestamp_millis(),
+        )
+        .ignore()
+        .lpush(&samples_key, serde_json::to_string(sample)?)
+        .ignore()
+        .ltrim(&samples_key, 0, MAX_HIT_SAMPLES - 1)
+        .ignore()
+        .expire(&samples_key, HIT_SAMPLES_TTL_SECS)
+        .ignore()
+        .query_async::<()>(conn)
+        .await?;
+    Ok(())
+}
+
+/// Hits of a rule since the last flush
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingRuleHits {
+    pub rule_id: Uuid,
+    pub hits: u64,
+    pub last_hit_at: DateTime<Utc>,
+}
+
+/// Takes the hits recorded since the last flush, leaving the counters empty
+pub async fn take_rule_hits<C: ConnectionLike>(
+    conn: &mut C,
+) -> redis::RedisResult<Vec<PendingRuleHits>> {
+    let (hits, last_hits): (HashMap<String, u64>, HashMap<String, i64>) = redis::pipe()
+        .atomic()
+        .hgetall(USER_RESTRICTION_HITS_KEY)
+        .hgetall(USER_RESTRICTION_LAST_HITS_KEY)
+        .del(USER_RESTRICTION_HITS_KEY)
+        .ignore()
+        .del(USER_RESTRICTION_LAST_HITS_KEY)
+        .ignore()
+        .query_async(conn)
+        .await?;
+    Ok(pending_rule_hits(hits, last_hits))
+}
+
+/// Puts back hits that could not be flushed, to be taken by the next flush
+pub async fn restore_rule_hits<C: ConnectionLike>(
+    conn: &mut C,
+    pending: &[PendingRuleHits],
+) -> redis::RedisResult<()> {
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    for hits in pending {
+        let rule_id = hits.rule_id.to_string();
+        pipe.hincr(USER_RESTRICTION_HITS_KEY, &rule_id, hits.hits)
+            .ignore()
+            // A hit recorded in the meantime is newer
+            .hset_nx(
+                USER_RESTRICTION_LAST_HITS_KEY,
+                &rule_id,
+                hits.last_hit_at.timestamp_millis(),
+            )
+            .ignore();
+    }
+    pipe.query_async(conn).await
+}
+
+/// Latest samples of each rule first
+pub async fn get_rule_hit_samples<C: ConnectionLike>(
+    conn: &mut C,
+    rule_ids: &[Uuid],
+) -> redis::RedisResult<Vec<Vec<RestrictionHitSample>>> {
+    let mut pipe = redis::pipe();
+    for rule_id in rule_ids {
+        pipe.lrange(
+            user_restriction_hit_samples_key(&rule_id.to_string()),
+            0,
+            MAX_HIT_SAMPLES - 1,
+        );
+    }
+    let samples: Vec<Vec<String>> = pipe.query_async(conn).await?;
+    Ok(samples
+        .into_iter()
+        .map(|samples| {
+            samples
+                .iter()
+                .filter_map(|sample| serde_json::from_str(sample).ok())
+                .collect()
+        })
+        .collect())
+}
+
+fn pending_rule_hits(
+    hits: HashMap<String, u64>,
+    last_hits: HashMap<String, i64>,
+) -> Vec<PendingRuleHits> {
+    let mut pending = hits
+        .into_iter()
+        .filter_map(|(rule_id, hits)| {
+            let last_hit_at = last_hits
+                .get(&rule_id)
+                .and_then(|millis| DateTime::from_timestamp_millis(*millis))?;
+            Some(PendingRuleHits {
+                rule_id: rule_id.parse().ok()?,
+                hits,
+                last_hit_at,
+            })
+        })
+        .filter(|pending| pending.hits > 0)
+        .collect::<Vec<_>>();
+    pending.sort_by_key(|pending| pending.rule_id);
+    pending
+}
+
+/// Hits of a rule flushed to MySQL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestrictionRuleHits {
+    pub rule_id: Uuid,
+    pub hit_count: u64,
+    pub last_hit_at: Option<DateTime<Utc>>,
+    /// Latest first, as of the last flush
+    pub recent_samples: Vec<RestrictionHitSample>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_rule_hits() {
+        let (first, second) = (Uuid::now_v7(), Uuid::now_v7());
+        let hits = HashMap::from([
+            (second.to_string(), 3),
+            (first.to_string(), 1),
+            // Both of these are left out
+            ("not-a-uuid".to_string(), 2),
+            (Uuid::now_v7().to_string(), 4),
+        ]);
+        let last_hits = HashMap::from([
+            (first.to_string(), 1_719_543_845_123),
+            (second.to_string(), 1_719_543_900_000),
+            ("not-a-uuid".to_string(), 1_719_543_900_000),
+        ]);
+
+        assert_eq!(
+            pending_rule_hits(hits, last_hits),
+            vec![
+                PendingRuleHits {
+                    rule_id: first,
+                    hits: 1,
+                    last_hit_at: DateTime::from_timestamp_millis(1_719_543_845_123).unwrap(),
+                },
+                PendingRuleHits {
+                    rule_id: second,
+                    hits: 3,
+                    last_hit_at: DateTime::from_timestamp_millis(1_719_543_900_000).unwrap(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,357 @@
+use std::{
+    env,
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use eddist_core::domain::{
+    user_restriction::UserRestrictionRule,
+    user_restriction_bulk::{UserRestrictionSubscription, plan_restriction_import},
+};
+use reqwest::Url;
+use uuid::Uuid;
+
+use crate::repository::Repository;
+
+/// Larger than any public IP blocklist, a source beyond this is most likely misconfigured
+const MAX_SOURCE_BYTES: u64 = 32 * 1024 * 1024;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Where the sources of the subscriptions may be read from, so that an admin cannot make
+/// the cron host read its own files or the services of its network
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SourcePolicy {
+    /// `BLOCKLIST_SOURCE_DIR`, file sources are refused without it
+    pub source_dir: Option<PathBuf>,
+    /// `BLOCKLIST_ALLOW_PRIVATE_URLS`, for a mirror in the same network
+    pub allow_private_urls: bool,
+}
+
+impl SourcePolicy {
+    pub fn from_env() -> Self {
+        Self {
+            source_dir: env::var("BLOCKLIST_SOURCE_DIR")
+                .ok()
+                .filter(|x| !x.trim().is_empty())
+                .map(|x| PathBuf::from(x.trim())),
+            allow_private_urls: env::var("BLOCKLIST_ALLOW_PRIVATE_URLS") == Ok("true".to_string()),
+        }
+    }
+}
+
+/// Refreshes the due subscriptions one by one, a failing source leaving its last rules in place
+/// until they expire
+pub(crate) async fn refresh_blocklists(repo: &Repository, now: DateTime<Utc>) {
+    let subscriptions = match repo.get_restriction_subscriptions().await {
+        Ok(subscriptions) => subscriptions,
+        Err(e) => {
+            log::error!("Failed to get blocklist subscriptions: {e}");
+            return;
+        }
+    };
+    let policy = SourcePolicy::from_env();
+
+    for subscription in subscriptions.iter().filter(|s| s.is_due(now)) {
+        let rules = match fetch_source(&policy, subscription).await {
+            Ok(content) => build_rules(subscription, &content, now),
+            Err(e) => Err(format!("failed to fetch {}: {e}", subscription.source)),
+        };
+
+        let result = match &rules {
+            Ok((rules, row_errors)) => {
+                log::info!(
+                    "Blocklist subscription {} refreshed with {} rules",
+                    subscription.name,
+                    rules.len()
+                );
+                repo.replace_subscription_rules(subscription.id, rules, now, row_errors.as_deref())
+                    .await
+            }
+            Err(e) => {
+                log::error!(
+                    "Failed to refresh blocklist subscription {}: {e}",
+                    subscription.name
+                );
+                repo.set_subscription_error(subscription.id, now, e).await
+            }
+        };
+        if let Err(e) = result {
+            log::error!(
+                "Failed to save blocklist subscription {}: {e}",
+                subscription.name
+            );
+        }
+    }
+}
+
+pub(crate) async fn fetch_source(
+    policy: &SourcePolicy,
+    subscription: &UserRestrictionSubscription,
+) -> anyhow::Result<String> {
+    let bytes = if let Some(path) = subscription.local_path() {
+        let path = allowed_local_path(policy, Path::new(path)).await?;
+        let len = tokio::fs::metadata(&path).await?.len();
+        if len > MAX_SOURCE_BYTES {
+            anyhow::bail!("the file is {len} bytes, over the limit of {MAX_SOURCE_BYTES}");
+        }
+        tokio::fs::read(path).await?
+    } else {
+        let url = Url::parse(&subscription.source)?;
+        let addrs = allowed_url_addrs(policy, &url).await?;
+        // Pinned to the checked addresses and without redirects, which could lead anywhere
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .resolve_to_addrs(url.host_str().unwrap_or_default(), &addrs)
+            .build()?;
+        let response = client.get(url).send().await?.error_for_status()?;
+        if response
+            .content_length()
+            .is_some_and(|len| len > MAX_SOURCE_BYTES)
+        {
+            anyhow::bail!("the response is over the limit of {MAX_SOURCE_BYTES} bytes");
+        }
+        let bytes = response.bytes().await?;
+        if bytes.len() as u64 > MAX_SOURCE_BYTES {
+            anyhow::bail!("the response is over the limit of {MAX_SOURCE_BYTES} bytes");
+        }
+        bytes.to_vec()
+    };
+
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// The canonical path of a file source, which must be in the source directory
+async fn allowed_local_path(policy: &SourcePolicy, path: &Path) -> anyhow::Result<PathBuf> {
+    let Some(source_dir) = &policy.source_dir else {
+        anyhow::bail!("file sources are disabled, set BLOCKLIST_SOURCE_DIR to allow them");
+    };
+    // Resolves `..` and symlinks, which could otherwise lead out of the directory
+    let source_dir = tokio::fs::canonicalize(source_dir).await?;
+    let path = tokio::fs::canonicalize(path).await?;
+    if !path.starts_with(&source_dir) {
+        anyhow::bail!("the file is outside of BLOCKLIST_SOURCE_DIR");
+    }
+    Ok(path)
+}
+
+/// The addresses of the host of the URL, which must all be public unless allowed
+async fn allowed_url_addrs(policy: &SourcePolicy, url: &Url) -> anyhow::Result<Vec<SocketAddr>> {
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        anyhow::bail!("the URL has no host");
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs = tokio::net::lookup_host((host, port))
+        .await?
+        .collect::<Vec<_>>();
+    if addrs.is_empty() {
+        anyhow::bail!("the host has no address");
+    }
+    if !policy.allow_private_urls && addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+        anyhow::bail!(
+            "the host resolves to a private address, set BLOCKLIST_ALLOW_PRIVATE_URLS to allow it"
+        );
+    }
+    Ok(addrs)
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip.to_canonical() {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // Shared address space of carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => {
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local())
+        }
+    }
+}
+
+/// The rules of the subscription and a summary of the rows that were skipped, if any. The
+/// stored summaries never quote the content, which every admin can read.
+pub(crate) fn build_rules(
+    subscription: &UserRestrictionSubscription,
+    content: &str,
+    now: DateTime<Utc>,
+) -> Result<(Vec<UserRestrictionRule>, Option<String>), String> {
+    let plan = plan_restriction_import(
+        subscription.format,
+        content,
+        &subscription.import_defaults(now),
+        &[],
+    )
+    .map_err(|e| {
+        log::warn!(
+            "Blocklist subscription {} is malformed: {e}",
+            subscription.name
+        );
+        format!(
+            "the source is not a valid {} blocklist",
+            subscription.format.as_str()
+        )
+    })?;
+    let row_errors = plan.errors.first().map(|first| {
+        format!(
+            "{} invalid rows skipped, first at row {}",
+            plan.errors.len(),
+            first.row,
+        )
+    });
+    let rules = plan
+        .rules
+        .into_iter()
+        .map(|input| input.into_rule(Uuid::now_v7(), now))
+        .collect();
+
+    Ok((rules, row_errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use eddist_core::domain::{
+        user_restriction::{RestrictionAction, RestrictionRuleType, RestrictionTarget},
+        user_restriction_bulk::{CreateUserRestrictionSubscriptionInput, RestrictionRuleFormat},
+    };
+
+    use super::*;
+
+    fn subscription(source: String) -> UserRestrictionSubscription {
+        CreateUserRestrictionSubscriptionInput {
+            name: "drop".to_string(),
+            source,
+            format: RestrictionRuleFormat::Cidr,
+            board_keys: None,
+            targets: RestrictionTarget::ALL.to_vec(),
+            action: RestrictionAction::Block,
+            captcha_provider: None,
+            slow_down_seconds: 0,
+            refresh_interval_seconds: 3600,
+            expires_after_seconds: 3 * 3600,
+            enabled: true,
+            created_by_email: "admin@example.com".to_string(),
+        }
+        .into_subscription(Uuid::now_v7(), Utc::now())
+    }
+
+    #[tokio::test]
+    async fn test_local_file_source() {
+        let path = std::env::temp_dir().join(format!("eddist-blocklist-{}.txt", Uuid::now_v7()));
+        tokio::fs::write(
+            &path,
+            "; DROP list\n192.0.2.0/24 ; SBL1\n198.51.100.7\n192.0.2.0/24\nnot-an-ip\n",
+        )
+        .await
+        .unwrap();
+
+        let policy = SourcePolicy {
+            source_dir: Some(std::env::temp_dir()),
+            ..Default::default()
+        };
+        let now = Utc::now();
+        for source in [
+            path.to_string_lossy().to_string(),
+            format!("file://{}", path.to_string_lossy()),
+        ] {
+            let subscription = subscription(source);
+            let content = fetch_source(&policy, &subscription).await.unwrap();
+            let (rules, row_errors) = build_rules(&subscription, &content, now).unwrap();
+
+            assert_eq!(
+                rules
+                    .iter()
+                    .map(|rule| (rule.rule_type.clone(), rule.rule_value.as_str()))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (RestrictionRuleType::IPCidr, "192.0.2.0/24"),
+                    (RestrictionRuleType::IP, "198.51.100.7"),
+                ]
+            );
+            for rule in &rules {
+                assert_eq!(rule.subscription_id, Some(subscription.id));
+                assert_eq!(
+                    rule.expires_at,
+                    Some(now + chrono::Duration::seconds(3 * 3600))
+                );
+            }
+            assert_eq!(
+                row_errors.as_deref(),
+                Some("1 invalid rows skipped, first at row 5")
+            );
+        }
+
+        // Outside of the source directory, or without one
+        let other_dir = SourcePolicy {
+            source_dir: Some(std::env::temp_dir().join(format!("eddist-{}", Uuid::now_v7()))),
+            ..Default::default()
+        };
+        tokio::fs::create_dir(other_dir.source_dir.as_ref().unwrap())
+            .await
+            .unwrap();
+        let escaping = format!(
+            "{}/../{}",
+            other_dir.source_dir.as_ref().unwrap().to_string_lossy(),
+            path.file_name().unwrap().to_string_lossy()
+        );
+        for (policy, source) in [
+            (&other_dir, path.to_string_lossy().to_string()),
+            (&other_dir, escaping),
+            (&SourcePolicy::default(), path.to_string_lossy().to_string()),
+        ] {
+            assert!(fetch_source(policy, &subscription(source)).await.is_err());
+        }
+
+        tokio::fs::remove_file(&path).await.unwrap();
+        assert!(
+            fetch_source(&policy, &subscription(path.to_string_lossy().to_string()))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_private_url_source() {
+        for source in [
+            "http://127.0.0.1/drop.txt",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://[::1]:8080/drop.txt",
+            "http://10.0.0.1/drop.txt",
+        ] {
+            let error = fetch_source(&SourcePolicy::default(), &subscription(source.to_string()))
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains("private address"), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_build_rules_does_not_quote_content() {
+        let subscription = subscription("https://example.com/drop.txt".to_string());
+        let (_, row_errors) = build_rules(&subscription, "SECRET_TOKEN=abc\n", Utc::now()).unwrap();
+        assert_eq!(
+            row_errors.as_deref(),
+            Some("1 invalid rows skipped, first at row 1")
+        );
+
+        let json = UserRestrictionSubscription {
+            format: RestrictionRuleFormat::Json,
+            ..subscription
+        };
+        let error = build_rules(&json, "\"SECRET_TOKEN=abc\"", Utc::now()).unwrap_err();
+        assert!(!error.contains("SECRET"));
+    }
+}
@@ -1,23 +1,23 @@
 use std::{env, str::FromStr, time::Duration};
 
-use aws_sdk_s3::{
-    Client,
-    config::{Credentials, Region},
-    error::SdkError,
-    operation::head_object::HeadObjectError,
-    primitives::ByteStream,
-};
 use chrono::{TimeDelta, TimeZone, Timelike, Utc};
 use cron::Schedule;
 use eddist_core::{
-    domain::res::get_1001_sjis_bytes, redis_keys::unsafe_threads_key, tracing::init_tracing,
+    archive_storage::{ArchiveStorage, archive_storage_from_env},
+    archived_dat::{ArchivedDatKind, archived_dat_exists, put_archived_dat},
+    domain::res::get_1001_sjis_bytes,
+    redis_keys::unsafe_threads_key,
+    tracing::init_tracing,
     utils::is_prod,
 };
 use redis::AsyncCommands;
 use sqlx::mysql::MySqlPoolOptions;
 use tokio::time::sleep;
 
+mod blocklist;
 mod repository;
+mod restriction_hits;
+mod search_index;
 
 #[tokio::main]
 async fn main() {
@@ -30,7 +30,12 @@ async fn main() {
     // Jobs:
     // - inactivate and archive (not to show thread list),
     // - archive (move to archive table)
-    // - convert (to dat text file compressed by gzip and delete responses, and publish to S3 compatible storage)
+    // - convert (to dat text file compressed by gzip and delete responses, and publish to the archive storage)
+    // - refresh-blocklists (replace the restriction rules of the due blocklist subscriptions)
+    // - flush-restriction-hits (move the restriction rule hits counted in Redis to MySQL)
+    // - expire-idle-restriction-rules (expire the restriction rules without a recent hit)
+    // - index-search (index the recent posts for search and clear old personal data from the index)
+    // - backfill-search-index (index every post missing from the search index, archives included)
 
     let args = std::env::args().collect::<Vec<String>>();
     if args.len() < 2 {
@@ -208,9 +213,9 @@ async fn main() {
         }
         "convert" => {
             // convert
-            // - convert (to dat text file compressed by gzip and delete responses, and publish to S3 compatible storage)
+            // - convert (to dat text file compressed by gzip and delete responses, and publish to the archive storage)
             let boards = repo.get_all_boards_info().await.unwrap();
-            let (s3_client, s3_bucket_name) = make_s3_client();
+            let archive_storage = archive_storage_from_env().unwrap();
 
             for board in boards {
                 let threads = repo
@@ -261,16 +266,11 @@ async fn main() {
                         admin_dat.extend_from_slice(&bytes_1001);
                     }
 
-                    // TODO: sjis to utf-8 workarounds for now
-                    let admin_dat = encoding_rs::SHIFT_JIS.decode(&admin_dat).0.into_owned();
-                    let dat = encoding_rs::SHIFT_JIS.decode(&dat).0.into_owned();
-
                     if retry(
-                        &s3_client,
-                        &s3_bucket_name,
+                        archive_storage.as_ref(),
                         &board.board_key,
                         thread_number,
-                        admin_dat.as_bytes(),
+                        &admin_dat,
                         true,
                     )
                     .await
@@ -285,11 +285,10 @@ async fn main() {
                     }
 
                     if retry(
-                        &s3_client,
-                        &s3_bucket_name,
+                        archive_storage.as_ref(),
                         &board.board_key,
                         thread_number,
-                        dat.as_bytes(),
+                        &dat,
                         false,
                     )
                     .await
@@ -324,10 +323,10 @@ async fn main() {
             let end = args[3].parse::<u64>().unwrap();
 
             // backfill-convert
-            // - convert (to dat text file compressed by gzip and delete responses, and publish to S3 compatible storage)
+            // - convert (to dat text file compressed by gzip and delete responses, and publish to the archive storage)
             //   with only threads that are not converted yet because of the previous error
             let boards = repo.get_all_boards_info().await.unwrap();
-            let (s3_client, s3_bucket_name) = make_s3_client();
+            let archive_storage = archive_storage_from_env().unwrap();
 
             for board in boards {
                 let threads = repo
@@ -373,21 +372,15 @@ async fn main() {
                         admin_dat.append(&mut admin_res.get_inner());
                     }
 
-                    // TODO: sjis to utf-8 workarounds for now
-                    let admin_dat = encoding_rs::SHIFT_JIS.decode(&admin_dat).0.into_owned();
-                    let dat = encoding_rs::SHIFT_JIS.decode(&dat).0.into_owned();
-
-                    let admin_needs = match s3_client
-                        .head_object()
-                        .bucket(&s3_bucket_name)
-                        .key(format!(
-                            "{}/{}/{}.dat",
-                            board.board_key, "admin", thread_number
-                        ))
-                        .send()
-                        .await
+                    let admin_needs = match archived_dat_exists(
+                        archive_storage.as_ref(),
+                        &board.board_key,
+                        ArchivedDatKind::Admin,
+                        thread_number,
+                    )
+                    .await
                     {
-                        Ok(_) => {
+                        Ok(true) => {
                             log::info!(
                                 "admin.dat already exists: {}/{}",
                                 board.board_key,
@@ -395,11 +388,7 @@ async fn main() {
                             );
                             false
                         }
-                        Err(SdkError::ServiceError(e))
-                            if matches!(e.err(), HeadObjectError::NotFound(_)) =>
-                        {
-                            true
-                        }
+                        Ok(false) => true,
                         Err(err) => {
                             log::warn!(
                                 "Failed to check admin.dat existence: {}/{}, assuming upload needed: {err:?}",
@@ -419,11 +408,10 @@ async fn main() {
                         );
 
                         if retry(
-                            &s3_client,
-                            &s3_bucket_name,
+                            archive_storage.as_ref(),
                             &board.board_key,
                             thread_number,
-                            admin_dat.as_bytes(),
+                            &admin_dat,
                             true,
                         )
                         .await
@@ -438,17 +426,15 @@ async fn main() {
                         }
                     }
 
-                    let dat_needs = match s3_client
-                        .head_object()
-                        .bucket(&s3_bucket_name)
-                        .key(format!(
-                            "{}/{}/{}.dat",
-                            board.board_key, "dat", thread_number
-                        ))
-                        .send()
-                        .await
+                    let dat_needs = match archived_dat_exists(
+                        archive_storage.as_ref(),
+                        &board.board_key,
+                        ArchivedDatKind::Dat,
+                        thread_number,
+                    )
+                    .await
                     {
-                        Ok(_) => {
+                        Ok(true) => {
                             log::info!(
                                 "normal.dat already exists: {}/{}",
                                 board.board_key,
@@ -456,11 +442,7 @@ async fn main() {
                             );
                             false
                         }
-                        Err(SdkError::ServiceError(e))
-                            if matches!(e.err(), HeadObjectError::NotFound(_)) =>
-                        {
-                            true
-                        }
+                        Ok(false) => true,
                         Err(err) => {
                             log::warn!(
                                 "Failed to check normal.dat existence: {}/{}, assuming upload needed: {err:?}",
@@ -480,11 +462,10 @@ async fn main() {
                         );
 
                         if retry(
-                            &s3_client,
-                            &s3_bucket_name,
+                            archive_storage.as_ref(),
                             &board.board_key,
                             thread_number,
-                            dat.as_bytes(),
+                            &dat,
                             false,
                         )
                         .await
@@ -508,6 +489,26 @@ async fn main() {
                 );
             }
         }
+        "refresh-blocklists" => {
+            blocklist::refresh_blocklists(&repo, executed_time).await;
+        }
+        "flush-restriction-hits" => {
+            let mut redis_conn = redis::Client::open(env::var("REDIS_URL").unwrap())
+                .unwrap()
+                .get_connection_manager()
+                .await
+                .unwrap();
+            restriction_hits::flush_restriction_hits(&repo, &mut redis_conn).await;
+        }
+        "expire-idle-restriction-rules" => {
+            restriction_hits::expire_idle_restriction_rules(&repo, executed_time).await;
+        }
+        "index-search" => {
+            search_index::index_recent_posts(&repo, executed_time).await;
+        }
+        "backfill-search-index" => {
+            search_index::backfill_search_index(&repo, executed_time).await;
+        }
 
         job => {
             log::error!("Unknown job: {job}");
@@ -516,32 +517,8 @@ async fn main() {
     }
 }
 
-fn make_s3_client() -> (Client, String) {
-    let r2_account_id = env::var("R2_ACCOUNT_ID").unwrap();
-    let bucket_name = env::var("S3_BUCKET_NAME").unwrap().trim().to_string();
-    let endpoint = format!("https://{}.r2.cloudflarestorage.com", r2_account_id.trim());
-
-    let creds = Credentials::new(
-        env::var("S3_ACCESS_KEY").unwrap().trim(),
-        env::var("S3_ACCESS_SECRET_KEY").unwrap().trim(),
-        None,
-        None,
-        "custom",
-    );
-
-    let config = aws_sdk_s3::Config::builder()
-        .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
-        .credentials_provider(creds)
-        .region(Region::new("auto"))
-        .endpoint_url(endpoint)
-        .build();
-
-    (Client::from_conf(config), bucket_name)
-}
-
 async fn retry(
-    s3_client: &Client,
-    bucket_name: &str,
+    archive_storage: &dyn ArchiveStorage,
     board_key: &str,
     thread_number: u64,
     content: &[u8],
@@ -555,19 +532,18 @@ async fn retry(
         if retry_count >= 0 {
             tokio::time::sleep(Duration::from_secs(retry_delay)).await;
         }
-        let key = format!(
-            "{}/{}/{}.dat",
+        let result = put_archived_dat(
+            archive_storage,
             board_key,
-            if is_admin { "admin" } else { "dat" },
-            thread_number
-        );
-        let result = s3_client
-            .put_object()
-            .bucket(bucket_name)
-            .key(&key)
-            .body(ByteStream::from(content.to_vec()))
-            .send()
-            .await;
+            if is_admin {
+                ArchivedDatKind::Admin
+            } else {
+                ArchivedDatKind::Dat
+            },
+            thread_number,
+            content,
+        )
+        .await;
         retry_count += 1;
         retry_delay *= 2;
         match result {
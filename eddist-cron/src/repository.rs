@@ -1,8 +1,24 @@
-use chrono::{TimeZone, Utc};
-use sqlx::{MySqlPool, types::Json};
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use sqlx::{MySql, MySqlPool, QueryBuilder, types::Json};
 use uuid::Uuid;
 
-use eddist_core::domain::{client_info::ClientInfo, res::ResView};
+use eddist_core::{
+    domain::{
+        client_info::ClientInfo,
+        res::ResView,
+        user_restriction::{RestrictionAction, RestrictionTarget, UserRestrictionRule},
+        user_restriction_bulk::{RestrictionRuleFormat, UserRestrictionSubscription},
+    },
+    server_settings::ServerSettingKey,
+    user_restriction_hits::{PendingRuleHits, RestrictionHitSample},
+};
+
+/// Rows per INSERT when replacing the rules of a subscription
+const INSERT_RULES_CHUNK_SIZE: usize = 1000;
+/// Rows per INSERT of the n-grams of a search document
+const SEARCH_NGRAM_INSERT_CHUNK: usize = 500;
 
 #[derive(Clone)]
 pub(crate) struct Repository(MySqlPool);
@@ -78,6 +94,7 @@ impl Repository {
                     FROM threads
                     WHERE board_id = (SELECT id FROM boards WHERE board_key = ?)
                     AND archived = 0
+                    AND no_pool = 0
                     ORDER BY last_modified_at DESC
                     LIMIT 1000000 OFFSET ?
                 ) AS tmp
@@ -379,6 +396,394 @@ impl Repository {
     }
 }
 
+impl Repository {
+    pub async fn get_restriction_subscriptions(
+        &self,
+    ) -> anyhow::Result<Vec<UserRestrictionSubscription>> {
+        let rows = sqlx::query_as::<_, SelectionUserRestrictionSubscription>(
+            r#"
+            SELECT
+                id,
+                name,
+                source,
+                format,
+                board_keys,
+                targets,
+                action,
+                captcha_provider,
+                slow_down_seconds,
+                refresh_interval_seconds,
+                expires_after_seconds,
+                enabled,
+                last_refreshed_at,
+                last_error,
+                rule_count,
+                created_at,
+                updated_at,
+                created_by_email
+            FROM user_restriction_subscriptions
+            WHERE enabled = 1
+            "#,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        rows.into_iter()
+            .map(UserRestrictionSubscription::try_from)
+            .collect()
+    }
+
+    /// Swaps the rules of the subscription for `rules` in one transaction, so the restriction
+    /// cache never sees the group half imported. A rule with the same value as a replaced one
+    /// keeps its id and hits, so that the hits still pending in Redis are not dropped.
+    pub async fn replace_subscription_rules(
+        &self,
+        subscription_id: Uuid,
+        rules: &[UserRestrictionRule],
+        refreshed_at: DateTime<Utc>,
+        last_error: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.0.begin().await?;
+        let current = sqlx::query_as::<_, SelectionSubscriptionRule>(
+            r#"
+            SELECT id, rule_type, rule_value, hit_count, last_hit_at, recent_hit_samples
+            FROM user_restriction_rules
+            WHERE subscription_id = ?
+            "#,
+        )
+        .bind(subscription_id)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|rule| ((rule.rule_type.clone(), rule.rule_value.clone()), rule))
+        .collect::<HashMap<_, _>>();
+
+        sqlx::query("DELETE FROM user_restriction_rules WHERE subscription_id = ?")
+            .bind(subscription_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for chunk in rules.chunks(INSERT_RULES_CHUNK_SIZE) {
+            let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+                r#"
+                INSERT INTO user_restriction_rules
+                (id, name, rule_type, rule_value, board_keys, targets, action, captcha_provider,
+                 slow_down_seconds, expires_at, subscription_id, created_at, updated_at,
+                 created_by_email, hit_count, last_hit_at, recent_hit_samples)
+                "#,
+            );
+            builder.push_values(chunk, |mut b, rule| {
+                let current =
+                    current.get(&(rule.rule_type.as_str().to_string(), rule.rule_value.clone()));
+                b.push_bind(current.map_or(rule.id, |current| current.id))
+                    .push_bind(&rule.name)
+                    .push_bind(rule.rule_type.as_str())
+                    .push_bind(&rule.rule_value)
+                    .push_bind(rule.board_keys.as_ref().map(|keys| serde_json::json!(keys)))
+                    .push_bind(RestrictionTarget::join_list(&rule.targets))
+                    .push_bind(rule.action.as_str())
+                    .push_bind(&rule.captcha_provider)
+                    .push_bind(rule.slow_down_seconds)
+                    .push_bind(rule.expires_at.map(|dt| dt.naive_utc()))
+                    .push_bind(rule.subscription_id)
+                    .push_bind(rule.created_at.naive_utc())
+                    .push_bind(rule.updated_at.naive_utc())
+                    .push_bind(&rule.created_by_email)
+                    .push_bind(current.map_or(0, |current| current.hit_count))
+                    .push_bind(current.and_then(|current| current.last_hit_at))
+                    .push_bind(current.and_then(|current| current.recent_hit_samples.clone()));
+            });
+            builder.build().execute(&mut *tx).await?;
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE user_restriction_subscriptions
+            SET last_refreshed_at = ?, last_error = ?, rule_count = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(refreshed_at.naive_utc())
+        .bind(last_error)
+        .bind(rules.len() as u32)
+        .bind(subscription_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Records a failed refresh, the rules of the last good one stay until they expire
+    pub async fn set_subscription_error(
+        &self,
+        subscription_id: Uuid,
+        refreshed_at: DateTime<Utc>,
+        error: &str,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE user_restriction_subscriptions
+            SET last_refreshed_at = ?, last_error = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(refreshed_at.naive_utc())
+        .bind(error)
+        .bind(subscription_id)
+        .execute(&self.0)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Adds the hits taken from Redis to the rules, replacing their samples. Hits of deleted
+    /// rules are dropped.
+    pub async fn add_restriction_rule_hits(
+        &self,
+        hits: &[(PendingRuleHits, Vec<RestrictionHitSample>)],
+    ) -> anyhow::Result<()> {
+        let mut tx = self.0.begin().await?;
+        for (pending, samples) in hits {
+            let last_hit_at = pending.last_hit_at.naive_utc();
+            sqlx::query(
+                r#"
+                UPDATE user_restriction_rules
+                SET hit_count = hit_count + ?,
+                    last_hit_at = GREATEST(COALESCE(last_hit_at, ?), ?),
+                    recent_hit_samples = COALESCE(?, recent_hit_samples)
+                WHERE id = ?
+                "#,
+            )
+            .bind(pending.hits)
+            .bind(last_hit_at)
+            .bind(last_hit_at)
+            // Samples expire from Redis a week after the last hit, keep the flushed ones then
+            .bind((!samples.is_empty()).then_some(Json(samples)))
+            .bind(pending.rule_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Expires the rules not hit since `idle_since`, counting from their creation for rules
+    /// never hit. Rules of subscriptions are left to their subscription.
+    pub async fn expire_idle_restriction_rules(
+        &self,
+        idle_since: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_restriction_rules
+            SET expires_at = ?, updated_at = ?
+            WHERE subscription_id IS NULL
+                AND (expires_at IS NULL OR expires_at > ?)
+                AND COALESCE(last_hit_at, created_at) < ?
+            "#,
+        )
+        .bind(now.naive_utc())
+        .bind(now.naive_utc())
+        .bind(now.naive_utc())
+        .bind(idle_since.naive_utc())
+        .execute(&self.0)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_server_setting(
+        &self,
+        key: ServerSettingKey,
+    ) -> anyhow::Result<Option<String>> {
+        let value = sqlx::query_scalar::<_, String>(
+            "SELECT value FROM server_settings WHERE setting_key = ?",
+        )
+        .bind(key.as_str())
+        .fetch_optional(&self.0)
+        .await?;
+
+        Ok(value)
+    }
+}
+
+/// Source table of the posts to index
+#[derive(Debug, Clone, Copy)]
+pub enum SearchSource {
+    Responses,
+    ArchivedResponses,
+}
+
+/// A post missing from the search index
+#[derive(Debug, sqlx::FromRow)]
+pub struct UnindexedPost {
+    pub response_id: Uuid,
+    pub board_id: Uuid,
+    pub board_key: String,
+    pub thread_id: Uuid,
+    pub thread_number: i64,
+    pub thread_title: String,
+    pub res_order: i32,
+    pub body: String,
+    pub author_id: String,
+    pub authed_token_id: Uuid,
+    pub ip_addr: String,
+    pub is_abone: bool,
+    pub created_at: NaiveDateTime,
+}
+
+impl Repository {
+    /// Posts created after `after` without a search document, oldest first
+    pub async fn get_unindexed_posts(
+        &self,
+        source: SearchSource,
+        after: (NaiveDateTime, Uuid),
+        limit: u32,
+    ) -> anyhow::Result<Vec<UnindexedPost>> {
+        let (responses, threads) = match source {
+            SearchSource::Responses => ("responses", "threads"),
+            SearchSource::ArchivedResponses => ("archived_responses", "archived_threads"),
+        };
+        let posts = sqlx::query_as::<_, UnindexedPost>(&format!(
+            r#"
+            SELECT
+                r.id AS response_id,
+                r.board_id AS board_id,
+                b.board_key AS board_key,
+                r.thread_id AS thread_id,
+                t.thread_number AS thread_number,
+                t.title AS thread_title,
+                r.res_order AS res_order,
+                r.body AS body,
+                r.author_id AS author_id,
+                r.authed_token_id AS authed_token_id,
+                r.ip_addr AS ip_addr,
+                r.is_abone AS is_abone,
+                r.created_at AS created_at
+            FROM
+                {responses} AS r
+                JOIN {threads} AS t ON t.id = r.thread_id
+                JOIN boards AS b ON b.id = r.board_id
+                LEFT OUTER JOIN search_documents AS d ON d.response_id = r.id
+            WHERE
+                d.response_id IS NULL
+                AND (r.created_at > ? OR (r.created_at = ? AND r.id > ?))
+            ORDER BY
+                r.created_at,
+                r.id
+            LIMIT ?
+            "#
+        ))
+        .bind(after.0)
+        .bind(after.0)
+        .bind(after.1)
+        .bind(limit)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(posts)
+    }
+
+    /// Adds the post and its n-grams to the search index, counting the n-grams for the
+    /// rarest-first lookup. Returns `false` when the post was indexed already.
+    pub async fn index_search_document(
+        &self,
+        post: &UnindexedPost,
+        ngrams: &[String],
+        keep_personal_data: bool,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.0.begin().await?;
+        let inserted = sqlx::query(
+            r#"
+            INSERT IGNORE INTO search_documents
+                (
+                    response_id,
+                    board_id,
+                    board_key,
+                    thread_id,
+                    thread_number,
+                    thread_title,
+                    res_order,
+                    body,
+                    author_id,
+                    authed_token_id,
+                    ip_addr,
+                    is_abone,
+                    created_at
+                )
+            VALUES
+                (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(post.response_id)
+        .bind(post.board_id)
+        .bind(&post.board_key)
+        .bind(post.thread_id)
+        .bind(post.thread_number)
+        .bind(&post.thread_title)
+        .bind(post.res_order)
+        .bind(&post.body)
+        .bind(&post.author_id)
+        .bind(keep_personal_data.then_some(post.authed_token_id))
+        .bind(keep_personal_data.then_some(&post.ip_addr))
+        .bind(post.is_abone)
+        .bind(post.created_at)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if !inserted {
+            return Ok(false);
+        }
+
+        for chunk in ngrams.chunks(SEARCH_NGRAM_INSERT_CHUNK) {
+            let mut builder: QueryBuilder<MySql> =
+                QueryBuilder::new("INSERT IGNORE INTO search_ngrams (ngram, response_id) ");
+            builder.push_values(chunk, |mut b, ngram| {
+                b.push_bind(ngram).push_bind(post.response_id);
+            });
+            builder.build().execute(&mut *tx).await?;
+
+            let mut builder: QueryBuilder<MySql> =
+                QueryBuilder::new("INSERT INTO search_ngram_stats (ngram, doc_count) ");
+            builder.push_values(chunk, |mut b, ngram| {
+                b.push_bind(ngram).push_bind(1);
+            });
+            builder.push(" ON DUPLICATE KEY UPDATE doc_count = doc_count + 1");
+            builder.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Clears the IP addresses and tokens of the documents created before `before`
+    pub async fn clear_search_personal_data(
+        &self,
+        before: DateTime<Utc>,
+        limit: u32,
+    ) -> anyhow::Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE search_documents
+            SET ip_addr = NULL, authed_token_id = NULL
+            WHERE created_at < ?
+                AND (ip_addr IS NOT NULL OR authed_token_id IS NOT NULL)
+            LIMIT ?
+            "#,
+        )
+        .bind(before.naive_utc())
+        .bind(limit)
+        .execute(&self.0)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
 struct Res {
     author_name: String,
     mail: String,
@@ -400,3 +805,68 @@ pub struct SelectionBoardInfo {
     pub enable_1001_message: bool,
     pub custom_1001_message: Option<String>,
 }
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionUserRestrictionSubscription {
+    id: Uuid,
+    name: String,
+    source: String,
+    format: String,
+    board_keys: Option<serde_json::Value>,
+    targets: String,
+    action: String,
+    captcha_provider: Option<String>,
+    slow_down_seconds: u32,
+    refresh_interval_seconds: u32,
+    expires_after_seconds: u32,
+    enabled: bool,
+    last_refreshed_at: Option<NaiveDateTime>,
+    last_error: Option<String>,
+    rule_count: u32,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    created_by_email: String,
+}
+
+impl TryFrom<SelectionUserRestrictionSubscription> for UserRestrictionSubscription {
+    type Error = anyhow::Error;
+
+    fn try_from(x: SelectionUserRestrictionSubscription) -> anyhow::Result<Self> {
+        Ok(UserRestrictionSubscription {
+            id: x.id,
+            name: x.name,
+            source: x.source,
+            format: x
+                .format
+                .parse::<RestrictionRuleFormat>()
+                .map_err(anyhow::Error::msg)?,
+            board_keys: x.board_keys.map(serde_json::from_value).transpose()?,
+            targets: RestrictionTarget::parse_list(&x.targets).map_err(anyhow::Error::msg)?,
+            action: x
+                .action
+                .parse::<RestrictionAction>()
+                .map_err(anyhow::Error::msg)?,
+            captcha_provider: x.captcha_provider,
+            slow_down_seconds: x.slow_down_seconds,
+            refresh_interval_seconds: x.refresh_interval_seconds,
+            expires_after_seconds: x.expires_after_seconds,
+            enabled: x.enabled,
+            last_refreshed_at: x.last_refreshed_at.map(|dt| dt.and_utc()),
+            last_error: x.last_error,
+            rule_count: x.rule_count,
+            created_at: x.created_at.and_utc(),
+            updated_at: x.updated_at.and_utc(),
+            created_by_email: x.created_by_email,
+        })
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionSubscriptionRule {
+    id: Uuid,
+    rule_type: String,
+    rule_value: String,
+    hit_count: u64,
+    last_hit_at: Option<NaiveDateTime>,
+    recent_hit_samples: Option<serde_json::Value>,
+}
@@ -0,0 +1,76 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use eddist_core::{
+    server_settings::ServerSettingKey,
+    user_restriction_hits::{get_rule_hit_samples, restore_rule_hits, take_rule_hits},
+};
+use redis::aio::ConnectionManager;
+
+use crate::repository::Repository;
+
+/// Moves the hits counted by eddist-server into MySQL, putting them back into Redis if
+/// they cannot be saved
+pub(crate) async fn flush_restriction_hits(repo: &Repository, redis_conn: &mut ConnectionManager) {
+    let pending = match take_rule_hits(redis_conn).await {
+        Ok(pending) => pending,
+        Err(e) => {
+            log::error!("Failed to take restriction rule hits: {e}");
+            return;
+        }
+    };
+    if pending.is_empty() {
+        return;
+    }
+
+    let rule_ids = pending.iter().map(|hits| hits.rule_id).collect::<Vec<_>>();
+    // Samples only add to the counts, a rule is still flushed without them
+    let samples = get_rule_hit_samples(redis_conn, &rule_ids)
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("Failed to get restriction rule hit samples: {e}");
+            vec![Vec::new(); rule_ids.len()]
+        });
+    let hits = pending.into_iter().zip(samples).collect::<Vec<_>>();
+
+    if let Err(e) = repo.add_restriction_rule_hits(&hits).await {
+        log::error!("Failed to save restriction rule hits: {e}");
+        let pending = hits.into_iter().map(|(hits, _)| hits).collect::<Vec<_>>();
+        if let Err(e) = restore_rule_hits(redis_conn, &pending).await {
+            log::error!(
+                "Failed to restore {} restriction rule hits, they are lost: {e}",
+                pending.len()
+            );
+        }
+        return;
+    }
+    log::info!("Flushed the hits of {} restriction rules", hits.len());
+}
+
+/// Expires the rules without a hit for the days of
+/// [`ServerSettingKey::RestrictionRuleIdleExpiryDays`], doing nothing while it is unset
+pub(crate) async fn expire_idle_restriction_rules(repo: &Repository, now: DateTime<Utc>) {
+    let days = match repo
+        .get_server_setting(ServerSettingKey::RestrictionRuleIdleExpiryDays)
+        .await
+    {
+        Ok(value) => value
+            .and_then(|value| value.trim().parse::<i64>().ok())
+            .unwrap_or(0),
+        Err(e) => {
+            log::error!("Failed to get the idle expiry of restriction rules: {e}");
+            return;
+        }
+    };
+    if days <= 0 {
+        return;
+    }
+
+    match repo
+        .expire_idle_restriction_rules(now - TimeDelta::days(days), now)
+        .await
+    {
+        Ok(expired) => {
+            log::info!("Expired {expired} restriction rules without a hit for {days} days")
+        }
+        Err(e) => log::error!("Failed to expire idle restriction rules: {e}"),
+    }
+}
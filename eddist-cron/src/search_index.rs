@@ -0,0 +1,114 @@
+use std::env;
+
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use eddist_core::domain::search::search_ngrams;
+use uuid::Uuid;
+
+use crate::repository::{Repository, SearchSource, UnindexedPost};
+
+/// Posts indexed per query
+const INDEX_BATCH_SIZE: u32 = 500;
+/// How far back `index-search` looks for posts missing from the index, which covers the
+/// held posts approved since and the bodies edited by the admins
+const INDEX_LOOKBACK: TimeDelta = TimeDelta::hours(24);
+/// Documents whose personal data is cleared per query
+const CLEAR_BATCH_SIZE: u32 = 10000;
+const DEFAULT_PERSONAL_DATA_RETENTION_DAYS: i64 = 30;
+
+/// Days the IP addresses and tokens are kept in the search index for the admin search
+fn personal_data_retention() -> TimeDelta {
+    let days = env::var("SEARCH_PERSONAL_DATA_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_PERSONAL_DATA_RETENTION_DAYS);
+    TimeDelta::days(days.max(0))
+}
+
+/// Indexes the recent posts missing from the search index, then clears the personal data
+/// of the documents past the retention
+pub(crate) async fn index_recent_posts(repo: &Repository, now: DateTime<Utc>) {
+    let since = (now - INDEX_LOOKBACK).naive_utc();
+    index_posts(repo, SearchSource::Responses, since, now).await;
+
+    let before = now - personal_data_retention();
+    loop {
+        match repo
+            .clear_search_personal_data(before, CLEAR_BATCH_SIZE)
+            .await
+        {
+            Ok(cleared) if cleared < CLEAR_BATCH_SIZE as u64 => break,
+            Ok(_) => {}
+            Err(e) => {
+                log::error!("Failed to clear the personal data of the search index: {e}");
+                break;
+            }
+        }
+    }
+}
+
+/// Indexes every post missing from the search index, the archived ones included
+pub(crate) async fn backfill_search_index(repo: &Repository, now: DateTime<Utc>) {
+    for source in [SearchSource::Responses, SearchSource::ArchivedResponses] {
+        index_posts(repo, source, DateTime::UNIX_EPOCH.naive_utc(), now).await;
+    }
+}
+
+async fn index_posts(
+    repo: &Repository,
+    source: SearchSource,
+    since: NaiveDateTime,
+    now: DateTime<Utc>,
+) {
+    let keep_personal_data_since = (now - personal_data_retention()).naive_utc();
+    let mut after = (since, Uuid::nil());
+    let (mut indexed, mut failed) = (0, 0);
+
+    loop {
+        let posts = match repo
+            .get_unindexed_posts(source, after, INDEX_BATCH_SIZE)
+            .await
+        {
+            Ok(posts) => posts,
+            Err(e) => {
+                log::error!("Failed to get the posts to index from {source:?}: {e}");
+                break;
+            }
+        };
+        let Some(last) = posts.last() else {
+            break;
+        };
+        after = (last.created_at, last.response_id);
+        let is_last_batch = posts.len() < INDEX_BATCH_SIZE as usize;
+
+        for post in &posts {
+            let ngrams = document_ngrams(post);
+            match repo
+                .index_search_document(post, &ngrams, post.created_at >= keep_personal_data_since)
+                .await
+            {
+                Ok(true) => indexed += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    log::error!("Failed to index post {}: {e}", post.response_id);
+                    failed += 1;
+                }
+            }
+        }
+        if is_last_batch {
+            break;
+        }
+    }
+
+    log::info!("Indexed {indexed} posts from {source:?} for search, {failed} failed");
+}
+
+/// Bi-grams of the body, and of the title for the first response of a thread
+fn document_ngrams(post: &UnindexedPost) -> Vec<String> {
+    let mut ngrams = search_ngrams(&post.body);
+    if post.res_order == 1 {
+        ngrams.extend(search_ngrams(&post.thread_title));
+        ngrams.sort();
+        ngrams.dedup();
+    }
+    ngrams
+}
@@ -1,3 +1,4 @@
+mod moderation_rules;
 mod persistence;
 mod shutdown;
 mod subscriber;
@@ -5,15 +6,13 @@ mod token_backup;
 
 use std::env;
 
-use aws_sdk_s3::{
-    Client,
-    config::{Credentials, Region},
-};
 use eddist_core::{
+    archive_storage::archive_storage_from_env,
     tracing::init_tracing,
     utils::{is_authed_token_backup_enabled, is_prod},
 };
 use tokio::join;
+use tracing::warn;
 
 use subscriber::SubRepository;
 
@@ -31,32 +30,20 @@ async fn main() -> anyhow::Result<()> {
 
     tokio::spawn(shutdown::run_shutdown_server(ctrl_c_tx));
 
-    let (s3_client, s3_bucket_name) = if is_authed_token_backup_enabled() {
-        let bucket_name = env::var("S3_BUCKET_NAME")?;
-        let account_id = env::var("R2_ACCOUNT_ID")?;
-        let access_key = env::var("S3_ACCESS_KEY")?;
-        let secret_key = env::var("S3_ACCESS_SECRET_KEY")?;
-        let endpoint = format!("https://{}.r2.cloudflarestorage.com", account_id.trim());
-        let creds = Credentials::new(access_key.trim(), secret_key.trim(), None, None, "custom");
-        let config = aws_sdk_s3::Config::builder()
-            .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
-            .credentials_provider(creds)
-            .region(Region::new("auto"))
-            .endpoint_url(endpoint)
-            .build();
-        (
-            Some(Client::from_conf(config)),
-            Some(bucket_name.trim().to_string()),
-        )
+    let backup_storage = if is_authed_token_backup_enabled() {
+        Some(archive_storage_from_env()?)
     } else {
-        (None, None)
+        None
     };
 
-    let db_pool = if is_authed_token_backup_enabled() {
-        let database_url = env::var("DATABASE_URL")?;
-        Some(sqlx::MySqlPool::connect(&database_url).await?)
-    } else {
-        None
+    // Used by the token backup and the moderation rules
+    let db_pool = match env::var("DATABASE_URL") {
+        Ok(database_url) => Some(sqlx::MySqlPool::connect(&database_url).await?),
+        Err(e) if is_authed_token_backup_enabled() => return Err(e.into()),
+        Err(_) => {
+            warn!("DATABASE_URL is not set, the moderation rules are disabled");
+            None
+        }
     };
 
     let client = redis::Client::open(env::var("REDIS_URL").unwrap())?;
@@ -67,8 +54,7 @@ async fn main() -> anyhow::Result<()> {
         pubsub_conn,
         conn.clone(),
         ctrl_c_sub_sub,
-        s3_client,
-        s3_bucket_name,
+        backup_storage,
         db_pool,
     );
 
@@ -0,0 +1,329 @@
+use chrono::{DateTime, Duration, Utc};
+use eddist_core::{
+    domain::{
+        moderation_rule::{ModerationRule, ModerationRuleAction},
+        pubsub_repository::{CreatingRes, CreatingThread, ModerationResult},
+        res::ResView,
+    },
+    redis_keys::{
+        authed_token_internal_level_cap_key, authed_token_suspended_key, thread_cache_key,
+        unsafe_threads_key,
+    },
+};
+use redis::{AsyncCommands, Cmd};
+use sqlx::MySqlPool;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// The post a moderation result belongs to
+pub struct ModeratedPost {
+    pub board_id: Uuid,
+    pub thread_id: Uuid,
+    pub response_id: Uuid,
+    pub authed_token_id: Uuid,
+}
+
+impl From<&CreatingRes> for ModeratedPost {
+    fn from(res: &CreatingRes) -> Self {
+        Self {
+            board_id: res.board_id,
+            thread_id: res.thread_id,
+            response_id: res.id,
+            authed_token_id: res.authed_token_id,
+        }
+    }
+}
+
+impl From<&CreatingThread> for ModeratedPost {
+    fn from(th: &CreatingThread) -> Self {
+        Self {
+            board_id: th.board_id,
+            thread_id: th.thread_id,
+            response_id: th.response_id,
+            authed_token_id: th.authed_token_id,
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionModerationRule {
+    id: Uuid,
+    name: String,
+    board_id: Option<Uuid>,
+    category: String,
+    threshold: f64,
+    action: String,
+    duration_seconds: u64,
+    internal_level: u32,
+    enabled: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SelectionResponseLine {
+    author_name: String,
+    mail: String,
+    body: String,
+    created_at: DateTime<Utc>,
+    author_id: String,
+    res_order: i32,
+    board_key: String,
+    default_name: String,
+    thread_number: i64,
+    title: String,
+}
+
+/// Runs the enabled moderation rules against a moderation result and records every action taken
+pub async fn apply_moderation_rules(
+    pool: &MySqlPool,
+    conn: &mut redis::aio::ConnectionManager,
+    post: ModeratedPost,
+    result: &ModerationResult,
+) -> anyhow::Result<()> {
+    let rules = get_rules(pool, post.board_id).await?;
+
+    let mut thread_number = None;
+    for rule in rules {
+        let Some((category, score)) = rule.matched_score(post.board_id, result) else {
+            continue;
+        };
+
+        let thread_number = match thread_number {
+            Some(n) => n,
+            None => {
+                let n = get_thread_number(pool, post.thread_id).await?;
+                thread_number = Some(n);
+                n
+            }
+        };
+
+        let applied = match rule.action {
+            ModerationRuleAction::AboneResponse => abone_response(pool, conn, &post).await,
+            ModerationRuleAction::SuspendToken => conn
+                .set_ex::<_, _, ()>(
+                    authed_token_suspended_key(&post.authed_token_id.to_string()),
+                    "1",
+                    rule.duration_seconds,
+                )
+                .await
+                .map_err(anyhow::Error::from),
+            ModerationRuleAction::LowerInternalLevel => conn
+                .set_ex::<_, _, ()>(
+                    authed_token_internal_level_cap_key(&post.authed_token_id.to_string()),
+                    rule.internal_level,
+                    rule.duration_seconds,
+                )
+                .await
+                .map_err(anyhow::Error::from),
+            ModerationRuleAction::MarkThreadUnsafe => conn
+                .sadd::<_, _, ()>(unsafe_threads_key(post.board_id), thread_number)
+                .await
+                .map_err(anyhow::Error::from),
+        };
+        if let Err(e) = applied {
+            error!(
+                rule_id = rule.id.to_string().as_str(),
+                action = rule.action.as_str(),
+                error = e.to_string().as_str(),
+                "Failed to apply moderation rule"
+            );
+            continue;
+        }
+
+        info!(
+            rule_id = rule.id.to_string().as_str(),
+            action = rule.action.as_str(),
+            response_id = post.response_id.to_string().as_str(),
+            category = category.as_str(),
+            score,
+            "Moderation rule applied"
+        );
+        if let Err(e) = record_action(pool, &rule, &post, thread_number, &category, score).await {
+            error!(
+                rule_id = rule.id.to_string().as_str(),
+                error = e.to_string().as_str(),
+                "Failed to record moderation action"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn get_rules(pool: &MySqlPool, board_id: Uuid) -> anyhow::Result<Vec<ModerationRule>> {
+    let selections = sqlx::query_as::<_, SelectionModerationRule>(
+        r#"
+        SELECT
+            id,
+            name,
+            board_id,
+            category,
+            threshold,
+            action,
+            duration_seconds,
+            internal_level,
+            enabled,
+            created_at,
+            updated_at
+        FROM moderation_rules
+        WHERE enabled = 1 AND (board_id IS NULL OR board_id = ?)
+        "#,
+    )
+    .bind(board_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(selections
+        .into_iter()
+        .filter_map(|x| {
+            let action = x
+                .action
+                .parse()
+                .inspect_err(|e| warn!("Skipping moderation rule {}: {e}", x.id))
+                .ok()?;
+            Some(ModerationRule {
+                id: x.id,
+                name: x.name,
+                board_id: x.board_id,
+                category: x.category,
+                threshold: x.threshold,
+                action,
+                duration_seconds: x.duration_seconds,
+                internal_level: x.internal_level,
+                enabled: x.enabled,
+                created_at: x.created_at,
+                updated_at: x.updated_at,
+            })
+        })
+        .collect())
+}
+
+async fn get_thread_number(pool: &MySqlPool, thread_id: Uuid) -> anyhow::Result<u64> {
+    let thread_number =
+        sqlx::query_scalar::<_, i64>("SELECT thread_number FROM threads WHERE id = ?")
+            .bind(thread_id)
+            .fetch_one(pool)
+            .await?;
+    Ok(thread_number as u64)
+}
+
+/// Marks the response as aboned in the database and rewrites its line in the dat cache
+async fn abone_response(
+    pool: &MySqlPool,
+    conn: &mut redis::aio::ConnectionManager,
+    post: &ModeratedPost,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE responses SET is_abone = 1 WHERE id = ?")
+        .bind(post.response_id)
+        .execute(pool)
+        .await?;
+    sqlx::query("UPDATE search_documents SET is_abone = 1 WHERE response_id = ?")
+        .bind(post.response_id)
+        .execute(pool)
+        .await?;
+
+    let res = sqlx::query_as::<_, SelectionResponseLine>(
+        r#"
+        SELECT
+            r.author_name AS author_name,
+            r.mail AS mail,
+            r.body AS body,
+            r.created_at AS created_at,
+            r.author_id AS author_id,
+            r.res_order AS res_order,
+            b.board_key AS board_key,
+            b.default_name AS default_name,
+            t.thread_number AS thread_number,
+            t.title AS title
+        FROM responses AS r
+            JOIN threads AS t ON r.thread_id = t.id
+            JOIN boards AS b ON r.board_id = b.id
+        WHERE r.id = ?
+        "#,
+    )
+    .bind(post.response_id)
+    .fetch_one(pool)
+    .await?;
+
+    let line = ResView {
+        author_name: res.author_name,
+        mail: res.mail,
+        body: res.body,
+        created_at: res.created_at,
+        author_id: res.author_id,
+        is_abone: true,
+    }
+    .get_sjis_bytes(
+        &res.default_name,
+        (res.res_order == 1).then_some(res.title.as_str()),
+    );
+
+    // The cache may already be gone (e.g. archived), and a response posted while it was
+    // missing carries the placeholder order, so the database is enough in those cases
+    if !(1..10000).contains(&res.res_order) {
+        return Ok(());
+    }
+    let key = thread_cache_key(&res.board_key, res.thread_number as u64);
+    if conn.llen::<_, i64>(&key).await? >= res.res_order as i64 {
+        conn.send_packed_command(&Cmd::lset(
+            key,
+            res.res_order as isize - 1,
+            line.get_inner(),
+        ))
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn record_action(
+    pool: &MySqlPool,
+    rule: &ModerationRule,
+    post: &ModeratedPost,
+    thread_number: u64,
+    category: &str,
+    score: f64,
+) -> anyhow::Result<()> {
+    let now = Utc::now();
+    let expires_at = rule
+        .action
+        .is_timed()
+        .then(|| now + Duration::seconds(rule.duration_seconds as i64));
+
+    sqlx::query(
+        r#"
+        INSERT INTO moderation_action_logs
+            (
+                id,
+                rule_id,
+                action,
+                board_id,
+                thread_number,
+                response_id,
+                authed_token_id,
+                category,
+                score,
+                expires_at,
+                created_at
+            )
+        VALUES
+            (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(Uuid::now_v7())
+    .bind(rule.id)
+    .bind(rule.action.as_str())
+    .bind(post.board_id)
+    .bind(thread_number)
+    .bind(post.response_id)
+    .bind(post.authed_token_id)
+    .bind(category)
+    .bind(score)
+    .bind(expires_at)
+    .bind(now)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
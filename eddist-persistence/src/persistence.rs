@@ -182,6 +182,7 @@ async fn insert_multiple_res(
                     authed_token_id,
                     created_at,
                     client_info,
+                    is_abone,
                     res_order
                 )",
         );
@@ -200,6 +201,7 @@ async fn insert_multiple_res(
                 .push_bind(res.authed_token_id)
                 .push_bind(res.created_at)
                 .push_bind(client_info)
+                .push_bind(res.is_abone)
                 .push_bind(res.res_order);
         });
 
@@ -236,8 +238,9 @@ async fn insert_multiple_res(
             // query which is updating to responses_count, last_modified_at and active
             // response_count is calculated by select count(*) from responses where thread_id = ?
             // active is calculated response_count <= 1000, unless the thread was already
-            // archived (in which case it must stay inactive). last_modified_at only moves
-            // forward, so it can't be rewound by replaying older cached responses.
+            // archived or stopped by an admin (in which case it must stay inactive).
+            // last_modified_at only moves forward, so it can't be rewound by replaying older
+            // cached responses.
             // NOTE: this query is not crusial, so we can ignore the error
             let query = query!(
                 r#"
@@ -249,7 +252,7 @@ async fn insert_multiple_res(
             SET response_count = (SELECT cnt FROM response_count),
                 last_modified_at = GREATEST(last_modified_at, ?),
                 active = CASE
-                    WHEN archived = 1 THEN 0
+                    WHEN archived = 1 OR active = 0 THEN 0
                     ELSE (SELECT cnt FROM response_count) <= 1000
                 END
             WHERE id = ?;
@@ -286,8 +289,9 @@ async fn insert_single_res(
             authed_token_id,
             created_at,
             client_info,
+            is_abone,
             res_order
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         res.id,
         res.name,
@@ -300,6 +304,7 @@ async fn insert_single_res(
         res.authed_token_id,
         res.created_at,
         client_info,
+        res.is_abone,
         res.res_order,
     )
     .execute(&mut **tx)
@@ -1,24 +1,35 @@
-use std::env;
+use std::{env, sync::Arc};
 
 use eddist_core::{
+    archive_storage::ArchiveStorage,
     domain::pubsub_repository::{
-        CHANNEL_AUTH_TOKEN_REVOKED, CHANNEL_AUTH_TOKEN_SUCCEEDED, CHANNEL_PUBSUB_ITEM, PubSubItem,
+        CHANNEL_AUTH_TOKEN_REVOKED, CHANNEL_AUTH_TOKEN_SUCCEEDED, CHANNEL_PUBSUB_ITEM,
+        ModerationResult, PubSubItem,
+    },
+    proto::{
+        decode_auth_token_revoked, decode_auth_token_succeeded, decode_creating_res,
+        decode_creating_thread,
+    },
+    redis_keys::{
+        CHANNEL_RES_MODERATED, CHANNEL_THREAD_CREATED, CHANNEL_THREAD_MODERATED,
+        DB_FAILED_CACHE_RES_KEY, unsafe_threads_key,
     },
-    proto::{decode_auth_token_revoked, decode_auth_token_succeeded, decode_creating_thread},
-    redis_keys::{CHANNEL_THREAD_CREATED, DB_FAILED_CACHE_RES_KEY, unsafe_threads_key},
 };
 use futures::StreamExt;
 use redis::AsyncCommands;
 use tokio::{select, time::sleep};
 use tracing::{error, info, warn};
 
-use crate::token_backup::{backup_token, remove_token_backup};
+use crate::{
+    moderation_rules::{ModeratedPost, apply_moderation_rules},
+    token_backup::{backup_token, remove_token_backup},
+};
 
 pub struct RedisSubRepository {
     pubsub_conn: redis::aio::PubSub,
     conn: redis::aio::ConnectionManager,
     cancel: tokio::sync::broadcast::Receiver<()>,
-    s3_bucket: Option<(aws_sdk_s3::Client, String)>,
+    backup_storage: Option<Arc<dyn ArchiveStorage>>,
     db_pool: Option<sqlx::MySqlPool>,
 }
 
@@ -27,15 +38,14 @@ impl RedisSubRepository {
         pubsub_conn: redis::aio::PubSub,
         conn: redis::aio::ConnectionManager,
         cancel: tokio::sync::broadcast::Receiver<()>,
-        s3_client: Option<aws_sdk_s3::Client>,
-        s3_bucket_name: Option<String>,
+        backup_storage: Option<Arc<dyn ArchiveStorage>>,
         db_pool: Option<sqlx::MySqlPool>,
     ) -> Self {
         Self {
             pubsub_conn,
             conn,
             cancel,
-            s3_bucket: s3_client.zip(s3_bucket_name),
+            backup_storage,
             db_pool,
         }
     }
@@ -54,12 +64,16 @@ impl SubRepository for RedisSubRepository {
     async fn subscribe(&mut self) -> Result<(), anyhow::Error> {
         let mut error_count = 0u32;
         let redis_url = env::var("REDIS_URL").unwrap();
-        let backup_enabled = self.s3_bucket.is_some();
+        let backup_enabled = self.backup_storage.is_some();
         let mut channels = vec![CHANNEL_PUBSUB_ITEM, CHANNEL_THREAD_CREATED];
         if backup_enabled {
             channels.push(CHANNEL_AUTH_TOKEN_SUCCEEDED);
             channels.push(CHANNEL_AUTH_TOKEN_REVOKED);
         }
+        if self.db_pool.is_some() {
+            channels.push(CHANNEL_RES_MODERATED);
+            channels.push(CHANNEL_THREAD_MODERATED);
+        }
 
         loop {
             let subscribe_result = self.pubsub_conn.subscribe(channels.as_slice()).await;
@@ -135,6 +149,24 @@ impl SubRepository for RedisSubRepository {
     }
 }
 
+fn spawn_moderation_rules(
+    pool: &sqlx::MySqlPool,
+    conn: &redis::aio::ConnectionManager,
+    post: ModeratedPost,
+    result: ModerationResult,
+) {
+    let pool = pool.clone();
+    let mut conn = conn.clone();
+    tokio::spawn(async move {
+        if let Err(e) = apply_moderation_rules(&pool, &mut conn, post, &result).await {
+            error!(
+                error = e.to_string().as_str(),
+                "Failed to apply moderation rules"
+            );
+        }
+    });
+}
+
 impl RedisSubRepository {
     /// Returns Ok(true) for shutdown, Ok(false) for connection lost.
     async fn handle_messages(&mut self) -> Result<bool, anyhow::Error> {
@@ -224,16 +256,13 @@ impl RedisSubRepository {
                         }
                     };
                     let token_id = event.authed_token_id;
-                    if let (Some(pool), Some((client, bucket_name))) =
-                        (self.db_pool.as_ref(), self.s3_bucket.as_ref())
+                    if let (Some(storage), Some(pool)) =
+                        (self.backup_storage.as_ref(), self.db_pool.as_ref())
                     {
                         let pool = pool.clone();
-                        let client = client.clone();
-                        let bucket_name = bucket_name.clone();
+                        let storage = storage.clone();
                         tokio::spawn(async move {
-                            if let Err(e) =
-                                backup_token(&pool, &client, &bucket_name, token_id).await
-                            {
+                            if let Err(e) = backup_token(&pool, storage.as_ref(), token_id).await {
                                 warn!("Failed to backup token {token_id}: {e}");
                             }
                         });
@@ -279,6 +308,74 @@ impl RedisSubRepository {
                         }
                     }
                 }
+                ch if ch == CHANNEL_RES_MODERATED => {
+                    let payload = match msg.get_payload::<Vec<u8>>() {
+                        Ok(p) => p,
+                        Err(e) => {
+                            error!(
+                                error = e.to_string().as_str(),
+                                "Failed to get res_moderated payload"
+                            );
+                            continue;
+                        }
+                    };
+
+                    let event = match decode_creating_res(&payload) {
+                        Ok(e) => e,
+                        Err(e) => {
+                            error!(
+                                error = e.to_string().as_str(),
+                                "Failed to decode CreatingRes"
+                            );
+                            continue;
+                        }
+                    };
+
+                    if let (Some(pool), Some(result)) =
+                        (self.db_pool.as_ref(), event.moderation_result.as_ref())
+                    {
+                        spawn_moderation_rules(
+                            pool,
+                            &self.conn,
+                            ModeratedPost::from(&event),
+                            result.clone(),
+                        );
+                    }
+                }
+                ch if ch == CHANNEL_THREAD_MODERATED => {
+                    let payload = match msg.get_payload::<Vec<u8>>() {
+                        Ok(p) => p,
+                        Err(e) => {
+                            error!(
+                                error = e.to_string().as_str(),
+                                "Failed to get thread_moderated payload"
+                            );
+                            continue;
+                        }
+                    };
+
+                    let event = match decode_creating_thread(&payload) {
+                        Ok(e) => e,
+                        Err(e) => {
+                            error!(
+                                error = e.to_string().as_str(),
+                                "Failed to decode CreatingThread"
+                            );
+                            continue;
+                        }
+                    };
+
+                    if let (Some(pool), Some(result)) =
+                        (self.db_pool.as_ref(), event.moderation_result.as_ref())
+                    {
+                        spawn_moderation_rules(
+                            pool,
+                            &self.conn,
+                            ModeratedPost::from(&event),
+                            result.clone(),
+                        );
+                    }
+                }
                 ch if ch == CHANNEL_AUTH_TOKEN_REVOKED => {
                     let payload = match msg.get_payload::<Vec<u8>>() {
                         Ok(p) => p,
@@ -302,13 +399,10 @@ impl RedisSubRepository {
                         }
                     };
                     let token_id = event.authed_token_id;
-                    if let Some((client, bucket_name)) = self.s3_bucket.as_ref() {
-                        let client = client.clone();
-                        let bucket_name = bucket_name.clone();
+                    if let Some(storage) = self.backup_storage.as_ref() {
+                        let storage = storage.clone();
                         tokio::spawn(async move {
-                            if let Err(e) =
-                                remove_token_backup(&client, &bucket_name, token_id).await
-                            {
+                            if let Err(e) = remove_token_backup(storage.as_ref(), token_id).await {
                                 warn!("Failed to remove token backup {token_id}: {e}");
                             }
                         });
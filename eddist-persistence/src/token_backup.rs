@@ -1,11 +1,12 @@
-use aws_sdk_s3::{Client, primitives::ByteStream};
-use eddist_core::domain::authed_token_backup::{AUTHED_TOKENS_S3_PREFIX, AuthedTokenBackup};
+use eddist_core::{
+    archive_storage::ArchiveStorage,
+    domain::authed_token_backup::{AUTHED_TOKENS_S3_PREFIX, AuthedTokenBackup},
+};
 use uuid::Uuid;
 
 pub async fn backup_token(
     pool: &sqlx::MySqlPool,
-    client: &Client,
-    bucket_name: &str,
+    storage: &dyn ArchiveStorage,
     token_id: Uuid,
 ) -> anyhow::Result<()> {
     let backup = sqlx::query_as!(
@@ -31,27 +32,16 @@ pub async fn backup_token(
     .await?;
 
     let bytes = serde_json::to_vec(&backup)?;
-    client
-        .put_object()
-        .bucket(bucket_name)
-        .key(format!("{AUTHED_TOKENS_S3_PREFIX}/{token_id}.json"))
-        .body(ByteStream::from(bytes))
-        .send()
-        .await?;
-
-    Ok(())
+    storage
+        .put(&format!("{AUTHED_TOKENS_S3_PREFIX}/{token_id}.json"), bytes)
+        .await
 }
 
 pub async fn remove_token_backup(
-    client: &Client,
-    bucket_name: &str,
+    storage: &dyn ArchiveStorage,
     token_id: Uuid,
 ) -> anyhow::Result<()> {
-    client
-        .delete_object()
-        .bucket(bucket_name)
-        .key(format!("{AUTHED_TOKENS_S3_PREFIX}/{token_id}.json"))
-        .send()
-        .await?;
-    Ok(())
+    storage
+        .delete(&format!("{AUTHED_TOKENS_S3_PREFIX}/{token_id}.json"))
+        .await
 }
@@ -0,0 +1,72 @@
+//! Compares the partial dat fetch against assembling the whole dat and slicing it, for a
+//! 1000-response thread whose client already holds all but the last few responses.
+
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use eddist::services::dat_line_index::{DatLineIndex, assemble_lines};
+
+const RESPONSE_COUNT: usize = 1000;
+
+fn thread_lines() -> Vec<Vec<u8>> {
+    (0..RESPONSE_COUNT)
+        .map(|idx| {
+            format!(
+                "名無しさん<><>2024/06/28(金) 12:34:56.789 ID:abcd1234<> {} <>{}\n",
+                "テスト本文です<br>".repeat(idx % 20 + 1),
+                if idx == 0 {
+                    "テストスレッド"
+                } else {
+                    ""
+                }
+            )
+        })
+        .map(|line| encoding_rs::SHIFT_JIS.encode(&line).0.into_owned())
+        .collect()
+}
+
+fn bench_dat_retrieval(c: &mut Criterion) {
+    let lines = thread_lines();
+    let index = DatLineIndex::from_lengths(lines.iter().map(Vec::len));
+    let total_len = lines.iter().map(Vec::len).sum::<usize>();
+
+    let mut group = c.benchmark_group("dat_retrieval");
+    for known_responses in [0, 900, 990, 999] {
+        // Clients request from the last byte they hold, to check that it is still `\n`
+        let offset = lines[..known_responses]
+            .iter()
+            .map(Vec::len)
+            .sum::<usize>()
+            .saturating_sub(1);
+
+        group.bench_with_input(
+            BenchmarkId::new("full_assembly", known_responses),
+            &offset,
+            |b, &offset| {
+                b.iter(|| {
+                    let mut raw = Vec::with_capacity(total_len);
+                    for line in &lines {
+                        raw.extend_from_slice(line);
+                    }
+                    black_box(raw.get(offset..).unwrap_or_default().to_vec())
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("indexed_partial", known_responses),
+            &offset,
+            |b, &offset| {
+                b.iter(|| {
+                    let (line, start, _) = index.find_line(black_box(offset)).unwrap();
+                    black_box(assemble_lines(&lines[line..], offset - start))
+                })
+            },
+        );
+    }
+    group.finish();
+
+    c.bench_function("dat_line_index_build", |b| {
+        b.iter(|| DatLineIndex::from_lengths(black_box(&lines).iter().map(Vec::len)))
+    });
+}
+
+criterion_group!(benches, bench_dat_retrieval);
+criterion_main!(benches);
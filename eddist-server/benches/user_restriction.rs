@@ -0,0 +1,128 @@
+//! Compares looking up the matching user restriction rule through the rule index against
+//! scanning the rules, with a blocklist-sized set of CIDR, ASN and user agent rules.
+
+use chrono::Utc;
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use eddist_core::domain::{
+    user_restriction::{
+        CompiledRestrictionRule, RestrictionAction, RestrictionClient, RestrictionRuleType,
+        RestrictionTarget, UserRestrictionRule,
+    },
+    user_restriction_index::RestrictionRuleIndex,
+};
+use uuid::Uuid;
+
+const CIDR_RULES: u32 = 20_000;
+const ASN_RULES: u32 = 1_000;
+const USER_AGENT_RULES: u32 = 500;
+
+fn rule(rule_type: RestrictionRuleType, rule_value: String) -> UserRestrictionRule {
+    UserRestrictionRule {
+        id: Uuid::now_v7(),
+        name: rule_value.clone(),
+        rule_type,
+        rule_value,
+        board_keys: None,
+        targets: RestrictionTarget::ALL.to_vec(),
+        action: RestrictionAction::Block,
+        captcha_provider: None,
+        slow_down_seconds: 0,
+        expires_at: None,
+        subscription_id: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        created_by_email: "admin@example.com".to_string(),
+    }
+}
+
+fn rules() -> Vec<UserRestrictionRule> {
+    let cidrs = (0..CIDR_RULES).map(|idx| {
+        rule(
+            RestrictionRuleType::IPCidr,
+            format!("10.{}.{}.0/24", idx / 256, idx % 256),
+        )
+    });
+    let asns = (0..ASN_RULES).map(|idx| rule(RestrictionRuleType::Asn, (64_512 + idx).to_string()));
+    let user_agents = (0..USER_AGENT_RULES)
+        .map(|idx| rule(RestrictionRuleType::UserAgent, format!("BadBot{idx}/")));
+    cidrs.chain(asns).chain(user_agents).collect()
+}
+
+fn bench_user_restriction(c: &mut Criterion) {
+    let rules = rules();
+    let compiled = rules
+        .iter()
+        .map(|rule| rule.compile().unwrap())
+        .collect::<Vec<CompiledRestrictionRule>>();
+    let index = RestrictionRuleIndex::new(compiled.clone());
+
+    let clients = [
+        (
+            "miss",
+            "192.0.2.1",
+            1,
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64)",
+        ),
+        (
+            "cidr_hit",
+            "10.78.31.5",
+            1,
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64)",
+        ),
+        (
+            "user_agent_hit",
+            "192.0.2.1",
+            1,
+            "Mozilla/5.0 BadBot499/1.0",
+        ),
+    ];
+
+    let mut group = c.benchmark_group("user_restriction");
+    for (name, ip, asn, ua) in clients {
+        group.bench_with_input(
+            BenchmarkId::new("reparsing_scan", name),
+            &(ip, asn, ua),
+            |b, &(ip, asn, ua)| {
+                b.iter(|| {
+                    black_box(
+                        rules
+                            .iter()
+                            .find(|rule| rule.matches(ip, asn, ua))
+                            .map(|rule| rule.id),
+                    )
+                })
+            },
+        );
+
+        let client = RestrictionClient::new(ip, asn, ua);
+        group.bench_with_input(
+            BenchmarkId::new("compiled_scan", name),
+            &client,
+            |b, client| {
+                b.iter(|| {
+                    black_box(
+                        compiled
+                            .iter()
+                            .find(|rule| {
+                                rule.matches(client, Some("news"), RestrictionTarget::Response)
+                            })
+                            .map(|rule| rule.rule.id),
+                    )
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("index", name), &client, |b, client| {
+            b.iter(|| {
+                black_box(
+                    index
+                        .find(client, Some("news"), RestrictionTarget::Response)
+                        .map(|rule| rule.rule.id),
+                )
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_user_restriction);
+criterion_main!(benches);
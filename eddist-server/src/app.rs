@@ -4,11 +4,14 @@ use axum::{
     Extension, Json, Router,
     body::{Body, Bytes},
     extract::{MatchedPath, Path, State},
-    response::{IntoResponse, Redirect, Response},
+    response::{IntoResponse, Response},
     routing::{get, post},
 };
 use axum_prometheus::PrometheusMetricLayer;
-use eddist_core::domain::board::{BoardInfo, validate_board_key};
+use eddist_core::{
+    client_addr::ClientAddrConfig,
+    domain::board::{BoardInfo, validate_board_key},
+};
 use handlebars::Handlebars;
 use http::{HeaderMap, Request, StatusCode};
 use tower_http::{
@@ -19,6 +22,7 @@ use tracing::{Span, info_span};
 
 use crate::{
     middleware::{
+        client_addr::client_addr_middleware,
         not_found_rate_limit::{NotFoundPenaltyCache, not_found_rate_limit_middleware},
         user_restriction::user_restriction_middleware,
     },
@@ -27,6 +31,7 @@ use crate::{
         bbs_repository::BbsRepositoryImpl,
         idp_repository::IdpRepositoryImpl,
         notice_repository::NoticeRepositoryImpl,
+        search_repository::SearchRepositoryImpl,
         stats_repository::StatsRepositoryImpl,
         terms_repository::TermsRepositoryImpl,
         user_repository::UserRepositoryImpl,
@@ -36,14 +41,21 @@ use crate::{
         auth_code::{get_auth_code, post_auth_code},
         bbs_cgi::post_bbs_cgi,
         dat_routing::{get_dat_txt, get_kako_dat_txt},
+        event_stream::{get_board_stream, get_thread_stream},
+        feed::{get_board_feed, get_thread_feed},
+        json_api::{get_api_thread, get_api_threads},
         notice::{get_latest_notices, get_notice_by_slug, get_notices_paginated},
         re_auth::{get_re_auth, post_re_auth},
+        read_cgi::{get_read_cgi, get_read_cgi_range},
         safe_mode::get_unsafe_thread_ids,
+        search::get_search,
         stats::get_stats,
         subject_list::{get_subject_txt, get_subject_txt_with_metadent},
         terms::get_terms,
+        trending::get_trending,
         user::user_routes,
     },
+    services::event_stream_hub::EventStreamHub,
     services::server_settings_cache::{ServerSettingKey, get_server_setting_bool},
     services::{
         AppService, AppServiceContainer,
@@ -66,10 +78,13 @@ pub struct AppState {
     pub notice_repo: NoticeRepositoryImpl,
     pub terms_repo: TermsRepositoryImpl,
     pub stats_repo: StatsRepositoryImpl,
+    pub search_repo: SearchRepositoryImpl,
     pub template_engine: Arc<Handlebars<'static>>,
     pub tinker_secret: String,
     pub redis_conn: redis::aio::ConnectionManager,
     pub not_found_penalty_cache: NotFoundPenaltyCache,
+    pub event_stream_hub: EventStreamHub,
+    pub client_addr_config: Arc<ClientAddrConfig>,
 }
 
 impl AppState {
@@ -252,6 +267,8 @@ pub fn create_app(app_state: AppState, conn_mgr: redis::aio::ConnectionManager)
         )
         .route("/{boardKey}/head.txt", get(get_head_txt))
         .route("/{boardKey}/SETTING.TXT", get(get_setting_txt))
+        .route("/{boardKey}/feed.atom", get(get_board_feed))
+        .route("/{boardKey}/{threadId}/feed.atom", get(get_thread_feed))
         .route("/{boardKey}/dat/{threadId}", get(get_dat_txt))
         .route(
             "/{boardKey}/kako/{th4}/{th5}/{threadId}",
@@ -264,10 +281,22 @@ pub fn create_app(app_state: AppState, conn_mgr: redis::aio::ConnectionManager)
         .route("/api/notices/{slug}", get(get_notice_by_slug))
         .route("/api/client-config", get(get_api_client_config))
         .route("/api/stats", get(get_stats))
+        .route("/api/search", get(get_search))
+        .route("/api/trending", get(get_trending))
         .route(
             "/api/{boardKey}/unsafe-thread-ids",
             get(get_unsafe_thread_ids),
         )
+        .route("/api/{boardKey}/threads", get(get_api_threads))
+        .route(
+            "/api/{boardKey}/threads/{threadNumber}",
+            get(get_api_thread),
+        )
+        .route("/api/{boardKey}/stream", get(get_board_stream))
+        .route(
+            "/api/{boardKey}/threads/{threadNumber}/stream",
+            get(get_thread_stream),
+        )
         .nest("/user", user_routes())
         .route(
             "/{boardKey}",
@@ -281,21 +310,10 @@ pub fn create_app(app_state: AppState, conn_mgr: redis::aio::ConnectionManager)
             "/{boardKey}/{threadId}",
             get(|| async move { Response::builder().status(404).body(Body::empty()).unwrap() }),
         )
-        .route(
-            "/test/read.cgi/{boardKey}/{threadId}",
-            get(
-                |Path((board_key, thread_id)): Path<(String, String)>| async move {
-                    Redirect::permanent(&format!("/{}/{}", board_key, thread_id))
-                },
-            ),
-        )
+        .route("/test/read.cgi/{boardKey}/{threadId}", get(get_read_cgi))
         .route(
             "/test/read.cgi/{boardKey}/{threadId}/{*pos}",
-            get(
-                |Path((board_key, thread_id, _)): Path<(String, String, String)>| async move {
-                    Redirect::permanent(&format!("/{}/{}", board_key, thread_id))
-                },
-            ),
+            get(get_read_cgi_range),
         );
 
     let app = app
@@ -368,8 +386,14 @@ pub fn create_app(app_state: AppState, conn_mgr: redis::aio::ConnectionManager)
 
     // Add 404 rate limit middleware as the outermost layer so penalized IPs are
     // rejected as cheaply as possible, before any other request processing.
+    let app = app.layer(axum::middleware::from_fn_with_state(
+        app_state.clone(),
+        not_found_rate_limit_middleware,
+    ));
+
+    // Resolve the client IP and ASN first, every layer inside reads them
     app.layer(axum::middleware::from_fn_with_state(
         app_state,
-        not_found_rate_limit_middleware,
+        client_addr_middleware,
     ))
 }
@@ -0,0 +1,297 @@
+use std::{collections::HashSet, fmt::Write, sync::OnceLock};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use regex::Regex;
+
+use super::{thread_list::ThreadList, thread_res_list::JsonThreadResList};
+
+static ENTITY_REGEX: OnceLock<Regex> = OnceLock::new();
+static ANCHOR_REGEX: OnceLock<Regex> = OnceLock::new();
+static TAG_REGEX: OnceLock<Regex> = OnceLock::new();
+
+/// Escapes text for XML element content and attribute values
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Renders the body of a dat line as safe HTML.
+///
+/// The body is already escaped except for `&`, which posters may use for character
+/// references, and `<br>`. Valid references are kept, any other markup is escaped, and
+/// `>>N` anchors are linked to the responses of the thread at `thread_url`.
+pub fn dat_body_to_html(body: &str, thread_url: &str) -> String {
+    let entity_re = ENTITY_REGEX.get_or_init(|| {
+        Regex::new(r"^&(?:[A-Za-z][A-Za-z0-9]*|#[0-9]+|#[Xx][0-9A-Fa-f]+);").unwrap()
+    });
+    let anchor_re =
+        ANCHOR_REGEX.get_or_init(|| Regex::new(r"&gt;&gt;([0-9]{1,4})(-[0-9]{1,4})?").unwrap());
+
+    let lines = body.split("<br>").map(|line| {
+        let mut escaped = String::with_capacity(line.len());
+        for (idx, c) in line.char_indices() {
+            match c {
+                '&' if entity_re.is_match(&line[idx..]) => escaped.push('&'),
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                _ => escaped.push(c),
+            }
+        }
+        anchor_re
+            .replace_all(&escaped, |caps: &regex::Captures| {
+                format!(
+                    r#"<a href="{}/{}{}">{}</a>"#,
+                    escape_xml(thread_url),
+                    &caps[1],
+                    caps.get(2).map_or("", |m| m.as_str()),
+                    &caps[0]
+                )
+            })
+            .into_owned()
+    });
+    lines.collect::<Vec<_>>().join("<br />")
+}
+
+/// Drops the markup of a name field, e.g. the `<b>` around a trip
+pub fn strip_tags(text: &str) -> String {
+    let tag_re = TAG_REGEX.get_or_init(|| Regex::new(r"<[^>]*>").unwrap());
+    tag_re
+        .replace_all(text, "")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[derive(Debug, Clone)]
+pub struct AtomEntry {
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    pub published: Option<DateTime<Utc>>,
+    pub updated: DateTime<Utc>,
+    pub summary: Option<String>,
+    /// HTML, escaped when the feed is rendered
+    pub content_html: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AtomFeed {
+    pub id: String,
+    pub title: String,
+    pub self_link: String,
+    pub alternate_link: String,
+    pub author: String,
+    pub updated: DateTime<Utc>,
+    pub entries: Vec<AtomEntry>,
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+impl AtomFeed {
+    /// The newest threads of the board, leaving out those in `excluded_threads`
+    pub fn from_thread_list(
+        base_url: &str,
+        threads: &ThreadList,
+        excluded_threads: &HashSet<u64>,
+        limit: usize,
+    ) -> Self {
+        let board_key = &threads.board.board_key;
+        let board_url = format!("{base_url}/{board_key}/");
+
+        let mut thread_list = threads
+            .thread_list
+            .iter()
+            .filter(|th| !excluded_threads.contains(&(th.thread_number as u64)))
+            .collect::<Vec<_>>();
+        thread_list.sort_by_key(|th| std::cmp::Reverse(th.thread_number));
+        thread_list.truncate(limit);
+
+        let entries = thread_list
+            .into_iter()
+            .map(|th| {
+                let url = format!("{base_url}/test/read.cgi/{board_key}/{}/", th.thread_number);
+                AtomEntry {
+                    id: url.clone(),
+                    title: th.title.clone(),
+                    link: url,
+                    published: DateTime::from_timestamp(th.thread_number, 0),
+                    updated: th.last_modified_at,
+                    summary: Some(format!("{} responses", th.response_count)),
+                    content_html: None,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            id: board_url.clone(),
+            title: threads.board.name.clone(),
+            self_link: format!("{board_url}feed.atom"),
+            alternate_link: board_url,
+            author: threads.board.name.clone(),
+            updated: entries
+                .iter()
+                .map(|e| e.updated)
+                .max()
+                .unwrap_or(DateTime::UNIX_EPOCH),
+            entries,
+        }
+    }
+
+    /// The latest `limit` responses of the thread, newest first, leaving out aboned ones
+    pub fn from_thread_res_list(
+        base_url: &str,
+        board_key: &str,
+        board_name: &str,
+        thread: &JsonThreadResList,
+        limit: usize,
+    ) -> Self {
+        let thread_url = format!(
+            "{base_url}/test/read.cgi/{board_key}/{}",
+            thread.thread_number
+        );
+
+        let entries = thread
+            .responses
+            .iter()
+            .rev()
+            .filter(|res| !res.is_abone)
+            .take(limit)
+            .filter_map(|res| {
+                let created_at = res.created_at?;
+                let url = format!("{thread_url}/{}", res.order);
+                Some(AtomEntry {
+                    id: url.clone(),
+                    title: format!("{}: {}", res.order, strip_tags(&res.name)),
+                    link: url,
+                    published: Some(created_at),
+                    updated: created_at,
+                    summary: None,
+                    content_html: Some(dat_body_to_html(&res.body, &thread_url)),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            id: format!("{thread_url}/"),
+            title: strip_tags(&thread.title),
+            self_link: format!("{base_url}/{board_key}/{}/feed.atom", thread.thread_number),
+            alternate_link: format!("{thread_url}/"),
+            author: board_name.to_string(),
+            updated: entries
+                .iter()
+                .map(|e| e.updated)
+                .max()
+                .unwrap_or(DateTime::UNIX_EPOCH),
+            entries,
+        }
+    }
+
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::with_capacity(1024 + self.entries.len() * 512);
+        xml.push_str(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+        xml.push('\n');
+        xml.push_str(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+        let _ = write!(
+            xml,
+            r#"<id>{}</id><title>{}</title><link rel="self" href="{}"/><link rel="alternate" type="text/html" href="{}"/><updated>{}</updated><author><name>{}</name></author>"#,
+            escape_xml(&self.id),
+            escape_xml(&self.title),
+            escape_xml(&self.self_link),
+            escape_xml(&self.alternate_link),
+            format_time(self.updated),
+            escape_xml(&self.author),
+        );
+        for entry in &self.entries {
+            let _ = write!(
+                xml,
+                r#"<entry><id>{}</id><title>{}</title><link rel="alternate" type="text/html" href="{}"/>"#,
+                escape_xml(&entry.id),
+                escape_xml(&entry.title),
+                escape_xml(&entry.link),
+            );
+            if let Some(published) = entry.published {
+                let _ = write!(xml, "<published>{}</published>", format_time(published));
+            }
+            let _ = write!(xml, "<updated>{}</updated>", format_time(entry.updated));
+            if let Some(summary) = &entry.summary {
+                let _ = write!(xml, "<summary>{}</summary>", escape_xml(summary));
+            }
+            if let Some(content) = &entry.content_html {
+                let _ = write!(
+                    xml,
+                    r#"<content type="html">{}</content>"#,
+                    escape_xml(content)
+                );
+            }
+            xml.push_str("</entry>");
+        }
+        xml.push_str("</feed>\n");
+        xml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THREAD_URL: &str = "https://example.com/test/read.cgi/board/1719543845";
+
+    #[test]
+    fn test_dat_body_to_html() {
+        assert_eq!(
+            dat_body_to_html("first<br>&gt;&gt;1 thanks", THREAD_URL),
+            r#"first<br /><a href="https://example.com/test/read.cgi/board/1719543845/1">&gt;&gt;1</a> thanks"#
+        );
+        assert_eq!(
+            dat_body_to_html("&gt;&gt;2-5", THREAD_URL),
+            r#"<a href="https://example.com/test/read.cgi/board/1719543845/2-5">&gt;&gt;2-5</a>"#
+        );
+        // Character references are kept, bare ampersands and stray markup are escaped
+        assert_eq!(
+            dat_body_to_html("&#12354; & &amp; <script>", THREAD_URL),
+            "&#12354; &amp; &amp; &lt;script&gt;"
+        );
+    }
+
+    #[test]
+    fn test_strip_tags() {
+        assert_eq!(strip_tags("名無し</b>◆abcdef<b>"), "名無し◆abcdef");
+        assert_eq!(strip_tags("a&amp;b"), "a&b");
+    }
+
+    #[test]
+    fn test_to_xml_escapes_content() {
+        let updated = DateTime::from_timestamp(1719543845, 0).unwrap();
+        let feed = AtomFeed {
+            id: "https://example.com/board/".to_string(),
+            title: "A & B".to_string(),
+            self_link: "https://example.com/board/feed.atom".to_string(),
+            alternate_link: "https://example.com/board/".to_string(),
+            author: "board".to_string(),
+            updated,
+            entries: vec![AtomEntry {
+                id: format!("{THREAD_URL}/2"),
+                title: "2: 名無し".to_string(),
+                link: format!("{THREAD_URL}/2"),
+                published: Some(updated),
+                updated,
+                summary: None,
+                content_html: Some("a<br />b".to_string()),
+            }],
+        };
+
+        let xml = feed.to_xml();
+        assert!(xml.contains("<title>A &amp; B</title>"));
+        assert!(xml.contains("<updated>2024-06-28T03:04:05Z</updated>"));
+        assert!(xml.contains(r#"<content type="html">a&lt;br /&gt;b</content>"#));
+    }
+}
@@ -1,53 +1,81 @@
 use chrono::NaiveDateTime;
+use eddist_core::domain::ng_word::{
+    CompiledNgWord, NgWordAction, NgWordMatchType, NgWordMatcher, NgWordSubject, NgWordTarget,
+    NgWordVerdict,
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::res::{Res, ResState};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NgWord {
     pub id: Uuid,
     pub name: String,
     pub word: String,
+    // Defaults keep NG words cached before these fields existed readable
+    #[serde(default)]
+    pub match_type: NgWordMatchType,
+    #[serde(default = "default_targets")]
+    pub targets: Vec<NgWordTarget>,
+    #[serde(default)]
+    pub action: NgWordAction,
+    #[serde(default)]
+    pub penalty_seconds: u32,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
 
+fn default_targets() -> Vec<NgWordTarget> {
+    NgWordTarget::DEFAULT.to_vec()
+}
+
+impl NgWord {
+    pub fn compile(&self) -> Result<CompiledNgWord, regex::Error> {
+        CompiledNgWord::compile(
+            self.id,
+            &self.name,
+            &self.word,
+            self.match_type,
+            &self.targets,
+        )
+        .map(|compiled| compiled.with_action(self.action, self.penalty_seconds))
+    }
+}
+
 pub trait NgWordRestrictable {
-    fn contains_ng_word(&self, ng_words: &[NgWord]) -> bool;
+    fn ng_word_subject(&self) -> NgWordSubject;
+
+    fn ng_word_verdict(&self, matcher: &NgWordMatcher) -> Option<NgWordVerdict> {
+        if matcher.is_empty() {
+            return None;
+        }
+        matcher.verdict(&self.ng_word_subject())
+    }
 }
 
 impl NgWordRestrictable for str {
-    fn contains_ng_word(&self, ng_words: &[NgWord]) -> bool {
-        ng_words.iter().any(|ng_word| self.contains(&ng_word.word))
+    fn ng_word_subject(&self) -> NgWordSubject {
+        NgWordSubject::new(self, "", "", None)
     }
 }
 
 impl NgWordRestrictable for String {
-    fn contains_ng_word(&self, ng_words: &[NgWord]) -> bool {
-        ng_words.iter().any(|ng_word| self.contains(&ng_word.word))
+    fn ng_word_subject(&self) -> NgWordSubject {
+        self.as_str().ng_word_subject()
     }
 }
 
 impl<T: ResState> NgWordRestrictable for Res<T> {
-    fn contains_ng_word(&self, ng_words: &[NgWord]) -> bool {
-        ng_words.iter().any(|ng_word| {
-            self.body().contains(&ng_word.word)
-                || self.mail().contains(&ng_word.word)
-                || self.author_name().contains(&ng_word.word)
-        })
+    fn ng_word_subject(&self) -> NgWordSubject {
+        NgWordSubject::new(self.body(), self.author_name(), self.mail(), None)
     }
 }
 
 // for thread
 impl<T: ResState> NgWordRestrictable for (&Res<T>, String) {
-    fn contains_ng_word(&self, ng_words: &[NgWord]) -> bool {
+    fn ng_word_subject(&self) -> NgWordSubject {
         let (res, thread_name) = self;
-        ng_words.iter().any(|ng_word| {
-            res.body().contains(&ng_word.word)
-                || res.mail().contains(&ng_word.word)
-                || res.author_name().contains(&ng_word.word)
-                || thread_name.contains(&ng_word.word)
-        })
+        NgWordSubject::new(res.body(), res.author_name(), res.mail(), Some(thread_name))
     }
 }
@@ -0,0 +1,358 @@
+use eddist_core::utils::to_ja_datetime;
+use serde::Serialize;
+
+use super::{
+    feed::{dat_body_to_html, strip_tags},
+    thread_res_list::{JsonRes, JsonThreadResList},
+};
+
+/// Longest `l` view accepted, the same as the response limit of a thread
+const MAX_LAST_COUNT: usize = 1000;
+
+/// Characters of the first response used as the OpenGraph description
+const DESCRIPTION_CHARS: usize = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReadRangeKind {
+    All,
+    /// The latest N responses, e.g. `l50`
+    Last(usize),
+    /// Responses from `start` to `end` inclusive, e.g. `1-100`, `50-`, `-100` or `5`
+    Span {
+        start: usize,
+        end: Option<usize>,
+    },
+}
+
+/// Range of responses shown by read.cgi, given as the path after the thread number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadRange {
+    kind: ReadRangeKind,
+    /// Whether the first response is shown even when it is out of the range, turned off
+    /// by an `n` suffix as in `l50n`
+    include_first: bool,
+}
+
+impl ReadRange {
+    pub const ALL: Self = Self {
+        kind: ReadRangeKind::All,
+        include_first: true,
+    };
+
+    /// Parses the range of a read.cgi path, `None` if it is not a valid one. A single
+    /// response does not come with the first one unless asked for, the others do.
+    pub fn parse(pos: &str) -> Option<Self> {
+        let pos = pos.trim_matches('/');
+        if pos.is_empty() {
+            return Some(Self::ALL);
+        }
+        let (pos, no_first) = match pos.strip_suffix('n') {
+            Some(pos) => (pos, true),
+            None => (pos, false),
+        };
+        let parse_order = |s: &str| s.parse::<usize>().ok().filter(|&n| n > 0);
+
+        let (kind, single) = if pos.is_empty() {
+            (ReadRangeKind::All, false)
+        } else if let Some(count) = pos.strip_prefix('l') {
+            let count = parse_order(count)?.min(MAX_LAST_COUNT);
+            (ReadRangeKind::Last(count), false)
+        } else if let Some((start, end)) = pos.split_once('-') {
+            let start = if start.is_empty() {
+                1
+            } else {
+                parse_order(start)?
+            };
+            let end = if end.is_empty() {
+                None
+            } else {
+                Some(parse_order(end)?)
+            };
+            if end.is_some_and(|end| end < start) {
+                return None;
+            }
+            (ReadRangeKind::Span { start, end }, false)
+        } else {
+            let order = parse_order(pos)?;
+            (
+                ReadRangeKind::Span {
+                    start: order,
+                    end: Some(order),
+                },
+                true,
+            )
+        };
+
+        Some(Self {
+            kind,
+            include_first: !no_first && !single,
+        })
+    }
+
+    /// Whether the response is in the range itself, leaving aside the first response
+    fn in_range(&self, order: usize, response_count: usize) -> bool {
+        match self.kind {
+            ReadRangeKind::All => true,
+            ReadRangeKind::Last(count) => order + count > response_count,
+            ReadRangeKind::Span { start, end } => order >= start && end.is_none_or(|e| order <= e),
+        }
+    }
+
+    fn contains(&self, order: usize, response_count: usize) -> bool {
+        (order == 1 && self.include_first) || self.in_range(order, response_count)
+    }
+}
+
+/// A response as shown on the thread page, with the body already rendered as HTML
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadViewRes {
+    pub order: usize,
+    pub name: String,
+    pub mail: String,
+    pub date: String,
+    pub author_id: String,
+    pub body_html: String,
+    pub is_abone: bool,
+}
+
+impl ReadViewRes {
+    fn from_json_res(res: &JsonRes, thread_url: &str) -> Self {
+        if res.is_abone {
+            return Self {
+                order: res.order,
+                name: "あぼーん".to_string(),
+                mail: String::new(),
+                date: String::new(),
+                author_id: String::new(),
+                body_html: "あぼーん".to_string(),
+                is_abone: true,
+            };
+        }
+        Self {
+            order: res.order,
+            name: strip_tags(&res.name),
+            mail: strip_tags(&res.mail),
+            date: res.created_at.map(to_ja_datetime).unwrap_or_default(),
+            author_id: res.author_id.clone(),
+            body_html: dat_body_to_html(&res.body, thread_url),
+            is_abone: false,
+        }
+    }
+}
+
+/// Template data of the `read-cgi.get` page
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadView {
+    pub board_key: String,
+    pub board_name: String,
+    pub thread_number: u64,
+    pub title: String,
+    pub response_count: usize,
+    /// Canonical URL of the thread, without a range
+    pub thread_url: String,
+    /// Plain text of the first response for the OpenGraph description
+    pub description: String,
+    /// Hidden from crawlers, e.g. threads marked unsafe by the safe mode
+    pub noindex: bool,
+    pub responses: Vec<ReadViewRes>,
+    pub prev_range: Option<String>,
+    pub next_range: Option<String>,
+}
+
+impl ReadView {
+    pub fn new(
+        base_url: &str,
+        board_name: &str,
+        board_key: &str,
+        thread: &JsonThreadResList,
+        range: ReadRange,
+        noindex: bool,
+    ) -> Self {
+        let thread_url = format!(
+            "{base_url}/test/read.cgi/{board_key}/{}",
+            thread.thread_number
+        );
+        let count = thread.response_count;
+
+        let responses = thread
+            .responses
+            .iter()
+            .filter(|res| range.contains(res.order, count))
+            .map(|res| ReadViewRes::from_json_res(res, &thread_url))
+            .collect::<Vec<_>>();
+
+        let description = thread
+            .responses
+            .first()
+            .filter(|res| !res.is_abone)
+            .map(|res| {
+                let text = strip_tags(&res.body.replace("<br>", " "));
+                text.split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .chars()
+                    .take(DESCRIPTION_CHARS)
+                    .collect::<String>()
+            })
+            .unwrap_or_default();
+
+        // Pages of 100 responses around the shown ones
+        let shown = responses
+            .iter()
+            .map(|res| res.order)
+            .filter(|&order| range.in_range(order, count));
+        let (first_shown, last_shown) = (shown.clone().min(), shown.max());
+        let prev_range = first_shown
+            .filter(|&first| first > 1)
+            .map(|first| format!("{}-{}", first.saturating_sub(100).max(1), first - 1));
+        let next_range = last_shown
+            .filter(|&last| last < count)
+            .map(|last| format!("{}-{}", last + 1, last + 100));
+
+        Self {
+            board_key: board_key.to_string(),
+            board_name: board_name.to_string(),
+            thread_number: thread.thread_number,
+            title: strip_tags(&thread.title),
+            response_count: count,
+            thread_url: format!("{thread_url}/"),
+            description,
+            noindex,
+            responses,
+            prev_range,
+            next_range,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json_res(order: usize, author_id: &str, body: &str) -> JsonRes {
+        JsonRes {
+            order,
+            name: "名無し".to_string(),
+            mail: String::new(),
+            created_at: Some(chrono::Utc::now()),
+            author_id: author_id.to_string(),
+            body: body.to_string(),
+            is_abone: false,
+        }
+    }
+
+    fn thread(title: &str, responses: Vec<JsonRes>) -> JsonThreadResList {
+        JsonThreadResList {
+            thread_number: 1719545696,
+            title: title.to_string(),
+            response_count: responses.len(),
+            active: true,
+            metadent: None,
+            responses,
+        }
+    }
+
+    fn orders(range: &str, count: usize) -> Vec<usize> {
+        let range = ReadRange::parse(range).unwrap();
+        (1..=count)
+            .filter(|&order| range.contains(order, count))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_read_range() {
+        assert_eq!(ReadRange::parse(""), Some(ReadRange::ALL));
+        assert_eq!(ReadRange::parse("/"), Some(ReadRange::ALL));
+        assert_eq!(ReadRange::parse("abc"), None);
+        assert_eq!(ReadRange::parse("l0"), None);
+        assert_eq!(ReadRange::parse("10-5"), None);
+        assert_eq!(ReadRange::parse("0"), None);
+    }
+
+    #[test]
+    fn test_read_range_orders() {
+        assert_eq!(orders("", 3), vec![1, 2, 3]);
+        assert_eq!(orders("l2", 5), vec![1, 4, 5]);
+        assert_eq!(orders("l2n", 5), vec![4, 5]);
+        assert_eq!(orders("2-3", 5), vec![1, 2, 3]);
+        assert_eq!(orders("2-3n", 5), vec![2, 3]);
+        assert_eq!(orders("4-", 5), vec![1, 4, 5]);
+        assert_eq!(orders("-2", 5), vec![1, 2]);
+        assert_eq!(orders("3", 5), vec![3]);
+        assert_eq!(orders("n", 3), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_read_view() {
+        let thread = thread(
+            "テスト &amp; スレ",
+            vec![
+                json_res(1, "abcdefgh", "最初の&lt;書き込み&gt;"),
+                JsonRes {
+                    order: 2,
+                    name: String::new(),
+                    mail: String::new(),
+                    created_at: None,
+                    author_id: String::new(),
+                    body: String::new(),
+                    is_abone: true,
+                },
+                json_res(3, "ijklmnop", "&gt;&gt;1 乙"),
+            ],
+        );
+
+        let view = ReadView::new(
+            "https://example.com",
+            "Board",
+            "board",
+            &thread,
+            ReadRange::parse("2-3n").unwrap(),
+            false,
+        );
+        assert_eq!(view.title, "テスト & スレ");
+        assert_eq!(view.description, "最初の<書き込み>");
+        assert_eq!(
+            view.responses.iter().map(|r| r.order).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert!(view.responses[0].is_abone);
+        assert_eq!(
+            view.responses[1].body_html,
+            r#"<a href="https://example.com/test/read.cgi/board/1719545696/1">&gt;&gt;1</a> 乙"#
+        );
+        assert_eq!(view.prev_range.as_deref(), Some("1-1"));
+        assert_eq!(view.next_range, None);
+    }
+
+    #[test]
+    fn test_render_read_view() {
+        let mut handlebars = handlebars::Handlebars::new();
+        handlebars
+            .register_template_file(
+                "read-cgi.get",
+                concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/resources/templates/read-cgi.get.hbs"
+                ),
+            )
+            .unwrap();
+
+        let thread = thread(
+            "\"スレ\" &lt;script&gt;",
+            vec![json_res(
+                1,
+                "abcdefgh",
+                "&lt;b&gt;太字&lt;/b&gt;<br>&gt;&gt;1",
+            )],
+        );
+        let view = ReadView::new("", "Board", "board", &thread, ReadRange::ALL, true);
+
+        let html = handlebars.render("read-cgi.get", &view).unwrap();
+        assert!(html.contains("<title>&quot;スレ&quot; &lt;script&gt; - Board</title>"));
+        assert!(html.contains(r#"<meta name="robots" content="noindex">"#));
+        assert!(html.contains(
+            r#"&lt;b&gt;太字&lt;/b&gt;<br /><a href="/test/read.cgi/board/1719545696/1">&gt;&gt;1</a>"#
+        ));
+        assert!(!html.contains("<script>"));
+    }
+}
@@ -76,6 +76,14 @@ impl<T: ResState> Res<T> {
         self.mail == "sage"
     }
 
+    pub fn set_abone(&mut self, is_abone: bool) {
+        self.is_abone = is_abone;
+    }
+
+    pub fn is_abone(&self) -> bool {
+        self.is_abone
+    }
+
     pub fn is_email_authed(&self) -> bool {
         self.is_email_authed
     }
@@ -5,7 +5,7 @@ use rand::RngExt;
 use chrono::Utc;
 use eddist_core::simple_rate_limiter::RateLimiter;
 use metrics::counter;
-use redis::{AsyncCommands, aio::ConnectionManager};
+use redis::{AsyncCommands, ExistenceCheck, SetExpiry, SetOptions, aio::ConnectionManager};
 use tokio::sync::Mutex;
 
 use crate::{
@@ -21,10 +21,15 @@ use crate::{
 };
 use eddist_core::{
     domain::pubsub_repository::AuthTokenInitiated,
-    redis_keys::{authed_token_suspended_key, reauth_lock_key, reauth_temp_key},
+    redis_keys::{
+        authed_token_suspended_key, reauth_lock_key, reauth_temp_key, user_restriction_reauth_key,
+    },
     utils::is_auth_token_pub_enabled,
 };
 
+/// How long a token stays exempt from a REAUTH rule after being sent to re-auth by it
+const USER_RESTRICTION_REAUTH_TTL_SECS: u64 = 60 * 60 * 24 * 30;
+
 pub static USER_CREATION_RATE_LIMIT: OnceLock<Mutex<RateLimiter>> = OnceLock::new();
 
 #[derive(Clone)]
@@ -67,13 +72,17 @@ impl<T: BbsRepository, E: CreationEventRepository> BbsCgiAuthService<T, E> {
 
     pub async fn check_validity(
         &self,
-        token: Option<&str>,
-        ip_addr: String,
-        user_agent: String,
-        asn_num: i32,
-        created_at: chrono::DateTime<chrono::Utc>,
-        require_user_registration: bool,
+        input: BbsCgiAuthInput<'_>,
     ) -> Result<AuthedToken, BbsCgiError> {
+        let BbsCgiAuthInput {
+            token,
+            ip_addr,
+            user_agent,
+            asn_num,
+            created_at,
+            require_user_registration,
+            reauth_rule_id,
+        } = input;
         let Some(authed_token) = token else {
             let authed_token = AuthedToken::new(ip_addr.clone(), user_agent.clone(), asn_num);
             self.repo
@@ -152,11 +161,36 @@ impl<T: BbsRepository, E: CreationEventRepository> BbsCgiAuthService<T, E> {
             return Err(BbsCgiError::TemporarilySuspended);
         }
 
+        // A REAUTH user restriction rule sends each token to re-auth once, so that passing it
+        // lets the token post again while the rule stays in place
+        let require_reauth = authed_token.require_reauth
+            || match reauth_rule_id {
+                Some(rule_id) => {
+                    let first_hit = conn
+                        .set_options::<_, _, bool>(
+                            user_restriction_reauth_key(
+                                &rule_id.to_string(),
+                                &authed_token.id.to_string(),
+                            ),
+                            1,
+                            SetOptions::default()
+                                .conditional_set(ExistenceCheck::NX)
+                                .with_expiration(SetExpiry::EX(USER_RESTRICTION_REAUTH_TTL_SECS)),
+                        )
+                        .await
+                        .map_err(|e| BbsCgiError::Other(e.into()))?;
+                    if first_hit {
+                        self.repo.set_require_reauth(authed_token.id).await?;
+                    }
+                    first_hit
+                }
+                None => false,
+            };
         // Check require_reauth flag — generate a one-time temp key so the re-auth page
         // can uniquely identify this token without relying on IP (which may change on mobile).
         // A per-token lock key (reauth:lock:{id}) caps Redis entries at 2 per token regardless
         // of how many post attempts are made; repeated attempts reuse the existing code.
-        if authed_token.require_reauth {
+        if require_reauth {
             let token_id_str = authed_token.id.to_string();
             let lock_key = reauth_lock_key(&token_id_str);
             let existing_code: Option<String> = conn.get(&lock_key).await.unwrap_or(None);
@@ -210,6 +244,17 @@ impl<T: BbsRepository, E: CreationEventRepository> BbsCgiAuthService<T, E> {
     }
 }
 
+pub struct BbsCgiAuthInput<'a> {
+    pub token: Option<&'a str>,
+    pub ip_addr: String,
+    pub user_agent: String,
+    pub asn_num: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub require_user_registration: bool,
+    /// Set by a `REAUTH` user restriction rule matching the request
+    pub reauth_rule_id: Option<uuid::Uuid>,
+}
+
 /// Generates an 8-character Crockford Base32 key (digits + uppercase letters, no I/L/O/U).
 /// 32^8 ≈ 1 trillion combinations — sufficient for a 5-minute TTL key.
 fn gen_reauth_temp_key() -> String {
@@ -1,11 +1,43 @@
-use eddist_core::cache_aside::{AsCache, ToCache, cache_aside};
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
+
+use eddist_core::{
+    cache_aside::{AsCache, ToCache, cache_aside},
+    domain::ng_word::NgWordMatcher,
+};
 use redis::aio::ConnectionManager;
 use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
 
 use crate::{
     domain::ng_word::NgWord, error::BbsCgiError, repositories::bbs_repository::BbsRepository,
 };
 
+/// Compiled matchers per board key, alongside the NG words they were built from
+type CompiledMatcherCache = RwLock<HashMap<String, (Vec<NgWord>, Arc<NgWordMatcher>)>>;
+
+static GLOBAL_COMPILED_MATCHER_CACHE: OnceLock<CompiledMatcherCache> = OnceLock::new();
+
+fn get_compiled_matcher_cache() -> &'static CompiledMatcherCache {
+    GLOBAL_COMPILED_MATCHER_CACHE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+fn compile_matcher(ng_words: &[NgWord]) -> NgWordMatcher {
+    let compiled = ng_words
+        .iter()
+        .filter_map(|ng_word| match ng_word.compile() {
+            Ok(compiled) => Some(compiled),
+            Err(e) => {
+                tracing::warn!(id = %ng_word.id, error = %e, "Skipping ng word with invalid pattern");
+                None
+            }
+        })
+        .collect();
+    NgWordMatcher::new(compiled)
+}
+
 #[derive(Clone)]
 pub struct NgWordReadingService<T: BbsRepository>(T, ConnectionManager);
 
@@ -56,4 +88,28 @@ impl<T: BbsRepository + Clone> NgWordReadingService<T> {
         .await
         .map_err(BbsCgiError::Other)
     }
+
+    /// Returns the compiled matcher for the board, recompiling only when the
+    /// NG words read through the Redis cache have changed.
+    pub async fn get_ng_word_matcher(
+        &self,
+        board_key: &str,
+    ) -> Result<Arc<NgWordMatcher>, BbsCgiError> {
+        let ng_words = self.get_ng_words(board_key).await?;
+
+        let cache = get_compiled_matcher_cache();
+        if let Some((cached_words, matcher)) = cache.read().await.get(board_key)
+            && *cached_words == ng_words
+        {
+            return Ok(matcher.clone());
+        }
+
+        let matcher = Arc::new(compile_matcher(&ng_words));
+        cache
+            .write()
+            .await
+            .insert(board_key.to_string(), (ng_words, matcher.clone()));
+
+        Ok(matcher)
+    }
 }
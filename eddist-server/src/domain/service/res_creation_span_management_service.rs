@@ -192,6 +192,32 @@ impl ResCreationSpanManagementService {
             .unwrap();
     }
 
+    /// Add extra penalty seconds to the creation span of the given auth IP (e.g. for NG word hits).
+    /// Must be called after `update_last_res_creation_time` so that the span is measured from this creation.
+    pub async fn add_penalty(&self, auth_ip: &str, penalty_seconds: u64) {
+        if self.span == 0 || penalty_seconds == 0 {
+            return;
+        }
+
+        let mut redis_conn = self.redis_conn.clone();
+
+        let total_penalty = redis_conn
+            .incr::<_, _, u64>(&res_creation_penalty_key(auth_ip), penalty_seconds)
+            .await
+            .unwrap();
+
+        // Both keys must outlive the penalty, or the span check would forget about it
+        let ttl = (self.span * 3 + total_penalty) as i64;
+        redis_conn
+            .expire::<_, ()>(&res_creation_penalty_key(auth_ip), ttl)
+            .await
+            .unwrap();
+        redis_conn
+            .expire::<_, ()>(&res_creation_span_key(auth_ip), ttl)
+            .await
+            .unwrap();
+    }
+
     /// Update the last response creation time for the given ip address.
     async fn update_last_res_creation_time_by_ip(&self, ip: &str, timestamp: u64) {
         if self.span == 0 {
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use eddist_core::redis_keys::shadow_abone_key;
+use redis::{AsyncCommands, aio::ConnectionManager};
+use sha3::{Digest, Sha3_256};
+
+/// Same lifetime as the thread cache the aboned lines live in
+const SHADOW_ABONE_TTL_SECS: i64 = 60 * 60 * 24 * 7;
+
+/// Keeps shadow-aboned responses readable by their poster.
+///
+/// The thread cache only holds the あぼーん line, so the original line is stored
+/// separately under the poster's edge-token and spliced back into the dat when
+/// that poster fetches it.
+#[derive(Clone)]
+pub struct ShadowAboneService(ConnectionManager);
+
+impl ShadowAboneService {
+    pub fn new(redis_conn: ConnectionManager) -> Self {
+        Self(redis_conn)
+    }
+
+    fn key(board_key: &str, thread_number: u64, authed_token: &str) -> String {
+        let digest = Sha3_256::digest(authed_token.as_bytes());
+        let digest = digest
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>();
+        shadow_abone_key(board_key, thread_number, &digest)
+    }
+
+    pub async fn record(
+        &self,
+        board_key: &str,
+        thread_number: u64,
+        authed_token: &str,
+        res_order: i32,
+        original_line: &[u8],
+    ) -> anyhow::Result<()> {
+        let mut redis_conn = self.0.clone();
+        let key = Self::key(board_key, thread_number, authed_token);
+
+        redis_conn
+            .hset::<_, _, _, ()>(&key, res_order, original_line)
+            .await?;
+        redis_conn
+            .expire::<_, ()>(&key, SHADOW_ABONE_TTL_SECS)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Original lines of the poster's shadow-aboned responses in the thread, keyed by res order
+    pub async fn get_original_lines(
+        &self,
+        board_key: &str,
+        thread_number: u64,
+        authed_token: &str,
+    ) -> anyhow::Result<HashMap<usize, Vec<u8>>> {
+        let mut redis_conn = self.0.clone();
+        let lines = redis_conn
+            .hgetall::<_, HashMap<usize, Vec<u8>>>(Self::key(
+                board_key,
+                thread_number,
+                authed_token,
+            ))
+            .await?;
+
+        Ok(lines)
+    }
+}
+
+/// Replaces the 1-indexed lines of `dat` found in `original_lines`
+pub fn overlay_original_lines(dat: &[u8], original_lines: &HashMap<usize, Vec<u8>>) -> Vec<u8> {
+    let mut result = Vec::with_capacity(dat.len());
+    for (idx, line) in dat.split_inclusive(|b| *b == b'\n').enumerate() {
+        match original_lines.get(&(idx + 1)) {
+            Some(original) => result.extend_from_slice(original),
+            None => result.extend_from_slice(line),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay_original_lines() {
+        let dat = b"first\nabone\nthird\n";
+        let original_lines = HashMap::from([(2, b"second\n".to_vec()), (5, b"none\n".to_vec())]);
+
+        assert_eq!(
+            overlay_original_lines(dat, &original_lines),
+            b"first\nsecond\nthird\n"
+        );
+        assert_eq!(overlay_original_lines(dat, &HashMap::new()), dat);
+    }
+}
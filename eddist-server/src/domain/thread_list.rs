@@ -1,5 +1,10 @@
-use eddist_core::domain::board::Board;
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use eddist_core::domain::{board::Board, metadent::MetadentType};
 use encoding_rs::SHIFT_JIS;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use super::thread::Thread;
 
@@ -9,7 +14,108 @@ pub struct ThreadList {
     pub thread_list: Vec<Thread>,
 }
 
+/// Item of a thread list, with or without its metadent
+trait ListedThread {
+    fn thread(&self) -> &Thread;
+}
+
+impl ListedThread for Thread {
+    fn thread(&self) -> &Thread {
+        self
+    }
+}
+
+impl ListedThread for (Thread, String) {
+    fn thread(&self) -> &Thread {
+        &self.0
+    }
+}
+
+/// Order of a thread list, most first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreadListSort {
+    /// Last bumped first, as stored
+    #[default]
+    Bump,
+    /// Newest thread first
+    Created,
+    Responses,
+    /// Responses within the last hour (勢い)
+    Momentum,
+}
+
+/// Query options of subject.txt and the JSON thread list. Without any, the list is
+/// returned whole in bump order.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ThreadListQuery {
+    #[serde(default)]
+    pub sort: ThreadListSort,
+    pub min_responses: Option<u32>,
+    /// Leaves out the threads hidden by the safe mode
+    #[serde(default)]
+    pub exclude_unsafe: bool,
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl ThreadListQuery {
+    /// Filters, sorts and pages the threads. Pinned threads stay on top whatever the sort.
+    ///
+    /// `momentum` is keyed by thread id and only needed when sorting by it.
+    fn apply<T: ListedThread>(
+        &self,
+        threads: &mut Vec<T>,
+        unsafe_threads: &HashSet<u64>,
+        momentum: &HashMap<Uuid, u64>,
+    ) {
+        threads.retain(|th| {
+            let th = th.thread();
+            self.min_responses
+                .is_none_or(|min| th.response_count >= min)
+                && !(self.exclude_unsafe && unsafe_threads.contains(&(th.thread_number as u64)))
+        });
+
+        match self.sort {
+            ThreadListSort::Bump => {}
+            ThreadListSort::Created => {
+                threads.sort_by_key(|th| (!th.thread().no_pool, -th.thread().thread_number))
+            }
+            ThreadListSort::Responses => threads.sort_by_key(|th| {
+                (
+                    !th.thread().no_pool,
+                    std::cmp::Reverse(th.thread().response_count),
+                )
+            }),
+            ThreadListSort::Momentum => threads.sort_by_key(|th| {
+                (
+                    !th.thread().no_pool,
+                    std::cmp::Reverse(momentum.get(&th.thread().id).copied().unwrap_or(0)),
+                )
+            }),
+        }
+
+        let end = self
+            .limit
+            .map_or(threads.len(), |limit| self.offset.saturating_add(limit))
+            .min(threads.len());
+        let start = self.offset.min(end);
+        threads.truncate(end);
+        threads.drain(..start);
+    }
+}
+
 impl ThreadList {
+    pub fn apply_query(
+        &mut self,
+        query: &ThreadListQuery,
+        unsafe_threads: &HashSet<u64>,
+        momentum: &HashMap<Uuid, u64>,
+    ) {
+        query.apply(&mut self.thread_list, unsafe_threads, momentum);
+    }
+
     pub fn get_sjis_thread_list(&self) -> Vec<u8> {
         use std::fmt::Write;
         let mut text = String::with_capacity(self.thread_list.len() * 100);
@@ -22,6 +128,71 @@ impl ThreadList {
         }
         SHIFT_JIS.encode(&text).0.to_vec()
     }
+
+    pub fn get_json_thread_list(&self) -> Vec<JsonThread> {
+        self.thread_list
+            .iter()
+            .map(|thread| JsonThread {
+                thread_number: thread.thread_number,
+                title: thread.title.clone(),
+                response_count: thread.response_count,
+                active: thread.active,
+                pinned: thread.no_pool,
+                metadent: Option::<&str>::from(MetadentType::from(thread.metadent.as_str()))
+                    .map(str::to_string),
+                last_modified_at: thread.last_modified_at,
+            })
+            .collect()
+    }
+
+    /// Threads with the most responses within the last hour, given by thread id in
+    /// `momentum`, most first
+    pub fn get_trending_threads(
+        &self,
+        momentum: &HashMap<Uuid, u64>,
+        limit: usize,
+    ) -> Vec<TrendingThread> {
+        let mut threads = self
+            .thread_list
+            .iter()
+            .filter_map(|thread| {
+                let momentum = *momentum.get(&thread.id)?;
+                Some(TrendingThread {
+                    thread_number: thread.thread_number,
+                    title: thread.title.clone(),
+                    response_count: thread.response_count,
+                    momentum,
+                })
+            })
+            .collect::<Vec<_>>();
+        threads.sort_by_key(|th| (std::cmp::Reverse(th.momentum), -th.thread_number));
+        threads.truncate(limit);
+        threads
+    }
+}
+
+/// Thread of the trending API
+#[derive(Debug, Clone, Serialize)]
+pub struct TrendingThread {
+    pub thread_number: i64,
+    pub title: String,
+    pub response_count: u32,
+    /// Responses within the last hour
+    pub momentum: u64,
+}
+
+/// Thread of the JSON read API, in the same order as subject.txt
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonThread {
+    pub thread_number: i64,
+    pub title: String,
+    pub response_count: u32,
+    /// Whether the thread can still be posted to
+    pub active: bool,
+    pub pinned: bool,
+    /// `v`, `vv` or `vvv`, `None` when the thread has no metadent
+    pub metadent: Option<String>,
+    pub last_modified_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone)]
@@ -31,6 +202,15 @@ pub struct ThreadListWithMetadent {
 }
 
 impl ThreadListWithMetadent {
+    pub fn apply_query(
+        &mut self,
+        query: &ThreadListQuery,
+        unsafe_threads: &HashSet<u64>,
+        momentum: &HashMap<Uuid, u64>,
+    ) {
+        query.apply(&mut self.thread_list, unsafe_threads, momentum);
+    }
+
     pub fn get_sjis_thread_list(&self) -> Vec<u8> {
         use std::fmt::Write;
         let mut text = String::with_capacity(self.thread_list.len() * 120);
@@ -44,3 +224,132 @@ impl ThreadListWithMetadent {
         SHIFT_JIS.encode(&text).0.to_vec()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thread(thread_number: i64, response_count: u32, no_pool: bool) -> Thread {
+        let now = DateTime::from_timestamp(thread_number, 0).unwrap();
+        Thread {
+            id: thread_id(thread_number),
+            board_id: Uuid::nil(),
+            thread_number,
+            last_modified_at: now,
+            sage_last_modified_at: now,
+            title: format!("thread {thread_number}"),
+            authed_token_id: Uuid::nil(),
+            metadent: String::new(),
+            response_count,
+            no_pool,
+            active: true,
+            archived: false,
+        }
+    }
+
+    fn thread_id(thread_number: i64) -> Uuid {
+        Uuid::from_u128(thread_number as u128)
+    }
+
+    fn apply(query: &ThreadListQuery, unsafe_threads: &[u64]) -> Vec<i64> {
+        // In bump order, with the pinned thread first
+        let mut threads = vec![
+            thread(100, 1, true),
+            thread(86_400, 10, false),
+            thread(10_000, 50, false),
+            thread(160_000, 5, false),
+        ];
+        let unsafe_threads = unsafe_threads.iter().copied().collect();
+        let momentum = HashMap::from([(thread_id(86_400), 2), (thread_id(160_000), 7)]);
+        query.apply(&mut threads, &unsafe_threads, &momentum);
+        threads.iter().map(|th| th.thread_number).collect()
+    }
+
+    #[test]
+    fn test_thread_list_query_sort() {
+        let query = |sort| ThreadListQuery {
+            sort,
+            ..Default::default()
+        };
+        assert_eq!(
+            apply(&ThreadListQuery::default(), &[]),
+            vec![100, 86_400, 10_000, 160_000]
+        );
+        assert_eq!(
+            apply(&query(ThreadListSort::Created), &[]),
+            vec![100, 160_000, 86_400, 10_000]
+        );
+        assert_eq!(
+            apply(&query(ThreadListSort::Responses), &[]),
+            vec![100, 10_000, 86_400, 160_000]
+        );
+        // Threads without responses within the hour keep their bump order
+        assert_eq!(
+            apply(&query(ThreadListSort::Momentum), &[]),
+            vec![100, 160_000, 86_400, 10_000]
+        );
+    }
+
+    #[test]
+    fn test_thread_list_query_filter_and_page() {
+        let query = ThreadListQuery {
+            min_responses: Some(5),
+            exclude_unsafe: true,
+            ..Default::default()
+        };
+        assert_eq!(apply(&query, &[86_400]), vec![10_000, 160_000]);
+        // Unsafe threads are kept unless asked for
+        assert_eq!(
+            apply(
+                &ThreadListQuery {
+                    exclude_unsafe: false,
+                    ..query
+                },
+                &[86_400]
+            ),
+            vec![86_400, 10_000, 160_000]
+        );
+
+        let page = |offset, limit| ThreadListQuery {
+            offset,
+            limit,
+            ..Default::default()
+        };
+        assert_eq!(apply(&page(1, Some(2)), &[]), vec![86_400, 10_000]);
+        assert_eq!(apply(&page(3, None), &[]), vec![160_000]);
+        assert_eq!(apply(&page(10, Some(2)), &[]), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn test_get_trending_threads() {
+        let threads = ThreadList {
+            board: Board {
+                id: Uuid::nil(),
+                name: "Board".to_string(),
+                board_key: "board".to_string(),
+                default_name: "名無し".to_string(),
+            },
+            thread_list: vec![
+                thread(100, 1, true),
+                thread(86_400, 10, false),
+                thread(10_000, 50, false),
+            ],
+        };
+        // Threads out of the list, e.g. archived ones, are left out
+        let momentum = HashMap::from([
+            (thread_id(86_400), 3),
+            (thread_id(10_000), 3),
+            (thread_id(100), 1),
+            (thread_id(5), 100),
+        ]);
+
+        let trending = threads.get_trending_threads(&momentum, 2);
+        assert_eq!(
+            trending
+                .iter()
+                .map(|th| (th.thread_number, th.momentum))
+                .collect::<Vec<_>>(),
+            vec![(86_400, 3), (10_000, 3)]
+        );
+    }
+}
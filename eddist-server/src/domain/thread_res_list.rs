@@ -1,4 +1,10 @@
-use eddist_core::domain::res::ResView;
+use chrono::{DateTime, Utc};
+use eddist_core::domain::{
+    metadent::MetadentType,
+    pubsub_repository::CreatingRes,
+    res::{ResView, ResViewRef},
+};
+use serde::Serialize;
 
 use super::thread::Thread;
 
@@ -9,32 +15,198 @@ pub struct ThreadResList {
 }
 
 impl ThreadResList {
-    pub fn get_sjis_thread_res_list(&self, default_name: &str) -> Vec<u8> {
-        let mut result = Vec::with_capacity(self.res_list.len() * 200);
-        for chunk in self.get_sjis_list_inner(default_name) {
-            result.extend_from_slice(&chunk);
+    /// Renders the dat lines of the responses, `res_list` starting at the `start`th (0-based)
+    /// response of the thread
+    pub fn get_sjis_list_thread_res_list(&self, default_name: &str, start: usize) -> Vec<Vec<u8>> {
+        self.res_list
+            .iter()
+            .enumerate()
+            .map(|(idx, x)| {
+                x.get_sjis_bytes(
+                    default_name,
+                    if start + idx == 0 {
+                        Some(&self.thread.title)
+                    } else {
+                        None
+                    },
+                )
+                .get_inner()
+            })
+            .collect()
+    }
+}
+
+/// Response of the JSON read API, with the fields shown in its dat line
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonRes {
+    pub order: usize,
+    pub name: String,
+    pub mail: String,
+    /// `None` for aboned responses, whose lines carry no date
+    pub created_at: Option<DateTime<Utc>>,
+    pub author_id: String,
+    /// HTML as in the dat, with `<br>` line breaks
+    pub body: String,
+    pub is_abone: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonThreadResList {
+    pub thread_number: u64,
+    pub title: String,
+    pub response_count: usize,
+    /// Whether the thread can still be posted to
+    pub active: bool,
+    /// `v`, `vv` or `vvv`, `None` when the thread has no metadent
+    pub metadent: Option<String>,
+    /// Responses whose order is greater than the requested `since`
+    pub responses: Vec<JsonRes>,
+}
+
+impl JsonThreadResList {
+    /// Builds the list from the responses of the thread from the `start`th (0-based) on
+    pub fn new(thread: &Thread, default_name: &str, start: usize, responses: &[ResView]) -> Self {
+        Self {
+            thread_number: thread.thread_number as u64,
+            title: thread.title.clone(),
+            response_count: thread.response_count as usize,
+            active: thread.active,
+            metadent: Option::<&str>::from(MetadentType::from(thread.metadent.as_str()))
+                .map(str::to_string),
+            responses: responses
+                .iter()
+                .enumerate()
+                .map(|(idx, res)| JsonRes::from_res_view(start + idx + 1, res, default_name))
+                .collect(),
         }
-        result
+    }
+}
+
+impl JsonRes {
+    pub fn from_res_view(order: usize, res: &ResView, default_name: &str) -> Self {
+        Self::from_ref(
+            order,
+            ResViewRef {
+                author_name: &res.author_name,
+                mail: &res.mail,
+                body: &res.body,
+                created_at: res.created_at,
+                author_id: &res.author_id,
+                is_abone: res.is_abone,
+            },
+            default_name,
+        )
     }
 
-    pub fn get_sjis_list_thread_res_list(&self, default_name: &str) -> Vec<Vec<u8>> {
-        self.get_sjis_list_inner(default_name).collect()
+    /// The name of a created response already falls back to the default name
+    pub fn from_creating_res(res: &CreatingRes) -> Self {
+        Self::from_ref(
+            res.res_order as usize,
+            ResViewRef {
+                author_name: &res.name,
+                mail: &res.mail,
+                body: &res.body,
+                created_at: res.created_at,
+                author_id: &res.author_ch5id,
+                is_abone: res.is_abone,
+            },
+            "",
+        )
     }
 
-    fn get_sjis_list_inner<'a>(
-        &'a self,
-        default_name: &'a str,
-    ) -> impl Iterator<Item = Vec<u8>> + 'a {
-        self.res_list.iter().enumerate().map(move |(idx, x)| {
-            x.get_sjis_bytes(
-                default_name,
-                if idx == 0 {
-                    Some(&self.thread.title)
-                } else {
-                    None
-                },
-            )
-            .get_inner()
-        })
+    /// Same fields as the dat line, which only shows `sage` of the mail
+    fn from_ref(order: usize, res: ResViewRef<'_>, default_name: &str) -> Self {
+        if res.is_abone {
+            return Self {
+                order,
+                name: String::new(),
+                mail: String::new(),
+                created_at: None,
+                author_id: String::new(),
+                body: String::new(),
+                is_abone: true,
+            };
+        }
+
+        Self {
+            order,
+            name: if res.author_name.is_empty() {
+                default_name.to_string()
+            } else {
+                res.author_name.to_string()
+            },
+            mail: if res.mail == "sage" { "sage" } else { "" }.to_string(),
+            created_at: Some(res.created_at),
+            author_id: res.author_id.to_string(),
+            body: res.body.to_string(),
+            is_abone: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn test_json_thread_res_list() {
+        let created_at = Utc.with_ymd_and_hms(2024, 6, 28, 3, 4, 5).unwrap();
+        let res_view = |body: &str, is_abone: bool| ResView {
+            author_name: String::new(),
+            mail: "sage".to_string(),
+            body: body.to_string(),
+            created_at,
+            author_id: "abcd1234".to_string(),
+            is_abone,
+        };
+        let thread = Thread {
+            id: Uuid::nil(),
+            board_id: Uuid::nil(),
+            thread_number: 1719543845,
+            last_modified_at: created_at,
+            sage_last_modified_at: created_at,
+            title: "テストスレッド".to_string(),
+            authed_token_id: Uuid::nil(),
+            metadent: "vvv".to_string(),
+            response_count: 3,
+            no_pool: false,
+            active: true,
+            archived: false,
+        };
+        let responses = [
+            res_view("first<br>post", false),
+            res_view("spam", true),
+            res_view("third", false),
+        ];
+
+        let list = JsonThreadResList::new(&thread, "名無しさん", 0, &responses);
+        assert_eq!(list.title, "テストスレッド");
+        assert_eq!(list.response_count, 3);
+        assert!(list.active);
+        assert_eq!(list.metadent.as_deref(), Some("vvv"));
+        assert_eq!(
+            list.responses[0],
+            JsonRes {
+                order: 1,
+                name: "名無しさん".to_string(),
+                mail: "sage".to_string(),
+                created_at: Some(created_at),
+                author_id: "abcd1234".to_string(),
+                body: "first<br>post".to_string(),
+                is_abone: false,
+            }
+        );
+        assert!(list.responses[1].is_abone);
+        assert_eq!(list.responses[1].body, "");
+        assert_eq!(list.responses[2].body, "third");
+        assert_eq!(list.responses.len(), 3);
+
+        let list = JsonThreadResList::new(&thread, "名無しさん", 2, &responses[2..]);
+        assert_eq!(list.response_count, 3);
+        assert_eq!(list.responses.len(), 1);
+        assert_eq!(list.responses[0].order, 3);
     }
 }
@@ -0,0 +1,166 @@
+use std::{sync::OnceLock, time::Duration};
+
+use eddist_core::domain::{
+    moderation_provider::{HttpClassifierConfig, KeywordScorer},
+    pubsub_repository::ModerationResult,
+};
+use jsonpath_rust::JsonPath;
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+static HTTP_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// Client shared by all providers so connections are reused and a stuck endpoint can't hang a post
+fn get_http_client() -> &'static reqwest::Client {
+    HTTP_CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(2))
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap()
+    })
+}
+
+#[async_trait::async_trait]
+pub trait ModerationProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn moderate(&self, text: &str) -> anyhow::Result<ModerationResult>;
+}
+
+pub struct OpenAiModerationClient {
+    api_key: String,
+}
+
+impl OpenAiModerationClient {
+    pub fn new(api_key: String) -> Self {
+        Self { api_key }
+    }
+}
+
+#[async_trait::async_trait]
+impl ModerationProvider for OpenAiModerationClient {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    async fn moderate(&self, text: &str) -> anyhow::Result<ModerationResult> {
+        #[derive(Serialize)]
+        struct Request<'a> {
+            input: &'a str,
+        }
+
+        #[derive(Deserialize)]
+        struct Response {
+            results: Vec<RawResult>,
+        }
+
+        #[derive(Deserialize)]
+        struct RawResult {
+            flagged: bool,
+            categories: Value,
+            category_scores: Value,
+        }
+
+        counter!("openai_moderation_api_calls", "provider" => self.name()).increment(1);
+
+        let resp = get_http_client()
+            .post("https://api.openai.com/v1/moderations")
+            .bearer_auth(&self.api_key)
+            .json(&Request { input: text })
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Response>()
+            .await?;
+
+        let raw = resp
+            .results
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("empty moderation results"))?;
+
+        Ok(ModerationResult {
+            flagged: raw.flagged,
+            categories: raw.categories,
+            category_scores: raw.category_scores,
+        })
+    }
+}
+
+/// Classifier that accepts a JSON body and returns its scores as JSON, e.g. a self-hosted model
+pub struct HttpClassifierClient {
+    config: HttpClassifierConfig,
+    /// Decrypted `config.headers`
+    headers: Vec<(String, String)>,
+}
+
+impl HttpClassifierClient {
+    pub fn new(config: HttpClassifierConfig, headers: Vec<(String, String)>) -> Self {
+        Self { config, headers }
+    }
+
+    fn extract_scores(&self, resp: &Value) -> anyhow::Result<Value> {
+        let score = resp
+            .query(&format!("$.{}", self.config.score_path))
+            .ok()
+            .and_then(|results| results.first().and_then(|v| v.as_f64()))
+            .ok_or_else(|| {
+                anyhow::anyhow!("no score found at {} in response", self.config.score_path)
+            })?;
+
+        let mut scores = self
+            .config
+            .category_scores_path
+            .as_ref()
+            .and_then(|path| resp.query(&format!("$.{path}")).ok())
+            .and_then(|results| results.first().and_then(|v| v.as_object()).cloned())
+            .unwrap_or_default();
+        scores.insert("score".to_string(), score.into());
+
+        Ok(Value::Object(scores))
+    }
+}
+
+#[async_trait::async_trait]
+impl ModerationProvider for HttpClassifierClient {
+    fn name(&self) -> &'static str {
+        "http_classifier"
+    }
+
+    async fn moderate(&self, text: &str) -> anyhow::Result<ModerationResult> {
+        counter!("openai_moderation_api_calls", "provider" => self.name()).increment(1);
+
+        let mut req = get_http_client()
+            .post(&self.config.url)
+            .header("Content-Type", "application/json");
+        for (k, v) in &self.headers {
+            req = req.header(k.as_str(), v.as_str());
+        }
+
+        let resp = req
+            .body(self.config.render_body(text))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Value>()
+            .await?;
+
+        Ok(ModerationResult {
+            flagged: false,
+            categories: Value::Object(Default::default()),
+            category_scores: self.extract_scores(&resp)?,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl ModerationProvider for KeywordScorer {
+    fn name(&self) -> &'static str {
+        "keyword"
+    }
+
+    async fn moderate(&self, text: &str) -> anyhow::Result<ModerationResult> {
+        Ok(self.score(text))
+    }
+}
@@ -12,6 +12,7 @@ pub mod repositories {
     pub mod captcha_config_repository;
     pub mod idp_repository;
     pub mod notice_repository;
+    pub mod search_repository;
     pub mod stats_repository;
     pub mod terms_repository;
     pub mod user_repository;
@@ -26,11 +27,14 @@ mod domain {
         pub mod ng_word_reading_service;
         pub mod oidc_client_service;
         pub mod res_creation_span_management_service;
+        pub mod shadow_abone_service;
     }
     pub(crate) mod authed_token;
     pub(crate) mod captcha_like;
+    pub(crate) mod feed;
     pub(crate) mod metadent;
     pub(crate) mod ng_word;
+    pub(crate) mod read_view;
     pub(crate) mod res;
     pub(crate) mod res_core;
     pub(crate) mod thread;
@@ -40,24 +44,33 @@ mod domain {
     pub(crate) mod utils;
 }
 mod error;
+pub mod listener;
 pub mod middleware;
 pub mod services;
 mod template;
 pub(crate) mod external {
     pub mod captcha_like_client;
+    pub mod moderation_provider;
     pub mod oidc_client;
 }
 pub(crate) mod utils;
 mod routes {
     pub mod auth_code;
     pub mod bbs_cgi;
+    pub mod common;
     pub mod dat_routing;
+    pub mod event_stream;
+    pub mod feed;
+    pub mod json_api;
     pub mod notice;
     pub mod re_auth;
+    pub mod read_cgi;
     pub mod safe_mode;
+    pub mod search;
     pub mod stats;
     pub mod subject_list;
     pub mod terms;
+    pub mod trending;
     pub mod user;
 }
 
@@ -88,15 +101,11 @@ pub fn create_test_app(
         user_restriction_repository::UserRestrictionRepositoryImpl,
     };
     use crate::services::{AppServiceContainer, PubSubRepos};
+    use eddist_core::archive_storage::LocalArchiveStorage;
 
-    let s3_creds = aws_sdk_s3::config::Credentials::new("test", "test", None, None, "custom");
-    let s3_config = aws_sdk_s3::Config::builder()
-        .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
-        .credentials_provider(s3_creds)
-        .region(aws_sdk_s3::config::Region::new("auto"))
-        .endpoint_url("https://test.r2.cloudflarestorage.com")
-        .build();
-    let s3_client = aws_sdk_s3::Client::from_conf(s3_config);
+    let archive_storage = std::sync::Arc::new(LocalArchiveStorage::new(
+        std::env::temp_dir().join("eddist-test-archive"),
+    ));
 
     let user_restriction_repo = UserRestrictionRepositoryImpl::new(pool.clone());
     let pub_repo = RedisPubRepository::new(redis_conn.clone());
@@ -104,6 +113,8 @@ pub fn create_test_app(
     let notice_repo = NoticeRepositoryImpl::new(pool.clone());
     let terms_repo = crate::repositories::terms_repository::TermsRepositoryImpl::new(pool.clone());
     let stats_repo = crate::repositories::stats_repository::StatsRepositoryImpl::new(pool.clone());
+    let search_repo =
+        crate::repositories::search_repository::SearchRepositoryImpl::new(pool.clone());
 
     drop(refresh_server_settings_cache(&pool));
     start_captcha_config_refresh_task(pool.clone(), std::time::Duration::from_secs(300));
@@ -120,18 +131,22 @@ pub fn create_test_app(
                 pub_repo,
                 event_repo,
             },
-            s3_client,
-            "test-bucket".to_string(),
+            archive_storage,
         ),
         notice_repo,
         terms_repo,
         stats_repo,
+        search_repo,
         template_engine: std::sync::Arc::new(load_template_engine()),
         tinker_secret: base64::engine::general_purpose::STANDARD
             .encode(Uuid::now_v7().as_bytes())
             .to_string(),
         redis_conn: redis_conn.clone(),
         not_found_penalty_cache: NotFoundPenaltyCache::new(),
+        event_stream_hub: crate::services::event_stream_hub::EventStreamHub::new(),
+        client_addr_config: std::sync::Arc::new(
+            eddist_core::client_addr::ClientAddrConfig::default(),
+        ),
     };
 
     // Use the actual create_app from app module
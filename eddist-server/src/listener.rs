@@ -0,0 +1,345 @@
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+use axum::{
+    extract::connect_info::Connected,
+    serve::{IncomingStream, Listener},
+};
+use ipnet::IpNet;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    net::{TcpListener, TcpStream},
+    task::JoinSet,
+};
+
+/// Time given to a connection to send its PROXY protocol header
+const PROXY_HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest v1 header, `PROXY TCP6` with the longest addresses and ports
+const MAX_V1_HEADER_LEN: usize = 107;
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Accepts the TCP connections of the app, reading the PROXY protocol header (v1 or v2)
+/// sent by HAProxy or a load balancer first when enabled
+pub struct ClientListener {
+    listener: TcpListener,
+    /// Proxies allowed to send the PROXY protocol header, `None` when it is disabled
+    proxy_protocol: Option<Vec<IpNet>>,
+    handshakes: JoinSet<Option<(TcpStream, SocketAddr)>>,
+}
+
+impl ClientListener {
+    pub fn new(listener: TcpListener, proxy_protocol: Option<Vec<IpNet>>) -> Self {
+        Self {
+            listener,
+            proxy_protocol,
+            handshakes: JoinSet::new(),
+        }
+    }
+}
+
+impl Listener for ClientListener {
+    type Io = TcpStream;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        loop {
+            tokio::select! {
+                accepted = self.listener.accept() => match (accepted, &self.proxy_protocol) {
+                    (Ok((stream, peer)), None) => return (stream, peer),
+                    // Anyone else could claim any address in the header
+                    (Ok((_, peer)), Some(trusted_proxies))
+                        if !trusted_proxies
+                            .iter()
+                            .any(|net| net.contains(&peer.ip().to_canonical())) =>
+                    {
+                        tracing::warn!("Dropped a connection from {peer}, not a trusted proxy");
+                    }
+                    // Read in its own task so that a slow client does not hold up the others
+                    (Ok((stream, peer)), Some(_)) => {
+                        self.handshakes.spawn(proxy_handshake(stream, peer));
+                    }
+                    (Err(e), _) => {
+                        tracing::error!("Failed to accept a connection: {e}");
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                },
+                Some(joined) = self.handshakes.join_next() => {
+                    if let Ok(Some(connection)) = joined {
+                        return connection;
+                    }
+                }
+            }
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.listener.local_addr()
+    }
+}
+
+async fn proxy_handshake(
+    mut stream: TcpStream,
+    peer: SocketAddr,
+) -> Option<(TcpStream, SocketAddr)> {
+    match tokio::time::timeout(PROXY_HEADER_TIMEOUT, read_proxy_header(&mut stream)).await {
+        Ok(Ok(ProxyHeader::Proxied(source))) => Some((stream, source)),
+        // Health checks of the proxy itself
+        Ok(Ok(ProxyHeader::Local)) => Some((stream, peer)),
+        Ok(Err(e)) => {
+            tracing::warn!("Invalid PROXY protocol header from {peer}: {e}");
+            None
+        }
+        Err(_) => {
+            tracing::warn!("No PROXY protocol header from {peer} in time");
+            None
+        }
+    }
+}
+
+/// Address of the peer of a connection, the source told by the PROXY protocol header
+/// when it is enabled
+#[derive(Debug, Clone, Copy)]
+pub struct PeerAddr(pub SocketAddr);
+
+impl Connected<IncomingStream<'_, ClientListener>> for PeerAddr {
+    fn connect_info(stream: IncomingStream<'_, ClientListener>) -> Self {
+        Self(*stream.remote_addr())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyHeader {
+    /// Proxied connection of the client at this address
+    Proxied(SocketAddr),
+    /// Connection of the proxy itself, or of an unknown protocol
+    Local,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads exactly the PROXY protocol header from the start of the stream, leaving the
+/// request after it unread
+pub async fn read_proxy_header<R: AsyncRead + Unpin>(stream: &mut R) -> io::Result<ProxyHeader> {
+    let mut header = [0u8; 16];
+    stream.read_exact(&mut header[..12]).await?;
+
+    if header[..12] == V2_SIGNATURE {
+        stream.read_exact(&mut header[12..]).await?;
+        let len = u16::from_be_bytes([header[14], header[15]]) as usize;
+        let mut addresses = vec![0u8; len];
+        stream.read_exact(&mut addresses).await?;
+        return parse_v2(&header, &addresses);
+    }
+
+    if !header.starts_with(b"PROXY ") {
+        return Err(invalid("missing PROXY protocol header"));
+    }
+    // Byte by byte not to read past the header, it is sent in one packet anyway
+    let mut line = header[..12].to_vec();
+    while !line.ends_with(b"\r\n") {
+        if line.len() >= MAX_V1_HEADER_LEN {
+            return Err(invalid("too long PROXY protocol v1 header"));
+        }
+        line.push(stream.read_u8().await?);
+    }
+    line.truncate(line.len() - 2);
+    parse_v1(&line)
+}
+
+/// Parses a v1 header without its CRLF, e.g. `PROXY TCP4 192.0.2.1 198.51.100.1 56324 443`
+fn parse_v1(line: &[u8]) -> io::Result<ProxyHeader> {
+    let line = std::str::from_utf8(line).map_err(|_| invalid("non-ASCII PROXY v1 header"))?;
+    let mut fields = line.split(' ');
+    if fields.next() != Some("PROXY") {
+        return Err(invalid("missing PROXY protocol header"));
+    }
+
+    let is_v6 = match fields.next() {
+        Some("TCP4") => false,
+        Some("TCP6") => true,
+        Some("UNKNOWN") => return Ok(ProxyHeader::Local),
+        _ => return Err(invalid("unknown PROXY v1 protocol")),
+    };
+    let (Some(source), Some(_destination), Some(source_port), Some(_destination_port), None) = (
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+    ) else {
+        return Err(invalid("malformed PROXY v1 header"));
+    };
+
+    let source = source
+        .parse::<IpAddr>()
+        .ok()
+        .filter(|ip| ip.is_ipv6() == is_v6)
+        .ok_or_else(|| invalid("invalid PROXY v1 source address"))?;
+    let source_port = source_port
+        .parse::<u16>()
+        .map_err(|_| invalid("invalid PROXY v1 source port"))?;
+    Ok(ProxyHeader::Proxied(SocketAddr::new(source, source_port)))
+}
+
+/// Parses a v2 header from its 16 fixed bytes and the address block following them
+fn parse_v2(header: &[u8; 16], addresses: &[u8]) -> io::Result<ProxyHeader> {
+    if header[12] >> 4 != 2 {
+        return Err(invalid("unsupported PROXY protocol version"));
+    }
+    match header[12] & 0x0f {
+        0 => return Ok(ProxyHeader::Local),
+        1 => {}
+        _ => return Err(invalid("unknown PROXY v2 command")),
+    }
+
+    // The family in the high nibble, the transport (stream or datagram) in the low one
+    let port = |at: usize| u16::from_be_bytes([addresses[at], addresses[at + 1]]);
+    match header[13] >> 4 {
+        1 if addresses.len() >= 12 => {
+            let source = Ipv4Addr::new(addresses[0], addresses[1], addresses[2], addresses[3]);
+            Ok(ProxyHeader::Proxied(SocketAddr::new(
+                source.into(),
+                port(8),
+            )))
+        }
+        2 if addresses.len() >= 36 => {
+            let source = <[u8; 16]>::try_from(&addresses[..16]).unwrap();
+            Ok(ProxyHeader::Proxied(SocketAddr::new(
+                Ipv6Addr::from(source).into(),
+                port(32),
+            )))
+        }
+        1 | 2 => Err(invalid("truncated PROXY v2 addresses")),
+        // AF_UNSPEC or AF_UNIX, there is no client IP
+        _ => Ok(ProxyHeader::Local),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+
+    async fn listen(trusted_proxies: &str) -> (ClientListener, SocketAddr) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let trusted_proxies = eddist_core::client_addr::parse_trusted_proxies(trusted_proxies);
+        (
+            ClientListener::new(listener, Some(trusted_proxies.unwrap())),
+            addr,
+        )
+    }
+
+    const PROXY_HEADER: &[u8] = b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 443\r\n";
+
+    #[tokio::test]
+    async fn test_accept_proxy_header_from_trusted_proxy() {
+        let (mut listener, addr) = listen("127.0.0.1").await;
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(PROXY_HEADER).await.unwrap();
+
+        let (_, source) = listener.accept().await;
+        assert_eq!(source, "192.0.2.1:56324".parse().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_drop_proxy_header_from_untrusted_peer() {
+        let (mut listener, addr) = listen("192.0.2.0/24").await;
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(PROXY_HEADER).await.unwrap();
+
+        let accepted = tokio::time::timeout(Duration::from_millis(200), listener.accept()).await;
+        assert!(accepted.is_err());
+        // Closed without reading the header
+        let mut buf = [0u8; 1];
+        assert!(matches!(client.read(&mut buf).await, Ok(0) | Err(_)));
+    }
+
+    async fn read(bytes: &[u8]) -> (io::Result<ProxyHeader>, Vec<u8>) {
+        let mut stream = bytes;
+        let header = read_proxy_header(&mut stream).await;
+        (header, stream.to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_read_proxy_header_v1() {
+        let (header, rest) =
+            read(b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 443\r\nGET / HTTP/1.1\r\n").await;
+        assert_eq!(
+            header.unwrap(),
+            ProxyHeader::Proxied("192.0.2.1:56324".parse().unwrap())
+        );
+        assert_eq!(rest, b"GET / HTTP/1.1\r\n");
+
+        let (header, _) = read(b"PROXY TCP6 2001:db8::1 2001:db8::2 4711 443\r\n").await;
+        assert_eq!(
+            header.unwrap(),
+            ProxyHeader::Proxied("[2001:db8::1]:4711".parse().unwrap())
+        );
+
+        let (header, rest) = read(b"PROXY UNKNOWN\r\nGET").await;
+        assert_eq!(header.unwrap(), ProxyHeader::Local);
+        assert_eq!(rest, b"GET");
+
+        assert!(read(b"GET / HTTP/1.1\r\n").await.0.is_err());
+        assert!(
+            read(b"PROXY TCP4 2001:db8::1 192.0.2.1 1 2\r\n")
+                .await
+                .0
+                .is_err()
+        );
+        assert!(
+            read(&[b"PROXY TCP4 ".as_slice(), &[b'1'; 200]].concat())
+                .await
+                .0
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_proxy_header_v2() {
+        let mut bytes = V2_SIGNATURE.to_vec();
+        // PROXY command over TCP4
+        bytes.extend([0x21, 0x11, 0, 12]);
+        bytes.extend([192, 0, 2, 1, 198, 51, 100, 1]);
+        bytes.extend(56324u16.to_be_bytes());
+        bytes.extend(443u16.to_be_bytes());
+        bytes.extend(b"GET");
+        let (header, rest) = read(&bytes).await;
+        assert_eq!(
+            header.unwrap(),
+            ProxyHeader::Proxied("192.0.2.1:56324".parse().unwrap())
+        );
+        assert_eq!(rest, b"GET");
+
+        let mut bytes = V2_SIGNATURE.to_vec();
+        // PROXY command over TCP6, with a TLV after the addresses
+        bytes.extend([0x21, 0x21, 0, 40]);
+        bytes.extend("2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        bytes.extend("2001:db8::2".parse::<Ipv6Addr>().unwrap().octets());
+        bytes.extend(4711u16.to_be_bytes());
+        bytes.extend(443u16.to_be_bytes());
+        bytes.extend([0x04, 0, 1, 0]);
+        let (header, rest) = read(&bytes).await;
+        assert_eq!(
+            header.unwrap(),
+            ProxyHeader::Proxied("[2001:db8::1]:4711".parse().unwrap())
+        );
+        assert!(rest.is_empty());
+
+        let mut bytes = V2_SIGNATURE.to_vec();
+        // LOCAL command
+        bytes.extend([0x20, 0x00, 0, 0]);
+        assert_eq!(read(&bytes).await.0.unwrap(), ProxyHeader::Local);
+
+        let mut bytes = V2_SIGNATURE.to_vec();
+        bytes.extend([0x21, 0x11, 0, 4, 192, 0, 2, 1]);
+        assert!(read(&bytes).await.0.is_err());
+    }
+}
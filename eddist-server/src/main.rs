@@ -9,6 +9,7 @@ use axum::{
 use eddist::{
     AppState,
     app::create_app,
+    listener::{ClientListener, PeerAddr},
     load_template_engine,
     middleware::not_found_rate_limit::NotFoundPenaltyCache,
     repositories::{
@@ -17,6 +18,7 @@ use eddist::{
         captcha_config_repository::CaptchaConfigRepositoryImpl,
         idp_repository::IdpRepositoryImpl,
         notice_repository::NoticeRepositoryImpl,
+        search_repository::SearchRepositoryImpl,
         stats_repository::StatsRepositoryImpl,
         terms_repository::TermsRepositoryImpl,
         user_repository::UserRepositoryImpl,
@@ -25,6 +27,7 @@ use eddist::{
     services::{
         AppServiceContainer, PubSubRepos,
         captcha_config_cache::{refresh_captcha_config_cache, start_captcha_config_refresh_task},
+        event_stream_hub::{EventStreamHub, start_event_stream_subscriber},
         server_settings_cache::{
             refresh_server_settings_cache, start_server_settings_refresh_task,
         },
@@ -32,7 +35,10 @@ use eddist::{
     },
     start_cache_refresh_task,
 };
-use eddist_core::{tracing::init_tracing, utils::is_prod};
+use eddist_core::{
+    archive_storage::archive_storage_from_env, client_addr::ClientAddrConfig,
+    tracing::init_tracing, utils::is_prod,
+};
 use hyper::{server::conn::http1, service::service_fn};
 use hyper_util::rt::{TokioIo, TokioTimer};
 use metrics::describe_counter;
@@ -86,34 +92,24 @@ async fn main() -> anyhow::Result<()> {
 
     let template_engine = load_template_engine();
 
-    let r2_account_id = env::var("R2_ACCOUNT_ID").unwrap();
-    let s3_bucket_name = env::var("S3_BUCKET_NAME").unwrap().trim().to_string();
-    let s3_endpoint = format!("https://{}.r2.cloudflarestorage.com", r2_account_id.trim());
-    let s3_creds = aws_sdk_s3::config::Credentials::new(
-        env::var("S3_ACCESS_KEY").unwrap().trim(),
-        env::var("S3_ACCESS_SECRET_KEY").unwrap().trim(),
-        None,
-        None,
-        "custom",
-    );
-    let s3_config = aws_sdk_s3::Config::builder()
-        .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
-        .credentials_provider(s3_creds)
-        .region(aws_sdk_s3::config::Region::new("auto"))
-        .endpoint_url(s3_endpoint)
-        .build();
-    let s3_client = aws_sdk_s3::Client::from_conf(s3_config);
+    let archive_storage = archive_storage_from_env()?;
 
     let user_restriction_repo = UserRestrictionRepositoryImpl::new(pool.clone());
     let notice_repo = NoticeRepositoryImpl::new(pool.clone());
     let terms_repo = TermsRepositoryImpl::new(pool.clone());
     let stats_repo = StatsRepositoryImpl::new(pool.clone());
+    let search_repo = SearchRepositoryImpl::new(pool.clone());
     let stats_repo_for_flush = stats_repo.clone();
     let stats_repo_for_shutdown = stats_repo.clone();
 
     // Load initial server settings from database and initialize cache
     refresh_server_settings_cache(&pool).await?;
 
+    let event_stream_hub = EventStreamHub::new();
+
+    let client_addr_config = ClientAddrConfig::from_env()?;
+    let trusted_proxies = client_addr_config.trusted_proxies.clone();
+
     let app_state = AppState {
         services: AppServiceContainer::new(
             BbsRepositoryImpl::new(pool.clone()),
@@ -125,16 +121,18 @@ async fn main() -> anyhow::Result<()> {
                 pub_repo,
                 event_repo,
             },
-            s3_client,
-            s3_bucket_name,
+            archive_storage,
         ),
         notice_repo,
         terms_repo,
         stats_repo,
+        search_repo,
         template_engine: Arc::new(template_engine),
         tinker_secret,
         redis_conn: conn_mgr.clone(),
         not_found_penalty_cache: NotFoundPenaltyCache::new(),
+        event_stream_hub: event_stream_hub.clone(),
+        client_addr_config: Arc::new(client_addr_config),
     };
 
     // Start background task for user restriction cache refresh
@@ -149,6 +147,9 @@ async fn main() -> anyhow::Result<()> {
     // Start background task for stats flush (every 30 seconds)
     start_stats_flush_task(stats_repo_for_flush, Duration::from_secs(30));
 
+    // Start background task forwarding creation events to the streaming clients
+    start_event_stream_subscriber(client, event_stream_hub);
+
     log::info!("Start application server with 0.0.0.0:8080");
 
     describe_counter!("token_request", "token request count from bbs.cgi by state");
@@ -166,15 +167,19 @@ async fn main() -> anyhow::Result<()> {
     );
     describe_counter!(
         "openai_moderation_requests",
-        "OpenAI moderation request count by result (success/error)"
+        "moderation request count by provider and result (success/error)"
     );
     describe_counter!(
         "openai_moderation_api_calls",
-        "individual HTTP calls to OpenAI moderation API including retries"
+        "individual HTTP calls to moderation providers including retries"
     );
     describe_counter!(
         "openai_moderation_retries",
-        "OpenAI moderation retry count (excludes first attempt)"
+        "moderation retry count by provider (excludes first attempt)"
+    );
+    describe_counter!(
+        "user_restriction_hit",
+        "restricted request count by subscription (manual for hand-made rules), rule type and action"
     );
 
     let app = create_app(app_state, conn_mgr);
@@ -190,8 +195,20 @@ async fn main() -> anyhow::Result<()> {
     .await
     .unwrap();
 
+    // Behind HAProxy or a load balancer sending the client address in a PROXY header,
+    // only trusted from the proxies themselves
+    let proxy_protocol =
+        (env::var("PROXY_PROTOCOL") == Ok("true".to_string())).then_some(trusted_proxies);
+    if proxy_protocol
+        .as_ref()
+        .is_some_and(|proxies| proxies.is_empty())
+    {
+        anyhow::bail!("PROXY_PROTOCOL needs the proxies sending it in TRUSTED_PROXIES");
+    }
+    let listener = ClientListener::new(listener, proxy_protocol);
+
     let app = NormalizePathLayer::trim_trailing_slash().layer(app);
-    let app = AxumServiceExt::<AxumRequest>::into_make_service(app);
+    let app = AxumServiceExt::<AxumRequest>::into_make_service_with_connect_info::<PeerAddr>(app);
     axum::serve(listener, app)
         .with_graceful_shutdown(graceful_shutdown_http())
         .await
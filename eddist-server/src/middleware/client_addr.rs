@@ -0,0 +1,49 @@
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use eddist_core::client_addr::ClientAddrConfig;
+use http::HeaderMap;
+
+use crate::{AppState, listener::PeerAddr};
+
+/// Client IP and ASN of a request, resolved once by [`client_addr_middleware`] for the
+/// routes and the other middlewares
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientAddr {
+    pub ip: Option<String>,
+    pub asn: Option<u32>,
+}
+
+impl ClientAddr {
+    pub fn resolve(
+        config: &ClientAddrConfig,
+        headers: &HeaderMap,
+        peer: Option<SocketAddr>,
+    ) -> Self {
+        let ip = config.client_ip(headers, peer);
+        Self {
+            ip: ip.map(|ip| ip.to_string()),
+            asn: config.client_asn(headers, peer, ip),
+        }
+    }
+}
+
+pub async fn client_addr_middleware(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    // Missing when the app is served without the connect info, as in the tests
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<PeerAddr>>()
+        .map(|ConnectInfo(PeerAddr(peer))| *peer);
+    let client_addr = ClientAddr::resolve(&state.client_addr_config, request.headers(), peer);
+    request.extensions_mut().insert(client_addr);
+
+    next.run(request).await
+}
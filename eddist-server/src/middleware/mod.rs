@@ -1,2 +1,3 @@
+pub mod client_addr;
 pub mod not_found_rate_limit;
 pub mod user_restriction;
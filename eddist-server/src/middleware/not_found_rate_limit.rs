@@ -14,7 +14,7 @@ use redis::AsyncCommands as _;
 
 use eddist_core::redis_keys::not_found_access_count_key;
 
-use crate::{AppState, utils::get_origin_ip};
+use crate::{AppState, middleware::client_addr::ClientAddr, utils::get_origin_ip};
 
 /// Window over which 404 accesses are counted.
 const NOT_FOUND_WINDOW_SECS: u64 = 60;
@@ -73,9 +73,15 @@ pub async fn not_found_rate_limit_middleware(
         return next.run(request).await;
     }
 
-    // No CDN-provided IP means the request bypassed the CDN (internal/probe
-    // traffic); there's nothing to key the 404 rate limiter on, so let it through.
-    let Some(ip) = get_origin_ip(request.headers()) else {
+    // No client IP means the request bypassed the CDN or the trusted proxies
+    // (internal/probe traffic); there's nothing to key the 404 rate limiter on, so
+    // let it through.
+    let client_addr = request
+        .extensions()
+        .get::<ClientAddr>()
+        .cloned()
+        .unwrap_or_default();
+    let Some(ip) = get_origin_ip(&client_addr) else {
         return next.run(request).await;
     };
     let ip = ip.to_string();
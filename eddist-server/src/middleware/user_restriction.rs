@@ -1,68 +1,213 @@
 use axum::{
+    body::Body,
     extract::{Request, State},
     http::{Method, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
 };
-use eddist_core::domain::user_restriction::UserRestrictionRule;
+use chrono::Utc;
+use eddist_core::{
+    domain::user_restriction::{RestrictionAction, RestrictionTarget, UserRestrictionRule},
+    redis_keys::user_restriction_slow_down_key,
+    user_restriction_hits::{RestrictionHitSample, record_rule_hit},
+};
+use metrics::counter;
+use redis::AsyncCommands;
+use uuid::Uuid;
 
 use crate::{
     AppState,
+    error::BbsCgiError,
+    middleware::client_addr::ClientAddr,
     services::{
         AppService,
         user_restriction_service::{UserRestrictionCheckInput, UserRestrictionCheckOutput},
     },
+    shiftjis::shift_jis_url_encodeded_body_to_vec,
     utils::{get_asn_num, get_origin_ip, get_ua},
 };
 
+/// Same as the default body limit of axum, bbs.cgi bodies are far smaller
+const MAX_BBS_CGI_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Put on a bbs.cgi request by a `REAUTH` rule, the authed token is sent to re-auth once
+/// per rule
+#[derive(Debug, Clone, Copy)]
+pub struct RestrictionReAuth {
+    pub rule_id: Uuid,
+}
+
+/// Put on an auth-code request by a `CAPTCHA` rule
+#[derive(Debug, Clone)]
+pub struct RestrictionCaptcha {
+    pub provider: String,
+}
+
 pub async fn user_restriction_middleware(
     State(state): State<AppState>,
     request: Request,
     next: Next,
 ) -> Response {
     // Check if this is a route we want to restrict
-    let path = request.uri().path();
-    let should_check = path.starts_with("/test/bbs.cgi")
-        || (path.starts_with("/auth-code") && request.method() == Method::POST);
+    let path = request.uri().path().to_string();
+    let is_bbs_cgi = path.starts_with("/test/bbs.cgi");
+    let is_auth_code_page = path.starts_with("/auth-code") && request.method() == Method::GET;
+    let is_auth_code = path.starts_with("/auth-code") && request.method() == Method::POST;
 
-    if !should_check {
+    if !is_bbs_cgi && !is_auth_code && !is_auth_code_page {
         return next.run(request).await;
     }
 
-    let headers = request.headers();
+    let client_addr = request
+        .extensions()
+        .get::<ClientAddr>()
+        .cloned()
+        .unwrap_or_default();
     let (Some(ip), Some(asn), Some(ua)) = (
-        get_origin_ip(headers),
-        get_asn_num(headers),
-        get_ua(headers),
+        get_origin_ip(&client_addr),
+        get_asn_num(&client_addr),
+        get_ua(request.headers()),
     ) else {
+        if is_auth_code_page {
+            return next.run(request).await;
+        }
         return (StatusCode::FORBIDDEN, "Access denied").into_response();
     };
+    let (ip, ua) = (ip.to_string(), ua.to_string());
+
+    // The board and whether it is a thread or a response are only known from the form
+    let (mut request, board_key, target) = if is_bbs_cgi {
+        let (parts, body) = request.into_parts();
+        let Ok(bytes) = axum::body::to_bytes(body, MAX_BBS_CGI_BODY_BYTES).await else {
+            return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+        };
+        let form = std::str::from_utf8(&bytes)
+            .ok()
+            .and_then(|body| shift_jis_url_encodeded_body_to_vec(body).ok())
+            .map(|form| {
+                let target = if form.get("submit").map(|x| x.as_str()) == Some("新規スレッド作成")
+                {
+                    RestrictionTarget::Thread
+                } else {
+                    RestrictionTarget::Response
+                };
+                (form.get("bbs").cloned(), target)
+            });
+        let request = Request::from_parts(parts, Body::from(bytes));
+        // A malformed form is rejected by bbs.cgi itself
+        let Some((board_key, target)) = form else {
+            return next.run(request).await;
+        };
+        (request, board_key, target)
+    } else {
+        (request, None, RestrictionTarget::AuthCode)
+    };
 
     let restriction_service = state.get_container().user_restriction();
 
     let check_input = UserRestrictionCheckInput {
-        ip: ip.to_string(),
+        ip: ip.clone(),
         asn,
-        user_agent: ua.to_string(),
+        user_agent: ua.clone(),
+        board_key: board_key.clone(),
+        target,
     };
 
-    match restriction_service.execute(check_input).await {
-        Ok(UserRestrictionCheckOutput { matching_rule }) => {
-            if let Some(UserRestrictionRule {
-                name,
-                rule_type,
-                rule_value,
-                ..
-            }) = matching_rule
-            {
-                tracing::warn!(
-                    "Request blocked by user restriction filter: IP={ip}, ASN={asn}, UA={ua}, path={path}; rule={name}, {rule_type}, {rule_value}"
-                );
+    let rule = match restriction_service.execute(check_input).await {
+        Ok(UserRestrictionCheckOutput {
+            matching_rule: Some(rule),
+        }) => rule,
+        Ok(UserRestrictionCheckOutput {
+            matching_rule: None,
+        }) => return next.run(request).await,
+        Err(e) => {
+            tracing::error!("Error checking user restrictions: {}", e);
+            return next.run(request).await;
+        }
+    };
+    let UserRestrictionRule {
+        id,
+        name,
+        rule_type,
+        rule_value,
+        action,
+        ..
+    } = &rule;
+
+    // Only the captcha is put on the page, the form post gets the other actions
+    if is_auth_code_page && *action != RestrictionAction::Captcha {
+        return next.run(request).await;
+    }
+    tracing::warn!(
+        "Request restricted by user restriction filter: IP={ip}, ASN={asn}, UA={ua}, path={path}, board={board_key:?}, target={target}; rule={name}, {rule_type}, {rule_value}, action={action}"
+    );
+    // Labelled by subscription rather than by rule, which would make a series per rule and a
+    // new one whenever an import creates rules. Per-rule hits are recorded below.
+    let subscription = rule
+        .subscription_id
+        .map_or_else(|| "manual".to_string(), |id| id.to_string());
+    counter!(
+        "user_restriction_hit",
+        "subscription" => subscription,
+        "rule_type" => rule_type.as_str(),
+        "action" => action.as_str()
+    )
+    .increment(1);
+    let sample = RestrictionHitSample {
+        ip: ip.clone(),
+        asn,
+        user_agent: ua,
+        board_key,
+        target,
+        hit_at: Utc::now(),
+    };
+    if let Err(e) = record_rule_hit(&mut state.redis_conn.clone(), *id, &sample).await {
+        tracing::error!("Failed to record the hit of a user restriction rule: {e}");
+    }
+
+    match action {
+        RestrictionAction::Block => (StatusCode::FORBIDDEN, "Access denied").into_response(),
+        RestrictionAction::ReAuth => {
+            request
+                .extensions_mut()
+                .insert(RestrictionReAuth { rule_id: *id });
+            next.run(request).await
+        }
+        RestrictionAction::Captcha => {
+            let Some(provider) = rule.captcha_provider.clone() else {
                 return (StatusCode::FORBIDDEN, "Access denied").into_response();
+            };
+            request
+                .extensions_mut()
+                .insert(RestrictionCaptcha { provider });
+            next.run(request).await
+        }
+        RestrictionAction::SlowDown => {
+            let key = user_restriction_slow_down_key(&id.to_string(), &ip);
+            let mut conn = state.redis_conn.clone();
+            let acquired = redis::cmd("SET")
+                .arg(&key)
+                .arg(1)
+                .arg("NX")
+                .arg("EX")
+                .arg(rule.slow_down_seconds.max(1))
+                .query_async::<Option<String>>(&mut conn)
+                .await;
+            match acquired {
+                Ok(Some(_)) => next.run(request).await,
+                Ok(None) => {
+                    let wait_sec = conn.ttl::<_, i64>(&key).await.unwrap_or(1).max(1) as u32;
+                    if is_bbs_cgi {
+                        BbsCgiError::ResCreationSpanRestriction { wait_sec }.into_response()
+                    } else {
+                        (StatusCode::TOO_MANY_REQUESTS, "Too many requests").into_response()
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to apply the slow down of a user restriction: {e}");
+                    next.run(request).await
+                }
             }
         }
-        Err(e) => tracing::error!("Error checking user restrictions: {}", e),
     }
-
-    next.run(request).await
 }
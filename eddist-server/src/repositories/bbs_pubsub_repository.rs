@@ -12,7 +12,7 @@ use redis::{AsyncCommands, aio::ConnectionManager};
 use super::bbs_repository::CreatingThread;
 use eddist_core::redis_keys::{
     CHANNEL_AUTH_TOKEN_INITIATED, CHANNEL_AUTH_TOKEN_REQUESTED, CHANNEL_AUTH_TOKEN_SUCCEEDED,
-    CHANNEL_RES_CREATED, CHANNEL_THREAD_CREATED,
+    CHANNEL_RES_CREATED, CHANNEL_RES_MODERATED, CHANNEL_THREAD_CREATED, CHANNEL_THREAD_MODERATED,
 };
 
 #[derive(Clone)]
@@ -64,6 +64,8 @@ impl RedisCreationEventRepository {
 pub trait CreationEventRepository: Clone + 'static + Send + Sync {
     async fn publish_res_created(&self, event: CreatingRes) -> Result<(), anyhow::Error>;
     async fn publish_thread_created(&self, event: CreatingThread) -> Result<(), anyhow::Error>;
+    async fn publish_res_moderated(&self, event: &CreatingRes) -> Result<(), anyhow::Error>;
+    async fn publish_thread_moderated(&self, event: &CreatingThread) -> Result<(), anyhow::Error>;
     async fn publish_auth_token_initiated(
         &self,
         event: AuthTokenInitiated,
@@ -90,6 +92,16 @@ impl CreationEventRepository for RedisCreationEventRepository {
             .await
     }
 
+    async fn publish_res_moderated(&self, event: &CreatingRes) -> Result<(), anyhow::Error> {
+        self.publish_bytes(CHANNEL_RES_MODERATED, &encode_creating_res(event))
+            .await
+    }
+
+    async fn publish_thread_moderated(&self, event: &CreatingThread) -> Result<(), anyhow::Error> {
+        self.publish_bytes(CHANNEL_THREAD_MODERATED, &encode_creating_thread(event))
+            .await
+    }
+
     async fn publish_auth_token_initiated(
         &self,
         event: AuthTokenInitiated,
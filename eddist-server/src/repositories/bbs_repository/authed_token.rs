@@ -42,6 +42,7 @@ pub trait AuthedTokenRepository: Send + Sync + 'static {
         tx: sqlx::Transaction<'a, sqlx::MySql>,
     ) -> anyhow::Result<sqlx::Transaction<'a, sqlx::MySql>>;
     async fn clear_require_reauth(&self, id: Uuid) -> anyhow::Result<()>;
+    async fn set_require_reauth(&self, id: Uuid) -> anyhow::Result<()>;
 }
 
 #[async_trait::async_trait]
@@ -297,6 +298,15 @@ impl AuthedTokenRepository for BbsRepositoryImpl {
 
         Ok(())
     }
+
+    async fn set_require_reauth(&self, id: Uuid) -> anyhow::Result<()> {
+        sqlx::query("UPDATE authed_tokens SET require_reauth = 1 WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
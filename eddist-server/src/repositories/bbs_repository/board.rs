@@ -1,6 +1,8 @@
+use chrono::NaiveDateTime;
 use eddist_core::domain::{
-    board::{Board, BoardInfo},
+    board::{Board, BoardInfo, BoardModerationMode},
     cap::Cap,
+    ng_word::{NgWordAction, NgWordMatchType, NgWordTarget},
 };
 use sqlx::query_as;
 use uuid::Uuid;
@@ -9,6 +11,79 @@ use crate::domain::ng_word::NgWord;
 
 use super::BbsRepositoryImpl;
 
+#[derive(Debug)]
+struct SelectionNgWord {
+    id: Uuid,
+    name: String,
+    word: String,
+    match_type: String,
+    target_fields: String,
+    action: String,
+    penalty_seconds: u32,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+}
+
+#[derive(Debug)]
+struct SelectionBoardInfo {
+    id: Uuid,
+    local_rules: String,
+    base_thread_creation_span_sec: i32,
+    base_response_creation_span_sec: i32,
+    max_thread_name_byte_length: i32,
+    max_author_name_byte_length: i32,
+    max_email_byte_length: i32,
+    max_response_body_byte_length: i32,
+    max_response_body_lines: i32,
+    threads_archive_cron: Option<String>,
+    threads_archive_trigger_thread_count: Option<i32>,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    read_only: bool,
+    force_metadent_type: Option<String>,
+    enable_1001_message: bool,
+    custom_1001_message: Option<String>,
+    moderation_mode: String,
+    moderation_level_threshold: u32,
+    moderation_config: Option<serde_json::Value>,
+}
+
+impl From<SelectionBoardInfo> for BoardInfo {
+    fn from(x: SelectionBoardInfo) -> Self {
+        BoardInfo {
+            id: x.id,
+            local_rules: x.local_rules,
+            base_thread_creation_span_sec: x.base_thread_creation_span_sec,
+            base_response_creation_span_sec: x.base_response_creation_span_sec,
+            max_thread_name_byte_length: x.max_thread_name_byte_length,
+            max_author_name_byte_length: x.max_author_name_byte_length,
+            max_email_byte_length: x.max_email_byte_length,
+            max_response_body_byte_length: x.max_response_body_byte_length,
+            max_response_body_lines: x.max_response_body_lines,
+            threads_archive_cron: x.threads_archive_cron,
+            threads_archive_trigger_thread_count: x.threads_archive_trigger_thread_count,
+            created_at: x.created_at,
+            updated_at: x.updated_at,
+            read_only: x.read_only,
+            force_metadent_type: x.force_metadent_type,
+            enable_1001_message: x.enable_1001_message,
+            custom_1001_message: x.custom_1001_message,
+            moderation_mode: x.moderation_mode.parse().unwrap_or_else(|e| {
+                tracing::warn!("unknown moderation mode, moderation is disabled: {e}");
+                BoardModerationMode::Off
+            }),
+            moderation_level_threshold: x.moderation_level_threshold,
+            moderation_config: x.moderation_config.and_then(|v| {
+                serde_json::from_value(v)
+                    .inspect_err(|e| {
+                        tracing::warn!("invalid moderation config, the default is used: {e}")
+                    })
+                    .ok()
+            }),
+        }
+    }
+}
+
 #[async_trait::async_trait]
 pub trait BoardRepository: Send + Sync + 'static {
     async fn get_boards(&self) -> anyhow::Result<Vec<Board>>;
@@ -67,8 +142,8 @@ impl BoardRepository for BbsRepositoryImpl {
     }
 
     async fn get_board_info(&self, board_id: Uuid) -> anyhow::Result<Option<BoardInfo>> {
-        Ok(query_as!(
-            BoardInfo,
+        let selection = query_as!(
+            SelectionBoardInfo,
             r#"
         SELECT
             id AS "id: Uuid",
@@ -87,23 +162,32 @@ impl BoardRepository for BbsRepositoryImpl {
             read_only AS "read_only: bool",
             force_metadent_type,
             enable_1001_message AS "enable_1001_message: bool",
-            custom_1001_message
+            custom_1001_message,
+            moderation_mode,
+            moderation_level_threshold,
+            moderation_config AS "moderation_config: serde_json::Value"
         FROM boards_info
         WHERE id = ?
         "#,
             board_id
         )
         .fetch_optional(&self.pool)
-        .await?)
+        .await?;
+
+        Ok(selection.map(BoardInfo::from))
     }
 
     async fn get_ng_words_by_board_key(&self, board_key: &str) -> anyhow::Result<Vec<NgWord>> {
-        let ng_words = sqlx::query_as!(
-            NgWord,
+        let selections = sqlx::query_as!(
+            SelectionNgWord,
             r#"SELECT
                 nw.id AS "id: Uuid",
                 nw.name AS name,
                 nw.word AS word,
+                nw.match_type AS match_type,
+                nw.target_fields AS target_fields,
+                nw.action AS action,
+                nw.penalty_seconds AS penalty_seconds,
                 nw.created_at AS created_at,
                 nw.updated_at AS updated_at
             FROM ng_words AS nw
@@ -118,6 +202,35 @@ impl BoardRepository for BbsRepositoryImpl {
         .fetch_all(&self.pool)
         .await?;
 
+        let mut ng_words = Vec::with_capacity(selections.len());
+        for selection in selections {
+            let (Ok(match_type), Ok(targets), Ok(action)) = (
+                selection.match_type.parse::<NgWordMatchType>(),
+                NgWordTarget::parse_list(&selection.target_fields),
+                selection.action.parse::<NgWordAction>(),
+            ) else {
+                tracing::warn!(
+                    "Skipping ng word with invalid options: id={}, match_type={}, target_fields={}, action={}",
+                    selection.id,
+                    selection.match_type,
+                    selection.target_fields,
+                    selection.action
+                );
+                continue;
+            };
+            ng_words.push(NgWord {
+                id: selection.id,
+                name: selection.name,
+                word: selection.word,
+                match_type,
+                targets,
+                action,
+                penalty_seconds: selection.penalty_seconds,
+                created_at: selection.created_at,
+                updated_at: selection.updated_at,
+            });
+        }
+
         Ok(ng_words)
     }
 
@@ -1,11 +1,13 @@
 mod authed_token;
 mod board;
+mod moderation_queue;
 mod response;
 mod thread;
 
 pub use authed_token::{AuthedTokenRepository, CreatingAuthedToken};
 pub use board::BoardRepository;
 pub use eddist_core::domain::pubsub_repository::CreatingThread;
+pub use moderation_queue::ModerationQueueRepository;
 pub use response::ResponseRepository;
 pub use thread::{ThreadRepository, ThreadStatus};
 
@@ -13,7 +15,11 @@ use sqlx::MySqlPool;
 
 #[async_trait::async_trait]
 pub trait BbsRepository:
-    BoardRepository + ThreadRepository + ResponseRepository + AuthedTokenRepository
+    BoardRepository
+    + ThreadRepository
+    + ResponseRepository
+    + AuthedTokenRepository
+    + ModerationQueueRepository
 {
 }
 
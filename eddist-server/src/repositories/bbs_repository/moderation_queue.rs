@@ -0,0 +1,64 @@
+use chrono::Utc;
+use eddist_core::domain::moderation_queue::{HeldPost, HoldReason};
+use uuid::Uuid;
+
+use super::BbsRepositoryImpl;
+
+#[async_trait::async_trait]
+pub trait ModerationQueueRepository: Send + Sync + 'static {
+    async fn enqueue_held_post(
+        &self,
+        post: HeldPost,
+        reason: HoldReason,
+        ng_word_id: Option<Uuid>,
+    ) -> anyhow::Result<Uuid>;
+}
+
+#[async_trait::async_trait]
+impl ModerationQueueRepository for BbsRepositoryImpl {
+    async fn enqueue_held_post(
+        &self,
+        post: HeldPost,
+        reason: HoldReason,
+        ng_word_id: Option<Uuid>,
+    ) -> anyhow::Result<Uuid> {
+        let id = Uuid::now_v7();
+        let (board_id, thread_id, authed_token_id) = match &post {
+            HeldPost::Response(res) => (res.board_id, res.thread_id, res.authed_token_id),
+            HeldPost::Thread(th) => (th.board_id, th.thread_id, th.authed_token_id),
+        };
+        let payload = serde_json::to_string(&post)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO moderation_queue
+                (
+                    id,
+                    board_id,
+                    thread_id,
+                    authed_token_id,
+                    post_type,
+                    payload,
+                    reason,
+                    ng_word_id,
+                    status,
+                    created_at
+                )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, 'PENDING', ?)
+            "#,
+        )
+        .bind(id)
+        .bind(board_id)
+        .bind(thread_id)
+        .bind(authed_token_id)
+        .bind(post.post_type())
+        .bind(payload)
+        .bind(reason.as_str())
+        .bind(ng_word_id)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+}
@@ -8,13 +8,14 @@ use super::BbsRepositoryImpl;
 
 #[async_trait::async_trait]
 pub trait ResponseRepository: Send + Sync + 'static {
-    async fn get_responses(&self, thread_id: Uuid) -> anyhow::Result<Vec<ResView>>;
+    /// Responses of the thread in dat order, from the `start`th (0-based) on
+    async fn get_responses(&self, thread_id: Uuid, start: usize) -> anyhow::Result<Vec<ResView>>;
     async fn create_response(&self, res: CreatingRes) -> anyhow::Result<()>;
 }
 
 #[async_trait::async_trait]
 impl ResponseRepository for BbsRepositoryImpl {
-    async fn get_responses(&self, thread_id: Uuid) -> anyhow::Result<Vec<ResView>> {
+    async fn get_responses(&self, thread_id: Uuid, start: usize) -> anyhow::Result<Vec<ResView>> {
         let responses = sqlx::query_as!(
             SelectionRes,
             r#"SELECT
@@ -25,8 +26,10 @@ impl ResponseRepository for BbsRepositoryImpl {
                 author_id,
                 is_abone AS "is_abone: bool"
             FROM responses WHERE thread_id = ?
-            ORDER BY res_order, id"#,
-            thread_id
+            ORDER BY res_order, id
+            LIMIT 1000000 OFFSET ?"#,
+            thread_id,
+            start as u64
         )
         .fetch_all(&self.pool)
         .await?;
@@ -60,7 +63,7 @@ impl ResponseRepository for BbsRepositoryImpl {
                 ),
                 active = (
                     CASE
-                        WHEN response_count >= 1000 THEN 0
+                        WHEN response_count >= 1000 OR active = 0 THEN 0
                         ELSE 1
                     END
                 )
@@ -87,13 +90,14 @@ impl ResponseRepository for BbsRepositoryImpl {
                     authed_token_id,
                     created_at,
                     client_info,
+                    is_abone,
                     res_order
                 )
                 VALUES
                 (
                     ?, ?, ?, ?, ?,
                     ?, ?, ?, ?, ?,
-                    ?, ?
+                    ?, ?, ?
                 )",
             res_id,
             res.name,
@@ -106,6 +110,7 @@ impl ResponseRepository for BbsRepositoryImpl {
             res.authed_token_id,
             res.created_at,
             client_info_json,
+            res.is_abone,
             res.res_order,
         );
 
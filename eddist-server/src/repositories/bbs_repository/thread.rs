@@ -327,13 +327,14 @@ impl ThreadRepository for BbsRepositoryImpl {
                     authed_token_id,
                     created_at,
                     client_info,
+                    is_abone,
                     res_order
                 )
                 VALUES
                 (
                     ?, ?, ?, ?, ?,
                     ?, ?, ?, ?, ?,
-                    ?, 1
+                    ?, ?, 1
                 )",
             response_id,
             thread.name,
@@ -346,6 +347,7 @@ impl ThreadRepository for BbsRepositoryImpl {
             thread.authed_token_id,
             thread.created_at,
             client_info_json,
+            thread.is_abone,
         );
 
         let mut tx = self.pool.begin().await?;
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use eddist_core::domain::search::rarest_ngrams;
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+
+/// Postings of the rarest n-gram of a query scanned at most, response IDs being UUIDv7 these
+/// are the newest posts having it
+const MAX_SCANNED_POSTINGS: u32 = 20000;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SearchHit {
+    pub board_key: String,
+    pub thread_number: i64,
+    pub thread_title: String,
+    pub res_order: i32,
+    pub body: String,
+    pub author_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[async_trait::async_trait]
+pub trait SearchRepository: Send + Sync + 'static {
+    /// Returns the newest unaboned posts having the rarest n-grams of the query, which may
+    /// still not contain the query itself
+    async fn search_candidates(
+        &self,
+        ngrams: &[String],
+        board_key: Option<&str>,
+        limit: u32,
+    ) -> anyhow::Result<Vec<SearchHit>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchRepositoryImpl {
+    pool: MySqlPool,
+}
+
+impl SearchRepositoryImpl {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchRepository for SearchRepositoryImpl {
+    async fn search_candidates(
+        &self,
+        ngrams: &[String],
+        board_key: Option<&str>,
+        limit: u32,
+    ) -> anyhow::Result<Vec<SearchHit>> {
+        let Some(ngrams) = lookup_ngrams(&self.pool, ngrams).await? else {
+            return Ok(Vec::new());
+        };
+
+        // Driven by the newest postings of the rarest n-gram, the others only being probed
+        let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+            r#"
+            SELECT
+                d.board_key,
+                d.thread_number,
+                d.thread_title,
+                d.res_order,
+                d.body,
+                d.author_id,
+                d.created_at
+            FROM (
+                SELECT response_id FROM search_ngrams WHERE ngram = "#,
+        );
+        builder.push_bind(ngrams[0].clone());
+        builder.push(" ORDER BY response_id DESC LIMIT ");
+        builder.push_bind(MAX_SCANNED_POSTINGS);
+        builder.push(
+            ") AS n JOIN search_documents AS d ON d.response_id = n.response_id WHERE d.is_abone = 0",
+        );
+        for ngram in &ngrams[1..] {
+            builder.push(
+                " AND EXISTS (SELECT 1 FROM search_ngrams AS o WHERE o.response_id = n.response_id AND o.ngram = ",
+            );
+            builder.push_bind(ngram.clone());
+            builder.push(")");
+        }
+        if let Some(board_key) = board_key {
+            builder.push(" AND d.board_key = ");
+            builder.push_bind(board_key.to_string());
+        }
+        builder.push(" ORDER BY d.created_at DESC LIMIT ");
+        builder.push_bind(limit);
+
+        let hits = builder
+            .build_query_as::<SearchHit>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(hits)
+    }
+}
+
+/// The n-grams to look up, rarest first, `None` when one of them is in no document
+async fn lookup_ngrams(pool: &MySqlPool, ngrams: &[String]) -> anyhow::Result<Option<Vec<String>>> {
+    if ngrams.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder: QueryBuilder<MySql> =
+        QueryBuilder::new("SELECT ngram, doc_count FROM search_ngram_stats WHERE ngram IN (");
+    let mut separated = builder.separated(", ");
+    for ngram in ngrams {
+        separated.push_bind(ngram.clone());
+    }
+    builder.push(")");
+    let doc_counts = builder
+        .build_query_as::<(String, i64)>()
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|(ngram, count)| (ngram, count.max(0) as u64))
+        .collect::<HashMap<_, _>>();
+
+    Ok(rarest_ngrams(ngrams, &doc_counts))
+}
@@ -1,5 +1,8 @@
 use async_trait::async_trait;
-use eddist_core::domain::user_restriction::{RestrictionRuleType, UserRestrictionRule};
+use chrono::NaiveDateTime;
+use eddist_core::domain::user_restriction::{
+    RestrictionAction, RestrictionRuleType, RestrictionTarget, UserRestrictionRule,
+};
 use sqlx::{MySql, Pool};
 use uuid::Uuid;
 
@@ -19,17 +22,71 @@ impl UserRestrictionRepositoryImpl {
     }
 }
 
+#[derive(Debug)]
+struct SelectionUserRestrictionRule {
+    id: Uuid,
+    name: String,
+    rule_type: String,
+    rule_value: String,
+    board_keys: Option<serde_json::Value>,
+    targets: String,
+    action: String,
+    captcha_provider: Option<String>,
+    slow_down_seconds: u32,
+    expires_at: Option<NaiveDateTime>,
+    subscription_id: Option<Uuid>,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    created_by_email: String,
+}
+
+impl TryFrom<SelectionUserRestrictionRule> for UserRestrictionRule {
+    type Error = anyhow::Error;
+
+    fn try_from(x: SelectionUserRestrictionRule) -> anyhow::Result<Self> {
+        Ok(UserRestrictionRule {
+            id: x.id,
+            name: x.name,
+            rule_type: x
+                .rule_type
+                .parse::<RestrictionRuleType>()
+                .map_err(|e| anyhow::anyhow!("Invalid rule type '{}': {}", x.rule_type, e))?,
+            rule_value: x.rule_value,
+            board_keys: x.board_keys.map(serde_json::from_value).transpose()?,
+            targets: RestrictionTarget::parse_list(&x.targets).map_err(anyhow::Error::msg)?,
+            action: x
+                .action
+                .parse::<RestrictionAction>()
+                .map_err(anyhow::Error::msg)?,
+            captcha_provider: x.captcha_provider,
+            slow_down_seconds: x.slow_down_seconds,
+            expires_at: x.expires_at.map(|dt| dt.and_utc()),
+            subscription_id: x.subscription_id,
+            created_at: x.created_at.and_utc(),
+            updated_at: x.updated_at.and_utc(),
+            created_by_email: x.created_by_email,
+        })
+    }
+}
+
 #[async_trait]
 impl UserRestrictionRepository for UserRestrictionRepositoryImpl {
     async fn get_all_active_rules(&self) -> anyhow::Result<Vec<UserRestrictionRule>> {
-        let rows = sqlx::query!(
+        let rows = sqlx::query_as!(
+            SelectionUserRestrictionRule,
             r#"
-            SELECT 
-                BIN_TO_UUID(id) as id,
+            SELECT
+                id AS "id: Uuid",
                 name,
                 rule_type,
                 rule_value,
+                board_keys AS "board_keys: serde_json::Value",
+                targets,
+                action,
+                captcha_provider,
+                slow_down_seconds,
                 expires_at,
+                subscription_id AS "subscription_id: Uuid",
                 created_at,
                 updated_at,
                 created_by_email
@@ -41,27 +98,8 @@ impl UserRestrictionRepository for UserRestrictionRepositoryImpl {
         .fetch_all(&self.pool)
         .await?;
 
-        let mut rules = Vec::with_capacity(rows.len());
-        for row in rows {
-            if let Some(id) = row.id {
-                let rule_type = row
-                    .rule_type
-                    .parse::<RestrictionRuleType>()
-                    .map_err(|e| anyhow::anyhow!("Invalid rule type '{}': {}", row.rule_type, e))?;
-
-                rules.push(UserRestrictionRule {
-                    id: Uuid::parse_str(&id)?,
-                    name: row.name,
-                    rule_type,
-                    rule_value: row.rule_value,
-                    expires_at: row.expires_at.map(|dt| dt.and_utc()),
-                    created_at: row.created_at.and_utc(),
-                    updated_at: row.updated_at.and_utc(),
-                    created_by_email: row.created_by_email,
-                });
-            }
-        }
-
-        Ok(rules)
+        rows.into_iter()
+            .map(UserRestrictionRule::try_from)
+            .collect()
     }
 }
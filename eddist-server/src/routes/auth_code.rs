@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use axum::{
-    Form,
+    Extension, Form,
     extract::State,
     http::StatusCode,
     response::{Html, IntoResponse},
@@ -16,11 +16,14 @@ use crate::{
     AppState,
     domain::captcha_like::CaptchaProviderConfig,
     error::BbsPostAuthWithCodeError,
+    middleware::{client_addr::ClientAddr, user_restriction::RestrictionCaptcha},
     services::{
         AppService,
         auth_with_code_service::{AuthWithCodeServiceInput, AuthWithCodeServiceOutput},
         bind_token_to_user_service::BindTokenToUserServiceInput,
-        captcha_config_cache::get_cached_captcha_configs_for_auth_code,
+        captcha_config_cache::{
+            get_cached_captcha_config_by_name, get_cached_captcha_configs_for_auth_code,
+        },
     },
     utils::{get_asn_num, get_origin_ip, get_ua},
 };
@@ -92,9 +95,35 @@ pub fn build_template_variables(configs: &[CaptchaProviderConfig]) -> serde_json
     })
 }
 
+/// Captcha configs of the auth-code page, with the one forced by a user restriction rule.
+/// `None` if the forced provider is not configured, the request is refused then.
+async fn get_auth_code_captcha_configs(
+    forced: Option<&RestrictionCaptcha>,
+) -> Option<Vec<CaptchaProviderConfig>> {
+    let mut configs = get_cached_captcha_configs_for_auth_code().await;
+    if let Some(RestrictionCaptcha { provider }) = forced
+        && !configs.iter().any(|c| &c.name == provider)
+    {
+        let Some(config) = get_cached_captcha_config_by_name(provider).await else {
+            log::warn!(
+                "Captcha provider forced by a user restriction rule is not found: {provider}"
+            );
+            return None;
+        };
+        configs.push(config);
+    }
+    Some(configs)
+}
+
 // NOTE: this system will be changed in the future
-pub async fn get_auth_code(State(state): State<AppState>) -> impl IntoResponse {
-    let captcha_configs = get_cached_captcha_configs_for_auth_code().await;
+pub async fn get_auth_code(
+    State(state): State<AppState>,
+    forced_captcha: Option<Extension<RestrictionCaptcha>>,
+) -> impl IntoResponse {
+    let Some(captcha_configs) = get_auth_code_captcha_configs(forced_captcha.as_deref()).await
+    else {
+        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    };
     let template_vars = build_template_variables(&captcha_configs);
 
     let html = state
@@ -111,18 +140,23 @@ pub async fn get_auth_code(State(state): State<AppState>) -> impl IntoResponse {
 
 pub async fn post_auth_code(
     headers: HeaderMap,
+    Extension(client_addr): Extension<ClientAddr>,
     jar: CookieJar,
     State(state): State<AppState>,
+    forced_captcha: Option<Extension<RestrictionCaptcha>>,
     Form(form): Form<HashMap<String, String>>,
 ) -> impl IntoResponse {
     let rate_limit_token = jar
         .get("auth_rate_limit")
         .map(|cookie| cookie.value().to_string());
-    let captcha_configs = get_cached_captcha_configs_for_auth_code().await;
+    let Some(captcha_configs) = get_auth_code_captcha_configs(forced_captcha.as_deref()).await
+    else {
+        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    };
     let (Some(origin_ip), Some(user_agent), Some(asn_num)) = (
-        get_origin_ip(&headers),
+        get_origin_ip(&client_addr),
         get_ua(&headers),
-        get_asn_num(&headers),
+        get_asn_num(&client_addr),
     ) else {
         return (StatusCode::FORBIDDEN, "Access denied").into_response();
     };
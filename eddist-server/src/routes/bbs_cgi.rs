@@ -1,4 +1,5 @@
 use axum::{
+    Extension,
     body::Body,
     extract::State,
     http::StatusCode,
@@ -12,6 +13,7 @@ use jsonwebtoken::EncodingKey;
 use crate::{
     AppState,
     error::{BbsCgiError, InsufficientParamType, InvalidParamType},
+    middleware::{client_addr::ClientAddr, user_restriction::RestrictionReAuth},
     services::{
         AppService, BbsCgiService,
         bind_token_to_user_service::BindTokenToUserServiceInput,
@@ -26,8 +28,10 @@ use crate::{
 
 pub async fn post_bbs_cgi(
     headers: HeaderMap,
+    Extension(client_addr): Extension<ClientAddr>,
     jar: CookieJar,
     State(state): State<AppState>,
+    restriction_reauth: Option<Extension<RestrictionReAuth>>,
     body: String,
 ) -> Response {
     let Ok(form) = shift_jis_url_encodeded_body_to_vec(&body) else {
@@ -46,9 +50,9 @@ pub async fn post_bbs_cgi(
     };
 
     let (Some(origin_ip), Some(ua), Some(asn_num)) = (
-        get_origin_ip(&headers),
+        get_origin_ip(&client_addr),
         get_ua(&headers),
-        get_asn_num(&headers),
+        get_asn_num(&client_addr),
     ) else {
         return (StatusCode::FORBIDDEN, "Access denied").into_response();
     };
@@ -132,6 +136,7 @@ pub async fn post_bbs_cgi(
                 user_agent: ua.to_string(),
                 asn_num,
                 require_user_registration,
+                reauth_rule_id: restriction_reauth.map(|x| x.rule_id),
             })
             .await
         {
@@ -170,6 +175,7 @@ pub async fn post_bbs_cgi(
                 user_agent: ua.to_string(),
                 asn_num,
                 require_user_registration,
+                reauth_rule_id: restriction_reauth.map(|x| x.rule_id),
             })
             .await
         {
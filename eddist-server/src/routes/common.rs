@@ -0,0 +1,81 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{body::Body, response::Response};
+use chrono::Utc;
+use eddist_core::thread_momentum::get_threads_momentum;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    domain::thread_list::{ThreadListQuery, ThreadListSort},
+    routes::safe_mode::get_unsafe_threads,
+    services::{
+        AppService,
+        board_info_service::{BoardInfoServiceInput, BoardInfoServiceOutput},
+    },
+};
+
+pub(crate) fn not_found() -> Response {
+    Response::builder().status(404).body(Body::empty()).unwrap()
+}
+
+pub(crate) fn base_url() -> String {
+    std::env::var("BASE_URL")
+        .unwrap_or_default()
+        .trim_end_matches('/')
+        .to_string()
+}
+
+pub(crate) async fn get_board_info(
+    state: &AppState,
+    board_key: &str,
+) -> Result<BoardInfoServiceOutput, Response> {
+    state
+        .get_container()
+        .board_info()
+        .execute(BoardInfoServiceInput {
+            board_key: board_key.to_string(),
+        })
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("board not found") {
+                not_found()
+            } else {
+                log::error!("Failed to get board info: {e:?}");
+                Response::builder().status(500).body(Body::empty()).unwrap()
+            }
+        })
+}
+
+/// The unsafe threads of the board, only looked up when the query excludes them
+pub(crate) async fn get_excluded_threads(
+    state: &AppState,
+    query: &ThreadListQuery,
+    board_id: Uuid,
+) -> HashSet<u64> {
+    if query.exclude_unsafe {
+        get_unsafe_threads(state, board_id).await
+    } else {
+        HashSet::new()
+    }
+}
+
+/// The momentum of the threads, only looked up when the query sorts by it
+pub(crate) async fn get_sort_momentum(
+    state: &AppState,
+    query: &ThreadListQuery,
+    thread_ids: impl Iterator<Item = Uuid>,
+) -> HashMap<Uuid, u64> {
+    if query.sort != ThreadListSort::Momentum {
+        return HashMap::new();
+    }
+    let thread_ids = thread_ids.collect::<Vec<_>>();
+    let mut redis_conn = state.redis_conn.clone();
+    get_threads_momentum(&mut redis_conn, &thread_ids, Utc::now())
+        .await
+        .unwrap_or_else(|e| {
+            // Sorted by bump order instead
+            log::error!("Failed to get thread momentum: {e:?}");
+            HashMap::new()
+        })
+}
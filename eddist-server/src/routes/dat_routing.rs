@@ -1,22 +1,27 @@
-use core::str;
-
 use axum::{
     body::Body,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::{IntoResponse, Response},
 };
+use axum_extra::extract::CookieJar;
 use chrono::Utc;
-use eddist_core::domain::{board::validate_board_key, sjis_str::SJisStr};
+use eddist_core::{
+    archived_dat::DatCompression,
+    domain::{board::validate_board_key, sjis_str::SJisStr},
+};
 use http::{HeaderMap, StatusCode};
+use serde::Deserialize;
 
 use crate::{
     AppState,
+    domain::service::shadow_abone_service::{ShadowAboneService, overlay_original_lines},
     services::{
-        AppService, kako_thread_retrieval_service::KakoThreadRetrievalServiceInput,
-        thread_retrieval_service::ThreadRetrievalServiceInput,
+        AppService,
+        kako_thread_retrieval_service::KakoThreadRetrievalServiceInput,
+        thread_retrieval_service::{DatStart, ThreadRetrievalServiceInput, slice_dat},
     },
     shiftjis::{SJisResponseBuilder, SjisContentType},
-    utils::get_ua,
+    utils::{accepts_encoding, allows_precompressed_dat, get_ua},
 };
 
 /// Extracts the byte-size suffix from a dat ETag of the form `W/"board-thread-SIZE"`.
@@ -27,10 +32,34 @@ fn parse_etag_byte_size(inm: &str) -> Option<usize> {
     inner.rsplit('-').next()?.parse().ok()
 }
 
+/// Returns the start offset of a `Range: bytes=N-` header, which is ignored for Xeno
+fn range_start(range: Option<&str>, ua: Option<&str>) -> Result<Option<usize>, StatusCode> {
+    match (range, ua) {
+        (Some(range), Some(ua)) if !ua.contains("Xeno") => match range.split('=').nth(1) {
+            Some(range) => range
+                .split('-')
+                .next()
+                .and_then(|x| x.parse::<usize>().ok())
+                .map(Some)
+                .ok_or(StatusCode::BAD_REQUEST),
+            None => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DatQuery {
+    /// Only the responses from this number on are returned, taking precedence over `Range`
+    from: Option<usize>,
+}
+
 pub async fn get_dat_txt(
     State(state): State<AppState>,
     headers: HeaderMap,
+    jar: CookieJar,
     Path((board_key, thread_id_with_dat)): Path<(String, String)>,
+    Query(query): Query<DatQuery>,
 ) -> Response {
     if thread_id_with_dat.len() != 14 {
         return Response::builder().status(404).body(Body::empty()).unwrap();
@@ -45,12 +74,40 @@ pub async fn get_dat_txt(
         return Response::builder().status(404).body(Body::empty()).unwrap();
     }
 
+    // Shadow-aboned responses are shown in their original form to the poster only
+    let shadow_lines = match jar.get("edge-token") {
+        Some(edge_token) => ShadowAboneService::new(state.redis_conn.clone())
+            .get_original_lines(&board_key, thread_number_num as u64, edge_token.value())
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!("failed to get shadow-aboned lines: {e}");
+                Default::default()
+            }),
+        None => Default::default(),
+    };
+    let is_private = !shadow_lines.is_empty();
+
+    let ua = get_ua(&headers);
+    let range = headers.get("Range").and_then(|x| x.to_str().ok());
+    let range_start = match range_start(range, ua) {
+        Ok(range_start) => range_start,
+        Err(code) => {
+            return Response::builder()
+                .status(code)
+                .body(Body::empty())
+                .unwrap();
+        }
+    };
+    let start = query
+        .from
+        .map(DatStart::Res)
+        .or(range_start.map(DatStart::Byte));
+
     // Parse the expected byte size from If-None-Match before the service call so
     // the service can skip flatten+collect when the cache size hasn't changed.
-    // Range requests don't use ETags for conditional checks, so skip them.
-    let range = headers.get("Range");
+    // Partial requests don't use ETags for conditional checks, so skip them.
     let if_none_match_hdr = headers.get("If-None-Match");
-    let expected_byte_size = if range.is_none() {
+    let expected_byte_size = if start.is_none() && !is_private {
         if_none_match_hdr
             .and_then(|v| v.to_str().ok())
             .and_then(parse_etag_byte_size)
@@ -64,6 +121,8 @@ pub async fn get_dat_txt(
             board_key: board_key.clone(),
             thread_number: thread_number_num as u64,
             expected_byte_size,
+            // The shadow-aboned lines are overlaid on the whole dat before slicing it
+            start: start.filter(|_| !is_private),
         })
         .await
     {
@@ -112,25 +171,14 @@ pub async fn get_dat_txt(
             .unwrap();
     };
 
-    let ua = get_ua(&headers);
-    let range = range.and_then(|x| x.to_str().ok());
-
-    let (result, is_partial) = match (range, ua) {
-        (Some(range), Some(ua)) if !ua.contains("Xeno") => {
-            if let Some(range) = range.split('=').nth(1) {
-                let range = range.split('-').collect::<Vec<_>>();
-                let Some(start) = range.first().and_then(|x| x.parse::<usize>().ok()) else {
-                    return Response::builder().status(400).body(Body::empty()).unwrap();
-                };
-                (result.get(start..).unwrap_or_default().to_vec(), true)
-            } else {
-                (result, false)
-            }
-        }
-        _ => (result, false),
+    let result = match (is_private, start) {
+        (true, Some(start)) => slice_dat(&overlay_original_lines(&result, &shadow_lines), start),
+        (true, None) => overlay_original_lines(&result, &shadow_lines),
+        (false, _) => result,
     };
+    let is_partial = matches!(start, Some(DatStart::Byte(_)));
 
-    let (if_none_match, etag) = if !is_partial {
+    let (if_none_match, etag) = if start.is_none() {
         let inm = if_none_match_hdr
             .and_then(|v| v.to_str().ok())
             .map(|s| s.to_string());
@@ -149,6 +197,7 @@ pub async fn get_dat_txt(
         .content_type(SjisContentType::TextPlain)
         .client_ttl(5)
         .server_ttl(1)
+        .private(is_private)
         .if_none_match(if_none_match)
         .with_etag(etag)
         .status_code(if is_partial {
@@ -194,16 +243,50 @@ pub async fn get_kako_dat_txt(
         }
     };
 
+    // Derived from the stored object, so that every representation of it shares the ETag
     let etag = Some(format!(
         "W/\"{}-{}-{}\"",
         board_key,
         thread_number,
-        result.len()
+        result.data.len()
     ));
-    let sjis_str = if let Ok(result) = str::from_utf8(&result) {
-        SJisStr::from(result)
+    let compression = result.compression;
+
+    let ua = get_ua(&headers);
+    let range = headers.get("Range").and_then(|x| x.to_str().ok());
+    // Ranges are offsets in the uncompressed dat, so they are always served decompressed
+    let content_encoding = compression.content_encoding().filter(|encoding| {
+        range.is_none()
+            && accepts_encoding(&headers, encoding)
+            && ua.is_some_and(allows_precompressed_dat)
+    });
+
+    let (body, is_partial) = if content_encoding.is_some() {
+        (result.data, false)
     } else {
-        SJisStr::from_unchecked_vec(result)
+        let dat = match result.decompress() {
+            Ok(dat) => dat,
+            Err(err) => {
+                log::error!("Failed to decompress kako thread: {board_key}/{thread_number}: {err}");
+                return Response::builder().status(500).body(Body::empty()).unwrap();
+            }
+        };
+        // Uncompressed dats are stored in UTF-8
+        let dat = match (compression, String::from_utf8(dat)) {
+            (DatCompression::None, Ok(dat)) => SJisStr::from(dat.as_str()).get_inner(),
+            (_, Ok(dat)) => dat.into_bytes(),
+            (_, Err(err)) => err.into_bytes(),
+        };
+        match range_start(range, ua) {
+            Ok(Some(range_start)) => (slice_dat(&dat, DatStart::Byte(range_start)), true),
+            Ok(None) => (dat, false),
+            Err(code) => {
+                return Response::builder()
+                    .status(code)
+                    .body(Body::empty())
+                    .unwrap();
+            }
+        }
     };
 
     let if_none_match = headers
@@ -211,11 +294,22 @@ pub async fn get_kako_dat_txt(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
-    SJisResponseBuilder::new(sjis_str)
+    let mut builder = SJisResponseBuilder::new(SJisStr::from_unchecked_vec(body))
         .content_type(SjisContentType::TextPlain)
         .server_ttl(3600)
         .if_none_match(if_none_match)
         .with_etag(etag)
-        .build()
-        .into_response()
+        .status_code(if is_partial {
+            StatusCode::PARTIAL_CONTENT
+        } else {
+            StatusCode::OK
+        });
+    if compression != DatCompression::None {
+        builder = builder.add_header("Vary".to_string(), "Accept-Encoding".to_string());
+    }
+    if let Some(content_encoding) = content_encoding {
+        builder = builder.add_header("Content-Encoding".to_string(), content_encoding.to_string());
+    }
+
+    builder.build().into_response()
 }
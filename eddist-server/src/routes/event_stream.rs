@@ -0,0 +1,342 @@
+use std::{collections::HashMap, collections::VecDeque, convert::Infallible};
+
+use axum::{
+    Extension,
+    body::Body,
+    extract::{Path, Query, State},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use eddist_core::domain::board::validate_board_key;
+use futures::{Stream, stream};
+use http::{HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{Receiver, error::RecvError};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    domain::{thread_list::ThreadList, thread_res_list::JsonRes},
+    middleware::client_addr::ClientAddr,
+    services::{
+        AppService,
+        event_stream_hub::{CreationEvent, StreamConnectionGuard},
+        json_thread_retrieval_service::JsonThreadRetrievalServiceInput,
+        thread_list_service::BoardKey,
+    },
+    utils::get_origin_ip,
+};
+
+/// A dat holds at most 1000 responses; a larger order means the thread cache was missing
+/// when the response was created, so its position is unknown
+const MAX_STREAMED_RES_ORDER: i32 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct StreamQuery {
+    /// Responses after this order are replayed first, overridden by `Last-Event-ID`
+    #[serde(default)]
+    since: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct ThreadCreatedEvent<'a> {
+    thread_number: u64,
+    title: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct ThreadBumpedEvent {
+    thread_number: u64,
+    response_count: i32,
+}
+
+fn status(code: StatusCode) -> Response {
+    Response::builder()
+        .status(code)
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn get_thread_list(state: &AppState, board_key: &str) -> Result<ThreadList, StatusCode> {
+    let svc = state.get_container().thread_list();
+    svc.execute(BoardKey(board_key.to_string()))
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("failed to find board info") {
+                StatusCode::NOT_FOUND
+            } else {
+                log::error!("Failed to get thread list: {e:?}");
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })
+}
+
+fn subscribe(
+    state: &AppState,
+    client_addr: &ClientAddr,
+) -> Result<(Receiver<CreationEvent>, StreamConnectionGuard), StatusCode> {
+    let ip = get_origin_ip(client_addr).ok_or(StatusCode::BAD_REQUEST)?;
+    state
+        .event_stream_hub
+        .subscribe(ip)
+        .ok_or(StatusCode::TOO_MANY_REQUESTS)
+}
+
+fn sse_response(
+    stream: impl Stream<Item = Result<Event, Infallible>> + Send + 'static,
+) -> Response {
+    let mut resp = Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response();
+    resp.headers_mut()
+        .insert("Cache-Control", "no-store".parse().unwrap());
+    resp
+}
+
+struct ThreadStream {
+    state: AppState,
+    events: Receiver<CreationEvent>,
+    _guard: StreamConnectionGuard,
+    board_key: String,
+    thread_number: u64,
+    thread_id: Uuid,
+    last_order: usize,
+    pending: VecDeque<JsonRes>,
+    needs_catch_up: bool,
+}
+
+impl ThreadStream {
+    /// Reads the responses after `last_order` from the database, used on connect and whenever
+    /// the live events cannot be relied on (lagged receiver or a gap in the orders)
+    async fn catch_up(&mut self) {
+        self.needs_catch_up = false;
+
+        let svc = self.state.get_container().json_thread_retrieval();
+        let thread = match svc
+            .execute(JsonThreadRetrievalServiceInput {
+                board_key: self.board_key.clone(),
+                thread_number: self.thread_number,
+                since: self.last_order,
+                revealed_orders: Default::default(),
+            })
+            .await
+        {
+            Ok(thread) => thread,
+            Err(e) => {
+                log::warn!("Failed to catch up the thread stream: {e:?}");
+                return;
+            }
+        };
+
+        if let Some(last) = thread.responses.last() {
+            self.last_order = last.order;
+        }
+        self.pending.extend(thread.responses);
+    }
+
+    async fn next_event(&mut self) -> Option<Event> {
+        loop {
+            if let Some(res) = self.pending.pop_front() {
+                return Some(
+                    Event::default()
+                        .event("res")
+                        .id(res.order.to_string())
+                        .json_data(res)
+                        .unwrap(),
+                );
+            }
+            if self.needs_catch_up {
+                self.catch_up().await;
+                continue;
+            }
+
+            match self.events.recv().await {
+                Ok(CreationEvent::ResCreated(res))
+                    if res.thread_id == self.thread_id
+                        && (1..=MAX_STREAMED_RES_ORDER).contains(&res.res_order) =>
+                {
+                    let order = res.res_order as usize;
+                    if order <= self.last_order {
+                        continue;
+                    }
+                    if order > self.last_order + 1 {
+                        self.needs_catch_up = true;
+                        continue;
+                    }
+                    self.last_order = order;
+                    self.pending.push_back(JsonRes::from_creating_res(&res));
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(_)) => self.needs_catch_up = true,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// Streams the responses of a thread as server-sent events, rendered as in the dat.
+///
+/// Each event carries its order as the event id, so a reconnecting client resumes from
+/// `Last-Event-ID` (or `?since=`) without missing or duplicating responses.
+pub async fn get_thread_stream(
+    State(state): State<AppState>,
+    Extension(client_addr): Extension<ClientAddr>,
+    headers: HeaderMap,
+    Path((board_key, thread_number)): Path<(String, u64)>,
+    Query(query): Query<StreamQuery>,
+) -> Response {
+    if validate_board_key(&board_key).is_err() {
+        return status(StatusCode::NOT_FOUND);
+    }
+
+    let threads = match get_thread_list(&state, &board_key).await {
+        Ok(threads) => threads,
+        Err(code) => return status(code),
+    };
+    let Some(thread_id) = threads
+        .thread_list
+        .iter()
+        .find(|thread| thread.thread_number as u64 == thread_number)
+        .map(|thread| thread.id)
+    else {
+        return status(StatusCode::NOT_FOUND);
+    };
+
+    let since = headers
+        .get("Last-Event-ID")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(query.since);
+
+    // Subscribe before reading the dat so that no response falls between the two
+    let (events, guard) = match subscribe(&state, &client_addr) {
+        Ok(subscription) => subscription,
+        Err(code) => return status(code),
+    };
+    let thread_stream = ThreadStream {
+        state,
+        events,
+        _guard: guard,
+        board_key,
+        thread_number,
+        thread_id,
+        last_order: since,
+        pending: VecDeque::new(),
+        needs_catch_up: true,
+    };
+
+    sse_response(stream::unfold(
+        thread_stream,
+        |mut thread_stream| async move {
+            let event = thread_stream.next_event().await?;
+            Some((Ok(event), thread_stream))
+        },
+    ))
+}
+
+struct BoardStream {
+    state: AppState,
+    events: Receiver<CreationEvent>,
+    _guard: StreamConnectionGuard,
+    board_key: String,
+    board_id: Uuid,
+    /// Thread numbers of the unarchived threads, by thread id
+    thread_numbers: HashMap<Uuid, u64>,
+}
+
+impl BoardStream {
+    fn set_thread_numbers(&mut self, threads: &ThreadList) {
+        self.thread_numbers = threads
+            .thread_list
+            .iter()
+            .map(|thread| (thread.id, thread.thread_number as u64))
+            .collect();
+    }
+
+    async fn next_event(&mut self) -> Option<Event> {
+        loop {
+            match self.events.recv().await {
+                Ok(CreationEvent::ThreadCreated(thread)) if thread.board_id == self.board_id => {
+                    self.thread_numbers
+                        .insert(thread.thread_id, thread.unix_time);
+                    return Some(
+                        Event::default()
+                            .event("thread_created")
+                            .json_data(ThreadCreatedEvent {
+                                thread_number: thread.unix_time,
+                                title: &thread.title,
+                            })
+                            .unwrap(),
+                    );
+                }
+                Ok(CreationEvent::ResCreated(res))
+                    if res.board_id == self.board_id
+                        && !res.is_sage
+                        && (1..=MAX_STREAMED_RES_ORDER).contains(&res.res_order) =>
+                {
+                    let Some(&thread_number) = self.thread_numbers.get(&res.thread_id) else {
+                        continue;
+                    };
+                    return Some(
+                        Event::default()
+                            .event("thread_bumped")
+                            .json_data(ThreadBumpedEvent {
+                                thread_number,
+                                response_count: res.res_order,
+                            })
+                            .unwrap(),
+                    );
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(_)) => {
+                    // Some events were dropped, so the client has to reload the thread list
+                    if let Ok(threads) = get_thread_list(&self.state, &self.board_key).await {
+                        self.set_thread_numbers(&threads);
+                    }
+                    return Some(Event::default().event("resync").data(""));
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// Streams the threads created and bumped on a board as server-sent events
+pub async fn get_board_stream(
+    State(state): State<AppState>,
+    Extension(client_addr): Extension<ClientAddr>,
+    Path(board_key): Path<String>,
+) -> Response {
+    if validate_board_key(&board_key).is_err() {
+        return status(StatusCode::NOT_FOUND);
+    }
+
+    let (events, guard) = match subscribe(&state, &client_addr) {
+        Ok(subscription) => subscription,
+        Err(code) => return status(code),
+    };
+    let threads = match get_thread_list(&state, &board_key).await {
+        Ok(threads) => threads,
+        Err(code) => return status(code),
+    };
+
+    let mut board_stream = BoardStream {
+        state,
+        events,
+        _guard: guard,
+        board_key,
+        board_id: threads.board.id,
+        thread_numbers: HashMap::new(),
+    };
+    board_stream.set_thread_numbers(&threads);
+
+    sse_response(stream::unfold(
+        board_stream,
+        |mut board_stream| async move {
+            let event = board_stream.next_event().await?;
+            Some((Ok(event), board_stream))
+        },
+    ))
+}
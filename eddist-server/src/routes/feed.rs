@@ -0,0 +1,138 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    response::Response,
+};
+use base64::Engine;
+use eddist_core::domain::board::validate_board_key;
+use http::{HeaderMap, StatusCode};
+use md5::{Digest, Md5};
+
+use crate::{
+    AppState,
+    domain::feed::AtomFeed,
+    routes::{
+        common::{base_url, get_board_info, not_found},
+        safe_mode::get_unsafe_threads,
+    },
+    services::{
+        AppService, json_thread_retrieval_service::JsonThreadRetrievalServiceInput,
+        thread_list_service::BoardKey,
+    },
+};
+
+const BOARD_FEED_LIMIT: usize = 50;
+const THREAD_FEED_LIMIT: usize = 50;
+
+/// Same caching as the Shift_JIS responses: a digest ETag honoured with 304, and the
+/// feeds of read-only boards, which no longer change, are cached for longer
+fn feed_response(xml: String, headers: &HeaderMap, read_only: bool) -> Response {
+    let digest = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Md5::digest(&xml));
+    let etag = format!("W/\"{digest}\"");
+    let cache_control = if read_only {
+        "max-age=3600,s-maxage=3600"
+    } else {
+        "max-age=60,s-maxage=30"
+    };
+
+    let if_none_match = headers.get("If-None-Match").and_then(|v| v.to_str().ok());
+    if if_none_match.is_some_and(|inm| inm.trim() == etag || inm.trim() == "*") {
+        return Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header("ETag", etag)
+            .header("Cache-Control", cache_control)
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    Response::builder()
+        .header("Content-Type", "application/atom+xml; charset=utf-8")
+        .header("ETag", etag)
+        .header("Cache-Control", cache_control)
+        .body(Body::from(xml))
+        .unwrap()
+}
+
+pub async fn get_board_feed(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(board_key): Path<String>,
+) -> Response {
+    if validate_board_key(&board_key).is_err() {
+        return not_found();
+    }
+    let board = match get_board_info(&state, &board_key).await {
+        Ok(board) => board,
+        Err(resp) => return resp,
+    };
+
+    let threads = match state
+        .get_container()
+        .thread_list()
+        .execute(BoardKey(board_key))
+        .await
+    {
+        Ok(threads) => threads,
+        Err(e) => {
+            log::error!("Failed to get thread list for feed: {e:?}");
+            return Response::builder().status(500).body(Body::empty()).unwrap();
+        }
+    };
+    let unsafe_threads = get_unsafe_threads(&state, board.board_id).await;
+
+    let feed = AtomFeed::from_thread_list(&base_url(), &threads, &unsafe_threads, BOARD_FEED_LIMIT);
+    feed_response(feed.to_xml(), &headers, board.board_info.read_only)
+}
+
+pub async fn get_thread_feed(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((board_key, thread_number)): Path<(String, u64)>,
+) -> Response {
+    if validate_board_key(&board_key).is_err() {
+        return not_found();
+    }
+    let board = match get_board_info(&state, &board_key).await {
+        Ok(board) => board,
+        Err(resp) => return resp,
+    };
+    if get_unsafe_threads(&state, board.board_id)
+        .await
+        .contains(&thread_number)
+    {
+        return not_found();
+    }
+
+    let thread = match state
+        .get_container()
+        .json_thread_retrieval()
+        .execute(JsonThreadRetrievalServiceInput {
+            board_key: board_key.clone(),
+            thread_number,
+            since: 0,
+            revealed_orders: Default::default(),
+        })
+        .await
+    {
+        Ok(thread) => thread,
+        Err(e) => {
+            if !e
+                .root_cause()
+                .to_string()
+                .contains("cannot find such thread")
+            {
+                log::error!("Failed to get thread for feed: {e:?}");
+            }
+            return not_found();
+        }
+    };
+
+    let feed = AtomFeed::from_thread_res_list(
+        &base_url(),
+        &board_key,
+        &board.name,
+        &thread,
+        THREAD_FEED_LIMIT,
+    );
+    feed_response(feed.to_xml(), &headers, board.board_info.read_only)
+}
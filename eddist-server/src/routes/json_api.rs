@@ -0,0 +1,152 @@
+use axum::{
+    Json,
+    body::Body,
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::CookieJar;
+use base64::Engine;
+use eddist_core::domain::board::validate_board_key;
+use http::{HeaderMap, HeaderValue, StatusCode};
+use md5::{Digest, Md5};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    domain::{service::shadow_abone_service::ShadowAboneService, thread_list::ThreadListQuery},
+    routes::common::{get_excluded_threads, get_sort_momentum},
+    services::{
+        AppService, json_thread_retrieval_service::JsonThreadRetrievalServiceInput,
+        thread_list_service::BoardKey,
+    },
+};
+
+fn not_modified(etag: &str, cache_control: &str) -> Response {
+    Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header("ETag", etag)
+        .header("Cache-Control", cache_control)
+        .body(Body::empty())
+        .unwrap()
+}
+
+fn json_response(body: impl serde::Serialize, etag: &str, cache_control: &str) -> Response {
+    let mut resp = Json(body).into_response();
+    let headers = resp.headers_mut();
+    headers.insert("ETag", HeaderValue::from_str(etag).unwrap());
+    headers.insert(
+        "Cache-Control",
+        HeaderValue::from_str(cache_control).unwrap(),
+    );
+    resp
+}
+
+pub async fn get_api_threads(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(board_key): Path<String>,
+    Query(query): Query<ThreadListQuery>,
+) -> Response {
+    if validate_board_key(&board_key).is_err() {
+        return Response::builder().status(404).body(Body::empty()).unwrap();
+    }
+
+    let svc = state.get_container().thread_list();
+    let mut threads = match svc.execute(BoardKey(board_key)).await {
+        Ok(threads) => threads,
+        Err(e) => {
+            return if e.to_string().contains("failed to find board info") {
+                Response::builder().status(404).body(Body::empty()).unwrap()
+            } else {
+                log::error!("Failed to get thread list: {e:?}");
+                Response::builder().status(500).body(Body::empty()).unwrap()
+            };
+        }
+    };
+
+    // A response count can change without changing the size of the list, so the ETag is
+    // a digest of the body rather than its size
+    let excluded_threads = get_excluded_threads(&state, &query, threads.board.id).await;
+    let momentum =
+        get_sort_momentum(&state, &query, threads.thread_list.iter().map(|th| th.id)).await;
+    threads.apply_query(&query, &excluded_threads, &momentum);
+    let threads = threads.get_json_thread_list();
+    let body = serde_json::to_vec(&threads).unwrap();
+    let digest = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Md5::digest(&body));
+    let etag = format!("W/\"{digest}\"");
+
+    let cache_control = "max-age=5,s-maxage=1";
+    let if_none_match = headers.get("If-None-Match").and_then(|v| v.to_str().ok());
+    if if_none_match.is_some_and(|inm| inm.trim() == etag || inm.trim() == "*") {
+        return not_modified(&etag, cache_control);
+    }
+    json_response(threads, &etag, cache_control)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ThreadQuery {
+    /// Only the responses after this order are returned
+    #[serde(default)]
+    since: usize,
+}
+
+pub async fn get_api_thread(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Path((board_key, thread_number)): Path<(String, u64)>,
+    Query(query): Query<ThreadQuery>,
+) -> Response {
+    if validate_board_key(&board_key).is_err() {
+        return Response::builder().status(404).body(Body::empty()).unwrap();
+    }
+
+    // Shadow-aboned responses are shown in their original form to the poster only
+    let shadow_lines = match jar.get("edge-token") {
+        Some(edge_token) => ShadowAboneService::new(state.redis_conn.clone())
+            .get_original_lines(&board_key, thread_number, edge_token.value())
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!("failed to get shadow-aboned lines: {e}");
+                Default::default()
+            }),
+        None => Default::default(),
+    };
+    let cache_control = if shadow_lines.is_empty() {
+        "max-age=5,s-maxage=1"
+    } else {
+        "private,max-age=0"
+    };
+
+    let svc = state.get_container().json_thread_retrieval();
+    let thread = match svc
+        .execute(JsonThreadRetrievalServiceInput {
+            board_key,
+            thread_number,
+            since: query.since,
+            revealed_orders: shadow_lines.into_keys().collect(),
+        })
+        .await
+    {
+        Ok(thread) => thread,
+        Err(e) => {
+            if !e
+                .root_cause()
+                .to_string()
+                .contains("cannot find such thread")
+            {
+                log::error!("Failed to get thread: {e:?}");
+            }
+            return Response::builder().status(404).body(Body::empty()).unwrap();
+        }
+    };
+
+    let body = serde_json::to_vec(&thread).unwrap();
+    let digest = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Md5::digest(&body));
+    let etag = format!("W/\"{digest}\"");
+    let if_none_match = headers.get("If-None-Match").and_then(|v| v.to_str().ok());
+    if if_none_match.is_some_and(|inm| inm.trim() == etag) {
+        return not_modified(&etag, cache_control);
+    }
+    json_response(thread, &etag, cache_control)
+}
@@ -1,17 +1,18 @@
 use std::collections::HashMap;
 
 use axum::{
-    Form,
+    Extension, Form,
     extract::State,
     http::StatusCode,
     response::{Html, IntoResponse},
 };
-use http::{HeaderMap, HeaderValue};
+use http::HeaderValue;
 use serde_json::json;
 
 use crate::{
     AppState,
     error::BbsPostAuthWithCodeError,
+    middleware::client_addr::ClientAddr,
     services::{
         AppService, captcha_config_cache::get_cached_captcha_configs_for_reauth,
         reauth_service::ReAuthServiceInput,
@@ -37,13 +38,13 @@ pub async fn get_re_auth(State(state): State<AppState>) -> impl IntoResponse {
 }
 
 pub async fn post_re_auth(
-    headers: HeaderMap,
+    Extension(client_addr): Extension<ClientAddr>,
     State(state): State<AppState>,
     Form(form): Form<HashMap<String, String>>,
 ) -> impl IntoResponse {
     let captcha_configs = get_cached_captcha_configs_for_reauth().await;
     let temp_key = form.get("temp_key").cloned().unwrap_or_default();
-    let Some(origin_ip) = get_origin_ip(&headers) else {
+    let Some(origin_ip) = get_origin_ip(&client_addr) else {
         return (StatusCode::FORBIDDEN, "Access denied").into_response();
     };
     match state
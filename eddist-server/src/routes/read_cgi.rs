@@ -0,0 +1,157 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    response::Response,
+};
+use axum_extra::extract::CookieJar;
+use base64::Engine;
+use eddist_core::domain::board::validate_board_key;
+use http::{HeaderMap, StatusCode};
+use md5::{Digest, Md5};
+
+use crate::{
+    AppState,
+    domain::{
+        read_view::{ReadRange, ReadView},
+        service::shadow_abone_service::ShadowAboneService,
+    },
+    routes::{
+        common::{base_url, get_board_info, not_found},
+        safe_mode::get_unsafe_threads,
+    },
+    services::{AppService, json_thread_retrieval_service::JsonThreadRetrievalServiceInput},
+};
+
+pub async fn get_read_cgi(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Path((board_key, thread_number)): Path<(String, String)>,
+) -> Response {
+    render_thread(
+        state,
+        headers,
+        jar,
+        board_key,
+        thread_number,
+        ReadRange::ALL,
+    )
+    .await
+}
+
+pub async fn get_read_cgi_range(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Path((board_key, thread_number, pos)): Path<(String, String, String)>,
+) -> Response {
+    let Some(range) = ReadRange::parse(&pos) else {
+        return not_found();
+    };
+    render_thread(state, headers, jar, board_key, thread_number, range).await
+}
+
+async fn render_thread(
+    state: AppState,
+    headers: HeaderMap,
+    jar: CookieJar,
+    board_key: String,
+    thread_number: String,
+    range: ReadRange,
+) -> Response {
+    if validate_board_key(&board_key).is_err() {
+        return not_found();
+    }
+    let Ok(thread_number) = thread_number.parse::<u64>() else {
+        return not_found();
+    };
+    let board = match get_board_info(&state, &board_key).await {
+        Ok(board) => board,
+        Err(resp) => return resp,
+    };
+
+    // Shadow-aboned responses are shown in their original form to the poster only, as in
+    // the JSON API
+    let shadow_lines = match jar.get("edge-token") {
+        Some(edge_token) => ShadowAboneService::new(state.redis_conn.clone())
+            .get_original_lines(&board_key, thread_number, edge_token.value())
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!("failed to get shadow-aboned lines: {e}");
+                Default::default()
+            }),
+        None => Default::default(),
+    };
+    let is_private = !shadow_lines.is_empty();
+
+    let thread = match state
+        .get_container()
+        .json_thread_retrieval()
+        .execute(JsonThreadRetrievalServiceInput {
+            board_key: board_key.clone(),
+            thread_number,
+            since: 0,
+            revealed_orders: shadow_lines.into_keys().collect(),
+        })
+        .await
+    {
+        Ok(thread) => thread,
+        Err(e) => {
+            if !e
+                .root_cause()
+                .to_string()
+                .contains("cannot find such thread")
+            {
+                log::error!("Failed to get thread for read.cgi: {e:?}");
+            }
+            return not_found();
+        }
+    };
+
+    let noindex = get_unsafe_threads(&state, board.board_id)
+        .await
+        .contains(&thread_number);
+    let view = ReadView::new(
+        &base_url(),
+        &board.name,
+        &board_key,
+        &thread,
+        range,
+        noindex,
+    );
+    let html = match state.template_engine.render("read-cgi.get", &view) {
+        Ok(html) => html,
+        Err(e) => {
+            log::error!("Failed to render read.cgi: {e:?}");
+            return Response::builder().status(500).body(Body::empty()).unwrap();
+        }
+    };
+
+    let digest = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Md5::digest(&html));
+    let etag = format!("W/\"{digest}\"");
+    let cache_control = if is_private {
+        "private,max-age=0"
+    } else if board.board_info.read_only {
+        "max-age=3600,s-maxage=3600"
+    } else {
+        "max-age=5,s-maxage=1"
+    };
+
+    let if_none_match = headers.get("If-None-Match").and_then(|v| v.to_str().ok());
+    if if_none_match.is_some_and(|inm| inm.trim() == etag) {
+        return Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header("ETag", etag)
+            .header("Cache-Control", cache_control)
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    Response::builder()
+        .header("Content-Type", "text/html; charset=utf-8")
+        .header("ETag", etag)
+        .header("Cache-Control", cache_control)
+        .header("X-Content-Type-Options", "nosniff")
+        .body(Body::from(html))
+        .unwrap()
+}
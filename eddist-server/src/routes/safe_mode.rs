@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use axum::{
     Json,
     body::Body,
@@ -7,6 +9,7 @@ use axum::{
 use eddist_core::{domain::board::validate_board_key, redis_keys::unsafe_threads_key};
 use redis::AsyncCommands;
 use serde::Serialize;
+use uuid::Uuid;
 
 use crate::{
     AppState,
@@ -17,6 +20,21 @@ use crate::{
     },
 };
 
+/// Threads hidden by the safe mode, none while it is disabled
+pub(crate) async fn get_unsafe_threads(state: &AppState, board_id: Uuid) -> HashSet<u64> {
+    if !get_server_setting_bool(ServerSettingKey::EnableSafeMode).await {
+        return HashSet::new();
+    }
+    let mut redis_conn = state.redis_conn.clone();
+    redis_conn
+        .smembers(unsafe_threads_key(board_id))
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("Failed to query unsafe threads from Redis: {e:?}");
+            HashSet::new()
+        })
+}
+
 #[derive(Serialize)]
 pub struct UnsafeThreadIdsResponse {
     pub thread_ids: Vec<u64>,
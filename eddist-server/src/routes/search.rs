@@ -0,0 +1,144 @@
+use axum::{
+    Extension, Json,
+    extract::{Query, State},
+    http::HeaderValue,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use eddist_core::domain::{
+    board::validate_board_key,
+    search::{MAX_SEARCH_QUERY_CHARS, matches_search_query, search_ngrams},
+};
+use eddist_core::redis_keys::search_access_count_key;
+use http::StatusCode;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::AppState, middleware::client_addr::ClientAddr,
+    repositories::search_repository::SearchRepository, utils::get_origin_ip,
+};
+
+const DEFAULT_SEARCH_LIMIT: u32 = 50;
+const MAX_SEARCH_LIMIT: u32 = 100;
+/// Candidates fetched per requested result, as some of them only have the n-grams of the
+/// query without containing it
+const SEARCH_CANDIDATE_FACTOR: u32 = 4;
+/// Searches allowed per IP within [`SEARCH_RATE_LIMIT_WINDOW_SECS`]
+const SEARCH_RATE_LIMIT: i64 = 30;
+const SEARCH_RATE_LIMIT_WINDOW_SECS: i64 = 60;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    q: String,
+    board: Option<String>,
+    limit: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct SearchResult {
+    pub board_key: String,
+    pub thread_number: u64,
+    pub thread_title: String,
+    pub res_order: i32,
+    pub body: String,
+    pub author_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct SearchResponse {
+    pub results: Vec<SearchResult>,
+}
+
+pub async fn get_search(
+    State(state): State<AppState>,
+    Extension(client_addr): Extension<ClientAddr>,
+    Query(query): Query<SearchQuery>,
+) -> Response {
+    if query.q.chars().count() > MAX_SEARCH_QUERY_CHARS {
+        return (StatusCode::BAD_REQUEST, "query too long").into_response();
+    }
+    let ngrams = search_ngrams(&query.q);
+    if ngrams.is_empty() {
+        return (StatusCode::BAD_REQUEST, "query too short").into_response();
+    }
+    if let Some(board_key) = &query.board
+        && validate_board_key(board_key).is_err()
+    {
+        return (StatusCode::BAD_REQUEST, "invalid board key").into_response();
+    }
+    if let Some(ip) = get_origin_ip(&client_addr)
+        && is_rate_limited(&state, ip).await
+    {
+        return (StatusCode::TOO_MANY_REQUESTS, "Too Many Requests").into_response();
+    }
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+
+    let candidates = match state
+        .search_repo
+        .search_candidates(
+            &ngrams,
+            query.board.as_deref(),
+            limit * SEARCH_CANDIDATE_FACTOR,
+        )
+        .await
+    {
+        Ok(candidates) => candidates,
+        Err(e) => {
+            tracing::error!("Failed to search: {e:?}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response();
+        }
+    };
+
+    let results = candidates
+        .into_iter()
+        .filter(|hit| {
+            matches_search_query(&hit.body, &query.q)
+                || (hit.res_order == 1 && matches_search_query(&hit.thread_title, &query.q))
+        })
+        .take(limit as usize)
+        .map(|hit| SearchResult {
+            board_key: hit.board_key,
+            thread_number: hit.thread_number as u64,
+            thread_title: hit.thread_title,
+            res_order: hit.res_order,
+            body: hit.body,
+            author_id: hit.author_id,
+            created_at: hit.created_at,
+        })
+        .collect();
+
+    let mut resp = Json(SearchResponse { results }).into_response();
+    resp.headers_mut()
+        .insert("Cache-Control", HeaderValue::from_static("s-maxage=10"));
+    resp
+}
+
+/// Counts the search in Redis, as every instance serves the same clients. A Redis failure
+/// lets the search through.
+async fn is_rate_limited(state: &AppState, ip: &str) -> bool {
+    let mut redis_conn = state.redis_conn.clone();
+    let key = search_access_count_key(ip);
+
+    let count = match redis_conn.incr::<_, _, i64>(&key, 1).await {
+        Ok(count) => count,
+        Err(e) => {
+            tracing::error!("Failed to increment the search counter for {ip}: {e}");
+            return false;
+        }
+    };
+    if count == 1
+        && let Err(e) = redis_conn
+            .expire::<_, ()>(&key, SEARCH_RATE_LIMIT_WINDOW_SECS)
+            .await
+    {
+        tracing::error!("Failed to set expiry on the search counter for {ip}: {e}");
+    }
+
+    count > SEARCH_RATE_LIMIT
+}
@@ -1,12 +1,14 @@
 use axum::{
     body::Body,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::{IntoResponse, Response},
 };
 use eddist_core::domain::{board::validate_board_key, sjis_str::SJisStr};
 
 use crate::{
     AppState,
+    domain::thread_list::ThreadListQuery,
+    routes::common::{get_excluded_threads, get_sort_momentum},
     services::{AppService, thread_list_service::BoardKey},
     shiftjis::{SJisResponseBuilder, SjisContentType},
 };
@@ -14,13 +16,14 @@ use crate::{
 pub async fn get_subject_txt(
     State(state): State<AppState>,
     Path(board_key): Path<String>,
+    Query(query): Query<ThreadListQuery>,
 ) -> impl IntoResponse {
     if validate_board_key(&board_key).is_err() {
         return Response::builder().status(404).body(Body::empty()).unwrap();
     }
 
     let svc = state.get_container().thread_list();
-    let threads = match svc.execute(BoardKey(board_key)).await {
+    let mut threads = match svc.execute(BoardKey(board_key)).await {
         Ok(threads) => threads,
         Err(e) => {
             return if e.to_string().contains("failed to find board info") {
@@ -32,6 +35,11 @@ pub async fn get_subject_txt(
         }
     };
 
+    let excluded_threads = get_excluded_threads(&state, &query, threads.board.id).await;
+    let momentum =
+        get_sort_momentum(&state, &query, threads.thread_list.iter().map(|th| th.id)).await;
+    threads.apply_query(&query, &excluded_threads, &momentum);
+
     SJisResponseBuilder::new(SJisStr::from_unchecked_vec(threads.get_sjis_thread_list()))
         .content_type(SjisContentType::TextPlain)
         .client_ttl(5)
@@ -43,13 +51,14 @@ pub async fn get_subject_txt(
 pub async fn get_subject_txt_with_metadent(
     State(state): State<AppState>,
     Path(board_key): Path<String>,
+    Query(query): Query<ThreadListQuery>,
 ) -> impl IntoResponse {
     if validate_board_key(&board_key).is_err() {
         return Response::builder().status(404).body(Body::empty()).unwrap();
     }
 
     let svc = state.get_container().metadent_thread_list();
-    let threads = match svc
+    let mut threads = match svc
         .execute(crate::services::metadent_thread_list_service::BoardKey(
             board_key,
         ))
@@ -66,6 +75,15 @@ pub async fn get_subject_txt_with_metadent(
         }
     };
 
+    let excluded_threads = get_excluded_threads(&state, &query, threads.board.id).await;
+    let momentum = get_sort_momentum(
+        &state,
+        &query,
+        threads.thread_list.iter().map(|(th, _)| th.id),
+    )
+    .await;
+    threads.apply_query(&query, &excluded_threads, &momentum);
+
     SJisResponseBuilder::new(SJisStr::from_unchecked_vec(threads.get_sjis_thread_list()))
         .content_type(SjisContentType::TextPlain)
         .client_ttl(5)
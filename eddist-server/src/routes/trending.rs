@@ -0,0 +1,78 @@
+use axum::{
+    Json,
+    body::Body,
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use eddist_core::{domain::board::validate_board_key, thread_momentum::get_threads_momentum};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    routes::safe_mode::get_unsafe_threads,
+    services::{AppService, thread_list_service::BoardKey},
+};
+
+const DEFAULT_TRENDING_LIMIT: usize = 20;
+const MAX_TRENDING_LIMIT: usize = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct TrendingQuery {
+    board: String,
+    limit: Option<usize>,
+    /// Leaves out the threads hidden by the safe mode
+    #[serde(default)]
+    exclude_unsafe: bool,
+}
+
+pub async fn get_trending(
+    State(state): State<AppState>,
+    Query(query): Query<TrendingQuery>,
+) -> Response {
+    if validate_board_key(&query.board).is_err() {
+        return Response::builder().status(404).body(Body::empty()).unwrap();
+    }
+
+    let svc = state.get_container().thread_list();
+    let mut threads = match svc.execute(BoardKey(query.board.clone())).await {
+        Ok(threads) => threads,
+        Err(e) => {
+            return if e.to_string().contains("failed to find board info") {
+                Response::builder().status(404).body(Body::empty()).unwrap()
+            } else {
+                log::error!("Failed to get thread list: {e:?}");
+                Response::builder().status(500).body(Body::empty()).unwrap()
+            };
+        }
+    };
+    if query.exclude_unsafe {
+        let unsafe_threads = get_unsafe_threads(&state, threads.board.id).await;
+        threads
+            .thread_list
+            .retain(|th| !unsafe_threads.contains(&(th.thread_number as u64)));
+    }
+
+    let mut redis_conn = state.redis_conn.clone();
+    let thread_ids = threads
+        .thread_list
+        .iter()
+        .map(|th| th.id)
+        .collect::<Vec<_>>();
+    let momentum = match get_threads_momentum(&mut redis_conn, &thread_ids, Utc::now()).await {
+        Ok(momentum) => momentum,
+        Err(e) => {
+            log::error!("Failed to get thread momentum: {e:?}");
+            return Response::builder().status(500).body(Body::empty()).unwrap();
+        }
+    };
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_TRENDING_LIMIT)
+        .min(MAX_TRENDING_LIMIT);
+    let mut resp = Json(threads.get_trending_threads(&momentum, limit)).into_response();
+    resp.headers_mut()
+        .insert("Cache-Control", "max-age=30,s-maxage=10".parse().unwrap());
+    resp
+}
@@ -1,5 +1,5 @@
 use axum::{
-    Router,
+    Extension, Router,
     body::Body,
     extract::{Path, State},
     response::{IntoResponse, Response},
@@ -13,6 +13,7 @@ use serde_json::json;
 
 use crate::{
     AppState,
+    middleware::client_addr::ClientAddr,
     services::{
         AppService,
         server_settings_cache::{ServerSettingKey, get_server_setting_bool},
@@ -348,6 +349,7 @@ struct AuthzIdpCallbackQuery {
 
 async fn get_user_authz_idp_callback(
     headers: HeaderMap,
+    Extension(client_addr): Extension<ClientAddr>,
     State(state): State<AppState>,
     jar: CookieJar,
     query: axum::extract::Query<AuthzIdpCallbackQuery>,
@@ -375,9 +377,9 @@ async fn get_user_authz_idp_callback(
 
     let browser_edge_token = jar.get("edge-token").map(|c| c.value().to_string());
     let (Some(ip_addr), Some(user_agent), Some(asn_num)) = (
-        get_origin_ip(&headers),
+        get_origin_ip(&client_addr),
         get_ua(&headers),
-        get_asn_num(&headers),
+        get_asn_num(&client_addr),
     ) else {
         return Response::builder()
             .status(403)
@@ -1,7 +1,10 @@
+use std::sync::Arc;
+
 use auth_with_code_service::AuthWithCodeService;
-use aws_sdk_s3::Client;
 use bind_token_to_user_service::BindTokenToUserService;
 use board_info_service::BoardInfoService;
+use eddist_core::archive_storage::ArchiveStorage;
+use json_thread_retrieval_service::JsonThreadRetrievalService;
 use kako_thread_retrieval_service::KakoThreadRetrievalService;
 use list_boards_service::ListBoardsService;
 use metadent_thread_list_service::MetadentThreadListService;
@@ -41,10 +44,13 @@ pub(crate) mod auth_with_code_service;
 pub(crate) mod bind_token_to_user_service;
 pub(crate) mod board_info_service;
 pub mod captcha_config_cache;
+pub mod dat_line_index;
+pub mod event_stream_hub;
+pub(crate) mod json_thread_retrieval_service;
 pub(crate) mod kako_thread_retrieval_service;
 pub(crate) mod list_boards_service;
 pub(crate) mod metadent_thread_list_service;
-pub(crate) mod openai_moderation_service;
+pub(crate) mod moderation_service;
 pub(crate) mod reauth_service;
 pub(crate) mod res_creation_service;
 pub mod server_settings_cache;
@@ -92,6 +98,7 @@ pub struct AppServiceContainer<
     thread_list: ThreadListService<B>,
     metadent_thread_list: MetadentThreadListService<B>,
     thread_retrieval: ThreadRetrievalService<B>,
+    json_thread_retrieval: JsonThreadRetrievalService<B>,
     kako_thread_retrieval: KakoThreadRetrievalService,
 
     user_reg_temp_url: UserRegTempUrlService<I, U, B>,
@@ -121,8 +128,7 @@ impl<
         user_restriction_repo: R,
         redis_conn: ConnectionManager,
         pubsub: PubSubRepos<P, E>,
-        client: Client,
-        bucket_name: String,
+        archive_storage: Arc<dyn ArchiveStorage>,
     ) -> Self {
         AppServiceContainer {
             auth_with_code: AuthWithCodeService::new(
@@ -149,7 +155,8 @@ impl<
             thread_list: ThreadListService::new(bbs_repo.clone()),
             metadent_thread_list: MetadentThreadListService::new(bbs_repo.clone()),
             thread_retrieval: ThreadRetrievalService::new(bbs_repo.clone(), redis_conn.clone()),
-            kako_thread_retrieval: KakoThreadRetrievalService::new(client, bucket_name),
+            json_thread_retrieval: JsonThreadRetrievalService::new(bbs_repo.clone()),
+            kako_thread_retrieval: KakoThreadRetrievalService::new(archive_storage),
 
             user_reg_temp_url: UserRegTempUrlService::new(
                 idp_repo.clone(),
@@ -225,6 +232,10 @@ impl<
         &self.thread_retrieval
     }
 
+    pub fn json_thread_retrieval(&self) -> &JsonThreadRetrievalService<B> {
+        &self.json_thread_retrieval
+    }
+
     pub fn list_boards(&self) -> &ListBoardsService<B> {
         &self.list_boards
     }
@@ -57,6 +57,13 @@ pub async fn get_cached_captcha_configs_for_reauth() -> Vec<CaptchaProviderConfi
         .collect()
 }
 
+/// Get a cached captcha config by name regardless of its endpoint usage, for the
+/// captcha forced by a user restriction rule
+pub async fn get_cached_captcha_config_by_name(name: &str) -> Option<CaptchaProviderConfig> {
+    let cache = get_global_cache().read().await;
+    cache.configs.iter().find(|c| c.name == name).cloned()
+}
+
 /// Refresh the cache with new configs from the database
 pub async fn refresh_captcha_config_cache(
     repo: &dyn CaptchaConfigRepository,
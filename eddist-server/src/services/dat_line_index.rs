@@ -0,0 +1,130 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+/// Indexes are rebuilt from a full read after this, as admin edits of the responses are
+/// not notified to the server
+const DAT_LINE_INDEX_TTL: Duration = Duration::from_secs(60);
+const MAX_INDEXED_DATS: usize = 4096;
+
+static DAT_LINE_INDEXES: OnceLock<Mutex<HashMap<String, (Instant, DatLineIndex)>>> =
+    OnceLock::new();
+
+fn get_indexes() -> &'static Mutex<HashMap<String, (Instant, DatLineIndex)>> {
+    DAT_LINE_INDEXES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Prefix sums of the line lengths of a dat, mapping byte offsets to responses
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatLineIndex {
+    /// Offset just past each line
+    line_ends: Vec<usize>,
+}
+
+impl DatLineIndex {
+    pub fn from_lengths(lengths: impl IntoIterator<Item = usize>) -> Self {
+        let mut index = Self::default();
+        index.replace_from(0, lengths);
+        index
+    }
+
+    /// Replaces the lines from `line` on, which must not be past the indexed lines
+    pub fn replace_from(&mut self, line: usize, lengths: impl IntoIterator<Item = usize>) {
+        self.line_ends.truncate(line);
+        let mut end = self.line_ends.last().copied().unwrap_or(0);
+        for len in lengths {
+            end += len;
+            self.line_ends.push(end);
+        }
+    }
+
+    /// Returns the line containing `offset` with its start offset and length, or `None`
+    /// when the offset is past the indexed lines.
+    ///
+    /// The end of the indexed lines maps to the next line with a zero length, as a client
+    /// that has read the whole dat only waits for new lines.
+    pub fn find_line(&self, offset: usize) -> Option<(usize, usize, usize)> {
+        let total = self.line_ends.last().copied().unwrap_or(0);
+        if offset == total {
+            return Some((self.line_ends.len(), total, 0));
+        }
+        let line = self.line_ends.partition_point(|&end| end <= offset);
+        let end = *self.line_ends.get(line)?;
+        let start = line.checked_sub(1).map_or(0, |prev| self.line_ends[prev]);
+        Some((line, start, end - start))
+    }
+}
+
+/// Concatenates the lines, skipping the first `skip` bytes
+pub fn assemble_lines(lines: &[Vec<u8>], skip: usize) -> Vec<u8> {
+    let total_len = lines.iter().map(Vec::len).sum::<usize>();
+    let mut raw = Vec::with_capacity(total_len.saturating_sub(skip));
+    let mut skip = skip;
+    for line in lines {
+        if skip >= line.len() {
+            skip -= line.len();
+            continue;
+        }
+        raw.extend_from_slice(&line[skip..]);
+        skip = 0;
+    }
+    raw
+}
+
+pub fn get_dat_line_index(key: &str) -> Option<DatLineIndex> {
+    let indexes = get_indexes().lock().unwrap();
+    indexes
+        .get(key)
+        .filter(|(indexed_at, _)| indexed_at.elapsed() < DAT_LINE_INDEX_TTL)
+        .map(|(_, index)| index.clone())
+}
+
+pub fn set_dat_line_index(key: &str, index: DatLineIndex) {
+    let mut indexes = get_indexes().lock().unwrap();
+    if indexes.len() >= MAX_INDEXED_DATS && !indexes.contains_key(key) {
+        indexes.retain(|_, (indexed_at, _)| indexed_at.elapsed() < DAT_LINE_INDEX_TTL);
+        if indexes.len() >= MAX_INDEXED_DATS {
+            indexes.clear();
+        }
+    }
+    indexes.insert(key.to_string(), (Instant::now(), index));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_line() {
+        let index = DatLineIndex::from_lengths([10, 5, 20]);
+
+        assert_eq!(index.find_line(0), Some((0, 0, 10)));
+        assert_eq!(index.find_line(9), Some((0, 0, 10)));
+        assert_eq!(index.find_line(10), Some((1, 10, 5)));
+        assert_eq!(index.find_line(14), Some((1, 10, 5)));
+        assert_eq!(index.find_line(34), Some((2, 15, 20)));
+        assert_eq!(index.find_line(35), Some((3, 35, 0)));
+        assert_eq!(index.find_line(36), None);
+    }
+
+    #[test]
+    fn test_replace_from() {
+        let mut index = DatLineIndex::from_lengths([10, 5, 20]);
+        index.replace_from(1, [6, 7, 8]);
+
+        assert_eq!(index, DatLineIndex::from_lengths([10, 6, 7, 8]));
+    }
+
+    #[test]
+    fn test_assemble_lines() {
+        let lines = vec![b"abc\n".to_vec(), b"de\n".to_vec(), b"f\n".to_vec()];
+
+        assert_eq!(assemble_lines(&lines, 0), b"abc\nde\nf\n");
+        assert_eq!(assemble_lines(&lines, 3), b"\nde\nf\n");
+        assert_eq!(assemble_lines(&lines, 4), b"de\nf\n");
+        assert_eq!(assemble_lines(&lines, 9), b"");
+        assert_eq!(assemble_lines(&lines, 20), b"");
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use eddist_core::{
+    domain::pubsub_repository::{CreatingRes, CreatingThread},
+    proto::{decode_creating_res, decode_creating_thread},
+    redis_keys::{CHANNEL_RES_CREATED, CHANNEL_THREAD_CREATED},
+};
+use futures::StreamExt;
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before it is reported as lagged
+const DEFAULT_STREAM_BUFFER: usize = 256;
+const DEFAULT_STREAM_MAX_CONNECTIONS_PER_IP: usize = 4;
+
+#[derive(Debug, Clone)]
+pub enum CreationEvent {
+    ResCreated(Arc<CreatingRes>),
+    ThreadCreated(Arc<CreatingThread>),
+}
+
+/// Fans the creation events published on Redis out to the streaming clients of this
+/// instance, so that a single pub/sub connection serves every client.
+///
+/// Each client reads from a bounded broadcast buffer; a client that cannot keep up is
+/// reported as lagged instead of slowing down the others.
+///
+/// The events are only published with ENABLE_RES_PUB/ENABLE_THREAD_PUB, and not before the
+/// background AI moderation of the post (if enabled) has finished, so the streams stay
+/// silent without the former and may lag behind the dat by a few seconds with the latter.
+#[derive(Clone)]
+pub struct EventStreamHub {
+    sender: broadcast::Sender<CreationEvent>,
+    connections: Arc<Mutex<HashMap<String, usize>>>,
+    max_connections_per_ip: usize,
+}
+
+impl EventStreamHub {
+    pub fn new() -> Self {
+        let buffer = env::var("STREAM_BUFFER_SIZE")
+            .ok()
+            .and_then(|x| x.parse().ok())
+            .unwrap_or(DEFAULT_STREAM_BUFFER);
+        let max_connections_per_ip = env::var("STREAM_MAX_CONNECTIONS_PER_IP")
+            .ok()
+            .and_then(|x| x.parse().ok())
+            .unwrap_or(DEFAULT_STREAM_MAX_CONNECTIONS_PER_IP);
+
+        Self::with_limits(buffer, max_connections_per_ip)
+    }
+
+    fn with_limits(buffer: usize, max_connections_per_ip: usize) -> Self {
+        let (sender, _) = broadcast::channel(buffer);
+        Self {
+            sender,
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            max_connections_per_ip,
+        }
+    }
+
+    /// Subscribes a client, or returns `None` when the IP already has the maximum number
+    /// of open streams. The connection is released when the guard is dropped.
+    pub fn subscribe(
+        &self,
+        ip: &str,
+    ) -> Option<(broadcast::Receiver<CreationEvent>, StreamConnectionGuard)> {
+        let mut connections = self.connections.lock().unwrap();
+        let count = connections.entry(ip.to_string()).or_default();
+        if *count >= self.max_connections_per_ip {
+            return None;
+        }
+        *count += 1;
+
+        let guard = StreamConnectionGuard {
+            ip: ip.to_string(),
+            connections: self.connections.clone(),
+        };
+        Some((self.sender.subscribe(), guard))
+    }
+
+    fn publish(&self, event: CreationEvent) {
+        // Fails only when no client is connected
+        let _ = self.sender.send(event);
+    }
+}
+
+impl Default for EventStreamHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct StreamConnectionGuard {
+    ip: String,
+    connections: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl Drop for StreamConnectionGuard {
+    fn drop(&mut self) {
+        let mut connections = self.connections.lock().unwrap();
+        if let Some(count) = connections.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                connections.remove(&self.ip);
+            }
+        }
+    }
+}
+
+/// Subscribes to the creation event channels and forwards them to the hub, reconnecting
+/// with a backoff when the pub/sub connection is lost
+pub fn start_event_stream_subscriber(client: redis::Client, hub: EventStreamHub) {
+    tokio::spawn(async move {
+        let mut error_count = 0u32;
+        loop {
+            match forward_creation_events(&client, &hub).await {
+                Ok(()) => {
+                    tracing::error!("Event stream pubsub connection lost, reconnecting");
+                    error_count = 0;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to subscribe to creation events: {e:?}");
+                    error_count = error_count.saturating_add(1);
+                }
+            }
+            let backoff_secs = std::cmp::min(2u64.pow(error_count), 60);
+            tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
+        }
+    });
+}
+
+async fn forward_creation_events(
+    client: &redis::Client,
+    hub: &EventStreamHub,
+) -> anyhow::Result<()> {
+    let mut pubsub = client.get_async_pubsub().await?;
+    pubsub
+        .subscribe(&[CHANNEL_RES_CREATED, CHANNEL_THREAD_CREATED])
+        .await?;
+
+    let mut messages = pubsub.on_message();
+    while let Some(msg) = messages.next().await {
+        let payload = msg.get_payload_bytes();
+        let event = match msg.get_channel_name() {
+            CHANNEL_RES_CREATED => {
+                decode_creating_res(payload).map(|res| CreationEvent::ResCreated(Arc::new(res)))
+            }
+            CHANNEL_THREAD_CREATED => decode_creating_thread(payload)
+                .map(|thread| CreationEvent::ThreadCreated(Arc::new(thread))),
+            _ => continue,
+        };
+        match event {
+            Ok(event) => hub.publish(event),
+            Err(e) => tracing::warn!("Failed to decode creation event: {e}"),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscribe_limits_connections_per_ip() {
+        let hub = EventStreamHub::with_limits(8, 2);
+
+        let first = hub.subscribe("203.0.113.1");
+        let second = hub.subscribe("203.0.113.1");
+        assert!(first.is_some());
+        assert!(second.is_some());
+        assert!(hub.subscribe("203.0.113.1").is_none());
+        assert!(hub.subscribe("203.0.113.2").is_some());
+
+        drop(first);
+        assert!(hub.subscribe("203.0.113.1").is_some());
+    }
+
+    #[test]
+    fn test_released_connections_are_removed() {
+        let hub = EventStreamHub::with_limits(8, 2);
+
+        drop(hub.subscribe("203.0.113.1"));
+        assert!(hub.connections.lock().unwrap().is_empty());
+    }
+}
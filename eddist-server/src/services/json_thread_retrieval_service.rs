@@ -0,0 +1,65 @@
+use std::collections::HashSet;
+
+use anyhow::anyhow;
+
+use crate::{
+    domain::thread_res_list::JsonThreadResList, repositories::bbs_repository::BbsRepository,
+};
+
+use super::AppService;
+
+/// Reads a thread for the JSON read API and the pages built on it from the database, as
+/// the thread cache only holds the rendered dat lines
+#[derive(Clone)]
+pub struct JsonThreadRetrievalService<T: BbsRepository>(T);
+
+impl<T: BbsRepository> JsonThreadRetrievalService<T> {
+    pub fn new(repo: T) -> Self {
+        Self(repo)
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: BbsRepository> AppService<JsonThreadRetrievalServiceInput, JsonThreadResList>
+    for JsonThreadRetrievalService<T>
+{
+    async fn execute(
+        &self,
+        input: JsonThreadRetrievalServiceInput,
+    ) -> anyhow::Result<JsonThreadResList> {
+        let Some(board) = self.0.get_board(&input.board_key).await? else {
+            return Err(anyhow!("failed to find board"));
+        };
+        let Some(th) = self
+            .0
+            .get_thread_by_board_key_and_thread_number(&input.board_key, input.thread_number)
+            .await?
+        else {
+            return Err(anyhow!("cannot find such thread"));
+        };
+
+        let mut responses = self.0.get_responses(th.id, input.since).await?;
+        for (idx, res) in responses.iter_mut().enumerate() {
+            if input.revealed_orders.contains(&(input.since + idx + 1)) {
+                res.is_abone = false;
+            }
+        }
+
+        Ok(JsonThreadResList::new(
+            &th,
+            &board.default_name,
+            input.since,
+            &responses,
+        ))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonThreadRetrievalServiceInput {
+    pub board_key: String,
+    pub thread_number: u64,
+    /// Only the responses after this order are read
+    pub since: usize,
+    /// Orders of the shadow-aboned responses, shown in their original form to their poster
+    pub revealed_orders: HashSet<usize>,
+}
@@ -1,31 +1,37 @@
-use aws_sdk_s3::{Client, error::SdkError, operation::get_object::GetObjectError};
+use std::sync::Arc;
+
+use eddist_core::{
+    archive_storage::ArchiveStorage,
+    archived_dat::{ArchivedDat, ArchivedDatKind, get_archived_dat},
+};
 
 use super::AppService;
 
 #[derive(Debug, Clone)]
-pub struct KakoThreadRetrievalService(Client, String);
+pub struct KakoThreadRetrievalService(Arc<dyn ArchiveStorage>);
 
 impl KakoThreadRetrievalService {
-    pub fn new(client: Client, bucket_name: String) -> Self {
-        Self(client, bucket_name)
+    pub fn new(storage: Arc<dyn ArchiveStorage>) -> Self {
+        Self(storage)
     }
 }
 
 #[async_trait::async_trait]
-impl AppService<KakoThreadRetrievalServiceInput, Vec<u8>> for KakoThreadRetrievalService {
-    async fn execute(&self, input: KakoThreadRetrievalServiceInput) -> anyhow::Result<Vec<u8>> {
-        let key = format!("{}/dat/{}.dat", input.board_key, input.thread_number);
-        match self.0.get_object().bucket(&self.1).key(&key).send().await {
-            Ok(output) => {
-                let bytes = output.body.collect().await?.into_bytes().to_vec();
-                Ok(bytes)
-            }
-            Err(SdkError::ServiceError(e)) if matches!(e.err(), GetObjectError::NoSuchKey(_)) => {
-                Err(anyhow::anyhow!("Thread not found"))
-            }
+impl AppService<KakoThreadRetrievalServiceInput, ArchivedDat> for KakoThreadRetrievalService {
+    async fn execute(&self, input: KakoThreadRetrievalServiceInput) -> anyhow::Result<ArchivedDat> {
+        match get_archived_dat(
+            self.0.as_ref(),
+            &input.board_key,
+            ArchivedDatKind::Dat,
+            &input.thread_number,
+        )
+        .await
+        {
+            Ok(Some(dat)) => Ok(dat),
+            Ok(None) => Err(anyhow::anyhow!("Thread not found")),
             Err(err) => {
                 log::error!(
-                    "Error retrieving kako thread: {err:?}, path: {}/dat/{}.dat",
+                    "Error retrieving kako thread: {err:?}, thread: {}/{}",
                     input.board_key,
                     input.thread_number
                 );
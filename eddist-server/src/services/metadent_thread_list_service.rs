@@ -37,7 +37,9 @@ impl<T: BbsRepository> AppService<BoardKey, ThreadListWithMetadent>
             .await?
             .ok_or_else(|| anyhow::anyhow!("failed to find board info"))?;
 
-        let threads = self.0.get_threads_with_metadent(board.id).await?;
+        let mut threads = self.0.get_threads_with_metadent(board.id).await?;
+        // Pinned (no_pool) threads come first, keeping the order of the rest
+        threads.sort_by_key(|(thread, _, _)| !thread.no_pool);
         let threads = threads
             .into_iter()
             .map(|(thread, client_info, authed_token)| {
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use eddist_core::{
+    domain::{
+        moderation_provider::{BoardModerationConfig, KeywordScorer, ModerationProviderConfig},
+        pubsub_repository::ModerationResult,
+    },
+    symmetric,
+};
+use metrics::counter;
+use tracing::{error, warn};
+
+use crate::external::moderation_provider::{
+    HttpClassifierClient, ModerationProvider, OpenAiModerationClient,
+};
+
+use super::server_settings_cache::{ServerSettingKey, get_server_setting};
+
+/// Time budget for moderating a post that is already published
+pub const BACKGROUND_MODERATION_BUDGET: Duration = Duration::from_secs(15);
+/// Time budget for deciding whether to hold a post, the poster waits for this one
+pub const HOLD_MODERATION_BUDGET: Duration = Duration::from_secs(3);
+
+/// Moderates `text` with the board's provider, defaulting to OpenAI when the board has none.
+///
+/// Returns `None` when the provider is unavailable, keeps failing or runs out of `budget`,
+/// so posting is never blocked by it.
+pub async fn moderate(
+    config: Option<&BoardModerationConfig>,
+    text: &str,
+    budget: Duration,
+) -> Option<ModerationResult> {
+    let default_config = BoardModerationConfig::default();
+    let config = config.unwrap_or(&default_config);
+
+    let provider = create_provider(&config.provider).await?;
+    let result = match tokio::time::timeout(budget, call_with_retry(provider.as_ref(), text)).await
+    {
+        Ok(result) => result,
+        Err(_) => Err(anyhow::anyhow!("moderation timed out after {budget:?}")),
+    };
+    // The metric names predate the other providers and are kept so existing dashboards still work
+    match result {
+        Ok(result) => {
+            counter!("openai_moderation_requests", "provider" => provider.name(), "result" => "success")
+                .increment(1);
+            Some(config.apply_thresholds(result))
+        }
+        Err(e) => {
+            warn!(provider = provider.name(), error = %e, "moderation call failed after retries");
+            counter!("openai_moderation_requests", "provider" => provider.name(), "result" => "error")
+                .increment(1);
+            None
+        }
+    }
+}
+
+async fn create_provider(config: &ModerationProviderConfig) -> Option<Box<dyn ModerationProvider>> {
+    match config {
+        ModerationProviderConfig::OpenAi => {
+            let encrypted_key = get_server_setting(ServerSettingKey::AiOpenAiApiKey).await?;
+            match symmetric::decrypt(&encrypted_key) {
+                Ok(api_key) => Some(Box::new(OpenAiModerationClient::new(api_key))),
+                Err(e) => {
+                    error!(error = %e, "Failed to decrypt OpenAI API key; skipping moderation");
+                    None
+                }
+            }
+        }
+        ModerationProviderConfig::HttpClassifier(http) => match http.decrypted_headers() {
+            Ok(headers) => Some(Box::new(HttpClassifierClient::new(http.clone(), headers))),
+            Err(e) => {
+                error!(error = %e, "Failed to decrypt classifier headers; skipping moderation");
+                None
+            }
+        },
+        ModerationProviderConfig::Keyword(keyword) => match KeywordScorer::compile(keyword) {
+            Ok(scorer) => Some(Box::new(scorer)),
+            Err(e) => {
+                error!(error = %e, "Invalid keyword moderation rule; skipping moderation");
+                None
+            }
+        },
+    }
+}
+
+async fn call_with_retry(
+    provider: &dyn ModerationProvider,
+    input: &str,
+) -> anyhow::Result<ModerationResult> {
+    let mut last_err = anyhow::anyhow!("no attempts made");
+    for attempt in 0u32..3 {
+        if attempt > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(500 * (1u64 << attempt))).await;
+            counter!("openai_moderation_retries", "provider" => provider.name()).increment(1);
+        }
+        match provider.moderate(input).await {
+            Ok(r) => return Ok(r),
+            Err(e) => {
+                warn!(attempt, provider = provider.name(), error = %e, "moderation call failed, retrying");
+                last_err = e;
+            }
+        }
+    }
+    Err(last_err)
+}
@@ -1,14 +1,21 @@
 use std::borrow::Cow;
 
 use anyhow::anyhow;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use eddist_core::{
     domain::{
         client_info::ClientInfo,
         ip_addr::ReducedIpAddr,
+        moderation_provider::BoardModerationConfig,
+        moderation_queue::HeldPost,
+        ng_word::NgWordAction,
         pubsub_repository::{CreatingRes, PubSubItem},
         tinker::Tinker,
     },
+    thread_momentum::{
+        MOMENTUM_ALERT_WINDOW_SECS, MomentumAlert, claim_momentum_alert, push_momentum_alert,
+        record_thread_response,
+    },
     utils::is_res_pub_enabled,
 };
 use metrics::counter;
@@ -22,13 +29,14 @@ use crate::{
         res::Res,
         res_core::ResCore,
         service::{
-            bbscgi_auth_service::BbsCgiAuthService,
+            bbscgi_auth_service::{BbsCgiAuthInput, BbsCgiAuthService},
             board_info_service::{
                 BoardInfoClientInfoResRestrictable, BoardInfoResRestrictable, BoardInfoService,
             },
             email_auth_restriction_service::EmailAuthRestrictionService,
             ng_word_reading_service::NgWordReadingService,
             res_creation_span_management_service::ResCreationSpanManagementService,
+            shadow_abone_service::ShadowAboneService,
         },
         utils::count_anchors,
     },
@@ -42,9 +50,11 @@ use crate::{
 use eddist_core::redis_keys::thread_cache_key;
 
 use super::{
-    BbsCgiService, openai_moderation_service,
-    server_settings_cache::{ServerSettingKey, get_server_setting_bool},
-    validation::{check_userreg, resolve_cap_name},
+    BbsCgiService, moderation_service,
+    server_settings_cache::{ServerSettingKey, get_server_setting, get_server_setting_bool},
+    validation::{
+        apply_internal_level_cap, check_moderation_hold, check_userreg, resolve_cap_name,
+    },
 };
 
 #[derive(Clone)]
@@ -80,11 +90,10 @@ impl<
 {
     async fn execute(
         &self,
-        input: ResCreationServiceInput,
+        mut input: ResCreationServiceInput,
     ) -> Result<ResCreationServiceOutput, BbsCgiError> {
-        let mut redis_conn = self.2.clone();
+        let redis_conn = self.2.clone();
         let bbs_repo = self.0.clone();
-        let pub_repo = self.3.clone();
 
         let res_id = Uuid::now_v7();
         let board_info_svc = BoardInfoService::new(self.0.clone());
@@ -146,14 +155,15 @@ impl<
         let auth_service =
             BbsCgiAuthService::new(self.0.clone(), redis_conn.clone(), self.4.clone());
         let authed_token = auth_service
-            .check_validity(
-                res.authed_token().map(|x| x.as_str()),
-                input.ip_addr.clone(),
-                input.user_agent.clone(),
-                input.asn_num as i32,
+            .check_validity(BbsCgiAuthInput {
+                token: res.authed_token().map(|x| x.as_str()),
+                ip_addr: input.ip_addr.clone(),
+                user_agent: input.user_agent.clone(),
+                asn_num: input.asn_num as i32,
                 created_at,
-                input.require_user_registration,
-            )
+                require_user_registration: input.require_user_registration,
+                reauth_rule_id: input.reauth_rule_id,
+            })
             .await?;
 
         let email_auth_service = EmailAuthRestrictionService::new(redis_conn.clone());
@@ -169,6 +179,8 @@ impl<
         if let Some(err) = check_userreg(&input.body, &authed_token, redis_conn.clone()).await? {
             return Err(err);
         }
+        input.tinker =
+            apply_internal_level_cap(redis_conn.clone(), &authed_token, input.tinker).await;
 
         let cap_name = resolve_cap_name(&self.0, &res, &input.board_key).await?;
         let mut res = res.set_author_id(&authed_token, cap_name);
@@ -195,12 +207,18 @@ impl<
             return Err(BbsCgiError::NgWordDetected);
         }
 
-        let ng_words = NgWordReadingService::new(self.0.clone(), redis_conn.clone())
-            .get_ng_words(&input.board_key)
+        let ng_word_matcher = NgWordReadingService::new(self.0.clone(), redis_conn.clone())
+            .get_ng_word_matcher(&input.board_key)
             .await?;
-        if res.contains_ng_word(&ng_words) {
-            return Err(BbsCgiError::NgWordDetected);
+        let ng_word_verdict = res.ng_word_verdict(&ng_word_matcher);
+        if let Some(verdict) = &ng_word_verdict {
+            counter!("ng_word_hit", "board_key" => input.board_key.clone(), "action" => verdict.action.as_str())
+                .increment(1);
+            if verdict.action == NgWordAction::Reject {
+                return Err(BbsCgiError::NgWordDetected);
+            }
         }
+        let ng_word_action = ng_word_verdict.as_ref().map(|v| v.action);
 
         // Determine response creation span and tinker level based on tinker
         // Level 1 users: 30 seconds, Level 2+ users: 5 seconds (base_response_creation_span_sec)
@@ -240,25 +258,34 @@ impl<
             });
         };
 
-        // RPUSHX appends only if the thread cache key exists, so the check and push are
-        // atomic (no EXISTS/RPUSH TTL race). Returns 0 if absent; we still persist to the DB.
-        let Value::Int(order) = redis_conn
-            .send_packed_command(&Cmd::rpush_exists(
-                thread_cache_key(&input.board_key, input.thread_number),
-                res.get_sjis_bytes(&board.default_name, None).get_inner(),
-            ))
-            .await
-            .map_err(|e| BbsCgiError::Other(e.into()))?
-        else {
-            return Err(BbsCgiError::Other(anyhow!(
-                "failed to parse redis response"
-            )));
+        let (hold_reason, moderation_result) = check_moderation_hold(
+            &board_info,
+            ng_word_verdict.as_ref(),
+            input.tinker.as_ref().map_or(0, |t| t.internal_level()),
+            res.body(),
+            ServerSettingKey::AiModerationOnRes,
+        )
+        .await;
+        let is_held = hold_reason.is_some();
+        // Shadow-aboned responses are stored as あぼーん; the poster keeps seeing the original line
+        let shadow_original_line = if ng_word_action == Some(NgWordAction::ShadowAbone) {
+            let original_line = res.get_sjis_bytes(&board.default_name, None);
+            res.set_abone(true);
+            Some(original_line)
+        } else {
+            None
         };
-        let order = if order > 0 {
-            order as i32
+
+        let order = if is_held {
+            // Held responses are not appended to the dat, they get their order on approval
+            0
         } else {
-            // Sort by order, and then by id (uuidv7), thus the order of non-cache-existence response is over 1000.
-            10000
+            self.push_to_thread_cache(
+                &input.board_key,
+                input.thread_number,
+                res.get_sjis_bytes(&board.default_name, None).get_inner(),
+            )
+            .await?
         };
 
         res_span_svc
@@ -268,6 +295,29 @@ impl<
                 created_at.timestamp() as u64,
             )
             .await;
+        if let Some(verdict) = &ng_word_verdict {
+            res_span_svc
+                .add_penalty(
+                    &authed_token.reduced_ip.to_string(),
+                    verdict.penalty_seconds as u64,
+                )
+                .await;
+        }
+
+        if let Some(original_line) = shadow_original_line
+            && (1..10000).contains(&order)
+            && let Err(e) = ShadowAboneService::new(redis_conn.clone())
+                .record(
+                    &input.board_key,
+                    input.thread_number,
+                    &authed_token.token,
+                    order,
+                    &original_line.get_inner(),
+                )
+                .await
+        {
+            log::error!("failed to record shadow-aboned response: {e}");
+        }
 
         let cres = CreatingRes {
             id: res_id,
@@ -281,40 +331,32 @@ impl<
             thread_id: th.id,
             board_id: th.board_id,
             client_info,
-            res_order: order as i32,
+            res_order: order,
             is_sage: res.is_sage(),
-            moderation_result: None,
+            moderation_result,
+            is_abone: res.is_abone(),
         };
 
-        let event_repo = self.4.clone();
-
-        tokio::spawn(async move {
-            if let Err(e) = bbs_repo.create_response(cres.clone()).await {
-                error_span!("failed to create response in database",
-                    error = %e
-                );
-                pub_repo
-                    .publish(PubSubItem::CreatingRes(Box::new(cres.clone())))
-                    .await
-                    .unwrap();
-            }
-
-            if is_res_pub_enabled() {
-                let moderation_result =
-                    if get_server_setting_bool(ServerSettingKey::AiModerationOnRes).await {
-                        openai_moderation_service::moderate(&cres.body).await
-                    } else {
-                        None
-                    };
-                let mut cres = cres;
-                cres.moderation_result = moderation_result;
-                let _ = event_repo.publish_res_created(cres).await;
-            }
-
-            let _ = bbs_repo
-                .update_authed_token_last_wrote(authed_token.id, created_at)
-                .await;
-        });
+        if let Some(hold_reason) = hold_reason {
+            bbs_repo
+                .enqueue_held_post(
+                    HeldPost::Response(Box::new(cres)),
+                    hold_reason,
+                    ng_word_verdict
+                        .filter(|v| v.action == NgWordAction::Hold)
+                        .map(|v| v.ng_word_id),
+                )
+                .await
+                .map_err(BbsCgiError::Other)?;
+        } else {
+            self.record_momentum(&input.board_key, input.thread_number, &cres);
+            self.persist_and_publish(
+                cres,
+                authed_token.id,
+                created_at,
+                board_info.moderation_config.clone(),
+            );
+        }
 
         let tinker = if let Some(tinker) = input.tinker {
             if tinker.authed_token() != authed_token.token {
@@ -327,9 +369,16 @@ impl<
         }
         .action_on_write(created_at);
 
-        counter!("response_creation", "board_key" => input.board_key).increment(1);
+        match hold_reason {
+            Some(hold_reason) => counter!("moderation_hold", "board_key" => input.board_key, "reason" => hold_reason.as_str()).increment(1),
+            None => counter!("response_creation", "board_key" => input.board_key).increment(1),
+        }
 
-        let res_order = if order <= 2000 { Some(order) } else { None };
+        let res_order = if (1..=2000).contains(&order) {
+            Some(order)
+        } else {
+            None
+        };
 
         Ok(ResCreationServiceOutput {
             tinker,
@@ -340,6 +389,149 @@ impl<
     }
 }
 
+impl<
+    T: BbsRepository + Clone,
+    U: UserRepository + Clone,
+    P: PubRepository,
+    E: CreationEventRepository,
+> ResCreationService<T, U, P, E>
+{
+    /// Appends the dat line to the thread cache and returns its order.
+    async fn push_to_thread_cache(
+        &self,
+        board_key: &str,
+        thread_number: u64,
+        sjis_line: Vec<u8>,
+    ) -> Result<i32, BbsCgiError> {
+        let mut redis_conn = self.2.clone();
+        // RPUSHX appends only if the thread cache key exists, so the check and push are
+        // atomic (no EXISTS/RPUSH TTL race). Returns 0 if absent; we still persist to the DB.
+        let Value::Int(order) = redis_conn
+            .send_packed_command(&Cmd::rpush_exists(
+                thread_cache_key(board_key, thread_number),
+                sjis_line,
+            ))
+            .await
+            .map_err(|e| BbsCgiError::Other(e.into()))?
+        else {
+            return Err(BbsCgiError::Other(anyhow!(
+                "failed to parse redis response"
+            )));
+        };
+
+        Ok(if order > 0 {
+            order as i32
+        } else {
+            // Sort by order, and then by id (uuidv7), thus the order of non-cache-existence response is over 1000.
+            10000
+        })
+    }
+
+    /// Counts the response for the momentum of the thread in the background, raising an
+    /// alert for the admin once the thread crosses the configured rate
+    fn record_momentum(&self, board_key: &str, thread_number: u64, cres: &CreatingRes) {
+        let mut redis_conn = self.2.clone();
+        let board_key = board_key.to_string();
+        let (thread_id, response_id, created_at) = (cres.thread_id, cres.id, cres.created_at);
+
+        tokio::spawn(async move {
+            let count = match record_thread_response(
+                &mut redis_conn,
+                thread_id,
+                response_id,
+                created_at,
+                MOMENTUM_ALERT_WINDOW_SECS,
+            )
+            .await
+            {
+                Ok(count) => count,
+                Err(e) => {
+                    log::error!("failed to record thread momentum: {e}");
+                    return;
+                }
+            };
+
+            let threshold = get_server_setting(ServerSettingKey::MomentumAlertThreshold)
+                .await
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .unwrap_or(0);
+            if threshold == 0 || count < threshold {
+                return;
+            }
+            match claim_momentum_alert(&mut redis_conn, thread_id).await {
+                Ok(true) => {}
+                Ok(false) => return,
+                Err(e) => {
+                    log::error!("failed to claim thread momentum alert: {e}");
+                    return;
+                }
+            }
+
+            log::warn!(
+                "thread momentum alert: {board_key}/{thread_number} got {count} responses within {MOMENTUM_ALERT_WINDOW_SECS} seconds"
+            );
+            counter!("thread_momentum_alert", "board_key" => board_key.clone()).increment(1);
+            let alert = MomentumAlert {
+                board_key,
+                thread_number,
+                responses: count,
+                window_secs: MOMENTUM_ALERT_WINDOW_SECS,
+                detected_at: created_at,
+            };
+            if let Err(e) = push_momentum_alert(&mut redis_conn, &alert).await {
+                log::error!("failed to push thread momentum alert: {e}");
+            }
+        });
+    }
+
+    fn persist_and_publish(
+        &self,
+        cres: CreatingRes,
+        authed_token_id: Uuid,
+        created_at: DateTime<Utc>,
+        moderation_config: Option<BoardModerationConfig>,
+    ) {
+        let bbs_repo = self.0.clone();
+        let pub_repo = self.3.clone();
+        let event_repo = self.4.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = bbs_repo.create_response(cres.clone()).await {
+                error_span!("failed to create response in database",
+                    error = %e
+                );
+                pub_repo
+                    .publish(PubSubItem::CreatingRes(Box::new(cres.clone())))
+                    .await
+                    .unwrap();
+            }
+
+            let mut cres = cres;
+            if cres.moderation_result.is_none()
+                && get_server_setting_bool(ServerSettingKey::AiModerationOnRes).await
+            {
+                cres.moderation_result = moderation_service::moderate(
+                    moderation_config.as_ref(),
+                    &cres.body,
+                    moderation_service::BACKGROUND_MODERATION_BUDGET,
+                )
+                .await;
+            }
+            // The moderation rules must run even when the creation events are not published
+            if cres.moderation_result.is_some() {
+                let _ = event_repo.publish_res_moderated(&cres).await;
+            }
+            if is_res_pub_enabled() {
+                let _ = event_repo.publish_res_created(cres).await;
+            }
+
+            let _ = bbs_repo
+                .update_authed_token_last_wrote(authed_token_id, created_at)
+                .await;
+        });
+    }
+}
+
 pub struct ResCreationServiceInput {
     pub board_key: String,
     pub thread_number: u64,
@@ -352,6 +544,8 @@ pub struct ResCreationServiceInput {
     pub user_agent: String,
     pub asn_num: u32,
     pub require_user_registration: bool,
+    /// Set by a `REAUTH` user restriction rule matching the request
+    pub reauth_rule_id: Option<Uuid>,
 }
 
 pub struct ResCreationServiceOutput {
@@ -2,7 +2,10 @@ use std::borrow::Cow;
 
 use chrono::Utc;
 use eddist_core::{
-    domain::{client_info::ClientInfo, tinker::Tinker},
+    domain::{
+        client_info::ClientInfo, moderation_provider::BoardModerationConfig,
+        moderation_queue::HeldPost, ng_word::NgWordAction, tinker::Tinker,
+    },
     utils::is_thread_pub_enabled,
 };
 use metrics::counter;
@@ -15,13 +18,14 @@ use crate::{
         res::Res,
         res_core::ResCore,
         service::{
-            bbscgi_auth_service::BbsCgiAuthService,
+            bbscgi_auth_service::{BbsCgiAuthInput, BbsCgiAuthService},
             board_info_service::{
                 BoardInfoClientInfoResRestrictable, BoardInfoResRestrictable, BoardInfoService,
             },
             email_auth_restriction_service::EmailAuthRestrictionService,
             ng_word_reading_service::NgWordReadingService,
             res_creation_span_management_service::ResCreationSpanManagementService,
+            shadow_abone_service::ShadowAboneService,
         },
         utils::{sanitize_base, sanitize_num_refs},
     },
@@ -35,9 +39,11 @@ use crate::{
 use eddist_core::redis_keys::thread_cache_key;
 
 use super::{
-    BbsCgiService, openai_moderation_service,
+    BbsCgiService, moderation_service,
     server_settings_cache::{ServerSettingKey, get_server_setting_bool},
-    validation::{check_userreg, resolve_cap_name},
+    validation::{
+        apply_internal_level_cap, check_moderation_hold, check_userreg, resolve_cap_name,
+    },
 };
 
 #[derive(Clone)]
@@ -63,9 +69,9 @@ impl<T: BbsRepository + Clone, U: UserRepository + Clone, E: CreationEventReposi
 {
     async fn execute(
         &self,
-        input: ThreadCreationServiceInput,
+        mut input: ThreadCreationServiceInput,
     ) -> Result<ThreadCreationServiceOutput, BbsCgiError> {
-        let mut redis_conn = self.2.clone();
+        let redis_conn = self.2.clone();
         let bbs_repo = self.0.clone();
 
         let (res_id, th_id) = (Uuid::now_v7(), Uuid::now_v7());
@@ -127,14 +133,15 @@ impl<T: BbsRepository + Clone, U: UserRepository + Clone, E: CreationEventReposi
         let auth_service =
             BbsCgiAuthService::new(self.0.clone(), redis_conn.clone(), self.3.clone());
         let authed_token = auth_service
-            .check_validity(
-                res.authed_token().map(|x| x.as_str()),
-                input.ip_addr.clone(),
-                input.user_agent.clone(),
-                input.asn_num as i32,
+            .check_validity(BbsCgiAuthInput {
+                token: res.authed_token().map(|x| x.as_str()),
+                ip_addr: input.ip_addr.clone(),
+                user_agent: input.user_agent.clone(),
+                asn_num: input.asn_num as i32,
                 created_at,
-                input.require_user_registration,
-            )
+                require_user_registration: input.require_user_registration,
+                reauth_rule_id: input.reauth_rule_id,
+            })
             .await?;
 
         let email_auth_service = EmailAuthRestrictionService::new(self.2.clone());
@@ -150,12 +157,14 @@ impl<T: BbsRepository + Clone, U: UserRepository + Clone, E: CreationEventReposi
         if let Some(err) = check_userreg(&input.body, &authed_token, redis_conn.clone()).await? {
             return Err(err);
         }
+        input.tinker =
+            apply_internal_level_cap(redis_conn.clone(), &authed_token, input.tinker).await;
 
         let cap_name = resolve_cap_name(&self.0, &res, &input.board_key).await?;
-        let res = res.set_author_id(&authed_token, cap_name);
+        let mut res = res.set_author_id(&authed_token, cap_name);
 
         let board_key = input.board_key.clone();
-        let creating_th = CreatingThread {
+        let mut creating_th = CreatingThread {
             thread_id: th_id,
             response_id: res_id,
             title: title.to_string(),
@@ -171,6 +180,7 @@ impl<T: BbsRepository + Clone, U: UserRepository + Clone, E: CreationEventReposi
             metadent: res.metadent_type(),
             client_info,
             moderation_result: None,
+            is_abone: false,
         };
 
         let res_span_svc = ResCreationSpanManagementService::new(
@@ -191,47 +201,51 @@ impl<T: BbsRepository + Clone, U: UserRepository + Clone, E: CreationEventReposi
             return Err(BbsCgiError::TooManyCreatingThreadWithoutTinker);
         }
 
-        let ng_words = NgWordReadingService::new(self.0.clone(), redis_conn.clone())
-            .get_ng_words(&input.board_key)
+        let ng_word_matcher = NgWordReadingService::new(self.0.clone(), redis_conn.clone())
+            .get_ng_word_matcher(&input.board_key)
             .await?;
-        if (&res, title.clone()).contains_ng_word(&ng_words) {
-            return Err(BbsCgiError::NgWordDetected);
+        let ng_word_verdict = (&res, title.clone()).ng_word_verdict(&ng_word_matcher);
+        if let Some(verdict) = &ng_word_verdict {
+            counter!("ng_word_hit", "board_key" => board_key.clone(), "action" => verdict.action.as_str())
+                .increment(1);
+            if verdict.action == NgWordAction::Reject {
+                return Err(BbsCgiError::NgWordDetected);
+            }
         }
+        let ng_word_action = ng_word_verdict.as_ref().map(|v| v.action);
+
+        // Shadow-aboned threads keep their title, but >>1 is stored as あぼーん except for the poster
+        let shadow_original_line = if ng_word_action == Some(NgWordAction::ShadowAbone) {
+            let original_line = res.get_sjis_bytes(&board.default_name, Some(&title));
+            res.set_abone(true);
+            creating_th.is_abone = true;
+            Some(original_line)
+        } else {
+            None
+        };
 
-        let event_repo = self.3.clone();
-        let creating_th_clone = creating_th.clone();
-
-        let db_result = bbs_repo.create_thread(creating_th).await;
-        if db_result.is_ok() && is_thread_pub_enabled() {
-            tokio::spawn(async move {
-                let moderation_result =
-                    if get_server_setting_bool(ServerSettingKey::AiModerationOnThread).await {
-                        let text =
-                            format!("{}\n{}", creating_th_clone.title, creating_th_clone.body);
-                        openai_moderation_service::moderate(&text).await
-                    } else {
-                        None
-                    };
-                let mut creating_th_clone = creating_th_clone;
-                creating_th_clone.moderation_result = moderation_result;
-                let _ = event_repo.publish_thread_created(creating_th_clone).await;
-            });
-        }
-        db_result.map_err(|e| {
-            if e.to_string() == "Given thread number is already exists" {
-                BbsCgiError::SameTimeThreadCration
-            } else {
-                BbsCgiError::Other(e)
-            }
-        })?;
-        let redis_result = tokio::spawn(async move {
-            redis_conn
-                .send_packed_command(&Cmd::rpush(
-                    thread_cache_key(&input.board_key, unix_time as u64),
-                    res.get_sjis_bytes(&board.default_name, Some(&title))
-                        .get_inner(),
-                ))
-                .await?;
+        let (hold_reason, moderation_result) = check_moderation_hold(
+            &board_info,
+            ng_word_verdict.as_ref(),
+            input.tinker.as_ref().map_or(0, |t| t.internal_level()),
+            &format!("{title}\n{}", res.body()),
+            ServerSettingKey::AiModerationOnThread,
+        )
+        .await;
+        creating_th.moderation_result = moderation_result;
+
+        if let Some(hold_reason) = hold_reason {
+            bbs_repo
+                .enqueue_held_post(
+                    HeldPost::Thread(Box::new(creating_th)),
+                    hold_reason,
+                    ng_word_verdict
+                        .as_ref()
+                        .filter(|v| v.action == NgWordAction::Hold)
+                        .map(|v| v.ng_word_id),
+                )
+                .await
+                .map_err(BbsCgiError::Other)?;
             res_span_svc
                 .update_last_res_creation_time(
                     &authed_token_reduced_ip,
@@ -246,18 +260,38 @@ impl<T: BbsRepository + Clone, U: UserRepository + Clone, E: CreationEventReposi
                     unix_time as u64,
                 )
                 .await;
-            redis_conn
-                .send_packed_command(&Cmd::expire(
-                    thread_cache_key(&input.board_key, unix_time as u64),
-                    60 * 60 * 24 * 7,
-                ))
-                .await
-        })
-        .await;
+        } else {
+            self.create_thread_and_cache(
+                &board_key,
+                creating_th,
+                res.get_sjis_bytes(&board.default_name, Some(&title))
+                    .get_inner(),
+                res_span_svc.clone(),
+                &authed_token_reduced_ip,
+                board_info.moderation_config.clone(),
+            )
+            .await?;
 
-        redis_result
-            .map_err(|e| BbsCgiError::Other(e.into()))?
-            .map_err(|e| BbsCgiError::Other(e.into()))?;
+            if let Some(original_line) = shadow_original_line
+                && let Err(e) = ShadowAboneService::new(redis_conn.clone())
+                    .record(
+                        &board_key,
+                        unix_time as u64,
+                        &authed_token.token,
+                        1,
+                        &original_line.get_inner(),
+                    )
+                    .await
+            {
+                log::error!("failed to record shadow-aboned thread: {e}");
+            }
+        }
+
+        if let Some(verdict) = &ng_word_verdict {
+            res_span_svc
+                .add_penalty(&authed_token_reduced_ip, verdict.penalty_seconds as u64)
+                .await;
+        }
 
         let tinker = if let Some(tinker) = input.tinker {
             if tinker.authed_token() != authed_token.token {
@@ -274,8 +308,13 @@ impl<T: BbsRepository + Clone, U: UserRepository + Clone, E: CreationEventReposi
             .update_authed_token_last_wrote(authed_token.id, created_at)
             .await;
 
-        counter!("response_creation", "board_key" => board_key.clone()).increment(1);
-        counter!("thread_creation", "board_key" => board_key.clone()).increment(1);
+        if let Some(hold_reason) = hold_reason {
+            counter!("moderation_hold", "board_key" => board_key.clone(), "reason" => hold_reason.as_str())
+                .increment(1);
+        } else {
+            counter!("response_creation", "board_key" => board_key.clone()).increment(1);
+            counter!("thread_creation", "board_key" => board_key.clone()).increment(1);
+        }
 
         Ok(ThreadCreationServiceOutput {
             tinker,
@@ -285,6 +324,90 @@ impl<T: BbsRepository + Clone, U: UserRepository + Clone, E: CreationEventReposi
     }
 }
 
+impl<T: BbsRepository + Clone, U: UserRepository + Clone, E: CreationEventRepository>
+    ThreadCreationService<T, U, E>
+{
+    /// Persists the thread, publishes its creation and seeds the thread cache with >>1.
+    async fn create_thread_and_cache(
+        &self,
+        board_key: &str,
+        creating_th: CreatingThread,
+        sjis_line: Vec<u8>,
+        res_span_svc: ResCreationSpanManagementService,
+        authed_token_reduced_ip: &str,
+        moderation_config: Option<BoardModerationConfig>,
+    ) -> Result<(), BbsCgiError> {
+        let mut redis_conn = self.2.clone();
+        let event_repo = self.3.clone();
+        let unix_time = creating_th.unix_time;
+        let ip_addr = creating_th.ip_addr.clone();
+        let creating_th_clone = creating_th.clone();
+
+        let db_result = self.0.create_thread(creating_th).await;
+        if db_result.is_ok() {
+            tokio::spawn(async move {
+                let mut creating_th_clone = creating_th_clone;
+                if creating_th_clone.moderation_result.is_none()
+                    && get_server_setting_bool(ServerSettingKey::AiModerationOnThread).await
+                {
+                    let text = format!("{}\n{}", creating_th_clone.title, creating_th_clone.body);
+                    creating_th_clone.moderation_result = moderation_service::moderate(
+                        moderation_config.as_ref(),
+                        &text,
+                        moderation_service::BACKGROUND_MODERATION_BUDGET,
+                    )
+                    .await;
+                }
+                // The moderation rules must run even when the creation events are not published
+                if creating_th_clone.moderation_result.is_some() {
+                    let _ = event_repo
+                        .publish_thread_moderated(&creating_th_clone)
+                        .await;
+                }
+                if is_thread_pub_enabled() {
+                    let _ = event_repo.publish_thread_created(creating_th_clone).await;
+                }
+            });
+        }
+        db_result.map_err(|e| {
+            if e.to_string() == "Given thread number is already exists" {
+                BbsCgiError::SameTimeThreadCration
+            } else {
+                BbsCgiError::Other(e)
+            }
+        })?;
+        let (board_key, authed_token_reduced_ip) =
+            (board_key.to_string(), authed_token_reduced_ip.to_string());
+        let redis_result = tokio::spawn(async move {
+            redis_conn
+                .send_packed_command(&Cmd::rpush(
+                    thread_cache_key(&board_key, unix_time),
+                    sjis_line,
+                ))
+                .await?;
+            res_span_svc
+                .update_last_res_creation_time(&authed_token_reduced_ip, &ip_addr, unix_time)
+                .await;
+            res_span_svc
+                .update_last_thread_creation_time(&authed_token_reduced_ip, &ip_addr, unix_time)
+                .await;
+            redis_conn
+                .send_packed_command(&Cmd::expire(
+                    thread_cache_key(&board_key, unix_time),
+                    60 * 60 * 24 * 7,
+                ))
+                .await
+        })
+        .await;
+
+        redis_result
+            .map_err(|e| BbsCgiError::Other(e.into()))?
+            .map_err(|e| BbsCgiError::Other(e.into()))?;
+
+        Ok(())
+    }
+}
+
 pub struct ThreadCreationServiceInput {
     pub board_key: String,
     pub title: String,
@@ -297,6 +420,8 @@ pub struct ThreadCreationServiceInput {
     pub user_agent: String,
     pub asn_num: u32,
     pub require_user_registration: bool,
+    /// Set by a `REAUTH` user restriction rule matching the request
+    pub reauth_rule_id: Option<Uuid>,
 }
 
 pub struct ThreadCreationServiceOutput {
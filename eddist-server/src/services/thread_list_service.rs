@@ -23,10 +23,12 @@ impl<T: BbsRepository> AppService<BoardKey, ThreadList> for ThreadListService<T>
             .await?
             .ok_or_else(|| anyhow::anyhow!("failed to find board info"))?;
 
-        let threads = self
+        let mut threads = self
             .0
             .get_threads(board.id, ThreadStatus::Unarchived)
             .await?;
+        // Pinned (no_pool) threads come first, keeping the order of the rest
+        threads.sort_by_key(|thread| !thread.no_pool);
 
         Ok(ThreadList {
             board,
@@ -6,7 +6,10 @@ use redis::{AsyncCommands, aio::ConnectionManager};
 use crate::{domain::thread_res_list::ThreadResList, repositories::bbs_repository::BbsRepository};
 use eddist_core::redis_keys::thread_cache_key;
 
-use super::AppService;
+use super::{
+    AppService,
+    dat_line_index::{DatLineIndex, assemble_lines, get_dat_line_index, set_dat_line_index},
+};
 
 #[derive(Clone)]
 pub struct ThreadRetrievalService<T: BbsRepository>(T, ConnectionManager);
@@ -36,26 +39,76 @@ impl<T: BbsRepository> ThreadRetrievalService<T> {
             .get_inner(),
         )
     }
-}
 
-#[async_trait::async_trait]
-impl<T: BbsRepository> AppService<ThreadRetrievalServiceInput, ThreadResListRaw>
-    for ThreadRetrievalService<T>
-{
-    async fn execute(
+    /// Reads the thread from the database, with the default name of the board and the 1001
+    /// stopper line when it is enabled
+    /// Reads the thread with its responses from the `start`th (0-based) on
+    async fn get_thread_from_db(
         &self,
-        input: ThreadRetrievalServiceInput,
-    ) -> anyhow::Result<ThreadResListRaw> {
-        let mut redis_conn = self.1.clone();
+        board_key: &str,
+        thread_number: u64,
+        start: usize,
+    ) -> anyhow::Result<(ThreadResList, String, Option<Vec<u8>>)> {
+        let Some(board) = self.0.get_board(board_key).await? else {
+            return Err(anyhow!("failed to find board"));
+        };
+        let board_info = self
+            .0
+            .get_board_info(board.id)
+            .await?
+            .ok_or_else(|| anyhow!("failed to find board info"))?;
 
-        match redis_conn
-            .lrange::<_, Vec<Vec<u8>>>(
-                thread_cache_key(&input.board_key, input.thread_number),
-                0,
-                -1,
+        let th = self
+            .0
+            .get_thread_by_board_key_and_thread_number(board_key, thread_number)
+            .await?;
+        let Some(th) = th else {
+            return Err(anyhow!("cannot find such thread"));
+        };
+        let responses = self.0.get_responses(th.id, start).await?;
+
+        let stopper = if th.response_count >= 1000 && board_info.enable_1001_message {
+            Some(
+                get_1001_sjis_bytes(
+                    th.thread_number,
+                    th.last_modified_at,
+                    board_info.custom_1001_message.as_deref(),
+                )
+                .get_inner(),
             )
+        } else {
+            None
+        };
+
+        let th_res_list = ThreadResList {
+            thread: th,
+            res_list: responses,
+        };
+        Ok((th_res_list, board.default_name, stopper))
+    }
+
+    /// Reads the cached lines from the `start`th (0-based) on, along with the number of
+    /// cached lines, or `None` when the thread is not cached
+    async fn get_cached_lines(&self, key: &str, start: usize) -> Option<(usize, Vec<Vec<u8>>)> {
+        let mut redis_conn = self.1.clone();
+        let (len, lines) = redis::pipe()
+            .atomic()
+            .llen(key)
+            .lrange(key, start as isize, -1)
+            .query_async::<(usize, Vec<Vec<u8>>)>(&mut redis_conn)
             .await
-        {
+            .ok()?;
+        (len > 0).then_some((len, lines))
+    }
+
+    async fn retrieve_full(
+        &self,
+        input: &ThreadRetrievalServiceInput,
+    ) -> anyhow::Result<ThreadResListRaw> {
+        let mut redis_conn = self.1.clone();
+        let key = thread_cache_key(&input.board_key, input.thread_number);
+
+        match redis_conn.lrange::<_, Vec<Vec<u8>>>(&key, 0, -1).await {
             Ok(chunks) if !chunks.is_empty() => {
                 counter!("dat_retrieval", "source" => "cache").increment(1);
 
@@ -75,6 +128,11 @@ impl<T: BbsRepository> AppService<ThreadRetrievalServiceInput, ThreadResListRaw>
                     return Ok(ThreadResListRaw { raw: None });
                 }
 
+                set_dat_line_index(
+                    &key,
+                    DatLineIndex::from_lengths(chunks.iter().map(Vec::len)),
+                );
+
                 let mut raw = Vec::with_capacity(total_len);
                 for chunk in chunks {
                     raw.extend_from_slice(&chunk);
@@ -87,53 +145,150 @@ impl<T: BbsRepository> AppService<ThreadRetrievalServiceInput, ThreadResListRaw>
             }
             _ => {
                 counter!("dat_retrieval", "source" => "db").increment(1);
-                let Some(board) = self.0.get_board(&input.board_key).await? else {
-                    return Err(anyhow!("failed to find board"));
-                };
-                let board_info = self
-                    .0
-                    .get_board_info(board.id)
-                    .await?
-                    .ok_or_else(|| anyhow!("failed to find board info"))?;
-
-                let th = self
-                    .0
-                    .get_thread_by_board_key_and_thread_number(
-                        &input.board_key,
-                        input.thread_number,
-                    )
+                let (th_res_list, default_name, stopper) = self
+                    .get_thread_from_db(&input.board_key, input.thread_number, 0)
                     .await?;
-                let Some(th) = th else {
-                    return Err(anyhow!("cannot find such thread"));
-                };
-                let responses = self.0.get_responses(th.id).await?;
-
-                let response_count = th.response_count;
-                let thread_number = th.thread_number;
-                let last_modified_at = th.last_modified_at;
-                let th_res_list = ThreadResList {
-                    thread: th,
-                    res_list: responses,
-                };
-                let mut raw = th_res_list.get_sjis_thread_res_list(&board.default_name);
-
-                if response_count >= 1000 && board_info.enable_1001_message {
-                    raw.extend_from_slice(
-                        &get_1001_sjis_bytes(
-                            thread_number,
-                            last_modified_at,
-                            board_info.custom_1001_message.as_deref(),
-                        )
-                        .get_inner(),
-                    );
+
+                let lines = th_res_list.get_sjis_list_thread_res_list(&default_name, 0);
+                set_dat_line_index(&key, DatLineIndex::from_lengths(lines.iter().map(Vec::len)));
+
+                let mut raw = assemble_lines(&lines, 0);
+                if let Some(s) = stopper {
+                    raw.extend_from_slice(&s);
                 }
 
                 Ok(ThreadResListRaw { raw: Some(raw) })
             }
         }
     }
+
+    /// Assembles the whole dat, which indexes it for the next partial reads, and slices it
+    async fn retrieve_full_from(
+        &self,
+        input: &ThreadRetrievalServiceInput,
+        start: DatStart,
+    ) -> anyhow::Result<ThreadResListRaw> {
+        let full = self
+            .retrieve_full(&ThreadRetrievalServiceInput {
+                expected_byte_size: None,
+                start: None,
+                ..input.clone()
+            })
+            .await?;
+        Ok(ThreadResListRaw {
+            raw: full.raw.map(|raw| slice_dat(&raw, start)),
+        })
+    }
+
+    /// Reads only the lines from `start` on, from the cache or from the database.
+    ///
+    /// Byte offsets are resolved with the line index of the dat; the first line read must
+    /// have its indexed length, otherwise a response was edited and the dat is reassembled.
+    async fn retrieve_partial(
+        &self,
+        input: &ThreadRetrievalServiceInput,
+        start: DatStart,
+    ) -> anyhow::Result<ThreadResListRaw> {
+        let key = thread_cache_key(&input.board_key, input.thread_number);
+
+        let (line, skip, indexed_len) = match start {
+            DatStart::Res(res_order) => (res_order.saturating_sub(1), 0, None),
+            DatStart::Byte(offset) => {
+                match get_dat_line_index(&key).and_then(|index| index.find_line(offset)) {
+                    Some((line, line_start, len)) => (line, offset - line_start, Some(len)),
+                    None => return self.retrieve_full_from(input, start).await,
+                }
+            }
+        };
+
+        let (response_count, lines, stopper) = match self.get_cached_lines(&key, line).await {
+            Some((response_count, lines)) => {
+                counter!("dat_retrieval", "source" => "cache_partial").increment(1);
+                let stopper = if response_count >= 1000 && line <= response_count {
+                    self.compute_stopper_bytes(&input.board_key, input.thread_number)
+                        .await
+                } else {
+                    None
+                };
+                (response_count, lines, stopper)
+            }
+            None => {
+                counter!("dat_retrieval", "source" => "db_partial").increment(1);
+                let (th_res_list, default_name, stopper) = self
+                    .get_thread_from_db(&input.board_key, input.thread_number, line)
+                    .await?;
+                (
+                    line + th_res_list.res_list.len(),
+                    th_res_list.get_sjis_list_thread_res_list(&default_name, line),
+                    stopper,
+                )
+            }
+        };
+
+        if let Some(indexed_len) = indexed_len {
+            // An offset at the end of the dat has nothing to compare, only new lines to send
+            if indexed_len > 0 && lines.first().map(Vec::len) != Some(indexed_len) {
+                return self.retrieve_full_from(input, start).await;
+            }
+            if let Some(mut index) = get_dat_line_index(&key) {
+                index.replace_from(line, lines.iter().map(Vec::len));
+                set_dat_line_index(&key, index);
+            }
+        }
+
+        let mut raw = assemble_lines(&lines, skip);
+        if line <= response_count
+            && let Some(s) = stopper
+        {
+            raw.extend_from_slice(&s);
+        }
+
+        Ok(ThreadResListRaw { raw: Some(raw) })
+    }
 }
 
+#[async_trait::async_trait]
+impl<T: BbsRepository> AppService<ThreadRetrievalServiceInput, ThreadResListRaw>
+    for ThreadRetrievalService<T>
+{
+    async fn execute(
+        &self,
+        input: ThreadRetrievalServiceInput,
+    ) -> anyhow::Result<ThreadResListRaw> {
+        match input.start {
+            None => self.retrieve_full(&input).await,
+            Some(start) => self.retrieve_partial(&input, start).await,
+        }
+    }
+}
+
+/// Where a partial dat starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatStart {
+    /// The response number, from 1
+    Res(usize),
+    /// The byte offset, as in `Range: bytes=N-`
+    Byte(usize),
+}
+
+/// Slices an assembled dat from `start`
+pub fn slice_dat(dat: &[u8], start: DatStart) -> Vec<u8> {
+    let offset = match start {
+        DatStart::Byte(offset) => offset,
+        DatStart::Res(res_order) => match res_order.checked_sub(2) {
+            None => 0,
+            Some(newline) => dat
+                .iter()
+                .enumerate()
+                .filter(|(_, b)| **b == b'\n')
+                .nth(newline)
+                .map_or(dat.len(), |(idx, _)| idx + 1),
+        },
+    };
+    dat.get(offset..).unwrap_or_default().to_vec()
+}
+
+#[derive(Clone)]
 pub struct ThreadRetrievalServiceInput {
     pub board_key: String,
     pub thread_number: u64,
@@ -141,6 +296,8 @@ pub struct ThreadRetrievalServiceInput {
     /// cache total matches this value the service returns `ThreadResListRaw { raw: None }`
     /// (not-modified signal) without allocating the response body.
     pub expected_byte_size: Option<usize>,
+    /// Reads only the part of the dat from there, ignoring `expected_byte_size`
+    pub start: Option<DatStart>,
 }
 
 #[derive(Debug, Clone)]
@@ -153,3 +310,22 @@ impl ThreadResListRaw {
         self.raw
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_dat() {
+        let dat = b"a<>1\nb<>2\nc<>3\n";
+
+        assert_eq!(slice_dat(dat, DatStart::Res(0)), dat);
+        assert_eq!(slice_dat(dat, DatStart::Res(1)), dat);
+        assert_eq!(slice_dat(dat, DatStart::Res(2)), b"b<>2\nc<>3\n");
+        assert_eq!(slice_dat(dat, DatStart::Res(3)), b"c<>3\n");
+        assert_eq!(slice_dat(dat, DatStart::Res(4)), b"");
+        assert_eq!(slice_dat(dat, DatStart::Res(10)), b"");
+        assert_eq!(slice_dat(dat, DatStart::Byte(4)), b"\nb<>2\nc<>3\n");
+        assert_eq!(slice_dat(dat, DatStart::Byte(100)), b"");
+    }
+}
@@ -3,7 +3,10 @@ use std::{
     time::{Duration, Instant},
 };
 
-use eddist_core::domain::user_restriction::UserRestrictionRule;
+use eddist_core::domain::{
+    user_restriction::{RestrictionClient, RestrictionTarget, UserRestrictionRule},
+    user_restriction_index::RestrictionRuleIndex,
+};
 use tokio::sync::RwLock;
 
 use crate::repositories::user_restriction_repository::UserRestrictionRepository;
@@ -23,20 +26,21 @@ pub struct UserRestrictionService<T: UserRestrictionRepository> {
 
 #[derive(Debug)]
 struct RestrictionCache {
-    rules: Vec<UserRestrictionRule>,
+    /// Swapped as a whole so that a refresh never holds the lock while indexing
+    index: Arc<RestrictionRuleIndex>,
     last_updated: Instant,
 }
 
 impl RestrictionCache {
     fn new() -> Self {
         Self {
-            rules: Vec::new(),
+            index: Arc::new(RestrictionRuleIndex::default()),
             last_updated: Instant::now(),
         }
     }
 
-    fn update_rules(&mut self, rules: Vec<UserRestrictionRule>) {
-        self.rules = rules;
+    fn update_index(&mut self, index: RestrictionRuleIndex) {
+        self.index = Arc::new(index);
         self.last_updated = Instant::now();
     }
 }
@@ -49,31 +53,26 @@ impl<T: UserRestrictionRepository + Clone> UserRestrictionService<T> {
     /// Refresh the cache immediately, typically called by background tasks
     pub async fn refresh_cache(&self) -> anyhow::Result<()> {
         let rules = self.repo.get_all_active_rules().await?;
-        let global_cache = get_global_cache();
-        let mut cache = global_cache.write().await;
-        cache.update_rules(rules);
-        tracing::info!(
-            "User restriction cache refreshed with {} rules",
-            cache.rules.len()
-        );
+        // Rules that fail to compile are left out rather than blocking every request
+        let index = RestrictionRuleIndex::from_rules(rules);
+        let rule_count = index.len();
+        get_global_cache().write().await.update_index(index);
+        tracing::info!("User restriction cache refreshed with {rule_count} rules");
         Ok(())
     }
 
+    /// The strictest rule matching the client on the endpoint, the newest of them on a tie
     pub async fn is_restricted(
         &self,
-        ip: &str,
-        asn: u32,
-        user_agent: &str,
+        client: &RestrictionClient,
+        board_key: Option<&str>,
+        target: RestrictionTarget,
     ) -> anyhow::Result<Option<UserRestrictionRule>> {
-        let global_cache = get_global_cache();
-        let cache = global_cache.read().await;
-        for rule in &cache.rules {
-            if rule.matches(ip, asn, user_agent) {
-                return Ok(Some(rule.clone()));
-            }
-        }
+        let index = get_global_cache().read().await.index.clone();
 
-        Ok(None)
+        Ok(index
+            .find(client, board_key, target)
+            .map(|rule| rule.rule.clone()))
     }
 }
 
@@ -86,8 +85,9 @@ impl<T: UserRestrictionRepository + Clone>
         &self,
         input: UserRestrictionCheckInput,
     ) -> anyhow::Result<UserRestrictionCheckOutput> {
+        let client = RestrictionClient::new(&input.ip, input.asn, &input.user_agent);
         let matching_rule = self
-            .is_restricted(&input.ip, input.asn, &input.user_agent)
+            .is_restricted(&client, input.board_key.as_deref(), input.target)
             .await?;
 
         Ok(UserRestrictionCheckOutput { matching_rule })
@@ -99,6 +99,9 @@ pub struct UserRestrictionCheckInput {
     pub ip: String,
     pub asn: u32,
     pub user_agent: String,
+    /// `None` for auth-code
+    pub board_key: Option<String>,
+    pub target: RestrictionTarget,
 }
 
 #[derive(Debug, Clone)]
@@ -1,7 +1,18 @@
 use std::env;
 
-use eddist_core::{domain::cap::calculate_cap_hash, simple_rate_limiter::RateLimiter};
-use redis::aio::ConnectionManager;
+use eddist_core::{
+    domain::{
+        board::BoardInfo,
+        cap::calculate_cap_hash,
+        moderation_queue::HoldReason,
+        ng_word::{NgWordAction, NgWordVerdict},
+        pubsub_repository::ModerationResult,
+        tinker::Tinker,
+    },
+    redis_keys::authed_token_internal_level_cap_key,
+    simple_rate_limiter::RateLimiter,
+};
+use redis::{AsyncCommands, aio::ConnectionManager};
 use tokio::sync::Mutex;
 
 use crate::{
@@ -14,7 +25,10 @@ use crate::{
     repositories::bbs_repository::BbsRepository,
 };
 
-use super::server_settings_cache::{ServerSettingKey, get_server_setting_bool};
+use super::{
+    moderation_service,
+    server_settings_cache::{ServerSettingKey, get_server_setting_bool},
+};
 use crate::domain::service::bbscgi_user_reg_temp_url_service::{
     UserRegTempUrlService, UserRegUrlKind,
 };
@@ -68,3 +82,53 @@ pub async fn resolve_cap_name(
         Ok(None)
     }
 }
+
+/// Applies the internal level cap an automated moderation action put on the token, if any.
+pub async fn apply_internal_level_cap(
+    mut redis_conn: ConnectionManager,
+    authed_token: &AuthedToken,
+    tinker: Option<Tinker>,
+) -> Option<Tinker> {
+    let tinker = tinker?;
+    let cap = redis_conn
+        .get::<_, Option<u32>>(authed_token_internal_level_cap_key(
+            &authed_token.id.to_string(),
+        ))
+        .await
+        .unwrap_or(None);
+    Some(tinker.with_internal_level_cap(cap))
+}
+
+/// Decides whether a post goes to the moderation queue instead of the dat.
+///
+/// When the board checks flagged posts up front, the AI moderation result is returned as
+/// well so that it is not requested again on publish.
+pub async fn check_moderation_hold(
+    board_info: &BoardInfo,
+    ng_word_verdict: Option<&NgWordVerdict>,
+    internal_level: u32,
+    text: &str,
+    ai_moderation_key: ServerSettingKey,
+) -> (Option<HoldReason>, Option<ModerationResult>) {
+    if ng_word_verdict.is_some_and(|v| v.action == NgWordAction::Hold) {
+        return (Some(HoldReason::NgWord), None);
+    }
+    if board_info.holds_all_posts_at(internal_level) {
+        return (Some(HoldReason::LowLevel), None);
+    }
+    if !board_info.holds_flagged_posts() || !get_server_setting_bool(ai_moderation_key).await {
+        return (None, None);
+    }
+
+    let moderation_result = moderation_service::moderate(
+        board_info.moderation_config.as_ref(),
+        text,
+        moderation_service::HOLD_MODERATION_BUDGET,
+    )
+    .await;
+    let hold_reason = moderation_result
+        .as_ref()
+        .filter(|result| result.flagged)
+        .map(|_| HoldReason::AiModeration);
+    (hold_reason, moderation_result)
+}
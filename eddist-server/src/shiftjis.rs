@@ -87,6 +87,7 @@ pub struct SJisResponseBuilder {
     headers: Vec<(String, String)>,
     if_none_match: Option<String>,
     etag: Option<String>,
+    is_private: bool,
 }
 
 pub enum SjisContentType {
@@ -106,6 +107,7 @@ impl SJisResponseBuilder {
             headers: Vec::new(),
             if_none_match: None,
             etag: None,
+            is_private: false,
         }
     }
 
@@ -134,6 +136,11 @@ impl SJisResponseBuilder {
         }
     }
 
+    /// Marks the response as specific to the requesting client, so shared caches must not store it
+    pub fn private(self, is_private: bool) -> Self {
+        Self { is_private, ..self }
+    }
+
     pub fn content_type(self, content_type: SjisContentType) -> Self {
         Self {
             content_type,
@@ -166,7 +173,9 @@ impl SJisResponseBuilder {
     pub fn build(self) -> SJisResponse {
         let body_bytes = self.body.get_inner();
 
-        let cache_control_value = if let Some(max_age) = self.max_age {
+        let cache_control_value = if self.is_private {
+            format!("private,max-age={}", self.max_age.unwrap_or(0))
+        } else if let Some(max_age) = self.max_age {
             format!("max-age={},s-maxage={}", max_age, self.s_max_age)
         } else {
             format!("s-maxage={}", self.s_max_age)
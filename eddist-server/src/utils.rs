@@ -8,15 +8,12 @@ use http::HeaderMap;
 use sqlx::{Database, Transaction};
 use uuid::Uuid;
 
-/// Returns the client origin IP, or `None` in production when no valid CDN-provided
-/// header is present. In non-prod, a missing/invalid header falls back to `localhost`.
-pub fn get_origin_ip(headers: &HeaderMap) -> Option<&str> {
-    let origin_ip = headers
-        .get("Cf-Connecting-IP")
-        .or_else(|| headers.get("X-Forwarded-For"))
-        .and_then(|x| x.to_str().ok());
-
-    match origin_ip {
+use crate::middleware::client_addr::ClientAddr;
+
+/// Returns the client origin IP, or `None` in production when it cannot be told from
+/// the request (see [`ClientAddrConfig`](eddist_core::client_addr::ClientAddrConfig)). In non-prod, it falls back to `localhost`.
+pub fn get_origin_ip(client_addr: &ClientAddr) -> Option<&str> {
+    match client_addr.ip.as_deref() {
         Some(ip) => Some(ip),
         None if !is_prod() => Some("localhost"),
         None => None,
@@ -33,23 +30,61 @@ pub fn get_ua(headers: &HeaderMap) -> Option<&str> {
     }
 }
 
-/// Returns the client ASN, or `None` in production when no valid CDN-provided
-/// header is present. In non-prod, a missing/invalid header falls back to `0`.
-pub fn get_asn_num(headers: &HeaderMap) -> Option<u32> {
-    let header_name = env::var("ASN_NUMBER_HEADER_NAME").unwrap_or("X-ASN-Num".to_string());
-
-    let asn = headers
-        .get(header_name)
-        .and_then(|x| x.to_str().ok())
-        .and_then(|x| x.parse::<u32>().ok());
-
-    match asn {
+/// Returns the client ASN, or `None` in production when neither a trusted proxy header
+/// nor the ASN database gives it. In non-prod, it falls back to `0`.
+pub fn get_asn_num(client_addr: &ClientAddr) -> Option<u32> {
+    match client_addr.asn {
         Some(asn) => Some(asn),
         None if !is_prod() => Some(0),
         None => None,
     }
 }
 
+/// Whether the request accepts the content coding, i.e. `Accept-Encoding` lists it (or
+/// `*`) without `q=0`
+pub fn accepts_encoding(headers: &HeaderMap, encoding: &str) -> bool {
+    let Some(accept_encoding) = headers.get("Accept-Encoding").and_then(|x| x.to_str().ok()) else {
+        return false;
+    };
+
+    accept_encoding.split(',').any(|coding| {
+        let mut params = coding.split(';').map(str::trim);
+        let name = params.next().unwrap_or_default();
+        let rejected = params.any(|param| {
+            param
+                .strip_prefix("q=")
+                .and_then(|q| q.parse::<f32>().ok())
+                .is_some_and(|q| q == 0.0)
+        });
+        (name.eq_ignore_ascii_case(encoding) || name == "*") && !rejected
+    })
+}
+
+/// Whether the dats stored compressed may be sent as-is to the client, decided by the
+/// comma-separated User-Agent substrings of `DAT_COMPRESSION_UA_ALLOW` (every client when
+/// unset) and `DAT_COMPRESSION_UA_DENY`, for the clients mishandling `Content-Encoding`
+pub fn allows_precompressed_dat(ua: &str) -> bool {
+    is_ua_listed(
+        ua,
+        env::var("DAT_COMPRESSION_UA_ALLOW")
+            .ok()
+            .filter(|x| !x.trim().is_empty())
+            .as_deref(),
+        env::var("DAT_COMPRESSION_UA_DENY").ok().as_deref(),
+    )
+}
+
+fn is_ua_listed(ua: &str, allow: Option<&str>, deny: Option<&str>) -> bool {
+    let contains_any = |list: &str| {
+        list.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .any(|entry| ua.contains(entry))
+    };
+
+    allow.is_none_or(contains_any) && !deny.is_some_and(contains_any)
+}
+
 pub fn get_tinker(tinker: &str, secret: &str) -> Option<Tinker> {
     let mut validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS256);
     validation.validate_exp = false;
@@ -138,6 +173,11 @@ impl CsrfState {
 mod tests {
     use super::*;
     use axum::http::HeaderMap;
+    use eddist_core::client_addr::ClientAddrConfig;
+
+    fn client_addr(headers: &HeaderMap) -> ClientAddr {
+        ClientAddr::resolve(&ClientAddrConfig::default(), headers, None)
+    }
 
     #[test]
     fn test_get_origin_ip_cloudflare() {
@@ -145,7 +185,7 @@ mod tests {
         headers.insert("Cf-Connecting-IP", "203.0.113.1".parse().unwrap());
 
         unsafe { std::env::set_var("ENV", "production") };
-        assert_eq!(get_origin_ip(&headers), Some("203.0.113.1"));
+        assert_eq!(get_origin_ip(&client_addr(&headers)), Some("203.0.113.1"));
         unsafe { std::env::remove_var("ENV") };
     }
 
@@ -155,14 +195,14 @@ mod tests {
         headers.insert("X-Forwarded-For", "198.51.100.1".parse().unwrap());
 
         unsafe { std::env::set_var("ENV", "production") };
-        assert_eq!(get_origin_ip(&headers), Some("198.51.100.1"));
+        assert_eq!(get_origin_ip(&client_addr(&headers)), Some("198.51.100.1"));
         unsafe { std::env::remove_var("ENV") };
     }
 
     #[test]
     fn test_get_origin_ip_localhost_fallback() {
         let headers = HeaderMap::new();
-        assert_eq!(get_origin_ip(&headers), Some("localhost"));
+        assert_eq!(get_origin_ip(&client_addr(&headers)), Some("localhost"));
     }
 
     #[test]
@@ -178,9 +218,53 @@ mod tests {
         assert_eq!(get_ua(&headers), Some("unknown"));
     }
 
+    #[test]
+    fn test_accepts_encoding() {
+        let mut headers = HeaderMap::new();
+        assert!(!accepts_encoding(&headers, "gzip"));
+
+        headers.insert(
+            "Accept-Encoding",
+            "deflate, GZIP;q=0.5, br;q=0".parse().unwrap(),
+        );
+        assert!(accepts_encoding(&headers, "gzip"));
+        assert!(!accepts_encoding(&headers, "br"));
+        assert!(!accepts_encoding(&headers, "zstd"));
+
+        headers.insert("Accept-Encoding", "*".parse().unwrap());
+        assert!(accepts_encoding(&headers, "br"));
+    }
+
+    #[test]
+    fn test_is_ua_listed() {
+        assert!(is_ua_listed("Monazilla/1.00 JaneStyle/4.23", None, None));
+        assert!(!is_ua_listed(
+            "Monazilla/1.00 JaneStyle/4.23",
+            None,
+            Some("Siki, JaneStyle")
+        ));
+        assert!(is_ua_listed(
+            "Monazilla/1.00 ChMate/0.8.10",
+            Some("ChMate,twinkle"),
+            Some("JaneStyle")
+        ));
+        assert!(!is_ua_listed(
+            "Monazilla/1.00 Xeno/1.0",
+            Some("ChMate,twinkle"),
+            None
+        ));
+    }
+
     #[test]
     fn test_get_asn_num_missing_fallback() {
         let headers = HeaderMap::new();
-        assert_eq!(get_asn_num(&headers), Some(0));
+        assert_eq!(get_asn_num(&client_addr(&headers)), Some(0));
+    }
+
+    #[test]
+    fn test_get_asn_num_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-ASN-Num", "64500".parse().unwrap());
+        assert_eq!(get_asn_num(&client_addr(&headers)), Some(64500));
     }
 }
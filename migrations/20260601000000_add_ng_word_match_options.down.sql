@@ -0,0 +1,4 @@
+ALTER TABLE ng_words
+    DROP COLUMN target_fields,
+    DROP COLUMN match_type,
+    MODIFY COLUMN word VARCHAR(255) NOT NULL;
@@ -0,0 +1,4 @@
+ALTER TABLE ng_words
+    MODIFY COLUMN word VARCHAR(1024) NOT NULL,
+    ADD COLUMN match_type VARCHAR(16) NOT NULL DEFAULT 'LITERAL',
+    ADD COLUMN target_fields VARCHAR(255) NOT NULL DEFAULT 'BODY,NAME,MAIL,TITLE';
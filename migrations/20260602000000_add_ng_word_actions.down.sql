@@ -0,0 +1,3 @@
+ALTER TABLE ng_words
+    DROP COLUMN penalty_seconds,
+    DROP COLUMN action;
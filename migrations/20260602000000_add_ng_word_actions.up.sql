@@ -0,0 +1,3 @@
+ALTER TABLE ng_words
+    ADD COLUMN action VARCHAR(16) NOT NULL DEFAULT 'REJECT',
+    ADD COLUMN penalty_seconds INT UNSIGNED NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+DROP TABLE moderation_queue;
+
+ALTER TABLE boards_info
+    DROP COLUMN moderation_mode,
+    DROP COLUMN moderation_level_threshold;
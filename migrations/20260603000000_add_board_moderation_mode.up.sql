@@ -0,0 +1,21 @@
+ALTER TABLE boards_info
+    ADD COLUMN moderation_mode VARCHAR(16) NOT NULL DEFAULT 'OFF',
+    ADD COLUMN moderation_level_threshold INT UNSIGNED NOT NULL DEFAULT 0;
+
+CREATE TABLE
+    moderation_queue (
+        id BINARY(16) PRIMARY KEY,
+        board_id BINARY(16) NOT NULL,
+        thread_id BINARY(16) NOT NULL,
+        authed_token_id BINARY(16) NOT NULL,
+        post_type VARCHAR(16) NOT NULL,
+        payload JSON NOT NULL,
+        reason VARCHAR(32) NOT NULL,
+        ng_word_id BINARY(16) NULL,
+        status VARCHAR(16) NOT NULL DEFAULT 'PENDING',
+        created_at DATETIME(3) NOT NULL,
+        reviewed_at DATETIME(3) NULL,
+        reviewed_by VARCHAR(255) NULL,
+        INDEX (status, created_at),
+        FOREIGN KEY (board_id) REFERENCES boards (id)
+    );
@@ -0,0 +1,2 @@
+ALTER TABLE boards_info
+    DROP COLUMN moderation_config;
@@ -0,0 +1,3 @@
+-- NULL keeps the OpenAI provider with its own verdicts
+ALTER TABLE boards_info
+    ADD COLUMN moderation_config JSON NULL;
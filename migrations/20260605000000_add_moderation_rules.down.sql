@@ -0,0 +1,3 @@
+DROP TABLE moderation_action_logs;
+
+DROP TABLE moderation_rules;
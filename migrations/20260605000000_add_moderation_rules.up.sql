@@ -0,0 +1,36 @@
+CREATE TABLE
+    moderation_rules (
+        id BINARY(16) PRIMARY KEY,
+        name VARCHAR(255) NOT NULL,
+        -- NULL applies the rule to every board
+        board_id BINARY(16) NULL,
+        category VARCHAR(64) NOT NULL,
+        threshold DOUBLE NOT NULL,
+        action VARCHAR(32) NOT NULL,
+        duration_seconds BIGINT UNSIGNED NOT NULL DEFAULT 0,
+        internal_level INT UNSIGNED NOT NULL DEFAULT 0,
+        enabled BOOLEAN NOT NULL DEFAULT TRUE,
+        created_at DATETIME(3) NOT NULL,
+        updated_at DATETIME(3) NOT NULL,
+        FOREIGN KEY (board_id) REFERENCES boards (id) ON DELETE CASCADE
+    );
+
+CREATE TABLE
+    moderation_action_logs (
+        id BINARY(16) PRIMARY KEY,
+        rule_id BINARY(16) NULL,
+        action VARCHAR(32) NOT NULL,
+        board_id BINARY(16) NOT NULL,
+        thread_number BIGINT UNSIGNED NOT NULL,
+        response_id BINARY(16) NOT NULL,
+        authed_token_id BINARY(16) NOT NULL,
+        category VARCHAR(64) NOT NULL,
+        score DOUBLE NOT NULL,
+        expires_at DATETIME(3) NULL,
+        created_at DATETIME(3) NOT NULL,
+        reverted_at DATETIME(3) NULL,
+        reverted_by VARCHAR(255) NULL,
+        INDEX (created_at),
+        INDEX (board_id, created_at),
+        FOREIGN KEY (rule_id) REFERENCES moderation_rules (id) ON DELETE SET NULL
+    );
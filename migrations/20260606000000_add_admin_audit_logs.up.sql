@@ -0,0 +1,16 @@
+CREATE TABLE
+    admin_audit_logs (
+        id BINARY(16) PRIMARY KEY,
+        actor_sub VARCHAR(255) NOT NULL,
+        actor_email VARCHAR(255) NOT NULL,
+        action VARCHAR(64) NOT NULL,
+        target_type VARCHAR(64) NOT NULL,
+        target_id VARCHAR(255) NOT NULL,
+        -- Only the fields that changed are kept when both states are present
+        before_state JSON NULL,
+        after_state JSON NULL,
+        created_at DATETIME(3) NOT NULL,
+        INDEX (created_at),
+        INDEX (actor_email, created_at),
+        INDEX (target_type, target_id, created_at)
+    );
@@ -0,0 +1,18 @@
+DELETE FROM admin_role_scopes
+WHERE
+    id = UUID_TO_BIN ('019a4f2e-6b1c-7d3e-8f40-5a6b7c8d9e10');
+
+DELETE FROM admin_roles
+WHERE
+    id = UUID_TO_BIN ('019a4f2e-6b1c-7d3e-8f40-5a6b7c8d9e0f');
+
+DROP INDEX idx_admin_users_email ON admin_users;
+
+ALTER TABLE admin_users
+DROP COLUMN updated_at,
+DROP COLUMN created_at,
+DROP COLUMN email;
+
+DROP INDEX idx_admin_role_scopes_role_scope ON admin_role_scopes;
+
+DROP INDEX idx_admin_roles_role_name ON admin_roles;
@@ -0,0 +1,21 @@
+CREATE UNIQUE INDEX idx_admin_roles_role_name ON admin_roles (role_name);
+
+CREATE UNIQUE INDEX idx_admin_role_scopes_role_scope ON admin_role_scopes (role_id, scope_key);
+
+-- Admin users are mapped to roles by the verified email of their IdP account
+ALTER TABLE admin_users
+ADD COLUMN email VARCHAR(255) NOT NULL,
+ADD COLUMN created_at DATETIME(3) NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+ADD COLUMN updated_at DATETIME(3) NOT NULL DEFAULT CURRENT_TIMESTAMP(3);
+
+CREATE UNIQUE INDEX idx_admin_users_email ON admin_users (email);
+
+INSERT INTO
+    admin_roles (id, role_name, role_description)
+VALUES
+    (UUID_TO_BIN ('019a4f2e-6b1c-7d3e-8f40-5a6b7c8d9e0f'), 'superadmin', 'Every scope');
+
+INSERT INTO
+    admin_role_scopes (id, role_id, scope_key)
+VALUES
+    (UUID_TO_BIN ('019a4f2e-6b1c-7d3e-8f40-5a6b7c8d9e10'), UUID_TO_BIN ('019a4f2e-6b1c-7d3e-8f40-5a6b7c8d9e0f'), '*');
@@ -0,0 +1,9 @@
+-- Admin users with rows here are board moderators restricted to these boards
+CREATE TABLE
+    admin_user_boards (
+        admin_user_id BINARY(16) NOT NULL,
+        board_id BINARY(16) NOT NULL,
+        PRIMARY KEY (admin_user_id, board_id),
+        FOREIGN KEY (admin_user_id) REFERENCES admin_users (id) ON DELETE CASCADE,
+        FOREIGN KEY (board_id) REFERENCES boards (id)
+    );
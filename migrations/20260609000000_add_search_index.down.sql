@@ -0,0 +1,3 @@
+DROP TABLE search_ngrams;
+
+DROP TABLE search_documents;
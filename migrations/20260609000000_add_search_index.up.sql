@@ -0,0 +1,31 @@
+CREATE TABLE
+    search_documents (
+        -- Kept apart from responses so that the threads moved to the archive stay searchable
+        response_id BINARY(16) PRIMARY KEY,
+        board_id BINARY(16) NOT NULL,
+        board_key VARCHAR(255) NOT NULL,
+        thread_id BINARY(16) NOT NULL,
+        thread_number BIGINT NOT NULL,
+        thread_title TEXT NOT NULL,
+        res_order INT NOT NULL,
+        body TEXT NOT NULL,
+        author_id VARCHAR(255) NOT NULL,
+        authed_token_id BINARY(16) NOT NULL,
+        ip_addr VARCHAR(255) NOT NULL,
+        is_abone BOOLEAN NOT NULL DEFAULT FALSE,
+        created_at DATETIME(3) NOT NULL,
+        INDEX (created_at),
+        INDEX (board_key, created_at),
+        INDEX (author_id),
+        INDEX (authed_token_id),
+        INDEX (ip_addr),
+        INDEX (thread_id)
+    );
+
+CREATE TABLE
+    search_ngrams (
+        ngram VARCHAR(2) CHARACTER SET utf8mb4 COLLATE utf8mb4_bin NOT NULL,
+        response_id BINARY(16) NOT NULL,
+        PRIMARY KEY (ngram, response_id),
+        INDEX (response_id)
+    );
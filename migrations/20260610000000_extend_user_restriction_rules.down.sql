@@ -0,0 +1,9 @@
+DELETE FROM user_restriction_rules WHERE rule_type = 'COMPOSITE';
+
+ALTER TABLE user_restriction_rules
+    DROP COLUMN slow_down_seconds,
+    DROP COLUMN captcha_provider,
+    DROP COLUMN action,
+    DROP COLUMN targets,
+    DROP COLUMN board_keys,
+    MODIFY COLUMN rule_type ENUM('ASN', 'IP', 'IP_CIDR', 'USER_AGENT') NOT NULL;
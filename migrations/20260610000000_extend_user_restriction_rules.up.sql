@@ -0,0 +1,8 @@
+-- Existing rules keep blocking every endpoint of every board
+ALTER TABLE user_restriction_rules
+    MODIFY COLUMN rule_type ENUM('ASN', 'IP', 'IP_CIDR', 'USER_AGENT', 'COMPOSITE') NOT NULL,
+    ADD COLUMN board_keys JSON NULL,
+    ADD COLUMN targets VARCHAR(64) NOT NULL DEFAULT 'THREAD,RESPONSE,AUTH_CODE',
+    ADD COLUMN action VARCHAR(16) NOT NULL DEFAULT 'BLOCK',
+    ADD COLUMN captcha_provider VARCHAR(255) NULL,
+    ADD COLUMN slow_down_seconds INT UNSIGNED NOT NULL DEFAULT 0;
@@ -0,0 +1,7 @@
+DELETE FROM user_restriction_rules WHERE subscription_id IS NOT NULL;
+
+ALTER TABLE user_restriction_rules
+    DROP INDEX subscription_id,
+    DROP COLUMN subscription_id;
+
+DROP TABLE user_restriction_subscriptions;
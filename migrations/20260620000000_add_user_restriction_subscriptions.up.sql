@@ -0,0 +1,26 @@
+-- Blocklists refreshed by `eddist-cron refresh-blocklists`, each owning the rules it imported
+CREATE TABLE user_restriction_subscriptions (
+    id BINARY(16) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    source TEXT NOT NULL,
+    format VARCHAR(16) NOT NULL,
+    board_keys JSON NULL,
+    targets VARCHAR(64) NOT NULL DEFAULT 'THREAD,RESPONSE,AUTH_CODE',
+    action VARCHAR(16) NOT NULL DEFAULT 'BLOCK',
+    captcha_provider VARCHAR(255) NULL,
+    slow_down_seconds INT UNSIGNED NOT NULL DEFAULT 0,
+    refresh_interval_seconds INT UNSIGNED NOT NULL,
+    expires_after_seconds INT UNSIGNED NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_refreshed_at DATETIME(3) NULL,
+    last_error TEXT NULL,
+    rule_count INT UNSIGNED NOT NULL DEFAULT 0,
+    created_at DATETIME(3) NOT NULL,
+    updated_at DATETIME(3) NOT NULL,
+    created_by_email VARCHAR(255) NOT NULL,
+    UNIQUE INDEX (name)
+);
+
+ALTER TABLE user_restriction_rules
+    ADD COLUMN subscription_id BINARY(16) NULL,
+    ADD INDEX (subscription_id);
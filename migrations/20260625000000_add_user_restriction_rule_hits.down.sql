@@ -0,0 +1,5 @@
+ALTER TABLE user_restriction_rules
+    DROP INDEX last_hit_at,
+    DROP COLUMN recent_hit_samples,
+    DROP COLUMN last_hit_at,
+    DROP COLUMN hit_count;
@@ -0,0 +1,6 @@
+-- Flushed from Redis by `eddist-cron flush-restriction-hits`
+ALTER TABLE user_restriction_rules
+    ADD COLUMN hit_count BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    ADD COLUMN last_hit_at DATETIME(3) NULL,
+    ADD COLUMN recent_hit_samples JSON NULL,
+    ADD INDEX (last_hit_at);
@@ -0,0 +1,17 @@
+UPDATE search_documents
+SET
+    ip_addr = ''
+WHERE
+    ip_addr IS NULL;
+
+UPDATE search_documents
+SET
+    authed_token_id = UNHEX(REPEAT('0', 32))
+WHERE
+    authed_token_id IS NULL;
+
+ALTER TABLE search_documents
+MODIFY ip_addr VARCHAR(255) NOT NULL,
+MODIFY authed_token_id BINARY(16) NOT NULL;
+
+DROP TABLE search_ngram_stats;
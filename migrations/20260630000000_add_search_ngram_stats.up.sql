@@ -0,0 +1,21 @@
+CREATE TABLE
+    search_ngram_stats (
+        -- Documents having the n-gram, so that a search scans its rarest n-grams first
+        ngram VARCHAR(2) CHARACTER SET utf8mb4 COLLATE utf8mb4_bin PRIMARY KEY,
+        doc_count BIGINT NOT NULL
+    );
+
+INSERT INTO
+    search_ngram_stats (ngram, doc_count)
+SELECT
+    ngram,
+    COUNT(*)
+FROM
+    search_ngrams
+GROUP BY
+    ngram;
+
+-- Cleared once past the retention of eddist-cron
+ALTER TABLE search_documents
+MODIFY ip_addr VARCHAR(255) NULL,
+MODIFY authed_token_id BINARY(16) NULL;